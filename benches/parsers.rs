@@ -0,0 +1,116 @@
+//! Benchmarks for the statement parsers, run over the corpora in `tests/`.
+//!
+//! `parse_query` hands a statement straight to the parser for its first keyword, so the
+//! `creation`, `selection` and `insertion` groups measure those parsers. Only statements that
+//! parse are included, so that the numbers are not skewed by early failures.
+
+#[macro_use]
+extern crate criterion;
+extern crate nom_sql;
+
+use criterion::{black_box, Criterion, Throughput};
+use nom_sql::{is_sql_keyword, parser::parse_query, tokenize, StatementSplitter, TokenKind};
+use std::fs::File;
+
+/// Statements of a file in which each statement ends with a semicolon, like a schema dump.
+fn script(path: &str) -> Vec<String> {
+    StatementSplitter::from_reader(File::open(path).unwrap())
+        .map(Result::unwrap)
+        .collect()
+}
+
+/// Statements of a file with one statement per line and `#` comments.
+fn lines(path: &str) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| String::from(l.trim_end_matches(';')))
+        .collect()
+}
+
+/// The statements that start with `keyword` and parse.
+fn corpus(statements: Vec<Vec<String>>, keyword: &str) -> Vec<String> {
+    statements
+        .into_iter()
+        .flatten()
+        .filter(|s| {
+            s.get(..keyword.len())
+                .is_some_and(|k| k.eq_ignore_ascii_case(keyword))
+        })
+        .filter(|s| parse_query(s).is_ok())
+        .collect()
+}
+
+fn bench_statements(c: &mut Criterion, name: &str, statements: &[String]) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(statements.len() as u64));
+    group.bench_function("corpus", |b| {
+        b.iter(|| {
+            for s in statements {
+                black_box(parse_query(black_box(s)).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn creation(c: &mut Criterion) {
+    let schemas = vec![
+        script("tests/mediawiki-schema.txt"),
+        script("tests/hotcrp-schema.txt"),
+        script("tests/tpc-w-tables.txt"),
+    ];
+    bench_statements(c, "creation", &corpus(schemas, "create table"));
+}
+
+fn selection(c: &mut Criterion) {
+    let queries = vec![
+        lines("tests/hotcrp-queries.txt"),
+        lines("tests/tpc-w-queries.txt"),
+    ];
+    bench_statements(c, "selection", &corpus(queries, "select"));
+}
+
+fn insertion(c: &mut Criterion) {
+    let queries = vec![
+        lines("tests/hotcrp-queries.txt"),
+        lines("tests/tpc-w-queries.txt"),
+    ];
+    bench_statements(c, "insertion", &corpus(queries, "insert"));
+}
+
+/// The keyword matcher, over every word of the corpora, most of which are not keywords.
+fn keywords(c: &mut Criterion) {
+    let text = [
+        "tests/mediawiki-schema.txt",
+        "tests/hotcrp-schema.txt",
+        "tests/hotcrp-queries.txt",
+        "tests/tpc-w-queries.txt",
+    ]
+    .iter()
+    .map(|path| std::fs::read_to_string(path).unwrap())
+    .collect::<Vec<_>>()
+    .join("\n");
+    let words: Vec<&str> = tokenize(&text)
+        .map_while(Result::ok)
+        .filter(|t| t.kind == TokenKind::Keyword || t.kind == TokenKind::Identifier)
+        .map(|t| t.text)
+        .collect();
+
+    let mut group = c.benchmark_group("keywords");
+    group.throughput(Throughput::Elements(words.len() as u64));
+    group.bench_function("is_sql_keyword", |b| {
+        b.iter(|| {
+            words
+                .iter()
+                .filter(|w| is_sql_keyword(black_box(w)))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, creation, selection, insertion, keywords);
+criterion_main!(benches);
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Parsing arbitrary bytes, and printing whatever parses, must never panic.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(query) = nom_sql::parse_query_bytes(data) {
+        let _ = query.to_string();
+    }
+});
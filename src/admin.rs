@@ -0,0 +1,264 @@
+use nom::branch::alt;
+use nom::character::complete::multispace1;
+use nom::combinator::{map, opt, value};
+use nom::multi::separated_list1;
+use nom::sequence::{preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{sql_identifier, statement_terminator, unsigned_number, ws_sep_comma};
+use keywords::{escape_if_keyword, tag_no_case};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum KillType {
+    Connection,
+    Query,
+}
+
+impl fmt::Display for KillType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KillType::Connection => write!(f, "CONNECTION"),
+            KillType::Query => write!(f, "QUERY"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum FlushOption {
+    /// `TABLES [tbl, ...]`; an empty list flushes all tables.
+    Tables(Vec<String>),
+    /// `TABLES [tbl, ...] WITH READ LOCK`
+    TablesWithReadLock(Vec<String>),
+    Logs,
+    Privileges,
+    Hosts,
+    Status,
+}
+
+fn table_names(tables: &[String]) -> String {
+    tables
+        .iter()
+        .map(|t| escape_if_keyword(t))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for FlushOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FlushOption::Tables(ref tables) if tables.is_empty() => write!(f, "TABLES"),
+            FlushOption::Tables(ref tables) => write!(f, "TABLES {}", table_names(tables)),
+            FlushOption::TablesWithReadLock(ref tables) if tables.is_empty() => {
+                write!(f, "TABLES WITH READ LOCK")
+            }
+            FlushOption::TablesWithReadLock(ref tables) => {
+                write!(f, "TABLES {} WITH READ LOCK", table_names(tables))
+            }
+            FlushOption::Logs => write!(f, "LOGS"),
+            FlushOption::Privileges => write!(f, "PRIVILEGES"),
+            FlushOption::Hosts => write!(f, "HOSTS"),
+            FlushOption::Status => write!(f, "STATUS"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum ResetOption {
+    Master,
+    Slave,
+    QueryCache,
+}
+
+impl fmt::Display for ResetOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResetOption::Master => write!(f, "MASTER"),
+            ResetOption::Slave => write!(f, "SLAVE"),
+            ResetOption::QueryCache => write!(f, "QUERY CACHE"),
+        }
+    }
+}
+
+/// Server administration statements issued by DBAs rather than applications.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum AdminStatement {
+    Kill(KillType, u64),
+    /// `FLUSH [LOCAL] option, ...`; the flag records `LOCAL`/`NO_WRITE_TO_BINLOG`.
+    Flush(bool, Vec<FlushOption>),
+    Reset(Vec<ResetOption>),
+}
+
+impl fmt::Display for AdminStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AdminStatement::Kill(kill_type, id) => write!(f, "KILL {} {}", kill_type, id),
+            AdminStatement::Flush(local, ref options) => {
+                write!(f, "FLUSH ")?;
+                if local {
+                    write!(f, "LOCAL ")?;
+                }
+                let options = options
+                    .iter()
+                    .map(|o| o.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{}", options)
+            }
+            AdminStatement::Reset(ref options) => {
+                let options = options
+                    .iter()
+                    .map(|o| o.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "RESET {}", options)
+            }
+        }
+    }
+}
+
+fn kill(i: &str) -> IResult<&str, AdminStatement> {
+    let (remaining_input, (_, _, kill_type, id)) = tuple((
+        tag_no_case("kill"),
+        multispace1,
+        opt(terminated(
+            alt((
+                value(KillType::Query, tag_no_case("query")),
+                value(KillType::Connection, tag_no_case("connection")),
+            )),
+            multispace1,
+        )),
+        unsigned_number,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        AdminStatement::Kill(kill_type.unwrap_or(KillType::Connection), id),
+    ))
+}
+
+fn flush_tables(i: &str) -> IResult<&str, FlushOption> {
+    let (remaining_input, (_, tables, read_lock)) = tuple((
+        alt((tag_no_case("tables"), tag_no_case("table"))),
+        opt(preceded(
+            multispace1,
+            separated_list1(ws_sep_comma, sql_identifier),
+        )),
+        opt(tuple((
+            multispace1,
+            tag_no_case("with"),
+            multispace1,
+            tag_no_case("read"),
+            multispace1,
+            tag_no_case("lock"),
+        ))),
+    ))(i)?;
+
+    let tables = tables
+        .unwrap_or_default()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    Ok((
+        remaining_input,
+        if read_lock.is_some() {
+            FlushOption::TablesWithReadLock(tables)
+        } else {
+            FlushOption::Tables(tables)
+        },
+    ))
+}
+
+fn flush_option(i: &str) -> IResult<&str, FlushOption> {
+    alt((
+        flush_tables,
+        value(FlushOption::Logs, tag_no_case("logs")),
+        value(FlushOption::Privileges, tag_no_case("privileges")),
+        value(FlushOption::Hosts, tag_no_case("hosts")),
+        value(FlushOption::Status, tag_no_case("status")),
+    ))(i)
+}
+
+fn flush(i: &str) -> IResult<&str, AdminStatement> {
+    let (remaining_input, (_, _, local, options)) = tuple((
+        tag_no_case("flush"),
+        multispace1,
+        opt(terminated(
+            alt((tag_no_case("local"), tag_no_case("no_write_to_binlog"))),
+            multispace1,
+        )),
+        separated_list1(ws_sep_comma, flush_option),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        AdminStatement::Flush(local.is_some(), options),
+    ))
+}
+
+fn reset(i: &str) -> IResult<&str, AdminStatement> {
+    map(
+        preceded(
+            tuple((tag_no_case("reset"), multispace1)),
+            separated_list1(
+                ws_sep_comma,
+                alt((
+                    value(ResetOption::Master, tag_no_case("master")),
+                    value(ResetOption::Slave, tag_no_case("slave")),
+                    value(
+                        ResetOption::QueryCache,
+                        tuple((tag_no_case("query"), multispace1, tag_no_case("cache"))),
+                    ),
+                )),
+            ),
+        ),
+        AdminStatement::Reset,
+    )(i)
+}
+
+/// Parses `KILL`, `FLUSH` and `RESET` statements.
+pub fn admin_statement(i: &str) -> IResult<&str, AdminStatement> {
+    terminated(alt((kill, flush, reset)), statement_terminator)(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kill_statements() {
+        assert_eq!(
+            admin_statement("KILL QUERY 42;").unwrap().1,
+            AdminStatement::Kill(KillType::Query, 42)
+        );
+        let res = admin_statement("kill 7").unwrap().1;
+        assert_eq!(res, AdminStatement::Kill(KillType::Connection, 7));
+        assert_eq!(format!("{}", res), "KILL CONNECTION 7");
+    }
+
+    #[test]
+    fn flush_and_reset() {
+        let res = admin_statement("FLUSH NO_WRITE_TO_BINLOG TABLES users, posts WITH READ LOCK")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res,
+            AdminStatement::Flush(
+                true,
+                vec![FlushOption::TablesWithReadLock(vec![
+                    String::from("users"),
+                    String::from("posts"),
+                ])]
+            )
+        );
+
+        let res = admin_statement("flush logs, privileges;").unwrap().1;
+        assert_eq!(format!("{}", res), "FLUSH LOGS, PRIVILEGES");
+
+        let res = admin_statement("RESET MASTER, QUERY CACHE").unwrap().1;
+        assert_eq!(
+            res,
+            AdminStatement::Reset(vec![ResetOption::Master, ResetOption::QueryCache])
+        );
+    }
+}
@@ -0,0 +1,564 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{map, opt, value};
+use nom::multi::many1;
+use nom::sequence::{pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use column::ColumnSpecification;
+use common::{sql_identifier, statement_terminator, table_name, ws_sep_comma, TableKey};
+use create::{field_specification, foreign_key_specification, key_specification};
+use foreignkey::ForeignKeySpecification;
+use keywords::{escape_if_keyword, tag_no_case};
+use table::Table;
+
+/// Where MySQL's `FIRST` or `AFTER` places a column that is added or redefined.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum ColumnPosition {
+    First,
+    After(String),
+}
+
+impl fmt::Display for ColumnPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColumnPosition::First => write!(f, "FIRST"),
+            ColumnPosition::After(ref name) => write!(f, "AFTER {}", escape_if_keyword(name)),
+        }
+    }
+}
+
+/// One change made by `ALTER TABLE`. A column that is added or redefined stays where it is, or
+/// goes last if it is new, unless it is given a position.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum AlterTableDefinition {
+    AddColumn(ColumnSpecification, Option<ColumnPosition>),
+    DropColumn(String),
+    /// MySQL's `CHANGE COLUMN old new_definition`, which may also rename the column.
+    ChangeColumn(String, ColumnSpecification, Option<ColumnPosition>),
+    /// MySQL's `MODIFY COLUMN definition`, which redefines a column but keeps its name.
+    ModifyColumn(ColumnSpecification, Option<ColumnPosition>),
+    /// `RENAME COLUMN old TO new`.
+    RenameColumn(String, String),
+    AddKey(TableKey),
+    DropPrimaryKey,
+    /// `DROP INDEX name`, for any key but the primary key.
+    DropKey(String),
+    AddForeignKey(ForeignKeySpecification),
+    DropForeignKey(String),
+}
+
+impl fmt::Display for AlterTableDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AlterTableDefinition::AddColumn(ref spec, ref position) => {
+                write!(f, "ADD COLUMN {}", spec)?;
+                write_position(f, position)
+            }
+            AlterTableDefinition::DropColumn(ref name) => {
+                write!(f, "DROP COLUMN {}", escape_if_keyword(name))
+            }
+            AlterTableDefinition::ChangeColumn(ref name, ref spec, ref position) => {
+                write!(f, "CHANGE COLUMN {} {}", escape_if_keyword(name), spec)?;
+                write_position(f, position)
+            }
+            AlterTableDefinition::ModifyColumn(ref spec, ref position) => {
+                write!(f, "MODIFY COLUMN {}", spec)?;
+                write_position(f, position)
+            }
+            AlterTableDefinition::RenameColumn(ref from, ref to) => write!(
+                f,
+                "RENAME COLUMN {} TO {}",
+                escape_if_keyword(from),
+                escape_if_keyword(to)
+            ),
+            AlterTableDefinition::AddKey(ref key) => write!(f, "ADD {}", key),
+            AlterTableDefinition::DropPrimaryKey => write!(f, "DROP PRIMARY KEY"),
+            AlterTableDefinition::DropKey(ref name) => {
+                write!(f, "DROP INDEX {}", escape_if_keyword(name))
+            }
+            AlterTableDefinition::AddForeignKey(ref fkey) => write!(f, "ADD {}", fkey),
+            AlterTableDefinition::DropForeignKey(ref name) => {
+                write!(f, "DROP FOREIGN KEY {}", escape_if_keyword(name))
+            }
+        }
+    }
+}
+
+fn write_position(f: &mut fmt::Formatter, position: &Option<ColumnPosition>) -> fmt::Result {
+    match *position {
+        Some(ref position) => write!(f, " {}", position),
+        None => Ok(()),
+    }
+}
+
+/// MySQL's `ALGORITHM` option, which chooses how the table is altered.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum AlterAlgorithm {
+    Default,
+    Instant,
+    Inplace,
+    Copy,
+}
+
+impl fmt::Display for AlterAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AlterAlgorithm::Default => write!(f, "DEFAULT"),
+            AlterAlgorithm::Instant => write!(f, "INSTANT"),
+            AlterAlgorithm::Inplace => write!(f, "INPLACE"),
+            AlterAlgorithm::Copy => write!(f, "COPY"),
+        }
+    }
+}
+
+/// MySQL's `LOCK` option, which limits the concurrent access allowed while the table is altered.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum AlterLock {
+    Default,
+    None,
+    Shared,
+    Exclusive,
+}
+
+impl fmt::Display for AlterLock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AlterLock::Default => write!(f, "DEFAULT"),
+            AlterLock::None => write!(f, "NONE"),
+            AlterLock::Shared => write!(f, "SHARED"),
+            AlterLock::Exclusive => write!(f, "EXCLUSIVE"),
+        }
+    }
+}
+
+/// `ALTER TABLE`. Statements can be built up change by change, e.g.
+/// `AlterTableStatement::new("users").drop_column("age").lock(AlterLock::None)`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct AlterTableStatement {
+    pub table: Table,
+    pub definitions: Vec<AlterTableDefinition>,
+    pub algorithm: Option<AlterAlgorithm>,
+    pub lock: Option<AlterLock>,
+}
+
+impl AlterTableStatement {
+    pub fn new<T: Into<Table>>(table: T) -> Self {
+        AlterTableStatement {
+            table: table.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn definition(mut self, definition: AlterTableDefinition) -> Self {
+        self.definitions.push(definition);
+        self
+    }
+
+    pub fn add_column(self, spec: ColumnSpecification) -> Self {
+        self.definition(AlterTableDefinition::AddColumn(spec, None))
+    }
+
+    pub fn drop_column(self, name: &str) -> Self {
+        self.definition(AlterTableDefinition::DropColumn(String::from(name)))
+    }
+
+    /// Replaces the definition of the column `name`, renaming it if `spec` names another column.
+    pub fn change_column(self, name: &str, spec: ColumnSpecification) -> Self {
+        self.definition(AlterTableDefinition::ChangeColumn(
+            String::from(name),
+            spec,
+            None,
+        ))
+    }
+
+    pub fn modify_column(self, spec: ColumnSpecification) -> Self {
+        self.definition(AlterTableDefinition::ModifyColumn(spec, None))
+    }
+
+    pub fn rename_column(self, from: &str, to: &str) -> Self {
+        self.definition(AlterTableDefinition::RenameColumn(
+            String::from(from),
+            String::from(to),
+        ))
+    }
+
+    /// Places the column added or redefined by the last definition at `position`.
+    ///
+    /// Panics if the last definition does not add, change or modify a column.
+    pub fn position(mut self, position: ColumnPosition) -> Self {
+        match self.definitions.last_mut() {
+            Some(&mut AlterTableDefinition::AddColumn(_, ref mut at))
+            | Some(&mut AlterTableDefinition::ChangeColumn(_, _, ref mut at))
+            | Some(&mut AlterTableDefinition::ModifyColumn(_, ref mut at)) => *at = Some(position),
+            _ => panic!("only an added, changed or modified column has a position"),
+        }
+        self
+    }
+
+    pub fn add_key(self, key: TableKey) -> Self {
+        self.definition(AlterTableDefinition::AddKey(key))
+    }
+
+    pub fn drop_primary_key(self) -> Self {
+        self.definition(AlterTableDefinition::DropPrimaryKey)
+    }
+
+    pub fn drop_key(self, name: &str) -> Self {
+        self.definition(AlterTableDefinition::DropKey(String::from(name)))
+    }
+
+    pub fn add_foreign_key(self, fkey: ForeignKeySpecification) -> Self {
+        self.definition(AlterTableDefinition::AddForeignKey(fkey))
+    }
+
+    pub fn drop_foreign_key(self, name: &str) -> Self {
+        self.definition(AlterTableDefinition::DropForeignKey(String::from(name)))
+    }
+
+    pub fn algorithm(mut self, algorithm: AlterAlgorithm) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    pub fn lock(mut self, lock: AlterLock) -> Self {
+        self.lock = Some(lock);
+        self
+    }
+}
+
+impl fmt::Display for AlterTableStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ALTER TABLE {}", self.table.escaped_name())?;
+        let mut items: Vec<_> = self.definitions.iter().map(|d| d.to_string()).collect();
+        if let Some(algorithm) = self.algorithm {
+            items.push(format!("ALGORITHM={}", algorithm));
+        }
+        if let Some(lock) = self.lock {
+            items.push(format!("LOCK={}", lock));
+        }
+        if !items.is_empty() {
+            write!(f, " {}", items.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// An element of the comma-separated list following `ALTER TABLE name`.
+#[allow(clippy::large_enum_variant)]
+enum AlterItem {
+    Definition(AlterTableDefinition),
+    Algorithm(AlterAlgorithm),
+    Lock(AlterLock),
+}
+
+fn keyword_then<'a, O, F>(
+    keyword: &'static str,
+    parser: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O>,
+{
+    preceded(pair(tag_no_case(keyword), multispace1), parser)
+}
+
+fn optional_column_keyword(i: &str) -> IResult<&str, Option<&str>> {
+    opt(terminated(tag_no_case("column"), multispace1))(i)
+}
+
+fn column_position(i: &str) -> IResult<&str, ColumnPosition> {
+    alt((
+        value(ColumnPosition::First, tag_no_case("first")),
+        map(keyword_then("after", sql_identifier), |name| {
+            ColumnPosition::After(String::from(name))
+        }),
+    ))(i)
+}
+
+/// A column definition, followed by its position if it has one.
+fn positioned_field(i: &str) -> IResult<&str, (ColumnSpecification, Option<ColumnPosition>)> {
+    pair(
+        field_specification,
+        opt(preceded(multispace0, column_position)),
+    )(i)
+}
+
+fn add_definition(i: &str) -> IResult<&str, AlterTableDefinition> {
+    keyword_then(
+        "add",
+        alt((
+            map(
+                foreign_key_specification,
+                AlterTableDefinition::AddForeignKey,
+            ),
+            map(key_specification, AlterTableDefinition::AddKey),
+            map(
+                preceded(optional_column_keyword, positioned_field),
+                |(spec, position)| AlterTableDefinition::AddColumn(spec, position),
+            ),
+        )),
+    )(i)
+}
+
+fn drop_definition(i: &str) -> IResult<&str, AlterTableDefinition> {
+    keyword_then(
+        "drop",
+        alt((
+            value(
+                AlterTableDefinition::DropPrimaryKey,
+                tuple((tag_no_case("primary"), multispace1, tag_no_case("key"))),
+            ),
+            map(
+                preceded(
+                    tuple((
+                        tag_no_case("foreign"),
+                        multispace1,
+                        tag_no_case("key"),
+                        multispace1,
+                    )),
+                    sql_identifier,
+                ),
+                |name| AlterTableDefinition::DropForeignKey(String::from(name)),
+            ),
+            map(
+                preceded(
+                    pair(alt((tag_no_case("index"), tag_no_case("key"))), multispace1),
+                    sql_identifier,
+                ),
+                |name| AlterTableDefinition::DropKey(String::from(name)),
+            ),
+            map(preceded(optional_column_keyword, sql_identifier), |name| {
+                AlterTableDefinition::DropColumn(String::from(name))
+            }),
+        )),
+    )(i)
+}
+
+fn change_definition(i: &str) -> IResult<&str, AlterTableDefinition> {
+    map(
+        keyword_then(
+            "change",
+            pair(
+                preceded(
+                    optional_column_keyword,
+                    terminated(sql_identifier, multispace1),
+                ),
+                positioned_field,
+            ),
+        ),
+        |(name, (spec, position))| {
+            AlterTableDefinition::ChangeColumn(String::from(name), spec, position)
+        },
+    )(i)
+}
+
+fn modify_definition(i: &str) -> IResult<&str, AlterTableDefinition> {
+    map(
+        keyword_then(
+            "modify",
+            preceded(optional_column_keyword, positioned_field),
+        ),
+        |(spec, position)| AlterTableDefinition::ModifyColumn(spec, position),
+    )(i)
+}
+
+fn rename_definition(i: &str) -> IResult<&str, AlterTableDefinition> {
+    map(
+        keyword_then(
+            "rename",
+            preceded(
+                pair(tag_no_case("column"), multispace1),
+                tuple((
+                    sql_identifier,
+                    multispace1,
+                    tag_no_case("to"),
+                    multispace1,
+                    sql_identifier,
+                )),
+            ),
+        ),
+        |(from, _, _, _, to)| {
+            AlterTableDefinition::RenameColumn(String::from(from), String::from(to))
+        },
+    )(i)
+}
+
+/// `name [=] value`, as the `ALGORITHM` and `LOCK` options are written.
+fn option<'a, O, F>(name: &'static str, values: F) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O>,
+{
+    preceded(
+        tuple((
+            tag_no_case(name),
+            alt((
+                map(tuple((multispace0, tag("="), multispace0)), |_| ()),
+                map(multispace1, |_| ()),
+            )),
+        )),
+        values,
+    )
+}
+
+fn alter_item(i: &str) -> IResult<&str, AlterItem> {
+    alt((
+        map(add_definition, AlterItem::Definition),
+        map(drop_definition, AlterItem::Definition),
+        map(change_definition, AlterItem::Definition),
+        map(modify_definition, AlterItem::Definition),
+        map(rename_definition, AlterItem::Definition),
+        map(
+            option(
+                "algorithm",
+                alt((
+                    value(AlterAlgorithm::Default, tag_no_case("default")),
+                    value(AlterAlgorithm::Instant, tag_no_case("instant")),
+                    value(AlterAlgorithm::Inplace, tag_no_case("inplace")),
+                    value(AlterAlgorithm::Copy, tag_no_case("copy")),
+                )),
+            ),
+            AlterItem::Algorithm,
+        ),
+        map(
+            option(
+                "lock",
+                alt((
+                    value(AlterLock::Default, tag_no_case("default")),
+                    value(AlterLock::None, tag_no_case("none")),
+                    value(AlterLock::Shared, tag_no_case("shared")),
+                    value(AlterLock::Exclusive, tag_no_case("exclusive")),
+                )),
+            ),
+            AlterItem::Lock,
+        ),
+    ))(i)
+}
+
+pub fn alter_table(i: &str) -> IResult<&str, AlterTableStatement> {
+    let (remaining_input, (_, _, _, _, table, _, items, _)) = tuple((
+        tag_no_case("alter"),
+        multispace1,
+        tag_no_case("table"),
+        multispace1,
+        table_name,
+        multispace1,
+        many1(terminated(alter_item, opt(ws_sep_comma))),
+        statement_terminator,
+    ))(i)?;
+
+    let mut alter = AlterTableStatement::new(table);
+    for item in items {
+        match item {
+            AlterItem::Definition(definition) => alter.definitions.push(definition),
+            AlterItem::Algorithm(algorithm) => alter.algorithm = Some(algorithm),
+            AlterItem::Lock(lock) => alter.lock = Some(lock),
+        }
+    }
+    Ok((remaining_input, alter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use column::{Column, ColumnConstraint};
+    use common::{IndexOptions, SqlType};
+
+    #[test]
+    fn build_alter_table() {
+        let alter = AlterTableStatement::new("users")
+            .add_column(ColumnSpecification::with_constraints(
+                Column::from("email"),
+                SqlType::Varchar(255),
+                vec![ColumnConstraint::NotNull],
+            ))
+            .drop_column("age")
+            .change_column(
+                "name",
+                ColumnSpecification::new(Column::from("full_name"), SqlType::Text),
+            )
+            .add_key(TableKey::UniqueKey(
+                Some(String::from("email_idx")),
+                vec![Column::from("email").into()],
+                IndexOptions::default(),
+            ))
+            .drop_key("name_idx")
+            .algorithm(AlterAlgorithm::Inplace)
+            .lock(AlterLock::None);
+        let sql = alter.to_string();
+        assert_eq!(
+            sql,
+            "ALTER TABLE users ADD COLUMN email VARCHAR(255) NOT NULL, DROP COLUMN age, \
+             CHANGE COLUMN name full_name TEXT, ADD UNIQUE KEY email_idx (email), \
+             DROP INDEX name_idx, ALGORITHM=INPLACE, LOCK=NONE"
+        );
+        assert_eq!(alter_table(&sql).unwrap().1, alter);
+    }
+
+    #[test]
+    fn parse_alter_table() {
+        let res = alter_table(
+            "ALTER TABLE t ADD c int, ADD PRIMARY KEY (id), DROP FOREIGN KEY fk_1, \
+             DROP PRIMARY KEY, CHANGE c d bigint, DROP KEY k, ALGORITHM = INSTANT;",
+        );
+        let alter = res.unwrap().1;
+        assert_eq!(alter.definitions.len(), 6);
+        assert_eq!(alter.algorithm, Some(AlterAlgorithm::Instant));
+        assert_eq!(alter.lock, None);
+        assert_eq!(
+            alter.to_string(),
+            "ALTER TABLE t ADD COLUMN c INT(32), ADD PRIMARY KEY (id), DROP FOREIGN KEY fk_1, \
+             DROP PRIMARY KEY, CHANGE COLUMN c d BIGINT(1), DROP INDEX k, ALGORITHM=INSTANT"
+        );
+        assert!(alter_table("ALTER TABLE t AS u DROP c").is_err());
+    }
+
+    #[test]
+    fn column_positions() {
+        let alter = alter_table(
+            "ALTER TABLE t RENAME COLUMN a TO b, MODIFY b INT FIRST, ADD COLUMN c AFTER b, \
+             CHANGE d e text after `order`",
+        )
+        .unwrap()
+        .1;
+        assert_eq!(
+            alter,
+            AlterTableStatement::new("t")
+                .rename_column("a", "b")
+                .modify_column(ColumnSpecification::new(
+                    Column::from("b"),
+                    SqlType::Int(32)
+                ))
+                .position(ColumnPosition::First)
+                .add_column(ColumnSpecification {
+                    sql_type: None,
+                    ..ColumnSpecification::new(Column::from("c"), SqlType::Text)
+                })
+                .position(ColumnPosition::After(String::from("b")))
+                .change_column(
+                    "d",
+                    ColumnSpecification::new(Column::from("e"), SqlType::Text)
+                )
+                .position(ColumnPosition::After(String::from("order")))
+        );
+        let sql = alter.to_string();
+        assert_eq!(
+            sql,
+            "ALTER TABLE t RENAME COLUMN a TO b, MODIFY COLUMN b INT(32) FIRST, \
+             ADD COLUMN c AFTER b, CHANGE COLUMN d e TEXT AFTER `order`"
+        );
+        assert_eq!(alter_table(&sql).unwrap().1, alter);
+        // a column without a position stays where it is
+        assert_eq!(
+            alter_table("ALTER TABLE t MODIFY COLUMN b bigint")
+                .unwrap()
+                .1,
+            AlterTableStatement::new("t").modify_column(ColumnSpecification::new(
+                Column::from("b"),
+                SqlType::Bigint(1)
+            ))
+        );
+        assert!(alter_table("ALTER TABLE t RENAME a TO b").is_err());
+    }
+}
@@ -1,28 +1,61 @@
-use nom::multispace;
-use nom::types::CompleteByteSlice;
-use std::{fmt, str};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::char;
+use nom::combinator::{map, not, opt, value};
+use nom::multi::many1;
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
 
-use column::Column;
+use column::{Column, FunctionExpression};
 use common::{
-    as_alias, column_identifier_no_alias, integer_literal, opt_multispace, type_identifier,
-    Literal, SqlType,
+    as_alias, column_identifier_no_alias, escape_user_variable, integer_literal, type_identifier,
+    user_variable, whitespace0, whitespace1, Literal, SqlType,
 };
+use keywords::{escape_if_keyword, tag_no_case};
+use limits::nested;
 
-#[derive(Debug, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum ArithmeticOperator {
     Add,
     Subtract,
     Multiply,
     Divide,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
-#[derive(Debug, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+impl ArithmeticOperator {
+    /// How tightly the operator binds, following MySQL: `^` first, then multiplication and
+    /// division, addition and subtraction, shifts, `&` and finally `|`.
+    pub fn precedence(&self) -> u8 {
+        match *self {
+            ArithmeticOperator::BitXor => 6,
+            ArithmeticOperator::Multiply | ArithmeticOperator::Divide => 5,
+            ArithmeticOperator::Add | ArithmeticOperator::Subtract => 4,
+            ArithmeticOperator::ShiftLeft | ArithmeticOperator::ShiftRight => 3,
+            ArithmeticOperator::BitAnd => 2,
+            ArithmeticOperator::BitOr => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum ArithmeticBase {
     Column(Column),
     Scalar(Literal),
+    Variable(String),
+    /// Bitwise inversion, `~operand`.
+    BitNot(Box<ArithmeticBase>),
+    /// An operand that is itself an expression, binding more tightly than the operator it is an
+    /// operand of.
+    Nested(Box<ArithmeticExpression>),
 }
 
-#[derive(Debug, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[derive(Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct ArithmeticExpression {
     pub op: ArithmeticOperator,
     pub left: ArithmeticBase,
@@ -30,7 +63,95 @@ pub struct ArithmeticExpression {
     pub alias: Option<String>,
 }
 
+impl ArithmeticBase {
+    /// Collects the columns, literals and variables the operand is computed from.
+    pub(crate) fn operands<'a>(&'a self, operands: &mut Vec<&'a ArithmeticBase>) {
+        match *self {
+            ArithmeticBase::BitNot(ref base) => base.operands(operands),
+            ArithmeticBase::Nested(ref expr) => {
+                expr.left.operands(operands);
+                expr.right.operands(operands);
+            }
+            _ => operands.push(self),
+        }
+    }
+
+    /// The aggregate functions used by the operand.
+    pub fn aggregates(&self) -> Vec<&FunctionExpression> {
+        let mut operands = vec![];
+        self.operands(&mut operands);
+        operands
+            .into_iter()
+            .flat_map(|base| match *base {
+                ArithmeticBase::Column(ref col) => col.aggregates(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    /// Collects the columns, literals and variables the operand is computed from, mutably.
+    pub(crate) fn operands_mut<'a>(&'a mut self, operands: &mut Vec<&'a mut ArithmeticBase>) {
+        match *self {
+            ArithmeticBase::BitNot(ref mut base) => base.operands_mut(operands),
+            ArithmeticBase::Nested(ref mut expr) => {
+                expr.left.operands_mut(operands);
+                expr.right.operands_mut(operands);
+            }
+            _ => operands.push(self),
+        }
+    }
+}
+
+impl Clone for ArithmeticExpression {
+    fn clone(&self) -> Self {
+        // chains such as `a + b + c` nest to the left, and may be too long to copy recursively
+        let mut chain = vec![self];
+        while let ArithmeticBase::Nested(ref left) = chain[chain.len() - 1].left {
+            chain.push(left);
+        }
+        let innermost = chain.pop().unwrap();
+        let mut cloned = ArithmeticExpression {
+            op: innermost.op.clone(),
+            left: innermost.left.clone(),
+            right: innermost.right.clone(),
+            alias: innermost.alias.clone(),
+        };
+        while let Some(expr) = chain.pop() {
+            cloned = ArithmeticExpression {
+                op: expr.op.clone(),
+                left: ArithmeticBase::Nested(Box::new(cloned)),
+                right: expr.right.clone(),
+                alias: expr.alias.clone(),
+            };
+        }
+        cloned
+    }
+}
+
 impl ArithmeticExpression {
+    /// The columns, literals and variables the expression is computed from, including those of
+    /// nested expressions, from left to right.
+    pub fn operands(&self) -> Vec<&ArithmeticBase> {
+        let mut operands = vec![];
+        self.left.operands(&mut operands);
+        self.right.operands(&mut operands);
+        operands
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut ArithmeticBase> {
+        let mut operands = vec![];
+        self.left.operands_mut(&mut operands);
+        self.right.operands_mut(&mut operands);
+        operands
+    }
+
+    /// The aggregate functions used by any operand.
+    pub fn aggregates(&self) -> Vec<&FunctionExpression> {
+        let mut aggregates = self.left.aggregates();
+        aggregates.extend(self.right.aggregates());
+        aggregates
+    }
+
     pub fn new(
         op: ArithmeticOperator,
         left: ArithmeticBase,
@@ -38,10 +159,10 @@ impl ArithmeticExpression {
         alias: Option<String>,
     ) -> Self {
         Self {
-            op: op,
-            left: left,
-            right: right,
-            alias: alias,
+            op,
+            left,
+            right,
+            alias,
         }
     }
 }
@@ -53,6 +174,11 @@ impl fmt::Display for ArithmeticOperator {
             ArithmeticOperator::Subtract => write!(f, "-"),
             ArithmeticOperator::Multiply => write!(f, "*"),
             ArithmeticOperator::Divide => write!(f, "/"),
+            ArithmeticOperator::BitAnd => write!(f, "&"),
+            ArithmeticOperator::BitOr => write!(f, "|"),
+            ArithmeticOperator::BitXor => write!(f, "^"),
+            ArithmeticOperator::ShiftLeft => write!(f, "<<"),
+            ArithmeticOperator::ShiftRight => write!(f, ">>"),
         }
     }
 }
@@ -61,83 +187,192 @@ impl fmt::Display for ArithmeticBase {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             ArithmeticBase::Column(ref col) => write!(f, "{}", col),
-            ArithmeticBase::Scalar(ref lit) => write!(f, "{}", lit.to_string()),
+            ArithmeticBase::Scalar(ref lit) => write!(f, "{}", lit),
+            ArithmeticBase::Variable(ref var) => write!(f, "{}", escape_user_variable(var)),
+            ArithmeticBase::BitNot(ref base) => write!(f, "~{}", base),
+            ArithmeticBase::Nested(ref expr) => write!(f, "({})", expr),
+        }
+    }
+}
+
+impl ArithmeticExpression {
+    /// Writes an operand, leaving out the parentheses around a nested expression where precedence
+    /// and left associativity make them redundant.
+    fn fmt_operand(
+        &self,
+        f: &mut fmt::Formatter,
+        operand: &ArithmeticBase,
+        left: bool,
+    ) -> fmt::Result {
+        match *operand {
+            ArithmeticBase::Nested(ref expr)
+                if expr.alias.is_none()
+                    && (expr.op.precedence() > self.op.precedence()
+                        || left && expr.op.precedence() == self.op.precedence()) =>
+            {
+                write!(f, "{}", expr)
+            }
+            ref operand => write!(f, "{}", operand),
         }
     }
 }
 
 impl fmt::Display for ArithmeticExpression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.alias {
-            Some(ref alias) => write!(f, "{} {} {} AS {}", self.left, self.op, self.right, alias),
-            None => write!(f, "{} {} {}", self.left, self.op, self.right),
+        // left operands written without parentheses make up chains such as `a + b + c`, which
+        // may be too long to print recursively
+        let mut chain = vec![self];
+        while let Some(&expr) = chain.last() {
+            match expr.left {
+                ArithmeticBase::Nested(ref left)
+                    if left.alias.is_none() && left.op.precedence() >= expr.op.precedence() =>
+                {
+                    chain.push(left)
+                }
+                _ => break,
+            }
+        }
+        let innermost = chain[chain.len() - 1];
+        innermost.fmt_operand(f, &innermost.left, true)?;
+        for expr in chain.iter().rev() {
+            write!(f, " {} ", expr.op)?;
+            expr.fmt_operand(f, &expr.right, false)?;
+        }
+        if let Some(ref alias) = self.alias {
+            write!(f, " AS {}", escape_if_keyword(alias))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn arithmetic_cast(i: &str) -> IResult<&str, (ArithmeticBase, Option<SqlType>)> {
+    alt((
+        map(
+            tuple((
+                tag_no_case("cast"),
+                whitespace0,
+                tag("("),
+                whitespace0,
+                // TODO(malte): should be arbitrary expr
+                arithmetic_base,
+                whitespace1,
+                tag_no_case("as"),
+                whitespace1,
+                opt(terminated(tag_no_case("signed"), whitespace1)),
+                type_identifier,
+                whitespace0,
+                tag(")"),
+            )),
+            |(_, _, _, _, v, _, _, _, _, typ, _, _)| (v, Some(typ)),
+        ),
+        map(arithmetic_base, |v| (v, None)),
+    ))(i)
+}
+
+/// Parse standard math and bitwise operators. `&&` and `||` are logical operators rather than
+/// bitwise ones.
+pub fn arithmetic_operator(i: &str) -> IResult<&str, ArithmeticOperator> {
+    alt((
+        value(ArithmeticOperator::Add, tag("+")),
+        value(ArithmeticOperator::Subtract, tag("-")),
+        value(ArithmeticOperator::Multiply, tag("*")),
+        value(ArithmeticOperator::Divide, tag("/")),
+        value(ArithmeticOperator::ShiftLeft, tag("<<")),
+        value(ArithmeticOperator::ShiftRight, tag(">>")),
+        value(
+            ArithmeticOperator::BitAnd,
+            terminated(tag("&"), not(char('&'))),
+        ),
+        value(
+            ArithmeticOperator::BitOr,
+            terminated(tag("|"), not(char('|'))),
+        ),
+        value(ArithmeticOperator::BitXor, tag("^")),
+    ))(i)
+}
+
+/// Parses `~operand`, returning the operand that is inverted.
+pub fn bit_not(i: &str) -> IResult<&str, ArithmeticBase> {
+    nested(
+        tag("~"),
+        preceded(pair(tag("~"), whitespace0), arithmetic_base),
+    )(i)
+}
+
+/// Base case for nested arithmetic expressions: column name, literal or user variable, the
+/// bitwise inversion of an operand, or a parenthesized expression.
+pub fn arithmetic_base(i: &str) -> IResult<&str, ArithmeticBase> {
+    alt((
+        map(integer_literal, ArithmeticBase::Scalar),
+        map(user_variable, |v| ArithmeticBase::Variable(String::from(v))),
+        map(column_identifier_no_alias, ArithmeticBase::Column),
+        map(bit_not, |base| ArithmeticBase::BitNot(Box::new(base))),
+        map(
+            nested(
+                tag("("),
+                delimited(
+                    pair(tag("("), whitespace0),
+                    arithmetic_expression,
+                    pair(whitespace0, tag(")")),
+                ),
+            ),
+            |expr| ArithmeticBase::Nested(Box::new(expr)),
+        ),
+    ))(i)
+}
+
+/// Combines operands and the operators between them into a tree, reducing the leftmost of the
+/// most tightly binding operators first.
+fn precedence_tree(
+    mut operands: Vec<ArithmeticBase>,
+    mut operators: Vec<ArithmeticOperator>,
+) -> ArithmeticExpression {
+    loop {
+        let mut at = 0;
+        for (idx, op) in operators.iter().enumerate() {
+            if op.precedence() > operators[at].precedence() {
+                at = idx;
+            }
         }
+        let expr = ArithmeticExpression {
+            op: operators.remove(at),
+            left: operands.remove(at),
+            right: operands.remove(at),
+            alias: None,
+        };
+        if operators.is_empty() {
+            return expr;
+        }
+        operands.insert(at, ArithmeticBase::Nested(Box::new(expr)));
     }
 }
 
-named!(pub arithmetic_cast<CompleteByteSlice, (ArithmeticBase, Option<SqlType>)>,
-    alt!(
-        do_parse!(
-            tag_no_case!("cast") >>
-            opt_multispace >>
-            tag!("(") >>
-            opt_multispace >>
-            // TODO(malte): should be arbitrary expr
-            v: arithmetic_base >>
-            multispace >>
-            tag_no_case!("as") >>
-            multispace >>
-            _sign: opt!(terminated!(tag_no_case!("signed"), multispace)) >>
-            typ: type_identifier >>
-            opt_multispace >>
-            tag!(")") >>
-            (v, Some(typ))
-        ) |
-        map!(arithmetic_base, |v| (v, None))
-    )
-);
-
-/// Parse standard math operators.
-/// TODO(malte): this doesn't currently observe operator precedence.
-named!(pub arithmetic_operator<CompleteByteSlice, ArithmeticOperator>,
-    alt!(
-          map!(tag!("+"), |_| ArithmeticOperator::Add)
-        | map!(tag!("-"), |_| ArithmeticOperator::Subtract)
-        | map!(tag!("*"), |_| ArithmeticOperator::Multiply)
-        | map!(tag!("/"), |_| ArithmeticOperator::Divide)
-    )
-);
-
-/// Base case for nested arithmetic expressions: column name or literal.
-named!(pub arithmetic_base<CompleteByteSlice, ArithmeticBase>,
-    alt!(
-          map!(integer_literal, |il| ArithmeticBase::Scalar(il))
-        | map!(column_identifier_no_alias, |ci| ArithmeticBase::Column(ci))
-    )
-);
-
-/// Parse simple arithmetic expressions combining literals, and columns and literals.
-/// TODO(malte): this doesn't currently support nested expressions.
-named!(pub arithmetic_expression<CompleteByteSlice, ArithmeticExpression>,
-    do_parse!(
-        left: arithmetic_cast >>
-        opt_multispace >>
-        op: arithmetic_operator >>
-        opt_multispace >>
-        right: arithmetic_cast >>
-        alias: opt!(as_alias) >>
-        (ArithmeticExpression {
-            op: op,
+/// Parse arithmetic expressions combining literals and columns, observing operator precedence.
+pub fn arithmetic_expression(i: &str) -> IResult<&str, ArithmeticExpression> {
+    map(
+        tuple((
+            arithmetic_cast,
+            many1(pair(
+                delimited(whitespace0, arithmetic_operator, whitespace0),
+                arithmetic_cast,
+            )),
+            opt(as_alias),
+        )),
+        |(first, rest, alias)| {
             // TODO(malte): discards casts
-            left: left.0,
-            right: right.0,
-            alias: match alias {
-                None => None,
-                Some(a) => Some(String::from(a)),
-            },
-        })
-    )
-);
+            let mut operands = vec![first.0];
+            let mut operators = vec![];
+            for (op, operand) in rest {
+                operators.push(op);
+                operands.push(operand.0);
+            }
+            ArithmeticExpression {
+                alias: alias.map(String::from),
+                ..precedence_tree(operands, operators)
+            }
+        },
+    )(i)
+}
 
 #[cfg(test)]
 mod tests {
@@ -196,7 +431,7 @@ mod tests {
             ArithmeticExpression::new(
                 Subtract,
                 ABColumn(Column {
-                    name: String::from("max(foo)"),
+                    name: "max(foo)".into(),
                     alias: None,
                     table: None,
                     function: Some(Box::new(FunctionExpression::Max("foo".into()))),
@@ -207,18 +442,85 @@ mod tests {
         ];
 
         for (i, e) in lit_ae.iter().enumerate() {
-            let res = arithmetic_expression(CompleteByteSlice(e.as_bytes()));
+            let res = arithmetic_expression(e);
             assert!(res.is_ok());
             assert_eq!(res.unwrap().1, expected_lit_ae[i]);
         }
 
         for (i, e) in col_lit_ae.iter().enumerate() {
-            let res = arithmetic_expression(CompleteByteSlice(e.as_bytes()));
+            let res = arithmetic_expression(e);
             assert!(res.is_ok());
             assert_eq!(res.unwrap().1, expected_col_lit_ae[i]);
         }
     }
 
+    #[test]
+    fn it_parses_bitwise_expressions_with_precedence() {
+        use super::ArithmeticBase::{Nested, Scalar};
+        use super::ArithmeticOperator::*;
+
+        let res = arithmetic_expression("flags & 4 | 1 << 2 + 1 AS f");
+        let expr = res.unwrap().1;
+        // `|` binds loosest, then `&`, then `<<`, then `+`
+        assert_eq!(expr.op, BitOr);
+        assert_eq!(
+            expr.left,
+            Nested(Box::new(ArithmeticExpression::new(
+                BitAnd,
+                ArithmeticBase::Column("flags".into()),
+                Scalar(4.into()),
+                None,
+            )))
+        );
+        assert_eq!(
+            expr.right,
+            Nested(Box::new(ArithmeticExpression::new(
+                ShiftLeft,
+                Scalar(1.into()),
+                Nested(Box::new(ArithmeticExpression::new(
+                    Add,
+                    Scalar(2.into()),
+                    Scalar(1.into()),
+                    None
+                ))),
+                None,
+            )))
+        );
+        assert_eq!(expr.alias, Some(String::from("f")));
+        assert_eq!(expr.to_string(), "flags & 4 | 1 << 2 + 1 AS f");
+
+        let expr = arithmetic_expression("~mask ^ 3 * 2 - a >> 1").unwrap().1;
+        assert_eq!(expr.op, ShiftRight);
+        assert_eq!(expr.to_string(), "~mask ^ 3 * 2 - a >> 1");
+        assert_eq!(
+            expr.operands(),
+            vec![
+                &ArithmeticBase::Column("mask".into()),
+                &Scalar(3.into()),
+                &Scalar(2.into()),
+                &ArithmeticBase::Column("a".into()),
+                &Scalar(1.into()),
+            ]
+        );
+
+        let expr = ArithmeticExpression::new(
+            Subtract,
+            Scalar(1.into()),
+            Nested(Box::new(ArithmeticExpression::new(
+                Subtract,
+                Scalar(2.into()),
+                Scalar(3.into()),
+                None,
+            ))),
+            None,
+        );
+        assert_eq!(expr.to_string(), "1 - (2 - 3)");
+
+        // logical operators are not bitwise ones
+        assert!(arithmetic_expression("a && b").is_err());
+        assert!(arithmetic_expression("a || b").is_err());
+    }
+
     #[test]
     fn it_displays_arithmetic_expressions() {
         use super::ArithmeticBase::Column as ABColumn;
@@ -249,6 +551,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn nested_operands_read_back() {
+        use super::ArithmeticBase::{BitNot, Column as ABColumn, Nested};
+        use super::ArithmeticOperator::*;
+
+        let sum = ArithmeticExpression::new(Add, ABColumn("b".into()), ABColumn("c".into()), None);
+        let expressions = [
+            ArithmeticExpression::new(
+                Multiply,
+                ABColumn("a".into()),
+                Nested(Box::new(sum.clone())),
+                None,
+            ),
+            ArithmeticExpression::new(
+                Multiply,
+                Nested(Box::new(sum.clone())),
+                ABColumn("a".into()),
+                None,
+            ),
+            ArithmeticExpression::new(
+                BitAnd,
+                ABColumn("a".into()),
+                BitNot(Box::new(Nested(Box::new(sum)))),
+                None,
+            ),
+        ];
+        let expected_strings = ["a * (b + c)", "(b + c) * a", "a & ~(b + c)"];
+        for (e, expected) in expressions.iter().zip(&expected_strings) {
+            let printed = e.to_string();
+            assert_eq!(&printed, expected);
+            assert_eq!(arithmetic_expression(&printed), Ok(("", e.clone())));
+        }
+    }
+
     #[test]
     fn it_parses_arithmetic_casts() {
         use super::ArithmeticBase::Column as ABColumn;
@@ -279,10 +615,9 @@ mod tests {
         ];
 
         for (i, e) in exprs.iter().enumerate() {
-            let res = arithmetic_expression(CompleteByteSlice(e.as_bytes()));
+            let res = arithmetic_expression(e);
             assert!(res.is_ok(), "{} failed to parse", e);
             assert_eq!(res.unwrap().1, expected[i]);
         }
     }
-
 }
@@ -0,0 +1,78 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::multispace0;
+use nom::combinator::{map, opt, value};
+use nom::multi::many1;
+use nom::sequence::{delimited, pair, tuple};
+use nom::IResult;
+use std::fmt;
+
+use column::Column;
+use common::{as_alias, column_identifier_no_alias, integer_literal, Literal};
+use keywords::escape_if_keyword;
+
+/// Element access on an array column, e.g. `matrix[1][2]`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct SubscriptExpression {
+    pub column: Column,
+    pub indices: Vec<Literal>,
+    pub alias: Option<String>,
+}
+
+impl fmt::Display for SubscriptExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.column)?;
+        for index in &self.indices {
+            write!(f, "[{}]", index)?;
+        }
+        if let Some(ref alias) = self.alias {
+            write!(f, " AS {}", escape_if_keyword(alias))?;
+        }
+        Ok(())
+    }
+}
+
+fn subscript(i: &str) -> IResult<&str, Literal> {
+    delimited(
+        pair(tag("["), multispace0),
+        alt((integer_literal, value(Literal::Placeholder, tag("?")))),
+        pair(multispace0, tag("]")),
+    )(i)
+}
+
+/// Parses a column followed by one or more subscripts, and an optional alias.
+pub fn subscript_expression(i: &str) -> IResult<&str, SubscriptExpression> {
+    map(
+        tuple((column_identifier_no_alias, many1(subscript), opt(as_alias))),
+        |(column, indices, alias)| SubscriptExpression {
+            column,
+            indices,
+            alias: alias.map(String::from),
+        },
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_dimensional_subscript() {
+        let res = subscript_expression("t.matrix[1][ 2 ] AS cell");
+        assert_eq!(
+            res.unwrap().1,
+            SubscriptExpression {
+                column: Column::from("t.matrix"),
+                indices: vec![Literal::Integer(1), Literal::Integer(2)],
+                alias: Some(String::from("cell")),
+            }
+        );
+    }
+
+    #[test]
+    fn format_subscript() {
+        let res = subscript_expression("tags[?]");
+        assert_eq!(format!("{}", res.unwrap().1), "tags[?]");
+        assert!(subscript_expression("tags").is_err());
+    }
+}
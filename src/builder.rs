@@ -0,0 +1,329 @@
+use column::{Column, ColumnConstraint, ColumnSpecification};
+use common::{
+    FieldDefinitionExpression, FieldValueExpression, IndexOptions, KeyPart, SqlType, TableKey,
+};
+use condition::ConditionExpression;
+use create::CreateTableStatement;
+use foreignkey::ForeignKeySpecification;
+use insert::{InsertPriority, InsertStatement};
+use join::{JoinConstraint, JoinOperator, JoinRightSide};
+use order::{FieldReference, OrderClause, OrderType};
+use select::{GroupByClause, JoinClause, LimitClause, SelectStatement};
+use simplify::and_conditions;
+use table::Table;
+
+fn columns(names: &[&str]) -> Vec<Column> {
+    names.iter().map(|&n| Column::from(n)).collect()
+}
+
+fn key_parts(names: &[&str]) -> Vec<KeyPart> {
+    names.iter().map(|&n| Column::from(n).into()).collect()
+}
+
+/// Builds a `SelectStatement` step by step, e.g.
+/// `SelectBuilder::from("users").columns(&["id"]).limit(10).build()`.
+#[derive(Clone, Debug, Default)]
+pub struct SelectBuilder {
+    select: SelectStatement,
+}
+
+impl SelectBuilder {
+    pub fn from<T: Into<Table>>(table: T) -> Self {
+        SelectBuilder::default().table(table)
+    }
+
+    /// Adds another table to the `FROM` list.
+    pub fn table<T: Into<Table>>(mut self, table: T) -> Self {
+        self.select.tables.push(table.into());
+        self
+    }
+
+    pub fn distinct(mut self) -> Self {
+        self.select.distinct = true;
+        self
+    }
+
+    pub fn field(mut self, field: FieldDefinitionExpression) -> Self {
+        self.select.fields.push(field);
+        self
+    }
+
+    /// Selects columns given as `name` or `table.name`.
+    pub fn columns(mut self, names: &[&str]) -> Self {
+        self.select.fields.extend(
+            columns(names)
+                .into_iter()
+                .map(FieldDefinitionExpression::Col),
+        );
+        self
+    }
+
+    pub fn all(self) -> Self {
+        self.field(FieldDefinitionExpression::All)
+    }
+
+    pub fn join<T: Into<Table>>(
+        mut self,
+        operator: JoinOperator,
+        table: T,
+        constraint: JoinConstraint,
+    ) -> Self {
+        self.select.join.push(JoinClause {
+            operator,
+            natural: false,
+            lateral: false,
+            right: JoinRightSide::Table(table.into()),
+            constraint,
+        });
+        self
+    }
+
+    /// Adds a condition to the `WHERE` clause, combined with any earlier ones using `AND`.
+    pub fn filter(mut self, cond: ConditionExpression) -> Self {
+        self.select.where_clause = and_conditions(
+            self.select
+                .where_clause
+                .take()
+                .into_iter()
+                .chain(Some(cond)),
+        );
+        self
+    }
+
+    pub fn group_by(mut self, names: &[&str]) -> Self {
+        let having = self.select.group_by.take().and_then(|g| g.having);
+        self.select.group_by = Some(GroupByClause {
+            columns: names.iter().map(|&n| FieldReference::from(n)).collect(),
+            having,
+        });
+        self
+    }
+
+    /// Sets the `HAVING` clause; requires an earlier call to `group_by`.
+    pub fn having(mut self, cond: ConditionExpression) -> Self {
+        if let Some(ref mut group_by) = self.select.group_by {
+            group_by.having = Some(cond);
+        }
+        self
+    }
+
+    pub fn order_by(mut self, name: &str, order: OrderType) -> Self {
+        self.select
+            .order
+            .get_or_insert_with(OrderClause::default)
+            .columns
+            .push((FieldReference::from(name), order));
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.select
+            .limit
+            .get_or_insert_with(LimitClause::default)
+            .limit = limit;
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.select
+            .limit
+            .get_or_insert_with(LimitClause::default)
+            .offset = offset;
+        self
+    }
+
+    pub fn build(self) -> SelectStatement {
+        self.select
+    }
+}
+
+/// Builds an `InsertStatement` step by step.
+#[derive(Clone, Debug, Default)]
+pub struct InsertBuilder {
+    insert: InsertStatement,
+}
+
+impl InsertBuilder {
+    pub fn new<T: Into<Table>>(table: T) -> Self {
+        InsertBuilder {
+            insert: InsertStatement {
+                table: table.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn columns(mut self, names: &[&str]) -> Self {
+        self.insert.fields = Some(columns(names));
+        self
+    }
+
+    /// Adds a row of values.
+    pub fn values<T: Into<FieldValueExpression>>(mut self, row: Vec<T>) -> Self {
+        self.insert
+            .data
+            .push(row.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn ignore(mut self) -> Self {
+        self.insert.ignore = true;
+        self
+    }
+
+    pub fn priority(mut self, priority: InsertPriority) -> Self {
+        self.insert.priority = Some(priority);
+        self
+    }
+
+    /// Restricts the insert to the named partition, in addition to any named before.
+    pub fn partition(mut self, name: &str) -> Self {
+        self.insert.partitions.push(String::from(name));
+        self
+    }
+
+    /// Adds an assignment to the `ON DUPLICATE KEY UPDATE` clause.
+    pub fn on_duplicate(mut self, name: &str, value: FieldValueExpression) -> Self {
+        self.insert
+            .on_duplicate
+            .get_or_insert_with(Vec::new)
+            .push((Column::from(name), value));
+        self
+    }
+
+    pub fn build(self) -> InsertStatement {
+        self.insert
+    }
+}
+
+/// Builds a `CreateTableStatement` step by step.
+#[derive(Clone, Debug, Default)]
+pub struct CreateTableBuilder {
+    create: CreateTableStatement,
+}
+
+impl CreateTableBuilder {
+    pub fn new<T: Into<Table>>(table: T) -> Self {
+        CreateTableBuilder {
+            create: CreateTableStatement {
+                table: table.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn column(self, name: &str, sql_type: SqlType) -> Self {
+        self.column_with_constraints(name, sql_type, vec![])
+    }
+
+    pub fn column_with_constraints(
+        mut self,
+        name: &str,
+        sql_type: SqlType,
+        constraints: Vec<ColumnConstraint>,
+    ) -> Self {
+        let mut column = Column::from(name);
+        column.table = Some(self.create.table.name.clone());
+        self.create
+            .fields
+            .push(ColumnSpecification::with_constraints(
+                column,
+                sql_type,
+                constraints,
+            ));
+        self
+    }
+
+    pub fn key(mut self, key: TableKey) -> Self {
+        self.create.keys.get_or_insert_with(Vec::new).push(key);
+        self
+    }
+
+    pub fn primary_key(self, names: &[&str]) -> Self {
+        self.key(TableKey::PrimaryKey(
+            None,
+            key_parts(names),
+            IndexOptions::default(),
+            false,
+        ))
+    }
+
+    pub fn unique_key(self, name: Option<&str>, names: &[&str]) -> Self {
+        self.key(TableKey::UniqueKey(
+            name.map(String::from),
+            key_parts(names),
+            IndexOptions::default(),
+        ))
+    }
+
+    pub fn foreign_key(mut self, fkey: ForeignKeySpecification) -> Self {
+        self.create.fkeys.get_or_insert_with(Vec::new).push(fkey);
+        self
+    }
+
+    pub fn build(self) -> CreateTableStatement {
+        self.create
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use column::ColumnConstraint;
+    use common::{Literal, Operator};
+    use condition::{ConditionBase, ConditionTree};
+
+    fn equals(name: &str, value: Literal) -> ConditionExpression {
+        ConditionExpression::ComparisonOp(ConditionTree {
+            operator: Operator::Equal,
+            left: Box::new(ConditionExpression::Base(ConditionBase::Field(
+                Column::from(name),
+            ))),
+            right: Box::new(ConditionExpression::Base(ConditionBase::Literal(value))),
+        })
+    }
+
+    #[test]
+    fn build_select() {
+        let select = SelectBuilder::from("users")
+            .columns(&["users.id", "name"])
+            .join(
+                JoinOperator::LeftJoin,
+                "posts",
+                JoinConstraint::Using(vec![Column::from("id")]),
+            )
+            .filter(equals("active", 1.into()))
+            .filter(equals("role", Literal::Placeholder))
+            .order_by("name", OrderType::OrderDescending)
+            .limit(10)
+            .offset(20)
+            .build();
+        assert_eq!(
+            select.to_string(),
+            "SELECT users.id, name FROM users LEFT JOIN posts USING (id) \
+             WHERE active = 1 AND role = ? ORDER BY name DESC LIMIT 10 OFFSET 20"
+        );
+    }
+
+    #[test]
+    fn build_insert_and_create_table() {
+        let insert = InsertBuilder::new("users")
+            .columns(&["id", "name"])
+            .values(vec![1.into(), Literal::String(String::from("a"))])
+            .build();
+        assert_eq!(
+            insert.to_string(),
+            "INSERT INTO users (id, name) VALUES (1, 'a')"
+        );
+
+        let create = CreateTableBuilder::new("users")
+            .column_with_constraints("id", SqlType::Int(32), vec![ColumnConstraint::NotNull])
+            .column("name", SqlType::Text)
+            .primary_key(&["id"])
+            .build();
+        assert_eq!(
+            create.to_string(),
+            "CREATE TABLE users (id INT(32) NOT NULL, name TEXT, PRIMARY KEY (id))"
+        );
+    }
+}
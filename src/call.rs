@@ -0,0 +1,92 @@
+use nom::bytes::complete::tag;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{opt, recognize};
+use nom::multi::separated_list0;
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{
+    field_value_expr, sql_identifier, statement_terminator, ws_sep_comma, FieldValueExpression,
+};
+use keywords::{escape_if_keyword, tag_no_case};
+
+/// `CALL procedure(argument, ...)`, which invokes a stored procedure. The name may be qualified
+/// with a schema, as in `CALL reports.daily()`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CallStatement {
+    pub name: String,
+    pub args: Vec<FieldValueExpression>,
+}
+
+impl fmt::Display for CallStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        // the name may be qualified with its schema, as `reports.daily`
+        let name = self
+            .name
+            .split('.')
+            .map(escape_if_keyword)
+            .collect::<Vec<_>>()
+            .join(".");
+        write!(f, "CALL {}({})", name, args)
+    }
+}
+
+pub fn call(i: &str) -> IResult<&str, CallStatement> {
+    let (remaining_input, (_, _, name, args, _)) = tuple((
+        tag_no_case("call"),
+        multispace1,
+        recognize(pair(
+            opt(terminated(sql_identifier, tag("."))),
+            sql_identifier,
+        )),
+        // MySQL lets a procedure without arguments be called without parentheses
+        opt(preceded(
+            multispace0,
+            delimited(
+                pair(tag("("), multispace0),
+                separated_list0(ws_sep_comma, field_value_expr),
+                pair(multispace0, tag(")")),
+            ),
+        )),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        CallStatement {
+            name: String::from(name),
+            args: args.unwrap_or_default(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{Literal, LiteralExpression};
+
+    #[test]
+    fn call_procedures() {
+        let res = call("CALL add_order(42, ?, 'x', @total);");
+        let stmt = res.unwrap().1;
+        assert_eq!(stmt.name, "add_order");
+        assert_eq!(stmt.args.len(), 4);
+        assert_eq!(
+            stmt.args[1],
+            FieldValueExpression::Literal(LiteralExpression::from(Literal::Placeholder))
+        );
+        assert_eq!(stmt.to_string(), "CALL add_order(42, ?, 'x', @total)");
+
+        let stmt = call("call reports.daily").unwrap().1;
+        assert_eq!(stmt.name, "reports.daily");
+        assert!(stmt.args.is_empty());
+        assert_eq!(stmt.to_string(), "CALL reports.daily()");
+    }
+}
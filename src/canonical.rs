@@ -0,0 +1,282 @@
+use std::mem;
+
+use common::Operator;
+use condition::{ConditionBase, ConditionExpression, ConditionTree};
+use create::SelectSpecification;
+use handler::HandlerAction;
+use join::{JoinConstraint, JoinRightSide};
+use lexer::{tokenize, TokenKind};
+use parser::SqlQuery;
+use select::{JoinClause, SelectStatement};
+use table::Table;
+
+/// Collects the operands of a chain of `op`, looking through parentheses. Chains may be too long
+/// to walk recursively, so the operands still to visit are kept on a stack.
+fn flatten(cond: ConditionExpression, op: &Operator, operands: &mut Vec<ConditionExpression>) {
+    let mut pending = vec![cond];
+    while let Some(cond) = pending.pop() {
+        match cond {
+            ConditionExpression::Bracketed(inner) => pending.push(*inner),
+            ConditionExpression::LogicalOp(tree) if tree.operator == *op => {
+                pending.push(*tree.right);
+                pending.push(*tree.left);
+            }
+            cond => operands.push(canonical_condition(cond)),
+        }
+    }
+}
+
+/// Parenthesizes `cond` if it would otherwise bind differently when printed as an operand.
+fn bracket_logical(cond: ConditionExpression) -> ConditionExpression {
+    match cond {
+        ConditionExpression::LogicalOp(_) => ConditionExpression::Bracketed(Box::new(cond)),
+        cond => cond,
+    }
+}
+
+fn canonical_condition(cond: ConditionExpression) -> ConditionExpression {
+    match cond {
+        ConditionExpression::Bracketed(inner) => canonical_condition(*inner),
+        ConditionExpression::LogicalOp(tree) => {
+            let op = tree.operator;
+            let mut operands = Vec::new();
+            flatten(*tree.left, &op, &mut operands);
+            flatten(*tree.right, &op, &mut operands);
+            // AND and OR are commutative, so order the operands by their printed form.
+            let mut operands: Vec<_> = operands
+                .into_iter()
+                .map(|o| {
+                    let o = bracket_logical(o);
+                    (o.to_string(), o)
+                })
+                .collect();
+            operands.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut operands = operands.into_iter().rev().map(|(_, o)| o);
+            let last = operands.next().expect("logical operator without operands");
+            operands.fold(last, |right, left| {
+                ConditionExpression::LogicalOp(ConditionTree {
+                    operator: op.clone(),
+                    left: Box::new(left),
+                    right: Box::new(right),
+                })
+            })
+        }
+        ConditionExpression::ComparisonOp(tree) => {
+            ConditionExpression::ComparisonOp(ConditionTree {
+                operator: tree.operator,
+                left: Box::new(canonical_condition(*tree.left)),
+                right: Box::new(canonical_condition(*tree.right)),
+            })
+        }
+        ConditionExpression::NegationOp(inner) => {
+            ConditionExpression::NegationOp(Box::new(bracket_logical(canonical_condition(*inner))))
+        }
+        ConditionExpression::Base(ConditionBase::NestedSelect(mut select)) => {
+            canonicalize_select(&mut select);
+            ConditionExpression::Base(ConditionBase::NestedSelect(select))
+        }
+        ConditionExpression::Base(ConditionBase::Any(inner)) => {
+            ConditionExpression::Base(ConditionBase::Any(Box::new(canonical_condition(*inner))))
+        }
+        ConditionExpression::Base(ConditionBase::Escaped(pattern, escape)) => {
+            ConditionExpression::Base(ConditionBase::Escaped(
+                Box::new(canonical_condition(*pattern)),
+                escape,
+            ))
+        }
+        ConditionExpression::Base(ConditionBase::Row(exprs, keyword)) => {
+            ConditionExpression::Base(ConditionBase::Row(
+                exprs.into_iter().map(canonical_condition).collect(),
+                keyword,
+            ))
+        }
+        ConditionExpression::Base(ConditionBase::ExpressionList(exprs)) => {
+            ConditionExpression::Base(ConditionBase::ExpressionList(
+                exprs.into_iter().map(canonical_condition).collect(),
+            ))
+        }
+        cond => cond,
+    }
+}
+
+fn canonicalize_condition(cond: &mut Option<ConditionExpression>) {
+    *cond = cond.take().map(canonical_condition);
+}
+
+/// Forgets how the names of `table` were quoted.
+fn canonicalize_table(table: &mut Table) {
+    table.quote = None;
+    table.alias_quote = None;
+    table.schema_quote = None;
+    if let Some(ref mut subquery) = table.subquery {
+        canonicalize_select(subquery);
+    }
+}
+
+fn canonicalize_join(join: &mut JoinClause) {
+    match join.right {
+        JoinRightSide::Table(ref mut table) => canonicalize_table(table),
+        JoinRightSide::Tables(ref mut tables) => tables.iter_mut().for_each(canonicalize_table),
+        JoinRightSide::NestedSelect(ref mut select, _) => canonicalize_select(select),
+        JoinRightSide::NestedJoin(ref mut nested) => canonicalize_join(nested),
+    }
+    join.constraint = match mem::replace(&mut join.constraint, JoinConstraint::Empty) {
+        JoinConstraint::On(cond) => JoinConstraint::On(canonical_condition(cond)),
+        constraint => constraint,
+    };
+}
+
+fn canonicalize_select(select: &mut SelectStatement) {
+    select.tables.iter_mut().for_each(canonicalize_table);
+    for join in &mut select.join {
+        canonicalize_join(join);
+    }
+    canonicalize_condition(&mut select.where_clause);
+    if let Some(ref mut group_by) = select.group_by {
+        canonicalize_condition(&mut group_by.having);
+    }
+}
+
+/// Rewrites `query` into a canonical form in which logically equivalent conditions are equal:
+/// redundant parentheses are dropped and the operands of `AND` and `OR` are sorted.
+/// Table names and aliases lose the quotes they were written in; whitespace, keyword case and the
+/// quoting of other identifiers are already normalized away by parsing.
+pub fn canonicalize(query: &SqlQuery) -> SqlQuery {
+    let mut query = query.clone();
+    match query {
+        SqlQuery::Select(ref mut select) => canonicalize_select(select),
+        SqlQuery::CompoundSelect(ref mut compound) => {
+            for (_, ref mut select) in &mut compound.selects {
+                canonicalize_select(select);
+            }
+        }
+        SqlQuery::CreateView(ref mut view) => match *view.definition {
+            SelectSpecification::Simple(ref mut select) => canonicalize_select(select),
+            SelectSpecification::Compound(ref mut compound) => {
+                for (_, ref mut select) in &mut compound.selects {
+                    canonicalize_select(select);
+                }
+            }
+        },
+        SqlQuery::Insert(ref mut insert) => canonicalize_table(&mut insert.table),
+        SqlQuery::Update(ref mut update) => {
+            canonicalize_table(&mut update.table);
+            canonicalize_condition(&mut update.where_clause);
+        }
+        SqlQuery::Delete(ref mut delete) => {
+            canonicalize_table(&mut delete.table);
+            canonicalize_condition(&mut delete.where_clause);
+        }
+        SqlQuery::Handler(ref mut handler) => {
+            if let HandlerAction::Read {
+                ref mut where_clause,
+                ..
+            } = handler.action
+            {
+                canonicalize_condition(where_clause);
+            }
+        }
+        _ => (),
+    }
+    query
+}
+
+/// Whether two queries are the same modulo formatting, redundant parentheses and the order of
+/// `AND`/`OR` operands.
+pub fn ast_eq(a: &SqlQuery, b: &SqlQuery) -> bool {
+    canonicalize(a) == canonicalize(b)
+}
+
+/// Version of the digests computed by `fingerprint` and `shape_fingerprint`. They are computed
+/// from a statement's printed form, so a release that prints some statement differently bumps
+/// this; callers that persist fingerprints should key on it as well.
+pub const FINGERPRINT_VERSION: u32 = 1;
+
+/// 64-bit FNV-1a over the tokens of `query`'s canonical form, each followed by a zero byte.
+/// Literals are replaced by `?` unless `literals` is set.
+fn digest(query: &SqlQuery, literals: bool) -> u64 {
+    let text = canonicalize(query).to_string();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for token in tokenize(&text).filter_map(Result::ok) {
+        let text = match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => continue,
+            TokenKind::String | TokenKind::Number | TokenKind::Placeholder if !literals => "?",
+            _ => token.text,
+        };
+        for &byte in text.as_bytes().iter().chain(&[0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// A digest of `query` that is the same for all queries equal under `ast_eq`, and is stable across
+/// processes, platforms and releases with the same `FINGERPRINT_VERSION`.
+pub fn fingerprint(query: &SqlQuery) -> u64 {
+    digest(query, true)
+}
+
+/// Like `fingerprint`, but ignoring the values of literals, so that queries differing only in
+/// their constants (or in whether they use placeholders for them) share a digest. A list of `n`
+/// values still only matches lists of `n` values.
+pub fn shape_fingerprint(query: &SqlQuery) -> u64 {
+    digest(query, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+
+    fn same(a: &str, b: &str) -> bool {
+        ast_eq(&parse_query(a).unwrap(), &parse_query(b).unwrap())
+    }
+
+    #[test]
+    fn equal_modulo_formatting() {
+        assert!(same(
+            "select `id`, name from users where a = 1 and b = 2",
+            "SELECT id,\n  name\nFROM `users`\nWHERE (b = 2) AND (a = 1)"
+        ));
+        assert!(same(
+            "DELETE FROM t WHERE a = 1 OR (b = 2 OR c = 3)",
+            "DELETE FROM t WHERE (c = 3 OR a = 1) OR b = 2"
+        ));
+        assert!(!same(
+            "SELECT id FROM t WHERE a = 1 AND b = 2",
+            "SELECT id FROM t WHERE a = 1 OR b = 2"
+        ));
+        assert!(!same(
+            "SELECT id FROM t WHERE (a = 1 OR b = 2) AND c = 3",
+            "SELECT id FROM t WHERE a = 1 OR b = 2 AND c = 3"
+        ));
+    }
+
+    #[test]
+    fn canonical_form_keeps_needed_parentheses() {
+        let q = parse_query("SELECT id FROM t WHERE c = 3 AND (b = 2 OR a = 1)").unwrap();
+        assert_eq!(
+            canonicalize(&q).to_string(),
+            "SELECT id FROM t WHERE (a = 1 OR b = 2) AND c = 3"
+        );
+    }
+
+    #[test]
+    fn fingerprints() {
+        let fp = |q: &str| fingerprint(&parse_query(q).unwrap());
+        let shape = |q: &str| shape_fingerprint(&parse_query(q).unwrap());
+        let q = "SELECT id FROM t WHERE a = 1 AND b = 'x'";
+        assert_eq!(fp(q), fp("select id\nfrom `t` where (b = 'x') and a=1"));
+        assert_ne!(fp(q), fp("SELECT id FROM t WHERE a = 2 AND b = 'x'"));
+        assert_ne!(fp(q), fp("SELECT id FROM t WHERE a = 1 OR b = 'x'"));
+        assert_eq!(shape(q), shape("SELECT id FROM t WHERE a = 2 AND b = ?"));
+        assert_ne!(shape(q), shape("SELECT id FROM t WHERE a = 1 AND c = 'x'"));
+        assert_ne!(
+            shape("SELECT id FROM t WHERE a IN (1, 2)"),
+            shape("SELECT id FROM t WHERE a IN (1, 2, 3)")
+        );
+        // the digest must not change within a FINGERPRINT_VERSION
+        assert_eq!(fp("SELECT id FROM t WHERE a = 1"), 0x2c9f_7978_c71a_3ef6);
+    }
+}
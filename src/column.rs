@@ -2,10 +2,15 @@ use std::cmp::Ordering;
 use std::fmt::{self, Display};
 use std::str;
 
-use common::{Literal, SqlType};
-use keywords::escape_if_keyword;
+use common::{escape_string, Literal, SqlType, TypeAttributes};
+use condition::ConditionExpression;
+use foreignkey::ReferentialAction;
+use ident::Ident;
+use keywords::{escape_function_name, escape_identifier};
+use sequence::SequenceOptions;
+use table::Table;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum FunctionExpression {
     Avg(Column, bool),
     Count(Column, bool),
@@ -14,6 +19,17 @@ pub enum FunctionExpression {
     Max(Column),
     Min(Column),
     GroupConcat(Column, String),
+    /// Fetches the next value of the named sequence.
+    NextVal(String),
+    /// Postgres' `set_config(setting, value, is_local)`, which pg_dump uses to set the search path,
+    /// with the schema it was qualified with, as in `pg_catalog.set_config(...)`.
+    SetConfig(Option<String>, String, String, bool),
+    /// Any other function, called by name with a list of arguments, such as `now()` or
+    /// `coalesce(a, 0)`.
+    Call(String, Vec<ConditionExpression>),
+    /// MySQL's `VALUES(column)`, the value an `INSERT ... ON DUPLICATE KEY UPDATE` would have
+    /// inserted into the column.
+    Values(Column),
 }
 
 impl Display for FunctionExpression {
@@ -30,17 +46,132 @@ impl Display for FunctionExpression {
             FunctionExpression::Max(ref col) => write!(f, "max({})", col),
             FunctionExpression::Min(ref col) => write!(f, "min({})", col),
             FunctionExpression::GroupConcat(ref col, ref s) => {
-                write!(f, "group_concat({}, {})", col, s)
+                write!(f, "group_concat({} separator '{}')", col, escape_string(s))
             }
+            FunctionExpression::NextVal(ref seq) => write!(f, "nextval('{}')", seq),
+            FunctionExpression::Values(ref col) => write!(f, "VALUES({})", col),
+            FunctionExpression::SetConfig(ref schema, ref setting, ref value, is_local) => {
+                if let Some(ref schema) = *schema {
+                    write!(f, "{}.", schema)?;
+                }
+                write!(
+                    f,
+                    "set_config('{}', '{}', {})",
+                    escape_string(setting),
+                    escape_string(value),
+                    is_local
+                )
+            }
+            FunctionExpression::Call(ref name, ref args) => write!(
+                f,
+                "{}({})",
+                escape_function_name(name),
+                args.iter()
+                    .map(|a| a.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// The aggregate functions of MySQL, Postgres, SQLite and SQL Server that are parsed as plain
+/// calls, in lowercase and sorted.
+const AGGREGATE_FUNCTIONS: &[&str] = &[
+    "any_value",
+    "array_agg",
+    "avg",
+    "bit_and",
+    "bit_or",
+    "bit_xor",
+    "bool_and",
+    "bool_or",
+    "checksum_agg",
+    "corr",
+    "count",
+    "count_big",
+    "covar_pop",
+    "covar_samp",
+    "every",
+    "group_concat",
+    "json_agg",
+    "json_arrayagg",
+    "json_group_array",
+    "json_group_object",
+    "json_object_agg",
+    "json_objectagg",
+    "jsonb_agg",
+    "jsonb_object_agg",
+    "listagg",
+    "max",
+    "min",
+    "mode",
+    "percentile_cont",
+    "percentile_disc",
+    "regr_avgx",
+    "regr_avgy",
+    "regr_count",
+    "regr_intercept",
+    "regr_r2",
+    "regr_slope",
+    "regr_sxx",
+    "regr_sxy",
+    "regr_syy",
+    "std",
+    "stddev",
+    "stddev_pop",
+    "stddev_samp",
+    "stdev",
+    "stdevp",
+    "string_agg",
+    "sum",
+    "total",
+    "var",
+    "var_pop",
+    "var_samp",
+    "variance",
+    "varp",
+    "xmlagg",
+];
+
+impl FunctionExpression {
+    /// Whether this function aggregates over a group of rows. A call is taken for one when its
+    /// name, in any case, is that of a known aggregate function such as `array_agg` or `stddev`.
+    pub fn is_aggregate(&self) -> bool {
+        match *self {
+            FunctionExpression::Call(ref name, _) => AGGREGATE_FUNCTIONS
+                .binary_search(&name.to_ascii_lowercase().as_str())
+                .is_ok(),
+            FunctionExpression::NextVal(_)
+            | FunctionExpression::SetConfig(..)
+            | FunctionExpression::Values(_) => false,
+            _ => true,
+        }
+    }
+
+    /// The column an aggregate is computed over, or `None` for `count(*)` and non-aggregates.
+    pub fn aggregated_column(&self) -> Option<&Column> {
+        match *self {
+            FunctionExpression::Avg(ref col, _)
+            | FunctionExpression::Count(ref col, _)
+            | FunctionExpression::Sum(ref col, _)
+            | FunctionExpression::Max(ref col)
+            | FunctionExpression::Min(ref col)
+            | FunctionExpression::GroupConcat(ref col, _) => Some(col),
+            FunctionExpression::CountStar
+            | FunctionExpression::NextVal(_)
+            | FunctionExpression::SetConfig(..)
+            | FunctionExpression::Call(..)
+            | FunctionExpression::Values(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct Column {
-    pub name: String,
-    pub alias: Option<String>,
-    pub table: Option<String>,
+    pub name: Ident,
+    pub alias: Option<Ident>,
+    pub table: Option<Ident>,
     pub function: Option<Box<FunctionExpression>>,
 }
 
@@ -50,77 +181,121 @@ impl fmt::Display for Column {
             write!(
                 f,
                 "{}.{}",
-                escape_if_keyword(table),
-                escape_if_keyword(&self.name)
+                escape_identifier(table),
+                escape_identifier(&self.name)
             )?;
         } else if let Some(ref function) = self.function {
             write!(f, "{}", *function)?;
         } else {
-            write!(f, "{}", escape_if_keyword(&self.name))?;
+            write!(f, "{}", escape_identifier(&self.name))?;
         }
         if let Some(ref alias) = self.alias {
-            write!(f, " AS {}", escape_if_keyword(alias))?;
+            write!(f, " AS {}", escape_identifier(alias))?;
         }
         Ok(())
     }
 }
 
-impl<'a> From<&'a str> for Column {
+impl From<&str> for Column {
     fn from(c: &str) -> Column {
         match c.find(".") {
             None => Column {
-                name: String::from(c),
+                name: Ident::from(c),
                 alias: None,
                 table: None,
                 function: None,
             },
             Some(i) => Column {
-                name: String::from(&c[i + 1..]),
+                name: Ident::from(&c[i + 1..]),
                 alias: None,
-                table: Some(String::from(&c[0..i])),
+                table: Some(Ident::from(&c[0..i])),
                 function: None,
             },
         }
     }
 }
 
-impl Ord for Column {
-    fn cmp(&self, other: &Column) -> Ordering {
-        if self.table.is_some() && other.table.is_some() {
-            match self.table.cmp(&other.table) {
-                Ordering::Equal => self.name.cmp(&other.name),
-                x => x,
+impl Column {
+    /// The aggregate function computing this column, if any.
+    pub fn aggregate(&self) -> Option<&FunctionExpression> {
+        match self.function {
+            Some(ref function) if function.is_aggregate() => Some(function),
+            _ => None,
+        }
+    }
+
+    /// The aggregate functions used in computing this column: its own aggregate, or those in the
+    /// arguments of the function it calls.
+    pub fn aggregates(&self) -> Vec<&FunctionExpression> {
+        match self.function.as_deref() {
+            Some(FunctionExpression::Call(_, args)) if self.aggregate().is_none() => {
+                args.iter().flat_map(|a| a.aggregates()).collect()
             }
-        } else {
-            self.name.cmp(&other.name)
+            _ => self.aggregate().into_iter().collect(),
         }
     }
 }
 
+/// Columns are ordered by table, then name, so that sorting groups the columns of a table
+/// together. Alias and function only break ties, which keeps the order consistent with `Eq`.
+impl Ord for Column {
+    fn cmp(&self, other: &Column) -> Ordering {
+        (&self.table, &self.name, &self.alias, &self.function).cmp(&(
+            &other.table,
+            &other.name,
+            &other.alias,
+            &other.function,
+        ))
+    }
+}
+
 impl PartialOrd for Column {
     fn partial_cmp(&self, other: &Column) -> Option<Ordering> {
-        if self.table.is_some() && other.table.is_some() {
-            match self.table.cmp(&other.table) {
-                Ordering::Equal => Some(self.name.cmp(&other.name)),
-                x => Some(x),
-            }
-        } else if self.table.is_none() && other.table.is_none() {
-            Some(self.name.cmp(&other.name))
-        } else {
-            None
+        Some(self.cmp(other))
+    }
+}
+
+/// Whether an identity column rejects explicitly supplied values (`ALWAYS`) or only fills in
+/// missing ones (`BY DEFAULT`).
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum IdentityGeneration {
+    Always,
+    ByDefault,
+}
+
+impl fmt::Display for IdentityGeneration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IdentityGeneration::Always => write!(f, "ALWAYS"),
+            IdentityGeneration::ByDefault => write!(f, "BY DEFAULT"),
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum ColumnConstraint {
     NotNull,
     CharacterSet(String),
     Collation(String),
     DefaultValue(Literal),
+    /// A default computed by a function call, such as `nextval('t_id_seq')`.
+    DefaultExpression(FunctionExpression),
     AutoIncrement,
+    /// SQLite's `AUTOINCREMENT`, which keeps an `INTEGER PRIMARY KEY` from reusing the rowids
+    /// of deleted rows.
+    Autoincrement,
+    /// `GENERATED { ALWAYS | BY DEFAULT } AS IDENTITY [ ( sequence options ) ]`
+    Identity(IdentityGeneration, SequenceOptions),
     PrimaryKey,
     Unique,
+    /// An inline foreign key, `REFERENCES table [(columns)] [ON DELETE ...] [ON UPDATE ...]`.
+    /// The columns are empty when the referenced table's primary key is meant.
+    References {
+        table: Table,
+        columns: Vec<Column>,
+        on_delete: Option<ReferentialAction>,
+        on_update: Option<ReferentialAction>,
+    },
 }
 
 impl fmt::Display for ColumnConstraint {
@@ -130,36 +305,72 @@ impl fmt::Display for ColumnConstraint {
             ColumnConstraint::CharacterSet(ref charset) => write!(f, "CHARACTER SET {}", charset),
             ColumnConstraint::Collation(ref collation) => write!(f, "COLLATE {}", collation),
             ColumnConstraint::DefaultValue(ref literal) => {
-                write!(f, "DEFAULT {}", literal.to_string())
+                write!(f, "DEFAULT {}", literal)
             }
+            ColumnConstraint::DefaultExpression(ref expr) => write!(f, "DEFAULT {}", expr),
             ColumnConstraint::AutoIncrement => write!(f, "AUTO_INCREMENT"),
+            ColumnConstraint::Autoincrement => write!(f, "AUTOINCREMENT"),
+            ColumnConstraint::Identity(generation, ref options) => {
+                write!(f, "GENERATED {} AS IDENTITY", generation)?;
+                if *options != SequenceOptions::default() {
+                    write!(f, " ({})", options.to_string().trim_start())?;
+                }
+                Ok(())
+            }
             ColumnConstraint::PrimaryKey => write!(f, "PRIMARY KEY"),
             ColumnConstraint::Unique => write!(f, "UNIQUE"),
+            ColumnConstraint::References {
+                ref table,
+                ref columns,
+                on_delete,
+                on_update,
+            } => {
+                write!(f, "REFERENCES {}", table.escaped_name())?;
+                if !columns.is_empty() {
+                    let columns = columns
+                        .iter()
+                        .map(|c| escape_identifier(&c.name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    write!(f, "({})", columns)?;
+                }
+                if let Some(action) = on_delete {
+                    write!(f, " ON DELETE {}", action)?;
+                }
+                if let Some(action) = on_update {
+                    write!(f, " ON UPDATE {}", action)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct ColumnSpecification {
     pub column: Column,
-    pub sql_type: SqlType,
+    /// The declared type, or `None` for a column declared without one, as SQLite allows.
+    pub sql_type: Option<SqlType>,
+    #[serde(default)]
+    pub type_attributes: TypeAttributes,
     pub constraints: Vec<ColumnConstraint>,
     pub comment: Option<String>,
 }
 
 impl fmt::Display for ColumnSpecification {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{} {}",
-            escape_if_keyword(&self.column.name),
-            self.sql_type
-        )?;
+        write!(f, "{}", escape_identifier(&self.column.name))?;
+        if let Some(ref sql_type) = self.sql_type {
+            write!(f, " {}", sql_type)?;
+        }
+        if !self.type_attributes.is_empty() {
+            write!(f, " {}", self.type_attributes)?;
+        }
         for constraint in self.constraints.iter() {
             write!(f, " {}", constraint)?;
         }
         if let Some(ref comment) = self.comment {
-            write!(f, " COMMENT '{}'", comment)?;
+            write!(f, " COMMENT '{}'", escape_string(comment))?;
         }
         Ok(())
     }
@@ -169,7 +380,8 @@ impl ColumnSpecification {
     pub fn new(c: Column, t: SqlType) -> ColumnSpecification {
         ColumnSpecification {
             column: c,
-            sql_type: t,
+            sql_type: Some(t),
+            type_attributes: TypeAttributes::default(),
             constraints: vec![],
             comment: None,
         }
@@ -182,11 +394,45 @@ impl ColumnSpecification {
     ) -> ColumnSpecification {
         ColumnSpecification {
             column: c,
-            sql_type: t,
+            sql_type: Some(t),
+            type_attributes: TypeAttributes::default(),
             constraints: ccs,
             comment: None,
         }
     }
+
+    /// Whether the column admits `NULL`, judging by its own definition. A column that is only
+    /// made part of the primary key by a table constraint is not recognized as `NOT NULL`.
+    pub fn is_nullable(&self) -> bool {
+        let serial = matches!(
+            self.sql_type,
+            Some(SqlType::Smallserial | SqlType::Serial | SqlType::Bigserial)
+        );
+        !serial
+            && !self.constraints.iter().any(|c| {
+                matches!(
+                    *c,
+                    ColumnConstraint::NotNull
+                        | ColumnConstraint::PrimaryKey
+                        | ColumnConstraint::Identity(..)
+                )
+            })
+    }
+
+    /// The Rust type that holds the column's values: `SqlType::rust_type`, unsigned if declared
+    /// `UNSIGNED`, and wrapped in `Option` if the column is nullable. An untyped column may hold
+    /// any value, so it is taken as bytes.
+    pub fn rust_type(&self) -> String {
+        let rust_type = match self.sql_type {
+            Some(ref sql_type) => sql_type.rust_type_with(self.type_attributes.unsigned),
+            None => String::from("Vec<u8>"),
+        };
+        if self.is_nullable() {
+            format!("Option<{}>", rust_type)
+        } else {
+            rust_type
+        }
+    }
 }
 
 #[cfg(test)]
@@ -201,9 +447,9 @@ mod tests {
         assert_eq!(
             c,
             Column {
-                name: String::from("col"),
+                name: "col".into(),
                 alias: None,
-                table: Some(String::from("table")),
+                table: Some("table".into()),
                 function: None,
             }
         );
@@ -238,4 +484,78 @@ mod tests {
         assert_eq!(format!("{}", c3), "sum(mytab.foo)");
     }
 
+    #[test]
+    fn aggregate_calls() {
+        assert!(AGGREGATE_FUNCTIONS.windows(2).all(|w| w[0] < w[1]));
+        let call = |name: &str| FunctionExpression::Call(String::from(name), vec![]);
+        for name in &[
+            "array_agg",
+            "STRING_AGG",
+            "StdDev",
+            "variance",
+            "bit_xor",
+            "bool_or",
+        ] {
+            assert!(call(name).is_aggregate(), "{}", name);
+        }
+        assert!(call("json_agg").is_aggregate() && call("group_concat").is_aggregate());
+        assert!(!call("coalesce").is_aggregate() && !call("lower").is_aggregate());
+        assert!(!FunctionExpression::NextVal(String::from("s")).is_aggregate());
+    }
+
+    #[test]
+    fn column_ordering_is_consistent_with_eq() {
+        let aliased = Column {
+            alias: Some("x".into()),
+            ..Column::from("t.a")
+        };
+        let mut columns = vec![
+            Column::from("t.b"),
+            aliased.clone(),
+            Column::from("a"),
+            Column::from("t.a"),
+        ];
+        columns.sort();
+        assert_eq!(
+            columns,
+            vec![
+                Column::from("a"),
+                Column::from("t.a"),
+                aliased.clone(),
+                Column::from("t.b"),
+            ]
+        );
+        assert_ne!(Column::from("t.a").cmp(&aliased), Ordering::Equal);
+    }
+
+    #[test]
+    fn rust_types() {
+        let mut id = ColumnSpecification::with_constraints(
+            Column::from("id"),
+            SqlType::Bigint(20),
+            vec![ColumnConstraint::PrimaryKey],
+        );
+        id.type_attributes.unsigned = true;
+        assert!(!id.is_nullable());
+        assert_eq!(id.rust_type(), "u64");
+
+        let tags = ColumnSpecification::new(
+            Column::from("tags"),
+            SqlType::Array(Box::new(SqlType::Varchar(20))),
+        );
+        assert!(tags.is_nullable());
+        assert_eq!(tags.rust_type(), "Option<Vec<String>>");
+        let serial = ColumnSpecification::new(Column::from("n"), SqlType::Serial);
+        assert_eq!(serial.rust_type(), "i32");
+
+        assert_eq!(SqlType::Decimal(10, 2).rust_type(), "rust_decimal::Decimal");
+        assert_eq!(SqlType::Timestamp(6).rust_type(), "chrono::NaiveDateTime");
+        assert_eq!(SqlType::Point.rust_type(), "Vec<u8>");
+        assert_eq!(SqlType::Inet.rust_type(), "ipnetwork::IpNetwork");
+        assert_eq!(SqlType::Bytea.rust_type(), "Vec<u8>");
+        assert_eq!(SqlType::TsVector.rust_type(), "String");
+        assert!(SqlType::Decimal(10, 2).is_numeric() && !SqlType::Text.is_numeric());
+        assert!(SqlType::Enum(vec![]).is_text() && !SqlType::Blob.is_text());
+        assert!(SqlType::Time(0).is_temporal() && !SqlType::Int(32).is_temporal());
+    }
 }
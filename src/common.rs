@@ -1,15 +1,37 @@
-use nom::{alphanumeric, digit, is_alphanumeric, line_ending, multispace, Compare, IResult};
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag, take, take_until, take_while1};
+use nom::character::complete::{
+    alphanumeric1, char, digit1, hex_digit0, hex_digit1, multispace1, satisfy,
+};
+use nom::combinator::{eof, map, map_opt, map_res, not, opt, peek, recognize, value, verify};
+use nom::error::{Error, ErrorKind};
+use nom::multi::{fold_many0, many0, many0_count, many1, many1_count, separated_list0};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::IResult;
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt::{self, Display};
-use std::str;
 use std::str::FromStr;
 
 use arithmetic::{arithmetic_expression, ArithmeticExpression};
+use array::{subscript_expression, SubscriptExpression};
 use column::{Column, FunctionExpression};
-use keywords::{escape_if_keyword, sql_keyword};
+use condition::{
+    condition_expr, predicate_continuation, predicate_expression, ConditionExpression,
+    PredicateExpression,
+};
+use dialect::{current_dialect, except_in, only_in, Dialect};
+use format::QuoteStyle;
+use ident::Ident;
+use json::{json_expression, JsonExpression};
+use keywords::{
+    escape_identifier, escape_if_keyword, non_reserved_keyword, sql_keyword, tag_no_case,
+};
+use limits::{memoized, nested};
+use order::OrderType;
 use table::Table;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum SqlType {
     Bool,
     Char(u16),
@@ -29,12 +51,48 @@ pub enum SqlType {
     Longtext,
     Text,
     Date,
+    /// Date and time types with their fractional-second precision, e.g. `DATETIME(6)`.
     DateTime(u16),
-    Timestamp,
+    Timestamp(u16),
+    Time(u16),
+    /// `TIMESTAMP WITH TIME ZONE`, or Postgres' `timestamptz`.
+    TimestampTz(u16),
+    /// `TIME WITH TIME ZONE`, or Postgres' `timetz`.
+    TimeTz(u16),
     Binary(u16),
     Varbinary(u16),
     Enum(Vec<Literal>),
     Decimal(u8, u8),
+    Geometry,
+    Point,
+    LineString,
+    Polygon,
+    MultiPoint,
+    MultiLineString,
+    MultiPolygon,
+    GeometryCollection,
+    Json,
+    Jsonb,
+    Array(Box<SqlType>),
+    Smallserial,
+    Serial,
+    Bigserial,
+    Nchar(u16),
+    Nvarchar(u16),
+    /// SQL Server `(MAX)` lengths, for values of up to 2GB.
+    VarcharMax,
+    NvarcharMax,
+    VarbinaryMax,
+    // Postgres built-in types for identifiers, network addresses, binary strings and currency
+    Uuid,
+    Inet,
+    Cidr,
+    MacAddr,
+    Bytea,
+    Money,
+    /// Postgres' full-text search types: a document as its normalized lexemes, and a query.
+    TsVector,
+    TsQuery,
 }
 
 impl fmt::Display for SqlType {
@@ -58,23 +116,227 @@ impl fmt::Display for SqlType {
             SqlType::Longtext => write!(f, "LONGTEXT"),
             SqlType::Text => write!(f, "TEXT"),
             SqlType::Date => write!(f, "DATE"),
-            SqlType::DateTime(len) => write!(f, "DATETIME({})", len),
-            SqlType::Timestamp => write!(f, "TIMESTAMP"),
+            SqlType::DateTime(0) => write!(f, "DATETIME"),
+            SqlType::DateTime(fsp) => write!(f, "DATETIME({})", fsp),
+            SqlType::Timestamp(0) => write!(f, "TIMESTAMP"),
+            SqlType::Timestamp(fsp) => write!(f, "TIMESTAMP({})", fsp),
+            SqlType::Time(0) => write!(f, "TIME"),
+            SqlType::Time(fsp) => write!(f, "TIME({})", fsp),
+            SqlType::TimestampTz(0) => write!(f, "TIMESTAMP WITH TIME ZONE"),
+            SqlType::TimestampTz(fsp) => write!(f, "TIMESTAMP({}) WITH TIME ZONE", fsp),
+            SqlType::TimeTz(0) => write!(f, "TIME WITH TIME ZONE"),
+            SqlType::TimeTz(fsp) => write!(f, "TIME({}) WITH TIME ZONE", fsp),
             SqlType::Binary(len) => write!(f, "BINARY({})", len),
             SqlType::Varbinary(len) => write!(f, "VARBINARY({})", len),
-            SqlType::Enum(_) => write!(f, "ENUM(...)"),
+            SqlType::Enum(ref variants) => write!(
+                f,
+                "ENUM({})",
+                variants
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
             SqlType::Decimal(m, d) => write!(f, "DECIMAL({}, {})", m, d),
+            SqlType::Geometry => write!(f, "GEOMETRY"),
+            SqlType::Point => write!(f, "POINT"),
+            SqlType::LineString => write!(f, "LINESTRING"),
+            SqlType::Polygon => write!(f, "POLYGON"),
+            SqlType::MultiPoint => write!(f, "MULTIPOINT"),
+            SqlType::MultiLineString => write!(f, "MULTILINESTRING"),
+            SqlType::MultiPolygon => write!(f, "MULTIPOLYGON"),
+            SqlType::GeometryCollection => write!(f, "GEOMETRYCOLLECTION"),
+            SqlType::Json => write!(f, "JSON"),
+            SqlType::Jsonb => write!(f, "JSONB"),
+            SqlType::Array(ref t) => write!(f, "{}[]", t),
+            SqlType::Smallserial => write!(f, "SMALLSERIAL"),
+            SqlType::Serial => write!(f, "SERIAL"),
+            SqlType::Bigserial => write!(f, "BIGSERIAL"),
+            SqlType::Nchar(len) => write!(f, "NCHAR({})", len),
+            SqlType::Nvarchar(len) => write!(f, "NVARCHAR({})", len),
+            SqlType::VarcharMax => write!(f, "VARCHAR(MAX)"),
+            SqlType::NvarcharMax => write!(f, "NVARCHAR(MAX)"),
+            SqlType::VarbinaryMax => write!(f, "VARBINARY(MAX)"),
+            SqlType::Uuid => write!(f, "UUID"),
+            SqlType::Inet => write!(f, "INET"),
+            SqlType::Cidr => write!(f, "CIDR"),
+            SqlType::MacAddr => write!(f, "MACADDR"),
+            SqlType::Bytea => write!(f, "BYTEA"),
+            SqlType::Money => write!(f, "MONEY"),
+            SqlType::TsVector => write!(f, "TSVECTOR"),
+            SqlType::TsQuery => write!(f, "TSQUERY"),
+        }
+    }
+}
+
+impl SqlType {
+    /// Integer, floating-point and fixed-point types.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            *self,
+            SqlType::Int(_)
+                | SqlType::Bigint(_)
+                | SqlType::Tinyint(_)
+                | SqlType::Double
+                | SqlType::Float
+                | SqlType::Real
+                | SqlType::Decimal(..)
+                | SqlType::Smallserial
+                | SqlType::Serial
+                | SqlType::Bigserial
+        )
+    }
+
+    /// Character string types, including `ENUM`.
+    pub fn is_text(&self) -> bool {
+        matches!(
+            *self,
+            SqlType::Char(_)
+                | SqlType::Varchar(_)
+                | SqlType::Tinytext
+                | SqlType::Mediumtext
+                | SqlType::Longtext
+                | SqlType::Text
+                | SqlType::Enum(_)
+                | SqlType::Nchar(_)
+                | SqlType::Nvarchar(_)
+                | SqlType::VarcharMax
+                | SqlType::NvarcharMax
+        )
+    }
+
+    /// Date and time types.
+    pub fn is_temporal(&self) -> bool {
+        matches!(
+            *self,
+            SqlType::Date
+                | SqlType::DateTime(_)
+                | SqlType::Timestamp(_)
+                | SqlType::Time(_)
+                | SqlType::TimestampTz(_)
+                | SqlType::TimeTz(_)
+        )
+    }
+
+    /// The Rust type that holds a value of this type, as a path for generated code. Types that the
+    /// standard library has no counterpart for are mapped to the crates commonly used for them:
+    /// `chrono` for dates and times, `rust_decimal` for `DECIMAL`, `serde_json` for JSON, `uuid`
+    /// for `UUID` and `ipnetwork` for `INET` and `CIDR`. Spatial types are held as their WKB
+    /// encoding, and `MONEY` as the integer number of cents Postgres stores.
+    pub fn rust_type(&self) -> String {
+        self.rust_type_with(false)
+    }
+
+    /// Like `rust_type`, but with unsigned integer types if `unsigned` is set.
+    pub(crate) fn rust_type_with(&self, unsigned: bool) -> String {
+        let integer = |bits: u8| format!("{}{}", if unsigned { 'u' } else { 'i' }, bits);
+        match *self {
+            SqlType::Bool => String::from("bool"),
+            SqlType::Tinyint(_) => integer(8),
+            SqlType::Smallserial => integer(16),
+            SqlType::Int(_) | SqlType::Serial => integer(32),
+            SqlType::Bigint(_) | SqlType::Bigserial => integer(64),
+            SqlType::Float => String::from("f32"),
+            // `REAL` is a double in MySQL and SQLite
+            SqlType::Double | SqlType::Real => String::from("f64"),
+            SqlType::Decimal(..) => String::from("rust_decimal::Decimal"),
+            SqlType::Date => String::from("chrono::NaiveDate"),
+            SqlType::DateTime(_) | SqlType::Timestamp(_) => String::from("chrono::NaiveDateTime"),
+            SqlType::TimestampTz(_) => String::from("chrono::DateTime<chrono::Utc>"),
+            // chrono has no time of day with an offset
+            SqlType::Time(_) | SqlType::TimeTz(_) => String::from("chrono::NaiveTime"),
+            SqlType::Json | SqlType::Jsonb => String::from("serde_json::Value"),
+            SqlType::Uuid => String::from("uuid::Uuid"),
+            SqlType::Inet | SqlType::Cidr => String::from("ipnetwork::IpNetwork"),
+            SqlType::MacAddr => String::from("[u8; 6]"),
+            SqlType::Money => integer(64),
+            SqlType::TsVector | SqlType::TsQuery => String::from("String"),
+            SqlType::Array(ref t) => format!("Vec<{}>", t.rust_type_with(unsigned)),
+            ref t if t.is_text() => String::from("String"),
+            _ => String::from("Vec<u8>"),
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// MySQL modifiers that may follow a type in a column definition, e.g. `INT(10) UNSIGNED ZEROFILL`
+/// or `VARCHAR(255) BINARY`.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
+pub struct TypeAttributes {
+    pub unsigned: bool,
+    pub zerofill: bool,
+    pub binary: bool,
+}
+
+impl TypeAttributes {
+    pub fn is_empty(&self) -> bool {
+        *self == TypeAttributes::default()
+    }
+}
+
+impl fmt::Display for TypeAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let attributes = [
+            (self.unsigned, "UNSIGNED"),
+            (self.zerofill, "ZEROFILL"),
+            (self.binary, "BINARY"),
+        ];
+        let names: Vec<_> = attributes
+            .iter()
+            .filter(|&&(set, _)| set)
+            .map(|&(_, name)| name)
+            .collect();
+        write!(f, "{}", names.join(" "))
+    }
+}
+
+/// An exact decimal number, `mantissa` × 10^-`scale`. The scale is the number of digits written
+/// after the decimal point, so `1.50` (150, 2) is kept apart from `1.5` (15, 1), as it is in the
+/// source text.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
 pub struct Real {
-    pub integral: i32,
-    pub fractional: i32,
+    pub mantissa: i128,
+    pub scale: u8,
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+impl Real {
+    pub fn new(mantissa: i128, scale: u8) -> Real {
+        Real { mantissa, scale }
+    }
+
+    /// The number with the given integral and fractional digits, or `None` if it has more digits
+    /// than the mantissa holds.
+    pub(crate) fn from_digits(negative: bool, integral: &str, fractional: &str) -> Option<Real> {
+        let scale = u8::try_from(fractional.len()).ok()?;
+        let mantissa = i128::from_str(&format!("{}{}", integral, fractional)).ok()?;
+        Some(Real {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale,
+        })
+    }
+}
+
+impl fmt::Display for Real {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.scale == 0 {
+            return write!(f, "{}", self.mantissa);
+        }
+        let scale = usize::from(self.scale);
+        let digits = format!(
+            "{:0>width$}",
+            self.mantissa.unsigned_abs(),
+            width = scale + 1
+        );
+        let (integral, fractional) = digits.split_at(digits.len() - scale);
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        write!(f, "{}{}.{}", sign, integral, fractional)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum Literal {
     Null,
     Integer(i64),
@@ -85,6 +347,17 @@ pub enum Literal {
     CurrentDate,
     CurrentTimestamp,
     Placeholder,
+    Array(Vec<Literal>),
+    /// A string or hex literal with a character set introducer, e.g. `_utf8mb4'text'` or
+    /// `_binary X'0A'`. The charset is recorded without the leading underscore.
+    Introduced(String, Box<Literal>),
+    /// A national character set string, `N'text'`.
+    NationalString(String),
+    /// The `DEFAULT` keyword, which stands for a column's default in `INSERT` values and `UPDATE`
+    /// assignments.
+    Default,
+    /// `TRUE` or `FALSE`.
+    Boolean(bool),
 }
 
 impl From<i64> for Literal {
@@ -105,29 +378,115 @@ impl<'a> From<&'a str> for Literal {
     }
 }
 
-impl ToString for Literal {
-    fn to_string(&self) -> String {
+impl Literal {
+    pub fn is_null(&self) -> bool {
+        *self == Literal::Null
+    }
+
+    /// The value of an integer literal.
+    pub fn as_i64(&self) -> Option<i64> {
         match *self {
-            Literal::Null => "NULL".to_string(),
-            Literal::Integer(ref i) => format!("{}", i),
-            Literal::FixedPoint(ref f) => format!("{}.{}", f.integral, f.fractional),
-            Literal::String(ref s) => format!("'{}'", s.replace('\'', "''")),
-            Literal::Blob(ref bv) => format!(
-                "{}",
+            Literal::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// The value of a numeric literal, converting integers.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Literal::Integer(i) => Some(i as f64),
+            Literal::FixedPoint(_) => self.to_string().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The text of a string literal, with or without a character set.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Literal::String(ref s) | Literal::NationalString(ref s) => Some(s),
+            Literal::Introduced(_, ref literal) => literal.as_str(),
+            _ => None,
+        }
+    }
+
+    /// The bytes of a hex literal, with or without a character set.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            Literal::Blob(ref bytes) => Some(bytes),
+            Literal::Introduced(_, ref literal) => literal.as_bytes(),
+            _ => None,
+        }
+    }
+
+    /// The literal as SQL, as written by `Display`: strings are quoted and escaped so that they
+    /// parse back to the same value.
+    pub fn to_sql_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Escapes the contents of a single-quoted string literal. Quotes are doubled; since MySQL reads
+/// backslash escapes, backslashes, NUL and Ctrl-Z are escaped with a backslash, except when
+/// printing for Postgres, whose standard strings take a backslash as it is.
+pub(crate) fn escape_string(s: &str) -> String {
+    if current_dialect() == Dialect::Postgres {
+        return s.replace('\'', "''");
+    }
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\'' => escaped.push_str("''"),
+            '\\' => escaped.push_str("\\\\"),
+            '\0' => escaped.push_str("\\0"),
+            '\x1a' => escaped.push_str("\\Z"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Literal::Null => write!(f, "NULL"),
+            Literal::Integer(ref i) => write!(f, "{}", i),
+            Literal::FixedPoint(ref r) => write!(f, "{}", r),
+            Literal::String(ref s) => write!(f, "'{}'", escape_string(s)),
+            Literal::Blob(ref bv) => write!(
+                f,
+                "X'{}'",
                 bv.iter()
-                    .map(|v| format!("{:x}", v))
+                    .map(|v| format!("{:02X}", v))
                     .collect::<Vec<String>>()
-                    .join(" ")
+                    .join("")
             ),
-            Literal::CurrentTime => "CURRENT_TIME".to_string(),
-            Literal::CurrentDate => "CURRENT_DATE".to_string(),
-            Literal::CurrentTimestamp => "CURRENT_TIMESTAMP".to_string(),
-            Literal::Placeholder => "?".to_string(),
+            Literal::CurrentTime => write!(f, "CURRENT_TIME"),
+            Literal::CurrentDate => write!(f, "CURRENT_DATE"),
+            Literal::CurrentTimestamp => write!(f, "CURRENT_TIMESTAMP"),
+            Literal::Placeholder => write!(f, "?"),
+            Literal::Array(ref elements) => write!(
+                f,
+                "ARRAY[{}]",
+                elements
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            // without the space, `_binary X'0A'` would read as the charset `binaryX`
+            Literal::Introduced(ref charset, ref literal) => match **literal {
+                Literal::Blob(_) => write!(f, "_{} {}", charset, literal),
+                _ => write!(f, "_{}{}", charset, literal),
+            },
+            Literal::NationalString(ref s) => write!(f, "N'{}'", escape_string(s)),
+            Literal::Default => write!(f, "DEFAULT"),
+            Literal::Boolean(true) => write!(f, "TRUE"),
+            Literal::Boolean(false) => write!(f, "FALSE"),
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct LiteralExpression {
     pub value: Literal,
     pub alias: Option<String>,
@@ -145,19 +504,52 @@ impl From<Literal> for LiteralExpression {
 impl fmt::Display for LiteralExpression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.alias {
-            Some(ref alias) => write!(f, "{} AS {}", self.value.to_string(), alias),
-            None => write!(f, "{}", self.value.to_string()),
+            Some(ref alias) => write!(f, "{} AS {}", self.value, escape_if_keyword(alias)),
+            None => write!(f, "{}", self.value),
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// Renders a user variable name with its `@` sigil, quoting it if necessary.
+pub fn escape_user_variable(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(is_sql_identifier) {
+        format!("@{}", name)
+    } else {
+        format!("@`{}`", name)
+    }
+}
+
+/// A user variable reference (`@name`), or an assignment to one (`@name := value`).
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct VariableExpression {
+    pub name: String,
+    pub value: Option<Box<FieldDefinitionExpression>>,
+    pub alias: Option<String>,
+}
+
+impl fmt::Display for VariableExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", escape_user_variable(&self.name))?;
+        if let Some(ref value) = self.value {
+            write!(f, " := {}", value)?;
+        }
+        if let Some(ref alias) = self.alias {
+            write!(f, " AS {}", escape_if_keyword(alias))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum Operator {
     Not,
     And,
     Or,
     Like,
     NotLike,
+    /// Postgres' case-insensitive `ILIKE`.
+    ILike,
+    NotILike,
     Equal,
     NotEqual,
     Greater,
@@ -166,6 +558,33 @@ pub enum Operator {
     LessOrEqual,
     In,
     Is,
+    /// `@>`
+    JsonContains,
+    /// `<@`
+    JsonContainedBy,
+    /// `?`
+    JsonKeyExists,
+    /// `?|`
+    JsonAnyKeyExists,
+    /// `?&`
+    JsonAllKeysExist,
+    /// MySQL's `REGEXP`, or its synonym `RLIKE`.
+    Regexp,
+    NotRegexp,
+    /// Postgres' case-sensitive regular expression match, `~`.
+    Match,
+    /// `!~`
+    NotMatch,
+    /// Postgres' case-insensitive regular expression match, `~*`.
+    MatchInsensitive,
+    /// `!~*`
+    NotMatchInsensitive,
+    /// The SQL standard's `SIMILAR TO` pattern match.
+    SimilarTo,
+    NotSimilarTo,
+    /// Postgres' full-text search match of a `tsvector` against a `tsquery`, `@@`.
+    TextSearchMatch,
+    NotIn,
 }
 
 impl Display for Operator {
@@ -175,7 +594,9 @@ impl Display for Operator {
             Operator::And => "AND",
             Operator::Or => "OR",
             Operator::Like => "LIKE",
-            Operator::NotLike => "NOT_LIKE",
+            Operator::NotLike => "NOT LIKE",
+            Operator::ILike => "ILIKE",
+            Operator::NotILike => "NOT ILIKE",
             Operator::Equal => "=",
             Operator::NotEqual => "!=",
             Operator::Greater => ">",
@@ -184,94 +605,193 @@ impl Display for Operator {
             Operator::LessOrEqual => "<=",
             Operator::In => "IN",
             Operator::Is => "IS",
+            Operator::JsonContains => "@>",
+            Operator::JsonContainedBy => "<@",
+            Operator::JsonKeyExists => "?",
+            Operator::JsonAnyKeyExists => "?|",
+            Operator::JsonAllKeysExist => "?&",
+            Operator::Regexp => "REGEXP",
+            Operator::NotRegexp => "NOT REGEXP",
+            Operator::Match => "~",
+            Operator::NotMatch => "!~",
+            Operator::MatchInsensitive => "~*",
+            Operator::NotMatchInsensitive => "!~*",
+            Operator::SimilarTo => "SIMILAR TO",
+            Operator::NotSimilarTo => "NOT SIMILAR TO",
+            Operator::TextSearchMatch => "@@",
+            Operator::NotIn => "NOT IN",
         };
         write!(f, "{}", op)
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum IndexType {
+    BTree,
+    Hash,
+}
+
+impl fmt::Display for IndexType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexType::BTree => write!(f, "BTREE"),
+            IndexType::Hash => write!(f, "HASH"),
+        }
+    }
+}
+
+/// Per-index options that may follow (or, for `USING`, precede) a key's column list.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct IndexOptions {
+    pub index_type: Option<IndexType>,
+    pub key_block_size: Option<u64>,
+    pub comment: Option<String>,
+    pub invisible: bool,
+}
+
+impl fmt::Display for IndexOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref index_type) = self.index_type {
+            write!(f, " USING {}", index_type)?;
+        }
+        if let Some(size) = self.key_block_size {
+            write!(f, " KEY_BLOCK_SIZE={}", size)?;
+        }
+        if let Some(ref comment) = self.comment {
+            write!(f, " COMMENT '{}'", escape_string(comment))?;
+        }
+        if self.invisible {
+            write!(f, " INVISIBLE")?;
+        }
+        Ok(())
+    }
+}
+
+/// One part of an index key: a column, indexed in full or by the given length of prefix, or an
+/// expression (MySQL functional key parts, Postgres expression indexes) such as `LOWER(email)`.
+/// Either kind may carry an explicit direction.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum KeyPart {
+    Column(Column, Option<u16>, Option<OrderType>),
+    Expression(Box<ConditionExpression>, Option<OrderType>),
+}
+
+impl fmt::Display for KeyPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let order = match *self {
+            KeyPart::Column(ref column, length, ref order) => {
+                write!(f, "{}", escape_identifier(&column.name))?;
+                if let Some(length) = length {
+                    write!(f, "({})", length)?;
+                }
+                order
+            }
+            KeyPart::Expression(ref expr, ref order) => {
+                write!(f, "({})", expr)?;
+                order
+            }
+        };
+        if let Some(ref order) = *order {
+            write!(f, " {}", order)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Column> for KeyPart {
+    fn from(column: Column) -> Self {
+        KeyPart::Column(column, None, None)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum TableKey {
-    PrimaryKey(Vec<Column>),
-    UniqueKey(Option<String>, Vec<Column>),
-    FulltextKey(Option<String>, Vec<Column>),
-    Key(String, Vec<Column>),
+    /// A primary key, with the name of the `CONSTRAINT` that declares it, if any, and whether
+    /// its columns are followed by SQLite's `AUTOINCREMENT`.
+    PrimaryKey(Option<String>, Vec<KeyPart>, IndexOptions, bool),
+    UniqueKey(Option<String>, Vec<KeyPart>, IndexOptions),
+    FulltextKey(Option<String>, Vec<KeyPart>, IndexOptions),
+    SpatialKey(Option<String>, Vec<KeyPart>, IndexOptions),
+    Key(String, Vec<KeyPart>, IndexOptions),
 }
 
 impl fmt::Display for TableKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            TableKey::PrimaryKey(ref columns) => {
+        let (name, columns, options) = match *self {
+            TableKey::PrimaryKey(ref name, ref columns, ref options, _) => {
+                if let Some(ref name) = *name {
+                    write!(f, "CONSTRAINT {} ", escape_if_keyword(name))?;
+                }
                 write!(f, "PRIMARY KEY ")?;
-                write!(
-                    f,
-                    "({})",
-                    columns
-                        .iter()
-                        .map(|c| escape_if_keyword(&c.name))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )
+                (None, columns, options)
             }
-            TableKey::UniqueKey(ref name, ref columns) => {
+            TableKey::UniqueKey(ref name, ref columns, ref options) => {
                 write!(f, "UNIQUE KEY ")?;
-                if let Some(ref name) = *name {
-                    write!(f, "{} ", escape_if_keyword(name))?;
-                }
-                write!(
-                    f,
-                    "({})",
-                    columns
-                        .iter()
-                        .map(|c| escape_if_keyword(&c.name))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )
+                (name.as_ref(), columns, options)
             }
-            TableKey::FulltextKey(ref name, ref columns) => {
+            TableKey::FulltextKey(ref name, ref columns, ref options) => {
                 write!(f, "FULLTEXT KEY ")?;
-                if let Some(ref name) = *name {
-                    write!(f, "{} ", escape_if_keyword(name))?;
-                }
-                write!(
-                    f,
-                    "({})",
-                    columns
-                        .iter()
-                        .map(|c| escape_if_keyword(&c.name))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )
+                (name.as_ref(), columns, options)
             }
-            TableKey::Key(ref name, ref columns) => {
-                write!(f, "KEY {} ", escape_if_keyword(name))?;
-                write!(
-                    f,
-                    "({})",
-                    columns
-                        .iter()
-                        .map(|c| escape_if_keyword(&c.name))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )
+            TableKey::SpatialKey(ref name, ref columns, ref options) => {
+                write!(f, "SPATIAL KEY ")?;
+                (name.as_ref(), columns, options)
             }
+            TableKey::Key(ref name, ref columns, ref options) => {
+                write!(f, "KEY ")?;
+                (Some(name), columns, options)
+            }
+        };
+        if let Some(name) = name {
+            write!(f, "{} ", escape_if_keyword(name))?;
         }
+        write!(
+            f,
+            "({}){}",
+            columns
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            options
+        )?;
+        if let TableKey::PrimaryKey(_, _, _, true) = *self {
+            write!(f, " AUTOINCREMENT")?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum FieldDefinitionExpression {
+    #[default]
     All,
     AllInTable(String),
     Col(Column),
     Value(FieldValueExpression),
 }
 
+impl FieldDefinitionExpression {
+    /// The aggregate functions used anywhere in this select list entry.
+    pub fn aggregates(&self) -> Vec<&FunctionExpression> {
+        match *self {
+            FieldDefinitionExpression::Col(ref col) => col.aggregates(),
+            FieldDefinitionExpression::Value(ref value) => value.aggregates(),
+            FieldDefinitionExpression::All | FieldDefinitionExpression::AllInTable(_) => vec![],
+        }
+    }
+
+    pub fn contains_aggregate(&self) -> bool {
+        !self.aggregates().is_empty()
+    }
+}
+
 impl Display for FieldDefinitionExpression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             FieldDefinitionExpression::All => write!(f, "*"),
             FieldDefinitionExpression::AllInTable(ref table) => {
-                write!(f, "{}.*", escape_if_keyword(table))
+                write!(f, "{}.*", escape_identifier(table))
             }
             FieldDefinitionExpression::Col(ref col) => write!(f, "{}", col),
             FieldDefinitionExpression::Value(ref val) => write!(f, "{}", val),
@@ -279,16 +799,47 @@ impl Display for FieldDefinitionExpression {
     }
 }
 
-impl Default for FieldDefinitionExpression {
-    fn default() -> FieldDefinitionExpression {
-        FieldDefinitionExpression::All
-    }
-}
-
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum FieldValueExpression {
     Arithmetic(ArithmeticExpression),
     Literal(LiteralExpression),
+    Variable(VariableExpression),
+    Json(JsonExpression),
+    Subscript(SubscriptExpression),
+    /// Any other value assigned by `UPDATE ... SET` or `ON DUPLICATE KEY UPDATE` or given in
+    /// `VALUES`, such as another column, a function call or a subquery.
+    Expression(ConditionExpression),
+    /// A comparison or logical combination in a select list.
+    Predicate(PredicateExpression),
+}
+
+impl FieldValueExpression {
+    /// The aggregate functions used anywhere in this expression.
+    pub fn aggregates(&self) -> Vec<&FunctionExpression> {
+        match *self {
+            FieldValueExpression::Arithmetic(ref expr) => expr.aggregates(),
+            FieldValueExpression::Variable(VariableExpression {
+                value: Some(ref value),
+                ..
+            }) => value.aggregates(),
+            FieldValueExpression::Json(JsonExpression { ref column, .. })
+            | FieldValueExpression::Subscript(SubscriptExpression { ref column, .. }) => {
+                column.aggregates()
+            }
+            FieldValueExpression::Expression(ref expr)
+            | FieldValueExpression::Predicate(PredicateExpression {
+                condition: ref expr,
+                ..
+            }) => expr.aggregates(),
+            FieldValueExpression::Variable(_) | FieldValueExpression::Literal(_) => vec![],
+        }
+    }
+}
+
+impl<T: Into<Literal>> From<T> for FieldValueExpression {
+    fn from(value: T) -> Self {
+        FieldValueExpression::Literal(LiteralExpression::from(value.into()))
+    }
 }
 
 impl Display for FieldValueExpression {
@@ -296,696 +847,1360 @@ impl Display for FieldValueExpression {
         match *self {
             FieldValueExpression::Arithmetic(ref expr) => write!(f, "{}", expr),
             FieldValueExpression::Literal(ref lit) => write!(f, "{}", lit),
+            FieldValueExpression::Variable(ref var) => write!(f, "{}", var),
+            FieldValueExpression::Json(ref json) => write!(f, "{}", json),
+            FieldValueExpression::Subscript(ref sub) => write!(f, "{}", sub),
+            FieldValueExpression::Expression(ref expr) => write!(f, "{}", expr),
+            FieldValueExpression::Predicate(ref pred) => write!(f, "{}", pred),
         }
     }
 }
 
 #[inline]
-pub fn is_sql_identifier(chr: u8) -> bool {
-    is_alphanumeric(chr) || chr == '_' as u8
+pub fn is_sql_identifier(chr: char) -> bool {
+    chr.is_ascii_alphanumeric() || chr == '_'
 }
 
-#[inline]
-fn len_as_u16(len: CompleteByteSlice) -> u16 {
-    match str::from_utf8(*len) {
-        Ok(s) => match u16::from_str(s) {
-            Ok(v) => v,
-            Err(e) => panic!(e),
+/// Parses an unsigned 16-bit length in parentheses, e.g. `(255)`, after a type name.
+fn delim_u16(i: &str) -> IResult<&str, u16> {
+    delimited(
+        tuple((whitespace0, tag("("), whitespace0)),
+        map_res(digit1, u16::from_str),
+        pair(whitespace0, tag(")")),
+    )(i)
+}
+
+/// Parses the modifiers following a type. `SIGNED` is accepted but, being the default, not
+/// recorded.
+pub fn type_attributes(i: &str) -> IResult<&str, TypeAttributes> {
+    fold_many0(
+        preceded(
+            whitespace0,
+            terminated(
+                alt((
+                    tag_no_case("unsigned"),
+                    tag_no_case("signed"),
+                    tag_no_case("zerofill"),
+                    tag_no_case("binary"),
+                )),
+                not(satisfy(is_sql_identifier)),
+            ),
+        ),
+        TypeAttributes::default,
+        |mut attributes, attribute: &str| {
+            if attribute.eq_ignore_ascii_case("unsigned") {
+                attributes.unsigned = true;
+            } else if attribute.eq_ignore_ascii_case("zerofill") {
+                attributes.zerofill = true;
+            } else if attribute.eq_ignore_ascii_case("binary") {
+                attributes.binary = true;
+            }
+            attributes
         },
-        Err(e) => panic!(e),
-    }
-}
-
-named!(pub precision<CompleteByteSlice, (u8, Option<u8>)>,
-    delimited!(tag!("("),
-               do_parse!(
-                   m: digit >>
-                   d: opt!(do_parse!(
-                             tag!(",") >>
-                             opt_multispace >>
-                             d: digit >>
-                             (d)
-                        )) >>
-                   ((m.0[0], d.map(|r| r.0[0])))
-               ),
-               tag!(")"))
-);
-
-/// A SQL type specifier.
-named!(pub type_identifier<CompleteByteSlice, SqlType>,
-    alt!(
-          do_parse!(
-              tag_no_case!("bool") >>
-              (SqlType::Bool)
-          )
-        | do_parse!(
-              tag_no_case!("mediumtext") >>
-              (SqlType::Mediumtext)
-          )
-        | do_parse!(
-              tag_no_case!("timestamp") >>
-              _len: opt!(delimited!(tag!("("), digit, tag!(")"))) >>
-              opt_multispace >>
-              (SqlType::Timestamp)
-          )
-         | do_parse!(
-               tag_no_case!("varbinary") >>
-               len: delimited!(tag!("("), digit, tag!(")")) >>
-               opt_multispace >>
-               (SqlType::Varbinary(len_as_u16(len)))
-           )
-         | do_parse!(
-               tag_no_case!("mediumblob") >>
-               (SqlType::Mediumblob)
-           )
-         | do_parse!(
-               tag_no_case!("longblob") >>
-               (SqlType::Longblob)
-           )
-         | do_parse!(
-               tag_no_case!("tinyblob") >>
-               (SqlType::Tinyblob)
-           )
-         | do_parse!(
-               tag_no_case!("tinytext") >>
-               (SqlType::Tinytext)
-           )
-         | do_parse!(
-               tag_no_case!("varchar") >>
-               len: delimited!(tag!("("), digit, tag!(")")) >>
-               opt_multispace >>
-               _binary: opt!(tag_no_case!("binary")) >>
-               (SqlType::Varchar(len_as_u16(len)))
-           )
-         | do_parse!(
-               tag_no_case!("binary") >>
-               len: delimited!(tag!("("), digit, tag!(")")) >>
-               opt_multispace >>
-               (SqlType::Binary(len_as_u16(len)))
-           )
-         | do_parse!(
-               tag_no_case!("varbinary") >>
-               len: delimited!(tag!("("), digit, tag!(")")) >>
-               opt_multispace >>
-               (SqlType::Varbinary(len_as_u16(len)))
-           )
-         | do_parse!(
-               tag_no_case!("tinyint") >>
-               len: opt!(delimited!(tag!("("), digit, tag!(")"))) >>
-               opt_multispace >>
-               _signed: opt!(alt!(tag_no_case!("unsigned") | tag_no_case!("signed"))) >>
-               (SqlType::Tinyint(len.map(|l|len_as_u16(l)).unwrap_or(1)))
-           )
-         | do_parse!(
-               tag_no_case!("bigint") >>
-               len: opt!(delimited!(tag!("("), digit, tag!(")"))) >>
-               opt_multispace >>
-               _signed: opt!(alt!(tag_no_case!("unsigned") | tag_no_case!("signed"))) >>
-               (SqlType::Bigint(len.map(|l|len_as_u16(l)).unwrap_or(1)))
-           )
-         | do_parse!(
-               tag_no_case!("double") >>
-               opt_multispace >>
-               _signed: opt!(alt!(tag_no_case!("unsigned") | tag_no_case!("signed"))) >>
-               (SqlType::Double)
-           )
-         | do_parse!(
-               tag_no_case!("float") >>
-               opt_multispace >>
-               _prec: opt!(precision) >>
-               opt_multispace >>
-               (SqlType::Float)
-           )
-         | do_parse!(
-               tag_no_case!("blob") >>
-               (SqlType::Blob)
-           )
-         | do_parse!(
-               tag_no_case!("datetime") >>
-               fsp: opt!(delimited!(tag!("("), digit, tag!(")"))) >>
-               (SqlType::DateTime(match fsp {
-                   Some(fsp) => len_as_u16(fsp),
-                   None => 0 as u16,
-               }))
-           )
-         | do_parse!(
-               tag_no_case!("date") >>
-               (SqlType::Date)
-           )
-         | do_parse!(
-               tag_no_case!("real") >>
-               opt_multispace >>
-               _signed: opt!(alt!(tag_no_case!("unsigned") | tag_no_case!("signed"))) >>
-               (SqlType::Real)
-           )
-         | do_parse!(
-               tag_no_case!("text") >>
-               (SqlType::Text)
-           )
-         | do_parse!(
-               tag_no_case!("longtext") >>
-               (SqlType::Longtext)
-           )
-         | do_parse!(
-               tag_no_case!("char") >>
-               len: delimited!(tag!("("), digit, tag!(")")) >>
-               opt_multispace >>
-               _binary: opt!(tag_no_case!("binary")) >>
-               (SqlType::Char(len_as_u16(len)))
-           )
-         | do_parse!(
-               alt!(tag_no_case!("integer") | tag_no_case!("int") | tag_no_case!("smallint")) >>
-               len: opt!(delimited!(tag!("("), digit, tag!(")"))) >>
-               opt_multispace >>
-               _signed: opt!(alt!(tag_no_case!("unsigned") | tag_no_case!("signed"))) >>
-               (SqlType::Int(match len {
-                   Some(len) => len_as_u16(len),
-                   None => 32 as u16,
-               }))
-           )
-         | do_parse!(
-               tag_no_case!("enum") >>
-               variants: delimited!(tag!("("), value_list, tag!(")")) >>
-               opt_multispace >>
-               (SqlType::Enum(variants))
-           )
-         | do_parse!(
-               // TODO(malte): not strictly ok to treat DECIMAL and NUMERIC as identical; the
-               // former has "at least" M precision, the latter "exactly".
-               // See https://dev.mysql.com/doc/refman/5.7/en/precision-math-decimal-characteristics.html
-               alt!(tag_no_case!("decimal") | tag_no_case!("numeric")) >>
-               prec: opt!(precision) >>
-               opt_multispace >>
-               (match prec {
-                   None => SqlType::Decimal(32, 0),
-                   Some((m, None)) => SqlType::Decimal(m, 0),
-                   Some((m, Some(d))) => SqlType::Decimal(m, d),
-                })
-           )
-       )
-);
+    )(i)
+}
+
+pub fn precision(i: &str) -> IResult<&str, (u8, Option<u8>)> {
+    delimited(
+        tuple((whitespace0, tag("("), whitespace0)),
+        pair(
+            map_res(digit1, u8::from_str),
+            opt(preceded(ws_sep_comma, map_res(digit1, u8::from_str))),
+        ),
+        pair(whitespace0, tag(")")),
+    )(i)
+}
+
+/// `WITH TIME ZONE` or `WITHOUT TIME ZONE` after `TIMESTAMP` or `TIME`, returning whether the
+/// type has a time zone.
+fn time_zone(i: &str) -> IResult<&str, bool> {
+    let zone = |with| {
+        tuple((
+            whitespace1,
+            tag_no_case(with),
+            whitespace1,
+            tag_no_case("time"),
+            whitespace1,
+            tag_no_case("zone"),
+        ))
+    };
+    map(
+        opt(alt((
+            value(true, zone("with")),
+            value(false, zone("without")),
+        ))),
+        |tz| tz.unwrap_or(false),
+    )(i)
+}
+
+fn type_identifier_temporal(i: &str) -> IResult<&str, SqlType> {
+    alt((
+        map(
+            preceded(tag_no_case("timestamptz"), opt(delim_u16)),
+            |fsp| SqlType::TimestampTz(fsp.unwrap_or(0)),
+        ),
+        map(
+            tuple((
+                tag_no_case("timestamp"),
+                opt(delim_u16),
+                time_zone,
+                whitespace0,
+            )),
+            |(_, fsp, tz, _)| {
+                let fsp = fsp.unwrap_or(0);
+                if tz {
+                    SqlType::TimestampTz(fsp)
+                } else {
+                    SqlType::Timestamp(fsp)
+                }
+            },
+        ),
+        map(preceded(tag_no_case("datetime"), opt(delim_u16)), |fsp| {
+            SqlType::DateTime(fsp.unwrap_or(0))
+        }),
+        value(SqlType::Date, tag_no_case("date")),
+        map(preceded(tag_no_case("timetz"), opt(delim_u16)), |fsp| {
+            SqlType::TimeTz(fsp.unwrap_or(0))
+        }),
+        map(
+            tuple((tag_no_case("time"), opt(delim_u16), time_zone)),
+            |(_, fsp, tz)| {
+                let fsp = fsp.unwrap_or(0);
+                if tz {
+                    SqlType::TimeTz(fsp)
+                } else {
+                    SqlType::Time(fsp)
+                }
+            },
+        ),
+    ))(i)
+}
+
+// `alt` is limited to 21 alternatives, so the type names are split across several parsers.
+fn type_identifier_first_half(i: &str) -> IResult<&str, SqlType> {
+    alt((
+        value(SqlType::Bool, tag_no_case("bool")),
+        value(SqlType::Mediumtext, tag_no_case("mediumtext")),
+        map(
+            tuple((tag_no_case("varbinary"), delim_u16, whitespace0)),
+            |(_, len, _)| SqlType::Varbinary(len),
+        ),
+        value(SqlType::Mediumblob, tag_no_case("mediumblob")),
+        value(SqlType::Longblob, tag_no_case("longblob")),
+        value(SqlType::Tinyblob, tag_no_case("tinyblob")),
+        value(SqlType::Tinytext, tag_no_case("tinytext")),
+        map(
+            tuple((tag_no_case("varchar"), delim_u16, whitespace0)),
+            |(_, len, _)| SqlType::Varchar(len),
+        ),
+        map(
+            tuple((tag_no_case("binary"), delim_u16, whitespace0)),
+            |(_, len, _)| SqlType::Binary(len),
+        ),
+        map(
+            tuple((tag_no_case("tinyint"), opt(delim_u16), whitespace0)),
+            |(_, len, _)| SqlType::Tinyint(len.unwrap_or(1)),
+        ),
+        map(
+            tuple((tag_no_case("bigint"), opt(delim_u16), whitespace0)),
+            |(_, len, _)| SqlType::Bigint(len.unwrap_or(1)),
+        ),
+        map(pair(tag_no_case("double"), whitespace0), |_| {
+            SqlType::Double
+        }),
+        map(
+            tuple((
+                tag_no_case("float"),
+                whitespace0,
+                opt(precision),
+                whitespace0,
+            )),
+            |_| SqlType::Float,
+        ),
+        value(SqlType::Blob, tag_no_case("blob")),
+    ))(i)
+}
+
+fn type_identifier_second_half(i: &str) -> IResult<&str, SqlType> {
+    alt((
+        map(pair(tag_no_case("real"), whitespace0), |_| SqlType::Real),
+        value(SqlType::Text, tag_no_case("text")),
+        value(SqlType::Longtext, tag_no_case("longtext")),
+        map(
+            tuple((tag_no_case("char"), delim_u16, whitespace0)),
+            |(_, len, _)| SqlType::Char(len),
+        ),
+        map(
+            tuple((
+                alt((
+                    tag_no_case("integer"),
+                    tag_no_case("int"),
+                    tag_no_case("smallint"),
+                )),
+                opt(delim_u16),
+                whitespace0,
+            )),
+            |(_, len, _)| SqlType::Int(len.unwrap_or(32)),
+        ),
+        map(
+            tuple((
+                tag_no_case("enum"),
+                delimited(
+                    tuple((whitespace0, tag("("), whitespace0)),
+                    value_list,
+                    pair(whitespace0, tag(")")),
+                ),
+                whitespace0,
+            )),
+            |(_, variants, _)| SqlType::Enum(variants),
+        ),
+        // TODO(malte): not strictly ok to treat DECIMAL and NUMERIC as identical; the
+        // former has "at least" M precision, the latter "exactly".
+        // See https://dev.mysql.com/doc/refman/5.7/en/precision-math-decimal-characteristics.html
+        map(
+            tuple((
+                alt((tag_no_case("decimal"), tag_no_case("numeric"))),
+                opt(precision),
+                whitespace0,
+            )),
+            |(_, prec, _)| match prec {
+                None => SqlType::Decimal(32, 0),
+                Some((m, None)) => SqlType::Decimal(m, 0),
+                Some((m, Some(d))) => SqlType::Decimal(m, d),
+            },
+        ),
+    ))(i)
+}
+
+fn type_identifier_structured(i: &str) -> IResult<&str, SqlType> {
+    alt((
+        value(
+            SqlType::GeometryCollection,
+            alt((
+                tag_no_case("geometrycollection"),
+                tag_no_case("geomcollection"),
+            )),
+        ),
+        value(SqlType::Geometry, tag_no_case("geometry")),
+        value(SqlType::Point, tag_no_case("point")),
+        value(SqlType::LineString, tag_no_case("linestring")),
+        value(SqlType::Polygon, tag_no_case("polygon")),
+        value(SqlType::MultiPoint, tag_no_case("multipoint")),
+        value(SqlType::MultiLineString, tag_no_case("multilinestring")),
+        value(SqlType::MultiPolygon, tag_no_case("multipolygon")),
+        value(SqlType::Jsonb, tag_no_case("jsonb")),
+        value(SqlType::Json, tag_no_case("json")),
+        value(
+            SqlType::Smallserial,
+            alt((tag_no_case("smallserial"), tag_no_case("serial2"))),
+        ),
+        value(
+            SqlType::Bigserial,
+            alt((tag_no_case("bigserial"), tag_no_case("serial8"))),
+        ),
+        value(
+            SqlType::Serial,
+            alt((tag_no_case("serial4"), tag_no_case("serial"))),
+        ),
+    ))(i)
+}
+
+fn type_identifier_postgres(i: &str) -> IResult<&str, SqlType> {
+    only_in(
+        Dialect::Postgres,
+        alt((
+            value(SqlType::Uuid, tag_no_case("uuid")),
+            value(SqlType::Inet, tag_no_case("inet")),
+            value(SqlType::Cidr, tag_no_case("cidr")),
+            value(SqlType::MacAddr, tag_no_case("macaddr")),
+            value(SqlType::Bytea, tag_no_case("bytea")),
+            value(SqlType::Money, tag_no_case("money")),
+            value(SqlType::TsVector, tag_no_case("tsvector")),
+            value(SqlType::TsQuery, tag_no_case("tsquery")),
+        )),
+    )(i)
+}
+
+fn max_length(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((
+            tag("("),
+            whitespace0,
+            tag_no_case("max"),
+            whitespace0,
+            tag(")"),
+            whitespace0,
+        )),
+    )(i)
+}
+
+fn type_identifier_national(i: &str) -> IResult<&str, SqlType> {
+    alt((
+        map(
+            tuple((tag_no_case("nvarchar"), delim_u16, whitespace0)),
+            |(_, len, _)| SqlType::Nvarchar(len),
+        ),
+        map(
+            tuple((tag_no_case("nchar"), delim_u16, whitespace0)),
+            |(_, len, _)| SqlType::Nchar(len),
+        ),
+        only_in(
+            Dialect::SqlServer,
+            alt((
+                value(
+                    SqlType::NvarcharMax,
+                    pair(tag_no_case("nvarchar"), max_length),
+                ),
+                value(
+                    SqlType::VarcharMax,
+                    pair(tag_no_case("varchar"), max_length),
+                ),
+                value(
+                    SqlType::VarbinaryMax,
+                    pair(tag_no_case("varbinary"), max_length),
+                ),
+            )),
+        ),
+    ))(i)
+}
+
+/// A SQL type specifier, optionally followed by Postgres array dimensions (`int[]`, `text[][]`).
+/// Type attributes such as `UNSIGNED` are skipped; use `type_with_attributes` to keep them.
+pub fn type_identifier(i: &str) -> IResult<&str, SqlType> {
+    map(type_with_attributes, |(sql_type, _)| sql_type)(i)
+}
+
+/// A SQL type specifier followed by its attributes, e.g. `BIGINT(20) UNSIGNED`.
+pub fn type_with_attributes(i: &str) -> IResult<&str, (SqlType, TypeAttributes)> {
+    pair(sql_type, type_attributes)(i)
+}
+
+fn sql_type(i: &str) -> IResult<&str, SqlType> {
+    let (remaining_input, (base, dimensions)) = pair(
+        alt((
+            type_identifier_temporal,
+            type_identifier_first_half,
+            type_identifier_second_half,
+            type_identifier_structured,
+            type_identifier_national,
+            type_identifier_postgres,
+        )),
+        // declared sizes are not enforced by Postgres, so they are dropped
+        many0(delimited(tag("["), opt(digit1), tag("]"))),
+    )(i)?;
+
+    let sql_type = dimensions
+        .iter()
+        .fold(base, |t, _| SqlType::Array(Box::new(t)));
+    Ok((remaining_input, sql_type))
+}
 
 /// Parses the arguments for an agregation function, and also returns whether the distinct flag is
 /// present.
-named!(pub function_arguments<CompleteByteSlice, (Column, bool)>,
-       do_parse!(
-           distinct: opt!(do_parse!(
-               tag_no_case!("distinct") >>
-               multispace >>
-               ()
-           )) >>
-           column: column_identifier_no_alias >>
-           (column, distinct.is_some())
-       )
-);
-
-named!(pub column_function<CompleteByteSlice, FunctionExpression>,
-    alt!(
-        do_parse!(
-            tag_no_case!("count(*)") >>
-            (FunctionExpression::CountStar)
-        )
-    |   do_parse!(
-            tag_no_case!("count") >>
-            args: delimited!(tag!("("), function_arguments, tag!(")")) >>
-            (FunctionExpression::Count(args.0.clone(), args.1))
-        )
-    |   do_parse!(
-            tag_no_case!("sum") >>
-            args: delimited!(tag!("("), function_arguments, tag!(")")) >>
-            (FunctionExpression::Sum(args.0.clone(), args.1))
-        )
-    |   do_parse!(
-            tag_no_case!("avg") >>
-            args: delimited!(tag!("("), function_arguments, tag!(")")) >>
-            (FunctionExpression::Avg(args.0.clone(), args.1))
-        )
-    |   do_parse!(
-            tag_no_case!("max") >>
-            args: delimited!(tag!("("), function_arguments, tag!(")")) >>
-            (FunctionExpression::Max(args.0.clone()))
-        )
-    |   do_parse!(
-            tag_no_case!("min") >>
-            args: delimited!(tag!("("), function_arguments, tag!(")")) >>
-            (FunctionExpression::Min(args.0.clone()))
-        )
-    |   do_parse!(
-            tag_no_case!("group_concat") >>
-            spec: delimited!(tag!("("),
-                       do_parse!(
-                               column: column_identifier_no_alias >>
-                               seperator: opt!(
-                                   do_parse!(
-                                       opt_multispace >>
-                                       tag_no_case!("separator") >>
-                                       sep: delimited!(tag!("'"), opt!(alphanumeric), tag!("'")) >>
-                                       opt_multispace >>
-                                       (sep.unwrap_or(CompleteByteSlice(&[])))
-                                   )
-                               ) >>
-                               (column, seperator)
-                       ),
-                       tag!(")")) >>
-            ({
-                let (ref col, ref sep) = spec;
-                let sep = match *sep {
-                    // default separator is a comma, see MySQL manual §5.7
-                    None => String::from(","),
-                    Some(s) => String::from_utf8(s.to_vec()).unwrap(),
-                };
-
-                FunctionExpression::GroupConcat(col.clone(), sep)
-            })
-        )
-    )
-);
+pub fn function_arguments(i: &str) -> IResult<&str, (Column, bool)> {
+    map(
+        pair(
+            opt(terminated(tag_no_case("distinct"), keyword_whitespace)),
+            column_identifier_no_alias,
+        ),
+        |(distinct, column)| (column, distinct.is_some()),
+    )(i)
+}
+
+fn delim_fn_args(i: &str) -> IResult<&str, (Column, bool)> {
+    delimited(
+        tuple((whitespace0, tag("("), whitespace0)),
+        function_arguments,
+        pair(whitespace0, tag(")")),
+    )(i)
+}
+
+fn group_concat_fx(i: &str) -> IResult<&str, (Column, Option<String>)> {
+    pair(
+        column_identifier_no_alias,
+        opt(delimited(
+            tuple((whitespace1, tag_no_case("separator"), whitespace0)),
+            raw_string_singlequoted,
+            whitespace0,
+        )),
+    )(i)
+}
+
+/// Sequence name passed to `nextval`, which Postgres takes as a (possibly schema-qualified) string
+/// and MariaDB as a bare identifier. pg_dump adds a `::regclass` cast to the string.
+fn sequence_name(i: &str) -> IResult<&str, &str> {
+    let qualified_name = |i| take_while1(|c| is_sql_identifier(c) || c == '.')(i);
+    alt((
+        terminated(
+            delimited(tag("'"), qualified_name, tag("'")),
+            opt(pair(tag("::"), tag_no_case("regclass"))),
+        ),
+        qualified_name,
+    ))(i)
+}
+
+pub fn nextval_fx(i: &str) -> IResult<&str, FunctionExpression> {
+    map(
+        alt((
+            preceded(
+                tag_no_case("nextval"),
+                delimited(
+                    pair(tag("("), whitespace0),
+                    sequence_name,
+                    pair(whitespace0, tag(")")),
+                ),
+            ),
+            preceded(
+                tuple((
+                    tag_no_case("next"),
+                    whitespace1,
+                    tag_no_case("value"),
+                    whitespace1,
+                    tag_no_case("for"),
+                    whitespace1,
+                )),
+                sequence_name,
+            ),
+        )),
+        |seq| FunctionExpression::NextVal(String::from(seq)),
+    )(i)
+}
+
+/// `VALUES(column)`, which is a keyword rather than a function name
+fn values_fx(i: &str) -> IResult<&str, FunctionExpression> {
+    map(
+        preceded(
+            tag_no_case("values"),
+            delimited(
+                tuple((whitespace0, tag("("), whitespace0)),
+                plain_column_identifier,
+                pair(whitespace0, tag(")")),
+            ),
+        ),
+        FunctionExpression::Values,
+    )(i)
+}
+
+/// `[pg_catalog.]set_config('setting', 'value', is_local)`
+fn set_config_fx(i: &str) -> IResult<&str, FunctionExpression> {
+    let string = |i| {
+        map(string_literal, |s| match s {
+            Literal::String(s) => s,
+            _ => unreachable!("string_literal only returns strings"),
+        })(i)
+    };
+    map(
+        tuple((
+            opt(terminated(tag_no_case("pg_catalog"), tag("."))),
+            tag_no_case("set_config"),
+            whitespace0,
+            pair(tag("("), whitespace0),
+            string,
+            ws_sep_comma,
+            string,
+            ws_sep_comma,
+            alt((
+                value(true, tag_no_case("true")),
+                value(false, tag_no_case("false")),
+            )),
+            pair(whitespace0, tag(")")),
+        )),
+        |(schema, _, _, _, setting, _, value, _, is_local, _)| {
+            FunctionExpression::SetConfig(schema.map(String::from), setting, value, is_local)
+        },
+    )(i)
+}
+
+/// Any other function, `name(arg, ...)`, where whitespace may separate the name from the
+/// parenthesis. Aggregates whose arguments the rules above do not take are not accepted as plain calls.
+fn call_fx(i: &str) -> IResult<&str, FunctionExpression> {
+    // `group_concat` of several expressions, which `GroupConcat` cannot hold, is left to a call
+    const AGGREGATES: [&str; 5] = ["avg", "count", "max", "min", "sum"];
+    map(
+        pair(
+            verify(
+                // checking for the parenthesis first spares plain columns the keyword lookup
+                preceded(
+                    peek(tuple((
+                        take_while1(is_sql_identifier),
+                        whitespace0,
+                        tag("("),
+                    ))),
+                    sql_identifier,
+                ),
+                |name: &str| !AGGREGATES.iter().any(|a| a.eq_ignore_ascii_case(name)),
+            ),
+            delimited(
+                tuple((whitespace0, tag("("), whitespace0)),
+                separated_list0(ws_sep_comma, condition_expr),
+                pair(whitespace0, tag(")")),
+            ),
+        ),
+        |(name, args)| FunctionExpression::Call(String::from(name), args),
+    )(i)
+}
+
+pub fn column_function(i: &str) -> IResult<&str, FunctionExpression> {
+    // a call is tried as a column, as an arithmetic operand and as a predicate's operand, each
+    // of which tries its arguments in the same ways
+    memoized(nested_function_call, i)
+}
+
+fn nested_function_call(i: &str) -> IResult<&str, FunctionExpression> {
+    alt((
+        nextval_fx,
+        set_config_fx,
+        values_fx,
+        nested(
+            tuple((take_while1(is_sql_identifier), whitespace0, tag("("))),
+            function_call,
+        ),
+    ))(i)
+}
+
+/// A call whose arguments may themselves contain calls.
+fn function_call(i: &str) -> IResult<&str, FunctionExpression> {
+    alt((
+        value(
+            FunctionExpression::CountStar,
+            tuple((
+                tag_no_case("count"),
+                whitespace0,
+                tag("("),
+                whitespace0,
+                tag("*"),
+                whitespace0,
+                tag(")"),
+            )),
+        ),
+        map(preceded(tag_no_case("count"), delim_fn_args), |args| {
+            FunctionExpression::Count(args.0, args.1)
+        }),
+        map(preceded(tag_no_case("sum"), delim_fn_args), |args| {
+            FunctionExpression::Sum(args.0, args.1)
+        }),
+        map(preceded(tag_no_case("avg"), delim_fn_args), |args| {
+            FunctionExpression::Avg(args.0, args.1)
+        }),
+        map(preceded(tag_no_case("max"), delim_fn_args), |args| {
+            FunctionExpression::Max(args.0)
+        }),
+        map(preceded(tag_no_case("min"), delim_fn_args), |args| {
+            FunctionExpression::Min(args.0)
+        }),
+        map(
+            preceded(
+                tag_no_case("group_concat"),
+                delimited(
+                    tuple((whitespace0, tag("("), whitespace0)),
+                    group_concat_fx,
+                    pair(whitespace0, tag(")")),
+                ),
+            ),
+            |(col, sep)| {
+                // default separator is a comma, see MySQL manual §5.7
+                let sep = sep.unwrap_or_else(|| String::from(","));
+                FunctionExpression::GroupConcat(col, sep)
+            },
+        ),
+        call_fx,
+    ))(i)
+}
+
+fn table_prefix(i: &str) -> IResult<&str, Cow<'_, str>> {
+    terminated(sql_identifier, tag("."))(i)
+}
 
 /// Parses a SQL column identifier in the table.column format
-named!(pub column_identifier_no_alias<CompleteByteSlice, Column>,
-    alt!(
-        do_parse!(
-            function: column_function >>
-            (Column {
-                name: format!("{}", function),
-                alias: None,
-                table: None,
-                function: Some(Box::new(function)),
-            })
-        )
-        | do_parse!(
-            table: opt!(
-                do_parse!(
-                    tbl_name: sql_identifier >>
-                    tag!(".") >>
-                    (str::from_utf8(*tbl_name).unwrap())
-                )
-            ) >>
-            column: sql_identifier >>
-            (Column {
-                name: String::from(str::from_utf8(*column).unwrap()),
-                alias: None,
-                table: match table {
-                    None => None,
-                    Some(t) => Some(String::from(t)),
-                },
-                function: None,
-            })
-        )
-    )
-);
+pub fn column_identifier_no_alias(i: &str) -> IResult<&str, Column> {
+    alt((
+        map(column_function, |function| Column {
+            name: format!("{}", function).into(),
+            alias: None,
+            table: None,
+            function: Some(Box::new(function)),
+        }),
+        plain_column_identifier,
+    ))(i)
+}
+
+/// Parses a column in the table.column format that is not a function call, as where columns are
+/// defined or indexed.
+pub fn plain_column_identifier(i: &str) -> IResult<&str, Column> {
+    map(
+        pair(opt(table_prefix), sql_identifier),
+        |(table, column)| Column {
+            name: Ident::from(column),
+            alias: None,
+            table: table.map(Ident::from),
+            function: None,
+        },
+    )(i)
+}
 
 /// Parses a SQL column identifier in the table.column format
-named!(pub column_identifier<CompleteByteSlice, Column>,
-    alt!(
-        do_parse!(
-            function: column_function >>
-            alias: opt!(as_alias) >>
-            (Column {
+pub fn column_identifier(i: &str) -> IResult<&str, Column> {
+    alt((
+        map(pair(column_function, opt(as_alias)), |(function, alias)| {
+            Column {
                 name: match alias {
-                    None => format!("{}", function),
-                    Some(a) => String::from(a),
-                },
-                alias: match alias {
-                    None => None,
-                    Some(a) => Some(String::from(a)),
+                    None => format!("{}", function).into(),
+                    Some(ref a) => Ident::from(&**a),
                 },
+                alias: alias.map(Ident::from),
                 table: None,
                 function: Some(Box::new(function)),
-            })
-        )
-        | do_parse!(
-            table: opt!(
-                do_parse!(
-                    tbl_name: sql_identifier >>
-                    tag!(".") >>
-                    (str::from_utf8(*tbl_name).unwrap())
-                )
-            ) >>
-            column: sql_identifier >>
-            alias: opt!(as_alias) >>
-            (Column {
-                name: String::from_utf8(column.to_vec()).unwrap(),
-                alias: match alias {
-                    None => None,
-                    Some(a) => Some(String::from(a)),
-                },
-                table: match table {
-                    None => None,
-                    Some(t) => Some(String::from(t)),
-                },
+            }
+        }),
+        map(
+            tuple((opt(table_prefix), sql_identifier, opt(as_alias))),
+            |(table, column, alias)| Column {
+                name: Ident::from(column),
+                alias: alias.map(Ident::from),
+                table: table.map(Ident::from),
                 function: None,
-            })
-        )
-    )
-);
-
-/// Parses a SQL identifier (alphanumeric and "_").
-named!(pub sql_identifier<CompleteByteSlice, CompleteByteSlice>,
-    alt!(
-          do_parse!(
-                not!(peek!(sql_keyword)) >>
-                ident: take_while1!(is_sql_identifier) >>
-                (ident)
-          )
-        | delimited!(tag!("`"), take_while1!(is_sql_identifier), tag!("`"))
-        | delimited!(tag!("["), take_while1!(is_sql_identifier), tag!("]"))
-    )
-);
+            },
+        ),
+    ))(i)
+}
+
+/// Parses a SQL identifier: a run of letters, digits and `_` that is not a reserved keyword, or
+/// any name in backticks or brackets, in which the closing quote is doubled to stand for itself.
+/// Postgres also takes a name in double quotes.
+pub fn sql_identifier(i: &str) -> IResult<&str, Cow<'_, str>> {
+    alt((
+        map(
+            preceded(not(sql_keyword), take_while1(is_sql_identifier)),
+            Cow::Borrowed,
+        ),
+        quoted_name('`', '`'),
+        quoted_name('[', ']'),
+        only_in(Dialect::Postgres, quoted_name('"', '"')),
+    ))(i)
+}
+
+/// An identifier in ANSI double quotes, as SQLite and Postgres schemas write them. MySQL takes
+/// double-quoted text for a string, so this is only accepted where no string can appear.
+pub fn double_quoted_identifier(i: &str) -> IResult<&str, Cow<'_, str>> {
+    quoted_name('"', '"')(i)
+}
 
 /// Parse an unsigned integer.
-named!(pub unsigned_number<CompleteByteSlice, u64>,
-    do_parse!(
-        d: digit >>
-        (FromStr::from_str(str::from_utf8(*d).unwrap()).unwrap())
-    )
-);
-
-/// Parse a terminator that ends a SQL statement.
-named!(pub statement_terminator<CompleteByteSlice, ()>,
-    do_parse!(
-        delimited!(
-            opt_multispace,
-            alt!(tag!(";") | line_ending | eof!()),
-            opt_multispace
-        ) >>
-        ()
-    )
-);
-
-named!(pub opt_multispace<CompleteByteSlice, Option<CompleteByteSlice>>,
-       opt!(multispace)
-);
-
-/// Parse binary comparison operators
-named!(pub binary_comparison_operator<CompleteByteSlice, Operator>,
-    alt!(
-           map!(tag_no_case!("not_like"), |_| Operator::NotLike)
-         | map!(tag_no_case!("like"), |_| Operator::Like)
-         | map!(tag_no_case!("!="), |_| Operator::NotEqual)
-         | map!(tag_no_case!("<>"), |_| Operator::NotEqual)
-         | map!(tag_no_case!(">="), |_| Operator::GreaterOrEqual)
-         | map!(tag_no_case!("<="), |_| Operator::LessOrEqual)
-         | map!(tag_no_case!("="), |_| Operator::Equal)
-         | map!(tag_no_case!("<"), |_| Operator::Less)
-         | map!(tag_no_case!(">"), |_| Operator::Greater)
-         | map!(tag_no_case!("in"), |_| Operator::In)
-    )
-);
-
-/// Parse rule for AS-based aliases for SQL entities.
-named!(pub as_alias<CompleteByteSlice, &str>,
-    do_parse!(
-        multispace >>
-        opt!(do_parse!(tag_no_case!("as") >> multispace >> ())) >>
-        alias: sql_identifier >>
-        (str::from_utf8(*alias).unwrap())
-    )
-);
-
-named!(field_value_expr<CompleteByteSlice, FieldValueExpression>,
-    alt!(
-        map!(literal, |l| FieldValueExpression::Literal(LiteralExpression {
-            value: l.into(),
-            alias: None,
-        }))
-        | map!(arithmetic_expression, |ae| FieldValueExpression::Arithmetic(ae))
-    )
-);
-
-named!(assignment_expr<CompleteByteSlice, (Column, FieldValueExpression) >,
-    do_parse!(
-        column: column_identifier_no_alias >>
-        opt_multispace >>
-        tag!("=") >>
-        opt_multispace >>
-        value: field_value_expr >>
-        (column, value)
-    )
-);
-
-named!(pub assignment_expr_list<CompleteByteSlice, Vec<(Column, FieldValueExpression)> >,
-       many1!(
-           do_parse!(
-               field_value: assignment_expr >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               (field_value)
-           )
-       )
-);
+pub fn unsigned_number(i: &str) -> IResult<&str, u64> {
+    map_res(digit1, u64::from_str)(i)
+}
+
+/// Parse a terminator that ends a SQL statement: a semicolon, or else the end of the input, after
+/// any whitespace and comments. Nothing following the semicolon is consumed, so that another
+/// statement may follow.
+pub fn statement_terminator(i: &str) -> IResult<&str, ()> {
+    value((), preceded(whitespace0, alt((tag(";"), eof))))(i)
+}
+
+/// Parse a comma surrounded by optional whitespace, as used between list elements.
+pub fn ws_sep_comma(i: &str) -> IResult<&str, &str> {
+    delimited(whitespace0, tag(","), whitespace0)(i)
+}
+
+/// Parses a comment running to the end of the line (`-- ` or `#`) or to a closing `*/`. As in
+/// the tokenizer, `--` must be followed by whitespace and `#` must not start a `#>` operator.
+pub fn sql_comment(i: &str) -> IResult<&str, &str> {
+    let line_comment = alt((
+        recognize(pair(tag("--"), alt((multispace1, eof)))),
+        terminated(tag("#"), not(tag(">"))),
+    ));
+    alt((
+        recognize(pair(line_comment, opt(is_not("\n")))),
+        recognize(tuple((tag("/*"), take_until("*/"), tag("*/")))),
+    ))(i)
+}
+
+/// Parses optional whitespace between tokens, which may consist of or include comments.
+pub fn whitespace0(i: &str) -> IResult<&str, &str> {
+    recognize(many0_count(alt((multispace1, sql_comment))))(i)
+}
+
+/// Parses the whitespace that must separate two tokens, which may consist of or include comments.
+pub fn whitespace1(i: &str) -> IResult<&str, &str> {
+    recognize(many1_count(alt((multispace1, sql_comment))))(i)
+}
+
+/// Parses the whitespace after a keyword, which may be left out where the next token cannot run
+/// into the keyword, as in `WHERE(a = 1)` or `LIKE'x%'`.
+pub fn keyword_whitespace(i: &str) -> IResult<&str, &str> {
+    preceded(not(satisfy(is_sql_identifier)), whitespace0)(i)
+}
+
+/// A parser chosen by `keyword_dispatch`.
+pub(crate) type KeywordParser<O> = fn(&str) -> IResult<&str, O>;
+
+/// Runs the parser keyed on the word that `i` starts with, ignoring leading whitespace and case,
+/// so that alternatives introduced by distinct keywords need not each be attempted in turn.
+pub(crate) fn keyword_dispatch<'a, O>(
+    i: &'a str,
+    parsers: &[(&str, KeywordParser<O>)],
+) -> IResult<&'a str, O> {
+    let word = i.trim_start();
+    let word = &word[..word.find(|c| !is_sql_identifier(c)).unwrap_or(word.len())];
+    match parsers.iter().find(|(k, _)| k.eq_ignore_ascii_case(word)) {
+        Some((_, parser)) => parser(i),
+        None => Err(nom::Err::Error(Error::new(i, ErrorKind::Tag))),
+    }
+}
+
+/// Parses a keyword of one or more words, such as `NOT SIMILAR TO` or `ORDER BY`, which may be
+/// separated by any whitespace.
+pub(crate) fn keyword_phrase<'a>(
+    words: &'static [&'static str],
+) -> impl FnMut(&'a str) -> IResult<&'a str, ()> {
+    move |mut i: &'a str| {
+        for (n, word) in words.iter().enumerate() {
+            if n > 0 {
+                i = whitespace1(i)?.0;
+            }
+            i = terminated(tag_no_case(word), not(satisfy(is_sql_identifier)))(i)?.0;
+        }
+        Ok((i, ()))
+    }
+}
+
+/// Parses the regular expression, pattern matching and full-text search operators other than
+/// `LIKE`.
+fn pattern_operator(i: &str) -> IResult<&str, Operator> {
+    alt((
+        value(Operator::ILike, keyword_phrase(&["ilike"])),
+        value(Operator::NotILike, keyword_phrase(&["not", "ilike"])),
+        value(Operator::NotLike, keyword_phrase(&["not", "like"])),
+        value(Operator::Regexp, keyword_phrase(&["regexp"])),
+        value(Operator::Regexp, keyword_phrase(&["rlike"])),
+        value(Operator::NotRegexp, keyword_phrase(&["not", "regexp"])),
+        value(Operator::NotRegexp, keyword_phrase(&["not", "rlike"])),
+        value(Operator::SimilarTo, keyword_phrase(&["similar", "to"])),
+        value(
+            Operator::NotSimilarTo,
+            keyword_phrase(&["not", "similar", "to"]),
+        ),
+        value(Operator::NotMatchInsensitive, tag("!~*")),
+        value(Operator::NotMatch, tag("!~")),
+        value(Operator::MatchInsensitive, tag("~*")),
+        value(Operator::Match, tag("~")),
+        value(Operator::TextSearchMatch, tag("@@")),
+    ))(i)
+}
+
+pub fn binary_comparison_operator(i: &str) -> IResult<&str, Operator> {
+    alt((
+        pattern_operator,
+        value(Operator::NotLike, keyword_phrase(&["not_like"])),
+        value(Operator::Like, keyword_phrase(&["like"])),
+        value(Operator::NotEqual, tag("!=")),
+        value(Operator::NotEqual, tag("<>")),
+        value(Operator::GreaterOrEqual, tag(">=")),
+        value(Operator::LessOrEqual, tag("<=")),
+        value(Operator::JsonContains, tag("@>")),
+        value(Operator::JsonContainedBy, tag("<@")),
+        value(Operator::JsonAnyKeyExists, tag("?|")),
+        value(Operator::JsonAllKeysExist, tag("?&")),
+        value(Operator::JsonKeyExists, tag("?")),
+        value(Operator::Equal, tag("=")),
+        value(Operator::Less, tag("<")),
+        value(Operator::Greater, tag(">")),
+        value(Operator::In, keyword_phrase(&["in"])),
+    ))(i)
+}
+
+/// Parse rule for AS-based aliases for SQL entities. Without `AS`, non-reserved keywords are not
+/// taken for an alias, so that `FROM t OFFSET 5` or `ELSE x END` keep their meaning.
+pub fn as_alias(i: &str) -> IResult<&str, Cow<'_, str>> {
+    preceded(
+        whitespace1,
+        alt((
+            preceded(pair(tag_no_case("as"), whitespace1), sql_identifier),
+            preceded(not(non_reserved_keyword), sql_identifier),
+        )),
+    )(i)
+}
+
+/// A name with the quotes it was written in, if any.
+pub type QuotedName<'a> = (Cow<'a, str>, Option<QuoteStyle>);
+
+/// Parses a non-empty name between `open` and `close`, in which a doubled `close` stands for the
+/// character itself, as in `` `a``b` `` or `[a]]b]`.
+fn quoted_name(open: char, close: char) -> impl Fn(&str) -> IResult<&str, Cow<'_, str>> {
+    move |i| {
+        let (rest, _) = char(open)(i)?;
+        let error = || nom::Err::Error(Error::new(i, ErrorKind::Char));
+        let mut unescaped: Option<String> = None;
+        let mut start = 0;
+        loop {
+            let at = start + rest[start..].find(close).ok_or_else(error)?;
+            let after = at + close.len_utf8();
+            if rest[after..].starts_with(close) {
+                unescaped
+                    .get_or_insert_with(String::new)
+                    .push_str(&rest[start..after]);
+                start = after + close.len_utf8();
+                continue;
+            }
+            let name = match unescaped {
+                Some(mut name) => {
+                    name.push_str(&rest[start..at]);
+                    Cow::Owned(name)
+                }
+                None => Cow::Borrowed(&rest[..at]),
+            };
+            if name.is_empty() {
+                return Err(error());
+            }
+            return Ok((&rest[after..], name));
+        }
+    }
+}
+
+/// Parses the name of a table, or its alias, along with the quotes it is written in. Besides the
+/// backticks and brackets that `sql_identifier` takes, ANSI double quotes are accepted, since no
+/// string can stand in for a table.
+pub fn table_identifier(i: &str) -> IResult<&str, QuotedName<'_>> {
+    let quoted =
+        |open, close, style| map(quoted_name(open, close), move |name| (name, Some(style)));
+    alt((
+        map(
+            preceded(not(sql_keyword), take_while1(is_sql_identifier)),
+            |name| (Cow::Borrowed(name), None),
+        ),
+        quoted('`', '`', QuoteStyle::Backtick),
+        quoted('"', '"', QuoteStyle::DoubleQuote),
+        quoted('[', ']', QuoteStyle::Bracket),
+    ))(i)
+}
+
+/// Parses the name of a stored table, which may be qualified by the schema (or database) it is
+/// in, as in `public.t` or `db.t`. Following a schema, the name may be a reserved keyword.
+pub fn table_name(i: &str) -> IResult<&str, Table> {
+    let qualified_name = alt((
+        table_identifier,
+        map(take_while1(is_sql_identifier), |name| {
+            (Cow::Borrowed(name), None)
+        }),
+    ));
+    alt((
+        map(
+            separated_pair(table_identifier, tag("."), qualified_name),
+            |(schema, name)| Table {
+                schema: Some(Ident::from(schema.0)),
+                schema_quote: schema.1,
+                quote: name.1,
+                ..Table::from(&*name.0)
+            },
+        ),
+        map(table_identifier, |name| Table {
+            quote: name.1,
+            ..Table::from(&*name.0)
+        }),
+    ))(i)
+}
+
+/// Like `as_alias`, but for a table, whose alias may be quoted in the ways `table_identifier`
+/// accepts.
+pub fn table_as_alias(i: &str) -> IResult<&str, QuotedName<'_>> {
+    preceded(
+        whitespace1,
+        alt((
+            preceded(pair(tag_no_case("as"), whitespace1), table_identifier),
+            preceded(not(non_reserved_keyword), table_identifier),
+        )),
+    )(i)
+}
+
+/// Parses a user variable reference such as `@total` or `` @`my var` ``, returning its name.
+pub fn user_variable(i: &str) -> IResult<&str, &str> {
+    preceded(
+        tag("@"),
+        alt((
+            delimited(tag("`"), is_not("`"), tag("`")),
+            take_while1(|c| is_sql_identifier(c) || c == '.' || c == '$'),
+        )),
+    )(i)
+}
+
+/// The right-hand side of a `:=` assignment; aliases belong to the enclosing expression.
+fn assigned_value(i: &str) -> IResult<&str, FieldDefinitionExpression> {
+    alt((
+        map(arithmetic_expression, |expr| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Arithmetic(expr))
+        }),
+        map(variable_expression, |var| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Variable(var))
+        }),
+        map(literal, |lit| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Literal(lit.into()))
+        }),
+        map(column_identifier_no_alias, FieldDefinitionExpression::Col),
+    ))(i)
+}
+
+/// Parses `@name`, `@name := value` and either form followed by an alias.
+pub fn variable_expression(i: &str) -> IResult<&str, VariableExpression> {
+    map(
+        tuple((
+            user_variable,
+            opt(preceded(
+                delimited(whitespace0, tag(":="), whitespace0),
+                assigned_value,
+            )),
+            opt(as_alias),
+        )),
+        |(name, value, alias)| VariableExpression {
+            name: String::from(name),
+            value: value.map(Box::new),
+            alias: alias.map(String::from),
+        },
+    )(i)
+}
+
+pub(crate) fn field_value_expr(i: &str) -> IResult<&str, FieldValueExpression> {
+    alt((
+        map(default_keyword, FieldValueExpression::from),
+        map(arithmetic_expression, FieldValueExpression::Arithmetic),
+        map(literal, FieldValueExpression::from),
+        map(json_expression, FieldValueExpression::Json),
+        map(subscript_expression, FieldValueExpression::Subscript),
+        map(variable_expression, FieldValueExpression::Variable),
+        map(condition_expr, FieldValueExpression::Expression),
+    ))(i)
+}
+
+fn assignment_expr(i: &str) -> IResult<&str, (Column, FieldValueExpression)> {
+    separated_pair(
+        column_identifier_no_alias,
+        delimited(whitespace0, tag("="), whitespace0),
+        field_value_expr,
+    )(i)
+}
+
+pub fn assignment_expr_list(i: &str) -> IResult<&str, Vec<(Column, FieldValueExpression)>> {
+    many1(terminated(assignment_expr, opt(ws_sep_comma)))(i)
+}
+
+/// Parses a parenthesized span, returning its trimmed contents verbatim. Nested parentheses and
+/// quoted strings are skipped over.
+pub fn parenthesized_text(i: &str) -> IResult<&str, &str> {
+    let error = || nom::Err::Error(Error::new(i, ErrorKind::Char));
+    if !i.starts_with('(') {
+        return Err(error());
+    }
+    let mut depth = 0;
+    let mut quote = None;
+    let mut chars = i.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match quote {
+            Some(_) if c == '\\' => {
+                chars.next();
+            }
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None => match c {
+                '\'' | '"' | '`' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok((&i[idx + 1..], i[1..idx].trim()));
+                    }
+                }
+                _ => (),
+            },
+        }
+    }
+    Err(error())
+}
 
 /// Parse rule for a comma-separated list of fields without aliases.
-named!(pub field_list<CompleteByteSlice, Vec<Column> >,
-       many0!(
-           do_parse!(
-               fieldname: column_identifier_no_alias >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               (fieldname)
-           )
-       )
-);
+pub fn field_list(i: &str) -> IResult<&str, Vec<Column>> {
+    many0(terminated(column_identifier_no_alias, opt(ws_sep_comma)))(i)
+}
+
+/// A parenthesized operand in a select list, as in `(a + b)` or `(max(a)) AS m`, which is taken as
+/// the operand itself. An alias may follow the parentheses but not be inside them as well.
+fn parenthesized_field(i: &str) -> IResult<&str, FieldDefinitionExpression> {
+    map_opt(
+        pair(
+            nested(
+                tag("("),
+                delimited(
+                    pair(tag("("), whitespace0),
+                    field_definition,
+                    pair(whitespace0, tag(")")),
+                ),
+            ),
+            opt(as_alias),
+        ),
+        |(mut field, alias)| {
+            let alias = match alias {
+                Some(alias) => alias,
+                None => return Some(field),
+            };
+            match field {
+                FieldDefinitionExpression::Col(ref mut col) if col.alias.is_none() => {
+                    // as in `column_identifier`, an aliased function call is named by its alias
+                    if col.function.is_some() {
+                        col.name = Ident::from(&*alias);
+                    }
+                    col.alias = Some(alias.into());
+                }
+                FieldDefinitionExpression::Value(ref mut value) => {
+                    let slot = match *value {
+                        FieldValueExpression::Arithmetic(ref mut e) => &mut e.alias,
+                        FieldValueExpression::Literal(ref mut e) => &mut e.alias,
+                        FieldValueExpression::Variable(ref mut e) => &mut e.alias,
+                        FieldValueExpression::Json(ref mut e) => &mut e.alias,
+                        FieldValueExpression::Subscript(ref mut e) => &mut e.alias,
+                        FieldValueExpression::Expression(_)
+                        | FieldValueExpression::Predicate(_) => return None,
+                    };
+                    if slot.is_some() {
+                        return None;
+                    }
+                    *slot = Some(String::from(alias));
+                }
+                _ => return None,
+            }
+            Some(field)
+        },
+    )(i)
+}
+
+fn field_definition(i: &str) -> IResult<&str, FieldDefinitionExpression> {
+    let predicate = |i| {
+        map(predicate_expression, |pred| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Predicate(pred))
+        })(i)
+    };
+    let operand = alt((
+        value(FieldDefinitionExpression::All, tag("*")),
+        map(terminated(sql_identifier, tag(".*")), |table| {
+            FieldDefinitionExpression::AllInTable(String::from(table))
+        }),
+        map(json_expression, |expr| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Json(expr))
+        }),
+        map(subscript_expression, |expr| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Subscript(expr))
+        }),
+        map(arithmetic_expression, |expr| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Arithmetic(expr))
+        }),
+        verify(parenthesized_field, |field| match *field {
+            FieldDefinitionExpression::Value(FieldValueExpression::Predicate(_)) => false,
+            FieldDefinitionExpression::Value(_) | FieldDefinitionExpression::Col(_) => true,
+            _ => false,
+        }),
+        map(literal_expression, |lit| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Literal(lit))
+        }),
+        map(variable_expression, |var| {
+            FieldDefinitionExpression::Value(FieldValueExpression::Variable(var))
+        }),
+        map(column_identifier, FieldDefinitionExpression::Col),
+    ))(i);
+    // Most fields are plain operands, so the condition grammar is only tried when an operand is
+    // followed by a comparison or logical operator, or when there is no operand at all, as with
+    // `NOT active`.
+    match operand {
+        Ok((rest, _)) if predicate_continuation(rest).is_ok() => predicate(i).or(operand),
+        Ok(_) => operand,
+        Err(nom::Err::Error(_)) => predicate(i),
+        Err(e) => Err(e),
+    }
+}
 
 /// Parse list of column/field definitions.
-named!(pub field_definition_expr<CompleteByteSlice, Vec<FieldDefinitionExpression>>,
-       many0!(
-           do_parse!(
-               field: alt!(
-                   do_parse!(
-                       tag!("*") >>
-                       (FieldDefinitionExpression::All)
-                   )
-                 | do_parse!(
-                     table: table_reference >>
-                     tag!(".*") >>
-                     (FieldDefinitionExpression::AllInTable(table.name.clone()))
-                 )
-                 | do_parse!(
-                     expr: arithmetic_expression >>
-                     (FieldDefinitionExpression::Value(
-                             FieldValueExpression::Arithmetic(expr)))
-                 )
-                 | do_parse!(
-                     literal: literal_expression >>
-                     (FieldDefinitionExpression::Value(
-                             FieldValueExpression::Literal(literal)))
-                 )
-                 | do_parse!(
-                     column: column_identifier >>
-                     (FieldDefinitionExpression::Col(column))
-                 )
-               ) >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               (field)
-           )
-       )
-);
+pub fn field_definition_expr(i: &str) -> IResult<&str, Vec<FieldDefinitionExpression>> {
+    separated_list0(ws_sep_comma, field_definition)(i)
+}
 
 /// Parse list of table names.
 /// XXX(malte): add support for aliases
-named!(pub table_list<CompleteByteSlice, Vec<Table> >,
-       many0!(
-           do_parse!(
-               table: table_reference >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               (table)
-           )
-       )
-);
-
-/// Integer literal value
-named!(pub integer_literal<CompleteByteSlice, Literal>,
-    do_parse!(
-        sign: opt!(tag!("-")) >>
-        val: digit >>
-        ({
-            let mut intval = i64::from_str(str::from_utf8(*val).unwrap()).unwrap();
-            if sign.is_some() {
-                intval *= -1;
-            }
-            Literal::Integer(intval)
-        })
-    )
-);
-
-/// Floating point literal value
-named!(pub float_literal<CompleteByteSlice, Literal>,
-    do_parse!(
-        sign: opt!(tag!("-")) >>
-        mant: digit >>
-        tag!(".") >>
-        frac: digit >>
-        ({
-            let unpack = |v: &[u8]| -> i32 {
-                i32::from_str(str::from_utf8(v).unwrap()).unwrap()
-            };
-            Literal::FixedPoint(Real {
-                integral: if sign.is_some() {
-                    -1 * unpack(mant.0)
-                } else {
-                    unpack(mant.0)
-                },
-                fractional: unpack(frac.0) as i32,
-            })
-        })
-    )
-);
+pub fn table_list(i: &str) -> IResult<&str, Vec<Table>> {
+    many0(terminated(table_reference, opt(ws_sep_comma)))(i)
+}
+
+/// Integer literal value. Digits that do not fit are a failure rather than an error, so that they
+/// are not taken for a column named by digits instead.
+pub fn integer_literal(i: &str) -> IResult<&str, Literal> {
+    let (remaining_input, digits) = recognize(pair(opt(tag("-")), digit1))(i)?;
+    match i64::from_str(digits) {
+        Ok(n) => Ok((remaining_input, Literal::Integer(n))),
+        Err(_) => out_of_range(i, remaining_input),
+    }
+}
+
+/// Floating point literal value, which fails like `integer_literal` if it has more digits than
+/// `Real` holds.
+pub fn float_literal(i: &str) -> IResult<&str, Literal> {
+    let (remaining_input, (sign, integral, _, fractional)) =
+        tuple((opt(tag("-")), digit1, tag("."), digit1))(i)?;
+    match Real::from_digits(sign.is_some(), integral, fractional) {
+        Some(real) => Ok((remaining_input, Literal::FixedPoint(real))),
+        None => out_of_range(i, remaining_input),
+    }
+}
+
+/// Rejects a number that does not fit in its literal, unless identifier characters follow the
+/// digits, which makes them the start of a name rather than a number.
+fn out_of_range<'a, O>(i: &'a str, remaining_input: &'a str) -> IResult<&'a str, O> {
+    if satisfy::<_, _, Error<&str>>(is_sql_identifier)(remaining_input).is_ok() {
+        return Err(nom::Err::Error(Error::new(i, ErrorKind::MapRes)));
+    }
+    Err(nom::Err::Failure(Error::new(i, ErrorKind::MapRes)))
+}
 
 /// String literal value
+fn raw_string_quoted(i: &str, single_quote: bool) -> IResult<&str, String> {
+    let (quote, double_quote, backslash_quote) = if single_quote {
+        ("'", "''", "\\'")
+    } else {
+        ("\"", "\"\"", "\\\"")
+    };
+    delimited(
+        tag(quote),
+        fold_many0(
+            alt((
+                is_not(backslash_quote),
+                value(quote, tag(double_quote)),
+                value("\\", tag("\\\\")),
+                value("\x7f", tag("\\b")),
+                value("\r", tag("\\r")),
+                value("\n", tag("\\n")),
+                value("\t", tag("\\t")),
+                value("\0", tag("\\0")),
+                value("\x1A", tag("\\Z")),
+                // kept escaped, so that they still match literally in a LIKE pattern
+                value("\\%", tag("\\%")),
+                value("\\_", tag("\\_")),
+                preceded(tag("\\"), take(1usize)),
+            )),
+            String::new,
+            |mut acc: String, s: &str| {
+                acc.push_str(s);
+                acc
+            },
+        ),
+        tag(quote),
+    )(i)
+}
 
-fn raw_string_quoted(input: CompleteByteSlice, quote: u8) -> IResult<CompleteByteSlice, Vec<u8>> {
-    let quote_slice: &[u8] = &[quote];
-    let double_quote_slice: &[u8] = &[quote, quote];
-    let backslash_quote: &[u8] = &[b'\\', quote];
-    delimited!(input,
-        tag!(quote_slice),
-        fold_many0!(
-            alt!(
-                is_not!(backslash_quote) |
-                map!(tag!(double_quote_slice), |_| CompleteByteSlice(quote_slice)) |
-                map!(tag!("\\\\"), |_| CompleteByteSlice(&b"\\"[..])) |
-                map!(tag!("\\b"), |_| CompleteByteSlice(&b"\x7f"[..])) |
-                map!(tag!("\\r"), |_| CompleteByteSlice(&b"\r"[..])) |
-                map!(tag!("\\n"), |_| CompleteByteSlice(&b"\n"[..])) |
-                map!(tag!("\\t"), |_| CompleteByteSlice(&b"\t"[..])) |
-                map!(tag!("\\0"), |_| CompleteByteSlice(&b"\0"[..])) |
-                map!(tag!("\\Z"), |_| CompleteByteSlice(&b"\x1A"[..])) |
-                do_parse!(
-                        _escape: tag!("\\") >>
-                        escaped: take!(1) >>
-                        (escaped ))
-            ),
-            Vec::new(),
-            |mut acc: Vec<u8>, bytes: CompleteByteSlice| {
-                acc.extend(bytes.0);
+/// A standard string, in which a quote is doubled and a backslash is an ordinary character.
+fn standard_string_singlequoted(i: &str) -> IResult<&str, String> {
+    delimited(
+        tag("'"),
+        fold_many0(
+            alt((is_not("'"), value("'", tag("''")))),
+            String::new,
+            |mut acc: String, s: &str| {
+                acc.push_str(s);
                 acc
-            }
+            },
         ),
-        tag!(quote_slice)
-    )
+        tag("'"),
+    )(i)
+}
+
+/// Postgres' escape string, `E'...'`, which takes C-style backslash escapes.
+fn escape_string_singlequoted(i: &str) -> IResult<&str, String> {
+    preceded(
+        pair(tag_no_case("e"), tag("'")),
+        terminated(
+            fold_many0(
+                alt((
+                    is_not("\\'"),
+                    value("'", tag("''")),
+                    value("\x08", tag("\\b")),
+                    value("\x0c", tag("\\f")),
+                    value("\n", tag("\\n")),
+                    value("\r", tag("\\r")),
+                    value("\t", tag("\\t")),
+                    preceded(tag("\\"), take(1usize)),
+                )),
+                String::new,
+                |mut acc: String, s: &str| {
+                    acc.push_str(s);
+                    acc
+                },
+            ),
+            tag("'"),
+        ),
+    )(i)
+}
+
+/// A string in single quotes: a standard string in Postgres, and one with MySQL's backslash
+/// escapes elsewhere.
+fn raw_string_singlequoted(i: &str) -> IResult<&str, String> {
+    alt((
+        only_in(Dialect::Postgres, standard_string_singlequoted),
+        except_in(Dialect::Postgres, |i| raw_string_quoted(i, true)),
+    ))(i)
+}
+
+fn raw_string_doublequoted(i: &str) -> IResult<&str, String> {
+    raw_string_quoted(i, false)
+}
+
+/// A string in single quotes, or in double quotes outside Postgres, which takes those for an
+/// identifier. Postgres reads backslash escapes only in an escape string, `E'...'`.
+pub fn string_literal(i: &str) -> IResult<&str, Literal> {
+    map(
+        alt((
+            raw_string_singlequoted,
+            only_in(Dialect::Postgres, escape_string_singlequoted),
+            except_in(Dialect::Postgres, raw_string_doublequoted),
+        )),
+        Literal::String,
+    )(i)
+}
+
+/// Hexadecimal literal, `X'0A1B'` or `0x0A1B`.
+fn hex_literal(i: &str) -> IResult<&str, Literal> {
+    let digits = |s: &str| s.len().is_multiple_of(2);
+    map_res(
+        alt((
+            delimited(tag_no_case("x'"), verify(hex_digit0, digits), tag("'")),
+            preceded(tag("0x"), verify(hex_digit1, digits)),
+        )),
+        |hex: &str| {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Literal::Blob)
+        },
+    )(i)
 }
 
-named!(raw_string_singlequoted< CompleteByteSlice, Vec<u8> >, call!(raw_string_quoted, b'\''));
-named!(raw_string_doublequoted< CompleteByteSlice, Vec<u8> >, call!(raw_string_quoted, b'"'));
+/// A string or hex literal preceded by a character set introducer, e.g. `_utf8mb4'text'`, or a
+/// national string, `N'text'`.
+fn charset_literal(i: &str) -> IResult<&str, Literal> {
+    alt((
+        map(
+            separated_pair(
+                preceded(tag("_"), alphanumeric1),
+                whitespace0,
+                alt((hex_literal, string_literal)),
+            ),
+            |(charset, literal)| Literal::Introduced(String::from(charset), Box::new(literal)),
+        ),
+        map(
+            preceded(tag_no_case("n"), raw_string_singlequoted),
+            Literal::NationalString,
+        ),
+    ))(i)
+}
 
-named!(pub string_literal<CompleteByteSlice, Literal>,
-       map!(alt!(raw_string_singlequoted | raw_string_doublequoted),
-             |bytes| match String::from_utf8(bytes) {
-                 Ok(s) => Literal::String(s),
-                 Err(err) => Literal::Blob(err.into_bytes())
-             }
-           )
-);
+/// Postgres array constructor, e.g. `ARRAY[1, 2, 3]`.
+fn array_literal(i: &str) -> IResult<&str, Literal> {
+    map(
+        preceded(
+            pair(tag_no_case("array"), whitespace0),
+            nested(
+                tag("["),
+                delimited(
+                    pair(tag("["), whitespace0),
+                    separated_list0(ws_sep_comma, literal),
+                    pair(whitespace0, tag("]")),
+                ),
+            ),
+        ),
+        Literal::Array,
+    )(i)
+}
 
 /// Any literal value.
-named!(pub literal<CompleteByteSlice, Literal>,
-    alt!(
-          float_literal
-        | integer_literal
-        | string_literal
-        | do_parse!(tag_no_case!("NULL") >> (Literal::Null))
-        | do_parse!(tag_no_case!("CURRENT_TIMESTAMP") >> (Literal::CurrentTimestamp))
-        | do_parse!(tag_no_case!("CURRENT_DATE") >> (Literal::CurrentDate))
-        | do_parse!(tag_no_case!("CURRENT_TIME") >> (Literal::CurrentTime))
-        | do_parse!(tag_no_case!("?") >> (Literal::Placeholder))
-    )
-);
-
-named!(pub literal_expression<CompleteByteSlice, LiteralExpression>,
-    do_parse!(
-        literal: delimited!(opt!(tag!("(")), literal, opt!(tag!(")"))) >>
-        alias: opt!(as_alias) >>
-        (LiteralExpression {
-            value: literal,
-            alias: alias.map(|a| a.to_string()),
-        })
-    )
-);
+pub fn literal(i: &str) -> IResult<&str, Literal> {
+    alt((
+        array_literal,
+        hex_literal,
+        charset_literal,
+        float_literal,
+        integer_literal,
+        string_literal,
+        value(Literal::Null, tag_no_case("null")),
+        value(
+            Literal::Boolean(true),
+            terminated(tag_no_case("true"), not(satisfy(is_sql_identifier))),
+        ),
+        value(
+            Literal::Boolean(false),
+            terminated(tag_no_case("false"), not(satisfy(is_sql_identifier))),
+        ),
+        value(Literal::CurrentTimestamp, tag_no_case("current_timestamp")),
+        value(Literal::CurrentDate, tag_no_case("current_date")),
+        value(Literal::CurrentTime, tag_no_case("current_time")),
+        value(Literal::Placeholder, tag("?")),
+    ))(i)
+}
+
+pub fn literal_expression(i: &str) -> IResult<&str, LiteralExpression> {
+    map(
+        pair(
+            alt((delimited(tag("("), literal, tag(")")), literal)),
+            opt(as_alias),
+        ),
+        |(value, alias)| LiteralExpression {
+            value,
+            alias: alias.map(String::from),
+        },
+    )(i)
+}
+
+/// `DEFAULT`, where a value may be replaced by the column's default.
+pub fn default_keyword(i: &str) -> IResult<&str, Literal> {
+    value(
+        Literal::Default,
+        terminated(tag_no_case("default"), not(satisfy(is_sql_identifier))),
+    )(i)
+}
 
 /// Parse a list of values (e.g., for INSERT syntax).
-named!(pub value_list<CompleteByteSlice, Vec<Literal> >,
-       many0!(
-           do_parse!(
-               val: literal >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               (val)
-           )
-       )
-);
-
-/// Parse a reference to a named table, with an optional alias
-/// TODO(malte): add support for schema.table notation
-named!(pub table_reference<CompleteByteSlice, Table>,
-    do_parse!(
-        table: sql_identifier >>
-        alias: opt!(as_alias) >>
-        (Table {
-            name: String::from(str::from_utf8(*table).unwrap()),
-            alias: match alias {
-                Some(a) => Some(String::from(a)),
-                None => None,
-            }
-        })
-    )
-);
+pub fn value_list(i: &str) -> IResult<&str, Vec<Literal>> {
+    many0(terminated(literal, opt(ws_sep_comma)))(i)
+}
+
+/// Parse a reference to a named table, possibly qualified by its schema, with an optional alias
+pub fn table_reference(i: &str) -> IResult<&str, Table> {
+    map(pair(table_name, opt(table_as_alias)), |(table, alias)| {
+        Table {
+            alias: alias.as_ref().map(|a| Ident::from(&*a.0)),
+            alias_quote: alias.and_then(|a| a.1),
+            ..table
+        }
+    })(i)
+}
 
 /// Parse rule for a comment part.
-named!(pub parse_comment<CompleteByteSlice, String>,
-    do_parse!(
-        opt_multispace >>
-        tag_no_case!("comment") >>
-        multispace >>
-        comment: delimited!(tag!("'"), take_until!("'"), tag!("'")) >>
-        (String::from(str::from_utf8(*comment).unwrap()))
-    )
-);
+pub fn parse_comment(i: &str) -> IResult<&str, String> {
+    map(
+        preceded(
+            tuple((whitespace0, tag_no_case("comment"), whitespace1)),
+            raw_string_singlequoted,
+        ),
+        String::from,
+    )(i)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use dialect::with_dialect;
 
     #[test]
     fn sql_identifiers() {
-        let id1 = CompleteByteSlice(b"foo");
-        let id2 = CompleteByteSlice(b"f_o_o");
-        let id3 = CompleteByteSlice(b"foo12");
-        let id4 = CompleteByteSlice(b":fo oo");
-        let id5 = CompleteByteSlice(b"primary ");
-        let id6 = CompleteByteSlice(b"`primary`");
+        let id1 = "foo";
+        let id2 = "f_o_o";
+        let id3 = "foo12";
+        let id4 = ":fo oo";
+        let id5 = "primary ";
+        let id6 = "`primary`";
 
         assert!(sql_identifier(id1).is_ok());
         assert!(sql_identifier(id2).is_ok());
@@ -995,35 +2210,107 @@ mod tests {
         assert!(sql_identifier(id6).is_ok());
     }
 
+    #[test]
+    fn quoted_identifiers() {
+        assert_eq!(sql_identifier("`my col`"), Ok(("", Cow::from("my col"))));
+        assert_eq!(sql_identifier("`a``b` x"), Ok((" x", Cow::from("a`b"))));
+        assert_eq!(sql_identifier("[a]]b]"), Ok(("", Cow::from("a]b"))));
+        assert!(sql_identifier("``").is_err());
+        assert_eq!(
+            column_identifier("`my table`.`my col` AS `the col`"),
+            Ok((
+                "",
+                Column {
+                    name: "my col".into(),
+                    alias: Some("the col".into()),
+                    table: Some("my table".into()),
+                    function: None,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn sql_server_types() {
+        assert_eq!(
+            type_identifier("nvarchar(40)").unwrap().1,
+            SqlType::Nvarchar(40)
+        );
+        assert_eq!(type_identifier("NCHAR(2)").unwrap().1, SqlType::Nchar(2));
+        assert!(type_identifier("NVARCHAR(MAX)").is_err());
+        let max = |t| with_dialect(Dialect::SqlServer, || type_identifier(t).unwrap().1);
+        assert_eq!(max("NVARCHAR(MAX)"), SqlType::NvarcharMax);
+        assert_eq!(max("varchar( max )"), SqlType::VarcharMax);
+        assert_eq!(max("VARBINARY(max)"), SqlType::VarbinaryMax);
+        assert_eq!(format!("{}", SqlType::NvarcharMax), "NVARCHAR(MAX)");
+    }
+
     #[test]
     fn sql_types() {
-        let ok = ["bool", "integer(16)", "datetime(16)"];
+        let ok = [
+            "bool",
+            "integer(16)",
+            "datetime(16)",
+            "datetime(6)",
+            "timestamp(3)",
+            "time",
+            "timestamptz",
+            "timestamp(3) with time zone",
+            "TIMESTAMP WITHOUT TIME ZONE",
+            "timetz(6)",
+            "time with time zone",
+        ];
         let not_ok = ["varchar"];
 
-        let res_ok: Vec<_> = ok
-            .iter()
-            .map(|t| type_identifier(CompleteByteSlice(t.as_bytes())).unwrap().1)
-            .collect();
-        let res_not_ok: Vec<_> = not_ok
-            .iter()
-            .map(|t| type_identifier(CompleteByteSlice(t.as_bytes())).is_ok())
-            .collect();
+        let res_ok: Vec<_> = ok.iter().map(|t| type_identifier(t).unwrap().1).collect();
+        let res_not_ok: Vec<_> = not_ok.iter().map(|t| type_identifier(t).is_ok()).collect();
 
         assert_eq!(
             res_ok,
-            vec![SqlType::Bool, SqlType::Int(16), SqlType::DateTime(16)]
+            vec![
+                SqlType::Bool,
+                SqlType::Int(16),
+                SqlType::DateTime(16),
+                SqlType::DateTime(6),
+                SqlType::Timestamp(3),
+                SqlType::Time(0),
+                SqlType::TimestampTz(0),
+                SqlType::TimestampTz(3),
+                SqlType::Timestamp(0),
+                SqlType::TimeTz(6),
+                SqlType::TimeTz(0),
+            ]
         );
+        assert_eq!(
+            SqlType::TimestampTz(3).to_string(),
+            "TIMESTAMP(3) WITH TIME ZONE"
+        );
+        assert_eq!(SqlType::TimeTz(0).to_string(), "TIME WITH TIME ZONE");
+        assert_eq!(SqlType::DateTime(6).to_string(), "DATETIME(6)");
+        assert_eq!(SqlType::Timestamp(0).to_string(), "TIMESTAMP");
 
-        assert!(res_not_ok.into_iter().all(|r| r == false));
+        assert!(res_not_ok.into_iter().all(|r| !r));
+    }
+
+    #[test]
+    fn decimal_precision() {
+        assert_eq!(
+            type_identifier("decimal(10, 2)").unwrap().1,
+            SqlType::Decimal(10, 2)
+        );
+        assert_eq!(
+            type_identifier("numeric(8)").unwrap().1,
+            SqlType::Decimal(8, 0)
+        );
     }
 
     #[test]
     fn simple_column_function() {
-        let qs = b"max(addr_id)";
+        let qs = "max(addr_id)";
 
-        let res = column_identifier(CompleteByteSlice(qs));
+        let res = column_identifier(qs);
         let expected = Column {
-            name: String::from("max(addr_id)"),
+            name: "max(addr_id)".into(),
             alias: None,
             table: None,
             function: Some(Box::new(FunctionExpression::Max(Column::from("addr_id")))),
@@ -1033,32 +2320,157 @@ mod tests {
 
     #[test]
     fn comment_data() {
-        let res = parse_comment(CompleteByteSlice(b" COMMENT 'test'"));
+        let res = parse_comment(" COMMENT 'test'");
         assert_eq!(res.unwrap().1, "test");
+        let res = parse_comment(" COMMENT 'it''s'");
+        assert_eq!(res.unwrap().1, "it's");
+    }
+
+    #[test]
+    fn comments_are_whitespace() {
+        let res = whitespace1(" -- a\n/* b */# c\n\tx");
+        assert_eq!(res, Ok(("x", " -- a\n/* b */# c\n\t")));
+        assert_eq!(whitespace0("x"), Ok(("x", "")));
+        assert!(whitespace1("x").is_err());
+        // neither an arithmetic `--` nor a JSON path operator starts a comment
+        assert!(whitespace1("--1").is_err());
+        assert!(whitespace1("#>'{a}'").is_err());
+        assert!(whitespace1("/* unclosed").is_err());
+        assert_eq!(keyword_whitespace("(a)"), Ok(("(a)", "")));
+        assert!(keyword_whitespace("a").is_err());
     }
 
     #[test]
     fn literal_string_single_backslash_escape() {
-        let all_escaped = br#"\0\'\"\b\n\r\t\Z\\\%\_"#;
-        for quote in [&b"'"[..], &b"\""[..]].iter() {
-            let quoted = &[quote, &all_escaped[..], quote].concat();
-            let res = string_literal(CompleteByteSlice(quoted));
-            let expected = Literal::String("\0\'\"\x7F\n\r\t\x1a\\%_".to_string());
-            assert_eq!(res, Ok((CompleteByteSlice(&b""[..]), expected)));
+        let all_escaped = r#"\0\'\"\b\n\r\t\Z\\\%\_"#;
+        for quote in ["'", "\""].iter() {
+            let quoted = &[quote, all_escaped, quote].concat();
+            let res = string_literal(quoted);
+            // as in MySQL, `\%` and `\_` keep their backslash so that patterns can match them
+            let expected = Literal::String("\0\'\"\x7F\n\r\t\x1a\\\\%\\_".to_string());
+            assert_eq!(res, Ok(("", expected)));
         }
     }
 
     #[test]
     fn literal_string_single_quote() {
-        let res = string_literal(CompleteByteSlice(b"'a''b'"));
+        let res = string_literal("'a''b'");
         let expected = Literal::String("a'b".to_string());
-        assert_eq!(res, Ok((CompleteByteSlice(&b""[..]), expected)));
+        assert_eq!(res, Ok(("", expected)));
+    }
+
+    #[test]
+    fn literal_string_display_escapes() {
+        for value in &["it's", "\\", "\\' OR 1=1 -- ", "a\0b\x1a\n"] {
+            let literal = Literal::from(*value);
+            let sql = literal.to_sql_string();
+            assert_eq!(string_literal(&sql), Ok(("", literal)), "{}", sql);
+        }
+        assert_eq!(Literal::from("\\'").to_string(), r"'\\'''");
+    }
+
+    #[test]
+    fn postgres_string_literals() {
+        fn postgres(i: &str) -> IResult<&str, Literal> {
+            with_dialect(Dialect::Postgres, || string_literal(i))
+        }
+        let string = |s: &str| Ok(("", Literal::from(s)));
+        assert_eq!(postgres(r"'C:\'"), string(r"C:\"));
+        assert_eq!(postgres(r"'C:\path'"), string(r"C:\path"));
+        assert_eq!(postgres("'a''b'"), string("a'b"));
+        assert_eq!(postgres(r"E'a\'b'"), string("a'b"));
+        assert_eq!(postgres(r"e'\\\n\tx'"), string("\\\n\tx"));
+        assert!(string_literal(r"E'a\'b'").is_err());
+        for value in &["it's", "\\", "\\' OR 1=1 -- ", "a\nb"] {
+            let literal = Literal::from(*value);
+            let sql = with_dialect(Dialect::Postgres, || literal.to_string());
+            assert_eq!(postgres(&sql), Ok(("", literal)), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn literal_accessors() {
+        assert_eq!(Literal::Integer(-3).as_i64(), Some(-3));
+        assert_eq!(Literal::from("3").as_i64(), None);
+        assert_eq!(Literal::Integer(2).as_f64(), Some(2.0));
+        let real = Literal::FixedPoint(Real::new(-15, 1));
+        assert_eq!(real.as_f64(), Some(-1.5));
+        let introduced = Literal::Introduced(String::from("utf8mb4"), Box::new("a".into()));
+        assert_eq!(introduced.as_str(), Some("a"));
+        assert_eq!(Literal::Blob(vec![1]).as_bytes(), Some(&[1u8][..]));
+        assert!(Literal::Null.is_null() && Literal::Integer(0).as_str().is_none());
     }
 
     #[test]
     fn literal_string_double_quote() {
-        let res = string_literal(CompleteByteSlice(br#""a""b""#));
+        let res = string_literal(r#""a""b""#);
         let expected = Literal::String(r#"a"b"#.to_string());
-        assert_eq!(res, Ok((CompleteByteSlice(&b""[..]), expected)));
+        assert_eq!(res, Ok(("", expected)));
+    }
+
+    #[test]
+    fn exact_decimal_literals() {
+        let parsed = |i| match float_literal(i).unwrap().1 {
+            Literal::FixedPoint(r) => r,
+            _ => unreachable!(),
+        };
+        assert_eq!(parsed("1.05"), Real::new(105, 2));
+        assert_ne!(parsed("1.05"), parsed("1.5"));
+        assert_ne!(parsed("1.50"), parsed("1.5"));
+        assert_eq!(parsed("-0.5"), Real::new(-5, 1));
+        for text in &[
+            "1.05",
+            "1.50",
+            "-0.5",
+            "0.001",
+            "12345678901234567890.123456789",
+        ] {
+            assert_eq!(parsed(text).to_string(), *text);
+        }
+        assert!(float_literal("1.000000000000000000000000000000000000001").is_err());
+    }
+
+    #[test]
+    fn integer_literal_overflow() {
+        assert_eq!(
+            integer_literal("-9223372036854775808"),
+            Ok(("", Literal::Integer(i64::MIN)))
+        );
+        assert!(integer_literal("9223372036854775808").is_err());
+    }
+
+    #[test]
+    fn charset_introducers_and_hex_literals() {
+        let parsed = |i| literal(i).unwrap().1;
+        assert_eq!(
+            parsed("_utf8mb4'caf\u{e9}'"),
+            Literal::Introduced(String::from("utf8mb4"), Box::new("caf\u{e9}".into()))
+        );
+        assert_eq!(
+            parsed("N'text'"),
+            Literal::NationalString(String::from("text"))
+        );
+        assert_eq!(parsed("0x0aFF"), Literal::Blob(vec![0x0a, 0xff]));
+        let binary = parsed("_binary X'0A1b'");
+        assert_eq!(
+            binary,
+            Literal::Introduced(
+                String::from("binary"),
+                Box::new(Literal::Blob(vec![10, 27]))
+            )
+        );
+        assert_eq!(binary.to_string(), "_binary X'0A1B'");
+        assert_eq!(parsed("_latin1 'a'").to_string(), "_latin1'a'");
+        assert!(hex_literal("X'ABC'").is_err());
+    }
+
+    #[test]
+    fn boolean_literals() {
+        assert_eq!(literal("TRUE"), Ok(("", Literal::Boolean(true))));
+        assert_eq!(literal("false)"), Ok((")", Literal::Boolean(false))));
+        assert!(literal("true_value").is_err());
+        assert_eq!(Literal::Boolean(false).to_string(), "FALSE");
+        // a column that is so named must be quoted
+        assert_eq!(Column::from("true").to_string(), "`true`");
     }
 }
@@ -1,13 +1,17 @@
-use nom::multispace;
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, opt, value};
+use nom::multi::many1;
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
 use std::fmt;
-use std::str;
 
-use common::{opt_multispace, statement_terminator};
+use common::{keyword_whitespace, statement_terminator, whitespace0, whitespace1};
+use keywords::tag_no_case;
 use order::{order_clause, OrderClause};
-use select::{limit_clause, nested_selection, LimitClause, SelectStatement};
+use select::{limit_clause, nested_selection, select_statement, LimitClause, SelectStatement};
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum CompoundSelectOperator {
     Union,
     DistinctUnion,
@@ -18,7 +22,7 @@ pub enum CompoundSelectOperator {
 impl fmt::Display for CompoundSelectOperator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            CompoundSelectOperator::Union => write!(f, "UNION"),
+            CompoundSelectOperator::Union => write!(f, "UNION ALL"),
             CompoundSelectOperator::DistinctUnion => write!(f, "UNION DISTINCT"),
             CompoundSelectOperator::Intersect => write!(f, "INTERSECT"),
             CompoundSelectOperator::Except => write!(f, "EXCEPT"),
@@ -26,90 +30,130 @@ impl fmt::Display for CompoundSelectOperator {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct CompoundSelectStatement {
     pub selects: Vec<(Option<CompoundSelectOperator>, SelectStatement)>,
     pub order: Option<OrderClause>,
     pub limit: Option<LimitClause>,
 }
 
+impl CompoundSelectStatement {
+    /// Whether the `n`th select must be printed in parentheses. An ORDER BY or LIMIT after the
+    /// last select is parsed as part of it, so a select is parenthesized if its own would
+    /// otherwise be taken for the compound's, or the compound's for its own.
+    pub(crate) fn is_bracketed(&self, n: usize) -> bool {
+        if n + 1 == self.selects.len() {
+            self.order.is_some() || self.limit.is_some()
+        } else {
+            let sel = &self.selects[n].1;
+            sel.order.is_some() || sel.limit.is_some()
+        }
+    }
+}
+
 impl fmt::Display for CompoundSelectStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (ref op, ref sel) in &self.selects {
-            if op.is_some() {
-                write!(f, " {}", op.as_ref().unwrap())?;
+        for (n, (op, sel)) in self.selects.iter().enumerate() {
+            if let Some(op) = op {
+                write!(f, " {} ", op)?;
+            }
+            if self.is_bracketed(n) {
+                write!(f, "({})", sel)?;
+            } else {
+                write!(f, "{}", sel)?;
             }
-            write!(f, " {}", sel)?;
         }
-        if self.order.is_some() {
-            write!(f, " {}", self.order.as_ref().unwrap())?;
+        if let Some(ref order) = self.order {
+            write!(f, " {}", order)?;
         }
-        if self.limit.is_some() {
-            write!(f, " {}", self.order.as_ref().unwrap())?;
+        if let Some(ref limit) = self.limit {
+            write!(f, " {}", limit)?;
         }
         Ok(())
     }
 }
 
 /// Parse compound operator
-named!(compound_op<CompleteByteSlice, CompoundSelectOperator>,
-    alt!(
-          do_parse!(
-              tag_no_case!("union") >>
-              distinct: opt!(
-                  preceded!(multispace,
-                            alt!(  map!(tag_no_case!("all"), |_| { false })
-                                          | map!(tag_no_case!("distinct"), |_| { true }))
-                            )) >>
-              (match distinct {
-                  // DISTINCT is the default in both MySQL and SQLite
-                  None => CompoundSelectOperator::DistinctUnion,
-                  Some(d) => {
-                      if d {
-                          CompoundSelectOperator::DistinctUnion
-                      } else {
-                          CompoundSelectOperator::Union
-                      }
-                  },
-              })
-          )
-        | map!(tag_no_case!("intersect"), |_| CompoundSelectOperator::Intersect)
-        | map!(tag_no_case!("except"), |_| CompoundSelectOperator::Except)
-    )
-);
+fn compound_op(i: &str) -> IResult<&str, CompoundSelectOperator> {
+    alt((
+        map(
+            preceded(
+                tag_no_case("union"),
+                opt(preceded(
+                    whitespace1,
+                    alt((
+                        value(false, tag_no_case("all")),
+                        value(true, tag_no_case("distinct")),
+                    )),
+                )),
+            ),
+            |distinct| match distinct {
+                // DISTINCT is the default in both MySQL and SQLite
+                None => CompoundSelectOperator::DistinctUnion,
+                Some(d) => {
+                    if d {
+                        CompoundSelectOperator::DistinctUnion
+                    } else {
+                        CompoundSelectOperator::Union
+                    }
+                }
+            },
+        ),
+        value(CompoundSelectOperator::Intersect, tag_no_case("intersect")),
+        value(CompoundSelectOperator::Except, tag_no_case("except")),
+    ))(i)
+}
+
+/// A selection, either bare or in parentheses, which must then be balanced.
+fn parenthesized_selection(i: &str) -> IResult<&str, SelectStatement> {
+    alt((
+        delimited(
+            pair(tag("("), whitespace0),
+            nested_selection,
+            pair(whitespace0, tag(")")),
+        ),
+        select_statement,
+    ))(i)
+}
+
+fn other_selects(i: &str) -> IResult<&str, (Option<CompoundSelectOperator>, SelectStatement)> {
+    let (remaining_input, (_, op, _, select)) = tuple((
+        whitespace0,
+        compound_op,
+        keyword_whitespace,
+        parenthesized_selection,
+    ))(i)?;
+
+    Ok((remaining_input, (Some(op), select)))
+}
 
 /// Parse compound selection
-named!(pub compound_selection<CompleteByteSlice, CompoundSelectStatement>,
-    do_parse!(
-        first_select: delimited!(opt!(tag!("(")), nested_selection, opt!(tag!(")"))) >>
-        other_selects: many1!(
-            do_parse!(opt_multispace >>
-                    op: compound_op >>
-                    multispace >>
-                    opt!(tag!("(")) >>
-                    opt_multispace >>
-                    select: nested_selection >>
-                    opt_multispace >>
-                    opt!(tag!(")")) >>
-                    (Some(op), select)
-            )
-        ) >>
-        opt_multispace >>
-        order: opt!(order_clause) >>
-        limit: opt!(limit_clause) >>
-        statement_terminator >>
-        ({
-            let mut v = vec![(None, first_select)];
-            v.extend(other_selects);
-
-            CompoundSelectStatement {
-                selects: v,
-                order: order,
-                limit: limit,
-            }
-        })
-    )
-);
+pub fn compound_selection(i: &str) -> IResult<&str, CompoundSelectStatement> {
+    terminated(compound_select, statement_terminator)(i)
+}
+
+/// A compound selection that need not end the statement, as in a common table expression.
+pub(crate) fn compound_select(i: &str) -> IResult<&str, CompoundSelectStatement> {
+    let (remaining_input, (first_select, other_selects, _, order, limit)) = tuple((
+        parenthesized_selection,
+        many1(other_selects),
+        whitespace0,
+        opt(order_clause),
+        opt(limit_clause),
+    ))(i)?;
+
+    let mut selects = vec![(None, first_select)];
+    selects.extend(other_selects);
+
+    Ok((
+        remaining_input,
+        CompoundSelectStatement {
+            selects,
+            order,
+            limit,
+        },
+    ))
+}
 
 #[cfg(test)]
 mod tests {
@@ -122,8 +166,8 @@ mod tests {
     fn union() {
         let qstr = "SELECT id, 1 FROM Vote UNION SELECT id, stars from Rating;";
         let qstr2 = "(SELECT id, 1 FROM Vote) UNION (SELECT id, stars from Rating);";
-        let res = compound_selection(CompleteByteSlice(qstr.as_bytes()));
-        let res2 = compound_selection(CompleteByteSlice(qstr2.as_bytes()));
+        let res = compound_selection(qstr);
+        let res2 = compound_selection(qstr2);
 
         let first_select = SelectStatement {
             tables: vec![Table::from("Vote")],
@@ -161,7 +205,7 @@ mod tests {
         let qstr = "SELECT id, 1 FROM Vote \
                     UNION SELECT id, stars from Rating \
                     UNION DISTINCT SELECT 42, 5 FROM Vote;";
-        let res = compound_selection(CompleteByteSlice(qstr.as_bytes()));
+        let res = compound_selection(qstr);
 
         let first_select = SelectStatement {
             tables: vec![Table::from("Vote")],
@@ -210,7 +254,7 @@ mod tests {
     #[test]
     fn union_all() {
         let qstr = "SELECT id, 1 FROM Vote UNION ALL SELECT id, stars from Rating;";
-        let res = compound_selection(CompleteByteSlice(qstr.as_bytes()));
+        let res = compound_selection(qstr);
 
         let first_select = SelectStatement {
             tables: vec![Table::from("Vote")],
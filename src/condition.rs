@@ -1,31 +1,57 @@
-use nom::multispace;
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, map_opt, not, opt, value, verify};
+use nom::multi::{many0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::IResult;
 use std::collections::{HashSet, VecDeque};
 use std::fmt;
-use std::str;
 
-use arithmetic::{arithmetic_expression, ArithmeticExpression};
-use column::Column;
+use arithmetic::{
+    arithmetic_expression, arithmetic_operator, bit_not, ArithmeticBase, ArithmeticExpression,
+};
+use array::{subscript_expression, SubscriptExpression};
+use column::{Column, FunctionExpression};
 use common::{
-    binary_comparison_operator, column_identifier, literal, opt_multispace, value_list, Literal,
-    Operator,
+    as_alias, binary_comparison_operator, column_identifier, escape_string, escape_user_variable,
+    keyword_phrase, keyword_whitespace, literal, string_literal, user_variable, value_list,
+    whitespace0, whitespace1, ws_sep_comma, Literal, Operator,
 };
-
+use json::{json_expression, JsonExpression};
+use keywords::{escape_if_keyword, tag_no_case};
+use limits::{memoized, nested};
 use select::{nested_selection, SelectStatement};
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum ConditionBase {
     Field(Column),
     Literal(Literal),
     LiteralList(Vec<Literal>),
+    /// The list of an `IN` whose elements are not all literals, as in `x IN (1, now(), y + 1)`.
+    ExpressionList(Vec<ConditionExpression>),
     NestedSelect(Box<SelectStatement>),
+    Variable(String),
+    Json(JsonExpression),
+    Subscript(SubscriptExpression),
+    /// `ANY(...)` over an array or subquery, as in `x = ANY(tags)`.
+    Any(Box<ConditionExpression>),
+    /// A row constructor, `(a, b)` or `ROW(a, b)`, as in `(a, b) > (1, 2)`, and whether it was
+    /// written with the `ROW` keyword. The list of an `IN` over rows, `(a, b) IN ((1, 2), (3, 4))`,
+    /// is a row of rows.
+    Row(Vec<ConditionExpression>, bool),
+    /// A `LIKE` pattern and the character escaping wildcards in it, as in
+    /// `name LIKE 'a\_%' ESCAPE '\'`.
+    Escaped(Box<ConditionExpression>, char),
+    /// Bitwise inversion of an operand standing on its own, `~operand`, as in `~flags = 0`.
+    /// Within an arithmetic expression it is an `ArithmeticBase::BitNot` instead.
+    BitNot(Box<ArithmeticBase>),
 }
 
 impl fmt::Display for ConditionBase {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             ConditionBase::Field(ref col) => write!(f, "{}", col),
-            ConditionBase::Literal(ref literal) => write!(f, "{}", literal.to_string()),
+            ConditionBase::Literal(ref literal) => write!(f, "{}", literal),
             ConditionBase::LiteralList(ref ll) => write!(
                 f,
                 "({})",
@@ -34,12 +60,53 @@ impl fmt::Display for ConditionBase {
                     .collect::<Vec<_>>()
                     .join(", ")
             ),
-            ConditionBase::NestedSelect(ref select) => write!(f, "{}", select),
+            ConditionBase::ExpressionList(ref exprs) => write!(
+                f,
+                "({})",
+                exprs
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ConditionBase::NestedSelect(ref select) => write!(f, "({})", select),
+            ConditionBase::Variable(ref var) => write!(f, "{}", escape_user_variable(var)),
+            ConditionBase::Json(ref json) => write!(f, "{}", json),
+            ConditionBase::Subscript(ref sub) => write!(f, "{}", sub),
+            // a nested select brings its own parentheses
+            ConditionBase::Any(ref expr) => match **expr {
+                ConditionExpression::Base(ConditionBase::NestedSelect(_)) => {
+                    write!(f, "ANY{}", expr)
+                }
+                _ => write!(f, "ANY({})", expr),
+            },
+            ConditionBase::Escaped(ref pattern, escape) => write!(
+                f,
+                "{} ESCAPE '{}'",
+                pattern,
+                escape_string(&escape.to_string())
+            ),
+            ConditionBase::BitNot(ref operand) => write!(f, "~{}", operand),
+            ConditionBase::Row(ref exprs, keyword) => {
+                // a single parenthesized expression is not a row
+                if keyword || exprs.len() == 1 {
+                    write!(f, "ROW")?;
+                }
+                write!(
+                    f,
+                    "({})",
+                    exprs
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct ConditionTree {
     pub operator: Operator,
     pub left: Box<ConditionExpression>,
@@ -51,7 +118,7 @@ impl<'a> ConditionTree {
         let mut s = HashSet::new();
         let mut q = VecDeque::<&'a ConditionTree>::new();
         q.push_back(self);
-        while let Some(ref ct) = q.pop_front() {
+        while let Some(ct) = q.pop_front() {
             match *ct.left.as_ref() {
                 ConditionExpression::Base(ConditionBase::Field(ref c)) => {
                     s.insert(c);
@@ -75,13 +142,21 @@ impl<'a> ConditionTree {
 
 impl fmt::Display for ConditionTree {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // `IS [NOT] NULL` is parsed into an equality with `NULL`
+        if let ConditionExpression::Base(ConditionBase::Literal(Literal::Null)) = *self.right {
+            match self.operator {
+                Operator::Equal => return write!(f, "{} IS NULL", self.left),
+                Operator::NotEqual => return write!(f, "{} IS NOT NULL", self.left),
+                _ => (),
+            }
+        }
         write!(f, "{}", self.left)?;
         write!(f, " {} ", self.operator)?;
         write!(f, "{}", self.right)
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum ConditionExpression {
     ComparisonOp(ConditionTree),
     LogicalOp(ConditionTree),
@@ -91,11 +166,96 @@ pub enum ConditionExpression {
     Bracketed(Box<ConditionExpression>),
 }
 
+impl Clone for ConditionExpression {
+    fn clone(&self) -> Self {
+        // chains of `AND`s or `OR`s nest to the right, and may be too long to copy recursively
+        let mut chain = vec![];
+        let mut expr = self;
+        while let ConditionExpression::LogicalOp(ref tree) = *expr {
+            chain.push((tree.operator.clone(), (*tree.left).clone()));
+            expr = &tree.right;
+        }
+        let mut cloned = match *expr {
+            ConditionExpression::ComparisonOp(ref tree) => {
+                ConditionExpression::ComparisonOp(tree.clone())
+            }
+            ConditionExpression::LogicalOp(_) => unreachable!("followed above"),
+            ConditionExpression::NegationOp(ref expr) => {
+                ConditionExpression::NegationOp(expr.clone())
+            }
+            ConditionExpression::Base(ref base) => ConditionExpression::Base(base.clone()),
+            ConditionExpression::Arithmetic(ref expr) => {
+                ConditionExpression::Arithmetic(expr.clone())
+            }
+            ConditionExpression::Bracketed(ref expr) => {
+                ConditionExpression::Bracketed(expr.clone())
+            }
+        };
+        while let Some((operator, left)) = chain.pop() {
+            cloned = ConditionExpression::LogicalOp(ConditionTree {
+                operator,
+                left: Box::new(left),
+                right: Box::new(cloned),
+            });
+        }
+        cloned
+    }
+}
+
+impl ConditionExpression {
+    /// The aggregate functions used anywhere in this condition, e.g. in a `HAVING` clause.
+    /// Aggregates inside nested selects belong to those selects and are not included.
+    pub fn aggregates(&self) -> Vec<&FunctionExpression> {
+        match *self {
+            ConditionExpression::ComparisonOp(ref tree)
+            | ConditionExpression::LogicalOp(ref tree) => {
+                let mut aggregates = tree.left.aggregates();
+                aggregates.extend(tree.right.aggregates());
+                aggregates
+            }
+            ConditionExpression::NegationOp(ref expr)
+            | ConditionExpression::Bracketed(ref expr)
+            | ConditionExpression::Base(ConditionBase::Any(ref expr))
+            | ConditionExpression::Base(ConditionBase::Escaped(ref expr, _)) => expr.aggregates(),
+            ConditionExpression::Arithmetic(ref expr) => expr.aggregates(),
+            ConditionExpression::Base(ConditionBase::BitNot(ref operand)) => operand.aggregates(),
+            ConditionExpression::Base(ConditionBase::Row(ref exprs, _))
+            | ConditionExpression::Base(ConditionBase::ExpressionList(ref exprs)) => {
+                exprs.iter().flat_map(|e| e.aggregates()).collect()
+            }
+            ConditionExpression::Base(ConditionBase::Field(ref col))
+            | ConditionExpression::Base(ConditionBase::Json(JsonExpression {
+                column: ref col,
+                ..
+            }))
+            | ConditionExpression::Base(ConditionBase::Subscript(SubscriptExpression {
+                column: ref col,
+                ..
+            })) => col.aggregates(),
+            ConditionExpression::Base(_) => vec![],
+        }
+    }
+
+    pub fn contains_aggregate(&self) -> bool {
+        !self.aggregates().is_empty()
+    }
+}
+
 impl fmt::Display for ConditionExpression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             ConditionExpression::ComparisonOp(ref tree) => write!(f, "{}", tree),
-            ConditionExpression::LogicalOp(ref tree) => write!(f, "{}", tree),
+            ConditionExpression::LogicalOp(ref tree) => {
+                // parsed chains nest to the right, and may be too long to print recursively
+                let mut tree = tree;
+                loop {
+                    write!(f, "{} {} ", tree.left, tree.operator)?;
+                    match *tree.right {
+                        ConditionExpression::LogicalOp(ref right) => tree = right,
+                        ref right => return write!(f, "{}", right),
+                    }
+                }
+            }
             ConditionExpression::NegationOp(ref expr) => write!(f, "NOT {}", expr),
             ConditionExpression::Bracketed(ref expr) => write!(f, "({})", expr),
             ConditionExpression::Base(ref base) => write!(f, "{}", base),
@@ -104,208 +264,439 @@ impl fmt::Display for ConditionExpression {
     }
 }
 
+impl ConditionExpression {
+    /// Whether this is a comparison or logical combination that yields a boolean, as opposed to
+    /// a plain operand such as a column or an arithmetic expression.
+    pub fn is_predicate(&self) -> bool {
+        match *self {
+            ConditionExpression::ComparisonOp(_)
+            | ConditionExpression::LogicalOp(_)
+            | ConditionExpression::NegationOp(_) => true,
+            ConditionExpression::Bracketed(ref expr) => expr.is_predicate(),
+            ConditionExpression::Base(_) | ConditionExpression::Arithmetic(_) => false,
+        }
+    }
+}
+
+/// A boolean-valued condition projected by a select list, e.g. `age >= 18 AS adult`, or a lone
+/// `~operand`, which only the condition grammar takes.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct PredicateExpression {
+    pub condition: ConditionExpression,
+    pub alias: Option<String>,
+}
+
+impl fmt::Display for PredicateExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.condition)?;
+        if let Some(ref alias) = self.alias {
+            write!(f, " AS {}", escape_if_keyword(alias))?;
+        }
+        Ok(())
+    }
+}
+
+/// Chains operands with a logical operator, grouped to the right: `a AND b AND c` becomes
+/// `a AND (b AND c)`.
+fn logical_chain(
+    operator: Operator,
+    (first, mut rest): (ConditionExpression, Vec<ConditionExpression>),
+) -> ConditionExpression {
+    let tree = |left, right| {
+        ConditionExpression::LogicalOp(ConditionTree {
+            operator: operator.clone(),
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    };
+    match rest.pop() {
+        Some(last) => {
+            let right = rest
+                .into_iter()
+                .rev()
+                .fold(last, |right, left| tree(left, right));
+            tree(first, right)
+        }
+        None => first,
+    }
+}
+
+/// Parses a predicate followed by an optional alias. Plain operands other than `~operand` are
+/// rejected, so that they are parsed as the columns, literals or arithmetic they are.
+pub fn predicate_expression(i: &str) -> IResult<&str, PredicateExpression> {
+    map(
+        pair(
+            verify(condition_expr, |cond| {
+                cond.is_predicate()
+                    || matches!(*cond, ConditionExpression::Base(ConditionBase::BitNot(_)))
+            }),
+            opt(as_alias),
+        ),
+        |(condition, alias)| PredicateExpression {
+            condition,
+            alias: alias.map(String::from),
+        },
+    )(i)
+}
+
+/// Recognizes what turns the operand before it into a predicate: a comparison operator, `IS`,
+/// `NOT IN`, or the `AND` or `OR` that joins it to another condition.
+pub fn predicate_continuation(i: &str) -> IResult<&str, ()> {
+    preceded(
+        whitespace0,
+        alt((
+            value((), binary_comparison_operator),
+            keyword_phrase(&["is"]),
+            keyword_phrase(&["not", "in"]),
+            keyword_phrase(&["and"]),
+            keyword_phrase(&["or"]),
+        )),
+    )(i)
+}
+
 /// Parse a conditional expression into a condition tree structure
-named!(pub condition_expr<CompleteByteSlice, ConditionExpression>,
-       alt!(
-           do_parse!(
-               left: and_expr >>
-               opt_multispace >>
-               tag_no_case!("or") >>
-               multispace >>
-               right: condition_expr >>
-               (ConditionExpression::LogicalOp(
-                   ConditionTree {
-                       operator: Operator::Or,
-                       left: Box::new(left),
-                       right: Box::new(right),
-                   }
-               ))
-           )
-       |   and_expr)
-);
-
-named!(pub and_expr<CompleteByteSlice, ConditionExpression>,
-       alt!(
-           do_parse!(
-               left: parenthetical_expr >>
-               opt_multispace >>
-               tag_no_case!("and") >>
-               multispace >>
-               right: and_expr >>
-               (ConditionExpression::LogicalOp(
-                   ConditionTree {
-                       operator: Operator::And,
-                       left: Box::new(left),
-                       right: Box::new(right),
-                   }
-               ))
-           )
-       |   parenthetical_expr)
-);
-
-named!(pub parenthetical_expr<CompleteByteSlice, ConditionExpression>,
-       alt!(
-           do_parse!(
-                tag!("(") >>
-                opt_multispace >>
-                left: simple_expr >>
-                opt_multispace >>
-                tag!(")") >>
-                opt_multispace >>
-                op: binary_comparison_operator >>
-                opt_multispace >>
-                right: simple_expr >>
-                (ConditionExpression::ComparisonOp(
-                    ConditionTree {
-                        operator: op,
-                        left: Box::new(
-                            ConditionExpression::Bracketed(Box::new(left))
+pub fn condition_expr(i: &str) -> IResult<&str, ConditionExpression> {
+    // the same text is tried as the operand of several kinds of expression
+    memoized(or_expr, i)
+}
+
+fn or_expr(i: &str) -> IResult<&str, ConditionExpression> {
+    // Operands are parsed in a loop, each exactly once: re-parsing an operand when no operator
+    // follows takes exponential time in the nesting depth, and recursing for each operand
+    // overflows the stack on long chains.
+    map(
+        pair(
+            and_expr,
+            many0(preceded(
+                tuple((whitespace0, tag_no_case("or"), keyword_whitespace)),
+                and_expr,
+            )),
+        ),
+        |operands| logical_chain(Operator::Or, operands),
+    )(i)
+}
+
+pub fn and_expr(i: &str) -> IResult<&str, ConditionExpression> {
+    map(
+        pair(
+            parenthetical_expr,
+            many0(preceded(
+                tuple((whitespace0, tag_no_case("and"), keyword_whitespace)),
+                parenthetical_expr,
+            )),
+        ),
+        |operands| logical_chain(Operator::And, operands),
+    )(i)
+}
+
+fn parenthetical_expr_helper(i: &str) -> IResult<&str, ConditionExpression> {
+    let (remaining_input, (_, _, left, _, _, _, operator, _, right)) = tuple((
+        tag("("),
+        whitespace0,
+        simple_expr,
+        whitespace0,
+        tag(")"),
+        whitespace0,
+        binary_comparison_operator,
+        whitespace0,
+        simple_expr,
+    ))(i)?;
+
+    let left = Box::new(ConditionExpression::Bracketed(Box::new(left)));
+    let right = Box::new(right);
+    let cond = ConditionExpression::ComparisonOp(ConditionTree {
+        operator,
+        left,
+        right,
+    });
+
+    Ok((remaining_input, cond))
+}
+
+pub fn parenthetical_expr(i: &str) -> IResult<&str, ConditionExpression> {
+    alt((
+        nested(
+            tag("("),
+            alt((
+                parenthetical_expr_helper,
+                // a parenthesized operand of an arithmetic expression, as in `(a + b) * c = 0`, is
+                // left to `boolean_primary`
+                map(
+                    terminated(
+                        delimited(
+                            pair(tag("("), whitespace0),
+                            condition_expr,
+                            pair(whitespace0, tag(")")),
                         ),
-                        right: Box::new(right)
-                    })
-                )
-            )
-        |    map!(
-               delimited!(
-                   do_parse!(tag!("(") >> opt_multispace >> ()),
-                   condition_expr,
-                   do_parse!(opt_multispace >> tag!(")") >> opt_multispace >> ())
-               ),
-               |inner| (ConditionExpression::Bracketed(Box::new(inner)))
-            )
-       |   not_expr)
-);
-
-named!(pub not_expr<CompleteByteSlice, ConditionExpression>,
-       alt!(
-           do_parse!(
-               tag_no_case!("not") >>
-               multispace >>
-               right: parenthetical_expr >>
-               (ConditionExpression::NegationOp(Box::new(right)))
-           )
-       |   boolean_primary)
-);
-
-named!(boolean_primary<CompleteByteSlice, ConditionExpression>,
-    alt!(
-        do_parse!(
-            left: predicate >>
-            opt_multispace >>
-            rest: alt!(
-                do_parse!(tag_no_case!("is") >>
-                          opt_multispace >>
-                          not: opt!(tag_no_case!("not")) >>
-                          opt_multispace >>
-                          tag_no_case!("null") >>
-                          (
-                              // XXX(malte): bit of a hack; would consumers ever need to know
-                              // about "IS NULL" vs. "= NULL"?
-                              if not.is_some() {
-                                  Operator::NotEqual
-                              } else {
-                                  Operator::Equal
-                              },
-                              ConditionExpression::Base(
-                                  ConditionBase::Literal(Literal::Null)
-                              )
-                          )
-
-                ) |
-                do_parse!(op: binary_comparison_operator >>
-                          opt_multispace >>
-                          right: predicate >>
-                          (op, right)
-                )
-            ) >>
-            (ConditionExpression::ComparisonOp(
-                    ConditionTree {
-                        operator: rest.0,
-                        left: Box::new(left),
-                        right: Box::new(rest.1),
-                    })
-            )
-        ) |
-        predicate
-    )
-);
-
-named!(predicate<CompleteByteSlice, ConditionExpression>,
-    do_parse!(
-        left: simple_expr >>
-        op_right: opt!(
-            alt!(
-                  do_parse!(
-                      neg: opt!(preceded!(opt_multispace, tag_no_case!("not"))) >>
-                      multispace >>
-                      tag_no_case!("in") >>
-                      multispace >>
-                      sq: nested_selection >>
-                      (ConditionExpression::Base(ConditionBase::NestedSelect(Box::new(sq))))
-                  )
-                | do_parse!(
-                      neg: opt!(preceded!(opt_multispace, tag_no_case!("not"))) >>
-                      multispace >>
-                      tag_no_case!("in") >>
-                      multispace >>
-                      vl: delimited!(tag!("("), value_list, tag!(")")) >>
-                      (ConditionExpression::Base(ConditionBase::LiteralList(vl)))
-                  )
-            )
-        ) >>
-        (match op_right {
-            Some(right) => ConditionExpression::ComparisonOp(
-                ConditionTree {
-                    operator: Operator::In,
-                    left: Box::new(left),
-                    right: Box::new(right),
-                }),
+                        not(preceded(whitespace0, arithmetic_operator)),
+                    ),
+                    |inner| ConditionExpression::Bracketed(Box::new(inner)),
+                ),
+            )),
+        ),
+        not_expr,
+    ))(i)
+}
+
+pub fn not_expr(i: &str) -> IResult<&str, ConditionExpression> {
+    alt((
+        map(
+            nested(
+                tag_no_case("not"),
+                preceded(
+                    pair(tag_no_case("not"), keyword_whitespace),
+                    parenthetical_expr,
+                ),
+            ),
+            |right| ConditionExpression::NegationOp(Box::new(right)),
+        ),
+        boolean_primary,
+    ))(i)
+}
+
+fn is_null(i: &str) -> IResult<&str, (Operator, ConditionExpression)> {
+    let (remaining_input, (_, _, not, _, _)) = tuple((
+        tag_no_case("is"),
+        whitespace0,
+        opt(tag_no_case("not")),
+        whitespace0,
+        tag_no_case("null"),
+    ))(i)?;
+
+    // XXX(malte): bit of a hack; would consumers ever need to know
+    // about "IS NULL" vs. "= NULL"?
+    let operator = if not.is_some() {
+        Operator::NotEqual
+    } else {
+        Operator::Equal
+    };
+    Ok((
+        remaining_input,
+        (
+            operator,
+            ConditionExpression::Base(ConditionBase::Literal(Literal::Null)),
+        ),
+    ))
+}
+
+/// The character given by `ESCAPE 'c'`. A lone backslash is taken as `'\'`, as Postgres and
+/// most examples write it, even though MySQL would read it as an escaped quote.
+fn escape_clause(i: &str) -> IResult<&str, char> {
+    preceded(
+        tuple((whitespace1, tag_no_case("escape"), whitespace0)),
+        alt((
+            value('\\', tag("'\\'")),
+            map_opt(string_literal, |s| match s {
+                Literal::String(ref s) if s.chars().count() == 1 => s.chars().next(),
+                _ => None,
+            }),
+        )),
+    )(i)
+}
+
+/// A comparison, whose right side is a pattern with an escape character if the operator is a
+/// `LIKE` or `SIMILAR TO`.
+fn comparison(i: &str) -> IResult<&str, (Operator, ConditionExpression)> {
+    let (remaining_input, (operator, right)) =
+        separated_pair(binary_comparison_operator, whitespace0, predicate)(i)?;
+    let takes_escape = matches!(
+        operator,
+        Operator::Like
+            | Operator::NotLike
+            | Operator::ILike
+            | Operator::NotILike
+            | Operator::SimilarTo
+            | Operator::NotSimilarTo
+    );
+    if takes_escape {
+        if let Ok((remaining_input, escape)) = escape_clause(remaining_input) {
+            let pattern = ConditionBase::Escaped(Box::new(right), escape);
+            return Ok((
+                remaining_input,
+                (operator, ConditionExpression::Base(pattern)),
+            ));
+        }
+    }
+    Ok((remaining_input, (operator, right)))
+}
+
+fn boolean_primary_rest(i: &str) -> IResult<&str, (Operator, ConditionExpression)> {
+    alt((is_null, comparison))(i)
+}
+
+fn boolean_primary(i: &str) -> IResult<&str, ConditionExpression> {
+    map(
+        pair(predicate, opt(preceded(whitespace0, boolean_primary_rest))),
+        |(left, rest)| match rest {
+            Some((operator, right)) => ConditionExpression::ComparisonOp(ConditionTree {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+            }),
             None => left,
-        })
-    )
-);
-
-named!(simple_expr<CompleteByteSlice, ConditionExpression>,
-    alt!(
-            do_parse!(
-                arit_expr: arithmetic_expression >>
-                (ConditionExpression::Arithmetic(Box::new(arit_expr)))
-            )
-        |   do_parse!(
-                tag!("(") >>
-                opt_multispace >>
-                arit_expr: arithmetic_expression >>
-                opt_multispace >>
-                tag!(")") >>
-                (ConditionExpression::Bracketed(Box::new(
-                    ConditionExpression::Arithmetic(Box::new(arit_expr))
-                )))
-            )
-        |    do_parse!(
-                lit: literal >>
-                (ConditionExpression::Base(ConditionBase::Literal(lit)))
-            )
-        |   do_parse!(
-                field: column_identifier >>
-                (ConditionExpression::Base(
-                    ConditionBase::Field(field)
-                ))
-            )
-        |   do_parse!(
-                select: delimited!(tag!("("), nested_selection, tag!(")")) >>
-                (ConditionExpression::Base(
-                    ConditionBase::NestedSelect(Box::new(select))
-                ))
-            )
-    )
-);
+        },
+    )(i)
+}
+
+fn in_operation(i: &str) -> IResult<&str, (Operator, ConditionExpression)> {
+    pair(
+        map(
+            tuple((
+                opt(preceded(whitespace0, tag_no_case("not"))),
+                whitespace1,
+                tag_no_case("in"),
+                keyword_whitespace,
+            )),
+            |(not, _, _, _)| match not {
+                Some(_) => Operator::NotIn,
+                None => Operator::In,
+            },
+        ),
+        alt((
+            map(nested_selection, |sq| {
+                ConditionExpression::Base(ConditionBase::NestedSelect(Box::new(sq)))
+            }),
+            map(
+                delimited(
+                    pair(tag("("), whitespace0),
+                    value_list,
+                    pair(whitespace0, tag(")")),
+                ),
+                |vl| ConditionExpression::Base(ConditionBase::LiteralList(vl)),
+            ),
+            map(
+                delimited(
+                    pair(tag("("), whitespace0),
+                    separated_list1(ws_sep_comma, row),
+                    pair(whitespace0, tag(")")),
+                ),
+                |rows| ConditionExpression::Base(ConditionBase::Row(rows, false)),
+            ),
+            map(
+                delimited(
+                    pair(tag("("), whitespace0),
+                    separated_list1(ws_sep_comma, simple_expr),
+                    pair(whitespace0, tag(")")),
+                ),
+                |exprs| ConditionExpression::Base(ConditionBase::ExpressionList(exprs)),
+            ),
+        )),
+    )(i)
+}
+
+/// A row constructor: `ROW(...)`, or a parenthesized list of at least two expressions.
+fn row(i: &str) -> IResult<&str, ConditionExpression> {
+    let elements = |min| {
+        delimited(
+            pair(tag("("), whitespace0),
+            verify(
+                separated_list1(ws_sep_comma, simple_expr),
+                move |e: &[_]| e.len() >= min,
+            ),
+            pair(whitespace0, tag(")")),
+        )
+    };
+    map(
+        nested(
+            alt((tag_no_case("row"), tag("("))),
+            alt((
+                map(
+                    preceded(pair(tag_no_case("row"), whitespace0), elements(1)),
+                    |exprs| (exprs, true),
+                ),
+                map(elements(2), |exprs| (exprs, false)),
+            )),
+        ),
+        |(exprs, keyword)| ConditionExpression::Base(ConditionBase::Row(exprs, keyword)),
+    )(i)
+}
+
+fn predicate(i: &str) -> IResult<&str, ConditionExpression> {
+    map(
+        pair(simple_expr, opt(in_operation)),
+        |(left, op_right)| match op_right {
+            Some((operator, right)) => ConditionExpression::ComparisonOp(ConditionTree {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+            }),
+            None => left,
+        },
+    )(i)
+}
+
+fn simple_expr(i: &str) -> IResult<&str, ConditionExpression> {
+    alt((
+        map(json_expression, |json| {
+            ConditionExpression::Base(ConditionBase::Json(json))
+        }),
+        map(subscript_expression, |sub| {
+            ConditionExpression::Base(ConditionBase::Subscript(sub))
+        }),
+        map(
+            nested(
+                tag_no_case("any"),
+                preceded(
+                    pair(tag_no_case("any"), whitespace0),
+                    delimited(
+                        pair(tag("("), whitespace0),
+                        alt((
+                            map(nested_selection, |s| {
+                                ConditionExpression::Base(ConditionBase::NestedSelect(Box::new(s)))
+                            }),
+                            simple_expr,
+                        )),
+                        pair(whitespace0, tag(")")),
+                    ),
+                ),
+            ),
+            |expr| ConditionExpression::Base(ConditionBase::Any(Box::new(expr))),
+        ),
+        map(arithmetic_expression, |ae| {
+            ConditionExpression::Arithmetic(Box::new(ae))
+        }),
+        map(
+            delimited(
+                pair(tag("("), whitespace0),
+                arithmetic_expression,
+                pair(whitespace0, tag(")")),
+            ),
+            |ae| {
+                ConditionExpression::Bracketed(Box::new(ConditionExpression::Arithmetic(Box::new(
+                    ae,
+                ))))
+            },
+        ),
+        map(bit_not, |operand| {
+            ConditionExpression::Base(ConditionBase::BitNot(Box::new(operand)))
+        }),
+        map(literal, |lit| {
+            ConditionExpression::Base(ConditionBase::Literal(lit))
+        }),
+        map(user_variable, |v| {
+            ConditionExpression::Base(ConditionBase::Variable(String::from(v)))
+        }),
+        map(column_identifier, |f| {
+            ConditionExpression::Base(ConditionBase::Field(f))
+        }),
+        map(
+            delimited(
+                pair(tag("("), whitespace0),
+                nested_selection,
+                pair(whitespace0, tag(")")),
+            ),
+            |s| ConditionExpression::Base(ConditionBase::NestedSelect(Box::new(s))),
+        ),
+        row,
+    ))(i)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use arithmetic::{ArithmeticBase, ArithmeticOperator};
     use column::Column;
     use common::{FieldDefinitionExpression, Literal, Operator};
-    use arithmetic::{ArithmeticBase, ArithmeticOperator};
 
     fn columns(cols: &[&str]) -> Vec<FieldDefinitionExpression> {
         cols.iter()
@@ -331,7 +722,7 @@ mod tests {
 
         let cond = "a.foo = ? and b.bar = 42";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         let c1 = Column::from("a.foo");
         let c2 = Column::from("b.bar");
         let mut expected_cols = HashSet::new();
@@ -349,7 +740,7 @@ mod tests {
     fn equality_placeholder() {
         let cond = "foo = ?";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(
             res.unwrap().1,
             flat_condition_tree(
@@ -361,21 +752,18 @@ mod tests {
     }
 
     fn x_operator_value(op: ArithmeticOperator, value: Literal) -> ConditionExpression {
-        ConditionExpression::Arithmetic(
-            Box::new(ArithmeticExpression::new(
-                op,
-                ArithmeticBase::Column(Column::from("x")),
-                ArithmeticBase::Scalar(value),
-                None
-                )
-            )
-        )
+        ConditionExpression::Arithmetic(Box::new(ArithmeticExpression::new(
+            op,
+            ArithmeticBase::Column(Column::from("x")),
+            ArithmeticBase::Scalar(value),
+            None,
+        )))
     }
     #[test]
     fn simple_arithmetic_expression() {
         let cond = "x + 3";
 
-        let res = simple_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = simple_expr(cond);
         assert_eq!(
             res.unwrap().1,
             x_operator_value(ArithmeticOperator::Add, 3.into())
@@ -386,12 +774,13 @@ mod tests {
     fn simple_arithmetic_expression_with_parenthesis() {
         let cond = "( x - 2 )";
 
-        let res = simple_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = simple_expr(cond);
         assert_eq!(
             res.unwrap().1,
-            ConditionExpression::Bracketed(Box::new(
-                x_operator_value(ArithmeticOperator::Subtract, 2.into())
-            ))
+            ConditionExpression::Bracketed(Box::new(x_operator_value(
+                ArithmeticOperator::Subtract,
+                2.into()
+            )))
         );
     }
 
@@ -399,12 +788,13 @@ mod tests {
     fn parenthetical_arithmetic_expression() {
         let cond = "( x * 5 )";
 
-        let res = parenthetical_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = parenthetical_expr(cond);
         assert_eq!(
             res.unwrap().1,
-            ConditionExpression::Bracketed(Box::new(
-                x_operator_value(ArithmeticOperator::Multiply, 5.into())
-            ))
+            ConditionExpression::Bracketed(Box::new(x_operator_value(
+                ArithmeticOperator::Multiply,
+                5.into()
+            )))
         );
     }
 
@@ -412,38 +802,226 @@ mod tests {
     fn condition_expression_with_arithmetics() {
         let cond = "x * 3 = 21";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(
             res.unwrap().1,
             ConditionExpression::ComparisonOp(ConditionTree {
                 operator: Operator::Equal,
-                left: Box::new(
-                    x_operator_value(ArithmeticOperator::Multiply, 3.into())
+                left: Box::new(x_operator_value(ArithmeticOperator::Multiply, 3.into())),
+                right: Box::new(ConditionExpression::Base(ConditionBase::Literal(21.into())))
+            })
+        );
+    }
+    #[test]
+    fn condition_expression_with_bitwise_mask() {
+        let cond = "flags & 4 = 4 AND x >> 1 > 2";
+
+        let res = condition_expr(cond).unwrap().1;
+        let mask = ConditionExpression::ComparisonOp(ConditionTree {
+            operator: Operator::Equal,
+            left: Box::new(ConditionExpression::Arithmetic(Box::new(
+                ArithmeticExpression::new(
+                    ArithmeticOperator::BitAnd,
+                    ArithmeticBase::Column(Column::from("flags")),
+                    ArithmeticBase::Scalar(4.into()),
+                    None,
                 ),
-                right: Box::new(
-                    ConditionExpression::Base(ConditionBase::Literal(21.into()))
-                )
+            ))),
+            right: Box::new(ConditionExpression::Base(ConditionBase::Literal(4.into()))),
+        });
+        match res {
+            ConditionExpression::LogicalOp(ref tree) => assert_eq!(*tree.left, mask),
+            ref c => panic!("unexpected condition {:?}", c),
+        }
+        assert_eq!(res.to_string(), cond);
+    }
+
+    #[test]
+    fn pattern_matching_operators() {
+        let ops = [
+            ("name REGEXP '^a'", Operator::Regexp, "name REGEXP '^a'"),
+            ("name rlike '^a'", Operator::Regexp, "name REGEXP '^a'"),
+            (
+                "name NOT REGEXP '^a'",
+                Operator::NotRegexp,
+                "name NOT REGEXP '^a'",
+            ),
+            ("name ~ '^a'", Operator::Match, "name ~ '^a'"),
+            ("name~*'^a'", Operator::MatchInsensitive, "name ~* '^a'"),
+            ("name !~ '^a'", Operator::NotMatch, "name !~ '^a'"),
+            (
+                "name !~* '^a'",
+                Operator::NotMatchInsensitive,
+                "name !~* '^a'",
+            ),
+            (
+                "name SIMILAR TO '%a%'",
+                Operator::SimilarTo,
+                "name SIMILAR TO '%a%'",
+            ),
+            (
+                "name not similar  to '%a%'",
+                Operator::NotSimilarTo,
+                "name NOT SIMILAR TO '%a%'",
+            ),
+        ];
+        for &(cond, ref op, display) in &ops {
+            let res = condition_expr(cond).unwrap().1;
+            assert_eq!(
+                res,
+                flat_condition_tree(
+                    op.clone(),
+                    ConditionBase::Field(Column::from("name")),
+                    ConditionBase::Literal(Literal::String(String::from(
+                        &display[display.find('\'').unwrap() + 1..display.len() - 1]
+                    )))
+                ),
+                "{}",
+                cond
+            );
+            assert_eq!(res.to_string(), display);
+        }
+        // bitwise inversion is not a match
+        let res = condition_expr("~flags & 1 = 0").unwrap().1;
+        assert_eq!(res.to_string(), "~flags & 1 = 0");
+    }
+
+    #[test]
+    fn full_text_search() {
+        let cond = "to_tsvector('english', body) @@ to_tsquery('english', 'fat & rat')";
+        let res = condition_expr(cond).unwrap().1;
+        match res {
+            ConditionExpression::ComparisonOp(ref tree) => {
+                assert_eq!(tree.operator, Operator::TextSearchMatch);
+                match *tree.right {
+                    ConditionExpression::Base(ConditionBase::Field(ref col)) => assert_eq!(
+                        col.function,
+                        Some(Box::new(FunctionExpression::Call(
+                            String::from("to_tsquery"),
+                            vec![
+                                ConditionExpression::Base(ConditionBase::Literal("english".into())),
+                                ConditionExpression::Base(ConditionBase::Literal(
+                                    "fat & rat".into()
+                                )),
+                            ]
+                        )))
+                    ),
+                    ref c => panic!("unexpected operand {:?}", c),
+                }
+            }
+            ref c => panic!("unexpected condition {:?}", c),
+        }
+        assert_eq!(res.to_string(), cond);
+        let res = condition_expr("tsv@@q AND id = 1").unwrap().1;
+        assert_eq!(res.to_string(), "tsv @@ q AND id = 1");
+    }
+
+    #[test]
+    fn in_list_of_expressions() {
+        let res = condition_expr("x IN (-1, ?, 'a')").unwrap().1;
+        assert_eq!(
+            res,
+            flat_condition_tree(
+                Operator::In,
+                ConditionBase::Field(Column::from("x")),
+                ConditionBase::LiteralList(vec![(-1).into(), Literal::Placeholder, "a".into()])
+            )
+        );
+
+        let res = condition_expr("x IN (1, coalesce(y, ?), z + 1)").unwrap().1;
+        let coalesce = FunctionExpression::Call(
+            String::from("coalesce"),
+            vec![
+                ConditionExpression::Base(ConditionBase::Field(Column::from("y"))),
+                ConditionExpression::Base(ConditionBase::Literal(Literal::Placeholder)),
+            ],
+        );
+        assert_eq!(
+            res,
+            ConditionExpression::ComparisonOp(ConditionTree {
+                operator: Operator::In,
+                left: Box::new(ConditionExpression::Base(ConditionBase::Field(
+                    Column::from("x")
+                ))),
+                right: Box::new(ConditionExpression::Base(ConditionBase::ExpressionList(
+                    vec![
+                        ConditionExpression::Base(ConditionBase::Literal(1.into())),
+                        ConditionExpression::Base(ConditionBase::Field(Column {
+                            name: "coalesce(y, ?)".into(),
+                            function: Some(Box::new(coalesce)),
+                            ..Default::default()
+                        })),
+                        ConditionExpression::Arithmetic(Box::new(ArithmeticExpression::new(
+                            ArithmeticOperator::Add,
+                            ArithmeticBase::Column(Column::from("z")),
+                            ArithmeticBase::Scalar(1.into()),
+                            None,
+                        ))),
+                    ]
+                ))),
             })
         );
+        assert_eq!(res.to_string(), "x IN (1, coalesce(y, ?), z + 1)");
+        assert_eq!(
+            condition_expr("x IN (now())").unwrap().1.to_string(),
+            "x IN (now())"
+        );
     }
+
+    #[test]
+    fn like_with_escape_and_ilike() {
+        let res = condition_expr("name LIKE '%x\\_%' ESCAPE '\\' AND a = 1")
+            .unwrap()
+            .1;
+        let expected = ConditionExpression::ComparisonOp(ConditionTree {
+            operator: Operator::Like,
+            left: Box::new(ConditionExpression::Base(ConditionBase::Field(
+                Column::from("name"),
+            ))),
+            right: Box::new(ConditionExpression::Base(ConditionBase::Escaped(
+                Box::new(ConditionExpression::Base(ConditionBase::Literal(
+                    Literal::String(String::from("%x\\_%")),
+                ))),
+                '\\',
+            ))),
+        });
+        match res {
+            ConditionExpression::LogicalOp(ref tree) => assert_eq!(*tree.left, expected),
+            ref c => panic!("unexpected condition {:?}", c),
+        }
+        assert_eq!(expected.to_string(), "name LIKE '%x\\\\_%' ESCAPE '\\\\'");
+        assert_eq!(condition_expr(&expected.to_string()).unwrap().1, expected);
+
+        let res = condition_expr("name NOT LIKE 'a!%' ESCAPE '!'").unwrap().1;
+        assert_eq!(res.to_string(), "name NOT LIKE 'a!%' ESCAPE '!'");
+
+        let res = condition_expr("name ilike 'a%'").unwrap().1;
+        assert_eq!(
+            res,
+            flat_condition_tree(
+                Operator::ILike,
+                ConditionBase::Field(Column::from("name")),
+                ConditionBase::Literal(Literal::from("a%"))
+            )
+        );
+        let res = condition_expr("name NOT ILIKE ?").unwrap().1;
+        assert_eq!(res.to_string(), "name NOT ILIKE ?");
+    }
+
     #[test]
     fn condition_expression_with_arithmetics_and_parenthesis() {
         let cond = "(x - 7 = 15)";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(
             res.unwrap().1,
-            ConditionExpression::Bracketed(Box::new(
-                ConditionExpression::ComparisonOp(ConditionTree {
+            ConditionExpression::Bracketed(Box::new(ConditionExpression::ComparisonOp(
+                ConditionTree {
                     operator: Operator::Equal,
-                    left: Box::new(
-                        x_operator_value(ArithmeticOperator::Subtract, 7.into())
-                    ),
-                    right: Box::new(
-                        ConditionExpression::Base(ConditionBase::Literal(15.into()))
-                    )
-                })
-            ))
+                    left: Box::new(x_operator_value(ArithmeticOperator::Subtract, 7.into())),
+                    right: Box::new(ConditionExpression::Base(ConditionBase::Literal(15.into())))
+                }
+            )))
         );
     }
 
@@ -451,19 +1029,16 @@ mod tests {
     fn condition_expression_with_arithmetics_in_parenthesis() {
         let cond = "( x + 2) = 15";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(
             res.unwrap().1,
             ConditionExpression::ComparisonOp(ConditionTree {
                 operator: Operator::Equal,
-                left: Box::new(
-                    ConditionExpression::Bracketed(Box::new(
-                        x_operator_value(ArithmeticOperator::Add, 2.into())
-                    ))
-                ),
-                right: Box::new(
-                    ConditionExpression::Base(ConditionBase::Literal(15.into()))
-                )
+                left: Box::new(ConditionExpression::Bracketed(Box::new(x_operator_value(
+                    ArithmeticOperator::Add,
+                    2.into()
+                )))),
+                right: Box::new(ConditionExpression::Base(ConditionBase::Literal(15.into())))
             })
         );
     }
@@ -472,43 +1047,39 @@ mod tests {
     fn condition_expression_with_arithmetics_in_parenthesis_in_both_side() {
         let cond = "( x + 2) =(x*3)";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(
             res.unwrap().1,
             ConditionExpression::ComparisonOp(ConditionTree {
                 operator: Operator::Equal,
-                left: Box::new(
-                    ConditionExpression::Bracketed(Box::new(
-                        x_operator_value(ArithmeticOperator::Add, 2.into())
-                    ))
-                ),
-                right: Box::new(
-                    ConditionExpression::Bracketed(Box::new(
-                        x_operator_value(ArithmeticOperator::Multiply, 3.into())
-                    ))
-                )
+                left: Box::new(ConditionExpression::Bracketed(Box::new(x_operator_value(
+                    ArithmeticOperator::Add,
+                    2.into()
+                )))),
+                right: Box::new(ConditionExpression::Bracketed(Box::new(x_operator_value(
+                    ArithmeticOperator::Multiply,
+                    3.into()
+                ))))
             })
         );
     }
 
-
-
     #[test]
     fn equality_literals() {
         let cond1 = "foo = 42";
         let cond2 = "foo = \"hello\"";
 
-        let res1 = condition_expr(CompleteByteSlice(cond1.as_bytes()));
+        let res1 = condition_expr(cond1);
         assert_eq!(
             res1.unwrap().1,
             flat_condition_tree(
                 Operator::Equal,
                 ConditionBase::Field(Column::from("foo")),
-                ConditionBase::Literal(Literal::Integer(42 as i64))
+                ConditionBase::Literal(Literal::Integer(42_i64))
             )
         );
 
-        let res2 = condition_expr(CompleteByteSlice(cond2.as_bytes()));
+        let res2 = condition_expr(cond2);
         assert_eq!(
             res2.unwrap().1,
             flat_condition_tree(
@@ -524,23 +1095,23 @@ mod tests {
         let cond1 = "foo >= 42";
         let cond2 = "foo <= 5";
 
-        let res1 = condition_expr(CompleteByteSlice(cond1.as_bytes()));
+        let res1 = condition_expr(cond1);
         assert_eq!(
             res1.unwrap().1,
             flat_condition_tree(
                 Operator::GreaterOrEqual,
                 ConditionBase::Field(Column::from("foo")),
-                ConditionBase::Literal(Literal::Integer(42 as i64))
+                ConditionBase::Literal(Literal::Integer(42_i64))
             )
         );
 
-        let res2 = condition_expr(CompleteByteSlice(cond2.as_bytes()));
+        let res2 = condition_expr(cond2);
         assert_eq!(
             res2.unwrap().1,
             flat_condition_tree(
                 Operator::LessOrEqual,
                 ConditionBase::Field(Column::from("foo")),
-                ConditionBase::Literal(Literal::Integer(5 as i64))
+                ConditionBase::Literal(Literal::Integer(5_i64))
             )
         );
     }
@@ -549,7 +1120,7 @@ mod tests {
     fn empty_string_literal() {
         let cond = "foo = ''";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(
             res.unwrap().1,
             flat_condition_tree(
@@ -598,7 +1169,7 @@ mod tests {
             right: Box::new(right),
         });
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(res.unwrap().1, complete);
     }
 
@@ -640,7 +1211,7 @@ mod tests {
             right: Box::new(right),
         });
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(res.unwrap().1, complete);
     }
 
@@ -670,7 +1241,7 @@ mod tests {
             right: Box::new(right),
         });
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         assert_eq!(res.unwrap().1, complete);
     }
 
@@ -683,7 +1254,7 @@ mod tests {
 
         let cond = "bar in (select col from foo)";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
 
         let nested_select = Box::new(SelectStatement {
             tables: vec![Table::from("foo")],
@@ -709,7 +1280,7 @@ mod tests {
 
         let cond = "paperId in (select paperId from PaperConflict) and size > 0";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
 
         let nested_select = Box::new(SelectStatement {
             tables: vec![Table::from("PaperConflict")],
@@ -740,7 +1311,7 @@ mod tests {
 
         let cond = "bar in (0)";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
 
         let expected = flat_condition_tree(
             Operator::In,
@@ -758,14 +1329,14 @@ mod tests {
 
         let cond = "bar IS NULL";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         let expected =
             flat_condition_tree(Operator::Equal, Field("bar".into()), Literal(Literal::Null));
         assert_eq!(res.unwrap().1, expected);
 
         let cond = "bar IS NOT NULL";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         let expected = flat_condition_tree(
             Operator::NotEqual,
             Field("bar".into()),
@@ -789,7 +1360,7 @@ mod tests {
                     OR `saldo` >= 0 ) \
                     AND `read_ribbons`.`user_id` = ?";
 
-        let res = condition_expr(CompleteByteSlice(cond.as_bytes()));
+        let res = condition_expr(cond);
         let expected = ConditionExpression::LogicalOp(ConditionTree {
             operator: Operator::And,
             left: Box::new(flat_condition_tree(
@@ -868,4 +1439,135 @@ mod tests {
         let res = res.unwrap().1;
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn json_operators() {
+        use json::JsonOperator;
+
+        let cond = "doc->>'$.name' = 'x' and attrs @> '{\"a\": 1}' and attrs ? 'k'";
+        let res = condition_expr(cond).unwrap().1;
+        let extracted = JsonExpression {
+            column: Column::from("doc"),
+            path: vec![(JsonOperator::ExtractText, Literal::from("$.name"))],
+            alias: None,
+        };
+        match res {
+            ConditionExpression::LogicalOp(ref ct) => {
+                assert_eq!(
+                    *ct.left,
+                    flat_condition_tree(
+                        Operator::Equal,
+                        ConditionBase::Json(extracted),
+                        ConditionBase::Literal(Literal::from("x")),
+                    )
+                );
+            }
+            _ => panic!(),
+        }
+        assert_eq!(
+            format!("{}", res),
+            "doc->>'$.name' = 'x' AND attrs @> '{\"a\": 1}' AND attrs ? 'k'"
+        );
+    }
+
+    #[test]
+    fn array_any_and_subscript() {
+        let res = condition_expr("tags[1] = 'a'").unwrap().1;
+        assert_eq!(
+            res,
+            flat_condition_tree(
+                Operator::Equal,
+                ConditionBase::Subscript(SubscriptExpression {
+                    column: Column::from("tags"),
+                    indices: vec![Literal::Integer(1)],
+                    alias: None,
+                }),
+                ConditionBase::Literal(Literal::from("a")),
+            )
+        );
+
+        let res = condition_expr("'b' = ANY(tags)").unwrap().1;
+        assert_eq!(
+            res,
+            flat_condition_tree(
+                Operator::Equal,
+                ConditionBase::Literal(Literal::from("b")),
+                ConditionBase::Any(Box::new(ConditionExpression::Base(ConditionBase::Field(
+                    Column::from("tags")
+                )))),
+            )
+        );
+
+        let cond = "id = any(select id from t) and ids = ARRAY[1, 2]";
+        let res = condition_expr(cond).unwrap().1;
+        assert_eq!(
+            format!("{}", res),
+            "id = ANY(SELECT id FROM t) AND ids = ARRAY[1, 2]"
+        );
+    }
+
+    #[test]
+    fn row_constructors() {
+        let field = |c: &str| ConditionExpression::Base(ConditionBase::Field(Column::from(c)));
+        let int = |i: i64| ConditionExpression::Base(ConditionBase::Literal(i.into()));
+        let row = |exprs| ConditionExpression::Base(ConditionBase::Row(exprs, false));
+
+        let res = condition_expr("(a, b) > (1, 2)").unwrap().1;
+        assert_eq!(
+            res,
+            ConditionExpression::ComparisonOp(ConditionTree {
+                operator: Operator::Greater,
+                left: Box::new(row(vec![field("a"), field("b")])),
+                right: Box::new(row(vec![int(1), int(2)])),
+            })
+        );
+
+        let res = condition_expr("(a,b) IN ((1,2),(3,4))").unwrap().1;
+        assert_eq!(
+            res,
+            ConditionExpression::ComparisonOp(ConditionTree {
+                operator: Operator::In,
+                left: Box::new(row(vec![field("a"), field("b")])),
+                right: Box::new(row(vec![
+                    row(vec![int(1), int(2)]),
+                    row(vec![int(3), int(4)])
+                ])),
+            })
+        );
+        assert_eq!(res.to_string(), "(a, b) IN ((1, 2), (3, 4))");
+
+        let res = condition_expr("ROW(a) = ROW(1) AND (b) = 2").unwrap().1;
+        assert_eq!(res.to_string(), "ROW(a) = ROW(1) AND (b) = 2");
+
+        let res = condition_expr("row (a, b) = ROW(1, 2)").unwrap().1;
+        assert_eq!(
+            res,
+            ConditionExpression::ComparisonOp(ConditionTree {
+                operator: Operator::Equal,
+                left: Box::new(ConditionExpression::Base(ConditionBase::Row(
+                    vec![field("a"), field("b")],
+                    true
+                ))),
+                right: Box::new(ConditionExpression::Base(ConditionBase::Row(
+                    vec![int(1), int(2)],
+                    true
+                ))),
+            })
+        );
+        assert_eq!(res.to_string(), "ROW(a, b) = ROW(1, 2)");
+    }
+
+    #[test]
+    fn not_in() {
+        let res = condition_expr("a NOT IN (1, 2)").unwrap().1;
+        match res {
+            ConditionExpression::ComparisonOp(ref tree) => {
+                assert_eq!(tree.operator, Operator::NotIn)
+            }
+            _ => panic!("{:?}", res),
+        }
+        assert_eq!(res.to_string(), "a NOT IN (1, 2)");
+        // `IN` is a whole word
+        assert_eq!(condition_expr("(a, b) info").unwrap().0, " info");
+    }
 }
@@ -1,35 +1,195 @@
-use nom::{digit, multispace};
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_until, take_while1};
+use nom::character::complete::digit1;
+use nom::combinator::{map, map_opt, map_res, not, opt, peek, recognize, value, verify};
+use nom::error::{Error, ErrorKind};
+use nom::multi::{many0, many1, separated_list1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::IResult;
+use std::borrow::Cow;
 use std::fmt;
-use std::str;
 use std::str::FromStr;
 
-use create_table_options::table_options;
-use column::{Column, ColumnConstraint, ColumnSpecification};
+use column::{Column, ColumnConstraint, ColumnSpecification, IdentityGeneration};
 use common::{
-    column_identifier_no_alias, opt_multispace, parse_comment, sql_identifier,
-    statement_terminator, table_reference, type_identifier, Literal, Real, SqlType,
-    TableKey,
+    column_identifier_no_alias, double_quoted_identifier, is_sql_identifier, keyword_dispatch,
+    keyword_phrase, nextval_fx, parse_comment, plain_column_identifier, sql_identifier,
+    statement_terminator, table_reference, type_identifier, type_with_attributes, unsigned_number,
+    whitespace0, whitespace1, ws_sep_comma, FieldDefinitionExpression, FieldValueExpression,
+    IndexOptions, IndexType, KeyPart, KeywordParser, Literal, LiteralExpression, Operator, Real,
+    SqlType, TableKey,
 };
 use compound_select::{compound_selection, CompoundSelectStatement};
-use keywords::escape_if_keyword;
+use condition::{condition_expr, ConditionBase, ConditionExpression, ConditionTree};
+use create_table_options::table_options;
+use foreignkey::{ForeignKeySpecification, ReferentialAction};
+use insert::InsertStatement;
+use keywords::{escape_if_keyword, tag_no_case};
 use order::{order_type, OrderType};
-use select::{nested_selection, SelectStatement};
+use partition::{partition_scheme, PartitionScheme};
+use select::{select_statement, SelectStatement};
+use sequence::identity_options;
+use simplify::and_conditions;
 use table::Table;
-use foreignkey::{ForeignKeySpecification};
+use update::UpdateStatement;
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct CreateTableStatement {
     pub table: Table,
     pub fields: Vec<ColumnSpecification>,
     pub keys: Option<Vec<TableKey>>,
     pub fkeys: Option<Vec<ForeignKeySpecification>>,
+    pub partitioning: Option<PartitionScheme>,
+    #[serde(default)]
+    pub if_not_exists: bool,
+    /// SQLite `WITHOUT ROWID`
+    #[serde(default)]
+    pub without_rowid: bool,
+}
+
+impl CreateTableStatement {
+    /// The column that SQLite makes an alias for the table's rowid: the sole primary key column,
+    /// if it is an integer, in a table that has a rowid. SQLite only does this when the type is
+    /// spelled `INTEGER` exactly, which the parsed type no longer tells apart from `INT`.
+    pub fn rowid_alias(&self) -> Option<&ColumnSpecification> {
+        if self.without_rowid {
+            return None;
+        }
+        let mut primary_keys = self.fields.iter().filter(|field| {
+            field.constraints.contains(&ColumnConstraint::PrimaryKey)
+                || self.keys.iter().flatten().any(|key| match *key {
+                    TableKey::PrimaryKey(_, ref columns, _, _) => {
+                        *columns == [KeyPart::Column(field.column.clone(), None, None)]
+                    }
+                    _ => false,
+                })
+        });
+        match (primary_keys.next(), primary_keys.next()) {
+            (Some(field), None) => match field.sql_type {
+                Some(SqlType::Int(_)) => Some(field),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The columns of the primary key, declared on a column or as a table key, or `None` if there
+    /// is none or it includes an expression.
+    pub fn primary_key(&self) -> Option<Vec<Column>> {
+        let inline = self
+            .fields
+            .iter()
+            .find(|field| field.constraints.contains(&ColumnConstraint::PrimaryKey));
+        if let Some(field) = inline {
+            return Some(vec![field.column.clone()]);
+        }
+        let parts = self.keys.iter().flatten().find_map(|key| match *key {
+            TableKey::PrimaryKey(_, ref parts, _, _) => Some(parts),
+            _ => None,
+        })?;
+        parts
+            .iter()
+            .map(|part| match *part {
+                KeyPart::Column(ref column, _, _) => Some(column.clone()),
+                KeyPart::Expression(..) => None,
+            })
+            .collect()
+    }
+
+    /// `INSERT INTO t (columns) VALUES (?, ...)` over every column a value may be given for.
+    pub fn insert_skeleton(&self) -> InsertStatement {
+        let fields: Vec<Column> = self
+            .fields
+            .iter()
+            .filter(|field| assignable(field))
+            .map(|field| unqualified(&field.column))
+            .collect();
+        InsertStatement {
+            table: Table::from(self.table.name.as_str()),
+            data: vec![vec![Literal::Placeholder.into(); fields.len()]],
+            fields: Some(fields),
+            ..Default::default()
+        }
+    }
+
+    /// `UPDATE t SET column = ?, ... WHERE key = ? AND ...`, setting every column outside of the
+    /// primary key that a value may be given for, or `None` if the table has no primary key or no other columns.
+    pub fn update_by_key_skeleton(&self) -> Option<UpdateStatement> {
+        let key = self.primary_key()?;
+        let fields: Vec<_> = self
+            .fields
+            .iter()
+            .filter(|field| assignable(field) && !key.iter().any(|k| k.name == field.column.name))
+            .map(|field| {
+                let value = LiteralExpression::from(Literal::Placeholder);
+                (
+                    unqualified(&field.column),
+                    FieldValueExpression::Literal(value),
+                )
+            })
+            .collect();
+        if fields.is_empty() {
+            return None;
+        }
+        Some(UpdateStatement {
+            table: Table::from(self.table.name.as_str()),
+            fields,
+            where_clause: key_condition(&key),
+            ..Default::default()
+        })
+    }
+
+    /// `SELECT columns FROM t WHERE key = ? AND ...`, listing every column, or `None` if the table
+    /// has no primary key.
+    pub fn select_by_key_skeleton(&self) -> Option<SelectStatement> {
+        let key = self.primary_key()?;
+        Some(SelectStatement {
+            tables: vec![Table::from(self.table.name.as_str())],
+            fields: self
+                .fields
+                .iter()
+                .map(|field| FieldDefinitionExpression::Col(unqualified(&field.column)))
+                .collect(),
+            where_clause: key_condition(&key),
+            ..Default::default()
+        })
+    }
+}
+
+/// Whether a value may be given for the column, which `GENERATED ALWAYS AS IDENTITY` rules out.
+fn assignable(field: &ColumnSpecification) -> bool {
+    !field.constraints.iter().any(|c| match *c {
+        ColumnConstraint::Identity(generation, _) => generation == IdentityGeneration::Always,
+        _ => false,
+    })
+}
+
+fn unqualified(column: &Column) -> Column {
+    Column::from(column.name.as_str())
+}
+
+/// `key1 = ? AND key2 = ? ...`
+fn key_condition(key: &[Column]) -> Option<ConditionExpression> {
+    and_conditions(key.iter().map(|column| {
+        ConditionExpression::ComparisonOp(ConditionTree {
+            operator: Operator::Equal,
+            left: Box::new(ConditionExpression::Base(ConditionBase::Field(
+                unqualified(column),
+            ))),
+            right: Box::new(ConditionExpression::Base(ConditionBase::Literal(
+                Literal::Placeholder,
+            ))),
+        })
+    }))
 }
 
 impl fmt::Display for CreateTableStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "CREATE TABLE {} ", escape_if_keyword(&self.table.name))?;
-        write!(f, "(")?;
+        write!(f, "CREATE TABLE ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        write!(f, "{} (", self.table.escaped_name())?;
         write!(
             f,
             "{}",
@@ -53,17 +213,26 @@ impl fmt::Display for CreateTableStatement {
             write!(
                 f,
                 ", {}",
-                fkeys.iter()
+                fkeys
+                    .iter()
                     .map(|fkey| format!("{}", fkey))
                     .collect::<Vec<_>>()
                     .join(", ")
             )?;
         }
-        write!(f, ")")
+        write!(f, ")")?;
+        if self.without_rowid {
+            write!(f, " WITHOUT ROWID")?;
+        }
+        if let Some(ref partitioning) = self.partitioning {
+            write!(f, " {}", partitioning)?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum SelectSpecification {
     Compound(CompoundSelectStatement),
     Simple(SelectStatement),
@@ -78,479 +247,1002 @@ impl fmt::Display for SelectSpecification {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// MySQL `ALGORITHM` of a view.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum ViewAlgorithm {
+    Undefined,
+    Merge,
+    TempTable,
+}
+
+impl fmt::Display for ViewAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ViewAlgorithm::Undefined => write!(f, "UNDEFINED"),
+            ViewAlgorithm::Merge => write!(f, "MERGE"),
+            ViewAlgorithm::TempTable => write!(f, "TEMPTABLE"),
+        }
+    }
+}
+
+/// Whose privileges a view is executed with (`SQL SECURITY ...`).
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum ViewSecurity {
+    Definer,
+    Invoker,
+}
+
+impl fmt::Display for ViewSecurity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ViewSecurity::Definer => write!(f, "DEFINER"),
+            ViewSecurity::Invoker => write!(f, "INVOKER"),
+        }
+    }
+}
+
+/// `WITH [CASCADED | LOCAL] CHECK OPTION`; `Unqualified` is the form without either keyword,
+/// which means `CASCADED`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum ViewCheckOption {
+    Unqualified,
+    Cascaded,
+    Local,
+}
+
+impl fmt::Display for ViewCheckOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ViewCheckOption::Unqualified => write!(f, "WITH CHECK OPTION"),
+            ViewCheckOption::Cascaded => write!(f, "WITH CASCADED CHECK OPTION"),
+            ViewCheckOption::Local => write!(f, "WITH LOCAL CHECK OPTION"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct CreateViewStatement {
     pub name: String,
     pub fields: Vec<Column>,
     pub definition: Box<SelectSpecification>,
+    #[serde(default)]
+    pub or_replace: bool,
+    #[serde(default)]
+    pub algorithm: Option<ViewAlgorithm>,
+    /// The account the view was defined by, as written (e.g. `` `root`@`localhost` ``).
+    #[serde(default)]
+    pub definer: Option<String>,
+    #[serde(default)]
+    pub security: Option<ViewSecurity>,
+    #[serde(default)]
+    pub check_option: Option<ViewCheckOption>,
 }
 
-impl fmt::Display for CreateViewStatement {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "CREATE VIEW {} ", escape_if_keyword(&self.name))?;
+impl CreateViewStatement {
+    /// Everything up to the `AS` that introduces the definition.
+    pub(crate) fn header(&self) -> String {
+        let mut header = String::from("CREATE");
+        if self.or_replace {
+            header.push_str(" OR REPLACE");
+        }
+        if let Some(algorithm) = self.algorithm {
+            header.push_str(&format!(" ALGORITHM={}", algorithm));
+        }
+        if let Some(ref definer) = self.definer {
+            header.push_str(&format!(" DEFINER={}", definer));
+        }
+        if let Some(security) = self.security {
+            header.push_str(&format!(" SQL SECURITY {}", security));
+        }
+        header.push_str(&format!(" VIEW {}", escape_if_keyword(&self.name)));
         if !self.fields.is_empty() {
-            write!(f, "(")?;
-            write!(
-                f,
-                "{}",
+            header.push_str(&format!(
+                " ({})",
                 self.fields
                     .iter()
                     .map(|field| format!("{}", field))
                     .collect::<Vec<_>>()
                     .join(", ")
-            )?;
-            write!(f, ") ")?;
+            ));
+        }
+        header
+    }
+}
+
+impl fmt::Display for CreateViewStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} AS {}", self.header(), self.definition)?;
+        if let Some(check_option) = self.check_option {
+            write!(f, " {}", check_option)?;
         }
-        write!(f, "AS ")?;
-        write!(f, "{}", self.definition)
+        Ok(())
     }
 }
 
 /// MySQL grammar element for index column definition (§13.1.18, index_col_name)
-named!(pub index_col_name<CompleteByteSlice, (Column, Option<u16>, Option<OrderType>)>,
-    do_parse!(
-        column: column_identifier_no_alias >>
-        opt_multispace >>
-        len: opt!(delimited!(tag!("("), digit, tag!(")"))) >>
-        order: opt!(order_type) >>
-        ((column, len.map(|l| u16::from_str(str::from_utf8(*l).unwrap()).unwrap()), order))
-    )
-);
+pub fn index_col_name(i: &str) -> IResult<&str, (Column, Option<u16>, Option<OrderType>)> {
+    let (remaining_input, (column, _, len, order)) = tuple((
+        column_name,
+        whitespace0,
+        opt(delimited(
+            tag("("),
+            map_res(digit1, u16::from_str),
+            tag(")"),
+        )),
+        opt(preceded(whitespace0, order_type)),
+    ))(i)?;
+
+    Ok((remaining_input, (column, len, order)))
+}
+
+/// An expression key part: parenthesized, or a bare function call as Postgres allows.
+fn key_expression(i: &str) -> IResult<&str, ConditionExpression> {
+    alt((
+        delimited(
+            pair(tag("("), whitespace0),
+            condition_expr,
+            pair(whitespace0, tag(")")),
+        ),
+        map(
+            verify(column_identifier_no_alias, |column: &Column| {
+                column.function.is_some()
+            }),
+            |column| ConditionExpression::Base(ConditionBase::Field(column)),
+        ),
+    ))(i)
+}
+
+fn key_part(i: &str) -> IResult<&str, KeyPart> {
+    alt((
+        map(
+            // a column followed by anything but the end of the part is a function call
+            terminated(
+                index_col_name,
+                peek(preceded(whitespace0, alt((tag(","), tag(")"))))),
+            ),
+            |(column, length, order)| KeyPart::Column(column, length, order),
+        ),
+        map(
+            pair(key_expression, opt(preceded(whitespace0, order_type))),
+            |(expr, order)| KeyPart::Expression(Box::new(expr), order),
+        ),
+    ))(i)
+}
 
 /// Helper for list of index columns
-named!(pub index_col_list<CompleteByteSlice, Vec<Column> >,
-       many0!(
-           do_parse!(
-               entry: index_col_name >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               // XXX(malte): ignores length and order
-               (entry.0)
-           )
-       )
-);
+pub fn index_col_list(i: &str) -> IResult<&str, Vec<KeyPart>> {
+    many0(terminated(key_part, opt(ws_sep_comma)))(i)
+}
+
+fn key_columns(i: &str) -> IResult<&str, Vec<KeyPart>> {
+    delimited(
+        tag("("),
+        delimited(whitespace0, index_col_list, whitespace0),
+        tag(")"),
+    )(i)
+}
+
+#[derive(Clone)]
+enum IndexOption {
+    Type(IndexType),
+    KeyBlockSize(u64),
+    Comment(String),
+    Invisible(bool),
+}
+
+pub fn index_type(i: &str) -> IResult<&str, IndexType> {
+    preceded(
+        pair(tag_no_case("using"), whitespace1),
+        alt((
+            value(IndexType::BTree, tag_no_case("btree")),
+            value(IndexType::Hash, tag_no_case("hash")),
+        )),
+    )(i)
+}
+
+fn index_option(i: &str) -> IResult<&str, IndexOption> {
+    alt((
+        map(preceded(whitespace0, index_type), IndexOption::Type),
+        map(
+            preceded(
+                tuple((
+                    whitespace0,
+                    tag_no_case("key_block_size"),
+                    whitespace0,
+                    opt(tag("=")),
+                    whitespace0,
+                )),
+                unsigned_number,
+            ),
+            IndexOption::KeyBlockSize,
+        ),
+        map(parse_comment, IndexOption::Comment),
+        value(
+            IndexOption::Invisible(true),
+            preceded(whitespace0, tag_no_case("invisible")),
+        ),
+        value(
+            IndexOption::Invisible(false),
+            preceded(whitespace0, tag_no_case("visible")),
+        ),
+    ))(i)
+}
+
+/// Parses a key's column list along with its index options. `USING` is accepted both before and
+/// after the column list.
+pub fn key_columns_with_options(i: &str) -> IResult<&str, (Vec<KeyPart>, IndexOptions)> {
+    let (remaining_input, (leading_type, columns, options)) = tuple((
+        opt(terminated(index_type, whitespace0)),
+        key_columns,
+        many0(index_option),
+    ))(i)?;
+
+    let mut opts = IndexOptions {
+        index_type: leading_type,
+        ..Default::default()
+    };
+    for option in options {
+        match option {
+            IndexOption::Type(t) => opts.index_type = Some(t),
+            IndexOption::KeyBlockSize(size) => opts.key_block_size = Some(size),
+            IndexOption::Comment(comment) => opts.comment = Some(comment),
+            IndexOption::Invisible(invisible) => opts.invisible = invisible,
+        }
+    }
+    Ok((remaining_input, (columns, opts)))
+}
+
+fn full_text_key(i: &str) -> IResult<&str, TableKey> {
+    let (remaining_input, (_, _, _, _, name, _, (columns, options))) = tuple((
+        tag_no_case("fulltext"),
+        whitespace1,
+        alt((tag_no_case("key"), tag_no_case("index"))),
+        whitespace0,
+        opt(sql_identifier),
+        whitespace0,
+        key_columns_with_options,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        TableKey::FulltextKey(name.map(String::from), columns, options),
+    ))
+}
+
+fn spatial_key(i: &str) -> IResult<&str, TableKey> {
+    let (remaining_input, (_, _, _, _, name, _, (columns, options))) = tuple((
+        tag_no_case("spatial"),
+        whitespace1,
+        alt((tag_no_case("key"), tag_no_case("index"))),
+        whitespace0,
+        opt(sql_identifier),
+        whitespace0,
+        key_columns_with_options,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        TableKey::SpatialKey(name.map(String::from), columns, options),
+    ))
+}
+
+/// `CONSTRAINT [name]`, which may precede a primary or unique key.
+fn constraint_name(i: &str) -> IResult<&str, Option<Cow<'_, str>>> {
+    delimited(
+        pair(tag_no_case("constraint"), whitespace1),
+        opt(terminated(sql_identifier, whitespace1)),
+        whitespace0,
+    )(i)
+}
+
+fn primary_key(i: &str) -> IResult<&str, TableKey> {
+    let (remaining_input, (name, _, _, (columns, options), autoincrement)) = tuple((
+        opt(constraint_name),
+        keyword_phrase(&["primary", "key"]),
+        whitespace0,
+        key_columns_with_options,
+        opt(pair(whitespace1, tag_no_case("autoincrement"))),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        TableKey::PrimaryKey(
+            name.flatten().map(String::from),
+            columns,
+            options,
+            autoincrement.is_some(),
+        ),
+    ))
+}
+
+fn unique(i: &str) -> IResult<&str, TableKey> {
+    let (remaining_input, (constraint, _, _, _, name, _, (columns, options))) = tuple((
+        opt(constraint_name),
+        tag_no_case("unique"),
+        opt(preceded(
+            whitespace1,
+            alt((tag_no_case("key"), tag_no_case("index"))),
+        )),
+        whitespace0,
+        opt(sql_identifier),
+        whitespace0,
+        key_columns_with_options,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        TableKey::UniqueKey(
+            name.or(constraint.flatten()).map(String::from),
+            columns,
+            options,
+        ),
+    ))
+}
+
+fn key_or_index(i: &str) -> IResult<&str, TableKey> {
+    let (remaining_input, (_, _, name, _, (columns, options))) = tuple((
+        alt((tag_no_case("key"), tag_no_case("index"))),
+        whitespace0,
+        sql_identifier,
+        whitespace0,
+        key_columns_with_options,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        TableKey::Key(String::from(name), columns, options),
+    ))
+}
+
+/// Key parsers keyed on their leading keyword.
+const KEY_PARSERS: &[(&str, KeywordParser<TableKey>)] = &[
+    ("constraint", |i| alt((primary_key, unique))(i)),
+    ("fulltext", full_text_key),
+    ("index", key_or_index),
+    ("key", key_or_index),
+    ("primary", primary_key),
+    ("spatial", spatial_key),
+    ("unique", unique),
+];
 
 /// Parse rule for an individual key specification.
-named!(pub key_specification<CompleteByteSlice, TableKey>,
-    alt!(
-          do_parse!(
-              tag_no_case!("fulltext") >>
-              multispace >>
-              alt!(tag_no_case!("key") | tag_no_case!("index")) >>
-              opt_multispace >>
-              name: opt!(sql_identifier) >>
-              opt_multispace >>
-              columns: delimited!(tag!("("), delimited!(opt_multispace, index_col_list, opt_multispace), tag!(")")) >>
-              (match name {
-                  Some(name) => {
-                      let n = String::from_utf8(name.to_vec()).unwrap();
-                      TableKey::FulltextKey(Some(n), columns)
-                  },
-                  None => TableKey::FulltextKey(None, columns),
-              })
-          )
-        | do_parse!(
-              tag_no_case!("primary key") >>
-              opt_multispace >>
-              columns: delimited!(tag!("("), delimited!(opt_multispace, index_col_list, opt_multispace), tag!(")")) >>
-              opt!(do_parse!(
-                          multispace >>
-                          tag_no_case!("autoincrement") >>
-                          ()
-                   )
-              ) >>
-              (TableKey::PrimaryKey(columns))
-          )
-        | do_parse!(
-              tag_no_case!("unique") >>
-              opt!(preceded!(multispace,
-                             alt!(
-                                   tag_no_case!("key")
-                                 | tag_no_case!("index")
-                             )
-                   )
-              ) >>
-              opt_multispace >>
-              name: opt!(sql_identifier) >>
-              opt_multispace >>
-              columns: delimited!(tag!("("), delimited!(opt_multispace, index_col_list, opt_multispace), tag!(")")) >>
-              (match name {
-                  Some(name) => {
-                      let n = String::from_utf8(name.to_vec()).unwrap();
-                      TableKey::UniqueKey(Some(n), columns)
-                  },
-                  None => TableKey::UniqueKey(None, columns),
-              })
-          )
-        | do_parse!(
-              alt!(tag_no_case!("key") | tag_no_case!("index")) >>
-              opt_multispace >>
-              name: sql_identifier >>
-              opt_multispace >>
-              columns: delimited!(tag!("("), delimited!(opt_multispace, index_col_list, opt_multispace), tag!(")")) >>
-              ({
-                  let n = String::from_utf8(name.to_vec()).unwrap();
-                  TableKey::Key(n, columns)
-              })
-          )
-    )
-);
+pub fn key_specification(i: &str) -> IResult<&str, TableKey> {
+    keyword_dispatch(i, KEY_PARSERS)
+}
 
 /// Parse rule for a comma-separated list.
-named!(pub key_specification_list<CompleteByteSlice, Vec<TableKey>>,
-       many1!(
-           do_parse!(
-               key: key_specification >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               (key)
-           )
-       )
-);
+pub fn key_specification_list(i: &str) -> IResult<&str, Vec<TableKey>> {
+    many1(terminated(key_specification, opt(ws_sep_comma)))(i)
+}
+
+/// A column name in a table definition, which may also be double-quoted.
+fn column_name(i: &str) -> IResult<&str, Column> {
+    alt((
+        plain_column_identifier,
+        map(double_quoted_identifier, |name| Column {
+            name: name.into(),
+            ..Default::default()
+        }),
+    ))(i)
+}
+
+pub(crate) fn field_specification(i: &str) -> IResult<&str, ColumnSpecification> {
+    let (remaining_input, (_, column, field_type, constraints, comment, _)) = tuple((
+        // FULLTEXT and SPATIAL are not reserved, so would otherwise be taken for untyped columns
+        not(key_specification),
+        column_name,
+        opt(delimited(whitespace1, type_with_attributes, whitespace0)),
+        many0(column_constraint),
+        opt(parse_comment),
+        opt(ws_sep_comma),
+    ))(i)?;
+
+    let (sql_type, type_attributes) = match field_type {
+        Some((sql_type, type_attributes)) => (Some(sql_type), type_attributes),
+        None => (None, Default::default()),
+    };
+    Ok((
+        remaining_input,
+        ColumnSpecification {
+            column,
+            sql_type,
+            type_attributes,
+            constraints: constraints.into_iter().flatten().collect(),
+            comment,
+        },
+    ))
+}
 
 /// Parse rule for a comma-separated list.
-named!(pub field_specification_list<CompleteByteSlice, Vec<ColumnSpecification> >,
-       many1!(
-           do_parse!(
-               identifier: column_identifier_no_alias >>
-               fieldtype: opt!(do_parse!(multispace >>
-                                      ti: type_identifier >>
-                                      opt_multispace >>
-                                      (ti)
-                               )
-               ) >>
-               constraints: many0!(column_constraint) >>
-               comment: opt!(parse_comment) >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               ({
-                   let t = match fieldtype {
-                       None => SqlType::Text,
-                       Some(ref t) => t.clone(),
-                   };
-                   ColumnSpecification {
-                       column: identifier,
-                       sql_type: t,
-                       constraints: constraints.into_iter().filter_map(|m|m).collect(),
-                       comment: comment,
-                   }
-               })
-           )
-       )
-);
+pub fn field_specification_list(i: &str) -> IResult<&str, Vec<ColumnSpecification>> {
+    many1(field_specification)(i)
+}
 
-/// Parse rule for a column definition contraint.
-named!(pub column_constraint<CompleteByteSlice, Option<ColumnConstraint>>,
-    alt!(
-          do_parse!(
-              opt_multispace >>
-              tag_no_case!("not null") >>
-              opt_multispace >>
-              (Some(ColumnConstraint::NotNull))
-          )
-        | do_parse!(
-              opt_multispace >>
-              tag_no_case!("null") >>
-              opt_multispace >>
-              (None)
-          )
-        | do_parse!(
-              opt_multispace >>
-              tag_no_case!("auto_increment") >>
-              opt_multispace >>
-              (Some(ColumnConstraint::AutoIncrement))
-          )
-        | do_parse!(
-              opt_multispace >>
-              tag_no_case!("default") >>
-              multispace >>
-              def: alt!(
-                    do_parse!(s: delimited!(tag!("'"), take_until!("'"), tag!("'")) >> (
-                        Literal::String(String::from_utf8(s.to_vec()).unwrap())
-                    ))
-                  | do_parse!(i: digit >>
-                              tag!(".") >>
-                              f: digit >> (
-                              Literal::FixedPoint(Real {
-                                  integral: i32::from_str(str::from_utf8(*i).unwrap()).unwrap(),
-                                  fractional: i32::from_str(str::from_utf8(*f).unwrap()).unwrap()
-                              })
-                    ))
-                  | do_parse!(d: digit >> (
-                        Literal::Integer(i64::from_str(str::from_utf8(*d).unwrap()).unwrap())
-                    ))
-                  | do_parse!(tag!("''") >> (Literal::String(String::from(""))))
-                  | do_parse!(tag_no_case!("null") >> (Literal::Null))
-                  | do_parse!(tag_no_case!("current_timestamp") >> (Literal::CurrentTimestamp))
-              ) >>
-              opt_multispace >>
-              (Some(ColumnConstraint::DefaultValue(def)))
-          )
-        | do_parse!(
-              opt_multispace >>
-              tag_no_case!("primary key") >>
-              opt_multispace >>
-              (Some(ColumnConstraint::PrimaryKey))
-          )
-        | do_parse!(
-              opt_multispace >>
-              tag_no_case!("unique") >>
-              opt_multispace >>
-              (Some(ColumnConstraint::Unique))
-          )
-        | do_parse!(
-              opt_multispace >>
-              tag_no_case!("character set") >>
-              multispace >>
-              charset: sql_identifier >>
-              (Some(ColumnConstraint::CharacterSet(str::from_utf8(*charset).unwrap().to_owned())))
-          )
-        | do_parse!(
-              opt_multispace >>
-              tag_no_case!("collate") >>
-              multispace >>
-              collation: sql_identifier >>
-              (Some(ColumnConstraint::Collation(str::from_utf8(*collation).unwrap().to_owned())))
-          )
+fn default_value(i: &str) -> IResult<&str, Literal> {
+    alt((
+        map(delimited(tag("'"), take_until("'"), tag("'")), |s| {
+            Literal::String(String::from(s))
+        }),
+        map_opt(
+            tuple((opt(tag("-")), digit1, tag("."), digit1)),
+            |(sign, i, _, f): (Option<&str>, &str, &str, &str)| {
+                Real::from_digits(sign.is_some(), i, f).map(Literal::FixedPoint)
+            },
+        ),
+        map_res(recognize(pair(opt(tag("-")), digit1)), |d| {
+            i64::from_str(d).map(Literal::Integer)
+        }),
+        value(Literal::String(String::from("")), tag("''")),
+        value(Literal::Null, tag_no_case("null")),
+        value(Literal::CurrentTimestamp, tag_no_case("current_timestamp")),
+    ))(i)
+}
+
+/// Postgres-style cast on a default value, e.g. `'draft'::character varying`. The cast is dropped.
+fn default_value_cast(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        pair(
+            tag("::"),
+            alt((
+                recognize(tuple((
+                    tag_no_case("character"),
+                    whitespace1,
+                    tag_no_case("varying"),
+                ))),
+                recognize(type_identifier),
+                recognize(sql_identifier),
+            )),
+        ),
+    )(i)
+}
+
+fn identity_generation(i: &str) -> IResult<&str, ColumnConstraint> {
+    let (remaining_input, (_, _, _, generation, _, _, _, _, options)) = tuple((
+        whitespace0,
+        tag_no_case("generated"),
+        whitespace1,
+        alt((
+            value(IdentityGeneration::Always, tag_no_case("always")),
+            value(
+                IdentityGeneration::ByDefault,
+                tuple((tag_no_case("by"), whitespace1, tag_no_case("default"))),
+            ),
+        )),
+        whitespace1,
+        tag_no_case("as"),
+        whitespace1,
+        tag_no_case("identity"),
+        opt(preceded(whitespace0, identity_options)),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        ColumnConstraint::Identity(generation, options.unwrap_or_default()),
+    ))
+}
+
+fn fixed_constraint<'a>(
+    keyword: &'static str,
+    constraint: Option<ColumnConstraint>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Option<ColumnConstraint>> {
+    value(
+        constraint,
+        delimited(whitespace0, tag_no_case(keyword), whitespace0),
     )
-);
+}
+
+fn referential_action(i: &str) -> IResult<&str, ReferentialAction> {
+    alt((
+        value(ReferentialAction::Cascade, tag_no_case("cascade")),
+        value(
+            ReferentialAction::SetNull,
+            tuple((tag_no_case("set"), whitespace1, tag_no_case("null"))),
+        ),
+        value(
+            ReferentialAction::SetDefault,
+            tuple((tag_no_case("set"), whitespace1, tag_no_case("default"))),
+        ),
+        value(ReferentialAction::Restrict, tag_no_case("restrict")),
+        value(
+            ReferentialAction::NoAction,
+            tuple((tag_no_case("no"), whitespace1, tag_no_case("action"))),
+        ),
+    ))(i)
+}
+
+/// `ON DELETE action` or `ON UPDATE action`, with `true` for the former.
+fn referential_trigger(i: &str) -> IResult<&str, (bool, ReferentialAction)> {
+    preceded(
+        tuple((whitespace0, tag_no_case("on"), whitespace1)),
+        separated_pair(
+            alt((
+                value(true, tag_no_case("delete")),
+                value(false, tag_no_case("update")),
+            )),
+            whitespace1,
+            referential_action,
+        ),
+    )(i)
+}
+
+/// Any `ON DELETE` and `ON UPDATE` actions of a reference, in either order.
+fn referential_triggers(
+    i: &str,
+) -> IResult<&str, (Option<ReferentialAction>, Option<ReferentialAction>)> {
+    map(many0(referential_trigger), |triggers| {
+        let mut on_delete = None;
+        let mut on_update = None;
+        for (delete, action) in triggers {
+            if delete {
+                on_delete = Some(action);
+            } else {
+                on_update = Some(action);
+            }
+        }
+        (on_delete, on_update)
+    })(i)
+}
+
+fn references_constraint(i: &str) -> IResult<&str, ColumnConstraint> {
+    let (remaining_input, (_, _, _, table, columns, triggers)) = tuple((
+        whitespace0,
+        tag_no_case("references"),
+        whitespace1,
+        table_reference,
+        opt(preceded(
+            whitespace0,
+            delimited(tag("("), field_fk_specification_list, tag(")")),
+        )),
+        referential_triggers,
+    ))(i)?;
+
+    let (on_delete, on_update) = triggers;
+    Ok((
+        remaining_input,
+        ColumnConstraint::References {
+            table,
+            columns: columns.unwrap_or_default(),
+            on_delete,
+            on_update,
+        },
+    ))
+}
+
+/// Column constraint parsers keyed on their leading keyword. The parsers, like the parse rule
+/// they make up, consume surrounding whitespace.
+const CONSTRAINT_PARSERS: &[(&str, KeywordParser<Option<ColumnConstraint>>)] = &[
+    ("auto_increment", |i| {
+        fixed_constraint("auto_increment", Some(ColumnConstraint::AutoIncrement))(i)
+    }),
+    ("autoincrement", |i| {
+        fixed_constraint("autoincrement", Some(ColumnConstraint::Autoincrement))(i)
+    }),
+    ("character", |i| {
+        map(
+            preceded(
+                tuple((
+                    whitespace0,
+                    keyword_phrase(&["character", "set"]),
+                    whitespace1,
+                )),
+                sql_identifier,
+            ),
+            |charset| Some(ColumnConstraint::CharacterSet(String::from(charset))),
+        )(i)
+    }),
+    ("collate", |i| {
+        map(
+            preceded(
+                tuple((whitespace0, tag_no_case("collate"), whitespace1)),
+                sql_identifier,
+            ),
+            |collation| Some(ColumnConstraint::Collation(String::from(collation))),
+        )(i)
+    }),
+    ("default", |i| {
+        map(
+            delimited(
+                tuple((whitespace0, tag_no_case("default"), whitespace1)),
+                alt((
+                    map(nextval_fx, ColumnConstraint::DefaultExpression),
+                    map(
+                        terminated(default_value, opt(default_value_cast)),
+                        ColumnConstraint::DefaultValue,
+                    ),
+                )),
+                whitespace0,
+            ),
+            Some,
+        )(i)
+    }),
+    ("generated", |i| {
+        map(terminated(identity_generation, whitespace0), Some)(i)
+    }),
+    ("not", |i| {
+        fixed_constraint("not null", Some(ColumnConstraint::NotNull))(i)
+    }),
+    ("null", |i| fixed_constraint("null", None)(i)),
+    ("primary", |i| {
+        fixed_constraint("primary key", Some(ColumnConstraint::PrimaryKey))(i)
+    }),
+    ("references", |i| {
+        map(terminated(references_constraint, whitespace0), Some)(i)
+    }),
+    ("unique", |i| {
+        fixed_constraint("unique", Some(ColumnConstraint::Unique))(i)
+    }),
+];
+
+/// Parse rule for a column definition contraint.
+pub fn column_constraint(i: &str) -> IResult<&str, Option<ColumnConstraint>> {
+    keyword_dispatch(i, CONSTRAINT_PARSERS)
+}
 
 /// Parse rule for a comma-separated list.
-named!(pub field_fk_specification_list<CompleteByteSlice, Vec<Column>>,
-    many1!(
-        do_parse!(
-            opt_multispace >>
-            column: sql_identifier >>
-            opt_multispace >>
-            opt!(
-                do_parse!(
-                    opt_multispace >>
-                    tag!(",") >>
-                    opt_multispace >>
-                    ()
-                )
-            ) >>
-            ({
-                Column {
-                    name: String::from_utf8(column.to_vec()).unwrap(),
-                    alias: None,
-                    table: None,
-                    function: None,
-                }
-            })
-        )
-    )
-);
-
-named!(pub foreign_key_ref_action_list<CompleteByteSlice, Vec<String> >,
-    many1!(
-        do_parse!(
-            opt_multispace >>
-            tag_no_case!("ON") >>
-            multispace >>
-            name: alt!(tag_no_case!("DELETE") | tag_no_case!("UPDATE")) >>
-            multispace >>
-            tag_no_case!("RESTRICT") >>
-            ({String::from_utf8(name.to_vec()).unwrap()})
-        )
-    ));
+pub fn field_fk_specification_list(i: &str) -> IResult<&str, Vec<Column>> {
+    many1(map(
+        delimited(
+            whitespace0,
+            sql_identifier,
+            pair(whitespace0, opt(ws_sep_comma)),
+        ),
+        |name| Column {
+            name: name.into(),
+            ..Default::default()
+        },
+    ))(i)
+}
+
+pub(crate) fn foreign_key_specification(i: &str) -> IResult<&str, ForeignKeySpecification> {
+    let (
+        remaining_input,
+        (name, _, _, _, _, _, _, from, _, _, _, _, that_table, _, _, to, _, triggers, _, _),
+    ) = tuple((
+        opt(preceded(
+            tuple((whitespace0, tag_no_case("constraint"), whitespace0)),
+            sql_identifier,
+        )),
+        whitespace0,
+        tag_no_case("foreign"),
+        whitespace1,
+        tag_no_case("key"),
+        whitespace0,
+        tag("("),
+        field_fk_specification_list,
+        tag(")"),
+        whitespace0,
+        tag_no_case("references"),
+        whitespace1,
+        table_reference,
+        whitespace0,
+        tag("("),
+        field_fk_specification_list,
+        tag(")"),
+        referential_triggers,
+        whitespace0,
+        opt(ws_sep_comma),
+    ))(i)?;
+
+    let (on_delete, on_update) = triggers;
+    Ok((
+        remaining_input,
+        ForeignKeySpecification {
+            name: name.map(String::from),
+            on_delete,
+            on_update,
+            from,
+            that_table,
+            to,
+        },
+    ))
+}
 
 /// Parse rule for CONSTRAINT FOREIGN KEY list.
-named!(pub foreign_key_specification_list<CompleteByteSlice, Vec<ForeignKeySpecification> >,
-       many1!(
-           do_parse!(
-               name: opt!(do_parse!(
-                           opt_multispace >>
-                           tag_no_case!("CONSTRAINT") >>
-                           opt_multispace >>
-                           name: sql_identifier >>
-                           (name)
-                     )) >>
-               opt_multispace >>
-               tag_no_case!("foreign") >>
-               multispace >>
-               tag_no_case!("key") >>
-               opt_multispace >>
-               tag!("(") >>
-               fromfields: field_fk_specification_list >>
-               tag!(")") >>
-               opt_multispace >>
-               tag_no_case!("REFERENCES") >>
-               multispace >>
-               that_table: table_reference >>
-               opt_multispace >>
-               tag!("(") >>
-               tofields: field_fk_specification_list >>
-               tag!(")") >>
-               ref_act: opt!(do_parse!(
-                   act: foreign_key_ref_action_list >>
-                   (act)
-               )) >>
-               opt_multispace >>
-               opt!(
-                   do_parse!(
-                       opt_multispace >>
-                       tag!(",") >>
-                       opt_multispace >>
-                       ()
-                   )
-               ) >>
-               ({
-                   let ref_action = if let Some(ref_act) = ref_act {
-                        Some(
-                            ref_act.into_iter().map(|a| {
-                                    format!("ON {} RESTRICT", a)
-                                }).collect::<Vec<_>>().join(" ")
-                        )
-                   } else {
-                       None
-                   };
-                   ForeignKeySpecification {
-                       name: if let Some(name) = name {
-                           Some(String::from_utf8(name.to_vec()).unwrap())
-                       } else {
-                           None
-                       },
-                       ref_action: ref_action,
-                       from: fromfields,
-                       that_table: that_table,
-                       to: tofields,
-                   }
-               })
-           )
-       )
-);
+pub fn foreign_key_specification_list(i: &str) -> IResult<&str, Vec<ForeignKeySpecification>> {
+    many1(foreign_key_specification)(i)
+}
+
+/// The whitespace after `CREATE TABLE`, along with any `IF NOT EXISTS`.
+fn if_not_exists(i: &str) -> IResult<&str, bool> {
+    map(
+        preceded(
+            whitespace1,
+            opt(terminated(
+                tuple((
+                    tag_no_case("if"),
+                    whitespace1,
+                    tag_no_case("not"),
+                    whitespace1,
+                    tag_no_case("exists"),
+                )),
+                whitespace1,
+            )),
+        ),
+        |o| o.is_some(),
+    )(i)
+}
 
 /// Parse rule for a SQL CREATE TABLE query.
-/// TODO(malte): support types, TEMPORARY tables, IF NOT EXISTS, AS stmt
-named!(pub creation<CompleteByteSlice, CreateTableStatement>,
-    do_parse!(
-        tag_no_case!("create") >>
-        multispace >>
-        tag_no_case!("table") >>
-        multispace >>
-        table: table_reference >>
-        opt_multispace >>
-        tag!("(") >>
-        opt_multispace >>
-        fields: field_specification_list >>
-        opt_multispace >>
-        keys: opt!(key_specification_list) >>
-        opt_multispace >>
-        fkeys: opt!(foreign_key_specification_list) >>
-        opt_multispace >>
-        tag!(")") >>
-        opt_multispace >>
-        table_options >>
-        statement_terminator >>
-        ({
-            // "table AS alias" isn't legal in CREATE statements
-            assert!(table.alias.is_none());
-            // attach table names to columns:
-            let named_fields = fields
-                .into_iter()
-                .map(|field| {
-                    let column = Column {
-                        table: Some(table.name.clone()),
-                        ..field.column
-                    };
-
-                    ColumnSpecification { column, ..field }
-                })
-                .collect();
-
-            // and to keys:
-            let named_keys = keys.and_then(|ks| {
-                Some(
-                    ks.into_iter()
-                        .map(|key| {
-                            let attach_names = |columns: Vec<Column>| {
-                                columns
-                                    .into_iter()
-                                    .map(|column| Column {
-                                        table: Some(table.name.clone()),
-                                        ..column
-                                    })
-                                    .collect()
-                            };
-
-                            match key {
-                                TableKey::PrimaryKey(columns) => {
-                                    TableKey::PrimaryKey(attach_names(columns))
-                                }
-                                TableKey::UniqueKey(name, columns) => {
-                                    TableKey::UniqueKey(name, attach_names(columns))
-                                }
-                                TableKey::FulltextKey(name, columns) => {
-                                    TableKey::FulltextKey(name, attach_names(columns))
-                                }
-                                TableKey::Key(name, columns) => {
-                                    TableKey::Key(name, attach_names(columns))
-                                }
-                            }
-                        })
-                        .collect(),
-                )
-            });
+/// TODO(malte): support types, TEMPORARY tables, AS stmt
+pub fn creation(i: &str) -> IResult<&str, CreateTableStatement> {
+    let (
+        remaining_input,
+        (
+            _,
+            _,
+            _,
+            if_not_exists,
+            table,
+            _,
+            _,
+            _,
+            fields,
+            _,
+            keys,
+            _,
+            fkeys,
+            _,
+            _,
+            without_rowid,
+            _,
+            _,
+            partitioning,
+            _,
+        ),
+    ) = tuple((
+        tag_no_case("create"),
+        whitespace1,
+        tag_no_case("table"),
+        if_not_exists,
+        alt((
+            table_reference,
+            map(double_quoted_identifier, |name| Table::from(&*name)),
+        )),
+        whitespace0,
+        tag("("),
+        whitespace0,
+        field_specification_list,
+        whitespace0,
+        opt(key_specification_list),
+        whitespace0,
+        opt(foreign_key_specification_list),
+        whitespace0,
+        tag(")"),
+        opt(preceded(
+            whitespace0,
+            tuple((tag_no_case("without"), whitespace1, tag_no_case("rowid"))),
+        )),
+        whitespace0,
+        table_options,
+        opt(preceded(whitespace0, partition_scheme)),
+        statement_terminator,
+    ))(i)?;
 
-            CreateTableStatement {
-                table: table,
-                fields: named_fields,
-                keys: named_keys,
-                fkeys: fkeys,
-            }
+    // "table AS alias" isn't legal in CREATE statements
+    if table.alias.is_some() {
+        return Err(nom::Err::Error(Error::new(i, ErrorKind::Verify)));
+    }
+    // attach table names to columns:
+    let fields = fields
+        .into_iter()
+        .map(|field| {
+            let column = Column {
+                table: Some(table.name.clone()),
+                ..field.column
+            };
+
+            ColumnSpecification { column, ..field }
         })
-    )
-);
+        .collect();
+
+    // and to keys:
+    let keys = keys.map(|ks| {
+        ks.into_iter()
+            .map(|key| {
+                let attach_names = |columns: Vec<KeyPart>| {
+                    columns
+                        .into_iter()
+                        .map(|part| match part {
+                            KeyPart::Column(column, length, order) => KeyPart::Column(
+                                Column {
+                                    table: Some(table.name.clone()),
+                                    ..column
+                                },
+                                length,
+                                order,
+                            ),
+                            expr => expr,
+                        })
+                        .collect()
+                };
+
+                match key {
+                    TableKey::PrimaryKey(name, columns, options, autoincrement) => {
+                        TableKey::PrimaryKey(name, attach_names(columns), options, autoincrement)
+                    }
+                    TableKey::UniqueKey(name, columns, options) => {
+                        TableKey::UniqueKey(name, attach_names(columns), options)
+                    }
+                    TableKey::FulltextKey(name, columns, options) => {
+                        TableKey::FulltextKey(name, attach_names(columns), options)
+                    }
+                    TableKey::SpatialKey(name, columns, options) => {
+                        TableKey::SpatialKey(name, attach_names(columns), options)
+                    }
+                    TableKey::Key(name, columns, options) => {
+                        TableKey::Key(name, attach_names(columns), options)
+                    }
+                }
+            })
+            .collect()
+    });
+
+    Ok((
+        remaining_input,
+        CreateTableStatement {
+            table,
+            fields,
+            keys,
+            fkeys,
+            partitioning,
+            if_not_exists,
+            without_rowid: without_rowid.is_some(),
+        },
+    ))
+}
 
 /// Parse rule for a SQL CREATE VIEW query.
-named!(pub view_creation<CompleteByteSlice, CreateViewStatement>,
-    do_parse!(
-        tag_no_case!("create") >>
-        multispace >>
-        tag_no_case!("view") >>
-        multispace >>
-        name: sql_identifier >>
-        multispace >>
-        tag_no_case!("as") >>
-        multispace >>
-        definition: alt!(
-              map!(compound_selection, |s| SelectSpecification::Compound(s))
-            | map!(nested_selection, |s| SelectSpecification::Simple(s))
-        ) >>
-        statement_terminator >>
-        ({
-            CreateViewStatement {
-                name: String::from_utf8(name.to_vec()).unwrap(),
-                fields: vec![],  // TODO(malte): support
-                definition: Box::new(definition),
-            }
-        })
-    )
-);
+fn view_algorithm(i: &str) -> IResult<&str, ViewAlgorithm> {
+    preceded(
+        tuple((tag_no_case("algorithm"), whitespace0, tag("="), whitespace0)),
+        alt((
+            value(ViewAlgorithm::Undefined, tag_no_case("undefined")),
+            value(ViewAlgorithm::Merge, tag_no_case("merge")),
+            value(ViewAlgorithm::TempTable, tag_no_case("temptable")),
+        )),
+    )(i)
+}
+
+/// A MySQL account name, `user[@host]`, where either part may be quoted, or `CURRENT_USER`.
+fn account_name(i: &str) -> IResult<&str, &str> {
+    let part = || {
+        alt((
+            delimited(tag("'"), take_until("'"), tag("'")),
+            delimited(tag("\""), take_until("\""), tag("\"")),
+            delimited(tag("`"), take_until("`"), tag("`")),
+            take_while1(|c: char| is_sql_identifier(c) || c == '.' || c == '%' || c == '-'),
+        ))
+    };
+    alt((
+        recognize(pair(tag_no_case("current_user"), opt(tag("()")))),
+        recognize(pair(part(), opt(pair(tag("@"), part())))),
+    ))(i)
+}
+
+fn view_definer(i: &str) -> IResult<&str, &str> {
+    preceded(
+        tuple((tag_no_case("definer"), whitespace0, tag("="), whitespace0)),
+        account_name,
+    )(i)
+}
+
+fn view_security(i: &str) -> IResult<&str, ViewSecurity> {
+    preceded(
+        tuple((
+            tag_no_case("sql"),
+            whitespace1,
+            tag_no_case("security"),
+            whitespace1,
+        )),
+        alt((
+            value(ViewSecurity::Definer, tag_no_case("definer")),
+            value(ViewSecurity::Invoker, tag_no_case("invoker")),
+        )),
+    )(i)
+}
+
+fn view_check_option(i: &str) -> IResult<&str, ViewCheckOption> {
+    delimited(
+        pair(tag_no_case("with"), whitespace1),
+        alt((
+            value(
+                ViewCheckOption::Cascaded,
+                terminated(tag_no_case("cascaded"), whitespace1),
+            ),
+            value(
+                ViewCheckOption::Local,
+                terminated(tag_no_case("local"), whitespace1),
+            ),
+            value(ViewCheckOption::Unqualified, tag("")),
+        )),
+        tuple((tag_no_case("check"), whitespace1, tag_no_case("option"))),
+    )(i)
+}
+
+/// The names given to the columns of a view, `(a, b, ...)`.
+pub fn view_columns(i: &str) -> IResult<&str, Vec<Column>> {
+    delimited(
+        pair(tag("("), whitespace0),
+        separated_list1(ws_sep_comma, plain_column_identifier),
+        pair(whitespace0, tag(")")),
+    )(i)
+}
+
+/// The definition of a view, which may be a compound selection.
+pub fn select_specification(i: &str) -> IResult<&str, SelectSpecification> {
+    alt((
+        map(compound_selection, SelectSpecification::Compound),
+        map(select_statement, SelectSpecification::Simple),
+    ))(i)
+}
+
+pub fn view_creation(i: &str) -> IResult<&str, CreateViewStatement> {
+    let (
+        remaining_input,
+        (
+            _,
+            or_replace,
+            algorithm,
+            definer,
+            security,
+            _,
+            _,
+            name,
+            fields,
+            _,
+            _,
+            _,
+            definition,
+            check_option,
+            _,
+        ),
+    ) = tuple((
+        tag_no_case("create"),
+        opt(tuple((
+            whitespace1,
+            tag_no_case("or"),
+            whitespace1,
+            tag_no_case("replace"),
+        ))),
+        opt(preceded(whitespace1, view_algorithm)),
+        opt(preceded(whitespace1, view_definer)),
+        opt(preceded(whitespace1, view_security)),
+        whitespace1,
+        tag_no_case("view"),
+        preceded(whitespace1, sql_identifier),
+        opt(preceded(whitespace0, view_columns)),
+        whitespace1,
+        tag_no_case("as"),
+        whitespace1,
+        select_specification,
+        opt(preceded(whitespace1, view_check_option)),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        CreateViewStatement {
+            name: String::from(name),
+            fields: fields.unwrap_or_default(),
+            definition: Box::new(definition),
+            or_replace: or_replace.is_some(),
+            algorithm,
+            definer: definer.map(String::from),
+            security,
+            check_option,
+        },
+    ))
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use column::Column;
+    use column::{Column, FunctionExpression};
+    use common::TypeAttributes;
+    use dialect::{with_dialect, Dialect};
+    use format::QuoteStyle;
+    use sequence::SequenceOptions;
     use table::Table;
 
     #[test]
@@ -558,10 +1250,119 @@ mod tests {
         let type0 = "bigint(20) unsigned";
         let type1 = "varchar(255) binary";
 
-        let res = type_identifier(CompleteByteSlice(type0.as_bytes()));
+        let res = type_identifier(type0);
         assert_eq!(res.unwrap().1, SqlType::Bigint(20));
-        let res = type_identifier(CompleteByteSlice(type1.as_bytes()));
+        let res = type_identifier(type1);
         assert_eq!(res.unwrap().1, SqlType::Varchar(255));
+        let (sql_type, attributes) = type_with_attributes("int(10) UNSIGNED zerofill").unwrap().1;
+        assert_eq!(sql_type, SqlType::Int(10));
+        assert_eq!(
+            attributes,
+            TypeAttributes {
+                unsigned: true,
+                zerofill: true,
+                binary: false,
+            }
+        );
+        assert!(type_with_attributes("bigint signed")
+            .unwrap()
+            .1
+             .1
+            .is_empty());
+
+        let qstring =
+            "CREATE TABLE t (id BIGINT(20) UNSIGNED ZEROFILL NOT NULL, name VARCHAR(255) BINARY)";
+        assert_eq!(creation(qstring).unwrap().1.to_string(), qstring);
+        assert_eq!(type_identifier("json").unwrap().1, SqlType::Json);
+        assert_eq!(type_identifier("JSONB").unwrap().1, SqlType::Jsonb);
+        assert_eq!(
+            type_identifier("integer[]").unwrap().1,
+            SqlType::Array(Box::new(SqlType::Int(32)))
+        );
+        let matrix = type_identifier("text[3][]").unwrap().1;
+        assert_eq!(
+            matrix,
+            SqlType::Array(Box::new(SqlType::Array(Box::new(SqlType::Text))))
+        );
+        assert_eq!(format!("{}", matrix), "TEXT[][]");
+    }
+
+    #[test]
+    fn quoted_names_with_spaces() {
+        let qstring =
+            "CREATE TABLE `my table` (`my col` int, `odd``name` text, KEY `my key` (`my col`))";
+        let res = creation(qstring).unwrap().1;
+        assert_eq!(res.table.name, "my table");
+        let names: Vec<_> = res
+            .fields
+            .iter()
+            .map(|f| f.column.name.to_string())
+            .collect();
+        assert_eq!(names, vec!["my col", "odd`name"]);
+        let printed = res.to_string();
+        assert_eq!(
+            printed,
+            "CREATE TABLE `my table` (`my col` INT(32), `odd``name` TEXT, KEY `my key` (`my col`))"
+        );
+        assert_eq!(creation(&printed).unwrap().1, res);
+    }
+
+    #[test]
+    fn negative_default_values() {
+        let qstring = "CREATE TABLE t (a int DEFAULT -1, b decimal(4,2) DEFAULT -0.50)";
+        let res = creation(qstring).unwrap().1;
+        let defaults: Vec<_> = res.fields.iter().map(|f| f.constraints.clone()).collect();
+        assert_eq!(
+            defaults,
+            vec![
+                vec![ColumnConstraint::DefaultValue(Literal::Integer(-1))],
+                vec![ColumnConstraint::DefaultValue(Literal::FixedPoint(
+                    Real::new(-50, 2)
+                ))],
+            ]
+        );
+        let printed = res.to_string();
+        assert_eq!(
+            printed,
+            "CREATE TABLE t (a INT(32) DEFAULT -1, b DECIMAL(4, 2) DEFAULT -0.50)"
+        );
+        assert_eq!(creation(&printed).unwrap().1, res);
+    }
+
+    #[test]
+    fn postgres_builtin_types() {
+        let qstring = "CREATE TABLE hosts (id uuid PRIMARY KEY, addr inet NOT NULL, net CIDR, \
+                       mac macaddr, fingerprint bytea, cost money, aliases inet[], \
+                       notes tsvector, filter tsquery)";
+        assert!(creation(qstring).is_err());
+        let res = with_dialect(Dialect::Postgres, || creation(qstring))
+            .unwrap()
+            .1;
+        let types: Vec<_> = res
+            .fields
+            .iter()
+            .map(|f| f.sql_type.clone().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                SqlType::Uuid,
+                SqlType::Inet,
+                SqlType::Cidr,
+                SqlType::MacAddr,
+                SqlType::Bytea,
+                SqlType::Money,
+                SqlType::Array(Box::new(SqlType::Inet)),
+                SqlType::TsVector,
+                SqlType::TsQuery,
+            ]
+        );
+        assert_eq!(
+            res.to_string(),
+            "CREATE TABLE hosts (id UUID PRIMARY KEY, addr INET NOT NULL, net CIDR, \
+             mac MACADDR, fingerprint BYTEA, cost MONEY, aliases INET[], \
+             notes TSVECTOR, filter TSQUERY)"
+        );
     }
 
     #[test]
@@ -570,7 +1371,7 @@ mod tests {
         // because it is never validly the end of a query
         let qstring = "id bigint(20), name varchar(255),";
 
-        let res = field_specification_list(CompleteByteSlice(qstring.as_bytes()));
+        let res = field_specification_list(qstring);
         assert_eq!(
             res.unwrap().1,
             vec![
@@ -584,7 +1385,7 @@ mod tests {
     fn simple_create() {
         let qstring = "CREATE TABLE users (id bigint(20), name varchar(255), email varchar(255));";
 
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateTableStatement {
@@ -602,14 +1403,15 @@ mod tests {
     #[test]
     fn create_without_space_after_tablename() {
         let qstring = "CREATE TABLE t(x integer);";
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateTableStatement {
                 table: Table::from("t"),
-                fields: vec![
-                    ColumnSpecification::new(Column::from("t.x"), SqlType::Int(32)),
-                ],
+                fields: vec![ColumnSpecification::new(
+                    Column::from("t.x"),
+                    SqlType::Int(32)
+                ),],
                 ..Default::default()
             }
         );
@@ -619,7 +1421,7 @@ mod tests {
     fn mediawiki_create() {
         let qstring = "CREATE TABLE user_newtalk (  user_id int(5) NOT NULL default '0',  user_ip \
                        varchar(40) NOT NULL default '') TYPE=MyISAM;";
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateTableStatement {
@@ -666,7 +1468,7 @@ mod tests {
                         user_editcount int,
                         user_password_expires varbinary(14) DEFAULT NULL
                        ) ENGINE=, DEFAULT CHARSET=utf8";
-        creation(CompleteByteSlice(qstring.as_bytes())).unwrap();
+        creation(qstring).unwrap();
     }
 
     #[test]
@@ -679,7 +1481,7 @@ mod tests {
  iw_local bool NOT NULL,
  iw_trans tinyint NOT NULL default 0
  ) ENGINE=, DEFAULT CHARSET=utf8";
-        creation(CompleteByteSlice(qstring.as_bytes())).unwrap();
+        creation(qstring).unwrap();
     }
 
     #[test]
@@ -699,7 +1501,7 @@ mod tests {
           KEY `el_index_60` (`el_index_60`,`el_id`),
           KEY `el_from_index_60` (`el_from`,`el_index_60`,`el_id`)
         )";
-        creation(CompleteByteSlice(qstring.as_bytes())).unwrap();
+        creation(qstring).unwrap();
     }
 
     #[test]
@@ -708,7 +1510,7 @@ mod tests {
         let qstring = "CREATE TABLE users (id bigint(20), name varchar(255), email varchar(255), \
                        PRIMARY KEY (id));";
 
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateTableStatement {
@@ -718,7 +1520,12 @@ mod tests {
                     ColumnSpecification::new(Column::from("users.name"), SqlType::Varchar(255)),
                     ColumnSpecification::new(Column::from("users.email"), SqlType::Varchar(255)),
                 ],
-                keys: Some(vec![TableKey::PrimaryKey(vec![Column::from("users.id")])]),
+                keys: Some(vec![TableKey::PrimaryKey(
+                    None,
+                    vec![Column::from("users.id").into()],
+                    IndexOptions::default(),
+                    false,
+                )]),
                 ..Default::default()
             }
         );
@@ -727,7 +1534,7 @@ mod tests {
         let qstring = "CREATE TABLE users (id bigint(20), name varchar(255), email varchar(255), \
                        UNIQUE KEY id_k (id));";
 
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateTableStatement {
@@ -739,8 +1546,9 @@ mod tests {
                 ],
                 keys: Some(vec![TableKey::UniqueKey(
                     Some(String::from("id_k")),
-                    vec![Column::from("users.id")],
-                ), ]),
+                    vec![Column::from("users.id").into()],
+                    IndexOptions::default(),
+                ),]),
                 ..Default::default()
             }
         );
@@ -757,11 +1565,14 @@ mod tests {
                        `object_repr` varchar(200) NOT NULL,
                        `action_flag` smallint UNSIGNED NOT NULL,
                        `change_message` longtext NOT NULL);";
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateTableStatement {
-                table: Table::from("django_admin_log"),
+                table: Table {
+                    quote: Some(QuoteStyle::Backtick),
+                    ..Table::from("django_admin_log")
+                },
                 fields: vec![
                     ColumnSpecification::with_constraints(
                         Column::from("django_admin_log.id"),
@@ -795,11 +1606,17 @@ mod tests {
                         SqlType::Varchar(200),
                         vec![ColumnConstraint::NotNull],
                     ),
-                    ColumnSpecification::with_constraints(
-                        Column::from("django_admin_log.action_flag"),
-                        SqlType::Int(32),
-                        vec![ColumnConstraint::NotNull],
-                    ),
+                    ColumnSpecification {
+                        type_attributes: TypeAttributes {
+                            unsigned: true,
+                            ..Default::default()
+                        },
+                        ..ColumnSpecification::with_constraints(
+                            Column::from("django_admin_log.action_flag"),
+                            SqlType::Int(32),
+                            vec![ColumnConstraint::NotNull],
+                        )
+                    },
                     ColumnSpecification::with_constraints(
                         Column::from("django_admin_log.change_message"),
                         SqlType::Longtext,
@@ -813,11 +1630,14 @@ mod tests {
         let qstring = "CREATE TABLE `auth_group` (
                        `id` integer AUTO_INCREMENT NOT NULL PRIMARY KEY,
                        `name` varchar(80) NOT NULL UNIQUE)";
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateTableStatement {
-                table: Table::from("auth_group"),
+                table: Table {
+                    quote: Some(QuoteStyle::Backtick),
+                    ..Table::from("auth_group")
+                },
                 fields: vec![
                     ColumnSpecification::with_constraints(
                         Column::from("auth_group.id"),
@@ -845,10 +1665,10 @@ mod tests {
                        `id` integer AUTO_INCREMENT NOT NULL PRIMARY KEY,
                        `name` varchar(80) NOT NULL UNIQUE)";
         // TODO(malte): INTEGER isn't quite reflected right here, perhaps
-        let expected = "CREATE TABLE auth_group (\
+        let expected = "CREATE TABLE `auth_group` (\
                         id INT(32) AUTO_INCREMENT NOT NULL PRIMARY KEY, \
                         name VARCHAR(80) NOT NULL UNIQUE)";
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(format!("{}", res.unwrap().1), expected);
     }
 
@@ -859,7 +1679,7 @@ mod tests {
 
         let qstring = "CREATE VIEW v AS SELECT * FROM users WHERE username = \"bob\";";
 
-        let res = view_creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = view_creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateViewStatement {
@@ -879,6 +1699,11 @@ mod tests {
                     })),
                     ..Default::default()
                 })),
+                or_replace: false,
+                algorithm: None,
+                definer: None,
+                security: None,
+                check_option: None,
             }
         );
     }
@@ -890,7 +1715,7 @@ mod tests {
 
         let qstring = "CREATE VIEW v AS SELECT * FROM users UNION SELECT * FROM old_users;";
 
-        let res = view_creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = view_creation(qstring);
         assert_eq!(
             res.unwrap().1,
             CreateViewStatement {
@@ -918,6 +1743,11 @@ mod tests {
                     order: None,
                     limit: None,
                 })),
+                or_replace: false,
+                algorithm: None,
+                definer: None,
+                security: None,
+                check_option: None,
             }
         );
     }
@@ -925,23 +1755,83 @@ mod tests {
     #[test]
     fn format_create_view() {
         let qstring = "CREATE VIEW `v` AS SELECT * FROM `t`;";
-        let expected = "CREATE VIEW v AS SELECT * FROM t";
-        let res = view_creation(CompleteByteSlice(qstring.as_bytes()));
+        let expected = "CREATE VIEW v AS SELECT * FROM `t`";
+        let res = view_creation(qstring);
         assert_eq!(format!("{}", res.unwrap().1), expected);
     }
 
+    #[test]
+    fn create_view_column_list() {
+        let qstring = "CREATE VIEW v(uid, `name`) AS SELECT id, name FROM users";
+        let res = view_creation(qstring).unwrap().1;
+        assert_eq!(res.fields, vec![Column::from("uid"), Column::from("name")]);
+        assert_eq!(
+            format!("{}", res),
+            "CREATE VIEW v (uid, name) AS SELECT id, name FROM users"
+        );
+        assert!(view_creation("CREATE VIEW v () AS SELECT id FROM users").is_err());
+    }
+
+    #[test]
+    fn create_view_options() {
+        let qstring = "CREATE ALGORITHM=UNDEFINED DEFINER=`root`@`localhost` SQL SECURITY DEFINER \
+                       VIEW `v` AS select `t`.`id` AS `id` from `t` WITH CASCADED CHECK OPTION";
+        let res = view_creation(qstring).unwrap().1;
+        assert!(!res.or_replace);
+        assert_eq!(res.algorithm, Some(ViewAlgorithm::Undefined));
+        assert_eq!(res.definer, Some(String::from("`root`@`localhost`")));
+        assert_eq!(res.security, Some(ViewSecurity::Definer));
+        assert_eq!(res.check_option, Some(ViewCheckOption::Cascaded));
+        assert_eq!(
+            format!("{}", res),
+            "CREATE ALGORITHM=UNDEFINED DEFINER=`root`@`localhost` SQL SECURITY DEFINER \
+             VIEW v AS SELECT t.id AS id FROM `t` WITH CASCADED CHECK OPTION"
+        );
+
+        let qstring = "create or replace algorithm = merge definer = 'app'@'%' \
+                       sql security invoker view v as select id from t with check option;";
+        let res = view_creation(qstring).unwrap().1;
+        assert!(res.or_replace);
+        assert_eq!(res.check_option, Some(ViewCheckOption::Unqualified));
+        assert_eq!(
+            format!("{}", res),
+            "CREATE OR REPLACE ALGORITHM=MERGE DEFINER='app'@'%' SQL SECURITY INVOKER \
+             VIEW v AS SELECT id FROM t WITH CHECK OPTION"
+        );
+        assert_eq!(
+            view_creation("CREATE DEFINER=CURRENT_USER VIEW v AS SELECT id FROM t")
+                .unwrap()
+                .1
+                .definer,
+            Some(String::from("CURRENT_USER"))
+        );
+    }
+
     #[test]
     fn table_foreign_key_spec() {
         let qstring = "FOREIGN KEY(this1, this2) REFERENCES that_table(that1, that2),FOREIGN KEY(this3) REFERENCES that_table2(that3),";
 
-        let res = foreign_key_specification_list(CompleteByteSlice(qstring.as_bytes()));
-        let resclone = res.clone();
-        println!("{:?}", resclone);
+        let res = foreign_key_specification_list(qstring);
+        println!("{:?}", res);
         assert_eq!(
             res.unwrap().1,
             vec![
-                ForeignKeySpecification::new(None, None, vec![Column::from("this1"), Column::from("this2")], Table::from("that_table"), vec![Column::from("that1"), Column::from("that2")]),
-                ForeignKeySpecification::new(None, None, vec![Column::from("this3")], Table::from("that_table2"), vec![Column::from("that3")]),
+                ForeignKeySpecification::new(
+                    None,
+                    None,
+                    None,
+                    vec![Column::from("this1"), Column::from("this2")],
+                    Table::from("that_table"),
+                    vec![Column::from("that1"), Column::from("that2")]
+                ),
+                ForeignKeySpecification::new(
+                    None,
+                    None,
+                    None,
+                    vec![Column::from("this3")],
+                    Table::from("that_table2"),
+                    vec![Column::from("that3")]
+                ),
             ]
         );
     }
@@ -952,11 +1842,11 @@ mod tests {
                        `id` integer AUTO_INCREMENT NOT NULL PRIMARY KEY,
                        `name` varchar(80) NOT NULL UNIQUE,
                        FOREIGN KEY(`name`) REFERENCES artist(`name`))";
-        let expected = "CREATE TABLE auth_group (\
+        let expected = "CREATE TABLE `auth_group` (\
                         id INT(32) AUTO_INCREMENT NOT NULL PRIMARY KEY, \
                         name VARCHAR(80) NOT NULL UNIQUE, \
                         FOREIGN KEY(name) REFERENCES artist(name))";
-        let res = creation(CompleteByteSlice(qstring.as_bytes()));
+        let res = creation(qstring);
         assert_eq!(format!("{}", res.unwrap().1), expected);
     }
 
@@ -964,7 +1854,7 @@ mod tests {
     fn foreign_key() {
         let qstring = "FOREIGN KEY(`name`) REFERENCES artist(`name`)";
         let expected = "FOREIGN KEY(name) REFERENCES artist(name)";
-        let res = foreign_key_specification_list(CompleteByteSlice(qstring.as_bytes()));
+        let res = foreign_key_specification_list(qstring);
         assert_eq!(format!("{}", res.unwrap().1[0]), expected);
     }
 
@@ -972,7 +1862,7 @@ mod tests {
     fn foreign_key2() {
         let qstring = "FOREIGN KEY   (   `name`   )    REFERENCES   artist    (  `name`  )";
         let expected = "FOREIGN KEY(name) REFERENCES artist(name)";
-        let res = foreign_key_specification_list(CompleteByteSlice(qstring.as_bytes()));
+        let res = foreign_key_specification_list(qstring);
         assert_eq!(format!("{}", res.unwrap().1[0]), expected);
     }
 
@@ -980,7 +1870,403 @@ mod tests {
     fn foreign_key3() {
         let qstring = "CONSTRAINT fk_name FOREIGN KEY(`name`) REFERENCES artist(`name`)";
         let expected = "CONSTRAINT fk_name FOREIGN KEY(name) REFERENCES artist(name)";
-        let res = foreign_key_specification_list(CompleteByteSlice(qstring.as_bytes()));
+        let res = foreign_key_specification_list(qstring);
         assert_eq!(format!("{}", res.unwrap().1[0]), expected);
+
+        let qstring = "FOREIGN KEY(a) REFERENCES b(c) ON DELETE RESTRICT";
+        let res = foreign_key_specification_list(qstring);
+        assert_eq!(format!("{}", res.unwrap().1[0]), qstring);
+
+        let qstring = "FOREIGN KEY(a) REFERENCES b(c) on update cascade ON DELETE SET NULL";
+        let res = foreign_key_specification_list(qstring).unwrap().1;
+        assert_eq!(res[0].on_delete, Some(ReferentialAction::SetNull));
+        assert_eq!(res[0].on_update, Some(ReferentialAction::Cascade));
+        assert_eq!(
+            format!("{}", res[0]),
+            "FOREIGN KEY(a) REFERENCES b(c) ON DELETE SET NULL ON UPDATE CASCADE"
+        );
+    }
+
+    #[test]
+    fn key_index_options() {
+        let qstring = "CREATE TABLE t (a int, b int, PRIMARY KEY (a) USING BTREE, \
+                       KEY b_idx USING HASH (b) KEY_BLOCK_SIZE=8 COMMENT 'lookup' INVISIBLE)";
+        let res = creation(qstring).unwrap().1;
+        assert_eq!(
+            res.keys,
+            Some(vec![
+                TableKey::PrimaryKey(
+                    None,
+                    vec![Column::from("t.a").into()],
+                    IndexOptions {
+                        index_type: Some(IndexType::BTree),
+                        ..Default::default()
+                    },
+                    false,
+                ),
+                TableKey::Key(
+                    String::from("b_idx"),
+                    vec![Column::from("t.b").into()],
+                    IndexOptions {
+                        index_type: Some(IndexType::Hash),
+                        key_block_size: Some(8),
+                        comment: Some(String::from("lookup")),
+                        invisible: true,
+                    },
+                ),
+            ])
+        );
+        assert_eq!(
+            format!("{}", res.keys.unwrap()[1]),
+            "KEY b_idx (b) USING HASH KEY_BLOCK_SIZE=8 COMMENT 'lookup' INVISIBLE"
+        );
+    }
+
+    #[test]
+    fn non_reserved_keyword_columns() {
+        let qstring = "CREATE TABLE events (status varchar(10), value text, \
+                       timestamp timestamp, action int, end int, offset int)";
+        let res = creation(qstring).unwrap().1;
+        let names: Vec<_> = res.fields.iter().map(|f| f.column.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["status", "value", "timestamp", "action", "end", "offset"]
+        );
+
+        // Reserved words still need quoting.
+        assert!(creation("CREATE TABLE t (rank int)").is_err());
+        assert!(creation("CREATE TABLE t (`rank` int)").is_ok());
+    }
+
+    #[test]
+    fn primary_key_autoincrement() {
+        let qstring = "CREATE TABLE t (id integer, name text, PRIMARY KEY (id) AUTOINCREMENT)";
+        let res = creation(qstring).unwrap().1;
+        assert_eq!(
+            res.keys,
+            Some(vec![TableKey::PrimaryKey(
+                None,
+                vec![Column::from("t.id").into()],
+                IndexOptions::default(),
+                true,
+            )])
+        );
+        let sql = res.to_string();
+        assert_eq!(
+            sql,
+            "CREATE TABLE t (id INT(32), name TEXT, PRIMARY KEY (id) AUTOINCREMENT)"
+        );
+        assert_eq!(creation(&sql).unwrap().1, res);
+    }
+
+    #[test]
+    fn sqlite_tables() {
+        let qstring = "CREATE TABLE IF NOT EXISTS \"messages\" (\
+                       \"id\" INTEGER PRIMARY KEY AUTOINCREMENT, [thread] int, body, \
+                       \"sent\" timestamp)";
+        let res = creation(qstring).unwrap().1;
+        assert!(res.if_not_exists);
+        assert_eq!(
+            res.table,
+            Table {
+                quote: Some(QuoteStyle::DoubleQuote),
+                ..Table::from("messages")
+            }
+        );
+        assert_eq!(
+            res.fields[0],
+            ColumnSpecification::with_constraints(
+                Column::from("messages.id"),
+                SqlType::Int(32),
+                vec![
+                    ColumnConstraint::PrimaryKey,
+                    ColumnConstraint::Autoincrement,
+                ],
+            )
+        );
+        // typeless columns have no declared type
+        assert_eq!(res.fields[2].column, Column::from("messages.body"));
+        assert_eq!(res.fields[2].sql_type, None);
+        assert_eq!(res.rowid_alias(), Some(&res.fields[0]));
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE IF NOT EXISTS \"messages\" (id INT(32) PRIMARY KEY AUTOINCREMENT, \
+             thread INT(32), body, sent TIMESTAMP)"
+        );
+
+        let qstring = "CREATE TABLE kv (k text, v blob, \"n\" int, PRIMARY KEY (\"n\")) \
+                       WITHOUT ROWID;";
+        let res = creation(qstring).unwrap().1;
+        assert!(res.without_rowid);
+        assert_eq!(res.rowid_alias(), None);
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE kv (k TEXT, v BLOB, n INT(32), PRIMARY KEY (n)) WITHOUT ROWID"
+        );
+        let res = creation("CREATE TABLE kv (k text, n int, PRIMARY KEY (n))");
+        assert_eq!(
+            res.as_ref().unwrap().1.rowid_alias().unwrap().column.name,
+            "n"
+        );
+    }
+
+    #[test]
+    fn inline_references() {
+        let qstring = "CREATE TABLE posts (id int PRIMARY KEY, \
+                       user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE, \
+                       parent_id int references posts on update set null on delete no action)";
+        let res = creation(qstring).unwrap().1;
+        assert_eq!(
+            res.fields[1].constraints,
+            vec![
+                ColumnConstraint::NotNull,
+                ColumnConstraint::References {
+                    table: Table::from("users"),
+                    columns: vec![Column::from("id")],
+                    on_delete: Some(ReferentialAction::Cascade),
+                    on_update: None,
+                },
+            ]
+        );
+        assert_eq!(
+            res.fields[2].constraints,
+            vec![ColumnConstraint::References {
+                table: Table::from("posts"),
+                columns: vec![],
+                on_delete: Some(ReferentialAction::NoAction),
+                on_update: Some(ReferentialAction::SetNull),
+            }]
+        );
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE posts (id INT(32) PRIMARY KEY, \
+             user_id INT(32) NOT NULL REFERENCES users(id) ON DELETE CASCADE, \
+             parent_id INT(32) REFERENCES posts ON DELETE NO ACTION ON UPDATE SET NULL)"
+        );
+    }
+
+    #[test]
+    fn named_composite_primary_key() {
+        let qstring = "CREATE TABLE t (a int, b int, \
+                       CONSTRAINT pk_t PRIMARY KEY (a ASC, b DESC), CONSTRAINT uq_b UNIQUE (b))";
+        let res = creation(qstring).unwrap().1;
+        assert_eq!(
+            res.keys,
+            Some(vec![
+                TableKey::PrimaryKey(
+                    Some(String::from("pk_t")),
+                    vec![
+                        KeyPart::Column(Column::from("t.a"), None, Some(OrderType::OrderAscending)),
+                        KeyPart::Column(
+                            Column::from("t.b"),
+                            None,
+                            Some(OrderType::OrderDescending)
+                        ),
+                    ],
+                    IndexOptions::default(),
+                    false,
+                ),
+                TableKey::UniqueKey(
+                    Some(String::from("uq_b")),
+                    vec![Column::from("t.b").into()],
+                    IndexOptions::default(),
+                ),
+            ])
+        );
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE t (a INT(32), b INT(32), \
+             CONSTRAINT pk_t PRIMARY KEY (a ASC, b DESC), UNIQUE KEY uq_b (b))"
+        );
+
+        // the constraint name is optional
+        let res = creation("CREATE TABLE t (a int, CONSTRAINT PRIMARY KEY (a))");
+        assert_eq!(
+            res.unwrap().1.keys.unwrap()[0],
+            TableKey::PrimaryKey(
+                None,
+                vec![Column::from("t.a").into()],
+                IndexOptions::default(),
+                false,
+            )
+        );
+    }
+
+    #[test]
+    fn wide_table() {
+        let columns: Vec<_> = (0..300)
+            .map(|n| format!("c{} int Not Null Default {} Unique", n, n))
+            .collect();
+        let qstring = format!(
+            "CREATE TABLE t ({}, Primary Key (c0), constraint k1 UNIQUE (c1), Key k2 (c2))",
+            columns.join(", ")
+        );
+        let res = creation(&qstring).unwrap().1;
+        assert_eq!(res.fields.len(), 300);
+        assert_eq!(
+            res.fields[299].constraints,
+            vec![
+                ColumnConstraint::NotNull,
+                ColumnConstraint::DefaultValue(Literal::Integer(299)),
+                ColumnConstraint::Unique,
+            ]
+        );
+        assert_eq!(res.keys.map(|k| k.len()), Some(3));
+    }
+
+    #[test]
+    fn functional_key_parts() {
+        let qstring = "CREATE TABLE users (id int, email varchar(255), \
+                       UNIQUE KEY email_idx ((LOWER(email))), KEY mixed (id, (id * 2) DESC))";
+        let res = creation(qstring).unwrap().1;
+        let expression = |e| Box::new(condition_expr(e).unwrap().1);
+        assert_eq!(
+            res.keys,
+            Some(vec![
+                TableKey::UniqueKey(
+                    Some(String::from("email_idx")),
+                    vec![KeyPart::Expression(expression("LOWER(email)"), None)],
+                    IndexOptions::default(),
+                ),
+                TableKey::Key(
+                    String::from("mixed"),
+                    vec![
+                        Column::from("users.id").into(),
+                        KeyPart::Expression(expression("id * 2"), Some(OrderType::OrderDescending)),
+                    ],
+                    IndexOptions::default(),
+                ),
+            ])
+        );
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE users (id INT(32), email VARCHAR(255), \
+             UNIQUE KEY email_idx ((LOWER(email))), KEY mixed (id, (id * 2) DESC))"
+        );
+        // a column key may index a prefix of the column
+        let res = creation("CREATE TABLE t (name text, KEY name_idx (name(10) DESC))").unwrap();
+        assert_eq!(
+            res.1.keys.as_ref().unwrap()[0],
+            TableKey::Key(
+                String::from("name_idx"),
+                vec![KeyPart::Column(
+                    Column::from("t.name"),
+                    Some(10),
+                    Some(OrderType::OrderDescending)
+                )],
+                IndexOptions::default(),
+            )
+        );
+        assert_eq!(
+            format!("{}", res.1),
+            "CREATE TABLE t (name TEXT, KEY name_idx (name(10) DESC))"
+        );
+    }
+
+    #[test]
+    fn partitioned_table() {
+        let qstring = "CREATE TABLE events (id int, ts int) ENGINE=InnoDB \
+                       PARTITION BY HASH (id) PARTITIONS 4;";
+        let res = creation(qstring).unwrap().1;
+        assert!(res.partitioning.is_some());
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE events (id INT(32), ts INT(32)) PARTITION BY HASH (id) PARTITIONS 4"
+        );
+    }
+
+    #[test]
+    fn spatial_columns_and_key() {
+        let qstring = "CREATE TABLE places (id int, location point NOT NULL, area polygon, \
+                       SPATIAL INDEX loc_idx (location))";
+        let res = creation(qstring).unwrap().1;
+        assert_eq!(res.fields[1].sql_type, Some(SqlType::Point));
+        assert_eq!(res.fields[2].sql_type, Some(SqlType::Polygon));
+        assert_eq!(
+            res.keys,
+            Some(vec![TableKey::SpatialKey(
+                Some(String::from("loc_idx")),
+                vec![Column::from("places.location").into()],
+                IndexOptions::default(),
+            )])
+        );
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE places (id INT(32), location POINT NOT NULL, area POLYGON, \
+             SPATIAL KEY loc_idx (location))"
+        );
+    }
+
+    #[test]
+    fn postgres_serial_and_identity() {
+        let qstring = "CREATE TABLE orders (\n    id bigserial PRIMARY KEY,\n    \
+                       ext_id integer GENERATED BY DEFAULT AS IDENTITY (START WITH 10 INCREMENT BY 5),\n    \
+                       seq integer DEFAULT nextval('public.orders_seq_seq'::regclass) NOT NULL,\n    \
+                       status text DEFAULT 'new'::character varying,\n    \
+                       code int GENERATED ALWAYS AS IDENTITY\n);";
+        let res = creation(qstring).unwrap().1;
+        assert_eq!(res.fields[0].sql_type, Some(SqlType::Bigserial));
+        assert_eq!(
+            res.fields[1].constraints,
+            vec![ColumnConstraint::Identity(
+                IdentityGeneration::ByDefault,
+                SequenceOptions {
+                    start: Some(10),
+                    increment: Some(5),
+                    ..SequenceOptions::default()
+                },
+            )]
+        );
+        assert_eq!(
+            res.fields[2].constraints,
+            vec![
+                ColumnConstraint::DefaultExpression(FunctionExpression::NextVal(String::from(
+                    "public.orders_seq_seq"
+                ))),
+                ColumnConstraint::NotNull,
+            ]
+        );
+        assert_eq!(
+            res.fields[3].constraints,
+            vec![ColumnConstraint::DefaultValue(Literal::from("new"))]
+        );
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE orders (id BIGSERIAL PRIMARY KEY, \
+             ext_id INT(32) GENERATED BY DEFAULT AS IDENTITY (START WITH 10 INCREMENT BY 5), \
+             seq INT(32) DEFAULT nextval('public.orders_seq_seq') NOT NULL, \
+             status TEXT DEFAULT 'new', code INT(32) GENERATED ALWAYS AS IDENTITY)"
+        );
+        assert_eq!(type_identifier("serial").unwrap().1, SqlType::Serial);
+    }
+
+    #[test]
+    fn statement_skeletons() {
+        let create = creation(
+            "CREATE TABLE items (shop int, sku int, name text, \
+             n int GENERATED ALWAYS AS IDENTITY, PRIMARY KEY (shop, sku))",
+        )
+        .unwrap()
+        .1;
+        assert_eq!(
+            create.insert_skeleton().to_string(),
+            "INSERT INTO items (shop, sku, name) VALUES (?, ?, ?)"
+        );
+        assert_eq!(
+            create.update_by_key_skeleton().unwrap().to_string(),
+            "UPDATE items SET name = ? WHERE shop = ? AND sku = ?"
+        );
+        assert_eq!(
+            create.select_by_key_skeleton().unwrap().to_string(),
+            "SELECT shop, sku, name, n FROM items WHERE shop = ? AND sku = ?"
+        );
+
+        let create = creation("CREATE TABLE tags (id int PRIMARY KEY)")
+            .unwrap()
+            .1;
+        assert_eq!(create.primary_key(), Some(vec![Column::from("tags.id")]));
+        assert_eq!(create.update_by_key_skeleton(), None);
+        let create = creation("CREATE TABLE log (msg text)").unwrap().1;
+        assert_eq!(create.select_by_key_skeleton(), None);
     }
 }
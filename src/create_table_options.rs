@@ -1,190 +1,158 @@
-use nom::{alphanumeric, multispace};
-use nom::types::CompleteByteSlice;
-
-use common::{
-    integer_literal, opt_multispace, sql_identifier, string_literal,
-};
-
-named!(pub table_options<CompleteByteSlice, ()>, do_parse!(
-       separated_list!(table_options_separator, create_option)
-        >>
-        (
-            // TODO: make the create options accessible
-            ()
-        )
-));
-
-named!(table_options_separator<CompleteByteSlice, ()>, do_parse!(
-    alt!(
-        map!(multispace, |_| ()) |
-        do_parse!(
-            opt_multispace >>
-            tag!(",") >>
-            opt_multispace >>
-            ()
-        )
-    ) >> ()
-));
-
-named!(create_option<CompleteByteSlice, ()>, alt!(
-        create_option_type |
-        create_option_pack_keys |
-        create_option_engine |
-        create_option_auto_increment |
-        create_option_default_charset |
-        create_option_collate |
-        create_option_comment |
-        create_option_max_rows |
-        create_option_avg_row_length |
-        create_option_row_format |
-        create_option_key_block_size
-));
-
-named!(create_option_type<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("type") >>
-        opt_multispace >>
-        tag!("=") >>
-        opt_multispace >>
-        alphanumeric >>
-        ()
-    )
-);
-
-named!(create_option_pack_keys<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("pack_keys") >>
-        opt_multispace >>
-        tag!("=") >>
-        opt_multispace >>
-        alt!(tag!("0") | tag!("1")) >>
-        ()
-    )
-);
-
-named!(create_option_engine<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("engine") >>
-        opt_multispace >>
-        tag!("=") >>
-        opt_multispace >>
-        opt!(alphanumeric) >>
-        ()
-    )
-);
-
-named!(create_option_auto_increment<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("auto_increment") >>
-        opt_multispace >>
-        tag!("=") >>
-        opt_multispace >>
-        integer_literal >>
-        ()
-    )
-);
-
-named!(create_option_default_charset<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("default charset") >>
-        opt_multispace >>
-        tag!("=") >>
-        opt_multispace >>
-        alt!(
-            tag!("utf8mb4") |
-            tag!("utf8") |
-            tag!("binary") |
-            tag!("big5") |
-            tag!("ucs2") |
-            tag!("latin1")
-            ) >>
-        ()
-    )
-);
-
-named!(create_option_collate<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("collate") >>
-        opt_multispace >>
-        tag!("=") >>
-        opt_multispace >>
-        // TODO(malte): imprecise hack, should not accept everything
-        sql_identifier >>
-        ()
-    )
-);
-
-named!(create_option_comment<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("comment") >>
-        opt_multispace >>
-        tag!("=") >>
-        opt_multispace >>
-        string_literal >>
-        ()
-    )
-);
-
-named!(create_option_max_rows<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("max_rows") >>
-        opt_multispace >>
-        opt!(tag!("=")) >>
-        opt_multispace >>
-        integer_literal >>
-        ()
-    )
-);
-
-named!(create_option_avg_row_length<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("avg_row_length") >>
-        opt_multispace >>
-        opt!(tag!("=")) >>
-        opt_multispace >>
-        integer_literal >>
-        ()
-    )
-);
-
-named!(create_option_row_format<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("row_format") >>
-        opt_multispace >>
-        opt!(tag!("=")) >>
-        opt_multispace >>
-        alt!(
-            tag_no_case!("DEFAULT")|
-            tag_no_case!("DYNAMIC") |
-            tag_no_case!("FIXED") |
-            tag_no_case!("COMPRESSED") |
-            tag_no_case!("REDUNDANT") |
-            tag_no_case!("COMPACT")
-        ) >>
-        ()
-    )
-);
-
-named!(create_option_key_block_size<CompleteByteSlice, ()>,
-    do_parse!(
-        tag_no_case!("key_block_size") >>
-        opt_multispace >>
-        opt!(tag!("=")) >>
-        opt_multispace >>
-        integer_literal >>
-        ()
-    )
-);
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{alphanumeric1, multispace0, multispace1};
+use nom::combinator::{map, opt, value};
+use nom::multi::separated_list0;
+use nom::sequence::tuple;
+use nom::IResult;
+
+use common::{integer_literal, sql_identifier, string_literal, ws_sep_comma};
+use keywords::tag_no_case;
+
+pub fn table_options(i: &str) -> IResult<&str, ()> {
+    // TODO: make the create options accessible
+    map(
+        separated_list0(table_options_separator, create_option),
+        |_| (),
+    )(i)
+}
+
+fn table_options_separator(i: &str) -> IResult<&str, ()> {
+    map(alt((multispace1, ws_sep_comma)), |_| ())(i)
+}
+
+fn create_option(i: &str) -> IResult<&str, ()> {
+    alt((
+        create_option_type,
+        create_option_pack_keys,
+        create_option_engine,
+        create_option_auto_increment,
+        create_option_default_charset,
+        create_option_collate,
+        create_option_comment,
+        create_option_max_rows,
+        create_option_avg_row_length,
+        create_option_row_format,
+        create_option_key_block_size,
+    ))(i)
+}
+
+/// Parses `name = ` (or `name ` if `optional_eq` is set) ahead of a table option value.
+fn option_name<'a>(
+    name: &'static str,
+    optional_eq: bool,
+) -> impl FnMut(&'a str) -> IResult<&'a str, ()> {
+    move |i: &'a str| {
+        let (i, _) = tag_no_case(name)(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, _) = if optional_eq {
+            opt(tag("="))(i)?
+        } else {
+            map(tag("="), Some)(i)?
+        };
+        let (i, _) = multispace0(i)?;
+        Ok((i, ()))
+    }
+}
+
+fn create_option_type(i: &str) -> IResult<&str, ()> {
+    value((), tuple((option_name("type", false), alphanumeric1)))(i)
+}
+
+fn create_option_pack_keys(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((option_name("pack_keys", false), alt((tag("0"), tag("1"))))),
+    )(i)
+}
+
+fn create_option_engine(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((option_name("engine", false), opt(alphanumeric1))),
+    )(i)
+}
+
+fn create_option_auto_increment(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((option_name("auto_increment", false), integer_literal)),
+    )(i)
+}
+
+fn create_option_default_charset(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((
+            option_name("default charset", false),
+            alt((
+                tag("utf8mb4"),
+                tag("utf8"),
+                tag("binary"),
+                tag("big5"),
+                tag("ucs2"),
+                tag("latin1"),
+            )),
+        )),
+    )(i)
+}
+
+fn create_option_collate(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((
+            option_name("collate", false),
+            // TODO(malte): imprecise hack, should not accept everything
+            sql_identifier,
+        )),
+    )(i)
+}
+
+fn create_option_comment(i: &str) -> IResult<&str, ()> {
+    value((), tuple((option_name("comment", false), string_literal)))(i)
+}
+
+fn create_option_max_rows(i: &str) -> IResult<&str, ()> {
+    value((), tuple((option_name("max_rows", true), integer_literal)))(i)
+}
+
+fn create_option_avg_row_length(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((option_name("avg_row_length", true), integer_literal)),
+    )(i)
+}
+
+fn create_option_row_format(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((
+            option_name("row_format", true),
+            alt((
+                tag_no_case("default"),
+                tag_no_case("dynamic"),
+                tag_no_case("fixed"),
+                tag_no_case("compressed"),
+                tag_no_case("redundant"),
+                tag_no_case("compact"),
+            )),
+        )),
+    )(i)
+}
+
+fn create_option_key_block_size(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((option_name("key_block_size", true), integer_literal)),
+    )(i)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
     fn should_parse_all(qstring: &str) {
-        assert_eq!(
-            Ok((CompleteByteSlice(&b""[..]), ())),
-            table_options(CompleteByteSlice(qstring.as_bytes()))
-        )
+        assert_eq!(Ok(("", ())), table_options(qstring))
     }
 
     #[test]
@@ -194,8 +162,10 @@ mod tests {
 
     #[test]
     fn create_table_option_list() {
-        should_parse_all("ENGINE=InnoDB AUTO_INCREMENT=44782967 \
-        DEFAULT CHARSET=binary ROW_FORMAT=COMPRESSED KEY_BLOCK_SIZE=8");
+        should_parse_all(
+            "ENGINE=InnoDB AUTO_INCREMENT=44782967 \
+        DEFAULT CHARSET=binary ROW_FORMAT=COMPRESSED KEY_BLOCK_SIZE=8",
+        );
     }
 
     #[test]
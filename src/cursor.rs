@@ -0,0 +1,275 @@
+use nom::branch::alt;
+use nom::character::complete::multispace1;
+use nom::combinator::{map, opt, value};
+use nom::sequence::{preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{sql_identifier, statement_terminator};
+use create::{select_specification, SelectSpecification};
+use keywords::{escape_if_keyword, tag_no_case};
+use sequence::signed_integer;
+
+/// `DECLARE name [[NO] SCROLL] CURSOR [WITH HOLD] FOR query`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct DeclareCursorStatement {
+    pub name: String,
+    /// Whether the cursor may move backwards; `None` leaves it to the server.
+    pub scroll: Option<bool>,
+    /// Whether the cursor outlives the transaction that declared it.
+    pub with_hold: bool,
+    pub query: SelectSpecification,
+}
+
+impl fmt::Display for DeclareCursorStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DECLARE {}", escape_if_keyword(&self.name))?;
+        match self.scroll {
+            Some(true) => write!(f, " SCROLL")?,
+            Some(false) => write!(f, " NO SCROLL")?,
+            None => (),
+        }
+        write!(f, " CURSOR")?;
+        if self.with_hold {
+            write!(f, " WITH HOLD")?;
+        }
+        write!(f, " FOR {}", self.query)
+    }
+}
+
+/// Which rows a `FETCH` retrieves, relative to the cursor's position.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum FetchDirection {
+    Next,
+    Prior,
+    First,
+    Last,
+    Absolute(i64),
+    Relative(i64),
+    /// The next `n` rows, or the previous ones if `n` is negative.
+    Count(i64),
+    All,
+}
+
+impl fmt::Display for FetchDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FetchDirection::Next => write!(f, "NEXT"),
+            FetchDirection::Prior => write!(f, "PRIOR"),
+            FetchDirection::First => write!(f, "FIRST"),
+            FetchDirection::Last => write!(f, "LAST"),
+            FetchDirection::Absolute(n) => write!(f, "ABSOLUTE {}", n),
+            FetchDirection::Relative(n) => write!(f, "RELATIVE {}", n),
+            FetchDirection::Count(n) => write!(f, "{}", n),
+            FetchDirection::All => write!(f, "ALL"),
+        }
+    }
+}
+
+/// `FETCH [direction] [FROM | IN] cursor`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct FetchStatement {
+    pub cursor: String,
+    /// `None` if no direction was given, which fetches the next row.
+    pub direction: Option<FetchDirection>,
+}
+
+impl fmt::Display for FetchStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FETCH ")?;
+        if let Some(direction) = self.direction {
+            write!(f, "{} FROM ", direction)?;
+        }
+        write!(f, "{}", escape_if_keyword(&self.cursor))
+    }
+}
+
+/// `CLOSE cursor`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CloseCursorStatement {
+    pub cursor: String,
+}
+
+impl fmt::Display for CloseCursorStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CLOSE {}", escape_if_keyword(&self.cursor))
+    }
+}
+
+fn scroll(i: &str) -> IResult<&str, bool> {
+    alt((
+        value(true, tag_no_case("scroll")),
+        value(
+            false,
+            tuple((tag_no_case("no"), multispace1, tag_no_case("scroll"))),
+        ),
+    ))(i)
+}
+
+pub fn declare_cursor(i: &str) -> IResult<&str, DeclareCursorStatement> {
+    let (remaining_input, (_, _, name, _, scroll, _, with_hold, _, _, query, _)) = tuple((
+        tag_no_case("declare"),
+        multispace1,
+        sql_identifier,
+        multispace1,
+        opt(terminated(scroll, multispace1)),
+        tag_no_case("cursor"),
+        opt(preceded(
+            multispace1,
+            alt((
+                value(
+                    true,
+                    tuple((tag_no_case("with"), multispace1, tag_no_case("hold"))),
+                ),
+                value(
+                    false,
+                    tuple((tag_no_case("without"), multispace1, tag_no_case("hold"))),
+                ),
+            )),
+        )),
+        multispace1,
+        terminated(tag_no_case("for"), multispace1),
+        select_specification,
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        DeclareCursorStatement {
+            name: String::from(name),
+            scroll,
+            with_hold: with_hold.unwrap_or(false),
+            query,
+        },
+    ))
+}
+
+fn fetch_direction(i: &str) -> IResult<&str, FetchDirection> {
+    alt((
+        value(FetchDirection::Next, tag_no_case("next")),
+        value(FetchDirection::Prior, tag_no_case("prior")),
+        value(FetchDirection::First, tag_no_case("first")),
+        value(FetchDirection::Last, tag_no_case("last")),
+        value(FetchDirection::All, tag_no_case("all")),
+        map(
+            preceded(
+                terminated(tag_no_case("absolute"), multispace1),
+                signed_integer,
+            ),
+            FetchDirection::Absolute,
+        ),
+        map(
+            preceded(
+                terminated(tag_no_case("relative"), multispace1),
+                signed_integer,
+            ),
+            FetchDirection::Relative,
+        ),
+        map(signed_integer, FetchDirection::Count),
+    ))(i)
+}
+
+pub fn fetch(i: &str) -> IResult<&str, FetchStatement> {
+    let (remaining_input, (_, _, direction, _, cursor, _)) = tuple((
+        tag_no_case("fetch"),
+        multispace1,
+        opt(terminated(fetch_direction, multispace1)),
+        opt(terminated(
+            alt((tag_no_case("from"), tag_no_case("in"))),
+            multispace1,
+        )),
+        sql_identifier,
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        FetchStatement {
+            cursor: String::from(cursor),
+            direction,
+        },
+    ))
+}
+
+pub fn close_cursor(i: &str) -> IResult<&str, CloseCursorStatement> {
+    let (remaining_input, (_, _, cursor, _)) = tuple((
+        tag_no_case("close"),
+        multispace1,
+        sql_identifier,
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        CloseCursorStatement {
+            cursor: String::from(cursor),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use select::SelectStatement;
+    use table::Table;
+
+    #[test]
+    fn declare_cursors() {
+        let res =
+            declare_cursor("DECLARE report CURSOR FOR SELECT id FROM orders WHERE total > 10;");
+        let cursor = res.unwrap().1;
+        assert_eq!(cursor.name, "report");
+        assert_eq!(cursor.scroll, None);
+        assert!(!cursor.with_hold);
+        match cursor.query {
+            SelectSpecification::Simple(SelectStatement { ref tables, .. }) => {
+                assert_eq!(*tables, vec![Table::from("orders")]);
+            }
+            ref q => panic!("unexpected query {:?}", q),
+        }
+        assert_eq!(
+            cursor.to_string(),
+            "DECLARE report CURSOR FOR SELECT id FROM orders WHERE total > 10"
+        );
+
+        let res = declare_cursor(
+            "declare report no scroll cursor with hold for \
+             select id from a union select id from b",
+        );
+        let cursor = res.unwrap().1;
+        assert_eq!(cursor.scroll, Some(false));
+        assert!(cursor.with_hold);
+        match cursor.query {
+            SelectSpecification::Compound(ref compound) => assert_eq!(compound.selects.len(), 2),
+            ref q => panic!("unexpected query {:?}", q),
+        }
+    }
+
+    #[test]
+    fn fetch_and_close() {
+        let fetches = [
+            ("FETCH report;", None),
+            ("FETCH 10 FROM report", Some(FetchDirection::Count(10))),
+            ("fetch next in report", Some(FetchDirection::Next)),
+            (
+                "FETCH ABSOLUTE -1 report",
+                Some(FetchDirection::Absolute(-1)),
+            ),
+            ("FETCH ALL FROM report", Some(FetchDirection::All)),
+        ];
+        for &(q, direction) in &fetches {
+            let res = fetch(q).unwrap().1;
+            assert_eq!(res.cursor, "report", "{}", q);
+            assert_eq!(res.direction, direction, "{}", q);
+        }
+        assert_eq!(
+            fetch("FETCH RELATIVE 2 report").unwrap().1.to_string(),
+            "FETCH RELATIVE 2 FROM report"
+        );
+        assert_eq!(fetch("FETCH nextrow").unwrap().1.cursor, "nextrow");
+        assert_eq!(
+            close_cursor("CLOSE report;").unwrap().1.to_string(),
+            "CLOSE report"
+        );
+    }
+}
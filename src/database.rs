@@ -0,0 +1,224 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, opt};
+use nom::multi::many0;
+use nom::sequence::{preceded, terminated, tuple};
+use nom::IResult;
+use std::borrow::Cow;
+use std::fmt;
+
+use common::{keyword_phrase, sql_identifier, statement_terminator, whitespace0, whitespace1};
+use keywords::{escape_if_keyword, tag_no_case};
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CreateDatabaseStatement {
+    pub name: String,
+    pub if_not_exists: bool,
+    pub charset: Option<String>,
+    pub collation: Option<String>,
+}
+
+impl fmt::Display for CreateDatabaseStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CREATE DATABASE ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        write!(f, "{}", escape_if_keyword(&self.name))?;
+        if let Some(ref charset) = self.charset {
+            write!(f, " CHARACTER SET {}", charset)?;
+        }
+        if let Some(ref collation) = self.collation {
+            write!(f, " COLLATE {}", collation)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct DropDatabaseStatement {
+    pub name: String,
+    pub if_exists: bool,
+}
+
+impl fmt::Display for DropDatabaseStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DROP DATABASE ")?;
+        if self.if_exists {
+            write!(f, "IF EXISTS ")?;
+        }
+        write!(f, "{}", escape_if_keyword(&self.name))
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct UseStatement {
+    pub database: String,
+}
+
+impl fmt::Display for UseStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "USE {}", escape_if_keyword(&self.database))
+    }
+}
+
+enum DatabaseOption {
+    Charset(String),
+    Collation(String),
+}
+
+fn database_keyword(i: &str) -> IResult<&str, &str> {
+    alt((tag_no_case("database"), tag_no_case("schema")))(i)
+}
+
+/// Parses `name [=] ` ahead of a database option value.
+fn option_value<'a, O>(
+    name: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Cow<'a, str>> {
+    preceded(
+        tuple((name, whitespace0, opt(tag("=")), whitespace0)),
+        sql_identifier,
+    )
+}
+
+fn database_option(i: &str) -> IResult<&str, DatabaseOption> {
+    preceded(
+        tuple((
+            whitespace0,
+            opt(terminated(tag_no_case("default"), whitespace1)),
+        )),
+        alt((
+            map(
+                option_value(alt((
+                    keyword_phrase(&["character", "set"]),
+                    keyword_phrase(&["charset"]),
+                ))),
+                |cs| DatabaseOption::Charset(String::from(cs)),
+            ),
+            map(option_value(tag_no_case("collate")), |c| {
+                DatabaseOption::Collation(String::from(c))
+            }),
+        )),
+    )(i)
+}
+
+pub fn create_database(i: &str) -> IResult<&str, CreateDatabaseStatement> {
+    let (remaining_input, (_, _, _, _, if_not_exists, name, options, _)) = tuple((
+        tag_no_case("create"),
+        whitespace1,
+        database_keyword,
+        whitespace1,
+        opt(terminated(
+            keyword_phrase(&["if", "not", "exists"]),
+            whitespace1,
+        )),
+        sql_identifier,
+        many0(database_option),
+        statement_terminator,
+    ))(i)?;
+
+    let mut stmt = CreateDatabaseStatement {
+        name: String::from(name),
+        if_not_exists: if_not_exists.is_some(),
+        ..Default::default()
+    };
+    for option in options {
+        match option {
+            DatabaseOption::Charset(cs) => stmt.charset = Some(cs),
+            DatabaseOption::Collation(c) => stmt.collation = Some(c),
+        }
+    }
+    Ok((remaining_input, stmt))
+}
+
+pub fn drop_database(i: &str) -> IResult<&str, DropDatabaseStatement> {
+    let (remaining_input, (_, _, _, _, if_exists, name, _)) = tuple((
+        tag_no_case("drop"),
+        whitespace1,
+        database_keyword,
+        whitespace1,
+        opt(terminated(keyword_phrase(&["if", "exists"]), whitespace1)),
+        sql_identifier,
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        DropDatabaseStatement {
+            name: String::from(name),
+            if_exists: if_exists.is_some(),
+        },
+    ))
+}
+
+pub fn use_statement(i: &str) -> IResult<&str, UseStatement> {
+    let (remaining_input, (_, _, database, _)) = tuple((
+        tag_no_case("use"),
+        whitespace1,
+        sql_identifier,
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        UseStatement {
+            database: String::from(database),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_create_database() {
+        let res = create_database("CREATE DATABASE shop;");
+        assert_eq!(
+            res.unwrap().1,
+            CreateDatabaseStatement {
+                name: String::from("shop"),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn create_database_with_options() {
+        let qstring = "create schema if not exists `shop` \
+                       default character set = utf8mb4 COLLATE utf8mb4_bin";
+        let res = create_database(qstring);
+        assert_eq!(
+            res.unwrap().1,
+            CreateDatabaseStatement {
+                name: String::from("shop"),
+                if_not_exists: true,
+                charset: Some(String::from("utf8mb4")),
+                collation: Some(String::from("utf8mb4_bin")),
+            }
+        );
+    }
+
+    #[test]
+    fn format_create_database() {
+        let res = create_database("CREATE DATABASE IF NOT EXISTS shop CHARSET utf8");
+        assert_eq!(
+            format!("{}", res.unwrap().1),
+            "CREATE DATABASE IF NOT EXISTS shop CHARACTER SET utf8"
+        );
+    }
+
+    #[test]
+    fn drop_database_and_use() {
+        let res = drop_database("DROP DATABASE IF EXISTS shop;");
+        assert_eq!(
+            res.unwrap().1,
+            DropDatabaseStatement {
+                name: String::from("shop"),
+                if_exists: true,
+            }
+        );
+        let res = use_statement("USE `shop`;");
+        assert_eq!(format!("{}", res.unwrap().1), "USE shop");
+    }
+}
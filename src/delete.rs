@@ -1,45 +1,65 @@
-use nom::types::CompleteByteSlice;
-use std::{fmt, str};
+use nom::combinator::{opt, verify};
+use nom::sequence::{delimited, tuple};
+use nom::IResult;
+use std::fmt;
 
-use common::{opt_multispace, statement_terminator, table_reference};
+use common::{keyword_whitespace, statement_terminator, table_reference, whitespace1};
 use condition::ConditionExpression;
-use keywords::escape_if_keyword;
-use select::where_clause;
+use keywords::tag_no_case;
+use order::{order_clause, OrderClause};
+use select::{limit_clause, where_clause, LimitClause};
 use table::Table;
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct DeleteStatement {
     pub table: Table,
     pub where_clause: Option<ConditionExpression>,
+    /// MySQL's `ORDER BY` and `LIMIT` on single-table deletes, which take no offset.
+    #[serde(default)]
+    pub order: Option<OrderClause>,
+    #[serde(default)]
+    pub limit: Option<LimitClause>,
 }
 
 impl fmt::Display for DeleteStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "DELETE FROM ")?;
-        write!(f, "{}", escape_if_keyword(&self.table.name))?;
+        write!(f, "{}", self.table)?;
         if let Some(ref where_clause) = self.where_clause {
             write!(f, " WHERE ")?;
             write!(f, "{}", where_clause)?;
         }
+        if let Some(ref order) = self.order {
+            write!(f, " {}", order)?;
+        }
+        if let Some(ref limit) = self.limit {
+            write!(f, " {}", limit)?;
+        }
         Ok(())
     }
 }
 
-named!(pub deletion<CompleteByteSlice, DeleteStatement>,
-    do_parse!(
-        tag_no_case!("delete") >>
-        delimited!(opt_multispace, tag_no_case!("from"), opt_multispace) >>
-        table: table_reference >>
-        cond: opt!(where_clause) >>
-        statement_terminator >>
-        ({
-            DeleteStatement {
-                table: table,
-                where_clause: cond,
-            }
-        })
-    )
-);
+pub fn deletion(i: &str) -> IResult<&str, DeleteStatement> {
+    let (remaining_input, (_, _, table, where_clause, order, limit, _)) = tuple((
+        tag_no_case("delete"),
+        delimited(whitespace1, tag_no_case("from"), keyword_whitespace),
+        table_reference,
+        opt(where_clause),
+        opt(order_clause),
+        opt(verify(limit_clause, |limit| limit.offset == 0)),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        DeleteStatement {
+            table,
+            where_clause,
+            order,
+            limit,
+        },
+    ))
+}
 
 #[cfg(test)]
 mod tests {
@@ -49,12 +69,13 @@ mod tests {
     use condition::ConditionBase::*;
     use condition::ConditionExpression::*;
     use condition::ConditionTree;
+    use order::OrderType;
     use table::Table;
 
     #[test]
     fn simple_delete() {
         let qstring = "DELETE FROM users;";
-        let res = deletion(CompleteByteSlice(qstring.as_bytes()));
+        let res = deletion(qstring);
         assert_eq!(
             res.unwrap().1,
             DeleteStatement {
@@ -67,7 +88,7 @@ mod tests {
     #[test]
     fn delete_with_where_clause() {
         let qstring = "DELETE FROM users WHERE id = 1;";
-        let res = deletion(CompleteByteSlice(qstring.as_bytes()));
+        let res = deletion(qstring);
         let expected_left = Base(Field(Column::from("id")));
         let expected_where_cond = Some(ComparisonOp(ConditionTree {
             left: Box::new(expected_left),
@@ -88,7 +109,40 @@ mod tests {
     fn format_delete() {
         let qstring = "DELETE FROM users WHERE id = 1";
         let expected = "DELETE FROM users WHERE id = 1";
-        let res = deletion(CompleteByteSlice(qstring.as_bytes()));
+        let res = deletion(qstring);
         assert_eq!(format!("{}", res.unwrap().1), expected);
     }
+
+    #[test]
+    fn delete_with_table_alias() {
+        let qstring = "DELETE FROM users u WHERE u.id = 1";
+        let res = deletion(qstring).unwrap().1;
+        assert_eq!(res.table.alias, Some("u".into()));
+        assert_eq!(format!("{}", res), "DELETE FROM users AS u WHERE u.id = 1");
+    }
+
+    #[test]
+    fn delete_with_order_and_limit() {
+        let qstring = "DELETE FROM events WHERE created < 100 ORDER BY created LIMIT 1000;";
+        let res = deletion(qstring).unwrap().1;
+        assert_eq!(
+            res.order,
+            Some(OrderClause {
+                columns: vec![("created".into(), OrderType::OrderAscending)],
+            })
+        );
+        assert_eq!(
+            res.limit,
+            Some(LimitClause {
+                limit: 1000,
+                offset: 0,
+            })
+        );
+        assert_eq!(
+            format!("{}", res),
+            "DELETE FROM events WHERE created < 100 ORDER BY created ASC LIMIT 1000"
+        );
+        // single-table deletes take no offset
+        assert!(deletion("DELETE FROM events LIMIT 10 OFFSET 5").is_err());
+    }
 }
@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use arithmetic::{ArithmeticBase, ArithmeticExpression};
+use column::{Column, FunctionExpression};
+use common::{FieldDefinitionExpression, FieldValueExpression};
+use condition::{ConditionBase, ConditionExpression};
+use create::{CreateTableStatement, CreateViewStatement, SelectSpecification};
+use join::{JoinConstraint, JoinRightSide};
+use order::OrderClause;
+use parser::SqlQuery;
+use select::SelectStatement;
+use table::Table;
+
+fn condition_tables<'a>(cond: &'a ConditionExpression, tables: &mut BTreeSet<&'a str>) {
+    match *cond {
+        ConditionExpression::ComparisonOp(ref tree) | ConditionExpression::LogicalOp(ref tree) => {
+            condition_tables(&tree.left, tables);
+            condition_tables(&tree.right, tables);
+        }
+        ConditionExpression::NegationOp(ref expr)
+        | ConditionExpression::Bracketed(ref expr)
+        | ConditionExpression::Base(ConditionBase::Any(ref expr))
+        | ConditionExpression::Base(ConditionBase::Escaped(ref expr, _)) => {
+            condition_tables(expr, tables)
+        }
+        ConditionExpression::Base(ConditionBase::NestedSelect(ref select)) => {
+            select_tables(select, tables)
+        }
+        ConditionExpression::Base(ConditionBase::Row(ref exprs, _))
+        | ConditionExpression::Base(ConditionBase::ExpressionList(ref exprs)) => {
+            for expr in exprs {
+                condition_tables(expr, tables);
+            }
+        }
+        ConditionExpression::Base(ConditionBase::Field(ref col)) => column_tables(col, tables),
+        ConditionExpression::Base(ConditionBase::BitNot(ref operand)) => {
+            arithmetic_base_tables(operand, tables)
+        }
+        ConditionExpression::Arithmetic(ref expr) => arithmetic_tables(expr, tables),
+        ConditionExpression::Base(_) => (),
+    }
+}
+
+/// The tables read by subqueries among the arguments of a function call.
+fn column_tables<'a>(col: &'a Column, tables: &mut BTreeSet<&'a str>) {
+    if let Some(FunctionExpression::Call(_, ref args)) = col.function.as_deref() {
+        for arg in args {
+            condition_tables(arg, tables);
+        }
+    }
+}
+
+fn arithmetic_tables<'a>(expr: &'a ArithmeticExpression, tables: &mut BTreeSet<&'a str>) {
+    arithmetic_base_tables(&expr.left, tables);
+    arithmetic_base_tables(&expr.right, tables);
+}
+
+fn arithmetic_base_tables<'a>(base: &'a ArithmeticBase, tables: &mut BTreeSet<&'a str>) {
+    match *base {
+        ArithmeticBase::Column(ref col) => column_tables(col, tables),
+        ArithmeticBase::BitNot(ref base) => arithmetic_base_tables(base, tables),
+        ArithmeticBase::Nested(ref expr) => arithmetic_tables(expr, tables),
+        ArithmeticBase::Scalar(_) | ArithmeticBase::Variable(_) => (),
+    }
+}
+
+fn field_tables<'a>(field: &'a FieldDefinitionExpression, tables: &mut BTreeSet<&'a str>) {
+    match *field {
+        FieldDefinitionExpression::Col(ref col) => column_tables(col, tables),
+        FieldDefinitionExpression::Value(ref value) => match *value {
+            FieldValueExpression::Arithmetic(ref expr) => arithmetic_tables(expr, tables),
+            FieldValueExpression::Expression(ref expr) => condition_tables(expr, tables),
+            FieldValueExpression::Predicate(ref pred) => condition_tables(&pred.condition, tables),
+            FieldValueExpression::Variable(ref var) => {
+                if let Some(ref value) = var.value {
+                    field_tables(value, tables);
+                }
+            }
+            FieldValueExpression::Json(ref json) => column_tables(&json.column, tables),
+            FieldValueExpression::Subscript(ref sub) => column_tables(&sub.column, tables),
+            FieldValueExpression::Literal(_) => (),
+        },
+        FieldDefinitionExpression::All | FieldDefinitionExpression::AllInTable(_) => (),
+    }
+}
+
+fn order_tables<'a>(order: &'a Option<OrderClause>, tables: &mut BTreeSet<&'a str>) {
+    for (field, _) in order.iter().flat_map(|o| o.columns.iter()) {
+        if let Some(col) = field.column() {
+            column_tables(col, tables);
+        }
+    }
+}
+
+fn table_tables<'a>(table: &'a Table, tables: &mut BTreeSet<&'a str>) {
+    if table.is_stored() {
+        tables.insert(&table.name);
+    }
+    if let Some(ref subquery) = table.subquery {
+        select_tables(subquery, tables);
+    }
+}
+
+fn select_tables<'a>(select: &'a SelectStatement, tables: &mut BTreeSet<&'a str>) {
+    for field in &select.fields {
+        field_tables(field, tables);
+    }
+    for table in &select.tables {
+        table_tables(table, tables);
+    }
+    for join in &select.join {
+        let mut right = &join.right;
+        while let JoinRightSide::NestedJoin(ref nested) = *right {
+            right = &nested.right;
+        }
+        match *right {
+            JoinRightSide::Table(ref t) => table_tables(t, tables),
+            JoinRightSide::Tables(ref ts) => {
+                for t in ts {
+                    table_tables(t, tables);
+                }
+            }
+            JoinRightSide::NestedSelect(ref nested, _) => select_tables(nested, tables),
+            JoinRightSide::NestedJoin(_) => (),
+        }
+        if let JoinConstraint::On(ref cond) = join.constraint {
+            condition_tables(cond, tables);
+        }
+    }
+    if let Some(ref cond) = select.where_clause {
+        condition_tables(cond, tables);
+    }
+    if let Some(ref group_by) = select.group_by {
+        for col in group_by.columns.iter().filter_map(|f| f.column()) {
+            column_tables(col, tables);
+        }
+        if let Some(ref cond) = group_by.having {
+            condition_tables(cond, tables);
+        }
+    }
+    order_tables(&select.order, tables);
+}
+
+/// The names of the tables and views a view's definition reads from, including those only
+/// referenced in subqueries, wherever they appear in it: in the select list, a function's
+/// arguments or `ORDER BY` as well as in `FROM` and the conditions.
+pub fn view_dependencies(view: &CreateViewStatement) -> BTreeSet<&str> {
+    definition_tables(&view.definition)
+}
+
+fn definition_tables(definition: &SelectSpecification) -> BTreeSet<&str> {
+    let mut tables = BTreeSet::new();
+    match *definition {
+        SelectSpecification::Simple(ref select) => select_tables(select, &mut tables),
+        SelectSpecification::Compound(ref compound) => {
+            for (_, select) in &compound.selects {
+                select_tables(select, &mut tables);
+            }
+            order_tables(&compound.order, &mut tables);
+        }
+    }
+    tables
+}
+
+/// The views of a cycle, in dependency order; the last one depends on the first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DependencyCycle(pub Vec<String>);
+
+impl fmt::Display for DependencyCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "circular view dependency: {}", self.0.join(" -> "))?;
+        if let Some(first) = self.0.first() {
+            write!(f, " -> {}", first)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for DependencyCycle {}
+
+/// Dependencies between the tables and views of a schema, used to find an order in which they
+/// can be created.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DependencyGraph {
+    /// Relations in the order they were added, so that the creation order is deterministic.
+    names: Vec<String>,
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    pub fn new(tables: &[CreateTableStatement], views: &[CreateViewStatement]) -> Self {
+        let mut graph = DependencyGraph::default();
+        for table in tables {
+            graph.add(&table.table.name, BTreeSet::new());
+        }
+        for view in views {
+            let deps = view_dependencies(view)
+                .into_iter()
+                .map(String::from)
+                .collect();
+            graph.add(&view.name, deps);
+        }
+        graph
+    }
+
+    /// Builds the graph from the `CREATE TABLE`, `CREATE VIEW` and `CREATE MATERIALIZED VIEW`
+    /// statements among `queries`, ignoring all others.
+    pub fn from_queries<'a, I>(queries: I) -> Self
+    where
+        I: IntoIterator<Item = &'a SqlQuery>,
+    {
+        let mut tables = Vec::new();
+        let mut views = Vec::new();
+        let mut materialized = Vec::new();
+        for query in queries {
+            match *query {
+                SqlQuery::CreateTable(ref table) => tables.push(table.clone()),
+                SqlQuery::CreateView(ref view) => views.push(view.clone()),
+                SqlQuery::CreateMaterializedView(ref view) => materialized.push(view),
+                _ => (),
+            }
+        }
+        let mut graph = DependencyGraph::new(&tables, &views);
+        for view in materialized {
+            let deps = definition_tables(&view.definition)
+                .into_iter()
+                .map(String::from)
+                .collect();
+            graph.add(&view.name, deps);
+        }
+        graph
+    }
+
+    fn add(&mut self, name: &str, deps: BTreeSet<String>) {
+        if !self.dependencies.contains_key(name) {
+            self.names.push(String::from(name));
+        }
+        self.dependencies.insert(String::from(name), deps);
+    }
+
+    /// The tables and views `name` directly reads from, including ones not in the graph.
+    pub fn dependencies(&self, name: &str) -> Option<&BTreeSet<String>> {
+        self.dependencies.get(name)
+    }
+
+    /// The order in which to create the tables and views so that every view comes after what it
+    /// depends on. References to relations outside the graph are assumed to already exist.
+    pub fn creation_order(&self) -> Result<Vec<&str>, DependencyCycle> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Visiting,
+            Done,
+        }
+
+        let mut order = Vec::with_capacity(self.names.len());
+        let mut state = HashMap::new();
+        for root in &self.names {
+            if state.contains_key(root.as_str()) {
+                continue;
+            }
+            // Iterative depth-first search, keeping the path to report cycles.
+            let mut path: Vec<(&str, Vec<&str>)> = Vec::new();
+            let deps = |name: &str| -> Vec<&str> {
+                self.dependencies[name]
+                    .iter()
+                    .filter(|d| self.dependencies.contains_key(d.as_str()))
+                    .map(String::as_str)
+                    .rev()
+                    .collect()
+            };
+            state.insert(root.as_str(), State::Visiting);
+            path.push((root, deps(root)));
+            while let Some(&mut (name, ref mut pending)) = path.last_mut() {
+                match pending.pop() {
+                    Some(dep) => match state.get(dep) {
+                        Some(&State::Done) => (),
+                        Some(&State::Visiting) => {
+                            let start = path.iter().position(|&(n, _)| n == dep).unwrap_or(0);
+                            return Err(DependencyCycle(
+                                path[start..]
+                                    .iter()
+                                    .map(|&(n, _)| String::from(n))
+                                    .collect(),
+                            ));
+                        }
+                        None => {
+                            state.insert(dep, State::Visiting);
+                            path.push((dep, deps(dep)));
+                        }
+                    },
+                    None => {
+                        state.insert(name, State::Done);
+                        order.push(name);
+                        path.pop();
+                    }
+                }
+            }
+        }
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+
+    fn graph(queries: &[&str]) -> DependencyGraph {
+        let queries: Vec<_> = queries.iter().map(|q| parse_query(q).unwrap()).collect();
+        DependencyGraph::from_queries(&queries)
+    }
+
+    #[test]
+    fn creation_order_follows_dependencies() {
+        let g = graph(&[
+            "CREATE VIEW top_users AS SELECT id FROM active_users WHERE score > 10",
+            "CREATE VIEW active_users AS SELECT users.id, score FROM users \
+             JOIN scores ON users.id = scores.user_id \
+             WHERE users.id IN (SELECT user_id FROM sessions)",
+            "CREATE TABLE users (id int)",
+            "CREATE TABLE scores (user_id int, score int)",
+        ]);
+        assert_eq!(
+            g.dependencies("active_users").unwrap(),
+            &["scores", "sessions", "users"]
+                .iter()
+                .map(|s| String::from(*s))
+                .collect::<BTreeSet<_>>()
+        );
+        assert_eq!(
+            g.creation_order().unwrap(),
+            vec!["users", "scores", "active_users", "top_users"]
+        );
+    }
+
+    #[test]
+    fn subqueries_outside_from_and_conditions() {
+        let dependencies = |q: &str| match parse_query(q).unwrap() {
+            SqlQuery::CreateView(ref view) => view_dependencies(view)
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            dependencies("CREATE VIEW v AS SELECT a IN (SELECT b FROM w) AS f FROM t"),
+            vec!["t", "w"]
+        );
+        assert_eq!(
+            dependencies(
+                "CREATE VIEW v AS SELECT coalesce((SELECT max(id) FROM x), 0) AS m FROM t \
+                 ORDER BY coalesce((SELECT count(*) FROM y), 0)"
+            ),
+            vec!["t", "x", "y"]
+        );
+    }
+
+    #[test]
+    fn materialized_views_are_ordered_with_views() {
+        let g = graph(&[
+            "CREATE MATERIALIZED VIEW daily AS SELECT id FROM recent",
+            "CREATE VIEW recent AS SELECT id FROM events",
+            "CREATE TABLE events (id int)",
+        ]);
+        assert_eq!(
+            g.creation_order().unwrap(),
+            vec!["events", "recent", "daily"]
+        );
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let g = graph(&[
+            "CREATE VIEW a AS SELECT x FROM b",
+            "CREATE VIEW b AS SELECT x FROM c",
+            "CREATE VIEW c AS SELECT x FROM a",
+        ]);
+        let err = g.creation_order().unwrap_err();
+        assert_eq!(
+            err,
+            DependencyCycle(vec![
+                String::from("a"),
+                String::from("b"),
+                String::from("c"),
+            ])
+        );
+        assert_eq!(
+            err.to_string(),
+            "circular view dependency: a -> b -> c -> a"
+        );
+    }
+}
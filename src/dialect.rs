@@ -0,0 +1,76 @@
+use nom::error::{Error, ErrorKind};
+use nom::IResult;
+use std::cell::Cell;
+
+/// The SQL dialect whose extensions `parse_query_with_dialect` and `ParseOptions` accept. Grammar
+/// that cannot be mistaken for anything else, such as `[bracketed]` identifiers or Postgres casts,
+/// is accepted in every dialect; only constructs that would change how other dialects parse are gated.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Dialect {
+    /// MySQL, along with the Postgres and SQLite syntax the parser understands other than
+    /// Postgres' built-in types.
+    #[default]
+    Generic,
+    /// Microsoft SQL Server (T-SQL): `SELECT TOP n` and `(MAX)` lengths.
+    SqlServer,
+    /// PostgreSQL: built-in types such as `UUID`, `INET`, `BYTEA` and `TSVECTOR`, whose names
+    /// MySQL leaves free for user-defined types and columns, `"quoted"` identifiers, which MySQL
+    /// reads as strings, and standard strings, in which only `E'...'` takes backslash escapes.
+    Postgres,
+}
+
+thread_local! {
+    static DIALECT: Cell<Dialect> = const { Cell::new(Dialect::Generic) };
+}
+
+/// The dialect that parsers, and the printing of string literals, follow on this thread.
+pub(crate) fn current_dialect() -> Dialect {
+    DIALECT.with(|d| d.get())
+}
+
+/// Runs `f` with `dialect` as the dialect for parsers and printed string literals on this thread.
+pub(crate) fn with_dialect<T, F: FnOnce() -> T>(dialect: Dialect, f: F) -> T {
+    struct Restore(Dialect);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            DIALECT.with(|d| d.set(self.0));
+        }
+    }
+
+    let _restore = Restore(DIALECT.with(|d| d.replace(dialect)));
+    f()
+}
+
+/// Wraps a parser for grammar that only `dialect` accepts, failing without consuming input when
+/// parsing for any other dialect.
+pub(crate) fn only_in<'a, O, F>(
+    dialect: Dialect,
+    mut parser: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O>,
+{
+    move |i: &'a str| {
+        if current_dialect() != dialect {
+            return Err(nom::Err::Error(Error::new(i, ErrorKind::Verify)));
+        }
+        parser(i)
+    }
+}
+
+/// Wraps a parser for grammar that every dialect but `dialect` accepts, failing without consuming
+/// input when parsing for `dialect`.
+pub(crate) fn except_in<'a, O, F>(
+    dialect: Dialect,
+    mut parser: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O>,
+{
+    move |i: &'a str| {
+        if current_dialect() == dialect {
+            return Err(nom::Err::Error(Error::new(i, ErrorKind::Verify)));
+        }
+        parser(i)
+    }
+}
@@ -0,0 +1,97 @@
+use nom::character::complete::multispace1;
+use nom::error::{Error, ErrorKind};
+use nom::multi::separated_list1;
+use nom::sequence::tuple;
+use nom::IResult;
+use std::fmt;
+
+use common::{sql_comment, statement_terminator, ws_sep_comma};
+use keywords::tag_no_case;
+
+/// MySQL `DO expr [, expr] ...`, which evaluates expressions without returning a result. The
+/// expressions are kept verbatim, since they are mostly calls to functions such as `SLEEP`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct DoStatement {
+    pub expressions: Vec<String>,
+}
+
+impl fmt::Display for DoStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DO {}", self.expressions.join(", "))
+    }
+}
+
+/// Takes the text of one expression, up to the next comma or semicolon outside of parentheses
+/// and quoted strings, or up to a closing parenthesis that it did not open or a comment.
+fn expression_text(i: &str) -> IResult<&str, &str> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut end = i.len();
+    let mut chars = i.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match quote {
+            Some(_) if c == '\\' => {
+                chars.next();
+            }
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None => match c {
+                '\'' | '"' | '`' => quote = Some(c),
+                '(' => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                ',' | ';' | ')' if depth == 0 => {
+                    end = idx;
+                    break;
+                }
+                _ if depth == 0 && sql_comment(&i[idx..]).is_ok() => {
+                    end = idx;
+                    break;
+                }
+                _ => (),
+            },
+        }
+    }
+    let text = i[..end].trim_end();
+    if text.is_empty() || depth > 0 || quote.is_some() {
+        return Err(nom::Err::Error(Error::new(i, ErrorKind::Char)));
+    }
+    Ok((&i[text.len()..], text))
+}
+
+pub fn do_statement(i: &str) -> IResult<&str, DoStatement> {
+    let (remaining_input, (_, _, expressions, _)) = tuple((
+        tag_no_case("do"),
+        multispace1,
+        separated_list1(ws_sep_comma, expression_text),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        DoStatement {
+            expressions: expressions.into_iter().map(String::from).collect(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn do_expressions() {
+        let res = do_statement("DO SLEEP(1), RELEASE_LOCK('a,b');").unwrap();
+        assert_eq!(res.0, "");
+        assert_eq!(
+            res.1,
+            DoStatement {
+                expressions: vec![
+                    String::from("SLEEP(1)"),
+                    String::from("RELEASE_LOCK('a,b')"),
+                ],
+            }
+        );
+        assert_eq!(format!("{}", res.1), "DO SLEEP(1), RELEASE_LOCK('a,b')");
+        assert!(do_statement("DO (1").is_err());
+    }
+}
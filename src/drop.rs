@@ -1,14 +1,19 @@
-use nom::types::CompleteByteSlice;
-use std::{fmt, str};
+use nom::combinator::opt;
+use nom::multi::separated_list1;
+use nom::sequence::{preceded, tuple};
+use nom::IResult;
+use std::fmt;
 
-use common::{opt_multispace, statement_terminator, table_list};
-use keywords::escape_if_keyword;
+use common::{keyword_phrase, statement_terminator, table_name, whitespace1, ws_sep_comma};
 use table::Table;
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct DropTableStatement {
     pub tables: Vec<Table>,
     pub if_exists: bool,
+    /// Postgres drops the objects that depend on the tables too; MySQL ignores `CASCADE`.
+    #[serde(default)]
+    pub cascade: bool,
 }
 
 impl fmt::Display for DropTableStatement {
@@ -20,35 +25,42 @@ impl fmt::Display for DropTableStatement {
         let ts = self
             .tables
             .iter()
-            .map(|t| escape_if_keyword(&t.name))
+            .map(Table::escaped_name)
             .collect::<Vec<_>>()
             .join(", ");
         write!(f, "{}", ts)?;
+        if self.cascade {
+            write!(f, " CASCADE")?;
+        }
         Ok(())
     }
 }
 
-named!(pub drop_table<CompleteByteSlice, DropTableStatement>,
-    do_parse!(
-        tag_no_case!("drop table") >>
-        if_exists: opt!(delimited!(opt_multispace, tag_no_case!("if exists"), opt_multispace)) >>
-        opt_multispace >>
-        tables: table_list >>
-        opt_multispace >>
+pub fn drop_table(i: &str) -> IResult<&str, DropTableStatement> {
+    let (remaining_input, (_, if_exists, _, tables, _, cascade, _)) = tuple((
+        keyword_phrase(&["drop", "table"]),
+        opt(preceded(whitespace1, keyword_phrase(&["if", "exists"]))),
+        whitespace1,
+        // the dropped tables are named, not aliased
+        separated_list1(ws_sep_comma, table_name),
         // MySQL 5.7 reference manual, §13.1.29:
         // The RESTRICT and CASCADE keywords do nothing. They are permitted to make porting easier from
         // other database systems.
-        opt!(delimited!(opt_multispace, tag_no_case!("restricted"), opt_multispace)) >>
-        opt!(delimited!(opt_multispace, tag_no_case!("cascade"), opt_multispace)) >>
-        statement_terminator >>
-        ({
-            DropTableStatement {
-                tables: tables,
-                if_exists: if_exists.is_some(),
-            }
-        })
-    )
-);
+        // In Postgres RESTRICT is the default, while CASCADE also drops dependent objects.
+        opt(preceded(whitespace1, keyword_phrase(&["restrict"]))),
+        opt(preceded(whitespace1, keyword_phrase(&["cascade"]))),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        DropTableStatement {
+            tables,
+            if_exists: if_exists.is_some(),
+            cascade: cascade.is_some(),
+        },
+    ))
+}
 
 #[cfg(test)]
 mod tests {
@@ -58,12 +70,13 @@ mod tests {
     #[test]
     fn simple_drop_table() {
         let qstring = "DROP TABLE users;";
-        let res = drop_table(CompleteByteSlice(qstring.as_bytes()));
+        let res = drop_table(qstring);
         assert_eq!(
             res.unwrap().1,
             DropTableStatement {
                 tables: vec![Table::from("users")],
                 if_exists: false,
+                cascade: false,
             }
         );
     }
@@ -72,7 +85,18 @@ mod tests {
     fn format_drop_table() {
         let qstring = "DROP TABLE IF EXISTS users,posts;";
         let expected = "DROP TABLE IF EXISTS users, posts";
-        let res = drop_table(CompleteByteSlice(qstring.as_bytes()));
+        let res = drop_table(qstring);
         assert_eq!(format!("{}", res.unwrap().1), expected);
+        let res = drop_table("DROP TABLE a RESTRICT CASCADE").unwrap().1;
+        assert!(res.cascade);
+        assert_eq!(format!("{}", res), "DROP TABLE a CASCADE");
+    }
+
+    #[test]
+    fn drop_table_rejects_aliases() {
+        assert!(drop_table("DROP TABLE a garbage").is_err());
+        assert!(drop_table("DROP TABLE IFEXISTS a, b").is_err());
+        assert!(drop_table("DROP TABLE a AS b").is_err());
+        assert!(drop_table("DROP TABLE").is_err());
     }
 }
@@ -0,0 +1,132 @@
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag};
+use nom::combinator::{map, opt};
+use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::IResult;
+use std::borrow::Cow;
+use std::fmt;
+
+use common::{
+    escape_string, is_sql_identifier, keyword_phrase, sql_identifier, statement_terminator,
+    string_literal, whitespace1, Literal,
+};
+use keywords::{escape_if_keyword, tag_no_case};
+
+/// Postgres' `CREATE EXTENSION [IF NOT EXISTS] name [WITH] [SCHEMA s] [VERSION v] [CASCADE]`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CreateExtensionStatement {
+    pub name: String,
+    pub if_not_exists: bool,
+    pub schema: Option<String>,
+    pub version: Option<String>,
+    pub cascade: bool,
+}
+
+impl fmt::Display for CreateExtensionStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CREATE EXTENSION ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        if self.name.chars().all(is_sql_identifier) {
+            write!(f, "{}", escape_if_keyword(&self.name))?;
+        } else {
+            write!(f, "\"{}\"", self.name)?;
+        }
+        if let Some(ref schema) = self.schema {
+            write!(f, " SCHEMA {}", escape_if_keyword(schema))?;
+        }
+        if let Some(ref version) = self.version {
+            write!(f, " VERSION '{}'", escape_string(version))?;
+        }
+        if self.cascade {
+            write!(f, " CASCADE")?;
+        }
+        Ok(())
+    }
+}
+
+/// An extension name, which is double-quoted when, like `"uuid-ossp"`, it is not a plain
+/// identifier.
+fn extension_name(i: &str) -> IResult<&str, Cow<'_, str>> {
+    alt((
+        map(delimited(tag("\""), is_not("\""), tag("\"")), Cow::Borrowed),
+        sql_identifier,
+    ))(i)
+}
+
+fn extension_version(i: &str) -> IResult<&str, String> {
+    alt((
+        map(string_literal, |v| match v {
+            Literal::String(v) => v,
+            _ => unreachable!("string_literal only returns strings"),
+        }),
+        map(sql_identifier, String::from),
+    ))(i)
+}
+
+pub fn create_extension(i: &str) -> IResult<&str, CreateExtensionStatement> {
+    let (remaining_input, (_, _, _, _, if_not_exists, name, _, schema, version, cascade, _)) =
+        tuple((
+            tag_no_case("create"),
+            whitespace1,
+            tag_no_case("extension"),
+            whitespace1,
+            opt(terminated(
+                keyword_phrase(&["if", "not", "exists"]),
+                whitespace1,
+            )),
+            extension_name,
+            opt(preceded(whitespace1, tag_no_case("with"))),
+            opt(preceded(
+                tuple((whitespace1, tag_no_case("schema"), whitespace1)),
+                sql_identifier,
+            )),
+            opt(preceded(
+                tuple((whitespace1, tag_no_case("version"), whitespace1)),
+                extension_version,
+            )),
+            opt(preceded(whitespace1, tag_no_case("cascade"))),
+            statement_terminator,
+        ))(i)?;
+
+    Ok((
+        remaining_input,
+        CreateExtensionStatement {
+            name: String::from(name),
+            if_not_exists: if_not_exists.is_some(),
+            schema: schema.map(String::from),
+            version,
+            cascade: cascade.is_some(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_extensions() {
+        let res = create_extension(
+            "CREATE EXTENSION IF NOT EXISTS \"uuid-ossp\" WITH SCHEMA public VERSION '1.1' CASCADE;",
+        );
+        let ext = res.unwrap().1;
+        assert_eq!(
+            ext,
+            CreateExtensionStatement {
+                name: String::from("uuid-ossp"),
+                if_not_exists: true,
+                schema: Some(String::from("public")),
+                version: Some(String::from("1.1")),
+                cascade: true,
+            }
+        );
+        assert_eq!(
+            ext.to_string(),
+            "CREATE EXTENSION IF NOT EXISTS \"uuid-ossp\" SCHEMA public VERSION '1.1' CASCADE"
+        );
+        let ext = create_extension("create extension pgcrypto").unwrap().1;
+        assert_eq!(ext.to_string(), "CREATE EXTENSION pgcrypto");
+    }
+}
@@ -1,13 +1,37 @@
 use std::fmt;
 use std::str;
 
-use column::{Column};
-use table::{Table};
+use column::Column;
+use keywords::escape_if_keyword;
+use table::Table;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// What happens to referencing rows when the referenced row is deleted or updated.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum ReferentialAction {
+    Cascade,
+    SetNull,
+    SetDefault,
+    Restrict,
+    NoAction,
+}
+
+impl fmt::Display for ReferentialAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReferentialAction::Cascade => write!(f, "CASCADE"),
+            ReferentialAction::SetNull => write!(f, "SET NULL"),
+            ReferentialAction::SetDefault => write!(f, "SET DEFAULT"),
+            ReferentialAction::Restrict => write!(f, "RESTRICT"),
+            ReferentialAction::NoAction => write!(f, "NO ACTION"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct ForeignKeySpecification {
     pub name: Option<String>,
-    pub ref_action: Option<String>,
+    pub on_delete: Option<ReferentialAction>,
+    pub on_update: Option<ReferentialAction>,
     pub from: Vec<Column>,
     pub that_table: Table,
     pub to: Vec<Column>,
@@ -16,7 +40,7 @@ pub struct ForeignKeySpecification {
 impl fmt::Display for ForeignKeySpecification {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(ref name) = self.name {
-            write!(f, "CONSTRAINT {} ", name)?;
+            write!(f, "CONSTRAINT {} ", escape_if_keyword(name))?;
         }
 
         let mut cnt = 0;
@@ -45,8 +69,11 @@ impl fmt::Display for ForeignKeySpecification {
             write!(f, ")")?;
         }
 
-        if let Some(ref ref_action) = self.ref_action {
-            write!(f, " {} ", ref_action)?;
+        if let Some(action) = self.on_delete {
+            write!(f, " ON DELETE {}", action)?;
+        }
+        if let Some(action) = self.on_update {
+            write!(f, " ON UPDATE {}", action)?;
         }
 
         Ok(())
@@ -54,13 +81,21 @@ impl fmt::Display for ForeignKeySpecification {
 }
 
 impl ForeignKeySpecification {
-    pub fn new(name: Option<String>, ref_action: Option<String>, from: Vec<Column>, that_table: Table, to: Vec<Column>) -> ForeignKeySpecification {
+    pub fn new(
+        name: Option<String>,
+        on_delete: Option<ReferentialAction>,
+        on_update: Option<ReferentialAction>,
+        from: Vec<Column>,
+        that_table: Table,
+        to: Vec<Column>,
+    ) -> ForeignKeySpecification {
         ForeignKeySpecification {
-            name: name,
-            ref_action: ref_action,
-            from: from,
-            that_table: that_table,
-            to: to,
+            name,
+            on_delete,
+            on_update,
+            from,
+            that_table,
+            to,
         }
     }
 }
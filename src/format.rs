@@ -0,0 +1,844 @@
+use common::Operator;
+use compound_select::CompoundSelectStatement;
+use condition::ConditionExpression;
+use create::{CreateTableStatement, CreateViewStatement, SelectSpecification};
+use delete::DeleteStatement;
+use dialect::{with_dialect, Dialect};
+use insert::InsertStatement;
+use keywords::{is_grammar_word, is_sql_keyword, with_identifier_quoting};
+use parser::SqlQuery;
+use select::SelectStatement;
+use update::UpdateStatement;
+
+/// Letter case used for SQL keywords in formatted output.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum KeywordCase {
+    Upper,
+    Lower,
+}
+
+/// Which identifiers are quoted in formatted output.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum IdentifierQuoting {
+    /// Every table and column name and alias. Other names, such as those of constraints and
+    /// indexes, are quoted only where needed.
+    Always,
+    /// Only names that would otherwise be taken for a reserved keyword.
+    #[default]
+    WhenNeeded,
+    /// No names at all, for targets that treat quoted names differently. Names that are reserved
+    /// keywords then give output that does not parse.
+    Never,
+}
+
+/// How identifiers are quoted in formatted output, which depends on the database it is for.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
+pub enum QuoteStyle {
+    /// `` `name` ``, as in MySQL and SQLite.
+    #[default]
+    Backtick,
+    /// `"name"`, as in Postgres and the SQL standard.
+    DoubleQuote,
+    /// `[name]`, as in SQL Server.
+    Bracket,
+}
+
+/// Options controlling the layout produced by `format_query`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FormatOptions {
+    /// Number of spaces per indentation level.
+    pub indent: usize,
+    /// Case used for keywords.
+    pub keyword_case: KeywordCase,
+    /// Preferred maximum line width. Clauses that do not fit are broken up one item per line.
+    pub max_width: usize,
+    /// Start each clause (FROM, WHERE, ...) on its own line.
+    pub clause_per_line: bool,
+    /// Which identifiers are quoted.
+    pub identifier_quoting: IdentifierQuoting,
+    /// How identifiers are quoted.
+    pub quote_style: QuoteStyle,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            indent: 4,
+            keyword_case: KeywordCase::Upper,
+            max_width: 80,
+            clause_per_line: true,
+            identifier_quoting: IdentifierQuoting::WhenNeeded,
+            quote_style: QuoteStyle::Backtick,
+        }
+    }
+}
+
+/// Separator placed between the items of a clause.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Separator {
+    /// Comma-separated items; the comma trails each item when broken over lines.
+    Comma,
+    /// Logical operator; the operator leads each continuation line when broken over lines.
+    Operator(&'static str),
+}
+
+/// A single clause of a statement, e.g. `WHERE a = 1 AND b = 2`.
+#[derive(Clone, Debug)]
+struct Clause {
+    head: String,
+    items: Vec<String>,
+    separator: Separator,
+}
+
+impl Clause {
+    fn new(head: &str) -> Clause {
+        Clause {
+            head: String::from(head),
+            items: vec![],
+            separator: Separator::Comma,
+        }
+    }
+
+    fn list(head: &str, items: Vec<String>) -> Clause {
+        Clause {
+            head: String::from(head),
+            items,
+            separator: Separator::Comma,
+        }
+    }
+
+    fn condition(head: &str, cond: &ConditionExpression) -> Clause {
+        let (separator, items) = match *cond {
+            ConditionExpression::LogicalOp(ref ct) => {
+                let mut conjuncts = vec![];
+                flatten_logical(cond, &ct.operator, &mut conjuncts);
+                let op = match ct.operator {
+                    Operator::Or => "OR",
+                    _ => "AND",
+                };
+                (
+                    Separator::Operator(op),
+                    conjuncts.iter().map(|c| format!("{}", c)).collect(),
+                )
+            }
+            _ => (Separator::Comma, vec![format!("{}", cond)]),
+        };
+        Clause {
+            head: String::from(head),
+            items,
+            separator,
+        }
+    }
+
+    /// Renders the clause on a single line, without indentation.
+    fn flat(&self) -> String {
+        if self.items.is_empty() {
+            return self.head.clone();
+        }
+        let body = match self.separator {
+            Separator::Comma => self.items.join(", "),
+            Separator::Operator(op) => self.items.join(&format!(" {} ", op)),
+        };
+        if self.head.is_empty() {
+            body
+        } else {
+            format!("{} {}", self.head, body)
+        }
+    }
+
+    fn render(&self, opts: &FormatOptions, level: usize) -> String {
+        let flat = format!("{}{}", indentation(opts, level), self.flat());
+        if self.items.is_empty() || flat.len() <= opts.max_width {
+            return flat;
+        }
+
+        let inner = indentation(opts, level + 1);
+        let mut lines = vec![format!("{}{}", indentation(opts, level), self.head)];
+        for (i, item) in self.items.iter().enumerate() {
+            let line = match self.separator {
+                Separator::Comma if i + 1 < self.items.len() => format!("{}{},", inner, item),
+                Separator::Operator(op) if i > 0 => format!("{}{} {}", inner, op, item),
+                _ => format!("{}{}", inner, item),
+            };
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+fn indentation(opts: &FormatOptions, level: usize) -> String {
+    " ".repeat(opts.indent * level)
+}
+
+/// Collects the operands of a chain of logical operators of the same kind, so that
+/// `a AND (b AND c)` yields `[a, b, c]`. The chain is walked with a stack rather than recursively,
+/// since it may be very long.
+fn flatten_logical<'a>(
+    cond: &'a ConditionExpression,
+    op: &Operator,
+    out: &mut Vec<&'a ConditionExpression>,
+) {
+    let mut pending = vec![cond];
+    while let Some(cond) = pending.pop() {
+        match *cond {
+            ConditionExpression::LogicalOp(ref ct) if ct.operator == *op => {
+                pending.push(&ct.right);
+                pending.push(&ct.left);
+            }
+            _ => out.push(cond),
+        }
+    }
+}
+
+fn render_clauses(clauses: &[Clause], opts: &FormatOptions, level: usize) -> String {
+    if opts.clause_per_line {
+        return clauses
+            .iter()
+            .map(|c| c.render(opts, level))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    // all clauses on one line, wrapping onto indented continuation lines if too wide
+    let mut lines: Vec<String> = vec![];
+    let mut current = indentation(opts, level);
+    let mut empty = true;
+    for clause in clauses {
+        let flat = clause.flat();
+        if !empty && current.len() + 1 + flat.len() > opts.max_width {
+            lines.push(current);
+            current = format!("{}{}", indentation(opts, level + 1), flat);
+        } else {
+            if !empty {
+                current.push(' ');
+            }
+            current.push_str(&flat);
+        }
+        empty = false;
+    }
+    lines.push(current);
+    lines.join("\n")
+}
+
+fn select_clauses(select: &SelectStatement) -> Vec<Clause> {
+    let mut clauses = vec![];
+    let mut head = String::from("SELECT");
+    if select.distinct {
+        head.push_str(" DISTINCT");
+    }
+    if let Some(top) = select.top {
+        head.push_str(&format!(" TOP {}", top));
+    }
+    if !select.modifiers.is_empty() {
+        head.push_str(&format!(" {}", select.modifiers));
+    }
+    clauses.push(Clause::list(
+        &head,
+        select.fields.iter().map(|f| format!("{}", f)).collect(),
+    ));
+    if !select.tables.is_empty() {
+        clauses.push(Clause::list(
+            "FROM",
+            select.tables.iter().map(|t| format!("{}", t)).collect(),
+        ));
+    }
+    for jc in &select.join {
+        clauses.push(Clause::new(&format!("{}", jc)));
+    }
+    if let Some(ref cond) = select.where_clause {
+        clauses.push(Clause::condition("WHERE", cond));
+    }
+    if let Some(ref group_by) = select.group_by {
+        clauses.push(Clause::list(
+            "GROUP BY",
+            group_by.columns.iter().map(|c| format!("{}", c)).collect(),
+        ));
+        if let Some(ref having) = group_by.having {
+            clauses.push(Clause::condition("HAVING", having));
+        }
+    }
+    if let Some(ref order) = select.order {
+        clauses.push(Clause::list(
+            "ORDER BY",
+            order
+                .columns
+                .iter()
+                .map(|(c, o)| format!("{} {}", c, o))
+                .collect(),
+        ));
+    }
+    if let Some(ref limit) = select.limit {
+        clauses.push(Clause::new(&format!("{}", limit)));
+    }
+    if let Some(ref lock) = select.lock {
+        clauses.push(Clause::new(&format!("{}", lock)));
+    }
+    if let Some(ref into) = select.into {
+        clauses.push(Clause::new(&format!("{}", into)));
+    }
+    clauses
+}
+
+fn format_select(select: &SelectStatement, opts: &FormatOptions, level: usize) -> String {
+    render_clauses(&select_clauses(select), opts, level)
+}
+
+fn format_compound_select(
+    csq: &CompoundSelectStatement,
+    opts: &FormatOptions,
+    level: usize,
+) -> String {
+    let mut parts = vec![];
+    for (n, (op, select)) in csq.selects.iter().enumerate() {
+        if let Some(op) = op {
+            parts.push(format!("{}{}", indentation(opts, level), op));
+        }
+        let formatted = format_select(select, opts, level);
+        if csq.is_bracketed(n) {
+            parts.push(format!(
+                "{}({})",
+                indentation(opts, level),
+                formatted.trim_start()
+            ));
+        } else {
+            parts.push(formatted);
+        }
+    }
+
+    let mut clauses = vec![];
+    if let Some(ref order) = csq.order {
+        clauses.push(Clause::new(&format!("{}", order)));
+    }
+    if let Some(ref limit) = csq.limit {
+        clauses.push(Clause::new(&format!("{}", limit)));
+    }
+    if !clauses.is_empty() {
+        parts.push(render_clauses(&clauses, opts, level));
+    }
+
+    if opts.clause_per_line {
+        parts.join("\n")
+    } else {
+        parts
+            .iter()
+            .map(|p| p.trim_start())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn format_insert(insert: &InsertStatement, opts: &FormatOptions) -> String {
+    let mut head = insert.header();
+    if let Some(ref fields) = insert.fields {
+        head.push_str(&format!(
+            " ({})",
+            fields
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let mut clauses = vec![Clause::new(&head)];
+    clauses.push(Clause::list(
+        "VALUES",
+        insert
+            .data
+            .iter()
+            .map(|row| {
+                format!(
+                    "({})",
+                    row.iter()
+                        .map(|l| l.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+            .collect(),
+    ));
+    if let Some(ref on_duplicate) = insert.on_duplicate {
+        clauses.push(Clause::list(
+            "ON DUPLICATE KEY UPDATE",
+            on_duplicate
+                .iter()
+                .map(|(c, v)| format!("{} = {}", c, v))
+                .collect(),
+        ));
+    }
+    render_clauses(&clauses, opts, 0)
+}
+
+fn format_update(update: &UpdateStatement, opts: &FormatOptions) -> String {
+    let mut clauses = vec![Clause::new(&format!("UPDATE {}", update.table))];
+    clauses.push(Clause::list(
+        "SET",
+        update
+            .fields
+            .iter()
+            .map(|(c, v)| format!("{} = {}", c, v))
+            .collect(),
+    ));
+    if let Some(ref cond) = update.where_clause {
+        clauses.push(Clause::condition("WHERE", cond));
+    }
+    if let Some(ref order) = update.order {
+        clauses.push(Clause::new(&format!("{}", order)));
+    }
+    if let Some(ref limit) = update.limit {
+        clauses.push(Clause::new(&format!("{}", limit)));
+    }
+    render_clauses(&clauses, opts, 0)
+}
+
+fn format_delete(delete: &DeleteStatement, opts: &FormatOptions) -> String {
+    let mut clauses = vec![Clause::new(&format!("DELETE FROM {}", delete.table))];
+    if let Some(ref cond) = delete.where_clause {
+        clauses.push(Clause::condition("WHERE", cond));
+    }
+    if let Some(ref order) = delete.order {
+        clauses.push(Clause::new(&format!("{}", order)));
+    }
+    if let Some(ref limit) = delete.limit {
+        clauses.push(Clause::new(&format!("{}", limit)));
+    }
+    render_clauses(&clauses, opts, 0)
+}
+
+fn format_create_table(create: &CreateTableStatement, opts: &FormatOptions) -> String {
+    let mut items: Vec<String> = create.fields.iter().map(|f| format!("{}", f)).collect();
+    if let Some(ref keys) = create.keys {
+        items.extend(keys.iter().map(|k| format!("{}", k)));
+    }
+    if let Some(ref fkeys) = create.fkeys {
+        items.extend(fkeys.iter().map(|k| format!("{}", k).trim_end().to_owned()));
+    }
+
+    let head = format!(
+        "CREATE TABLE {}{} (",
+        if create.if_not_exists {
+            "IF NOT EXISTS "
+        } else {
+            ""
+        },
+        create.table.escaped_name()
+    );
+    let mut tail = String::from(")");
+    if create.without_rowid {
+        tail.push_str(" WITHOUT ROWID");
+    }
+    if let Some(ref partitioning) = create.partitioning {
+        tail.push_str(&format!(" {}", partitioning));
+    }
+    let flat = format!("{}{}{}", head, items.join(", "), tail);
+    if !opts.clause_per_line && flat.len() <= opts.max_width {
+        return flat;
+    }
+
+    let inner = indentation(opts, 1);
+    let mut lines = vec![head];
+    for (i, item) in items.iter().enumerate() {
+        if i + 1 < items.len() {
+            lines.push(format!("{}{},", inner, item));
+        } else {
+            lines.push(format!("{}{}", inner, item));
+        }
+    }
+    lines.push(tail);
+    lines.join("\n")
+}
+
+fn format_create_view(view: &CreateViewStatement, opts: &FormatOptions) -> String {
+    let head = format!("{} AS", view.header());
+
+    let mut definition = match *view.definition {
+        SelectSpecification::Simple(ref select) => format_select(select, opts, 0),
+        SelectSpecification::Compound(ref csq) => format_compound_select(csq, opts, 0),
+    };
+    if let Some(check_option) = view.check_option {
+        let separator = if opts.clause_per_line { "\n" } else { " " };
+        definition.push_str(&format!("{}{}", separator, check_option));
+    }
+    if opts.clause_per_line {
+        format!("{}\n{}", head, definition)
+    } else {
+        format!("{} {}", head, definition)
+    }
+}
+
+/// Recases a reserved keyword, or any other word the grammar understands if it is in upper case,
+/// as `Display` emits them. The latter are left alone otherwise, since an identifier with the same
+/// name is not quoted on output.
+pub(crate) fn recase_word(word: &str, case: KeywordCase) -> String {
+    let recase =
+        is_sql_keyword(word) || (word.chars().all(|c| !c.is_lowercase()) && is_grammar_word(word));
+    if !recase {
+        return word.to_owned();
+    }
+    match case {
+        KeywordCase::Upper => word.to_uppercase(),
+        KeywordCase::Lower => word.to_lowercase(),
+    }
+}
+
+/// Recases all keywords in `sql`, leaving quoted strings and identifiers untouched. Square
+/// brackets only quote identifiers under `QuoteStyle::Bracket`; otherwise they are subscripts.
+fn apply_keyword_case(sql: &str, case: KeywordCase, style: QuoteStyle) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    for c in sql.chars() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            out.push_str(&recase_word(&word, case));
+            word.clear();
+        }
+        if c == '\'' || c == '"' || c == '`' {
+            quote = Some(c);
+        } else if c == '[' && style == QuoteStyle::Bracket {
+            quote = Some(']');
+        }
+        out.push(c);
+    }
+    if !word.is_empty() {
+        out.push_str(&recase_word(&word, case));
+    }
+    out
+}
+
+/// Renders a parsed statement according to `opts`.
+///
+/// Unlike the single-line `Display` output, this breaks statements into clauses, wraps lists
+/// that exceed the configured width, and applies the requested keyword case and quoting. Output
+/// in double quotes is for Postgres, so its strings are then written as standard strings, with
+/// backslashes left as they are.
+pub fn format_query(query: &SqlQuery, opts: &FormatOptions) -> String {
+    let dialect = match opts.quote_style {
+        QuoteStyle::DoubleQuote => Dialect::Postgres,
+        _ => Dialect::Generic,
+    };
+    with_dialect(dialect, || {
+        with_identifier_quoting(
+            opts.identifier_quoting,
+            opts.quote_style,
+            opts.keyword_case,
+            || format_with_quoting(query, opts),
+        )
+    })
+}
+
+fn format_with_quoting(query: &SqlQuery, opts: &FormatOptions) -> String {
+    let formatted = match *query {
+        SqlQuery::Select(ref select) => format_select(select, opts, 0),
+        SqlQuery::CompoundSelect(ref csq) => format_compound_select(csq, opts, 0),
+        SqlQuery::Insert(ref insert) => format_insert(insert, opts),
+        SqlQuery::Update(ref update) => format_update(update, opts),
+        SqlQuery::Delete(ref delete) => format_delete(delete, opts),
+        SqlQuery::CreateTable(ref create) => format_create_table(create, opts),
+        SqlQuery::CreateView(ref view) => format_create_view(view, opts),
+        // leave text we could not parse exactly as it was written
+        SqlQuery::Unparsed(ref text, _) => return text.clone(),
+        _ => format!("{}", query),
+    };
+    apply_keyword_case(&formatted, opts.keyword_case, opts.quote_style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+    use table::Table;
+
+    fn format_str(q: &str, opts: &FormatOptions) -> String {
+        format_query(&parse_query(q).unwrap(), opts)
+    }
+
+    #[test]
+    fn clause_per_line() {
+        let q = "select id, name from users where id = 1 and name = 'bob' order by name limit 10";
+        let expected = "SELECT id, name\n\
+                        FROM users\n\
+                        WHERE id = 1 AND name = 'bob'\n\
+                        ORDER BY name ASC\n\
+                        LIMIT 10";
+        assert_eq!(format_str(q, &FormatOptions::default()), expected);
+    }
+
+    #[test]
+    fn locking_clauses() {
+        let q = "select id from jobs where state = 'new' limit 1 for update skip locked";
+        let expected = "SELECT id\n\
+                        FROM jobs\n\
+                        WHERE state = 'new'\n\
+                        LIMIT 1\n\
+                        FOR UPDATE SKIP LOCKED";
+        assert_eq!(format_str(q, &FormatOptions::default()), expected);
+        let opts = FormatOptions {
+            clause_per_line: false,
+            ..Default::default()
+        };
+        let formatted = format_str("SELECT id FROM t FOR SHARE OF t NOWAIT", &opts);
+        assert_eq!(formatted, "SELECT id FROM t FOR SHARE OF t NOWAIT");
+        assert_eq!(
+            parse_query(&formatted).unwrap(),
+            parse_query("SELECT id FROM t FOR SHARE OF t NOWAIT").unwrap()
+        );
+    }
+
+    #[test]
+    fn wraps_long_clauses() {
+        let q = "SELECT a_long_column_name, another_long_column_name FROM t \
+                 WHERE a_long_column_name = 'some value' AND another_long_column_name = ?";
+        let opts = FormatOptions {
+            max_width: 40,
+            indent: 2,
+            ..Default::default()
+        };
+        let expected = "SELECT\n  \
+                        a_long_column_name,\n  \
+                        another_long_column_name\n\
+                        FROM t\n\
+                        WHERE\n  \
+                        a_long_column_name = 'some value'\n  \
+                        AND another_long_column_name = ?";
+        assert_eq!(format_str(q, &opts), expected);
+    }
+
+    #[test]
+    fn lowercase_keywords() {
+        let q = "SELECT `select`, `from`.name FROM `from` WHERE name LIKE 'SELECT%'";
+        let opts = FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            clause_per_line: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_str(q, &opts),
+            "select `select`, `from`.name from `from` where name like 'SELECT%'"
+        );
+    }
+
+    #[test]
+    fn identifier_quoting() {
+        let q = "SELECT u.id, lower(name) AS n FROM users AS u WHERE `order` = 1 ORDER BY u.id";
+        let quoted = |identifier_quoting| {
+            let opts = FormatOptions {
+                clause_per_line: false,
+                max_width: 120,
+                identifier_quoting,
+                ..Default::default()
+            };
+            format_str(q, &opts)
+        };
+        assert_eq!(
+            quoted(IdentifierQuoting::Always),
+            "SELECT `u`.`id`, lower(`name`) AS `n` FROM `users` AS `u` WHERE `order` = 1 \
+             ORDER BY `u`.`id` ASC"
+        );
+        assert_eq!(
+            quoted(IdentifierQuoting::WhenNeeded),
+            "SELECT u.id, lower(name) AS n FROM users AS u WHERE `order` = 1 ORDER BY u.id ASC"
+        );
+        // an unquoted keyword is taken for one
+        assert_eq!(
+            quoted(IdentifierQuoting::Never),
+            "SELECT u.id, lower(name) AS n FROM users AS u WHERE ORDER = 1 ORDER BY u.id ASC"
+        );
+        // only table and column names are quoted
+        let opts = FormatOptions {
+            identifier_quoting: IdentifierQuoting::Always,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_str("SELECT * FROM t WHERE a = TRUE", &opts),
+            "SELECT *\nFROM `t`\nWHERE `a` = TRUE"
+        );
+        assert_eq!(
+            format_str("SET search_path = public, app", &opts),
+            "SET search_path = public, app"
+        );
+        // the policy only applies to the formatted statement
+        assert_eq!(
+            parse_query(q).unwrap().to_string(),
+            "SELECT u.id, lower(name) AS n FROM users AS u WHERE `order` = 1 ORDER BY u.id ASC"
+        );
+    }
+
+    #[test]
+    fn quote_styles() {
+        let q = "SELECT `from`.`select` AS `key` FROM `from` WHERE id = 1";
+        let quoted = |quote_style, keyword_case| {
+            let opts = FormatOptions {
+                keyword_case,
+                clause_per_line: false,
+                quote_style,
+                ..Default::default()
+            };
+            format_str(q, &opts)
+        };
+        assert_eq!(
+            quoted(QuoteStyle::Backtick, KeywordCase::Upper),
+            "SELECT `from`.`select` AS `key` FROM `from` WHERE id = 1"
+        );
+        assert_eq!(
+            quoted(QuoteStyle::DoubleQuote, KeywordCase::Upper),
+            "SELECT \"from\".\"select\" AS \"key\" FROM \"from\" WHERE id = 1"
+        );
+        // the quoted names keep their case
+        assert_eq!(
+            quoted(QuoteStyle::Bracket, KeywordCase::Upper),
+            "SELECT [from].[select] AS [key] FROM [from] WHERE id = 1"
+        );
+        assert_eq!(
+            quoted(QuoteStyle::Bracket, KeywordCase::Lower),
+            "select [from].[select] as [key] from [from] where id = 1"
+        );
+
+        let q =
+            "CREATE TABLE t (id int, CONSTRAINT `check` FOREIGN KEY (id) REFERENCES `order` (id))";
+        let opts = FormatOptions {
+            quote_style: QuoteStyle::DoubleQuote,
+            ..Default::default()
+        };
+        assert!(format_str(q, &opts)
+            .contains("CONSTRAINT \"check\" FOREIGN KEY(id) REFERENCES \"order\"(id)"));
+
+        // a quote within a name is doubled in whichever style it is printed
+        let q = "SELECT * FROM `a\"b``c` AS [x]]y]";
+        let opts = FormatOptions {
+            clause_per_line: false,
+            quote_style: QuoteStyle::DoubleQuote,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_str(q, &opts),
+            "SELECT * FROM \"a\"\"b`c\" AS \"x]y\""
+        );
+    }
+
+    #[test]
+    fn ordinal_positions() {
+        let q = "SELECT a FROM t GROUP BY 1 ORDER BY 1";
+        for &quote_style in &[
+            QuoteStyle::Backtick,
+            QuoteStyle::DoubleQuote,
+            QuoteStyle::Bracket,
+        ] {
+            for &identifier_quoting in &[
+                IdentifierQuoting::Always,
+                IdentifierQuoting::WhenNeeded,
+                IdentifierQuoting::Never,
+            ] {
+                let opts = FormatOptions {
+                    clause_per_line: false,
+                    identifier_quoting,
+                    quote_style,
+                    ..Default::default()
+                };
+                let formatted = format_str(q, &opts);
+                assert!(
+                    formatted.ends_with("GROUP BY 1 ORDER BY 1 ASC"),
+                    "{}",
+                    formatted
+                );
+                // quoting every name marks the table as quoted, so only the clauses are compared
+                match (parse_query(&formatted).unwrap(), parse_query(q).unwrap()) {
+                    (SqlQuery::Select(printed), SqlQuery::Select(parsed)) => {
+                        assert_eq!(printed.group_by, parsed.group_by, "{}", formatted);
+                        assert_eq!(printed.order, parsed.order, "{}", formatted);
+                    }
+                    other => panic!("unexpected queries {:?}", other),
+                }
+            }
+        }
+        // a column named by digits is quoted to keep it apart from a position
+        let named = parse_query("SELECT `1` FROM t ORDER BY `1`").unwrap();
+        assert_eq!(named.to_string(), "SELECT `1` FROM t ORDER BY `1` ASC");
+        assert_ne!(
+            Some(named),
+            parse_query("SELECT `1` FROM t ORDER BY 1").ok()
+        );
+    }
+
+    #[test]
+    fn create_table_columns_per_line() {
+        let q = "CREATE TABLE users (id int NOT NULL, comment varchar(255), PRIMARY KEY (id))";
+        let opts = FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            ..Default::default()
+        };
+        let expected = "create table users (\n    \
+                        id int(32) not null,\n    \
+                        comment varchar(255),\n    \
+                        primary key (id)\n\
+                        )";
+        assert_eq!(format_str(q, &opts), expected);
+
+        let q = "CREATE TABLE IF NOT EXISTS kv (k text PRIMARY KEY, v blob) WITHOUT ROWID";
+        let expected = "create table if not exists kv (\n    \
+                        k text primary key,\n    \
+                        v blob\n\
+                        ) without rowid";
+        assert_eq!(format_str(q, &opts), expected);
+    }
+
+    #[test]
+    fn create_table_without_items() {
+        let create = CreateTableStatement {
+            table: Table::from("t"),
+            ..Default::default()
+        };
+        let formatted = format_query(&SqlQuery::CreateTable(create), &FormatOptions::default());
+        assert_eq!(formatted, "CREATE TABLE t (\n)");
+    }
+
+    #[test]
+    fn compound_selects_keep_parentheses() {
+        for q in &[
+            "(SELECT a FROM t ORDER BY a LIMIT 1) UNION SELECT b FROM u",
+            "SELECT a FROM t UNION (SELECT b FROM u LIMIT 1) ORDER BY a",
+        ] {
+            for clause_per_line in &[true, false] {
+                let opts = FormatOptions {
+                    clause_per_line: *clause_per_line,
+                    ..Default::default()
+                };
+                let formatted = format_str(q, &opts);
+                assert_eq!(parse_query(&formatted), parse_query(q), "{}", formatted);
+            }
+        }
+    }
+
+    #[test]
+    fn lowercase_grammar_words() {
+        let opts = FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            clause_per_line: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_str(
+                "SELECT * FROM generate_series(1, 3) WITH ORDINALITY AS g(n)",
+                &opts
+            ),
+            "select * from generate_series(1, 3) with ordinality as g(n)"
+        );
+        // names spelled like the keywords being recased are quoted to keep their spelling
+        assert_eq!(
+            format_str("SELECT STATUS, Value FROM t", &opts),
+            "select `STATUS`, Value from t"
+        );
+    }
+}
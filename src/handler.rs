@@ -0,0 +1,235 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{map, opt, success, value};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{
+    as_alias, literal, sql_identifier, statement_terminator, ws_sep_comma, Literal, Operator,
+};
+use condition::ConditionExpression;
+use keywords::{escape_if_keyword, tag_no_case};
+use select::{limit_clause, where_clause, LimitClause};
+
+/// Where a `HANDLER ... READ` starts reading.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum HandlerReadPosition {
+    First,
+    Next,
+    Prev,
+    Last,
+    /// Seek on the index, e.g. `>= (1, 'a')`.
+    Seek(Operator, Vec<Literal>),
+}
+
+impl fmt::Display for HandlerReadPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HandlerReadPosition::First => write!(f, "FIRST"),
+            HandlerReadPosition::Next => write!(f, "NEXT"),
+            HandlerReadPosition::Prev => write!(f, "PREV"),
+            HandlerReadPosition::Last => write!(f, "LAST"),
+            HandlerReadPosition::Seek(ref op, ref values) => write!(
+                f,
+                "{} ({})",
+                op,
+                values
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum HandlerAction {
+    Open(Option<String>),
+    Read {
+        index: Option<String>,
+        position: HandlerReadPosition,
+        where_clause: Option<ConditionExpression>,
+        limit: Option<LimitClause>,
+    },
+    Close,
+}
+
+impl fmt::Display for HandlerAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HandlerAction::Open(ref alias) => {
+                write!(f, "OPEN")?;
+                if let Some(ref alias) = *alias {
+                    write!(f, " AS {}", escape_if_keyword(alias))?;
+                }
+                Ok(())
+            }
+            HandlerAction::Read {
+                ref index,
+                ref position,
+                ref where_clause,
+                ref limit,
+            } => {
+                write!(f, "READ ")?;
+                if let Some(ref index) = *index {
+                    write!(f, "{} ", escape_if_keyword(index))?;
+                }
+                write!(f, "{}", position)?;
+                if let Some(ref cond) = *where_clause {
+                    write!(f, " WHERE {}", cond)?;
+                }
+                if let Some(ref limit) = *limit {
+                    write!(f, " {}", limit)?;
+                }
+                Ok(())
+            }
+            HandlerAction::Close => write!(f, "CLOSE"),
+        }
+    }
+}
+
+/// MySQL's low-level `HANDLER tbl OPEN | READ ... | CLOSE` table access.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct HandlerStatement {
+    pub table: String,
+    pub action: HandlerAction,
+}
+
+impl fmt::Display for HandlerStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "HANDLER {} {}",
+            escape_if_keyword(&self.table),
+            self.action
+        )
+    }
+}
+
+fn seek_operator(i: &str) -> IResult<&str, Operator> {
+    alt((
+        value(Operator::GreaterOrEqual, tag(">=")),
+        value(Operator::LessOrEqual, tag("<=")),
+        value(Operator::Equal, tag("=")),
+        value(Operator::Greater, tag(">")),
+        value(Operator::Less, tag("<")),
+    ))(i)
+}
+
+fn read_position(i: &str) -> IResult<&str, HandlerReadPosition> {
+    alt((
+        value(HandlerReadPosition::First, tag_no_case("first")),
+        value(HandlerReadPosition::Next, tag_no_case("next")),
+        value(HandlerReadPosition::Prev, tag_no_case("prev")),
+        value(HandlerReadPosition::Last, tag_no_case("last")),
+        map(
+            pair(
+                terminated(seek_operator, multispace0),
+                delimited(
+                    pair(tag("("), multispace0),
+                    separated_list1(ws_sep_comma, literal),
+                    pair(multispace0, tag(")")),
+                ),
+            ),
+            |(op, values)| HandlerReadPosition::Seek(op, values),
+        ),
+    ))(i)
+}
+
+fn handler_read(i: &str) -> IResult<&str, HandlerAction> {
+    let (remaining_input, (_, _, (index, position), where_clause, limit)) = tuple((
+        tag_no_case("read"),
+        multispace1,
+        alt((
+            pair(
+                map(terminated(sql_identifier, multispace1), Some),
+                read_position,
+            ),
+            pair(success(None), read_position),
+        )),
+        opt(where_clause),
+        opt(limit_clause),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        HandlerAction::Read {
+            index: index.map(String::from),
+            position,
+            where_clause,
+            limit,
+        },
+    ))
+}
+
+pub fn handler(i: &str) -> IResult<&str, HandlerStatement> {
+    let (remaining_input, (_, _, table, _, action, _)) = tuple((
+        tag_no_case("handler"),
+        multispace1,
+        sql_identifier,
+        multispace1,
+        alt((
+            map(preceded(tag_no_case("open"), opt(as_alias)), |alias| {
+                HandlerAction::Open(alias.map(String::from))
+            }),
+            handler_read,
+            value(HandlerAction::Close, tag_no_case("close")),
+        )),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        HandlerStatement {
+            table: String::from(table),
+            action,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handler_open_and_close() {
+        let res = handler("HANDLER users OPEN AS u;").unwrap().1;
+        assert_eq!(
+            res,
+            HandlerStatement {
+                table: String::from("users"),
+                action: HandlerAction::Open(Some(String::from("u"))),
+            }
+        );
+        assert_eq!(
+            handler("handler u close").unwrap().1.action,
+            HandlerAction::Close
+        );
+    }
+
+    #[test]
+    fn handler_read() {
+        let res = handler("HANDLER u READ `PRIMARY` >= (10) WHERE active = 1 LIMIT 5")
+            .unwrap()
+            .1;
+        assert_eq!(
+            format!("{}", res),
+            "HANDLER u READ `PRIMARY` >= (10) WHERE active = 1 LIMIT 5"
+        );
+
+        let res = handler("HANDLER u READ next").unwrap().1;
+        assert_eq!(
+            res.action,
+            HandlerAction::Read {
+                index: None,
+                position: HandlerReadPosition::Next,
+                where_clause: None,
+                limit: None,
+            }
+        );
+    }
+}
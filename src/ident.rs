@@ -0,0 +1,252 @@
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::borrow::{Borrow, Cow};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// The name of a table or column, or an alias for one.
+///
+/// Names are immutable and cheap to clone. Names made while an `Interner` is active (see
+/// `parse_query_interned`) share storage with equal names made by the same interner, and
+/// comparing two such names only compares pointers. An `Ident` serializes as a plain string.
+#[derive(Clone, Default)]
+pub struct Ident(Arc<str>);
+
+impl Ident {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether `self` and `other` share storage, which implies that they are equal.
+    pub fn ptr_eq(&self, other: &Ident) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Deref for Ident {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Ident {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Ident {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a str> for Ident {
+    fn from(s: &'a str) -> Self {
+        INTERNER.with(|interner| match *interner.borrow_mut() {
+            Some(ref mut interner) => interner.intern(s),
+            None => Ident(Arc::from(s)),
+        })
+    }
+}
+
+impl<'a> From<&'a String> for Ident {
+    fn from(s: &'a String) -> Self {
+        Ident::from(s.as_str())
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Ident {
+    fn from(s: Cow<'a, str>) -> Self {
+        Ident::from(&*s)
+    }
+}
+
+impl From<String> for Ident {
+    fn from(s: String) -> Self {
+        Ident::from(s.as_str())
+    }
+}
+
+impl From<Ident> for String {
+    fn from(ident: Ident) -> Self {
+        String::from(&*ident.0)
+    }
+}
+
+impl PartialEq for Ident {
+    fn eq(&self, other: &Ident) -> bool {
+        self.ptr_eq(other) || self.0 == other.0
+    }
+}
+
+impl Eq for Ident {}
+
+impl PartialEq<str> for Ident {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Ident {
+    fn eq(&self, other: &&'a str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Ident {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other.as_str()
+    }
+}
+
+impl PartialEq<Ident> for String {
+    fn eq(&self, other: &Ident) -> bool {
+        self.as_str() == &*other.0
+    }
+}
+
+impl PartialEq<Ident> for &str {
+    fn eq(&self, other: &Ident) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl PartialOrd for Ident {
+    fn partial_cmp(&self, other: &Ident) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ident {
+    fn cmp(&self, other: &Ident) -> Ordering {
+        if self.ptr_eq(other) {
+            Ordering::Equal
+        } else {
+            self.0.cmp(&other.0)
+        }
+    }
+}
+
+/// Hashes like the `str` it holds, so that maps keyed by names can be looked up with a `&str`.
+impl Hash for Ident {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl fmt::Debug for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for Ident {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ident {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Ident::from)
+    }
+}
+
+/// A set of names, handing out a shared `Ident` for each distinct one.
+///
+/// Large statements over wide tables repeat the same column and table names many times; parsing
+/// them with an interner stores each name once. An interner can be reused across statements so
+/// that they share names too.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    names: HashSet<Ident>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// The shared `Ident` for `name`.
+    pub fn intern(&mut self, name: &str) -> Ident {
+        if let Some(ident) = self.names.get(name) {
+            return ident.clone();
+        }
+        let ident = Ident(Arc::from(name));
+        self.names.insert(ident.clone());
+        ident
+    }
+
+    /// The number of distinct names interned.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+thread_local! {
+    static INTERNER: RefCell<Option<Interner>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `interner` making the names created on this thread.
+pub(crate) fn with_interner<T, F: FnOnce() -> T>(interner: &mut Interner, f: F) -> T {
+    struct Restore<'i> {
+        interner: &'i mut Interner,
+        previous: Option<Interner>,
+    }
+    impl<'i> Drop for Restore<'i> {
+        fn drop(&mut self) {
+            let active =
+                INTERNER.with(|i| mem::replace(&mut *i.borrow_mut(), self.previous.take()));
+            *self.interner = active.unwrap_or_default();
+        }
+    }
+
+    let active = mem::take(interner);
+    let previous = INTERNER.with(|i| i.borrow_mut().replace(active));
+    let _restore = Restore { interner, previous };
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interned_names_share_storage() {
+        let mut interner = Interner::new();
+        let (a, b, c) = with_interner(&mut interner, || {
+            (Ident::from("id"), Ident::from("id"), Ident::from("name"))
+        });
+        assert!(a.ptr_eq(&b));
+        assert!(!a.ptr_eq(&c));
+        assert_eq!(interner.len(), 2);
+        assert!(interner.intern("id").ptr_eq(&a));
+
+        // without an interner, equal names are still equal
+        let (d, e) = (Ident::from("id"), Ident::from(String::from("id")));
+        assert!(!d.ptr_eq(&e));
+        assert_eq!(d, e);
+        assert_eq!(d, a);
+        assert_eq!(d, "id");
+        assert_eq!(interner.len(), 2);
+    }
+}
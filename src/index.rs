@@ -0,0 +1,160 @@
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::opt;
+use nom::error::{Error, ErrorKind};
+use nom::sequence::{terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{
+    keyword_phrase, sql_identifier, statement_terminator, table_reference, IndexOptions, KeyPart,
+};
+use create::{index_type, key_columns_with_options};
+use keywords::{escape_if_keyword, tag_no_case};
+use table::Table;
+
+/// `CREATE [UNIQUE] INDEX [CONCURRENTLY] [name] ON table (key_part, ...)`, where `CONCURRENTLY`
+/// and leaving out the name are Postgres syntax.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CreateIndexStatement {
+    pub name: Option<String>,
+    pub table: Table,
+    pub unique: bool,
+    /// Whether the index is built without locking out writes to the table, as Postgres allows.
+    #[serde(default)]
+    pub concurrently: bool,
+    pub columns: Vec<KeyPart>,
+    pub options: IndexOptions,
+}
+
+impl fmt::Display for CreateIndexStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CREATE ")?;
+        if self.unique {
+            write!(f, "UNIQUE ")?;
+        }
+        write!(f, "INDEX ")?;
+        if self.concurrently {
+            write!(f, "CONCURRENTLY ")?;
+        }
+        if let Some(ref name) = self.name {
+            write!(f, "{} ", escape_if_keyword(name))?;
+        }
+        let columns = self
+            .columns
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            f,
+            "ON {} ({}){}",
+            self.table.escaped_name(),
+            columns,
+            self.options
+        )
+    }
+}
+
+pub fn create_index(i: &str) -> IResult<&str, CreateIndexStatement> {
+    let (remaining_input, (_, _, unique, _, _, concurrently, name, leading_type, _, table, _, key)) =
+        tuple((
+            tag_no_case("create"),
+            multispace1,
+            opt(terminated(tag_no_case("unique"), multispace1)),
+            tag_no_case("index"),
+            multispace1,
+            opt(terminated(keyword_phrase(&["concurrently"]), multispace1)),
+            opt(terminated(sql_identifier, multispace1)),
+            opt(terminated(index_type, multispace1)),
+            terminated(tag_no_case("on"), multispace1),
+            table_reference,
+            multispace0,
+            terminated(key_columns_with_options, statement_terminator),
+        ))(i)?;
+
+    // "table AS alias" isn't legal in CREATE INDEX statements
+    if table.alias.is_some() {
+        return Err(nom::Err::Error(Error::new(i, ErrorKind::Verify)));
+    }
+
+    let (columns, mut options) = key;
+    if options.index_type.is_none() {
+        options.index_type = leading_type;
+    }
+    Ok((
+        remaining_input,
+        CreateIndexStatement {
+            name: name.map(String::from),
+            table,
+            unique: unique.is_some(),
+            concurrently: concurrently.is_some(),
+            columns,
+            options,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use column::Column;
+    use condition::condition_expr;
+    use order::OrderType;
+
+    #[test]
+    fn create_expression_index() {
+        let res = create_index("CREATE INDEX ON users (lower(email), id DESC);").unwrap();
+        assert_eq!(res.0, "");
+        assert_eq!(
+            res.1,
+            CreateIndexStatement {
+                name: None,
+                table: Table::from("users"),
+                unique: false,
+                concurrently: false,
+                columns: vec![
+                    KeyPart::Expression(Box::new(condition_expr("lower(email)").unwrap().1), None),
+                    KeyPart::Column(Column::from("id"), None, Some(OrderType::OrderDescending)),
+                ],
+                options: IndexOptions::default(),
+            }
+        );
+        assert_eq!(
+            format!("{}", res.1),
+            "CREATE INDEX ON users ((lower(email)), id DESC)"
+        );
+    }
+
+    #[test]
+    fn create_unique_index() {
+        let res =
+            create_index("create unique index email_idx using btree on users ((LOWER(email)))")
+                .unwrap()
+                .1;
+        assert!(res.unique);
+        assert_eq!(res.name, Some(String::from("email_idx")));
+        assert_eq!(
+            format!("{}", res),
+            "CREATE UNIQUE INDEX email_idx ON users ((LOWER(email))) USING BTREE"
+        );
+    }
+
+    #[test]
+    fn create_index_concurrently() {
+        let res = create_index("CREATE INDEX concurrently idx ON users (email)")
+            .unwrap()
+            .1;
+        assert!(res.concurrently);
+        assert_eq!(res.name, Some(String::from("idx")));
+        assert_eq!(
+            format!("{}", res),
+            "CREATE INDEX CONCURRENTLY idx ON users (email)"
+        );
+
+        let res = create_index("CREATE INDEX CONCURRENTLY ON users (email)")
+            .unwrap()
+            .1;
+        assert!(res.concurrently);
+        assert_eq!(res.name, None);
+    }
+}
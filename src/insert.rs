@@ -1,35 +1,91 @@
-use nom::multispace;
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, opt, value};
+use nom::error::{Error, ErrorKind};
+use nom::multi::{many1, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
 use std::fmt;
-use std::str;
 
 use column::Column;
 use common::{
-    assignment_expr_list, field_list, opt_multispace, statement_terminator, table_reference,
-    value_list, FieldValueExpression, Literal,
+    as_alias, assignment_expr_list, field_list, field_value_expr, keyword_phrase,
+    keyword_whitespace, sql_identifier, statement_terminator, table_name, whitespace0, whitespace1,
+    ws_sep_comma, FieldValueExpression,
 };
-use keywords::escape_if_keyword;
+use keywords::{escape_if_keyword, tag_no_case};
 use table::Table;
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// MySQL's scheduling modifiers for `INSERT`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum InsertPriority {
+    LowPriority,
+    Delayed,
+    HighPriority,
+}
+
+impl fmt::Display for InsertPriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InsertPriority::LowPriority => write!(f, "LOW_PRIORITY"),
+            InsertPriority::Delayed => write!(f, "DELAYED"),
+            InsertPriority::HighPriority => write!(f, "HIGH_PRIORITY"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct InsertStatement {
     pub table: Table,
     pub fields: Option<Vec<Column>>,
-    pub data: Vec<Vec<Literal>>,
+    pub data: Vec<Vec<FieldValueExpression>>,
+    /// Whether the rows were written as `ROW(...)`, which MySQL takes in place of a parenthesized
+    /// row as long as every row is written the same way.
+    #[serde(default)]
+    pub row_constructors: bool,
     pub ignore: bool,
     pub on_duplicate: Option<Vec<(Column, FieldValueExpression)>>,
+    #[serde(default)]
+    pub priority: Option<InsertPriority>,
+    /// The partitions named by `PARTITION (p1, ...)`, outside of which no rows may be inserted.
+    #[serde(default)]
+    pub partitions: Vec<String>,
+}
+
+impl InsertStatement {
+    /// Everything up to the column list: `INSERT [priority] [IGNORE] INTO table [PARTITION (...)]`.
+    pub(crate) fn header(&self) -> String {
+        let mut header = String::from("INSERT ");
+        if let Some(priority) = self.priority {
+            header.push_str(&format!("{} ", priority));
+        }
+        if self.ignore {
+            header.push_str("IGNORE ");
+        }
+        header.push_str(&format!("INTO {}", self.table.escaped_name()));
+        if !self.partitions.is_empty() {
+            let partitions = self
+                .partitions
+                .iter()
+                .map(|p| escape_if_keyword(p))
+                .collect::<Vec<_>>()
+                .join(", ");
+            header.push_str(&format!(" PARTITION ({})", partitions));
+        }
+        header
+    }
 }
 
 impl fmt::Display for InsertStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "INSERT INTO {}", escape_if_keyword(&self.table.name))?;
+        write!(f, "{}", self.header())?;
         if let Some(ref fields) = self.fields {
             write!(
                 f,
                 " ({})",
                 fields
                     .iter()
-                    .map(|ref col| col.name.to_owned())
+                    .map(|col| col.to_string())
                     .collect::<Vec<_>>()
                     .join(", ")
             )?;
@@ -39,92 +95,178 @@ impl fmt::Display for InsertStatement {
             " VALUES {}",
             self.data
                 .iter()
-                .map(|datas| format!(
-                    "({})",
-                    datas
-                        .into_iter()
-                        .map(|l| l.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )).collect::<Vec<_>>()
+                .map(|values| row(values, self.row_constructors))
+                .collect::<Vec<_>>()
                 .join(", ")
-        )
+        )?;
+        if let Some(ref on_duplicate) = self.on_duplicate {
+            write!(
+                f,
+                " ON DUPLICATE KEY UPDATE {}",
+                on_duplicate
+                    .iter()
+                    .map(|(col, value)| format!("{} = {}", col, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        Ok(())
     }
 }
 
+/// A row of values as printed in `VALUES`, after the `ROW` keyword if `keyword` is set.
+pub(crate) fn row(values: &[FieldValueExpression], keyword: bool) -> String {
+    format!(
+        "{}({})",
+        if keyword { "ROW" } else { "" },
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+fn fields(i: &str) -> IResult<&str, Vec<Column>> {
+    delimited(
+        preceded(tag("("), whitespace0),
+        field_list,
+        delimited(whitespace0, tag(")"), whitespace0),
+    )(i)
+}
+
+/// A row of values, with or without the `ROW` keyword.
+pub(crate) fn data(i: &str) -> IResult<&str, Vec<FieldValueExpression>> {
+    preceded(opt(row_keyword), values)(i)
+}
+
+fn row_keyword(i: &str) -> IResult<&str, &str> {
+    terminated(tag_no_case("row"), whitespace0)(i)
+}
+
+fn values(i: &str) -> IResult<&str, Vec<FieldValueExpression>> {
+    delimited(
+        pair(tag("("), whitespace0),
+        separated_list0(ws_sep_comma, field_value_expr),
+        tuple((whitespace0, tag(")"), opt(ws_sep_comma))),
+    )(i)
+}
+
+/// The rows after `VALUES`, and whether they were written as `ROW(...)`, which must hold for all
+/// of them or for none.
+fn rows(i: &str) -> IResult<&str, (Vec<Vec<FieldValueExpression>>, bool)> {
+    alt((
+        map(many1(preceded(row_keyword, values)), |rows| (rows, true)),
+        map(many1(values), |rows| (rows, false)),
+    ))(i)
+}
+
+fn on_duplicate(i: &str) -> IResult<&str, Vec<(Column, FieldValueExpression)>> {
+    preceded(
+        tuple((
+            whitespace0,
+            keyword_phrase(&["on", "duplicate", "key", "update"]),
+            whitespace1,
+        )),
+        assignment_expr_list,
+    )(i)
+}
+
+fn insert_priority(i: &str) -> IResult<&str, InsertPriority> {
+    alt((
+        value(InsertPriority::LowPriority, tag_no_case("low_priority")),
+        value(InsertPriority::Delayed, tag_no_case("delayed")),
+        value(InsertPriority::HighPriority, tag_no_case("high_priority")),
+    ))(i)
+}
+
+fn partitions(i: &str) -> IResult<&str, Vec<String>> {
+    preceded(
+        pair(tag_no_case("partition"), whitespace0),
+        delimited(
+            pair(tag("("), whitespace0),
+            separated_list1(ws_sep_comma, map(sql_identifier, String::from)),
+            pair(whitespace0, tag(")")),
+        ),
+    )(i)
+}
+
 /// Parse rule for a SQL insert query.
 /// TODO(malte): support REPLACE, nested selection, DEFAULT VALUES
-named!(pub insertion<CompleteByteSlice, InsertStatement>,
-    do_parse!(
-        tag_no_case!("insert") >>
-        ignore: opt!(preceded!(multispace, tag_no_case!("ignore"))) >>
-        multispace >>
-        tag_no_case!("into") >>
-        multispace >>
-        table: table_reference >>
-        opt_multispace >>
-        fields: opt!(do_parse!(
-                tag!("(") >>
-                opt_multispace >>
-                fields: field_list >>
-                opt_multispace >>
-                tag!(")") >>
-                multispace >>
-                (fields)
-                )
-            ) >>
-        tag_no_case!("values") >>
-        opt_multispace >>
-        data: many1!(
-            do_parse!(
-                tag!("(") >>
-                values: value_list >>
-                tag!(")") >>
-                opt!(
-                    do_parse!(
-                            opt_multispace >>
-                            tag!(",") >>
-                            opt_multispace >>
-                            ()
-                    )
-                ) >>
-                (values)
-            )
-        ) >>
-        upd_if_dup: opt!(do_parse!(
-                opt_multispace >>
-                tag_no_case!("on duplicate key update") >>
-                multispace >>
-                assigns: assignment_expr_list >>
-                (assigns)
-        )) >>
-        statement_terminator >>
-        ({
-            // "table AS alias" isn't legal in INSERT statements
-            assert!(table.alias.is_none());
-            InsertStatement {
-                table: table,
-                fields: fields,
-                data: data,
-                ignore: ignore.is_some(),
-                on_duplicate: upd_if_dup,
-            }
-        })
-    )
-);
+pub fn insertion(i: &str) -> IResult<&str, InsertStatement> {
+    let (
+        remaining_input,
+        (
+            _,
+            priority,
+            ignore,
+            _,
+            _,
+            _,
+            table,
+            partitions,
+            alias,
+            _,
+            fields,
+            _,
+            _,
+            (data, row_constructors),
+            on_duplicate,
+            _,
+        ),
+    ) = tuple((
+        tag_no_case("insert"),
+        opt(preceded(whitespace1, insert_priority)),
+        opt(preceded(whitespace1, tag_no_case("ignore"))),
+        whitespace1,
+        tag_no_case("into"),
+        keyword_whitespace,
+        table_name,
+        opt(preceded(whitespace1, partitions)),
+        opt(as_alias),
+        whitespace0,
+        opt(fields),
+        tag_no_case("values"),
+        whitespace0,
+        rows,
+        opt(on_duplicate),
+        statement_terminator,
+    ))(i)?;
+
+    // "table AS alias" isn't legal in INSERT statements
+    if alias.is_some() {
+        return Err(nom::Err::Error(Error::new(i, ErrorKind::Verify)));
+    }
+
+    Ok((
+        remaining_input,
+        InsertStatement {
+            table,
+            fields,
+            data,
+            row_constructors,
+            ignore: ignore.is_some(),
+            on_duplicate,
+            priority,
+            partitions: partitions.unwrap_or_default(),
+        },
+    ))
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use arithmetic::{ArithmeticBase, ArithmeticExpression, ArithmeticOperator};
-    use column::Column;
+    use column::{Column, FunctionExpression};
+    use common::Literal;
+    use condition::{ConditionBase, ConditionExpression};
     use table::Table;
 
     #[test]
     fn simple_insert() {
         let qstring = "INSERT INTO users VALUES (42, \"test\");";
 
-        let res = insertion(CompleteByteSlice(qstring.as_bytes()));
+        let res = insertion(qstring);
         assert_eq!(
             res.unwrap().1,
             InsertStatement {
@@ -140,7 +282,7 @@ mod tests {
     fn complex_insert() {
         let qstring = "INSERT INTO users VALUES (42, 'test', \"test\", CURRENT_TIMESTAMP);";
 
-        let res = insertion(CompleteByteSlice(qstring.as_bytes()));
+        let res = insertion(qstring);
         assert_eq!(
             res.unwrap().1,
             InsertStatement {
@@ -150,7 +292,7 @@ mod tests {
                     42.into(),
                     "test".into(),
                     "test".into(),
-                    Literal::CurrentTimestamp,
+                    Literal::CurrentTimestamp.into(),
                 ],],
                 ..Default::default()
             }
@@ -161,7 +303,7 @@ mod tests {
     fn insert_with_field_names() {
         let qstring = "INSERT INTO users (id, name) VALUES (42, \"test\");";
 
-        let res = insertion(CompleteByteSlice(qstring.as_bytes()));
+        let res = insertion(qstring);
         assert_eq!(
             res.unwrap().1,
             InsertStatement {
@@ -178,7 +320,7 @@ mod tests {
     fn insert_without_spaces() {
         let qstring = "INSERT INTO users(id, name) VALUES(42, \"test\");";
 
-        let res = insertion(CompleteByteSlice(qstring.as_bytes()));
+        let res = insertion(qstring);
         assert_eq!(
             res.unwrap().1,
             InsertStatement {
@@ -194,7 +336,7 @@ mod tests {
     fn multi_insert() {
         let qstring = "INSERT INTO users (id, name) VALUES (42, \"test\"),(21, \"test2\");";
 
-        let res = insertion(CompleteByteSlice(qstring.as_bytes()));
+        let res = insertion(qstring);
         assert_eq!(
             res.unwrap().1,
             InsertStatement {
@@ -213,13 +355,16 @@ mod tests {
     fn insert_with_parameters() {
         let qstring = "INSERT INTO users (id, name) VALUES (?, ?);";
 
-        let res = insertion(CompleteByteSlice(qstring.as_bytes()));
+        let res = insertion(qstring);
         assert_eq!(
             res.unwrap().1,
             InsertStatement {
                 table: Table::from("users"),
                 fields: Some(vec![Column::from("id"), Column::from("name")]),
-                data: vec![vec![Literal::Placeholder, Literal::Placeholder]],
+                data: vec![vec![
+                    Literal::Placeholder.into(),
+                    Literal::Placeholder.into()
+                ]],
                 ..Default::default()
             }
         );
@@ -230,7 +375,7 @@ mod tests {
         let qstring = "INSERT INTO keystores (`key`, `value`) VALUES (?, ?) \
                        ON DUPLICATE KEY UPDATE `value` = `value` + 1";
 
-        let res = insertion(CompleteByteSlice(qstring.as_bytes()));
+        let res = insertion(qstring);
         let expected_ae = ArithmeticExpression {
             op: ArithmeticOperator::Add,
             left: ArithmeticBase::Column(Column::from("value")),
@@ -242,7 +387,10 @@ mod tests {
             InsertStatement {
                 table: Table::from("keystores"),
                 fields: Some(vec![Column::from("key"), Column::from("value")]),
-                data: vec![vec![Literal::Placeholder, Literal::Placeholder]],
+                data: vec![vec![
+                    Literal::Placeholder.into(),
+                    Literal::Placeholder.into()
+                ]],
                 on_duplicate: Some(vec![(
                     Column::from("value"),
                     FieldValueExpression::Arithmetic(expected_ae),
@@ -252,4 +400,140 @@ mod tests {
         );
     }
 
+    #[test]
+    fn display_ignore_and_on_dup_update() {
+        let qstring = "INSERT IGNORE INTO t (a, b) VALUES (1, 'x') ON DUPLICATE KEY UPDATE b = 'y'";
+        let res = insertion(qstring);
+        assert_eq!(res.unwrap().1.to_string(), qstring);
+    }
+
+    #[test]
+    fn on_dup_update_with_inserted_values() {
+        let qstring = "INSERT INTO t (a, b) VALUES (1, 2) \
+                       ON DUPLICATE KEY UPDATE a = VALUES(a), b = values (b) + 1";
+        let res = insertion(qstring).unwrap().1;
+        let inserted = |name: &str| Column {
+            name: format!("VALUES({})", name).into(),
+            function: Some(Box::new(FunctionExpression::Values(Column::from(name)))),
+            ..Default::default()
+        };
+        assert_eq!(
+            res.on_duplicate,
+            Some(vec![
+                (
+                    Column::from("a"),
+                    FieldValueExpression::Expression(ConditionExpression::Base(
+                        ConditionBase::Field(inserted("a"))
+                    )),
+                ),
+                (
+                    Column::from("b"),
+                    FieldValueExpression::Arithmetic(ArithmeticExpression {
+                        op: ArithmeticOperator::Add,
+                        left: ArithmeticBase::Column(inserted("b")),
+                        right: ArithmeticBase::Scalar(1.into()),
+                        alias: None,
+                    }),
+                ),
+            ])
+        );
+        assert_eq!(
+            res.to_string(),
+            "INSERT INTO t (a, b) VALUES (1, 2) \
+             ON DUPLICATE KEY UPDATE a = VALUES(a), b = VALUES(b) + 1"
+        );
+    }
+
+    #[test]
+    fn insert_modifiers_and_partitions() {
+        let qstring = "INSERT LOW_PRIORITY IGNORE INTO t PARTITION (p0, p1) (a) VALUES (1)";
+        let res = insertion(qstring).unwrap().1;
+        assert_eq!(res.priority, Some(InsertPriority::LowPriority));
+        assert!(res.ignore);
+        assert_eq!(res.partitions, vec![String::from("p0"), String::from("p1")]);
+        assert_eq!(res.to_string(), qstring);
+
+        let res = insertion("insert delayed into t partition(p2) values (1, 2)")
+            .unwrap()
+            .1;
+        assert_eq!(res.priority, Some(InsertPriority::Delayed));
+        assert!(!res.ignore);
+        assert_eq!(
+            res.to_string(),
+            "INSERT DELAYED INTO t PARTITION (p2) VALUES (1, 2)"
+        );
+    }
+
+    #[test]
+    fn insert_default_values() {
+        let qstring =
+            "INSERT INTO t (id, created, name) VALUES (1, DEFAULT, 'a'), (default, ?, 'b')";
+        let res = insertion(qstring).unwrap().1;
+        assert_eq!(
+            res.data,
+            vec![
+                vec![1.into(), Literal::Default.into(), "a".into()],
+                vec![
+                    Literal::Default.into(),
+                    Literal::Placeholder.into(),
+                    "b".into()
+                ],
+            ]
+        );
+        assert_eq!(
+            res.to_string(),
+            "INSERT INTO t (id, created, name) VALUES (1, DEFAULT, 'a'), (DEFAULT, ?, 'b')"
+        );
+    }
+
+    #[test]
+    fn insert_expressions() {
+        let qstring =
+            "INSERT INTO t (id, n, uid) VALUES (NOW(), -1, UUID()), ( 1 + 2 , ?, DEFAULT)";
+        let res = insertion(qstring).unwrap().1;
+        let call = |name: &str| {
+            let function = FunctionExpression::Call(String::from(name), vec![]);
+            FieldValueExpression::Expression(ConditionExpression::Base(ConditionBase::Field(
+                Column {
+                    name: function.to_string().into(),
+                    function: Some(Box::new(function)),
+                    ..Default::default()
+                },
+            )))
+        };
+        assert_eq!(res.data[0], vec![call("NOW"), (-1).into(), call("UUID")]);
+        assert_eq!(
+            res.data[1],
+            vec![
+                FieldValueExpression::Arithmetic(ArithmeticExpression::new(
+                    ArithmeticOperator::Add,
+                    ArithmeticBase::Scalar(1.into()),
+                    ArithmeticBase::Scalar(2.into()),
+                    None,
+                )),
+                Literal::Placeholder.into(),
+                Literal::Default.into(),
+            ]
+        );
+        assert_eq!(
+            res.to_string(),
+            "INSERT INTO t (id, n, uid) VALUES (NOW(), -1, UUID()), (1 + 2, ?, DEFAULT)"
+        );
+    }
+
+    #[test]
+    fn insert_row_constructors() {
+        let qstring = "INSERT INTO t (a, b) VALUES ROW(1, 2), row (3, 4)";
+        let res = insertion(qstring).unwrap().1;
+        assert_eq!(
+            res.data,
+            vec![vec![1.into(), 2.into()], vec![3.into(), 4.into()]]
+        );
+        assert!(res.row_constructors);
+        assert_eq!(
+            res.to_string(),
+            "INSERT INTO t (a, b) VALUES ROW(1, 2), ROW(3, 4)"
+        );
+        assert!(insertion("INSERT INTO t (a, b) VALUES ROW(1, 2), (3, 4)").is_err());
+    }
 }
@@ -0,0 +1,245 @@
+use nom::branch::alt;
+use nom::combinator::{map, map_opt, opt};
+use nom::multi::{many1, separated_list1};
+use nom::sequence::{preceded, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{
+    escape_string, escape_user_variable, sql_identifier, string_literal, user_variable,
+    whitespace0, whitespace1, ws_sep_comma, Literal,
+};
+use keywords::tag_no_case;
+
+/// Field and line formatting of a `SELECT ... INTO OUTFILE` export.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct OutfileOptions {
+    pub charset: Option<String>,
+    pub fields_terminated_by: Option<String>,
+    pub fields_enclosed_by: Option<String>,
+    /// `OPTIONALLY ENCLOSED BY`, which only encloses string fields.
+    pub optionally_enclosed: bool,
+    pub fields_escaped_by: Option<String>,
+    pub lines_starting_by: Option<String>,
+    pub lines_terminated_by: Option<String>,
+}
+
+fn quoted(s: &str) -> String {
+    format!("'{}'", escape_string(s))
+}
+
+impl fmt::Display for OutfileOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref charset) = self.charset {
+            write!(f, " CHARACTER SET {}", charset)?;
+        }
+        if self.fields_terminated_by.is_some()
+            || self.fields_enclosed_by.is_some()
+            || self.fields_escaped_by.is_some()
+        {
+            write!(f, " FIELDS")?;
+            if let Some(ref terminator) = self.fields_terminated_by {
+                write!(f, " TERMINATED BY {}", quoted(terminator))?;
+            }
+            if let Some(ref enclosure) = self.fields_enclosed_by {
+                if self.optionally_enclosed {
+                    write!(f, " OPTIONALLY")?;
+                }
+                write!(f, " ENCLOSED BY {}", quoted(enclosure))?;
+            }
+            if let Some(ref escape) = self.fields_escaped_by {
+                write!(f, " ESCAPED BY {}", quoted(escape))?;
+            }
+        }
+        if self.lines_starting_by.is_some() || self.lines_terminated_by.is_some() {
+            write!(f, " LINES")?;
+            if let Some(ref prefix) = self.lines_starting_by {
+                write!(f, " STARTING BY {}", quoted(prefix))?;
+            }
+            if let Some(ref terminator) = self.lines_terminated_by {
+                write!(f, " TERMINATED BY {}", quoted(terminator))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The target of `SELECT ... INTO`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum IntoClause {
+    Outfile(String, OutfileOptions),
+    Dumpfile(String),
+    /// User variables, stored without the leading `@`.
+    Variables(Vec<String>),
+}
+
+impl fmt::Display for IntoClause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IntoClause::Outfile(ref path, ref options) => {
+                write!(f, "INTO OUTFILE {}{}", quoted(path), options)
+            }
+            IntoClause::Dumpfile(ref path) => write!(f, "INTO DUMPFILE {}", quoted(path)),
+            IntoClause::Variables(ref vars) => write!(
+                f,
+                "INTO {}",
+                vars.iter()
+                    .map(|v| escape_user_variable(v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+fn string_value(i: &str) -> IResult<&str, String> {
+    map_opt(string_literal, |lit| match lit {
+        Literal::String(s) => Some(s),
+        _ => None,
+    })(i)
+}
+
+/// Parses `keyword BY 'value'`.
+fn by_value<'a>(keyword: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, String> {
+    preceded(
+        tuple((
+            tag_no_case(keyword),
+            whitespace1,
+            tag_no_case("by"),
+            whitespace1,
+        )),
+        string_value,
+    )
+}
+
+#[derive(Clone)]
+enum FieldsOption {
+    Terminator(String),
+    Enclosure(bool, String),
+    Escape(String),
+}
+
+fn fields_option(i: &str) -> IResult<&str, FieldsOption> {
+    alt((
+        map(by_value("terminated"), FieldsOption::Terminator),
+        map(
+            tuple((
+                opt(tuple((tag_no_case("optionally"), whitespace1))),
+                by_value("enclosed"),
+            )),
+            |(optionally, enclosure)| FieldsOption::Enclosure(optionally.is_some(), enclosure),
+        ),
+        map(by_value("escaped"), FieldsOption::Escape),
+    ))(i)
+}
+
+fn outfile_options(i: &str) -> IResult<&str, OutfileOptions> {
+    let (remaining_input, (charset, fields, lines)) = tuple((
+        opt(preceded(
+            tuple((
+                whitespace1,
+                tag_no_case("character"),
+                whitespace1,
+                tag_no_case("set"),
+                whitespace1,
+            )),
+            sql_identifier,
+        )),
+        opt(preceded(
+            tuple((
+                whitespace1,
+                alt((tag_no_case("fields"), tag_no_case("columns"))),
+            )),
+            many1(preceded(whitespace1, fields_option)),
+        )),
+        opt(preceded(
+            tuple((whitespace1, tag_no_case("lines"), whitespace1)),
+            tuple((
+                opt(by_value("starting")),
+                opt(preceded(whitespace0, by_value("terminated"))),
+            )),
+        )),
+    ))(i)?;
+
+    let mut options = OutfileOptions {
+        charset: charset.map(String::from),
+        ..Default::default()
+    };
+    for option in fields.unwrap_or_default() {
+        match option {
+            FieldsOption::Terminator(t) => options.fields_terminated_by = Some(t),
+            FieldsOption::Enclosure(optionally, e) => {
+                options.optionally_enclosed = optionally;
+                options.fields_enclosed_by = Some(e);
+            }
+            FieldsOption::Escape(e) => options.fields_escaped_by = Some(e),
+        }
+    }
+    if let Some((starting, terminated)) = lines {
+        options.lines_starting_by = starting;
+        options.lines_terminated_by = terminated;
+    }
+    Ok((remaining_input, options))
+}
+
+/// Parses an `INTO OUTFILE`, `INTO DUMPFILE` or `INTO @var, ...` clause.
+pub fn into_clause(i: &str) -> IResult<&str, IntoClause> {
+    preceded(
+        tuple((tag_no_case("into"), whitespace1)),
+        alt((
+            map(
+                tuple((
+                    tag_no_case("outfile"),
+                    whitespace1,
+                    string_value,
+                    outfile_options,
+                )),
+                |(_, _, path, options)| IntoClause::Outfile(path, options),
+            ),
+            map(
+                preceded(tuple((tag_no_case("dumpfile"), whitespace1)), string_value),
+                IntoClause::Dumpfile,
+            ),
+            map(separated_list1(ws_sep_comma, user_variable), |vars| {
+                IntoClause::Variables(vars.into_iter().map(String::from).collect())
+            }),
+        )),
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outfile_with_options() {
+        let qstring = "INTO OUTFILE '/tmp/users.csv' CHARACTER SET utf8mb4 \
+                       FIELDS TERMINATED BY ',' OPTIONALLY ENCLOSED BY '\"' \
+                       LINES TERMINATED BY '\\n'";
+        let res = into_clause(qstring).unwrap().1;
+        assert_eq!(
+            res,
+            IntoClause::Outfile(
+                String::from("/tmp/users.csv"),
+                OutfileOptions {
+                    charset: Some(String::from("utf8mb4")),
+                    fields_terminated_by: Some(String::from(",")),
+                    fields_enclosed_by: Some(String::from("\"")),
+                    optionally_enclosed: true,
+                    lines_terminated_by: Some(String::from("\n")),
+                    ..Default::default()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn into_variables() {
+        let res = into_clause("into @a, @`b c`").unwrap().1;
+        assert_eq!(
+            res,
+            IntoClause::Variables(vec![String::from("a"), String::from("b c")])
+        );
+        assert_eq!(format!("{}", res), "INTO @a, @`b c`");
+    }
+}
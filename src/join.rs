@@ -1,13 +1,16 @@
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::combinator::value;
+use nom::IResult;
 use std::fmt;
-use std::str;
 
 use column::Column;
+use common::keyword_phrase;
 use condition::ConditionExpression;
+use keywords::{escape_if_keyword, tag_no_case};
 use select::{JoinClause, SelectStatement};
 use table::Table;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum JoinRightSide {
     /// A single table.
     Table(Table),
@@ -23,21 +26,29 @@ impl fmt::Display for JoinRightSide {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             JoinRightSide::Table(ref t) => write!(f, "{}", t)?,
+            JoinRightSide::Tables(ref ts) => write!(
+                f,
+                "({})",
+                ts.iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?,
             JoinRightSide::NestedSelect(ref q, ref a) => {
                 write!(f, "({})", q)?;
-                if a.is_some() {
-                    write!(f, " AS {}", a.as_ref().unwrap())?;
+                if let Some(ref alias) = *a {
+                    write!(f, " AS {}", escape_if_keyword(alias))?;
                 }
             }
             JoinRightSide::NestedJoin(ref jc) => write!(f, "({})", jc)?,
-            _ => unimplemented!(),
         }
         Ok(())
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum JoinOperator {
+    #[default]
     Join,
     LeftJoin,
     LeftOuterJoin,
@@ -54,16 +65,18 @@ impl fmt::Display for JoinOperator {
             JoinOperator::LeftOuterJoin => write!(f, "LEFT OUTER JOIN")?,
             JoinOperator::InnerJoin => write!(f, "INNER JOIN")?,
             JoinOperator::CrossJoin => write!(f, "CROSS JOIN")?,
-            JoinOperator::StraightJoin => write!(f, "STRAIGHT JOIN")?,
+            JoinOperator::StraightJoin => write!(f, "STRAIGHT_JOIN")?,
         }
         Ok(())
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum JoinConstraint {
     On(ConditionExpression),
     Using(Vec<Column>),
+    /// No `ON` or `USING` clause, as in `CROSS JOIN t`.
+    Empty,
 }
 
 impl fmt::Display for JoinConstraint {
@@ -79,22 +92,26 @@ impl fmt::Display for JoinConstraint {
                     .collect::<Vec<_>>()
                     .join(", ")
             )?,
+            JoinConstraint::Empty => (),
         }
         Ok(())
     }
 }
 
 /// Parse binary comparison operators
-named!(pub join_operator<CompleteByteSlice, JoinOperator>,
-        alt!(
-              map!(tag_no_case!("join"), |_| JoinOperator::Join)
-            | map!(tag_no_case!("left join"), |_| JoinOperator::LeftJoin)
-            | map!(tag_no_case!("left outer join"), |_| JoinOperator::LeftOuterJoin)
-            | map!(tag_no_case!("inner join"), |_| JoinOperator::InnerJoin)
-            | map!(tag_no_case!("cross join"), |_| JoinOperator::CrossJoin)
-            | map!(tag_no_case!("straight_join"), |_| JoinOperator::StraightJoin)
-        )
-);
+pub fn join_operator(i: &str) -> IResult<&str, JoinOperator> {
+    alt((
+        value(JoinOperator::Join, tag_no_case("join")),
+        value(JoinOperator::LeftJoin, keyword_phrase(&["left", "join"])),
+        value(
+            JoinOperator::LeftOuterJoin,
+            keyword_phrase(&["left", "outer", "join"]),
+        ),
+        value(JoinOperator::InnerJoin, keyword_phrase(&["inner", "join"])),
+        value(JoinOperator::CrossJoin, keyword_phrase(&["cross", "join"])),
+        value(JoinOperator::StraightJoin, tag_no_case("straight_join")),
+    ))(i)
+}
 
 #[cfg(test)]
 mod tests {
@@ -110,7 +127,7 @@ mod tests {
         let qstring = "SELECT tags.* FROM tags \
                        INNER JOIN taggings ON tags.id = taggings.tag_id";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
 
         let ct = ConditionTree {
             left: Box::new(Base(Field(Column::from("tags.id")))),
@@ -123,6 +140,8 @@ mod tests {
             fields: vec![FieldDefinitionExpression::AllInTable("tags".into())],
             join: vec![JoinClause {
                 operator: JoinOperator::InnerJoin,
+                natural: false,
+                lateral: false,
                 right: JoinRightSide::Table(Table::from("taggings")),
                 constraint: JoinConstraint::On(join_cond),
             }],
@@ -133,4 +152,37 @@ mod tests {
         assert_eq!(q, expected_stmt);
         assert_eq!(qstring, format!("{}", q));
     }
+
+    #[test]
+    fn lateral_joins() {
+        let qstring = "SELECT u.id, r.id FROM users u \
+                       LEFT JOIN LATERAL (SELECT id FROM reviews WHERE reviews.uid = u.id LIMIT 3) AS r ON true \
+                       CROSS JOIN LATERAL UNNEST(u.tags) AS t(tag)";
+        let q = selection(qstring).unwrap().1;
+        assert_eq!(q.join.len(), 2);
+        assert!(q.join.iter().all(|jc| jc.lateral));
+        assert_eq!(q.join[0].operator, JoinOperator::LeftJoin);
+        match q.join[0].right {
+            JoinRightSide::NestedSelect(_, ref alias) => assert_eq!(alias.as_deref(), Some("r")),
+            _ => panic!(),
+        }
+        assert_eq!(q.join[1].constraint, JoinConstraint::Empty);
+        assert_eq!(
+            format!("{}", q),
+            "SELECT u.id, r.id FROM users AS u \
+             LEFT JOIN LATERAL (SELECT id FROM reviews WHERE reviews.uid = u.id LIMIT 3) AS r ON TRUE \
+             CROSS JOIN LATERAL UNNEST(u.tags) AS t(tag)"
+        );
+    }
+
+    #[test]
+    fn natural_and_straight_joins() {
+        let qstring = "SELECT * FROM a NATURAL LEFT JOIN b STRAIGHT_JOIN c ON a.id = c.id";
+        let q = selection(qstring).unwrap().1;
+        assert!(q.join[0].natural);
+        assert_eq!(q.join[0].operator, JoinOperator::LeftJoin);
+        assert!(!q.join[1].natural);
+        assert_eq!(q.join[1].operator, JoinOperator::StraightJoin);
+        assert_eq!(format!("{}", q), qstring);
+    }
 }
@@ -0,0 +1,111 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::multispace0;
+use nom::combinator::{map, opt, value};
+use nom::multi::many1;
+use nom::sequence::{delimited, tuple};
+use nom::IResult;
+use std::fmt;
+
+use column::Column;
+use common::{as_alias, column_identifier_no_alias, integer_literal, string_literal, Literal};
+use keywords::escape_if_keyword;
+
+/// Operators that extract a value from a JSON document.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum JsonOperator {
+    /// `->`
+    Extract,
+    /// `->>`, which unquotes the extracted value
+    ExtractText,
+    /// `#>`
+    ExtractPath,
+    /// `#>>`
+    ExtractPathText,
+}
+
+impl fmt::Display for JsonOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonOperator::Extract => write!(f, "->"),
+            JsonOperator::ExtractText => write!(f, "->>"),
+            JsonOperator::ExtractPath => write!(f, "#>"),
+            JsonOperator::ExtractPathText => write!(f, "#>>"),
+        }
+    }
+}
+
+/// A chain of JSON extractions applied to a column, e.g. `doc->'address'->>'city'`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct JsonExpression {
+    pub column: Column,
+    pub path: Vec<(JsonOperator, Literal)>,
+    pub alias: Option<String>,
+}
+
+impl fmt::Display for JsonExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.column)?;
+        for (op, key) in &self.path {
+            write!(f, "{}{}", op, key)?;
+        }
+        if let Some(ref alias) = self.alias {
+            write!(f, " AS {}", escape_if_keyword(alias))?;
+        }
+        Ok(())
+    }
+}
+
+fn json_operator(i: &str) -> IResult<&str, JsonOperator> {
+    alt((
+        value(JsonOperator::ExtractText, tag("->>")),
+        value(JsonOperator::Extract, tag("->")),
+        value(JsonOperator::ExtractPathText, tag("#>>")),
+        value(JsonOperator::ExtractPath, tag("#>")),
+    ))(i)
+}
+
+/// Parses a column followed by one or more JSON extraction operators, and an optional alias.
+pub fn json_expression(i: &str) -> IResult<&str, JsonExpression> {
+    map(
+        tuple((
+            column_identifier_no_alias,
+            many1(tuple((
+                delimited(multispace0, json_operator, multispace0),
+                alt((string_literal, integer_literal)),
+            ))),
+            opt(as_alias),
+        )),
+        |(column, path, alias)| JsonExpression {
+            column,
+            path,
+            alias: alias.map(String::from),
+        },
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mysql_json_extraction() {
+        let res = json_expression("doc->>'$.name' AS name");
+        assert_eq!(
+            res.unwrap().1,
+            JsonExpression {
+                column: Column::from("doc"),
+                path: vec![(JsonOperator::ExtractText, Literal::from("$.name"))],
+                alias: Some(String::from("name")),
+            }
+        );
+    }
+
+    #[test]
+    fn postgres_json_path() {
+        let res = json_expression("t.data -> 'tags' -> 0 #>> '{a,b}'");
+        let expr = res.unwrap().1;
+        assert_eq!(expr.path.len(), 3);
+        assert_eq!(format!("{}", expr), "t.data->'tags'->0#>>'{a,b}'");
+    }
+}
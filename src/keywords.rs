@@ -1,171 +1,737 @@
-use nom::types::CompleteByteSlice;
-
-named!(keyword_follow_char<CompleteByteSlice, CompleteByteSlice>,
-       peek!(alt!(tag!(" ") | tag!("\n") | tag!(";") |
-                           tag!("(") | tag!(")") | tag!("\t") |
-                           tag!(",") | tag!("=") | eof!()))
-);
-
-named!(keyword_a_to_c<CompleteByteSlice, CompleteByteSlice>,
-       alt!(
-          terminated!(tag_no_case!("ABORT"), keyword_follow_char)
-        | terminated!(tag_no_case!("ACTION"), keyword_follow_char)
-        | terminated!(tag_no_case!("ADD"), keyword_follow_char)
-        | terminated!(tag_no_case!("AFTER"), keyword_follow_char)
-        | terminated!(tag_no_case!("ALL"), keyword_follow_char)
-        | terminated!(tag_no_case!("ALTER"), keyword_follow_char)
-        | terminated!(tag_no_case!("ANALYZE"), keyword_follow_char)
-        | terminated!(tag_no_case!("AND"), keyword_follow_char)
-        | terminated!(tag_no_case!("AS"), keyword_follow_char)
-        | terminated!(tag_no_case!("ASC"), keyword_follow_char)
-        | terminated!(tag_no_case!("ATTACH"), keyword_follow_char)
-        | terminated!(tag_no_case!("AUTOINCREMENT"), keyword_follow_char)
-        | terminated!(tag_no_case!("BEFORE"), keyword_follow_char)
-        | terminated!(tag_no_case!("BEGIN"), keyword_follow_char)
-        | terminated!(tag_no_case!("BETWEEN"), keyword_follow_char)
-        | terminated!(tag_no_case!("BY"), keyword_follow_char)
-        | terminated!(tag_no_case!("CASCADE"), keyword_follow_char)
-        | terminated!(tag_no_case!("CASE"), keyword_follow_char)
-        | terminated!(tag_no_case!("CAST"), keyword_follow_char)
-        | terminated!(tag_no_case!("CHECK"), keyword_follow_char)
-        | terminated!(tag_no_case!("COLLATE"), keyword_follow_char)
-        | terminated!(tag_no_case!("COLUMN"), keyword_follow_char)
-        | terminated!(tag_no_case!("COMMIT"), keyword_follow_char)
-        | terminated!(tag_no_case!("CONFLICT"), keyword_follow_char)
-        | terminated!(tag_no_case!("CONSTRAINT"), keyword_follow_char)
-        | terminated!(tag_no_case!("CREATE"), keyword_follow_char)
-        | terminated!(tag_no_case!("CROSS"), keyword_follow_char)
-        | terminated!(tag_no_case!("CURRENT_DATE"), keyword_follow_char)
-        | terminated!(tag_no_case!("CURRENT_TIME"), keyword_follow_char)
-        | terminated!(tag_no_case!("CURRENT_TIMESTAMP"), keyword_follow_char)
-    )
-);
-
-named!(keyword_d_to_i<CompleteByteSlice, CompleteByteSlice>,
-    alt!(
-          terminated!(tag_no_case!("DATABASE"), keyword_follow_char)
-        | terminated!(tag_no_case!("DEFAULT"), keyword_follow_char)
-        | terminated!(tag_no_case!("DEFERRABLE"), keyword_follow_char)
-        | terminated!(tag_no_case!("DEFERRED"), keyword_follow_char)
-        | terminated!(tag_no_case!("DELETE"), keyword_follow_char)
-        | terminated!(tag_no_case!("DESC"), keyword_follow_char)
-        | terminated!(tag_no_case!("DETACH"), keyword_follow_char)
-        | terminated!(tag_no_case!("DISTINCT"), keyword_follow_char)
-        | terminated!(tag_no_case!("DROP"), keyword_follow_char)
-        | terminated!(tag_no_case!("EACH"), keyword_follow_char)
-        | terminated!(tag_no_case!("ELSE"), keyword_follow_char)
-        | terminated!(tag_no_case!("END"), keyword_follow_char)
-        | terminated!(tag_no_case!("ESCAPE"), keyword_follow_char)
-        | terminated!(tag_no_case!("EXCEPT"), keyword_follow_char)
-        | terminated!(tag_no_case!("EXCLUSIVE"), keyword_follow_char)
-        | terminated!(tag_no_case!("EXISTS"), keyword_follow_char)
-        | terminated!(tag_no_case!("EXPLAIN"), keyword_follow_char)
-        | terminated!(tag_no_case!("FAIL"), keyword_follow_char)
-        | terminated!(tag_no_case!("FOR"), keyword_follow_char)
-        | terminated!(tag_no_case!("FOREIGN"), keyword_follow_char)
-        | terminated!(tag_no_case!("FROM"), keyword_follow_char)
-        | terminated!(tag_no_case!("FULL"), keyword_follow_char)
-        | terminated!(tag_no_case!("GLOB"), keyword_follow_char)
-        | terminated!(tag_no_case!("GROUP"), keyword_follow_char)
-        | terminated!(tag_no_case!("HAVING"), keyword_follow_char)
-        | terminated!(tag_no_case!("IF"), keyword_follow_char)
-        | terminated!(tag_no_case!("IGNORE"), keyword_follow_char)
-        | terminated!(tag_no_case!("IMMEDIATE"), keyword_follow_char)
-        | terminated!(tag_no_case!("IN"), keyword_follow_char)
-        | terminated!(tag_no_case!("INDEX"), keyword_follow_char)
-        | terminated!(tag_no_case!("INDEXED"), keyword_follow_char)
-        | terminated!(tag_no_case!("INITIALLY"), keyword_follow_char)
-        | terminated!(tag_no_case!("INNER"), keyword_follow_char)
-        | terminated!(tag_no_case!("INSERT"), keyword_follow_char)
-        | terminated!(tag_no_case!("INSTEAD"), keyword_follow_char)
-        | terminated!(tag_no_case!("INTERSECT"), keyword_follow_char)
-        | terminated!(tag_no_case!("INTO"), keyword_follow_char)
-        | terminated!(tag_no_case!("IS"), keyword_follow_char)
-        | terminated!(tag_no_case!("ISNULL"), keyword_follow_char)
-    )
-);
-
-named!(keyword_j_to_s<CompleteByteSlice, CompleteByteSlice>,
-    alt!(
-          terminated!(tag_no_case!("ORDER"), keyword_follow_char)
-        | terminated!(tag_no_case!("JOIN"), keyword_follow_char)
-        | terminated!(tag_no_case!("KEY"), keyword_follow_char)
-        | terminated!(tag_no_case!("LEFT"), keyword_follow_char)
-        | terminated!(tag_no_case!("LIKE"), keyword_follow_char)
-        | terminated!(tag_no_case!("LIMIT"), keyword_follow_char)
-        | terminated!(tag_no_case!("MATCH"), keyword_follow_char)
-        | terminated!(tag_no_case!("NATURAL"), keyword_follow_char)
-        | terminated!(tag_no_case!("NO"), keyword_follow_char)
-        | terminated!(tag_no_case!("NOT"), keyword_follow_char)
-        | terminated!(tag_no_case!("NOTNULL"), keyword_follow_char)
-        | terminated!(tag_no_case!("NULL"), keyword_follow_char)
-        | terminated!(tag_no_case!("OF"), keyword_follow_char)
-        | terminated!(tag_no_case!("OFFSET"), keyword_follow_char)
-        | terminated!(tag_no_case!("ON"), keyword_follow_char)
-        | terminated!(tag_no_case!("OR"), keyword_follow_char)
-        | terminated!(tag_no_case!("OUTER"), keyword_follow_char)
-        | terminated!(tag_no_case!("PLAN"), keyword_follow_char)
-        | terminated!(tag_no_case!("PRAGMA"), keyword_follow_char)
-        | terminated!(tag_no_case!("PRIMARY"), keyword_follow_char)
-        | terminated!(tag_no_case!("QUERY"), keyword_follow_char)
-        | terminated!(tag_no_case!("RAISE"), keyword_follow_char)
-        | terminated!(tag_no_case!("RECURSIVE"), keyword_follow_char)
-        | terminated!(tag_no_case!("REFERENCES"), keyword_follow_char)
-        | terminated!(tag_no_case!("REGEXP"), keyword_follow_char)
-        | terminated!(tag_no_case!("REINDEX"), keyword_follow_char)
-        | terminated!(tag_no_case!("RELEASE"), keyword_follow_char)
-        | terminated!(tag_no_case!("RENAME"), keyword_follow_char)
-        | terminated!(tag_no_case!("REPLACE"), keyword_follow_char)
-        | terminated!(tag_no_case!("RESTRICT"), keyword_follow_char)
-        | terminated!(tag_no_case!("RIGHT"), keyword_follow_char)
-        | terminated!(tag_no_case!("ROLLBACK"), keyword_follow_char)
-        | terminated!(tag_no_case!("ROW"), keyword_follow_char)
-        | terminated!(tag_no_case!("SAVEPOINT"), keyword_follow_char)
-        | terminated!(tag_no_case!("SELECT"), keyword_follow_char)
-        | terminated!(tag_no_case!("SET"), keyword_follow_char)
-    )
-);
-
-named!(keyword_t_to_z<CompleteByteSlice, CompleteByteSlice>,
-    alt!(
-          terminated!(tag_no_case!("TABLE"), keyword_follow_char)
-        | terminated!(tag_no_case!("TEMP"), keyword_follow_char)
-        | terminated!(tag_no_case!("TEMPORARY"), keyword_follow_char)
-        | terminated!(tag_no_case!("THEN"), keyword_follow_char)
-        | terminated!(tag_no_case!("TO"), keyword_follow_char)
-        | terminated!(tag_no_case!("TRANSACTION"), keyword_follow_char)
-        | terminated!(tag_no_case!("TRIGGER"), keyword_follow_char)
-        | terminated!(tag_no_case!("UNION"), keyword_follow_char)
-        | terminated!(tag_no_case!("UNIQUE"), keyword_follow_char)
-        | terminated!(tag_no_case!("UPDATE"), keyword_follow_char)
-        | terminated!(tag_no_case!("USING"), keyword_follow_char)
-        | terminated!(tag_no_case!("VACUUM"), keyword_follow_char)
-        | terminated!(tag_no_case!("VALUES"), keyword_follow_char)
-        | terminated!(tag_no_case!("VIEW"), keyword_follow_char)
-        | terminated!(tag_no_case!("VIRTUAL"), keyword_follow_char)
-        | terminated!(tag_no_case!("WHEN"), keyword_follow_char)
-        | terminated!(tag_no_case!("WHERE"), keyword_follow_char)
-        | terminated!(tag_no_case!("WITH"), keyword_follow_char)
-        | terminated!(tag_no_case!("WITHOUT"), keyword_follow_char)
-    )
-);
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+use nom::bytes::complete::{tag, take_while1};
+use nom::combinator::{not, verify};
+use nom::error::{Error, ErrorKind};
+use nom::sequence::terminated;
+use nom::IResult;
+
+use format::{recase_word, IdentifierQuoting, KeywordCase, QuoteStyle};
+
+/// The MySQL 8 reserved words, in upper case and sorted so that they can be binary searched.
+/// None of these can be used as an identifier unless it is quoted.
+const KEYWORDS: &[&str] = &[
+    "ACCESSIBLE",
+    "ADD",
+    "ALL",
+    "ALTER",
+    "ANALYZE",
+    "AND",
+    "AS",
+    "ASC",
+    "ASENSITIVE",
+    "BEFORE",
+    "BETWEEN",
+    "BIGINT",
+    "BINARY",
+    "BLOB",
+    "BOTH",
+    "BY",
+    "CALL",
+    "CASCADE",
+    "CASE",
+    "CHANGE",
+    "CHAR",
+    "CHARACTER",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "CONDITION",
+    "CONSTRAINT",
+    "CONTINUE",
+    "CONVERT",
+    "CREATE",
+    "CROSS",
+    "CUBE",
+    "CUME_DIST",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "CURSOR",
+    "DATABASE",
+    "DATABASES",
+    "DAY_HOUR",
+    "DAY_MICROSECOND",
+    "DAY_MINUTE",
+    "DAY_SECOND",
+    "DEC",
+    "DECIMAL",
+    "DECLARE",
+    "DEFAULT",
+    "DELAYED",
+    "DELETE",
+    "DENSE_RANK",
+    "DESC",
+    "DESCRIBE",
+    "DETERMINISTIC",
+    "DISTINCT",
+    "DISTINCTROW",
+    "DIV",
+    "DOUBLE",
+    "DROP",
+    "EACH",
+    "ELSE",
+    "ELSEIF",
+    "EMPTY",
+    "ENCLOSED",
+    "ESCAPED",
+    "EXCEPT",
+    "EXISTS",
+    "EXIT",
+    "EXPLAIN",
+    "FALSE",
+    "FETCH",
+    "FIRST_VALUE",
+    "FLOAT",
+    "FLOAT4",
+    "FLOAT8",
+    "FOR",
+    "FORCE",
+    "FOREIGN",
+    "FROM",
+    "FULLTEXT",
+    "FUNCTION",
+    "GENERATED",
+    "GET",
+    "GRANT",
+    "GROUP",
+    "GROUPING",
+    "GROUPS",
+    "HAVING",
+    "HIGH_PRIORITY",
+    "HOUR_MICROSECOND",
+    "HOUR_MINUTE",
+    "HOUR_SECOND",
+    "IF",
+    "IGNORE",
+    "IN",
+    "INDEX",
+    "INFILE",
+    "INNER",
+    "INOUT",
+    "INSENSITIVE",
+    "INSERT",
+    "INT",
+    "INT1",
+    "INT2",
+    "INT3",
+    "INT4",
+    "INT8",
+    "INTEGER",
+    "INTERSECT",
+    "INTERVAL",
+    "INTO",
+    "IO_AFTER_GTIDS",
+    "IO_BEFORE_GTIDS",
+    "IS",
+    "ITERATE",
+    "JOIN",
+    "JSON_TABLE",
+    "KEY",
+    "KEYS",
+    "KILL",
+    "LAG",
+    "LAST_VALUE",
+    "LATERAL",
+    "LEAD",
+    "LEADING",
+    "LEAVE",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LINEAR",
+    "LINES",
+    "LOAD",
+    "LOCALTIME",
+    "LOCALTIMESTAMP",
+    "LOCK",
+    "LONG",
+    "LONGBLOB",
+    "LONGTEXT",
+    "LOOP",
+    "LOW_PRIORITY",
+    "MASTER_BIND",
+    "MASTER_SSL_VERIFY_SERVER_CERT",
+    "MATCH",
+    "MAXVALUE",
+    "MEDIUMBLOB",
+    "MEDIUMINT",
+    "MEDIUMTEXT",
+    "MIDDLEINT",
+    "MINUTE_MICROSECOND",
+    "MINUTE_SECOND",
+    "MOD",
+    "MODIFIES",
+    "NATURAL",
+    "NOT",
+    "NO_WRITE_TO_BINLOG",
+    "NTH_VALUE",
+    "NTILE",
+    "NULL",
+    "NUMERIC",
+    "OF",
+    "ON",
+    "OPTIMIZE",
+    "OPTIMIZER_COSTS",
+    "OPTION",
+    "OPTIONALLY",
+    "OR",
+    "ORDER",
+    "OUT",
+    "OUTER",
+    "OUTFILE",
+    "OVER",
+    "PARTITION",
+    "PERCENT_RANK",
+    "PRECISION",
+    "PRIMARY",
+    "PROCEDURE",
+    "PURGE",
+    "RANGE",
+    "RANK",
+    "READ",
+    "READS",
+    "READ_WRITE",
+    "REAL",
+    "RECURSIVE",
+    "REFERENCES",
+    "REGEXP",
+    "RELEASE",
+    "RENAME",
+    "REPEAT",
+    "REPLACE",
+    "REQUIRE",
+    "RESIGNAL",
+    "RESTRICT",
+    "RETURN",
+    "REVOKE",
+    "RIGHT",
+    "RLIKE",
+    "ROW",
+    "ROWS",
+    "ROW_NUMBER",
+    "SCHEMA",
+    "SCHEMAS",
+    "SECOND_MICROSECOND",
+    "SELECT",
+    "SENSITIVE",
+    "SEPARATOR",
+    "SET",
+    "SHOW",
+    "SIGNAL",
+    "SMALLINT",
+    "SPATIAL",
+    "SPECIFIC",
+    "SQL",
+    "SQLEXCEPTION",
+    "SQLSTATE",
+    "SQLWARNING",
+    "SQL_BIG_RESULT",
+    "SQL_CALC_FOUND_ROWS",
+    "SQL_SMALL_RESULT",
+    "SSL",
+    "STARTING",
+    "STORED",
+    "STRAIGHT_JOIN",
+    "SYSTEM",
+    "TABLE",
+    "TERMINATED",
+    "THEN",
+    "TINYBLOB",
+    "TINYINT",
+    "TINYTEXT",
+    "TO",
+    "TRAILING",
+    "TRIGGER",
+    "TRUE",
+    "UNDO",
+    "UNION",
+    "UNIQUE",
+    "UNLOCK",
+    "UNSIGNED",
+    "UPDATE",
+    "USAGE",
+    "USE",
+    "USING",
+    "UTC_DATE",
+    "UTC_TIME",
+    "UTC_TIMESTAMP",
+    "VALUES",
+    "VARBINARY",
+    "VARCHAR",
+    "VARCHARACTER",
+    "VARYING",
+    "VIRTUAL",
+    "WHEN",
+    "WHERE",
+    "WHILE",
+    "WINDOW",
+    "WITH",
+    "WRITE",
+    "XOR",
+    "YEAR_MONTH",
+    "ZEROFILL",
+];
+
+/// Keywords the parser understands that may still name a table, column or alias without quoting,
+/// sorted like `KEYWORDS`. They are only refused as an alias that is not introduced by `AS`, where
+/// they would be mistaken for the clause that follows. MySQL reserves `DUAL`, but the parser reads
+/// it as a name, so it is listed here.
+const NON_RESERVED_KEYWORDS: &[&str] = &[
+    "ABORT",
+    "ACTION",
+    "AFTER",
+    "ATTACH",
+    "AUTOINCREMENT",
+    "BEGIN",
+    "CAST",
+    "COMMIT",
+    "CONFLICT",
+    "DEFERRABLE",
+    "DEFERRED",
+    "DETACH",
+    "DUAL",
+    "END",
+    "ESCAPE",
+    "EXCLUSIVE",
+    "FAIL",
+    "FULL",
+    "GLOB",
+    "ILIKE",
+    "IMMEDIATE",
+    "INDEXED",
+    "INITIALLY",
+    "INSTEAD",
+    "ISNULL",
+    "NO",
+    "NOTNULL",
+    "OFFSET",
+    "PLAN",
+    "PRAGMA",
+    "QUERY",
+    "RAISE",
+    "REINDEX",
+    "ROLLBACK",
+    "SAVEPOINT",
+    "SIMILAR",
+    "TEMP",
+    "TEMPORARY",
+    "TRANSACTION",
+    "VACUUM",
+    "VIEW",
+    "WITHOUT",
+];
+
+/// The other words the grammar matches, such as type names and table options, sorted like
+/// `KEYWORDS`. They are never refused as a name, and are only listed so that the formatter can
+/// recase every word the parser understands.
+const GRAMMAR_WORDS: &[&str] = &[
+    "ABSOLUTE",
+    "ALGORITHM",
+    "ALWAYS",
+    "ANY",
+    "ARRAY",
+    "AUTO_INCREMENT",
+    "AVG",
+    "AVG_ROW_LENGTH",
+    "BERNOULLI",
+    "BIGSERIAL",
+    "BOOL",
+    "BTREE",
+    "BYTEA",
+    "CACHE",
+    "CASCADED",
+    "CHARSET",
+    "CIDR",
+    "CLOSE",
+    "COLUMNS",
+    "COMMENT",
+    "COMPACT",
+    "COMPRESSED",
+    "CONCURRENTLY",
+    "CONNECTION",
+    "COPY",
+    "COUNT",
+    "CYCLE",
+    "DATA",
+    "DATE",
+    "DATETIME",
+    "DEFINER",
+    "DO",
+    "DOMAIN",
+    "DUMPFILE",
+    "DUPLICATE",
+    "DYNAMIC",
+    "E",
+    "ENGINE",
+    "ENUM",
+    "EXTENSION",
+    "FIELDS",
+    "FIRST",
+    "FIXED",
+    "FLUSH",
+    "GEOMCOLLECTION",
+    "GEOMETRY",
+    "GEOMETRYCOLLECTION",
+    "GROUP_CONCAT",
+    "HANDLER",
+    "HASH",
+    "HOLD",
+    "HOSTS",
+    "IDENTITY",
+    "INCREMENT",
+    "INET",
+    "INPLACE",
+    "INSTANT",
+    "INVISIBLE",
+    "INVOKER",
+    "JSON",
+    "JSONB",
+    "KEY_BLOCK_SIZE",
+    "LAST",
+    "LESS",
+    "LINESTRING",
+    "LIST",
+    "LOCAL",
+    "LOCKED",
+    "LOGS",
+    "MACADDR",
+    "MASTER",
+    "MATCHED",
+    "MATERIALIZED",
+    "MAX",
+    "MAX_ROWS",
+    "MERGE",
+    "MIN",
+    "MINVALUE",
+    "MODE",
+    "MONEY",
+    "MULTILINESTRING",
+    "MULTIPOINT",
+    "MULTIPOLYGON",
+    "N",
+    "NCHAR",
+    "NEXT",
+    "NEXTVAL",
+    "NOCYCLE",
+    "NOMAXVALUE",
+    "NOMINVALUE",
+    "NONE",
+    "NOT_LIKE",
+    "NOWAIT",
+    "NVARCHAR",
+    "OPEN",
+    "ORDINALITY",
+    "PACK_KEYS",
+    "PG_CATALOG",
+    "POINT",
+    "POLYGON",
+    "PREV",
+    "PRIOR",
+    "PRIVILEGES",
+    "REDUNDANT",
+    "REFRESH",
+    "REGCLASS",
+    "RELATIVE",
+    "REPEATABLE",
+    "RESET",
+    "RESTART",
+    "ROWID",
+    "ROW_FORMAT",
+    "SCROLL",
+    "SEARCH_PATH",
+    "SECURITY",
+    "SEQUENCE",
+    "SERIAL",
+    "SERIAL2",
+    "SERIAL4",
+    "SERIAL8",
+    "SET_CONFIG",
+    "SHARE",
+    "SHARED",
+    "SIGNED",
+    "SKIP",
+    "SLAVE",
+    "SMALLSERIAL",
+    "SQL_BUFFER_RESULT",
+    "SQL_CACHE",
+    "SQL_NO_CACHE",
+    "START",
+    "STATUS",
+    "STORAGE",
+    "SUBPARTITION",
+    "SUM",
+    "TABLES",
+    "TABLESAMPLE",
+    "TEMPTABLE",
+    "TEXT",
+    "THAN",
+    "TIME",
+    "TIMESTAMP",
+    "TIMESTAMPTZ",
+    "TIMETZ",
+    "TOP",
+    "TSQUERY",
+    "TSVECTOR",
+    "TYPE",
+    "UNDEFINED",
+    "UUID",
+    "VALUE",
+    "VERSION",
+    "VISIBLE",
+    "WORK",
+    "X",
+    "ZONE",
+];
+
+/// The word before a `.` qualifies a name, as in `user.name`, and so is not a keyword. Any other
+/// character ends a keyword, whether or not whitespace separates it from the next token.
+fn keyword_follow_char(i: &str) -> IResult<&str, ()> {
+    not(tag("."))(i)
+}
+
+/// Compares `word` against an upper-case keyword, ignoring the case of `word`.
+fn cmp_caseless(word: &str, keyword: &str) -> Ordering {
+    word.bytes()
+        .map(|b| b.to_ascii_uppercase())
+        .cmp(keyword.bytes())
+}
+
+/// Matches `keyword` at the start of the input, ignoring ASCII case, like nom's `tag_no_case`.
+///
+/// nom folds both sides through Unicode case mapping a character at a time. Keywords are ASCII,
+/// so a byte comparison suffices, and the grammar attempts a great many keywords in turn.
+pub(crate) fn tag_no_case<'a, 'k>(
+    keyword: &'k str,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> + 'k {
+    move |i: &'a str| match i.as_bytes().get(..keyword.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(keyword.as_bytes()) => {
+            let (matched, rest) = i.split_at(keyword.len());
+            Ok((rest, matched))
+        }
+        _ => Err(nom::Err::Error(Error::new(i, ErrorKind::Tag))),
+    }
+}
+
+fn is_keyword_token(keywords: &[&str], word: &str) -> bool {
+    keywords
+        .binary_search_by(|kw| cmp_caseless(word, kw).reverse())
+        .is_ok()
+}
+
+fn keyword_in<'a>(keywords: &'static [&'static str], i: &'a str) -> IResult<&'a str, &'a str> {
+    terminated(
+        verify(
+            take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+            |word: &str| is_keyword_token(keywords, word),
+        ),
+        keyword_follow_char,
+    )(i)
+}
 
 /// Matches any SQL reserved keyword
-named!(pub sql_keyword<CompleteByteSlice, CompleteByteSlice>,
-    do_parse!(
-        kw: alt!(
-              keyword_a_to_c
-            | keyword_d_to_i
-            | keyword_j_to_s
-            | keyword_t_to_z) >>
-        (kw)
-    )
-);
+pub fn sql_keyword(i: &str) -> IResult<&str, &str> {
+    keyword_in(KEYWORDS, i)
+}
+
+/// Matches a keyword that is not reserved, and so is only a keyword where the grammar expects it.
+pub fn non_reserved_keyword(i: &str) -> IResult<&str, &str> {
+    keyword_in(NON_RESERVED_KEYWORDS, i)
+}
+
+/// Returns true if `s` is, in its entirety, a SQL reserved keyword (in any case).
+pub fn is_sql_keyword(s: &str) -> bool {
+    is_keyword_token(KEYWORDS, s)
+}
+
+/// Returns true if `s` is, in its entirety, a non-reserved keyword (in any case).
+pub fn is_non_reserved_keyword(s: &str) -> bool {
+    is_keyword_token(NON_RESERVED_KEYWORDS, s)
+}
+
+/// Returns true if `s` is, in its entirety, any word the grammar matches (in any case).
+pub(crate) fn is_grammar_word(s: &str) -> bool {
+    is_sql_keyword(s) || is_non_reserved_keyword(s) || is_keyword_token(GRAMMAR_WORDS, s)
+}
+
+thread_local! {
+    // no style means none was asked for, so quoted names keep the quotes they were written in
+    static QUOTING: Cell<(IdentifierQuoting, Option<QuoteStyle>)> =
+        const { Cell::new((IdentifierQuoting::WhenNeeded, None)) };
+    // the case the formatter gives keywords, if it is formatting
+    static RECASING: Cell<Option<KeywordCase>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with `quoting` as the policy for identifiers printed on this thread, `style` as the
+/// way they are quoted, and `case` as the case the output's keywords are then given.
+pub(crate) fn with_identifier_quoting<T, F: FnOnce() -> T>(
+    quoting: IdentifierQuoting,
+    style: QuoteStyle,
+    case: KeywordCase,
+    f: F,
+) -> T {
+    struct Restore((IdentifierQuoting, Option<QuoteStyle>), Option<KeywordCase>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            QUOTING.with(|q| q.set(self.0));
+            RECASING.with(|r| r.set(self.1));
+        }
+    }
 
+    let _restore = Restore(
+        QUOTING.with(|q| q.replace((quoting, Some(style)))),
+        RECASING.with(|r| r.replace(Some(case))),
+    );
+    f()
+}
+
+/// Quotes `s` in `style`, doubling any closing quote within it.
+fn quote(s: &str, style: QuoteStyle) -> String {
+    match style {
+        QuoteStyle::Backtick => format!("`{}`", s.replace('`', "``")),
+        QuoteStyle::DoubleQuote => format!("\"{}\"", s.replace('"', "\"\"")),
+        QuoteStyle::Bracket => format!("[{}]", s.replace(']', "]]")),
+    }
+}
+
+/// Whether `s` would not read back as the same identifier unquoted: reserved keywords, names that
+/// are empty, start with a digit or hold anything but letters, digits and underscores, and names
+/// the formatter would recase as keywords.
+fn needs_quotes(s: &str) -> bool {
+    sql_keyword(s).is_ok()
+        || s.is_empty()
+        || s.starts_with(|c: char| c.is_ascii_digit())
+        || !s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || RECASING
+            .with(|r| r.get())
+            .is_some_and(|case| recase_word(s, case) != s)
+}
+
+/// Renders a name other than a table or column, such as a constraint, index or schema, quoting it
+/// only where it would not read back as the same identifier, in backticks by default. The
+/// `Never` policy leaves it unquoted.
 pub fn escape_if_keyword(s: &str) -> String {
-    if sql_keyword(CompleteByteSlice(s.as_bytes())).is_ok() {
-        format!("`{}`", s)
-    } else {
-        s.to_owned()
+    match QUOTING.with(|q| q.get()) {
+        (IdentifierQuoting::Never, _) => s.to_owned(),
+        (_, style) if needs_quotes(s) => quote(s, style.unwrap_or_default()),
+        _ => s.to_owned(),
+    }
+}
+
+/// Renders a table or column name, or an alias of one, quoting it as the current
+/// `IdentifierQuoting` policy asks.
+pub fn escape_identifier(s: &str) -> String {
+    match QUOTING.with(|q| q.get()) {
+        (IdentifierQuoting::Always, style) => quote(s, style.unwrap_or_default()),
+        _ => escape_if_keyword(s),
+    }
+}
+
+/// Renders a name as `escape_identifier` does, except that a name that was written in quotes is
+/// always quoted: in the style the formatter asks for, or else in the quotes it was written in.
+/// Only the `Never` policy drops them.
+pub fn escape_quoted(s: &str, written: Option<QuoteStyle>) -> String {
+    match (QUOTING.with(|q| q.get()), written) {
+        ((IdentifierQuoting::Never, _), _) | (_, None) => escape_identifier(s),
+        ((_, style), Some(written)) => quote(s, style.unwrap_or(written)),
+    }
+}
+
+/// Renders the name of a called function, which is only quoted when it is a reserved keyword:
+/// some databases take a quoted name for a user-defined function rather than a built-in one.
+pub fn escape_function_name(s: &str) -> String {
+    match QUOTING.with(|q| q.get()) {
+        (IdentifierQuoting::Never, _) => s.to_owned(),
+        (_, style) if sql_keyword(s).is_ok() => quote(s, style.unwrap_or_default()),
+        _ => s.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyword_tables_are_sorted_and_disjoint() {
+        assert!(KEYWORDS.windows(2).all(|w| w[0] < w[1]));
+        assert!(NON_RESERVED_KEYWORDS.windows(2).all(|w| w[0] < w[1]));
+        assert!(NON_RESERVED_KEYWORDS
+            .iter()
+            .all(|w| !is_keyword_token(KEYWORDS, w)));
+        assert!(GRAMMAR_WORDS.windows(2).all(|w| w[0] < w[1]));
+        assert!(GRAMMAR_WORDS
+            .iter()
+            .all(|w| !is_sql_keyword(w) && !is_non_reserved_keyword(w)));
+    }
+
+    #[test]
+    fn grammar_words_cover_the_grammar() {
+        // every word matched by a literal in the parsers, outside their tests, must be listed
+        use std::fs;
+
+        let calls = [
+            "tag_no_case(",
+            "keyword_phrase(&[",
+            "option(",
+            "option_name(",
+            "option_value(",
+        ];
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+        for entry in fs::read_dir(dir).unwrap() {
+            let source = fs::read_to_string(entry.unwrap().path()).unwrap();
+            let source = source.split("#[cfg(test)]").next().unwrap();
+            for call in &calls {
+                for (start, _) in source.match_indices(call) {
+                    let mut rest = &source[start + call.len()..];
+                    while let Some(literal) = rest.strip_prefix('"') {
+                        let end = literal.find('"').unwrap();
+                        for word in
+                            literal[..end].split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                        {
+                            if !word.is_empty() && !word.starts_with(|c: char| c.is_ascii_digit()) {
+                                assert!(is_grammar_word(word), "{} is not listed", word);
+                            }
+                        }
+                        rest = literal[end + 1..]
+                            .trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn keyword_matching() {
+        assert_eq!(sql_keyword("select * from t"), Ok((" * from t", "select")));
+        assert_eq!(
+            sql_keyword("Current_Timestamp"),
+            Ok(("", "Current_Timestamp"))
+        );
+        assert!(sql_keyword("selected").is_err());
+        assert!(sql_keyword("key.id").is_err());
+        assert!(sql_keyword("users").is_err());
+        assert!(is_sql_keyword("WINDOW"));
+        assert!(!is_sql_keyword("WITH OUT"));
+
+        // Non-reserved keywords are kept apart, so they can still name columns.
+        assert!(!is_sql_keyword("WITHOUT"));
+        assert_eq!(non_reserved_keyword("offset 5"), Ok((" 5", "offset")));
+        assert!(non_reserved_keyword("select").is_err());
+    }
+
+    #[test]
+    fn caseless_tags() {
+        assert_eq!(
+            tag_no_case("order by")("ORDER BY id"),
+            Ok((" id", "ORDER BY"))
+        );
+        assert_eq!(tag_no_case("on")("oN"), Ok(("", "oN")));
+        assert!(tag_no_case("order")("ord").is_err());
+        assert!(tag_no_case("order by")("order  by").is_err());
+        // a multi-byte character never matches, and input is never split inside one
+        assert!(tag_no_case("ss")("ß").is_err());
+        assert_eq!(tag_no_case("a")("aé"), Ok(("é", "a")));
     }
 }
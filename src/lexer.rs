@@ -0,0 +1,374 @@
+use std::fmt;
+use std::ops::Range;
+
+use common::is_sql_identifier;
+use keywords::{is_non_reserved_keyword, is_sql_keyword};
+
+/// The lexical class of a `Token`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TokenKind {
+    Whitespace,
+    /// `-- ...`, `# ...` or `/* ... */`, including the delimiters and, for line comments, the
+    /// terminating newline.
+    Comment,
+    /// A reserved or non-reserved keyword; whether it acts as a name depends on the grammar.
+    Keyword,
+    Identifier,
+    /// A backtick-quoted identifier, including the backticks.
+    QuotedIdentifier,
+    /// A single- or double-quoted string literal, including the quotes.
+    String,
+    Number,
+    /// `?`, `$1` or `:name`.
+    Placeholder,
+    /// A user variable (`@name`) or system variable (`@@name`).
+    Variable,
+    Operator,
+    /// `(`, `)`, `[`, `]`, `,`, `.` and `;`.
+    Punctuation,
+}
+
+/// A token, borrowing its text from the tokenized input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset of the token in the input.
+    pub offset: usize,
+}
+
+impl<'a> Token<'a> {
+    /// The byte range of the token in the input.
+    pub fn span(&self) -> Range<usize> {
+        self.offset..self.offset + self.text.len()
+    }
+
+    /// Whether the token carries no meaning for the grammar (whitespace or a comment).
+    pub fn is_trivia(&self) -> bool {
+        self.kind == TokenKind::Whitespace || self.kind == TokenKind::Comment
+    }
+}
+
+/// A quoted literal, quoted identifier or block comment that is not closed before the input ends.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LexError {
+    /// Byte offset of the opening delimiter.
+    pub offset: usize,
+    pub expected: &'static str,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unterminated token at offset {}: expected `{}`",
+            self.offset, self.expected
+        )
+    }
+}
+
+impl ::std::error::Error for LexError {}
+
+/// Operators of more than one character, longest first so that the longest match wins.
+const MULTI_CHAR_OPERATORS: &[&str] = &[
+    "!~*", "#>>", "->>", "<=>", "!=", "!~", "#>", "&&", "->", "::", ":=", "<<", "<=", "<>", "<@",
+    ">=", ">>", "?&", "?|", "@>", "@@", "||", "~*",
+];
+
+const OPERATOR_CHARS: &str = "!%&*+-/<=>?@^|~:#";
+
+/// Iterator over the tokens of a SQL text, produced by `tokenize`.
+///
+/// The tokens cover the input without gaps, so concatenating their text reproduces it. After an
+/// error, the iterator is exhausted.
+pub struct Tokens<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+/// Splits `input` into tokens, following MySQL's quoting and comment rules.
+///
+/// The tokenizer is independent of the grammar and of the dialect: it does not know which
+/// statements are valid, and it always quotes identifiers with backticks and strings with single
+/// or double quotes, in which a backslash escapes the next character. It therefore differs from
+/// the parsers for other dialects: under `Dialect::Postgres` a double-quoted word is an identifier
+/// and a backslash in a standard string is an ordinary character, and a SQL Server `[name]` comes
+/// out as an identifier between two punctuation tokens. It is meant for tools such as syntax
+/// highlighters that need a token stream with spans. The statement parsers do not consume these
+/// tokens; they work on the text directly, since what a token is can depend on the grammar as
+/// well as the dialect: a non-reserved keyword is a name in some positions.
+pub fn tokenize(input: &str) -> Tokens<'_> {
+    Tokens { input, pos: 0 }
+}
+
+/// `--` only starts a comment if followed by whitespace (or the end of the input), so that
+/// `1--1` remains an arithmetic expression.
+fn starts_line_comment(s: &str) -> bool {
+    s.starts_with("--") && s[2..].chars().next().is_none_or(char::is_whitespace)
+}
+
+/// The length of a quoted token starting with `quote`, or `None` if it is not closed. Doubled
+/// quotes and, except in backticks, backslash escapes do not close it.
+fn quoted_len(s: &str, quote: char) -> Option<usize> {
+    let mut chars = s.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' && quote != '`' {
+            chars.next();
+        } else if c == quote {
+            if chars.peek().map(|&(_, n)| n) == Some(quote) {
+                chars.next();
+            } else {
+                return Some(i + 1);
+            }
+        }
+    }
+    None
+}
+
+fn identifier_len(s: &str) -> usize {
+    s.find(|c| !is_sql_identifier(c)).unwrap_or(s.len())
+}
+
+fn number_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let digits = |from: usize| {
+        from + bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut len = digits(0);
+    if bytes.get(len) == Some(&b'.') && bytes.get(len + 1).is_some_and(u8::is_ascii_digit) {
+        len = digits(len + 1);
+    }
+    if matches!(bytes.get(len), Some(b'e') | Some(b'E')) {
+        let sign = matches!(bytes.get(len + 1), Some(b'+') | Some(b'-')) as usize;
+        if bytes.get(len + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            len = digits(len + 1 + sign);
+        }
+    }
+    len
+}
+
+/// Classifies the token at the start of `rest`, returning its kind and length, or the delimiter
+/// that is missing.
+fn scan(rest: &str) -> Result<(TokenKind, usize), &'static str> {
+    let c = rest.chars().next().unwrap();
+    let second = rest[c.len_utf8()..].chars().next();
+    if c.is_whitespace() {
+        let len = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        return Ok((TokenKind::Whitespace, len));
+    }
+    if starts_line_comment(rest) || (c == '#' && second != Some('>')) {
+        let len = rest.find('\n').map(|i| i + 1).unwrap_or(rest.len());
+        return Ok((TokenKind::Comment, len));
+    }
+    if let Some(body) = rest.strip_prefix("/*") {
+        return body
+            .find("*/")
+            .map(|i| (TokenKind::Comment, i + 4))
+            .ok_or("*/");
+    }
+    match c {
+        '\'' | '"' => {
+            return quoted_len(rest, c)
+                .map(|len| (TokenKind::String, len))
+                .ok_or(if c == '"' { "\"" } else { "'" });
+        }
+        '`' => {
+            return quoted_len(rest, c)
+                .map(|len| (TokenKind::QuotedIdentifier, len))
+                .ok_or("`");
+        }
+        '(' | ')' | '[' | ']' | ',' | ';' => return Ok((TokenKind::Punctuation, 1)),
+        '.' if !second.is_some_and(|n| n.is_ascii_digit()) => {
+            return Ok((TokenKind::Punctuation, 1))
+        }
+        _ => (),
+    }
+    if c.is_ascii_digit() || c == '.' {
+        // a number directly followed by letters is an identifier, such as `1st`
+        let len = number_len(rest);
+        if c != '.' && rest[len..].starts_with(is_sql_identifier) {
+            return Ok((TokenKind::Identifier, identifier_len(rest)));
+        }
+        return Ok((TokenKind::Number, len.max(1)));
+    }
+    if is_sql_identifier(c) {
+        let len = identifier_len(rest);
+        let word = &rest[..len];
+        let kind = if is_sql_keyword(word) || is_non_reserved_keyword(word) {
+            TokenKind::Keyword
+        } else {
+            TokenKind::Identifier
+        };
+        return Ok((kind, len));
+    }
+    if c == '@' {
+        let name = if second == Some('@') { 2 } else { 1 };
+        if rest[name..].starts_with('`') {
+            return quoted_len(&rest[name..], '`')
+                .map(|len| (TokenKind::Variable, name + len))
+                .ok_or("`");
+        }
+        let len = identifier_len(&rest[name..]);
+        if len > 0 {
+            return Ok((TokenKind::Variable, name + len));
+        }
+    }
+    if c == '$' || c == ':' {
+        let len = identifier_len(&rest[1..]);
+        let numbered = rest[1..1 + len].chars().all(|d| d.is_ascii_digit());
+        if len > 0 && (c == ':' || numbered) {
+            return Ok((TokenKind::Placeholder, 1 + len));
+        }
+    }
+    if let Some(op) = MULTI_CHAR_OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+        return Ok((TokenKind::Operator, op.len()));
+    }
+    if c == '?' {
+        return Ok((TokenKind::Placeholder, 1));
+    }
+    if OPERATOR_CHARS.contains(c) {
+        return Ok((TokenKind::Operator, 1));
+    }
+    // anything else is passed through a character at a time for the grammar to reject
+    Ok((TokenKind::Operator, c.len_utf8()))
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.input[self.pos..];
+        if rest.is_empty() {
+            return None;
+        }
+        let offset = self.pos;
+        match scan(rest) {
+            Ok((kind, len)) => {
+                self.pos += len;
+                Some(Ok(Token {
+                    kind,
+                    text: &rest[..len],
+                    offset,
+                }))
+            }
+            Err(expected) => {
+                self.pos = self.input.len();
+                Some(Err(LexError { offset, expected }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(input)
+            .map(|t| t.unwrap())
+            .filter(|t| t.kind != TokenKind::Whitespace)
+            .map(|t| (t.kind, t.text))
+            .collect()
+    }
+
+    #[test]
+    fn tokens_cover_the_input() {
+        let input = "SELECT `a`, t.b -- note\nFROM t /* c */ WHERE x <=> 'it''s' AND y = ?;";
+        let tokens: Vec<_> = tokenize(input).map(|t| t.unwrap()).collect();
+        let text: String = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(text, input);
+        assert!(tokens.windows(2).all(|w| w[0].span().end == w[1].offset));
+    }
+
+    #[test]
+    fn token_kinds() {
+        use self::TokenKind::*;
+        assert_eq!(
+            kinds("select `from`, t.a::int, 1.5e3, .5, @x, @@y, $1, :name FROM t # done"),
+            vec![
+                (Keyword, "select"),
+                (QuotedIdentifier, "`from`"),
+                (Punctuation, ","),
+                (Identifier, "t"),
+                (Punctuation, "."),
+                (Identifier, "a"),
+                (Operator, "::"),
+                (Keyword, "int"),
+                (Punctuation, ","),
+                (Number, "1.5e3"),
+                (Punctuation, ","),
+                (Number, ".5"),
+                (Punctuation, ","),
+                (Variable, "@x"),
+                (Punctuation, ","),
+                (Variable, "@@y"),
+                (Punctuation, ","),
+                (Placeholder, "$1"),
+                (Punctuation, ","),
+                (Placeholder, ":name"),
+                (Keyword, "FROM"),
+                (Identifier, "t"),
+                (Comment, "# done"),
+            ]
+        );
+        assert_eq!(
+            kinds("a->>'k' #> b 1--1 status"),
+            vec![
+                (Identifier, "a"),
+                (Operator, "->>"),
+                (String, "'k'"),
+                (Operator, "#>"),
+                (Identifier, "b"),
+                (Number, "1"),
+                (Operator, "-"),
+                (Operator, "-"),
+                (Number, "1"),
+                (Identifier, "status"),
+            ]
+        );
+        assert_eq!(
+            kinds("a !~* b ~* c"),
+            vec![
+                (Identifier, "a"),
+                (Operator, "!~*"),
+                (Identifier, "b"),
+                (Operator, "~*"),
+                (Identifier, "c"),
+            ]
+        );
+        assert_eq!(
+            kinds("tsv @@ q"),
+            vec![(Identifier, "tsv"), (Operator, "@@"), (Identifier, "q"),]
+        );
+        // MySQL's quoting, whatever the dialect the text was written for
+        assert_eq!(
+            kinds("\"a\" [b] 'c\\'d'"),
+            vec![
+                (String, "\"a\""),
+                (Punctuation, "["),
+                (Identifier, "b"),
+                (Punctuation, "]"),
+                (String, "'c\\'d'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_tokens() {
+        let err = tokenize("SELECT 'abc").last().unwrap().unwrap_err();
+        assert_eq!(
+            err,
+            LexError {
+                offset: 7,
+                expected: "'",
+            }
+        );
+        assert!(tokenize("SELECT 1 /* open").any(|t| t.is_err()));
+        assert_eq!(tokenize("'a\\'").filter(|t| t.is_err()).count(), 1);
+    }
+}
@@ -1,4 +1,3 @@
-#[macro_use]
 extern crate nom;
 
 extern crate serde;
@@ -9,44 +8,171 @@ extern crate serde_derive;
 #[macro_use]
 extern crate pretty_assertions;
 
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+pub use self::admin::{AdminStatement, FlushOption, KillType, ResetOption};
+pub use self::alter::{
+    AlterAlgorithm, AlterLock, AlterTableDefinition, AlterTableStatement, ColumnPosition,
+};
 pub use self::arithmetic::{ArithmeticBase, ArithmeticExpression, ArithmeticOperator};
-pub use self::column::{Column, ColumnConstraint, ColumnSpecification, FunctionExpression};
+pub use self::array::SubscriptExpression;
+pub use self::builder::{CreateTableBuilder, InsertBuilder, SelectBuilder};
+pub use self::call::CallStatement;
+pub use self::canonical::{
+    ast_eq, canonicalize, fingerprint, shape_fingerprint, FINGERPRINT_VERSION,
+};
+pub use self::column::{
+    Column, ColumnConstraint, ColumnSpecification, FunctionExpression, IdentityGeneration,
+};
 pub use self::common::{
-    FieldDefinitionExpression, FieldValueExpression, Literal, LiteralExpression, Operator, Real,
-    SqlType, TableKey,
+    FieldDefinitionExpression, FieldValueExpression, IndexOptions, IndexType, KeyPart, Literal,
+    LiteralExpression, Operator, Real, SqlType, TableKey, TypeAttributes, VariableExpression,
 };
 pub use self::compound_select::{CompoundSelectOperator, CompoundSelectStatement};
-pub use self::condition::{ConditionBase, ConditionExpression, ConditionTree};
-pub use self::create::{CreateTableStatement, CreateViewStatement, SelectSpecification};
+pub use self::condition::{ConditionBase, ConditionExpression, ConditionTree, PredicateExpression};
+pub use self::create::{
+    CreateTableStatement, CreateViewStatement, SelectSpecification, ViewAlgorithm, ViewCheckOption,
+    ViewSecurity,
+};
+pub use self::cursor::{
+    CloseCursorStatement, DeclareCursorStatement, FetchDirection, FetchStatement,
+};
+pub use self::database::{CreateDatabaseStatement, DropDatabaseStatement, UseStatement};
 pub use self::delete::DeleteStatement;
-pub use self::insert::InsertStatement;
+pub use self::dependency::{view_dependencies, DependencyCycle, DependencyGraph};
+pub use self::dialect::Dialect;
+pub use self::do_statement::DoStatement;
+pub use self::extension::CreateExtensionStatement;
+pub use self::foreignkey::{ForeignKeySpecification, ReferentialAction};
+pub use self::format::{format_query, FormatOptions, IdentifierQuoting, KeywordCase, QuoteStyle};
+pub use self::handler::{HandlerAction, HandlerReadPosition, HandlerStatement};
+pub use self::ident::{Ident, Interner};
+pub use self::index::CreateIndexStatement;
+pub use self::insert::{InsertPriority, InsertStatement};
+pub use self::into::{IntoClause, OutfileOptions};
 pub use self::join::{JoinConstraint, JoinOperator, JoinRightSide};
-pub use self::order::{OrderClause, OrderType};
+pub use self::json::{JsonExpression, JsonOperator};
+pub use self::keywords::is_sql_keyword;
+pub use self::lexer::{tokenize, LexError, Token, TokenKind, Tokens};
+pub use self::limits::{ParserLimits, DEFAULT_MAX_DEPTH};
+pub use self::lineage::{
+    compound_select_lineage, query_lineage, select_lineage, ColumnLineage, SourceColumn,
+};
+pub use self::lock::{LockTablesStatement, LockType, TableLock};
+pub use self::lossless::{parse_query_lossless, parse_query_lossless_with_options, LosslessQuery};
+pub use self::materialized_view::{
+    CreateMaterializedViewStatement, DropMaterializedViewStatement,
+    RefreshMaterializedViewStatement,
+};
+pub use self::merge::{MergeAction, MergeClause, MergeStatement};
+pub use self::metrics::{query_metrics, QueryMetrics};
+pub use self::order::{FieldReference, OrderClause, OrderType};
 pub use self::parser::*;
-pub use self::select::{GroupByClause, JoinClause, LimitClause, SelectStatement};
-pub use self::set::SetStatement;
-pub use self::table::Table;
+pub use self::partition::{
+    PartitionBy, PartitionDefinition, PartitionExpression, PartitionKind, PartitionScheme,
+    PartitionValues,
+};
+pub use self::placeholder::{
+    bind, placeholder_spans, placeholders, BindError, Placeholder, PlaceholderContext,
+    PlaceholderSpan, PlaceholderSpans, PlaceholderSyntax,
+};
+pub use self::predicate::{split_predicates, split_where_predicates, PredicateSplit};
+pub use self::rewrite::{
+    enforce_limit, expand_stars, infer_insert_columns, inject_predicate, rename_tables,
+    strip_for_explain, to_count_query, InsertColumnsError, UnknownTable, UnsupportedStatement,
+};
+pub use self::select::{
+    GroupByClause, JoinClause, LimitClause, LockStrength, LockWait, LockingClause, SelectModifiers,
+    SelectStatement,
+};
+pub use self::sequence::{
+    AlterSequenceStatement, CreateSequenceStatement, DropSequenceStatement, SequenceOptions,
+};
+pub use self::serialization::{VersionedQuery, AST_VERSION};
+pub use self::set::{SetSearchPathStatement, SetStatement, SetValue};
+pub use self::shard::{shard_keys, ShardKey};
+pub use self::simplify::{
+    and_conditions, conjunctive_normal_form, conjuncts, simplify, SimplifiedCondition,
+    MAX_CNF_CLAUSES,
+};
+pub use self::stream::{
+    parse_script_lenient, parse_script_lenient_with_options, StatementSplitter, StatementStream,
+    StreamError,
+};
+#[cfg(feature = "rayon")]
+pub use self::stream::{parse_script_parallel, parse_script_parallel_with_options};
+pub use self::table::{
+    IndexHint, IndexHintScope, IndexHintType, Table, TableFunction, TableSample, TableValues,
+};
+pub use self::transaction::TransactionStatement;
 pub use self::update::UpdateStatement;
-pub use self::foreignkey::{ForeignKeySpecification};
+pub use self::user_type::{
+    CreateDomainStatement, CreateTypeStatement, DomainCheck, TypeDefinition,
+};
+pub use self::values::ValuesStatement;
+pub use self::with::{CommonTableExpression, WithStatement};
 
+pub mod format;
 pub mod parser;
+#[cfg(feature = "proptest")]
+pub mod strategy;
 
-#[macro_use]
-mod keywords;
+mod admin;
+mod alter;
 mod arithmetic;
+mod array;
+mod builder;
+mod call;
+mod canonical;
 mod column;
 mod common;
 mod compound_select;
 mod condition;
 mod create;
 mod create_table_options;
+mod cursor;
+mod database;
 mod delete;
+mod dependency;
+mod dialect;
+mod do_statement;
 mod drop;
+mod extension;
+mod foreignkey;
+mod handler;
+mod ident;
+mod index;
 mod insert;
+mod into;
 mod join;
+mod json;
+mod keywords;
+mod lexer;
+mod limits;
+mod lineage;
+mod lock;
+mod lossless;
+mod materialized_view;
+mod merge;
+mod metrics;
 mod order;
+mod partition;
+mod placeholder;
+mod predicate;
+mod rewrite;
 mod select;
+mod sequence;
+mod serialization;
 mod set;
+mod shard;
+mod simplify;
+mod stream;
 mod table;
+mod transaction;
 mod update;
-mod foreignkey;
+mod user_type;
+mod values;
+mod with;
@@ -0,0 +1,197 @@
+use nom::error::{Error, ErrorKind};
+use nom::IResult;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+/// Default for `ParserLimits::max_depth`, well within the stack of a spawned thread.
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+/// Bounds on the queries accepted by `parse_query_with_limits`, so that untrusted input cannot
+/// exhaust the stack or take unbounded time to parse.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ParserLimits {
+    /// Maximum length of a query in bytes.
+    pub max_length: usize,
+    /// Maximum nesting depth of parenthesized or negated conditions, subqueries, function calls
+    /// and nested joins.
+    pub max_depth: usize,
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        ParserLimits {
+            max_length: usize::MAX,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+/// What a memoized parser returned: the length of the input left over and the output, or where
+/// and how it failed.
+type Memoized<O> = Result<(usize, O), (bool, usize, usize, ErrorKind)>;
+
+/// The results of memoized parsers over one statement, keyed by the parser and the address and
+/// length of its input, along with the address range of the statement.
+struct Memo {
+    start: usize,
+    end: usize,
+    results: HashMap<(usize, usize, usize), Box<dyn Any>>,
+}
+
+thread_local! {
+    static MAX_DEPTH: Cell<usize> = const { Cell::new(DEFAULT_MAX_DEPTH) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// Address of the input at which the innermost level of nesting started.
+    static LEVEL_START: Cell<usize> = const { Cell::new(0) };
+    static MEMO: RefCell<Option<Memo>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `max_depth` as the nesting limit for parsers on this thread.
+pub(crate) fn with_max_depth<T, F: FnOnce() -> T>(max_depth: usize, f: F) -> T {
+    struct Restore(usize, usize, usize);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            MAX_DEPTH.with(|m| m.set(self.0));
+            DEPTH.with(|d| d.set(self.1));
+            LEVEL_START.with(|s| s.set(self.2));
+        }
+    }
+
+    let _restore = Restore(
+        MAX_DEPTH.with(|m| m.replace(max_depth)),
+        DEPTH.with(|d| d.replace(0)),
+        LEVEL_START.with(|s| s.replace(0)),
+    );
+    f()
+}
+
+/// Runs `f`, which parses `input`, remembering the results of `memoized` parsers over it.
+pub(crate) fn with_memo<T, F: FnOnce() -> T>(input: &str, f: F) -> T {
+    struct Restore(Option<Memo>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            MEMO.with(|m| *m.borrow_mut() = self.0.take());
+        }
+    }
+
+    let start = input.as_ptr() as usize;
+    let memo = Memo {
+        start,
+        end: start + input.len(),
+        results: HashMap::new(),
+    };
+    let _restore = Restore(MEMO.with(|m| m.borrow_mut().replace(memo)));
+    f()
+}
+
+/// Runs `parser` on `i`, or returns what it returned the last time it was run there. Parsers
+/// that try several readings of the same text, each of which may contain the construct again,
+/// take time exponential in how deeply it is nested unless the inner results are remembered.
+///
+/// Results are only remembered within `with_memo`, and for input within the statement it was
+/// given: elsewhere addresses may be reused for different text.
+pub(crate) fn memoized<'a, O: Clone + 'static>(
+    parser: fn(&'a str) -> IResult<&'a str, O>,
+    i: &'a str,
+) -> IResult<&'a str, O> {
+    let address = i.as_ptr() as usize;
+    let key = (parser as usize, address, i.len());
+    let within = MEMO.with(|m| match *m.borrow() {
+        Some(ref memo) => memo.start <= address && address + i.len() <= memo.end,
+        None => false,
+    });
+    if !within {
+        return parser(i);
+    }
+
+    let known = MEMO.with(|m| {
+        let memo = m.borrow();
+        let result = memo.as_ref()?.results.get(&key)?;
+        result.downcast_ref::<Memoized<O>>().cloned()
+    });
+    match known {
+        Some(Ok((rest, o))) => return Ok((&i[i.len() - rest..], o)),
+        Some(Err((failure, offset, len, kind))) => {
+            let e = Error::new(&i[offset..offset + len], kind);
+            return Err(if failure {
+                nom::Err::Failure(e)
+            } else {
+                nom::Err::Error(e)
+            });
+        }
+        None => (),
+    }
+
+    let result = parser(i);
+    let remembered: Option<Memoized<O>> = match result {
+        Ok((rest, ref o)) => Some(Ok((rest.len(), o.clone()))),
+        Err(nom::Err::Error(ref e)) | Err(nom::Err::Failure(ref e)) => {
+            let failure = matches!(result, Err(nom::Err::Failure(_)));
+            // an error is normally reported within the input; one that is not is not remembered
+            let offset = (e.input.as_ptr() as usize).wrapping_sub(address);
+            if offset <= i.len() && e.input.len() <= i.len() - offset {
+                Some(Err((failure, offset, e.input.len(), e.code)))
+            } else {
+                None
+            }
+        }
+        Err(nom::Err::Incomplete(_)) => None,
+    };
+    if let Some(remembered) = remembered {
+        MEMO.with(|m| {
+            if let Some(ref mut memo) = *m.borrow_mut() {
+                memo.results.insert(key, Box::new(remembered));
+            }
+        });
+    }
+    result
+}
+
+/// Whether `err` was caused by exceeding the nesting limit.
+pub(crate) fn is_too_deep(err: &nom::Err<Error<&str>>) -> bool {
+    match *err {
+        nom::Err::Failure(ref e) => e.code == ErrorKind::TooLarge,
+        _ => false,
+    }
+}
+
+/// Wraps a parser for a construct that can contain itself, counting how deeply such constructs
+/// are nested. A level is only counted once `opening` recognizes the start of the construct, so
+/// that an operand at the limit can still be parsed as something else. Exceeding the limit is a
+/// failure rather than an error, so that alternatives are not retried at every level on the way
+/// out.
+///
+/// A construct that starts where the innermost one being counted does, such as a parenthesized
+/// condition tried again as a parenthesized arithmetic operand, is the same level of nesting.
+pub(crate) fn nested<'a, O, P, G, F>(
+    mut opening: G,
+    mut parser: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    G: FnMut(&'a str) -> IResult<&'a str, P>,
+    F: FnMut(&'a str) -> IResult<&'a str, O>,
+{
+    struct Level(usize);
+    impl Drop for Level {
+        fn drop(&mut self) {
+            DEPTH.with(|d| d.set(d.get() - 1));
+            LEVEL_START.with(|s| s.set(self.0));
+        }
+    }
+
+    move |i: &'a str| {
+        let start = i.as_ptr() as usize;
+        if LEVEL_START.with(|s| s.get()) == start {
+            return parser(i);
+        }
+        opening(i)?;
+        let depth = DEPTH.with(|d| d.get()) + 1;
+        if depth > MAX_DEPTH.with(|m| m.get()) {
+            return Err(nom::Err::Failure(Error::new(i, ErrorKind::TooLarge)));
+        }
+        DEPTH.with(|d| d.set(depth));
+        let _level = Level(LEVEL_START.with(|s| s.replace(start)));
+        parser(i)
+    }
+}
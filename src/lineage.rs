@@ -0,0 +1,571 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use arithmetic::{ArithmeticBase, ArithmeticExpression};
+use column::{Column, FunctionExpression};
+use common::{FieldDefinitionExpression, FieldValueExpression};
+use compound_select::CompoundSelectStatement;
+use condition::{ConditionBase, ConditionExpression};
+use create::SelectSpecification;
+use ident::Ident;
+use join::JoinRightSide;
+use keywords::escape_if_keyword;
+use parser::SqlQuery;
+use select::SelectStatement;
+use table::Table;
+use with::WithStatement;
+
+/// A column of a stored table. `table` is `None` when an unqualified column is used in a query
+/// over several tables, since telling which table it belongs to requires the schema.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct SourceColumn {
+    pub table: Option<String>,
+    pub column: String,
+}
+
+impl fmt::Display for SourceColumn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref table) = self.table {
+            write!(f, "{}.", escape_if_keyword(table))?;
+        }
+        if self.column == "*" {
+            write!(f, "*")
+        } else {
+            write!(f, "{}", escape_if_keyword(&self.column))
+        }
+    }
+}
+
+/// The stored columns one output column of a query is derived from. A `*` over a stored table
+/// cannot be expanded without the schema, so it is reported as an output named `*` derived from
+/// `table.*`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ColumnLineage {
+    pub name: String,
+    pub sources: BTreeSet<SourceColumn>,
+}
+
+/// The common table expressions a query can read from, by name, with their output columns.
+type Scope<'a> = &'a [(String, Vec<ColumnLineage>)];
+
+/// Something a column reference in a select can resolve against.
+enum Relation {
+    Table {
+        name: String,
+        alias: Option<String>,
+    },
+    Function {
+        name: String,
+        alias: Option<String>,
+        arguments: BTreeSet<SourceColumn>,
+    },
+    Derived {
+        alias: Option<String>,
+        columns: Vec<ColumnLineage>,
+    },
+}
+
+impl Relation {
+    fn from_table(table: &Table, ctes: Scope) -> Relation {
+        if let Some(ref subquery) = table.subquery {
+            return Relation::Derived {
+                alias: Some(table.name.to_string()),
+                columns: select_lineage_in(subquery, ctes),
+            };
+        }
+        if let Some(ref values) = table.values {
+            // rows given in the query derive from no stored column; unnamed columns are called
+            // `column1`, `column2`, ... as in Postgres
+            let width = values.values.rows.first().map_or(0, |row| row.len());
+            let names = (values.column_aliases.len()..width).map(|n| format!("column{}", n + 1));
+            return Relation::Derived {
+                alias: Some(table.name.to_string()),
+                columns: values
+                    .column_aliases
+                    .iter()
+                    .cloned()
+                    .chain(names)
+                    .map(|name| ColumnLineage {
+                        name,
+                        sources: BTreeSet::new(),
+                    })
+                    .collect(),
+            };
+        }
+        match table.function {
+            Some(ref function) => Relation::Function {
+                name: table.name.to_string(),
+                alias: table.alias.as_ref().map(Ident::to_string),
+                arguments: function
+                    .all_arguments()
+                    .filter_map(|arg| match *arg {
+                        ArithmeticBase::Column(ref col) => Some(stored_column(col)),
+                        _ => None,
+                    })
+                    .collect(),
+            },
+            None => {
+                let cte = ctes
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| table.schema.is_none() && *table.name == **name);
+                match cte {
+                    Some((_, columns)) => Relation::Derived {
+                        alias: Some(table.alias.as_ref().unwrap_or(&table.name).to_string()),
+                        columns: columns.clone(),
+                    },
+                    None => Relation::Table {
+                        name: table.name.to_string(),
+                        alias: table.alias.as_ref().map(Ident::to_string),
+                    },
+                }
+            }
+        }
+    }
+
+    fn matches(&self, qualifier: &str) -> bool {
+        match *self {
+            Relation::Table {
+                ref name,
+                ref alias,
+            }
+            | Relation::Function {
+                ref name,
+                ref alias,
+                ..
+            } => alias.as_ref().unwrap_or(name) == qualifier,
+            Relation::Derived { ref alias, .. } => alias.as_deref() == Some(qualifier),
+        }
+    }
+
+    /// The sources of column `name` of this relation, or `None` if it does not have it.
+    fn resolve(&self, name: &str) -> Option<BTreeSet<SourceColumn>> {
+        match *self {
+            Relation::Table {
+                name: ref table, ..
+            } => Some(
+                Some(SourceColumn {
+                    table: Some(table.clone()),
+                    column: String::from(name),
+                })
+                .into_iter()
+                .collect(),
+            ),
+            Relation::Function { ref arguments, .. } => Some(arguments.clone()),
+            Relation::Derived { ref columns, .. } => columns
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.sources.clone()),
+        }
+    }
+
+    /// The output columns of `*` over this relation.
+    fn expand_all(&self) -> Vec<ColumnLineage> {
+        match *self {
+            Relation::Table { ref name, .. } => vec![ColumnLineage {
+                name: String::from("*"),
+                sources: Some(SourceColumn {
+                    table: Some(name.clone()),
+                    column: String::from("*"),
+                })
+                .into_iter()
+                .collect(),
+            }],
+            Relation::Function { ref arguments, .. } => vec![ColumnLineage {
+                name: String::from("*"),
+                sources: arguments.clone(),
+            }],
+            Relation::Derived { ref columns, .. } => columns.clone(),
+        }
+    }
+}
+
+fn stored_column(col: &Column) -> SourceColumn {
+    SourceColumn {
+        table: col.table.as_ref().map(Ident::to_string),
+        column: col.name.to_string(),
+    }
+}
+
+fn relations(stmt: &SelectStatement, ctes: Scope) -> Vec<Relation> {
+    let mut relations: Vec<_> = stmt
+        .tables
+        .iter()
+        .map(|table| Relation::from_table(table, ctes))
+        .collect();
+    let mut pending: Vec<_> = stmt.join.iter().map(|j| &j.right).collect();
+    while let Some(right) = pending.pop() {
+        match *right {
+            JoinRightSide::Table(ref table) => relations.push(Relation::from_table(table, ctes)),
+            JoinRightSide::Tables(ref tables) => {
+                relations.extend(tables.iter().map(|table| Relation::from_table(table, ctes)))
+            }
+            JoinRightSide::NestedSelect(ref select, ref alias) => {
+                relations.push(Relation::Derived {
+                    alias: alias.clone(),
+                    columns: select_lineage_in(select, ctes),
+                })
+            }
+            JoinRightSide::NestedJoin(ref join) => pending.push(&join.right),
+        }
+    }
+    relations
+}
+
+fn resolve_column(relations: &[Relation], col: &Column) -> BTreeSet<SourceColumn> {
+    if let Some(ref function) = col.function {
+        if let FunctionExpression::Call(_, ref args) = **function {
+            return args
+                .iter()
+                .flat_map(|arg| condition_sources(relations, arg))
+                .collect();
+        }
+        return match function.aggregated_column() {
+            Some(arg) => resolve_column(relations, arg),
+            None => BTreeSet::new(),
+        };
+    }
+    if let Some(ref qualifier) = col.table {
+        return relations
+            .iter()
+            .find(|r| r.matches(qualifier))
+            .and_then(|r| r.resolve(&col.name))
+            .unwrap_or_else(|| Some(stored_column(col)).into_iter().collect());
+    }
+    if relations.len() == 1 {
+        if let Some(sources) = relations[0].resolve(&col.name) {
+            return sources;
+        }
+    }
+    // With several relations, only derived ones know which columns they have.
+    relations
+        .iter()
+        .filter(|r| matches!(**r, Relation::Derived { .. }))
+        .find_map(|r| r.resolve(&col.name))
+        .unwrap_or_else(|| Some(stored_column(col)).into_iter().collect())
+}
+
+fn arithmetic_sources(
+    relations: &[Relation],
+    expr: &ArithmeticExpression,
+) -> BTreeSet<SourceColumn> {
+    operand_sources(relations, expr.operands())
+}
+
+fn operand_sources(
+    relations: &[Relation],
+    operands: Vec<&ArithmeticBase>,
+) -> BTreeSet<SourceColumn> {
+    let mut sources = BTreeSet::new();
+    for base in operands {
+        if let ArithmeticBase::Column(ref col) = *base {
+            sources.extend(resolve_column(relations, col));
+        }
+    }
+    sources
+}
+
+/// The columns an expression reads. Columns of nested selects belong to those selects.
+fn condition_sources(relations: &[Relation], expr: &ConditionExpression) -> BTreeSet<SourceColumn> {
+    match *expr {
+        ConditionExpression::ComparisonOp(ref tree) | ConditionExpression::LogicalOp(ref tree) => {
+            let mut sources = condition_sources(relations, &tree.left);
+            sources.extend(condition_sources(relations, &tree.right));
+            sources
+        }
+        ConditionExpression::NegationOp(ref expr)
+        | ConditionExpression::Bracketed(ref expr)
+        | ConditionExpression::Base(ConditionBase::Any(ref expr))
+        | ConditionExpression::Base(ConditionBase::Escaped(ref expr, _)) => {
+            condition_sources(relations, expr)
+        }
+        ConditionExpression::Arithmetic(ref expr) => arithmetic_sources(relations, expr),
+        ConditionExpression::Base(ConditionBase::BitNot(ref operand)) => {
+            let mut operands = vec![];
+            operand.operands(&mut operands);
+            operand_sources(relations, operands)
+        }
+        ConditionExpression::Base(ConditionBase::Field(ref col)) => resolve_column(relations, col),
+        ConditionExpression::Base(ConditionBase::Json(ref json)) => {
+            resolve_column(relations, &json.column)
+        }
+        ConditionExpression::Base(ConditionBase::Subscript(ref sub)) => {
+            resolve_column(relations, &sub.column)
+        }
+        ConditionExpression::Base(ConditionBase::Row(ref exprs, _))
+        | ConditionExpression::Base(ConditionBase::ExpressionList(ref exprs)) => exprs
+            .iter()
+            .flat_map(|expr| condition_sources(relations, expr))
+            .collect(),
+        ConditionExpression::Base(_) => BTreeSet::new(),
+    }
+}
+
+fn value_sources(relations: &[Relation], value: &FieldValueExpression) -> BTreeSet<SourceColumn> {
+    match *value {
+        FieldValueExpression::Arithmetic(ref expr) => arithmetic_sources(relations, expr),
+        FieldValueExpression::Json(ref json) => resolve_column(relations, &json.column),
+        FieldValueExpression::Subscript(ref sub) => resolve_column(relations, &sub.column),
+        FieldValueExpression::Variable(ref var) => match var.value {
+            Some(ref value) => field_lineage(relations, value)
+                .into_iter()
+                .flat_map(|c| c.sources)
+                .collect(),
+            None => BTreeSet::new(),
+        },
+        FieldValueExpression::Expression(ref expr) => condition_sources(relations, expr),
+        FieldValueExpression::Predicate(ref pred) => condition_sources(relations, &pred.condition),
+        FieldValueExpression::Literal(_) => BTreeSet::new(),
+    }
+}
+
+fn field_lineage(relations: &[Relation], field: &FieldDefinitionExpression) -> Vec<ColumnLineage> {
+    match *field {
+        FieldDefinitionExpression::All => relations.iter().flat_map(Relation::expand_all).collect(),
+        FieldDefinitionExpression::AllInTable(ref table) => {
+            match relations.iter().find(|r| r.matches(table)) {
+                Some(relation) => relation.expand_all(),
+                None => Relation::Table {
+                    name: table.clone(),
+                    alias: None,
+                }
+                .expand_all(),
+            }
+        }
+        FieldDefinitionExpression::Col(ref col) => vec![ColumnLineage {
+            name: col.alias.as_ref().unwrap_or(&col.name).to_string(),
+            sources: resolve_column(relations, col),
+        }],
+        FieldDefinitionExpression::Value(ref value) => {
+            let alias = match *value {
+                FieldValueExpression::Arithmetic(ref expr) => expr.alias.clone(),
+                FieldValueExpression::Literal(ref lit) => lit.alias.clone(),
+                FieldValueExpression::Variable(ref var) => var.alias.clone(),
+                FieldValueExpression::Json(ref json) => json.alias.clone(),
+                FieldValueExpression::Subscript(ref sub) => sub.alias.clone(),
+                FieldValueExpression::Predicate(ref pred) => pred.alias.clone(),
+                FieldValueExpression::Expression(_) => None,
+            };
+            vec![ColumnLineage {
+                name: alias.unwrap_or_else(|| value.to_string()),
+                sources: value_sources(relations, value),
+            }]
+        }
+    }
+}
+
+/// Maps each output column of `stmt` to the stored columns it is derived from, looking through
+/// nested selects in joins. Columns only used for filtering or grouping are not included.
+pub fn select_lineage(stmt: &SelectStatement) -> Vec<ColumnLineage> {
+    select_lineage_in(stmt, &[])
+}
+
+fn select_lineage_in(stmt: &SelectStatement, ctes: Scope) -> Vec<ColumnLineage> {
+    let relations = relations(stmt, ctes);
+    stmt.fields
+        .iter()
+        .flat_map(|field| field_lineage(&relations, field))
+        .collect()
+}
+
+/// Like `select_lineage`, with each output column derived from the corresponding column of
+/// every select in the compound. Output names are taken from the first select.
+pub fn compound_select_lineage(stmt: &CompoundSelectStatement) -> Vec<ColumnLineage> {
+    compound_select_lineage_in(stmt, &[])
+}
+
+fn compound_select_lineage_in(stmt: &CompoundSelectStatement, ctes: Scope) -> Vec<ColumnLineage> {
+    let mut selects = stmt
+        .selects
+        .iter()
+        .map(|(_, select)| select_lineage_in(select, ctes));
+    let mut lineage = selects.next().unwrap_or_default();
+    for columns in selects {
+        for (output, column) in lineage.iter_mut().zip(columns) {
+            output.sources.extend(column.sources);
+        }
+    }
+    lineage
+}
+
+fn view_lineage(
+    definition: &SelectSpecification,
+    fields: &[Column],
+    ctes: Scope,
+) -> Vec<ColumnLineage> {
+    let mut lineage = match *definition {
+        SelectSpecification::Simple(ref select) => select_lineage_in(select, ctes),
+        SelectSpecification::Compound(ref compound) => compound_select_lineage_in(compound, ctes),
+    };
+    for (output, field) in lineage.iter_mut().zip(fields) {
+        output.name = field.name.to_string();
+    }
+    lineage
+}
+
+/// The lineage of the query of a `WITH` statement, with the columns of each common table
+/// expression it reads from derived from those the expression reads in turn.
+///
+/// A recursive expression reads its own rows, so its lineage is recomputed with the columns found
+/// so far in scope until no more sources are added, starting from those of its first select.
+fn with_lineage(with: &WithStatement) -> Vec<ColumnLineage> {
+    let mut ctes = Vec::new();
+    for cte in &with.ctes {
+        let mut lineage = match (with.recursive, &cte.query) {
+            (true, SelectSpecification::Compound(compound)) => {
+                let first = compound.selects.first().map(|(_, select)| select);
+                let mut lineage = first.map_or_else(Vec::new, |s| select_lineage_in(s, &ctes));
+                for (output, field) in lineage.iter_mut().zip(&cte.columns) {
+                    output.name = field.name.to_string();
+                }
+                lineage
+            }
+            _ => view_lineage(&cte.query, &cte.columns, &ctes),
+        };
+        if with.recursive {
+            loop {
+                ctes.push((cte.name.clone(), lineage.clone()));
+                let next = view_lineage(&cte.query, &cte.columns, &ctes);
+                ctes.pop();
+                if next == lineage {
+                    break;
+                }
+                lineage = next;
+            }
+        }
+        ctes.push((cte.name.clone(), lineage));
+    }
+    view_lineage(&with.query, &[], &ctes)
+}
+
+/// The column lineage of a query returning rows: a select, a compound select, a `WITH` query, or
+/// the definition of a view or materialized view. Returns `None` for other statements.
+///
+/// Columns read from a common table expression are traced to the stored columns the expression
+/// reads. Common table expressions are only in scope for the query of the `WITH` statement that
+/// defines them.
+pub fn query_lineage(query: &SqlQuery) -> Option<Vec<ColumnLineage>> {
+    match *query {
+        SqlQuery::Select(ref select) => Some(select_lineage(select)),
+        SqlQuery::CompoundSelect(ref compound) => Some(compound_select_lineage(compound)),
+        SqlQuery::CreateView(ref view) => Some(view_lineage(&view.definition, &view.fields, &[])),
+        SqlQuery::CreateMaterializedView(ref view) => {
+            Some(view_lineage(&view.definition, &view.fields, &[]))
+        }
+        SqlQuery::With(ref with) => Some(with_lineage(with)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+
+    fn lineage(q: &str) -> Vec<(String, Vec<String>)> {
+        query_lineage(&parse_query(q).unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.name, c.sources.iter().map(|s| s.to_string()).collect()))
+            .collect()
+    }
+
+    fn entry(name: &str, sources: &[&str]) -> (String, Vec<String>) {
+        (
+            String::from(name),
+            sources.iter().map(|s| String::from(*s)).collect(),
+        )
+    }
+
+    #[test]
+    fn simple_select_lineage() {
+        assert_eq!(
+            lineage("SELECT id, u.name AS n, age * 2 AS double_age, max(score) FROM users AS u"),
+            vec![
+                entry("id", &["users.id"]),
+                entry("n", &["users.name"]),
+                entry("double_age", &["users.age"]),
+                entry("max(score)", &["users.score"]),
+            ]
+        );
+        assert_eq!(
+            lineage("SELECT *, count(*) AS c FROM users"),
+            vec![entry("*", &["users.*"]), entry("c", &[])]
+        );
+    }
+
+    #[test]
+    fn lineage_through_joins_and_subqueries() {
+        let qstring = "SELECT o.id, total, a.name FROM orders AS o \
+                       JOIN (SELECT order_id, amount AS total FROM payments) AS p \
+                       ON o.id = p.order_id \
+                       JOIN accounts AS a ON o.account_id = a.id";
+        assert_eq!(
+            lineage(qstring),
+            vec![
+                entry("id", &["orders.id"]),
+                entry("total", &["payments.amount"]),
+                entry("name", &["accounts.name"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn table_constructor_lineage() {
+        let qstring = "SELECT v.*, u.name FROM (VALUES (1, 'a')) AS v(id) \
+                       JOIN users AS u ON u.id = v.id";
+        assert_eq!(
+            lineage(qstring),
+            vec![
+                entry("id", &[]),
+                entry("column2", &[]),
+                entry("name", &["users.name"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn compound_and_view_lineage() {
+        assert_eq!(
+            lineage("SELECT id FROM users UNION SELECT user_id FROM admins"),
+            vec![entry("id", &["admins.user_id", "users.id"])]
+        );
+        assert_eq!(
+            lineage("CREATE VIEW v AS SELECT id AS uid FROM users"),
+            vec![entry("uid", &["users.id"])]
+        );
+        assert_eq!(
+            lineage("CREATE VIEW v (uid, n) AS SELECT id, name FROM users"),
+            vec![entry("uid", &["users.id"]), entry("n", &["users.name"])]
+        );
+        assert_eq!(
+            lineage("CREATE MATERIALIZED VIEW m (uid) AS SELECT id FROM users"),
+            vec![entry("uid", &["users.id"])]
+        );
+    }
+
+    #[test]
+    fn common_table_expression_lineage() {
+        let qstring = "WITH paid AS (SELECT order_id, amount FROM payments), \
+                       big (oid) AS (SELECT order_id FROM paid WHERE amount > 100) \
+                       SELECT b.oid, p.amount, o.* FROM big AS b \
+                       JOIN paid AS p ON p.order_id = b.oid JOIN orders AS o ON o.id = b.oid";
+        assert_eq!(
+            lineage(qstring),
+            vec![
+                entry("oid", &["payments.order_id"]),
+                entry("amount", &["payments.amount"]),
+                entry("*", &["orders.*"]),
+            ]
+        );
+        assert_eq!(
+            lineage("WITH t AS (SELECT id, name FROM users) SELECT * FROM t"),
+            vec![entry("id", &["users.id"]), entry("name", &["users.name"])]
+        );
+        let qstring = "WITH RECURSIVE tree (id, root) AS (\
+                       SELECT id, id FROM nodes WHERE parent IS NULL \
+                       UNION ALL SELECT n.id, t.root FROM nodes AS n JOIN tree AS t ON n.parent = t.id) \
+                       SELECT root FROM tree";
+        assert_eq!(lineage(qstring), vec![entry("root", &["nodes.id"])]);
+    }
+}
@@ -0,0 +1,181 @@
+use nom::branch::alt;
+use nom::bytes::complete::take_while1;
+use nom::character::complete::multispace1;
+use nom::combinator::{map, not, opt, value};
+use nom::multi::separated_list1;
+use nom::sequence::{pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{is_sql_identifier, statement_terminator, table_as_alias, table_name, ws_sep_comma};
+use ident::Ident;
+use keywords::tag_no_case;
+use table::Table;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum LockType {
+    Read,
+    ReadLocal,
+    Write,
+    LowPriorityWrite,
+}
+
+impl fmt::Display for LockType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LockType::Read => write!(f, "READ"),
+            LockType::ReadLocal => write!(f, "READ LOCAL"),
+            LockType::Write => write!(f, "WRITE"),
+            LockType::LowPriorityWrite => write!(f, "LOW_PRIORITY WRITE"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct TableLock {
+    pub table: Table,
+    pub lock_type: LockType,
+}
+
+impl fmt::Display for TableLock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.table, self.lock_type)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct LockTablesStatement {
+    pub tables: Vec<TableLock>,
+}
+
+impl fmt::Display for LockTablesStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LOCK TABLES ")?;
+        let tables = self
+            .tables
+            .iter()
+            .map(|t| format!("{}", t))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{}", tables)
+    }
+}
+
+fn lock_type(i: &str) -> IResult<&str, LockType> {
+    terminated(
+        alt((
+            value(
+                LockType::ReadLocal,
+                tuple((tag_no_case("read"), multispace1, tag_no_case("local"))),
+            ),
+            value(LockType::Read, tag_no_case("read")),
+            value(
+                LockType::LowPriorityWrite,
+                tuple((
+                    tag_no_case("low_priority"),
+                    multispace1,
+                    tag_no_case("write"),
+                )),
+            ),
+            value(LockType::Write, tag_no_case("write")),
+        )),
+        not(take_while1(is_sql_identifier)),
+    )(i)
+}
+
+fn table_lock(i: &str) -> IResult<&str, TableLock> {
+    map(
+        tuple((
+            table_name,
+            // the lock type would otherwise be taken for an alias
+            opt(preceded(not(pair(multispace1, lock_type)), table_as_alias)),
+            multispace1,
+            lock_type,
+        )),
+        |(table, alias, _, lock_type)| TableLock {
+            table: Table {
+                alias: alias.as_ref().map(|a| Ident::from(&*a.0)),
+                alias_quote: alias.and_then(|a| a.1),
+                ..table
+            },
+            lock_type,
+        },
+    )(i)
+}
+
+fn tables_keyword(i: &str) -> IResult<&str, &str> {
+    alt((tag_no_case("tables"), tag_no_case("table")))(i)
+}
+
+pub fn lock_tables(i: &str) -> IResult<&str, LockTablesStatement> {
+    let (remaining_input, (_, _, _, _, tables, _)) = tuple((
+        tag_no_case("lock"),
+        multispace1,
+        tables_keyword,
+        multispace1,
+        separated_list1(ws_sep_comma, table_lock),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((remaining_input, LockTablesStatement { tables }))
+}
+
+pub fn unlock_tables(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((
+            tag_no_case("unlock"),
+            multispace1,
+            tables_keyword,
+            statement_terminator,
+        )),
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_lock_tables() {
+        let qstring = "LOCK TABLES t READ, u WRITE;";
+        let res = lock_tables(qstring);
+        assert_eq!(
+            res.unwrap().1,
+            LockTablesStatement {
+                tables: vec![
+                    TableLock {
+                        table: Table::from("t"),
+                        lock_type: LockType::Read,
+                    },
+                    TableLock {
+                        table: Table::from("u"),
+                        lock_type: LockType::Write,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn lock_tables_with_aliases() {
+        let qstring =
+            "lock tables `users` AS u read local, posts p low_priority write, readers write";
+        let res = lock_tables(qstring).unwrap().1;
+        assert_eq!(res.tables[0].table.alias, Some("u".into()));
+        assert_eq!(res.tables[0].lock_type, LockType::ReadLocal);
+        assert_eq!(res.tables[1].table.alias, Some("p".into()));
+        assert_eq!(res.tables[1].lock_type, LockType::LowPriorityWrite);
+        assert_eq!(res.tables[2].table, Table::from("readers"));
+        assert_eq!(
+            format!("{}", res),
+            "LOCK TABLES `users` AS u READ LOCAL, posts AS p LOW_PRIORITY WRITE, readers WRITE"
+        );
+    }
+
+    #[test]
+    fn unlock() {
+        assert!(unlock_tables("UNLOCK TABLES;").is_ok());
+        assert!(unlock_tables("UNLOCK TABLESPACE").is_err());
+    }
+}
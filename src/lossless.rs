@@ -0,0 +1,810 @@
+use std::fmt;
+use std::ops::Range;
+
+use nom::combinator::eof;
+use nom::sequence::{pair, terminated};
+use nom::IResult;
+
+use common::{
+    assignment_expr_list, field_definition_expr, table_name, table_reference, whitespace0, Literal,
+};
+use condition::{condition_expr, ConditionExpression};
+use insert::{data, row};
+use join::{JoinConstraint, JoinRightSide};
+use lexer::{tokenize, Token};
+use parser::{sql_query, ParseOptions, SqlQuery};
+use select::{from_list, join_constraint, join_rhs, JoinClause};
+use table::Table;
+
+/// A parsed query that remembers the exact text it was parsed from.
+///
+/// As long as the AST is left untouched, `Display` reproduces the input byte for byte, including
+/// identifier quoting, keyword case, whitespace and comments. The text of the fields, tables,
+/// `WHERE` condition, `SET` assignments and inserted rows of a statement is also kept node by
+/// node, as is that of the table or subquery each join adds and of its `ON` or `USING` constraint,
+/// so that after a rewrite through `query_mut` only the nodes that changed are rendered from the
+/// AST and the rest of the statement stays as written. When anything else changes, such
+/// as a clause being added or a field removed, the whole statement is rendered from the AST. The
+/// comments and whitespace surrounding the statement are kept as written either way.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct LosslessQuery {
+    leading: String,
+    text: String,
+    trailing: String,
+    original: SqlQuery,
+    query: SqlQuery,
+    #[serde(default)]
+    spans: Vec<NodeSpan>,
+}
+
+/// A node of a statement whose text can be replaced on its own.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+enum Node {
+    Field(usize),
+    /// A table of a select, or the table an update, delete or insert works on.
+    Table(usize),
+    Where,
+    Assignment(usize),
+    Row(usize),
+    /// What a join of a select adds to it, after `JOIN` and any `LATERAL`.
+    JoinRight(usize),
+    /// The `ON` or `USING` constraint of a join of a select.
+    JoinConstraint(usize),
+}
+
+/// Where a node is in the statement text, and how it printed when parsed.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+struct NodeSpan {
+    node: Node,
+    start: usize,
+    end: usize,
+    printed: String,
+}
+
+impl LosslessQuery {
+    /// The parsed statement.
+    pub fn query(&self) -> &SqlQuery {
+        &self.query
+    }
+
+    /// Mutable access to the parsed statement, for rewriting.
+    pub fn query_mut(&mut self) -> &mut SqlQuery {
+        &mut self.query
+    }
+
+    /// Consumes this wrapper, returning the parsed statement.
+    pub fn into_query(self) -> SqlQuery {
+        self.query
+    }
+
+    /// Whitespace and comments that preceded the statement in the input.
+    pub fn leading(&self) -> &str {
+        &self.leading
+    }
+
+    /// The statement text exactly as it appeared in the input.
+    pub fn original_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Terminator, whitespace and comments that followed the statement in the input.
+    pub fn trailing(&self) -> &str {
+        &self.trailing
+    }
+
+    /// Returns true if the statement differs from the one originally parsed.
+    pub fn is_modified(&self) -> bool {
+        self.query != self.original
+    }
+
+    /// The statement text with the nodes that changed rendered from the AST, or `None` if
+    /// something outside the nodes whose text is known changed.
+    fn spliced(&self) -> Option<String> {
+        if self.spans.is_empty() {
+            return None;
+        }
+        let mut query = self.query.clone();
+        let mut original = self.original.clone();
+        for span in &self.spans {
+            blank(&mut query, span.node);
+            blank(&mut original, span.node);
+        }
+        if query != original {
+            return None;
+        }
+
+        let mut text = String::new();
+        let mut pos = 0;
+        for span in &self.spans {
+            text.push_str(&self.text[pos..span.start]);
+            let printed = print(&self.query, span.node)?;
+            if printed == span.printed {
+                text.push_str(&self.text[span.start..span.end]);
+            } else {
+                text.push_str(&printed);
+            }
+            pos = span.end;
+        }
+        text.push_str(&self.text[pos..]);
+        Some(text)
+    }
+}
+
+impl fmt::Display for LosslessQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.leading)?;
+        if !self.is_modified() {
+            write!(f, "{}", self.text)?;
+        } else if let Some(text) = self.spliced() {
+            write!(f, "{}", text)?;
+        } else {
+            write!(f, "{}", self.query)?;
+        }
+        write!(f, "{}", self.trailing)
+    }
+}
+
+/// Prints `node` of `query` as the statement prints it, or returns `None` if there is no such
+/// node.
+fn print(query: &SqlQuery, node: Node) -> Option<String> {
+    match (query, node) {
+        (SqlQuery::Select(select), Node::Field(i)) => select.fields.get(i).map(|f| f.to_string()),
+        (SqlQuery::Select(select), Node::Table(i)) => select.tables.get(i).map(|t| t.to_string()),
+        (SqlQuery::Update(update), Node::Table(0)) => Some(update.table.to_string()),
+        (SqlQuery::Delete(delete), Node::Table(0)) => Some(delete.table.to_string()),
+        (SqlQuery::Insert(insert), Node::Table(0)) => Some(insert.table.to_string()),
+        (SqlQuery::Select(select), Node::Where) => {
+            select.where_clause.as_ref().map(|c| c.to_string())
+        }
+        (SqlQuery::Update(update), Node::Where) => {
+            update.where_clause.as_ref().map(|c| c.to_string())
+        }
+        (SqlQuery::Delete(delete), Node::Where) => {
+            delete.where_clause.as_ref().map(|c| c.to_string())
+        }
+        (SqlQuery::Update(update), Node::Assignment(i)) => update
+            .fields
+            .get(i)
+            .map(|(column, value)| format!("{} = {}", column, value)),
+        (SqlQuery::Insert(insert), Node::Row(i)) => insert
+            .data
+            .get(i)
+            .map(|values| row(values, insert.row_constructors)),
+        (SqlQuery::Select(select), Node::JoinRight(i)) => {
+            select.join.get(i).map(|j| j.right.to_string())
+        }
+        (SqlQuery::Select(select), Node::JoinConstraint(i)) => {
+            select.join.get(i).map(|j| j.constraint.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Replaces `node` of `query` with a placeholder, so that the rest of the statement can be
+/// compared on its own.
+fn blank(query: &mut SqlQuery, node: Node) {
+    match (query, node) {
+        (SqlQuery::Select(select), Node::Field(i)) => {
+            if let Some(field) = select.fields.get_mut(i) {
+                *field = Default::default();
+            }
+        }
+        (SqlQuery::Select(select), Node::Table(i)) => {
+            if let Some(table) = select.tables.get_mut(i) {
+                *table = Default::default();
+            }
+        }
+        (SqlQuery::Update(update), Node::Table(_)) => update.table = Default::default(),
+        (SqlQuery::Delete(delete), Node::Table(_)) => delete.table = Default::default(),
+        (SqlQuery::Insert(insert), Node::Table(_)) => insert.table = Default::default(),
+        (SqlQuery::Select(select), Node::Where) => select.where_clause = None,
+        (SqlQuery::Update(update), Node::Where) => update.where_clause = None,
+        (SqlQuery::Delete(delete), Node::Where) => delete.where_clause = None,
+        (SqlQuery::Update(update), Node::Assignment(i)) => {
+            if let Some(assignment) = update.fields.get_mut(i) {
+                assignment.0 = Default::default();
+                assignment.1 = Literal::Null.into();
+            }
+        }
+        (SqlQuery::Insert(insert), Node::Row(i)) => {
+            if let Some(values) = insert.data.get_mut(i) {
+                values.clear();
+            }
+        }
+        (SqlQuery::Select(select), Node::JoinRight(i)) => {
+            if let Some(join) = select.join.get_mut(i) {
+                join.right = JoinRightSide::Table(Table::default());
+            }
+        }
+        (SqlQuery::Select(select), Node::JoinConstraint(i)) => {
+            if let Some(join) = select.join.get_mut(i) {
+                join.constraint = JoinConstraint::Empty;
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Words that end a list of fields or tables, or a `WHERE` condition.
+const CLAUSE_WORDS: &[&str] = &[
+    "except",
+    "fetch",
+    "for",
+    "from",
+    "group",
+    "having",
+    "intersect",
+    "into",
+    "limit",
+    "lock",
+    "offset",
+    "on",
+    "order",
+    "returning",
+    "union",
+    "where",
+    "window",
+];
+
+/// Words that start a join, ending the list of tables before it.
+const JOIN_WORDS: &[&str] = &[
+    "cross",
+    "full",
+    "inner",
+    "join",
+    "left",
+    "natural",
+    "right",
+    "straight_join",
+];
+
+fn is_word(token: &Token, words: &[&str]) -> bool {
+    words.iter().any(|w| token.text.eq_ignore_ascii_case(w))
+}
+
+/// The tokens of `text` other than whitespace and comments, leaving out those within parentheses
+/// or brackets, whose delimiters are kept.
+fn top_level(text: &str) -> Vec<Token<'_>> {
+    let mut depth = 0usize;
+    let mut tokens = vec![];
+    for token in tokenize(text).map_while(Result::ok) {
+        if token.is_trivia() {
+            continue;
+        }
+        match token.text {
+            "(" | "[" => {
+                if depth == 0 {
+                    tokens.push(token);
+                }
+                depth += 1;
+            }
+            ")" | "]" => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    tokens.push(token);
+                }
+            }
+            _ if depth == 0 => tokens.push(token),
+            _ => (),
+        }
+    }
+    tokens
+}
+
+/// The tokens following the first one that is `word`, up to one of `stops` or the end.
+fn clause<'t, 'a>(tokens: &'t [Token<'a>], word: &str, stops: &[&str]) -> &'t [Token<'a>] {
+    let start = match tokens.iter().position(|t| is_word(t, &[word])) {
+        Some(i) => i + 1,
+        None => return &[],
+    };
+    let len = tokens[start..]
+        .iter()
+        .position(|t| is_word(t, stops))
+        .unwrap_or(tokens.len() - start);
+    &tokens[start..start + len]
+}
+
+/// The byte ranges of the comma-separated items of a clause, from the first to the last token of
+/// each.
+fn items(tokens: &[Token]) -> Vec<Range<usize>> {
+    tokens
+        .split(|t| t.text == ",")
+        .filter_map(|item| Some(item.first()?.offset..item.last()?.span().end))
+        .collect()
+}
+
+/// Records the spans of `nodes`, given their ranges in `text`, if each range parses back to the
+/// node it is taken for. Otherwise the text of none of them is known.
+fn located<'a, T, P>(
+    spans: &mut Vec<NodeSpan>,
+    text: &'a str,
+    ranges: Vec<Range<usize>>,
+    nodes: &[T],
+    kind: fn(usize) -> Node,
+    parser: P,
+    print: impl Fn(&T) -> String,
+) where
+    T: PartialEq,
+    P: Fn(&'a str) -> IResult<&'a str, T>,
+{
+    if ranges.len() != nodes.len() {
+        return;
+    }
+    let parses_back = ranges
+        .iter()
+        .zip(nodes)
+        .all(|(range, node)| matches!(parser(&text[range.clone()]), Ok(("", ref n)) if n == node));
+    if !parses_back {
+        return;
+    }
+    for (i, (range, node)) in ranges.into_iter().zip(nodes).enumerate() {
+        spans.push(NodeSpan {
+            node: kind(i),
+            start: range.start,
+            end: range.end,
+            printed: print(node),
+        });
+    }
+}
+
+/// Only the item a list parser finds, if it finds exactly one.
+fn single<'a, T>(
+    parser: impl Fn(&'a str) -> IResult<&'a str, Vec<T>>,
+) -> impl Fn(&'a str) -> IResult<&'a str, T> {
+    move |i| {
+        let (rest, mut items) = parser(i)?;
+        match items.pop() {
+            Some(item) if items.is_empty() => Ok((rest, item)),
+            _ => Err(nom::Err::Error(nom::error::Error::new(
+                i,
+                nom::error::ErrorKind::Verify,
+            ))),
+        }
+    }
+}
+
+fn where_span(
+    spans: &mut Vec<NodeSpan>,
+    text: &str,
+    tokens: &[Token],
+    cond: &Option<ConditionExpression>,
+) {
+    if let Some(ref cond) = *cond {
+        // a condition has no commas outside parentheses
+        let ranges = items(clause(tokens, "where", CLAUSE_WORDS));
+        located(
+            spans,
+            text,
+            ranges,
+            std::slice::from_ref(cond),
+            |_| Node::Where,
+            condition_expr,
+            |c| c.to_string(),
+        );
+    }
+}
+
+/// The byte range from the first to the last of `tokens`.
+fn range(tokens: &[Token]) -> Option<Range<usize>> {
+    Some(tokens.first()?.offset..tokens.last()?.span().end)
+}
+
+/// Records the spans of the right side and constraint of each of `joins`, given the top-level
+/// tokens from the first join on, if each span parses back to the node it is taken for.
+fn join_spans(spans: &mut Vec<NodeSpan>, text: &str, tokens: &[Token], joins: &[JoinClause]) {
+    let mut found = vec![];
+    let mut rest = tokens;
+    while !rest.is_empty() {
+        // a join starts with a run of join words ending in `JOIN` or `STRAIGHT_JOIN`
+        if !is_word(&rest[0], JOIN_WORDS) {
+            return;
+        }
+        let operator = match rest
+            .iter()
+            .position(|t| is_word(t, &["join", "straight_join"]))
+        {
+            Some(i) => i + 1,
+            None => return,
+        };
+        let mut right = &rest[operator..];
+        if right.first().is_some_and(|t| is_word(t, &["lateral"])) {
+            right = &right[1..];
+        }
+        let end = right
+            .iter()
+            .position(|t| is_word(t, JOIN_WORDS))
+            .unwrap_or(right.len());
+        let (right, next) = right.split_at(end);
+        let (right, constraint) = match right.iter().position(|t| is_word(t, &["on", "using"])) {
+            Some(i) => (&right[..i], range(&right[i..])),
+            None => (right, None),
+        };
+        match range(right) {
+            Some(right) => found.push((right, constraint)),
+            None => return,
+        }
+        rest = next;
+    }
+    if found.len() != joins.len() {
+        return;
+    }
+    let parses_back = found.iter().zip(joins).all(|((right, constraint), join)| {
+        matches!(join_rhs(&text[right.clone()]), Ok(("", ref r)) if *r == join.right)
+            && match (constraint, &join.constraint) {
+                (None, JoinConstraint::Empty) => true,
+                (Some(range), c) => {
+                    matches!(join_constraint(&text[range.clone()]), Ok(("", ref p)) if p == c)
+                }
+                (None, _) => false,
+            }
+    });
+    if !parses_back {
+        return;
+    }
+    for (i, ((right, constraint), join)) in found.into_iter().zip(joins).enumerate() {
+        spans.push(NodeSpan {
+            node: Node::JoinRight(i),
+            start: right.start,
+            end: right.end,
+            printed: join.right.to_string(),
+        });
+        // a join without a constraint has no text to replace, so adding one changes more than
+        // a node
+        if let Some(constraint) = constraint {
+            spans.push(NodeSpan {
+                node: Node::JoinConstraint(i),
+                start: constraint.start,
+                end: constraint.end,
+                printed: join.constraint.to_string(),
+            });
+        }
+    }
+}
+
+/// Finds the nodes of `query` in `text`, the statement it was parsed from.
+fn node_spans(text: &str, query: &SqlQuery) -> Vec<NodeSpan> {
+    let tokens = top_level(text);
+    let mut spans = vec![];
+    match *query {
+        SqlQuery::Select(ref select) => {
+            if select.top.is_none() && select.modifiers.is_empty() {
+                let mut fields = clause(&tokens, "select", CLAUSE_WORDS);
+                if select.distinct && fields.first().is_some_and(|t| is_word(t, &["distinct"])) {
+                    fields = &fields[1..];
+                }
+                located(
+                    &mut spans,
+                    text,
+                    items(fields),
+                    &select.fields,
+                    Node::Field,
+                    single(field_definition_expr),
+                    |f| f.to_string(),
+                );
+            }
+            let stops = [CLAUSE_WORDS, JOIN_WORDS].concat();
+            let tables = items(clause(&tokens, "from", &stops));
+            located(
+                &mut spans,
+                text,
+                tables,
+                &select.tables,
+                Node::Table,
+                single(from_list),
+                |t| t.to_string(),
+            );
+            if !select.join.is_empty() {
+                // the joins run from the first join word to the end of the FROM clause
+                let stops: Vec<_> = CLAUSE_WORDS.iter().filter(|&&w| w != "on").collect();
+                let from = clause(&tokens, "from", &[]);
+                let end = from
+                    .iter()
+                    .position(|t| stops.iter().any(|w| t.text.eq_ignore_ascii_case(w)))
+                    .unwrap_or(from.len());
+                let from = &from[..end];
+                if let Some(start) = from.iter().position(|t| is_word(t, JOIN_WORDS)) {
+                    join_spans(&mut spans, text, &from[start..], &select.join);
+                }
+            }
+            where_span(&mut spans, text, &tokens, &select.where_clause);
+        }
+        SqlQuery::Update(ref update) => {
+            let table = items(clause(&tokens, "update", &["set"]));
+            located(
+                &mut spans,
+                text,
+                table,
+                std::slice::from_ref(&update.table),
+                Node::Table,
+                table_reference,
+                |t| t.to_string(),
+            );
+            let assignments = items(clause(&tokens, "set", CLAUSE_WORDS));
+            located(
+                &mut spans,
+                text,
+                assignments,
+                &update.fields,
+                Node::Assignment,
+                single(assignment_expr_list),
+                |(c, v)| format!("{} = {}", c, v),
+            );
+            where_span(&mut spans, text, &tokens, &update.where_clause);
+        }
+        SqlQuery::Delete(ref delete) => {
+            let table = items(clause(
+                &tokens,
+                "from",
+                &["where", "order", "limit", "using", "returning"],
+            ));
+            located(
+                &mut spans,
+                text,
+                table,
+                std::slice::from_ref(&delete.table),
+                Node::Table,
+                table_reference,
+                |t| t.to_string(),
+            );
+            where_span(&mut spans, text, &tokens, &delete.where_clause);
+        }
+        SqlQuery::Insert(ref insert) => {
+            let table = clause(&tokens, "into", &["partition", "values", "value", "as"]);
+            // the column list follows the table
+            let table = table.split(|t| t.text == "(").next().unwrap_or(&[]);
+            located(
+                &mut spans,
+                text,
+                items(table),
+                std::slice::from_ref(&insert.table),
+                Node::Table,
+                table_name,
+                |t| t.to_string(),
+            );
+            let rows = items(clause(&tokens, "values", &["on", "as", "returning"]));
+            located(
+                &mut spans,
+                text,
+                rows,
+                &insert.data,
+                Node::Row,
+                data,
+                |values| row(values, insert.row_constructors),
+            );
+        }
+        _ => (),
+    }
+    spans.sort_by_key(|span| span.start);
+    spans
+}
+
+/// Returns the length of the whitespace and comments (`-- ...`, `# ...` and `/* ... */`) at the
+/// start of `input`.
+fn skip_comments(input: &str) -> usize {
+    let mut pos = 0;
+    loop {
+        let rest = &input[pos..];
+        let trimmed = rest.trim_start();
+        pos += rest.len() - trimmed.len();
+        if trimmed.starts_with("--") || trimmed.starts_with('#') {
+            pos += trimmed.find('\n').map(|i| i + 1).unwrap_or(trimmed.len());
+        } else if let Some(comment) = trimmed.strip_prefix("/*") {
+            pos += comment.find("*/").map(|i| i + 4).unwrap_or(trimmed.len());
+        } else {
+            return pos;
+        }
+    }
+}
+
+/// Returns the length of `statement` up to the end of its last token, leaving out the
+/// terminator and any whitespace and comments after it.
+fn statement_len(statement: &str) -> usize {
+    tokenize(statement)
+        .map_while(Result::ok)
+        .filter(|token| !token.is_trivia() && token.text != ";")
+        .last()
+        .map_or(0, |token| token.span().end)
+}
+
+/// Parses a single statement, retaining its original text so that it can be reproduced exactly.
+pub fn parse_query_lossless<T>(input: T) -> Result<LosslessQuery, &'static str>
+where
+    T: AsRef<str>,
+{
+    parse_query_lossless_with_options(input, &ParseOptions::default())
+}
+
+/// Like `parse_query_lossless`, with the dialect and limits given by `options`.
+pub fn parse_query_lossless_with_options<T>(
+    input: T,
+    options: &ParseOptions,
+) -> Result<LosslessQuery, &'static str>
+where
+    T: AsRef<str>,
+{
+    let input = input.as_ref();
+    if input.len() > options.limits.max_length {
+        return Err("query too long");
+    }
+    options.apply(|| lossless_query(input))
+}
+
+fn lossless_query(input: &str) -> Result<LosslessQuery, &'static str> {
+    let start = skip_comments(input);
+    if terminated(sql_query, pair(whitespace0, eof))(&input[start..]).is_err() {
+        return Err("failed to parse query");
+    }
+
+    // the terminator and any comments after the statement belong to the trailing text, so that a
+    // rewritten statement is still terminated and annotated as before
+    let end = start + statement_len(&input[start..]);
+    let text = &input[start..end];
+
+    // reparse only the statement text itself, so that the AST cannot depend on trailing input
+    let query = match sql_query(text) {
+        Ok((_, q)) => q,
+        Err(_) => return Err("failed to parse query"),
+    };
+
+    Ok(LosslessQuery {
+        leading: String::from(&input[..start]),
+        text: String::from(text),
+        trailing: String::from(&input[end..]),
+        spans: node_spans(text, &query),
+        original: query.clone(),
+        query,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use table::Table;
+
+    #[test]
+    fn unchanged_query_is_reproduced_exactly() {
+        let qstrings = [
+            "select `id`, Name FROM users u where u.id = 1",
+            "  /* app=web */ SELECT * from `Users`   ;  -- trailing\n",
+            "-- leading\n# another\ninsert into users (name) values ('x');",
+            "UPDATE users SET name = 'a'",
+        ];
+        for q in qstrings.iter() {
+            let res = parse_query_lossless(q).unwrap();
+            assert!(!res.is_modified());
+            assert_eq!(format!("{}", res), *q);
+        }
+    }
+
+    #[test]
+    fn comments_are_split_off() {
+        let res = parse_query_lossless("/* hint */ select id from t; -- done").unwrap();
+        assert_eq!(res.leading(), "/* hint */ ");
+        assert_eq!(res.original_text(), "select id from t");
+        assert_eq!(res.trailing(), "; -- done");
+    }
+
+    #[test]
+    fn comments_before_terminator_are_trailing() {
+        let res = parse_query_lossless("select id from t /* why */ ; -- done").unwrap();
+        assert_eq!(res.original_text(), "select id from t");
+        assert_eq!(res.trailing(), " /* why */ ; -- done");
+        let res = parse_query_lossless("select id from t -- done").unwrap();
+        assert_eq!(res.original_text(), "select id from t");
+        assert!(parse_query_lossless("select id from t; select 1 from u").is_err());
+    }
+
+    #[test]
+    fn modified_query_keeps_surroundings() {
+        let mut res = parse_query_lossless("/* hint */ select id from t;\n").unwrap();
+        if let SqlQuery::Select(ref mut select) = *res.query_mut() {
+            select.tables.push(Table::from("u"));
+        }
+        assert!(res.is_modified());
+        // a table was added, so the statement is rendered anew
+        assert_eq!(format!("{}", res), "/* hint */ SELECT id FROM t, u;\n");
+    }
+
+    #[test]
+    fn only_modified_nodes_are_rendered() {
+        let q = "select `id`, Name FROM users u /* big */ where u.id = 1 order by id";
+        let mut res = parse_query_lossless(q).unwrap();
+        if let SqlQuery::Select(ref mut select) = *res.query_mut() {
+            select.tables[0].name = "t1_users".into();
+        }
+        assert_eq!(
+            res.to_string(),
+            "select `id`, Name FROM t1_users AS u /* big */ where u.id = 1 order by id"
+        );
+        if let SqlQuery::Select(ref mut select) = *res.query_mut() {
+            select.tables[0].name = "users".into();
+            select.where_clause = condition_expr("u.id = 2").ok().map(|(_, c)| c);
+        }
+        assert_eq!(
+            res.to_string(),
+            "select `id`, Name FROM users u /* big */ where u.id = 2 order by id"
+        );
+        // dropping the WHERE clause changes more than a node
+        if let SqlQuery::Select(ref mut select) = *res.query_mut() {
+            select.where_clause = None;
+        }
+        assert_eq!(
+            res.to_string(),
+            "SELECT id, Name FROM users AS u ORDER BY id ASC"
+        );
+
+        let mut res =
+            parse_query_lossless("UPDATE `users` SET name = 'a',  age=3 WHERE  id  = 1").unwrap();
+        if let SqlQuery::Update(ref mut update) = *res.query_mut() {
+            update.fields[1].1 = Literal::Integer(4).into();
+        }
+        assert_eq!(
+            res.to_string(),
+            "UPDATE `users` SET name = 'a',  age = 4 WHERE  id  = 1"
+        );
+
+        let mut res =
+            parse_query_lossless("insert into t (a, b) values (1,'x'),  (2,'y')").unwrap();
+        if let SqlQuery::Insert(ref mut insert) = *res.query_mut() {
+            insert.data[0][0] = Literal::Integer(5).into();
+        }
+        assert_eq!(
+            res.to_string(),
+            "insert into t (a, b) values (5, 'x'),  (2,'y')"
+        );
+
+        let mut res = parse_query_lossless("delete from Users where ID=1 limit 3").unwrap();
+        if let SqlQuery::Delete(ref mut delete) = *res.query_mut() {
+            delete.table.schema = Some("app".into());
+        }
+        assert_eq!(res.to_string(), "delete from app.Users where ID=1 limit 3");
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(parse_query_lossless("/* only a comment */").is_err());
+    }
+
+    #[test]
+    fn dialect_options() {
+        use dialect::Dialect;
+
+        let q = "create table hosts (id UUID, addr inet) -- inventory";
+        assert!(parse_query_lossless(q).is_err());
+        let res = parse_query_lossless_with_options(q, &ParseOptions::dialect(Dialect::Postgres))
+            .unwrap();
+        assert_eq!(res.to_string(), q);
+    }
+
+    #[test]
+    fn only_modified_joins_are_rendered() {
+        let q = "select o.id from `users` u /* owner */ LEFT JOIN Orders o on u.id=o.uid \
+                 join (select uid from carts) c using (uid) where u.id = 1";
+        let mut res = parse_query_lossless(q).unwrap();
+        if let SqlQuery::Select(ref mut select) = *res.query_mut() {
+            if let JoinRightSide::Table(ref mut table) = select.join[0].right {
+                table.name = "t1_orders".into();
+            }
+        }
+        assert_eq!(
+            res.to_string(),
+            "select o.id from `users` u /* owner */ LEFT JOIN t1_orders AS o on u.id=o.uid \
+             join (select uid from carts) c using (uid) where u.id = 1"
+        );
+        if let SqlQuery::Select(ref mut select) = *res.query_mut() {
+            select.join[1].constraint =
+                JoinConstraint::On(condition_expr("c.uid = u.id").unwrap().1);
+        }
+        assert_eq!(
+            res.to_string(),
+            "select o.id from `users` u /* owner */ LEFT JOIN t1_orders AS o on u.id=o.uid \
+             join (select uid from carts) c ON c.uid = u.id where u.id = 1"
+        );
+        // a constraint added to a join without one changes more than a node
+        let mut res = parse_query_lossless("select * from t cross join u").unwrap();
+        if let SqlQuery::Select(ref mut select) = *res.query_mut() {
+            select.join[0].constraint = JoinConstraint::Using(vec!["id".into()]);
+        }
+        assert_eq!(res.to_string(), "SELECT * FROM t CROSS JOIN u USING (id)");
+    }
+}
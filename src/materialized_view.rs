@@ -0,0 +1,275 @@
+use nom::branch::alt;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{opt, value};
+use nom::multi::separated_list1;
+use nom::sequence::{pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use column::Column;
+use common::{sql_identifier, statement_terminator, ws_sep_comma};
+use create::{select_specification, view_columns, SelectSpecification};
+use keywords::{escape_if_keyword, tag_no_case};
+
+fn fmt_with_data(f: &mut fmt::Formatter, with_data: Option<bool>) -> fmt::Result {
+    match with_data {
+        Some(true) => write!(f, " WITH DATA"),
+        Some(false) => write!(f, " WITH NO DATA"),
+        None => Ok(()),
+    }
+}
+
+/// Postgres `CREATE MATERIALIZED VIEW`. `with_data` records an explicit `WITH [NO] DATA`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CreateMaterializedViewStatement {
+    pub name: String,
+    pub if_not_exists: bool,
+    pub fields: Vec<Column>,
+    pub definition: Box<SelectSpecification>,
+    pub with_data: Option<bool>,
+}
+
+impl fmt::Display for CreateMaterializedViewStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CREATE MATERIALIZED VIEW ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        write!(f, "{}", escape_if_keyword(&self.name))?;
+        if !self.fields.is_empty() {
+            let fields = self
+                .fields
+                .iter()
+                .map(|field| format!("{}", field))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, " ({})", fields)?;
+        }
+        write!(f, " AS {}", self.definition)?;
+        fmt_with_data(f, self.with_data)
+    }
+}
+
+/// `REFRESH MATERIALIZED VIEW [CONCURRENTLY] name [WITH [NO] DATA]`
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct RefreshMaterializedViewStatement {
+    pub name: String,
+    pub concurrently: bool,
+    pub with_data: Option<bool>,
+}
+
+impl fmt::Display for RefreshMaterializedViewStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "REFRESH MATERIALIZED VIEW ")?;
+        if self.concurrently {
+            write!(f, "CONCURRENTLY ")?;
+        }
+        write!(f, "{}", escape_if_keyword(&self.name))?;
+        fmt_with_data(f, self.with_data)
+    }
+}
+
+/// `DROP MATERIALIZED VIEW [IF EXISTS] name, ... [CASCADE | RESTRICT]`, where `RESTRICT` is
+/// the default and not recorded.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct DropMaterializedViewStatement {
+    pub names: Vec<String>,
+    pub if_exists: bool,
+    pub cascade: bool,
+}
+
+impl fmt::Display for DropMaterializedViewStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DROP MATERIALIZED VIEW ")?;
+        if self.if_exists {
+            write!(f, "IF EXISTS ")?;
+        }
+        let names = self
+            .names
+            .iter()
+            .map(|n| escape_if_keyword(n))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{}", names)?;
+        if self.cascade {
+            write!(f, " CASCADE")?;
+        }
+        Ok(())
+    }
+}
+
+fn materialized_view(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((
+            tag_no_case("materialized"),
+            multispace1,
+            tag_no_case("view"),
+            multispace1,
+        )),
+    )(i)
+}
+
+fn with_data(i: &str) -> IResult<&str, bool> {
+    preceded(
+        pair(tag_no_case("with"), multispace1),
+        alt((
+            value(true, tag_no_case("data")),
+            value(
+                false,
+                tuple((tag_no_case("no"), multispace1, tag_no_case("data"))),
+            ),
+        )),
+    )(i)
+}
+
+pub fn create_materialized_view(i: &str) -> IResult<&str, CreateMaterializedViewStatement> {
+    let (
+        remaining_input,
+        (_, _, _, if_not_exists, name, fields, _, _, _, definition, with_data, _),
+    ) = tuple((
+        tag_no_case("create"),
+        multispace1,
+        materialized_view,
+        opt(terminated(
+            tuple((
+                tag_no_case("if"),
+                multispace1,
+                tag_no_case("not"),
+                multispace1,
+                tag_no_case("exists"),
+            )),
+            multispace1,
+        )),
+        sql_identifier,
+        opt(preceded(multispace0, view_columns)),
+        multispace1,
+        tag_no_case("as"),
+        multispace1,
+        select_specification,
+        opt(preceded(multispace1, with_data)),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        CreateMaterializedViewStatement {
+            name: String::from(name),
+            if_not_exists: if_not_exists.is_some(),
+            fields: fields.unwrap_or_default(),
+            definition: Box::new(definition),
+            with_data,
+        },
+    ))
+}
+
+pub fn refresh_materialized_view(i: &str) -> IResult<&str, RefreshMaterializedViewStatement> {
+    let (remaining_input, (_, _, _, concurrently, name, with_data, _)) = tuple((
+        tag_no_case("refresh"),
+        multispace1,
+        materialized_view,
+        opt(terminated(tag_no_case("concurrently"), multispace1)),
+        sql_identifier,
+        opt(preceded(multispace1, with_data)),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        RefreshMaterializedViewStatement {
+            name: String::from(name),
+            concurrently: concurrently.is_some(),
+            with_data,
+        },
+    ))
+}
+
+pub fn drop_materialized_view(i: &str) -> IResult<&str, DropMaterializedViewStatement> {
+    let (remaining_input, (_, _, _, if_exists, names, cascade, _)) = tuple((
+        tag_no_case("drop"),
+        multispace1,
+        materialized_view,
+        opt(terminated(
+            tuple((tag_no_case("if"), multispace1, tag_no_case("exists"))),
+            multispace1,
+        )),
+        separated_list1(ws_sep_comma, sql_identifier),
+        opt(preceded(
+            multispace1,
+            alt((
+                value(true, tag_no_case("cascade")),
+                value(false, tag_no_case("restrict")),
+            )),
+        )),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        DropMaterializedViewStatement {
+            names: names.into_iter().map(String::from).collect(),
+            if_exists: if_exists.is_some(),
+            cascade: cascade.unwrap_or(false),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_materialized_view_statement() {
+        let qstring = "CREATE MATERIALIZED VIEW IF NOT EXISTS daily (day, total) AS \
+                       SELECT day, count(*) FROM events GROUP BY day WITH NO DATA;";
+        let res = create_materialized_view(qstring).unwrap();
+        assert_eq!(res.0, "");
+        let view = res.1;
+        assert!(view.if_not_exists);
+        assert_eq!(
+            view.fields,
+            vec![Column::from("day"), Column::from("total")]
+        );
+        assert_eq!(view.with_data, Some(false));
+        assert_eq!(
+            format!("{}", view),
+            "CREATE MATERIALIZED VIEW IF NOT EXISTS daily (day, total) AS \
+             SELECT day, count(*) FROM events GROUP BY day WITH NO DATA"
+        );
+    }
+
+    #[test]
+    fn refresh_and_drop_materialized_views() {
+        let res = refresh_materialized_view("refresh materialized view concurrently daily")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res,
+            RefreshMaterializedViewStatement {
+                name: String::from("daily"),
+                concurrently: true,
+                with_data: None,
+            }
+        );
+        assert_eq!(
+            format!("{}", res),
+            "REFRESH MATERIALIZED VIEW CONCURRENTLY daily"
+        );
+
+        let res = drop_materialized_view("DROP MATERIALIZED VIEW IF EXISTS a, b CASCADE;")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res,
+            DropMaterializedViewStatement {
+                names: vec![String::from("a"), String::from("b")],
+                if_exists: true,
+                cascade: true,
+            }
+        );
+        assert_eq!(
+            format!("{}", res),
+            "DROP MATERIALIZED VIEW IF EXISTS a, b CASCADE"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{map, opt, value};
+use nom::multi::{many1, separated_list1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use column::Column;
+use common::{column_identifier_no_alias, statement_terminator, table_reference, ws_sep_comma};
+use condition::{condition_expr, ConditionExpression};
+use join::JoinRightSide;
+use keywords::{escape_if_keyword, escape_quoted, tag_no_case};
+use select::join_rhs;
+use table::Table;
+
+/// What a `WHEN [NOT] MATCHED` clause of a `MERGE` does with a row. Values may refer to columns
+/// of both the target and the source, so they are kept as expressions.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum MergeAction {
+    Update(Vec<(Column, ConditionExpression)>),
+    Delete,
+    /// Inserts a row, represented as (columns, values).
+    Insert(Option<Vec<Column>>, Vec<ConditionExpression>),
+}
+
+impl fmt::Display for MergeAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MergeAction::Update(ref assignments) => write!(
+                f,
+                "UPDATE SET {}",
+                assignments
+                    .iter()
+                    .map(|(col, value)| format!("{} = {}", col, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            MergeAction::Delete => write!(f, "DELETE"),
+            MergeAction::Insert(ref fields, ref values) => {
+                write!(f, "INSERT ")?;
+                if let Some(ref fields) = *fields {
+                    write!(
+                        f,
+                        "({}) ",
+                        fields
+                            .iter()
+                            .map(|col| escape_if_keyword(&col.name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )?;
+                }
+                write!(
+                    f,
+                    "VALUES ({})",
+                    values
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// `WHEN [NOT] MATCHED [AND condition] THEN action`
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MergeClause {
+    pub matched: bool,
+    pub condition: Option<ConditionExpression>,
+    pub action: MergeAction,
+}
+
+impl fmt::Display for MergeClause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WHEN ")?;
+        if !self.matched {
+            write!(f, "NOT ")?;
+        }
+        write!(f, "MATCHED")?;
+        if let Some(ref condition) = self.condition {
+            write!(f, " AND {}", condition)?;
+        }
+        write!(f, " THEN {}", self.action)
+    }
+}
+
+/// `MERGE INTO target USING source ON condition WHEN ...`, which updates, deletes or inserts
+/// rows of `target` depending on whether they match a row of `source`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MergeStatement {
+    pub target: Table,
+    /// A table or a nested selection.
+    pub source: JoinRightSide,
+    pub on: ConditionExpression,
+    pub clauses: Vec<MergeClause>,
+}
+
+impl fmt::Display for MergeStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MERGE INTO {}", self.target.escaped_name())?;
+        if let Some(ref alias) = self.target.alias {
+            write!(f, " AS {}", escape_quoted(alias, self.target.alias_quote))?;
+        }
+        write!(f, " USING {} ON {}", self.source, self.on)?;
+        for clause in &self.clauses {
+            write!(f, " {}", clause)?;
+        }
+        Ok(())
+    }
+}
+
+fn update_action(i: &str) -> IResult<&str, MergeAction> {
+    map(
+        preceded(
+            tuple((
+                tag_no_case("update"),
+                multispace1,
+                tag_no_case("set"),
+                multispace1,
+            )),
+            separated_list1(
+                ws_sep_comma,
+                separated_pair(
+                    column_identifier_no_alias,
+                    delimited(multispace0, tag("="), multispace0),
+                    condition_expr,
+                ),
+            ),
+        ),
+        MergeAction::Update,
+    )(i)
+}
+
+fn insert_action(i: &str) -> IResult<&str, MergeAction> {
+    let (remaining_input, (_, fields, _, _, values)) = tuple((
+        tag_no_case("insert"),
+        opt(preceded(
+            multispace0,
+            delimited(
+                pair(tag("("), multispace0),
+                separated_list1(ws_sep_comma, column_identifier_no_alias),
+                pair(multispace0, tag(")")),
+            ),
+        )),
+        multispace0,
+        tag_no_case("values"),
+        preceded(
+            multispace0,
+            delimited(
+                pair(tag("("), multispace0),
+                separated_list1(ws_sep_comma, condition_expr),
+                pair(multispace0, tag(")")),
+            ),
+        ),
+    ))(i)?;
+
+    Ok((remaining_input, MergeAction::Insert(fields, values)))
+}
+
+fn merge_clause(i: &str) -> IResult<&str, MergeClause> {
+    let (remaining_input, (_, _, not, _, condition, _, _, _, action)) = tuple((
+        tag_no_case("when"),
+        multispace1,
+        opt(terminated(tag_no_case("not"), multispace1)),
+        tag_no_case("matched"),
+        opt(preceded(
+            tuple((multispace1, tag_no_case("and"), multispace1)),
+            condition_expr,
+        )),
+        multispace1,
+        tag_no_case("then"),
+        multispace1,
+        alt((
+            update_action,
+            value(MergeAction::Delete, tag_no_case("delete")),
+            insert_action,
+        )),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        MergeClause {
+            matched: not.is_none(),
+            condition,
+            action,
+        },
+    ))
+}
+
+pub fn merge(i: &str) -> IResult<&str, MergeStatement> {
+    let (remaining_input, (_, _, _, _, target, _, _, _, source, _, _, _, on, clauses, _)) =
+        tuple((
+            tag_no_case("merge"),
+            multispace1,
+            tag_no_case("into"),
+            multispace1,
+            table_reference,
+            multispace1,
+            tag_no_case("using"),
+            multispace1,
+            join_rhs,
+            multispace1,
+            tag_no_case("on"),
+            multispace1,
+            condition_expr,
+            many1(preceded(multispace1, merge_clause)),
+            statement_terminator,
+        ))(i)?;
+
+    Ok((
+        remaining_input,
+        MergeStatement {
+            target,
+            source,
+            on,
+            clauses,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_with_all_actions() {
+        let qstring = "MERGE INTO accounts a USING (SELECT id, balance FROM staging) AS s \
+                       ON a.id = s.id \
+                       WHEN MATCHED AND s.balance = 0 THEN DELETE \
+                       WHEN MATCHED THEN UPDATE SET balance = a.balance + s.balance, seen = 1 \
+                       WHEN NOT MATCHED THEN INSERT (id, balance) VALUES (s.id, s.balance);";
+        let res = merge(qstring).unwrap();
+        assert_eq!(res.0, "");
+        let merge = res.1;
+        assert_eq!(merge.target.alias, Some("a".into()));
+        assert_eq!(merge.clauses.len(), 3);
+        assert!(!merge.clauses[2].matched);
+        assert_eq!(merge.clauses[0].action, MergeAction::Delete);
+        assert_eq!(
+            format!("{}", merge),
+            "MERGE INTO accounts AS a USING (SELECT id, balance FROM staging) AS s \
+             ON a.id = s.id \
+             WHEN MATCHED AND s.balance = 0 THEN DELETE \
+             WHEN MATCHED THEN UPDATE SET balance = a.balance + s.balance, seen = 1 \
+             WHEN NOT MATCHED THEN INSERT (id, balance) VALUES (s.id, s.balance)"
+        );
+    }
+
+    #[test]
+    fn merge_from_table() {
+        let qstring = "merge into t using src on t.k = src.k \
+                       when not matched then insert values (src.k, 'x')";
+        let merge = merge(qstring).unwrap().1;
+        assert_eq!(merge.source, JoinRightSide::Table(Table::from("src")));
+        assert_eq!(
+            format!("{}", merge),
+            "MERGE INTO t USING src ON t.k = src.k \
+             WHEN NOT MATCHED THEN INSERT VALUES (src.k, 'x')"
+        );
+        // at least one WHEN clause is required
+        assert!(super::merge("MERGE INTO t USING src ON t.k = src.k").is_err());
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::BTreeSet;
+
+use arithmetic::{ArithmeticBase, ArithmeticExpression};
+use column::{Column, FunctionExpression};
+use common::{FieldDefinitionExpression, FieldValueExpression};
+use compound_select::CompoundSelectStatement;
+use condition::{ConditionBase, ConditionExpression};
+use create::SelectSpecification;
+use join::{JoinConstraint, JoinRightSide};
+use merge::{MergeAction, MergeStatement};
+use parser::SqlQuery;
+use select::{JoinClause, SelectStatement};
+use table::Table;
+use with::WithStatement;
+
+/// Measures of the size and complexity of a statement, as returned by `query_metrics`.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
+pub struct QueryMetrics {
+    /// Joins in all of the statement's selects, counting each table after the first of a
+    /// comma-separated list as an implicit join.
+    pub joins: usize,
+    /// How deeply subqueries are nested, 0 if there are none. Derived tables and common table
+    /// expressions count as subqueries.
+    pub subquery_depth: usize,
+    /// Predicates in `WHERE`, `HAVING` and `ON` clauses and the `WHEN` clauses of a `MERGE`,
+    /// subqueries included: the conditions that `AND`, `OR` and `NOT` combine, such as `a = 1` or
+    /// `b IN (SELECT ...)`.
+    pub predicates: usize,
+    /// Distinct tables read or written anywhere in the statement. Table functions, `VALUES` lists,
+    /// subqueries and common table expressions are not tables.
+    pub tables: usize,
+    /// Columns returned by the statement, or by the first branch of a compound select. A `*` is
+    /// counted as one column; `expand_stars` can resolve it first.
+    pub projected_columns: usize,
+}
+
+/// Walks a statement, accumulating its metrics.
+#[derive(Default)]
+struct Walker<'a> {
+    metrics: QueryMetrics,
+    tables: BTreeSet<&'a str>,
+    /// Common table expressions in scope, which hide tables of the same name.
+    ctes: Vec<&'a str>,
+}
+
+impl<'a> Walker<'a> {
+    fn table(&mut self, table: &'a Table, depth: usize) {
+        let cte = table.schema.is_none() && self.ctes.contains(&&*table.name);
+        if table.is_stored() && !cte {
+            self.tables.insert(&table.name);
+        }
+        if let Some(ref subquery) = table.subquery {
+            self.select(subquery, depth + 1);
+        }
+    }
+
+    fn select(&mut self, select: &'a SelectStatement, depth: usize) {
+        self.metrics.subquery_depth = self.metrics.subquery_depth.max(depth);
+        self.metrics.joins += select.tables.len().saturating_sub(1);
+        for table in &select.tables {
+            self.table(table, depth);
+        }
+        for field in &select.fields {
+            self.field(field, depth);
+        }
+        for join in &select.join {
+            self.join(join, depth);
+        }
+        if let Some(ref cond) = select.where_clause {
+            self.predicate(cond, depth);
+        }
+        if let Some(cond) = select.group_by.as_ref().and_then(|g| g.having.as_ref()) {
+            self.predicate(cond, depth);
+        }
+    }
+
+    fn join(&mut self, join: &'a JoinClause, depth: usize) {
+        self.metrics.joins += 1;
+        self.join_right(&join.right, depth);
+        if let JoinConstraint::On(ref cond) = join.constraint {
+            self.predicate(cond, depth);
+        }
+    }
+
+    fn join_right(&mut self, right: &'a JoinRightSide, depth: usize) {
+        match *right {
+            JoinRightSide::Table(ref table) => self.table(table, depth),
+            JoinRightSide::Tables(ref tables) => {
+                self.metrics.joins += tables.len().saturating_sub(1);
+                for table in tables {
+                    self.table(table, depth);
+                }
+            }
+            JoinRightSide::NestedSelect(ref select, _) => self.select(select, depth + 1),
+            JoinRightSide::NestedJoin(ref nested) => self.join(nested, depth),
+        }
+    }
+
+    /// A condition in a boolean context, whose predicates are counted.
+    fn predicate(&mut self, cond: &'a ConditionExpression, depth: usize) {
+        match *cond {
+            ConditionExpression::LogicalOp(ref tree) => {
+                self.predicate(&tree.left, depth);
+                self.predicate(&tree.right, depth);
+            }
+            ConditionExpression::NegationOp(ref inner)
+            | ConditionExpression::Bracketed(ref inner) => self.predicate(inner, depth),
+            _ => {
+                self.metrics.predicates += 1;
+                self.expression(cond, depth);
+            }
+        }
+    }
+
+    /// Any other condition, which is only searched for subqueries.
+    fn expression(&mut self, cond: &'a ConditionExpression, depth: usize) {
+        match *cond {
+            ConditionExpression::ComparisonOp(ref tree)
+            | ConditionExpression::LogicalOp(ref tree) => {
+                self.expression(&tree.left, depth);
+                self.expression(&tree.right, depth);
+            }
+            ConditionExpression::NegationOp(ref inner)
+            | ConditionExpression::Bracketed(ref inner)
+            | ConditionExpression::Base(ConditionBase::Any(ref inner))
+            | ConditionExpression::Base(ConditionBase::Escaped(ref inner, _)) => {
+                self.expression(inner, depth)
+            }
+            ConditionExpression::Base(ConditionBase::NestedSelect(ref select)) => {
+                self.select(select, depth + 1)
+            }
+            ConditionExpression::Base(ConditionBase::Row(ref exprs, _))
+            | ConditionExpression::Base(ConditionBase::ExpressionList(ref exprs)) => {
+                for expr in exprs {
+                    self.expression(expr, depth);
+                }
+            }
+            ConditionExpression::Base(ConditionBase::Field(ref col)) => self.column(col, depth),
+            ConditionExpression::Arithmetic(ref expr) => self.arithmetic(expr, depth),
+            ConditionExpression::Base(ConditionBase::BitNot(ref base)) => {
+                self.arithmetic_base(base, depth)
+            }
+            ConditionExpression::Base(_) => (),
+        }
+    }
+
+    fn column(&mut self, col: &'a Column, depth: usize) {
+        if let Some(FunctionExpression::Call(_, ref args)) = col.function.as_deref() {
+            for arg in args {
+                self.expression(arg, depth);
+            }
+        }
+    }
+
+    fn arithmetic(&mut self, expr: &'a ArithmeticExpression, depth: usize) {
+        for base in &[&expr.left, &expr.right] {
+            self.arithmetic_base(base, depth);
+        }
+    }
+
+    fn arithmetic_base(&mut self, base: &'a ArithmeticBase, depth: usize) {
+        match *base {
+            ArithmeticBase::Column(ref col) => self.column(col, depth),
+            ArithmeticBase::BitNot(ref base) => self.arithmetic_base(base, depth),
+            ArithmeticBase::Nested(ref expr) => self.arithmetic(expr, depth),
+            ArithmeticBase::Scalar(_) | ArithmeticBase::Variable(_) => (),
+        }
+    }
+
+    fn value(&mut self, value: &'a FieldValueExpression, depth: usize) {
+        match *value {
+            FieldValueExpression::Arithmetic(ref expr) => self.arithmetic(expr, depth),
+            FieldValueExpression::Expression(ref cond) => self.expression(cond, depth),
+            FieldValueExpression::Predicate(ref pred) => self.expression(&pred.condition, depth),
+            FieldValueExpression::Variable(ref var) => {
+                if let Some(ref value) = var.value {
+                    self.field(value, depth);
+                }
+            }
+            FieldValueExpression::Json(ref json) => self.column(&json.column, depth),
+            FieldValueExpression::Subscript(ref sub) => self.column(&sub.column, depth),
+            FieldValueExpression::Literal(_) => (),
+        }
+    }
+
+    fn field(&mut self, field: &'a FieldDefinitionExpression, depth: usize) {
+        match *field {
+            FieldDefinitionExpression::Col(ref col) => self.column(col, depth),
+            FieldDefinitionExpression::Value(ref value) => self.value(value, depth),
+            FieldDefinitionExpression::All | FieldDefinitionExpression::AllInTable(_) => (),
+        }
+    }
+
+    /// Walks a `MERGE`, whose source counts as a join with its target.
+    fn merge(&mut self, merge: &'a MergeStatement) {
+        self.table(&merge.target, 0);
+        self.metrics.joins += 1;
+        self.join_right(&merge.source, 0);
+        self.predicate(&merge.on, 0);
+        for clause in &merge.clauses {
+            if let Some(ref cond) = clause.condition {
+                self.predicate(cond, 0);
+            }
+            match clause.action {
+                MergeAction::Update(ref assignments) => {
+                    for (_, value) in assignments {
+                        self.expression(value, 0);
+                    }
+                }
+                MergeAction::Insert(_, ref values) => {
+                    for value in values {
+                        self.expression(value, 0);
+                    }
+                }
+                MergeAction::Delete => (),
+            }
+        }
+    }
+
+    /// Walks the branches of a compound select, returning how many columns it has.
+    fn compound(&mut self, compound: &'a CompoundSelectStatement, depth: usize) -> usize {
+        for (_, select) in &compound.selects {
+            self.select(select, depth);
+        }
+        compound.selects.first().map_or(0, |(_, s)| s.fields.len())
+    }
+
+    /// Walks a select or compound select, returning how many columns it has.
+    fn specification(&mut self, spec: &'a SelectSpecification, depth: usize) -> usize {
+        match *spec {
+            SelectSpecification::Simple(ref select) => {
+                self.select(select, depth);
+                select.fields.len()
+            }
+            SelectSpecification::Compound(ref compound) => self.compound(compound, depth),
+        }
+    }
+
+    /// Walks the common table expressions of a `WITH` query as subqueries, then its main query,
+    /// returning how many columns that has.
+    fn with(&mut self, with: &'a WithStatement) -> usize {
+        for cte in &with.ctes {
+            if with.recursive {
+                self.ctes.push(&cte.name);
+            }
+            self.specification(&cte.query, 1);
+            if !with.recursive {
+                self.ctes.push(&cte.name);
+            }
+        }
+        self.specification(&with.query, 0)
+    }
+}
+
+/// Measures `query`'s joins, subquery nesting, predicates, tables and projected columns, for
+/// scoring how complex it is. Selects, compound selects, `WITH` queries, cursor declarations,
+/// inserts, updates, deletes, merges and view and materialized view definitions are measured;
+/// other statements have no metrics.
+pub fn query_metrics(query: &SqlQuery) -> QueryMetrics {
+    let mut walker = Walker::default();
+    match *query {
+        SqlQuery::Select(ref select) => {
+            walker.select(select, 0);
+            walker.metrics.projected_columns = select.fields.len();
+        }
+        SqlQuery::CompoundSelect(ref compound) => {
+            walker.metrics.projected_columns = walker.compound(compound, 0);
+        }
+        SqlQuery::CreateView(ref view) => {
+            walker.metrics.projected_columns = walker.specification(&view.definition, 0);
+        }
+        SqlQuery::CreateMaterializedView(ref view) => {
+            walker.metrics.projected_columns = walker.specification(&view.definition, 0);
+        }
+        SqlQuery::With(ref with) => {
+            walker.metrics.projected_columns = walker.with(with);
+        }
+        SqlQuery::DeclareCursor(ref declare) => {
+            walker.metrics.projected_columns = walker.specification(&declare.query, 0);
+        }
+        SqlQuery::Merge(ref merge) => walker.merge(merge),
+        SqlQuery::Insert(ref insert) => {
+            walker.table(&insert.table, 0);
+            let assigned = insert.on_duplicate.iter().flatten().map(|(_, v)| v);
+            for value in insert.data.iter().flatten().chain(assigned) {
+                walker.value(value, 0);
+            }
+        }
+        SqlQuery::Update(ref update) => {
+            walker.table(&update.table, 0);
+            for (_, value) in &update.fields {
+                walker.value(value, 0);
+            }
+            if let Some(ref cond) = update.where_clause {
+                walker.predicate(cond, 0);
+            }
+        }
+        SqlQuery::Delete(ref delete) => {
+            walker.table(&delete.table, 0);
+            if let Some(ref cond) = delete.where_clause {
+                walker.predicate(cond, 0);
+            }
+        }
+        _ => (),
+    }
+    walker.metrics.tables = walker.tables.len();
+    walker.metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+
+    fn metrics(q: &str) -> QueryMetrics {
+        query_metrics(&parse_query(q).unwrap())
+    }
+
+    #[test]
+    fn select_metrics() {
+        assert_eq!(
+            metrics(
+                "SELECT u.id, u.name, count(*) FROM users AS u \
+                 JOIN orders AS o ON o.uid = u.id AND o.total > 10 \
+                 LEFT JOIN (SELECT uid FROM refunds WHERE amount > 0) AS r ON r.uid = u.id \
+                 WHERE NOT (u.banned = 1 OR u.id IN (SELECT uid FROM bans WHERE uid IN \
+                 (SELECT id FROM users WHERE karma < 0))) GROUP BY u.id, u.name"
+            ),
+            QueryMetrics {
+                joins: 2,
+                subquery_depth: 2,
+                predicates: 8,
+                tables: 4,
+                projected_columns: 3,
+            }
+        );
+        assert_eq!(
+            metrics(
+                "SELECT * FROM a, b, generate_series(1, 3) AS g \
+                 WHERE coalesce((SELECT max(id) FROM c), 0) = 1"
+            ),
+            QueryMetrics {
+                joins: 2,
+                subquery_depth: 1,
+                predicates: 1,
+                tables: 3,
+                projected_columns: 1,
+            }
+        );
+        assert_eq!(
+            metrics(
+                "SELECT id, name FROM users UNION SELECT id, name FROM admins WHERE active = 1"
+            ),
+            QueryMetrics {
+                tables: 2,
+                predicates: 1,
+                projected_columns: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn write_metrics() {
+        assert_eq!(
+            metrics(
+                "UPDATE users SET karma = karma + 1 WHERE id = 1 OR id IN (SELECT uid FROM votes)"
+            ),
+            QueryMetrics {
+                subquery_depth: 1,
+                predicates: 2,
+                tables: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            metrics("DELETE FROM users WHERE id = 1"),
+            QueryMetrics {
+                predicates: 1,
+                tables: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            metrics("INSERT INTO users (id, name) VALUES (1, 'a')"),
+            QueryMetrics {
+                tables: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(metrics("DROP TABLE users"), QueryMetrics::default());
+    }
+
+    #[test]
+    fn with_metrics() {
+        assert_eq!(
+            metrics(
+                "WITH o AS (SELECT uid, total FROM orders WHERE total > 10), \
+                 u AS (SELECT id FROM users WHERE id IN (SELECT uid FROM o)) \
+                 SELECT u.id, o.total FROM u JOIN o ON o.uid = u.id"
+            ),
+            QueryMetrics {
+                joins: 1,
+                subquery_depth: 2,
+                predicates: 3,
+                tables: 2,
+                projected_columns: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn select_bearing_statements() {
+        assert_eq!(
+            metrics("SELECT id IN (SELECT uid FROM bans) AS banned FROM users"),
+            QueryMetrics {
+                subquery_depth: 1,
+                tables: 2,
+                projected_columns: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            metrics("DECLARE c CURSOR FOR SELECT id, name FROM users WHERE karma > 0"),
+            QueryMetrics {
+                predicates: 1,
+                tables: 1,
+                projected_columns: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            metrics(
+                "CREATE MATERIALIZED VIEW top AS SELECT users.id FROM users \
+                 JOIN orders ON orders.uid = users.id"
+            ),
+            QueryMetrics {
+                joins: 1,
+                predicates: 1,
+                tables: 2,
+                projected_columns: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            metrics(
+                "MERGE INTO archive USING (SELECT * FROM orders WHERE total > 0) AS o \
+                 ON archive.id = o.id WHEN MATCHED AND o.total > 10 THEN UPDATE SET total = o.total \
+                 WHEN NOT MATCHED THEN INSERT VALUES (o.id)"
+            ),
+            QueryMetrics {
+                joins: 1,
+                subquery_depth: 1,
+                predicates: 3,
+                tables: 2,
+                projected_columns: 0,
+            }
+        );
+    }
+}
@@ -1,14 +1,22 @@
-use nom::multispace;
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::character::complete::{digit1, satisfy};
+use nom::combinator::{map, map_res, not, opt, value};
+use nom::multi::many0;
+use nom::sequence::{preceded, terminated, tuple};
+use nom::IResult;
 use std::fmt;
-use std::str;
+use std::str::FromStr;
 
 use column::Column;
-use common::{column_identifier_no_alias, opt_multispace};
-use keywords::escape_if_keyword;
+use common::{
+    column_identifier_no_alias, is_sql_identifier, keyword_phrase, whitespace0, whitespace1,
+    ws_sep_comma,
+};
+use keywords::tag_no_case;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum OrderType {
+    #[default]
     OrderAscending,
     OrderDescending,
 }
@@ -22,9 +30,56 @@ impl fmt::Display for OrderType {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// A term of an ORDER BY or GROUP BY clause: a column, or the 1-based position of a field in the
+/// select list, as in `ORDER BY 1`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum FieldReference {
+    Column(Column),
+    Position(u64),
+}
+
+impl fmt::Display for FieldReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldReference::Column(ref col) => write!(f, "{}", col),
+            FieldReference::Position(pos) => write!(f, "{}", pos),
+        }
+    }
+}
+
+impl From<Column> for FieldReference {
+    fn from(col: Column) -> FieldReference {
+        FieldReference::Column(col)
+    }
+}
+
+impl From<&str> for FieldReference {
+    fn from(c: &str) -> FieldReference {
+        FieldReference::Column(c.into())
+    }
+}
+
+impl FieldReference {
+    /// The column referred to, or `None` for a position.
+    pub fn column(&self) -> Option<&Column> {
+        match *self {
+            FieldReference::Column(ref col) => Some(col),
+            FieldReference::Position(_) => None,
+        }
+    }
+
+    /// The column referred to, mutably, or `None` for a position.
+    pub fn column_mut(&mut self) -> Option<&mut Column> {
+        match *self {
+            FieldReference::Column(ref mut col) => Some(col),
+            FieldReference::Position(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct OrderClause {
-    pub columns: Vec<(Column, OrderType)>, // TODO(malte): can this be an arbitrary expr?
+    pub columns: Vec<(FieldReference, OrderType)>, // TODO(malte): can this be an arbitrary expr?
 }
 
 impl fmt::Display for OrderClause {
@@ -35,56 +90,59 @@ impl fmt::Display for OrderClause {
             "{}",
             self.columns
                 .iter()
-                .map(|&(ref c, ref o)| format!("{} {}", escape_if_keyword(&c.name), o))
+                .map(|(c, o)| format!("{} {}", c, o))
                 .collect::<Vec<_>>()
                 .join(", ")
         )
     }
 }
 
-named!(pub order_type<CompleteByteSlice, OrderType>,
-    alt!(
-          map!(tag_no_case!("desc"), |_| OrderType::OrderDescending)
-        | map!(tag_no_case!("asc"), |_| OrderType::OrderAscending)
-    )
-);
+pub fn order_type(i: &str) -> IResult<&str, OrderType> {
+    alt((
+        value(OrderType::OrderDescending, tag_no_case("desc")),
+        value(OrderType::OrderAscending, tag_no_case("asc")),
+    ))(i)
+}
+
+/// Parses a term of an ORDER BY or GROUP BY clause. A bare integer is a position in the select
+/// list; a column named by digits has to be quoted.
+pub fn field_reference(i: &str) -> IResult<&str, FieldReference> {
+    alt((
+        map(
+            map_res(
+                terminated(digit1, not(satisfy(is_sql_identifier))),
+                u64::from_str,
+            ),
+            FieldReference::Position,
+        ),
+        map(column_identifier_no_alias, FieldReference::Column),
+    ))(i)
+}
+
+fn order_expr(i: &str) -> IResult<&str, (FieldReference, OrderType)> {
+    let (remaining_input, (field_name, ordering, _)) = tuple((
+        field_reference,
+        opt(preceded(whitespace0, order_type)),
+        opt(ws_sep_comma),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        (field_name, ordering.unwrap_or(OrderType::OrderAscending)),
+    ))
+}
 
 /// Parse ORDER BY clause
-named!(pub order_clause<CompleteByteSlice, OrderClause>,
-    do_parse!(
-        opt_multispace >>
-        tag_no_case!("order by") >>
-        multispace >>
-        order_expr: many0!(
-            do_parse!(
-                fieldname: column_identifier_no_alias >>
-                ordering: opt!(
-                    do_parse!(
-                        opt_multispace >>
-                        ordering: order_type >>
-                        (ordering)
-                    )
-                ) >>
-                opt!(
-                    do_parse!(
-                        opt_multispace >>
-                        tag!(",") >>
-                        opt_multispace >>
-                        ()
-                    )
-                ) >>
-                (fieldname, ordering.unwrap_or(OrderType::OrderAscending))
-            )
-        ) >>
-        (OrderClause {
-            columns: order_expr,
-            // order: match ordering {
-            //     None => OrderType::OrderAscending,
-            //     Some(ref o) => o.clone(),
-            // },
-        })
-    )
-);
+pub fn order_clause(i: &str) -> IResult<&str, OrderClause> {
+    let (remaining_input, (_, _, _, columns)) = tuple((
+        whitespace0,
+        keyword_phrase(&["order", "by"]),
+        whitespace1,
+        many0(order_expr),
+    ))(i)?;
+
+    Ok((remaining_input, OrderClause { columns }))
+}
 
 #[cfg(test)]
 mod tests {
@@ -110,9 +168,9 @@ mod tests {
             columns: vec![("name".into(), OrderType::OrderAscending)],
         };
 
-        let res1 = selection(CompleteByteSlice(qstring1.as_bytes()));
-        let res2 = selection(CompleteByteSlice(qstring2.as_bytes()));
-        let res3 = selection(CompleteByteSlice(qstring3.as_bytes()));
+        let res1 = selection(qstring1);
+        let res2 = selection(qstring2);
+        let res3 = selection(qstring3);
         assert_eq!(res1.unwrap().1.order, Some(expected_ord1));
         assert_eq!(res2.unwrap().1.order, Some(expected_ord2));
         assert_eq!(res3.unwrap().1.order, Some(expected_ord3));
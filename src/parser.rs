@@ -1,17 +1,56 @@
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::combinator::{eof, map};
+use nom::error::{Error as NomError, ErrorKind};
+use nom::sequence::{pair, terminated};
+use nom::IResult;
+use std::error::Error;
 use std::fmt;
 use std::str;
 
+use admin::{admin_statement, AdminStatement};
+use alter::{alter_table, AlterTableStatement};
+use call::{call, CallStatement};
+use common::whitespace0;
 use compound_select::{compound_selection, CompoundSelectStatement};
 use create::{creation, view_creation, CreateTableStatement, CreateViewStatement};
+use cursor::{
+    close_cursor, declare_cursor, fetch, CloseCursorStatement, DeclareCursorStatement,
+    FetchStatement,
+};
+use database::{
+    create_database, drop_database, use_statement, CreateDatabaseStatement, DropDatabaseStatement,
+    UseStatement,
+};
 use delete::{deletion, DeleteStatement};
+use dialect::{with_dialect, Dialect};
+use do_statement::{do_statement, DoStatement};
 use drop::{drop_table, DropTableStatement};
+use extension::{create_extension, CreateExtensionStatement};
+use handler::{handler, HandlerStatement};
+use ident::{with_interner, Interner};
+use index::{create_index, CreateIndexStatement};
 use insert::{insertion, InsertStatement};
+use limits::{is_too_deep, with_max_depth, with_memo, ParserLimits};
+use lock::{lock_tables, unlock_tables, LockTablesStatement};
+use materialized_view::{
+    create_materialized_view, drop_materialized_view, refresh_materialized_view,
+    CreateMaterializedViewStatement, DropMaterializedViewStatement,
+    RefreshMaterializedViewStatement,
+};
+use merge::{merge, MergeStatement};
 use select::{selection, SelectStatement};
-use set::{set, SetStatement};
+use sequence::{
+    alter_sequence, create_sequence, drop_sequence, AlterSequenceStatement,
+    CreateSequenceStatement, DropSequenceStatement,
+};
+use set::{set, set_search_path, SetSearchPathStatement, SetStatement};
+use transaction::{transaction_statement, TransactionStatement};
 use update::{updating, UpdateStatement};
+use user_type::{create_domain, create_type, CreateDomainStatement, CreateTypeStatement};
+use values::{values_statement, ValuesStatement};
+use with::{with_statement, WithStatement};
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum SqlQuery {
     CreateTable(CreateTableStatement),
     CreateView(CreateViewStatement),
@@ -22,12 +61,65 @@ pub enum SqlQuery {
     DropTable(DropTableStatement),
     Update(UpdateStatement),
     Set(SetStatement),
+    CreateDatabase(CreateDatabaseStatement),
+    DropDatabase(DropDatabaseStatement),
+    Use(UseStatement),
+    CreateSequence(CreateSequenceStatement),
+    AlterSequence(AlterSequenceStatement),
+    DropSequence(DropSequenceStatement),
+    AlterTable(AlterTableStatement),
+    LockTables(LockTablesStatement),
+    UnlockTables,
+    Handler(HandlerStatement),
+    Do(DoStatement),
+    Admin(AdminStatement),
+    Transaction(TransactionStatement),
+    Values(ValuesStatement),
+    Merge(MergeStatement),
+    CreateMaterializedView(CreateMaterializedViewStatement),
+    RefreshMaterializedView(RefreshMaterializedViewStatement),
+    DropMaterializedView(DropMaterializedViewStatement),
+    CreateIndex(CreateIndexStatement),
+    CreateType(CreateTypeStatement),
+    CreateDomain(CreateDomainStatement),
+    CreateExtension(CreateExtensionStatement),
+    SetSearchPath(SetSearchPathStatement),
+    DeclareCursor(DeclareCursorStatement),
+    Fetch(FetchStatement),
+    CloseCursor(CloseCursorStatement),
+    Call(CallStatement),
+    With(WithStatement),
+    /// A statement that could not be parsed, kept verbatim by lenient script parsing.
+    Unparsed(String, ParseError),
+}
+
+/// Describes why a statement failed to parse.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ParseError {
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<&'static str> for ParseError {
+    fn from(message: &'static str) -> Self {
+        ParseError {
+            message: String::from(message),
+        }
+    }
 }
 
 impl fmt::Display for SqlQuery {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             SqlQuery::Select(ref select) => write!(f, "{}", select),
+            SqlQuery::CompoundSelect(ref compound) => write!(f, "{}", compound),
             SqlQuery::Insert(ref insert) => write!(f, "{}", insert),
             SqlQuery::CreateTable(ref create) => write!(f, "{}", create),
             SqlQuery::CreateView(ref create) => write!(f, "{}", create),
@@ -35,36 +127,269 @@ impl fmt::Display for SqlQuery {
             SqlQuery::DropTable(ref drop) => write!(f, "{}", drop),
             SqlQuery::Update(ref update) => write!(f, "{}", update),
             SqlQuery::Set(ref set) => write!(f, "{}", set),
-            _ => unimplemented!(),
+            SqlQuery::CreateDatabase(ref create) => write!(f, "{}", create),
+            SqlQuery::DropDatabase(ref drop) => write!(f, "{}", drop),
+            SqlQuery::Use(ref use_stmt) => write!(f, "{}", use_stmt),
+            SqlQuery::CreateSequence(ref create) => write!(f, "{}", create),
+            SqlQuery::AlterSequence(ref alter) => write!(f, "{}", alter),
+            SqlQuery::DropSequence(ref drop) => write!(f, "{}", drop),
+            SqlQuery::AlterTable(ref alter) => write!(f, "{}", alter),
+            SqlQuery::LockTables(ref lock) => write!(f, "{}", lock),
+            SqlQuery::UnlockTables => write!(f, "UNLOCK TABLES"),
+            SqlQuery::Handler(ref handler) => write!(f, "{}", handler),
+            SqlQuery::Do(ref do_stmt) => write!(f, "{}", do_stmt),
+            SqlQuery::Admin(ref admin) => write!(f, "{}", admin),
+            SqlQuery::Transaction(ref transaction) => write!(f, "{}", transaction),
+            SqlQuery::Values(ref values) => write!(f, "{}", values),
+            SqlQuery::Merge(ref merge) => write!(f, "{}", merge),
+            SqlQuery::CreateMaterializedView(ref create) => write!(f, "{}", create),
+            SqlQuery::RefreshMaterializedView(ref refresh) => write!(f, "{}", refresh),
+            SqlQuery::DropMaterializedView(ref drop) => write!(f, "{}", drop),
+            SqlQuery::CreateIndex(ref create) => write!(f, "{}", create),
+            SqlQuery::CreateType(ref create) => write!(f, "{}", create),
+            SqlQuery::CreateDomain(ref create) => write!(f, "{}", create),
+            SqlQuery::CreateExtension(ref create) => write!(f, "{}", create),
+            SqlQuery::SetSearchPath(ref set) => write!(f, "{}", set),
+            SqlQuery::DeclareCursor(ref declare) => write!(f, "{}", declare),
+            SqlQuery::Fetch(ref fetch) => write!(f, "{}", fetch),
+            SqlQuery::CloseCursor(ref close) => write!(f, "{}", close),
+            SqlQuery::Call(ref call) => write!(f, "{}", call),
+            SqlQuery::With(ref with) => write!(f, "{}", with),
+            SqlQuery::Unparsed(ref text, _) => write!(f, "{}", text),
         }
     }
 }
 
-named!(sql_query<CompleteByteSlice, SqlQuery>,
-    alt!(
-          do_parse!(c: creation >> (SqlQuery::CreateTable(c)))
-        | do_parse!(i: insertion >> (SqlQuery::Insert(i)))
-        | do_parse!(c: compound_selection >> (SqlQuery::CompoundSelect(c)))
-        | do_parse!(s: selection >> (SqlQuery::Select(s)))
-        | do_parse!(d: deletion >> (SqlQuery::Delete(d)))
-        | do_parse!(dt: drop_table >> (SqlQuery::DropTable(dt)))
-        | do_parse!(u: updating >> (SqlQuery::Update(u)))
-        | do_parse!(s: set >> (SqlQuery::Set(s)))
-        | do_parse!(c: view_creation >> (SqlQuery::CreateView(c)))
-    )
-);
+type StatementParser = fn(&str) -> IResult<&str, SqlQuery>;
+
+fn select_statement(i: &str) -> IResult<&str, SqlQuery> {
+    alt((
+        map(compound_selection, SqlQuery::CompoundSelect),
+        map(selection, SqlQuery::Select),
+    ))(i)
+}
+
+fn create_statement(i: &str) -> IResult<&str, SqlQuery> {
+    alt((
+        map(creation, SqlQuery::CreateTable),
+        map(view_creation, SqlQuery::CreateView),
+        map(create_database, SqlQuery::CreateDatabase),
+        map(create_sequence, SqlQuery::CreateSequence),
+        map(create_materialized_view, SqlQuery::CreateMaterializedView),
+        map(create_index, SqlQuery::CreateIndex),
+        map(create_type, SqlQuery::CreateType),
+        map(create_domain, SqlQuery::CreateDomain),
+        map(create_extension, SqlQuery::CreateExtension),
+    ))(i)
+}
+
+fn drop_statement(i: &str) -> IResult<&str, SqlQuery> {
+    alt((
+        map(drop_table, SqlQuery::DropTable),
+        map(drop_database, SqlQuery::DropDatabase),
+        map(drop_sequence, SqlQuery::DropSequence),
+        map(drop_materialized_view, SqlQuery::DropMaterializedView),
+    ))(i)
+}
+
+fn alter_statement(i: &str) -> IResult<&str, SqlQuery> {
+    alt((
+        map(alter_table, SqlQuery::AlterTable),
+        map(alter_sequence, SqlQuery::AlterSequence),
+    ))(i)
+}
+
+fn transaction(i: &str) -> IResult<&str, SqlQuery> {
+    map(transaction_statement, SqlQuery::Transaction)(i)
+}
+
+/// Statement parsers keyed on the leading keyword, so that only the parsers that can possibly
+/// match are attempted.
+const STATEMENT_PARSERS: &[(&str, StatementParser)] = &[
+    ("ALTER", alter_statement),
+    ("BEGIN", transaction),
+    ("CALL", |i| map(call, SqlQuery::Call)(i)),
+    ("CLOSE", |i| map(close_cursor, SqlQuery::CloseCursor)(i)),
+    ("COMMIT", transaction),
+    ("CREATE", create_statement),
+    ("DECLARE", |i| {
+        map(declare_cursor, SqlQuery::DeclareCursor)(i)
+    }),
+    ("DELETE", |i| map(deletion, SqlQuery::Delete)(i)),
+    ("DO", |i| map(do_statement, SqlQuery::Do)(i)),
+    ("DROP", drop_statement),
+    ("FETCH", |i| map(fetch, SqlQuery::Fetch)(i)),
+    ("FLUSH", |i| map(admin_statement, SqlQuery::Admin)(i)),
+    ("HANDLER", |i| map(handler, SqlQuery::Handler)(i)),
+    ("INSERT", |i| map(insertion, SqlQuery::Insert)(i)),
+    ("KILL", |i| map(admin_statement, SqlQuery::Admin)(i)),
+    ("LOCK", |i| map(lock_tables, SqlQuery::LockTables)(i)),
+    ("MERGE", |i| map(merge, SqlQuery::Merge)(i)),
+    ("REFRESH", |i| {
+        map(refresh_materialized_view, SqlQuery::RefreshMaterializedView)(i)
+    }),
+    ("RELEASE", transaction),
+    ("RESET", |i| map(admin_statement, SqlQuery::Admin)(i)),
+    ("ROLLBACK", transaction),
+    ("SAVEPOINT", transaction),
+    ("SELECT", select_statement),
+    ("SET", |i| {
+        alt((
+            map(set_search_path, SqlQuery::SetSearchPath),
+            map(set, SqlQuery::Set),
+        ))(i)
+    }),
+    ("START", transaction),
+    ("UNLOCK", |i| {
+        map(unlock_tables, |_| SqlQuery::UnlockTables)(i)
+    }),
+    ("UPDATE", |i| map(updating, SqlQuery::Update)(i)),
+    ("USE", |i| map(use_statement, SqlQuery::Use)(i)),
+    ("VALUES", |i| map(values_statement, SqlQuery::Values)(i)),
+    ("WITH", |i| map(with_statement, SqlQuery::With)(i)),
+];
+
+/// The input after any leading whitespace and comments, and the word it starts with.
+fn leading_keyword(i: &str) -> (&str, &str) {
+    let i = whitespace0(i).map_or(i, |(rest, _)| rest);
+    let end = i
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(i.len());
+    (i, &i[..end])
+}
+
+/// Looks up the parser for a statement by its first keyword, which may follow whitespace and
+/// comments, returning it along with the input from that keyword on. A parenthesized statement
+/// can only be a (compound) selection.
+fn statement_parser(i: &str) -> Option<(&str, StatementParser)> {
+    let (i, keyword) = leading_keyword(i);
+    if i.starts_with('(') {
+        return Some((i, select_statement));
+    }
+    STATEMENT_PARSERS
+        .iter()
+        .find(|&&(k, _)| k.eq_ignore_ascii_case(keyword))
+        .map(|&(_, parser)| (i, parser))
+}
+
+pub(crate) fn sql_query(i: &str) -> IResult<&str, SqlQuery> {
+    match statement_parser(i) {
+        Some((i, parser)) => parser(i),
+        None => Err(nom::Err::Error(NomError::new(i, ErrorKind::Tag))),
+    }
+}
 
 pub fn parse_query_bytes<T>(input: T) -> Result<SqlQuery, &'static str>
-    where T: AsRef<[u8]> {
-    match sql_query(CompleteByteSlice(input.as_ref())) {
-        Ok((_, o)) => Ok(o),
+where
+    T: AsRef<[u8]>,
+{
+    match str::from_utf8(input.as_ref()) {
+        Ok(s) => parse_query(s),
         Err(_) => Err("failed to parse query"),
     }
 }
 
 pub fn parse_query<T>(input: T) -> Result<SqlQuery, &'static str>
-    where T: AsRef<str> {
-    parse_query_bytes(input.as_ref().trim().as_bytes())
+where
+    T: AsRef<str>,
+{
+    parse_query_with_options(input, &ParseOptions::default())
+}
+
+/// How a statement is parsed: the dialect whose extensions are accepted, and the limits it must
+/// stay within. The script, streaming, lossless and interned parsers all take one, so that a
+/// dump written for one dialect parses the same through each of them.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ParseOptions {
+    pub dialect: Dialect,
+    pub limits: ParserLimits,
+}
+
+impl ParseOptions {
+    /// The default limits, for statements written for `dialect`.
+    pub fn dialect(dialect: Dialect) -> Self {
+        ParseOptions {
+            dialect,
+            ..Default::default()
+        }
+    }
+
+    /// Runs `f` with these options in effect for parsers on this thread. The length limit is
+    /// left to the caller, which knows what the statement is.
+    pub(crate) fn apply<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        with_dialect(self.dialect, || with_max_depth(self.limits.max_depth, f))
+    }
+}
+
+/// Parses a query written for `dialect`, accepting that dialect's extensions.
+pub fn parse_query_with_dialect<T>(input: T, dialect: Dialect) -> Result<SqlQuery, &'static str>
+where
+    T: AsRef<str>,
+{
+    parse_query_with_options(input, &ParseOptions::dialect(dialect))
+}
+
+/// Parses a query, making its table and column names with `interner` so that repeated names share
+/// storage, including with the names of other statements parsed with the same interner.
+pub fn parse_query_interned<T>(input: T, interner: &mut Interner) -> Result<SqlQuery, &'static str>
+where
+    T: AsRef<str>,
+{
+    parse_query_interned_with_options(input, interner, &ParseOptions::default())
+}
+
+/// Like `parse_query_interned`, with the dialect and limits given by `options`.
+pub fn parse_query_interned_with_options<T>(
+    input: T,
+    interner: &mut Interner,
+    options: &ParseOptions,
+) -> Result<SqlQuery, &'static str>
+where
+    T: AsRef<str>,
+{
+    with_interner(interner, || parse_query_with_options(input, options))
+}
+
+/// Parses a query, rejecting it if it is longer or more deeply nested than `limits` allow.
+pub fn parse_query_with_limits<T>(input: T, limits: &ParserLimits) -> Result<SqlQuery, &'static str>
+where
+    T: AsRef<str>,
+{
+    parse_query_with_options(
+        input,
+        &ParseOptions {
+            limits: *limits,
+            ..Default::default()
+        },
+    )
+}
+
+/// Parses a query written for `options.dialect`, rejecting it if it is longer or more deeply
+/// nested than `options.limits` allow.
+pub fn parse_query_with_options<T>(
+    input: T,
+    options: &ParseOptions,
+) -> Result<SqlQuery, &'static str>
+where
+    T: AsRef<str>,
+{
+    let input = input.as_ref().trim();
+    if input.len() > options.limits.max_length {
+        return Err("query too long");
+    }
+    if statement_parser(input).is_none() {
+        return Err("unsupported statement type");
+    }
+    // the statement must be all there is, save for comments after it
+    match options.apply(|| {
+        with_memo(input, || {
+            terminated(sql_query, pair(whitespace0, eof))(input)
+        })
+    }) {
+        Ok((_, o)) => Ok(o),
+        Err(ref e) if is_too_deep(e) => Err("query nested too deeply"),
+        Err(_) => Err("failed to parse query"),
+    }
 }
 
 #[cfg(test)]
@@ -73,6 +398,8 @@ mod tests {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
+    use common::{FieldDefinitionExpression, Operator};
+    use condition::ConditionExpression;
     use table::Table;
 
     #[test]
@@ -263,4 +590,321 @@ mod tests {
         assert_eq!(expected0, format!("{}", res0.unwrap()));
         assert_eq!(expected1, format!("{}", res1.unwrap()));
     }
+
+    #[test]
+    fn dispatch_on_first_keyword() {
+        assert_eq!(
+            parse_query("begin work;").unwrap(),
+            SqlQuery::Transaction(TransactionStatement::Start)
+        );
+        assert_eq!(
+            format!("{}", parse_query("SAVEPOINT sp1").unwrap()),
+            "SAVEPOINT sp1"
+        );
+        match parse_query("(SELECT id FROM a) UNION (SELECT id FROM b)").unwrap() {
+            SqlQuery::CompoundSelect(_) => (),
+            q => panic!("unexpected query {:?}", q),
+        }
+        assert_eq!(
+            parse_query("TRUNCATE users"),
+            Err("unsupported statement type")
+        );
+        match parse_query("WITH t AS (SELECT id FROM a) SELECT id FROM t").unwrap() {
+            SqlQuery::With(_) => (),
+            q => panic!("unexpected query {:?}", q),
+        }
+        assert_eq!(
+            parse_query("SELECT FROM WHERE"),
+            Err("failed to parse query")
+        );
+    }
+
+    #[test]
+    fn nesting_and_length_limits() {
+        let parens = |depth| {
+            format!(
+                "SELECT * FROM t WHERE {}a = 1{}",
+                "(".repeat(depth),
+                ")".repeat(depth)
+            )
+        };
+        assert!(parse_query(parens(20)).is_ok());
+        assert_eq!(parse_query(parens(1000)), Err("query nested too deeply"));
+        let subqueries = format!(
+            "SELECT * FROM t WHERE {}1{}",
+            "a IN (SELECT a FROM t WHERE ".repeat(1000),
+            ")".repeat(1000)
+        );
+        assert_eq!(parse_query(subqueries), Err("query nested too deeply"));
+        let functions = format!("SELECT {}x{} FROM t", "max(".repeat(1000), ")".repeat(1000));
+        assert_eq!(parse_query(functions), Err("query nested too deeply"));
+        let arrays = format!(
+            "SELECT * FROM t WHERE a = {}1{}",
+            "ARRAY[".repeat(10_000),
+            "]".repeat(10_000)
+        );
+        assert_eq!(parse_query(arrays), Err("query nested too deeply"));
+
+        let limits = ParserLimits {
+            max_length: 40,
+            max_depth: 64,
+        };
+        assert!(parse_query_with_limits(parens(5), &limits).is_ok());
+        assert_eq!(
+            parse_query_with_limits(parens(20), &limits),
+            Err("query too long")
+        );
+        assert_eq!(
+            parse_query_with_limits(parens(100), &ParserLimits::default()),
+            Err("query nested too deeply")
+        );
+        assert!(parse_query_with_limits(
+            parens(100),
+            &ParserLimits {
+                max_depth: 128,
+                ..Default::default()
+            }
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn nesting_limit_counts_each_level_once() {
+        // a construct tried again at the same place, or tried on an operand that turns out not
+        // to be one, is not another level
+        use limits::DEFAULT_MAX_DEPTH;
+        use std::time::{Duration, Instant};
+
+        let nest = |open: &str, innermost: &str, close: &str, depth| {
+            format!("{}{}{}", open.repeat(depth), innermost, close.repeat(depth))
+        };
+        for depth in DEFAULT_MAX_DEPTH..DEFAULT_MAX_DEPTH + 2 {
+            for q in &[
+                format!("SELECT * FROM t WHERE {}", nest("(", "a = 1", ")", depth)),
+                format!("SELECT * FROM t WHERE {}", nest("NOT ", "a = 1", "", depth)),
+                format!("SELECT * FROM t WHERE a = {}", nest("~", "a", "", depth)),
+                format!("SELECT {} FROM t", nest("f(", "a", ")", depth)),
+                format!("SELECT {} FROM t", nest("max(", "a", ")", depth)),
+                format!(
+                    "SELECT * FROM t WHERE a IN {}",
+                    nest("(SELECT a FROM t WHERE a IN ", "(1)", ")", depth)
+                ),
+            ] {
+                let start = Instant::now();
+                let result = parse_query(q);
+                if depth == DEFAULT_MAX_DEPTH {
+                    assert!(result.is_ok(), "{}", q);
+                } else {
+                    assert_eq!(result, Err("query nested too deeply"), "{}", q);
+                }
+                assert!(start.elapsed() < Duration::from_secs(1), "{}", q);
+            }
+        }
+    }
+
+    #[test]
+    fn nested_calls_parse_quickly() {
+        // every argument is tried as a predicate, an arithmetic expression and a column, which
+        // must not parse the calls nested in it again for each
+        use std::time::{Duration, Instant};
+
+        let limits = ParserLimits {
+            max_depth: 128,
+            ..Default::default()
+        };
+        let depth = 20;
+        let calls = |call: &str, innermost: &str| {
+            (0..depth).fold(String::from(innermost), |inner, _| {
+                format!("{}{})", call, inner)
+            })
+        };
+        for q in &[
+            format!("SELECT {} FROM t", calls("f(a, ", "a")),
+            format!("SELECT * FROM t WHERE {} = 1", calls("f(a, ", "a")),
+            format!("SELECT {} FROM t", calls("coalesce(1, ", "1")),
+            format!("SELECT * FROM t WHERE {}", calls("(", "a = 1")),
+        ] {
+            let start = Instant::now();
+            assert!(parse_query_with_limits(q, &limits).is_ok(), "{}", q);
+            assert!(start.elapsed() < Duration::from_secs(1), "{}", q);
+        }
+    }
+
+    #[test]
+    fn long_chains_print() {
+        // chains are parsed in a loop, and must not be printed recursively either, nor copied
+        // recursively when a parse nested in parentheses or a call is remembered
+        let terms = 10_000;
+        for q in &[
+            format!(
+                "SELECT * FROM t WHERE {}",
+                vec!["a = 1"; terms].join(" AND ")
+            ),
+            format!(
+                "SELECT * FROM t WHERE (({}))",
+                vec!["a = 1"; terms].join(" AND ")
+            ),
+            format!("SELECT f(g({})) FROM t", vec!["a"; terms].join(" + ")),
+            format!(
+                "SELECT * FROM t WHERE {}",
+                vec!["a = 1"; terms].join(" OR ")
+            ),
+            format!("SELECT {} FROM t", vec!["a"; terms].join(" + ")),
+        ] {
+            let printed = parse_query(q).unwrap().to_string();
+            assert_eq!(&printed, q);
+        }
+    }
+
+    #[test]
+    fn schema_qualified_tables() {
+        for q in &[
+            "SELECT * FROM db.t AS a JOIN db.u ON a.id = u.id",
+            "INSERT INTO public.t (a) VALUES (1)",
+            "CREATE TABLE public.t (id INT(32) REFERENCES public.u(id))",
+            "CREATE TYPE public.mood AS ENUM ('sad')",
+            "ALTER TABLE public.t ADD COLUMN b INT(32)",
+            "UPDATE db.t SET a = 1",
+            "DELETE FROM db.t WHERE a = 1",
+            "DROP TABLE public.t",
+        ] {
+            let parsed = parse_query(q).unwrap();
+            assert_eq!(&parsed.to_string(), q);
+        }
+    }
+
+    #[test]
+    fn bit_not_round_trips() {
+        for q in &[
+            "SELECT ~a FROM t",
+            "SELECT ~a AS x FROM t",
+            "SELECT ~(a + b) FROM t",
+            "SELECT ~a + 1 FROM t",
+            "SELECT * FROM t WHERE ~flags = 0",
+            "SELECT * FROM t WHERE a = ~b",
+            "SELECT * FROM t WHERE (a | b) & ~c = 0",
+            "SELECT * FROM t WHERE (a + b) * c = 0",
+            "UPDATE t SET a = ~b",
+        ] {
+            let parsed = parse_query(q).unwrap();
+            let printed = parsed.to_string();
+            assert_eq!(&printed, q);
+            assert_eq!(parse_query(&printed).unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn out_of_range_numbers() {
+        assert!(parse_query("SELECT * FROM t WHERE a = 18446744073709551616").is_err());
+        assert!(
+            parse_query("UPDATE t SET a = 99999999999999999999999999999999999999999.5").is_err()
+        );
+        assert!(parse_query("INSERT INTO t (a) VALUES (-9223372036854775809)").is_err());
+        // digits followed by letters are still a name
+        assert_eq!(
+            parse_query("SELECT * FROM t WHERE a = 18446744073709551616x")
+                .unwrap()
+                .to_string(),
+            "SELECT * FROM t WHERE a = `18446744073709551616x`"
+        );
+    }
+
+    #[test]
+    fn sql_server_dialect() {
+        let qstring = "CREATE TABLE [Customers] ([Id] int, [Name] NVARCHAR(MAX))";
+        assert!(parse_query(qstring).is_err());
+        let res = parse_query_with_dialect(qstring, Dialect::SqlServer).unwrap();
+        assert_eq!(
+            format!("{}", res),
+            "CREATE TABLE [Customers] (Id INT(32), Name NVARCHAR(MAX))"
+        );
+        // the dialect only applies to the one call
+        assert!(parse_query_with_dialect("SELECT TOP 1 a FROM t", Dialect::SqlServer).is_ok());
+        assert!(parse_query("SELECT TOP 1 a FROM t").is_err());
+    }
+
+    #[test]
+    fn postgres_double_quoted_identifiers() {
+        let qstring = "SELECT \"user id\" AS \"a\"\"b\" FROM t WHERE \"user id\" = 'x'";
+        let res = parse_query_with_dialect(qstring, Dialect::Postgres).unwrap();
+        assert_eq!(
+            res.to_string(),
+            "SELECT `user id` AS `a\"b` FROM t WHERE `user id` = 'x'"
+        );
+        // elsewhere double quotes hold a string
+        let res = parse_query("SELECT a FROM t WHERE b = \"user id\"").unwrap();
+        assert_eq!(res.to_string(), "SELECT a FROM t WHERE b = 'user id'");
+    }
+
+    #[test]
+    fn long_condition_chains() {
+        let conjuncts = vec!["a = 1"; 2000].join(" AND ");
+        let query = parse_query(format!("SELECT * FROM t WHERE {} OR b = 2", conjuncts)).unwrap();
+        match query {
+            SqlQuery::Select(select) => match select.where_clause {
+                Some(ConditionExpression::LogicalOp(ref tree)) => {
+                    assert_eq!(tree.operator, Operator::Or)
+                }
+                ref other => panic!("unexpected condition {:?}", other),
+            },
+            other => panic!("unexpected query {:?}", other),
+        }
+    }
+
+    #[test]
+    fn interned_names() {
+        let mut interner = Interner::new();
+        let q = "SELECT users.id, users.name FROM users WHERE users.id = 1";
+        let select = match parse_query_interned(q, &mut interner).unwrap() {
+            SqlQuery::Select(select) => select,
+            other => panic!("unexpected query {:?}", other),
+        };
+        let (id, name) = match (&select.fields[0], &select.fields[1]) {
+            (FieldDefinitionExpression::Col(id), FieldDefinitionExpression::Col(name)) => {
+                (id, name)
+            }
+            other => panic!("unexpected fields {:?}", other),
+        };
+        let users = id.table.as_ref().unwrap();
+        assert!(users.ptr_eq(name.table.as_ref().unwrap()));
+        assert!(users.ptr_eq(&select.tables[0].name));
+        let interned = interner.len();
+
+        // names are shared across the statements parsed with one interner, and only with them
+        let other = parse_query_interned("DELETE FROM users", &mut interner).unwrap();
+        match other {
+            SqlQuery::Delete(ref delete) => assert!(delete.table.name.ptr_eq(users)),
+            ref other => panic!("unexpected query {:?}", other),
+        }
+        assert_eq!(other, parse_query("DELETE FROM users").unwrap());
+        assert_eq!(interner.len(), interned);
+    }
+
+    #[test]
+    fn statement_terminators() {
+        let statements = [
+            "SELECT a FROM t",
+            "SELECT a FROM t UNION SELECT b FROM u",
+            "INSERT INTO t (a) VALUES (1)",
+            "UPDATE t SET a = 1",
+            "DELETE FROM t",
+            "CREATE TABLE t (a int) ENGINE=InnoDB",
+            "DROP TABLE t",
+            "BEGIN",
+            "DO 1",
+        ];
+        for statement in statements.iter() {
+            let expected = parse_query(statement).unwrap();
+            for suffix in &[";", " ; ", " -- c", " /* c */ ; # c\n", "\n"] {
+                let q = format!("{}{}", statement, suffix);
+                assert_eq!(parse_query(&q).as_ref(), Ok(&expected), "{:?}", q);
+            }
+            // a statement stops after its terminator, leaving any following statement
+            let q = format!("{}; SELECT 1 FROM v", statement);
+            assert_eq!(sql_query(&q).map(|r| r.0), Ok(" SELECT 1 FROM v"));
+            assert_eq!(parse_query(&q), Err("failed to parse query"));
+            let q = format!("{} )", statement);
+            assert_eq!(parse_query(&q), Err("failed to parse query"), "{:?}", q);
+        }
+    }
 }
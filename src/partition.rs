@@ -0,0 +1,464 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, opt, value};
+use nom::multi::{many0, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{
+    escape_string, keyword_phrase, parenthesized_text, sql_identifier, string_literal,
+    unsigned_number, whitespace0, whitespace1, ws_sep_comma, Literal,
+};
+use keywords::{escape_if_keyword, tag_no_case};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum PartitionKind {
+    Range,
+    List,
+    Hash,
+    Key,
+}
+
+impl fmt::Display for PartitionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PartitionKind::Range => write!(f, "RANGE"),
+            PartitionKind::List => write!(f, "LIST"),
+            PartitionKind::Hash => write!(f, "HASH"),
+            PartitionKind::Key => write!(f, "KEY"),
+        }
+    }
+}
+
+/// What rows are partitioned on. Expressions are kept as written, since they are usually
+/// function calls (`YEAR(created_at)`, `TO_DAYS(d)`) that the expression parser does not cover.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum PartitionExpression {
+    Expr(String),
+    Columns(Vec<String>),
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct PartitionBy {
+    pub kind: PartitionKind,
+    pub linear: bool,
+    pub expr: PartitionExpression,
+    /// `PARTITIONS n` (or `SUBPARTITIONS n`).
+    pub count: Option<u64>,
+}
+
+impl fmt::Display for PartitionBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.linear {
+            write!(f, "LINEAR ")?;
+        }
+        write!(f, "{}", self.kind)?;
+        match self.expr {
+            PartitionExpression::Expr(ref expr) => write!(f, " ({})", expr),
+            PartitionExpression::Columns(ref columns) => {
+                let columns = columns
+                    .iter()
+                    .map(|c| escape_if_keyword(c))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if self.kind == PartitionKind::Key {
+                    write!(f, " ({})", columns)
+                } else {
+                    write!(f, " COLUMNS({})", columns)
+                }
+            }
+        }
+    }
+}
+
+/// The `VALUES` clause of a partition definition; bounds are kept as written.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum PartitionValues {
+    LessThan(String),
+    LessThanMaxValue,
+    In(String),
+}
+
+impl fmt::Display for PartitionValues {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PartitionValues::LessThan(ref bound) => write!(f, "VALUES LESS THAN ({})", bound),
+            PartitionValues::LessThanMaxValue => write!(f, "VALUES LESS THAN MAXVALUE"),
+            PartitionValues::In(ref values) => write!(f, "VALUES IN ({})", values),
+        }
+    }
+}
+
+/// A `PARTITION` or `SUBPARTITION` definition.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct PartitionDefinition {
+    pub name: String,
+    pub values: Option<PartitionValues>,
+    pub engine: Option<String>,
+    pub comment: Option<String>,
+    pub subpartitions: Vec<PartitionDefinition>,
+}
+
+impl PartitionDefinition {
+    fn fmt_with_keyword(&self, f: &mut fmt::Formatter, keyword: &str) -> fmt::Result {
+        write!(f, "{} {}", keyword, escape_if_keyword(&self.name))?;
+        if let Some(ref values) = self.values {
+            write!(f, " {}", values)?;
+        }
+        if let Some(ref engine) = self.engine {
+            write!(f, " ENGINE = {}", engine)?;
+        }
+        if let Some(ref comment) = self.comment {
+            write!(f, " COMMENT = '{}'", escape_string(comment))?;
+        }
+        if !self.subpartitions.is_empty() {
+            write!(f, " (")?;
+            for (i, sub) in self.subpartitions.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                sub.fmt_with_keyword(f, "SUBPARTITION")?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PartitionDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_keyword(f, "PARTITION")
+    }
+}
+
+/// The `PARTITION BY` clause of a `CREATE TABLE` statement.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct PartitionScheme {
+    pub partition_by: PartitionBy,
+    pub subpartition_by: Option<PartitionBy>,
+    pub partitions: Vec<PartitionDefinition>,
+}
+
+impl fmt::Display for PartitionScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PARTITION BY {}", self.partition_by)?;
+        if let Some(count) = self.partition_by.count {
+            write!(f, " PARTITIONS {}", count)?;
+        }
+        if let Some(ref sub) = self.subpartition_by {
+            write!(f, " SUBPARTITION BY {}", sub)?;
+            if let Some(count) = sub.count {
+                write!(f, " SUBPARTITIONS {}", count)?;
+            }
+        }
+        if !self.partitions.is_empty() {
+            write!(
+                f,
+                " ({})",
+                self.partitions
+                    .iter()
+                    .map(|p| format!("{}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn column_name_list(i: &str) -> IResult<&str, Vec<String>> {
+    delimited(
+        pair(tag("("), whitespace0),
+        map(separated_list0(ws_sep_comma, sql_identifier), |cols| {
+            cols.into_iter().map(String::from).collect()
+        }),
+        pair(whitespace0, tag(")")),
+    )(i)
+}
+
+fn partition_method(i: &str) -> IResult<&str, PartitionBy> {
+    let (i, linear) = opt(terminated(tag_no_case("linear"), whitespace1))(i)?;
+    let (i, (kind, expr)) = alt((
+        pair(
+            value(
+                PartitionKind::Key,
+                terminated(
+                    tag_no_case("key"),
+                    // the hash algorithm only matters to the server
+                    opt(tuple((
+                        whitespace1,
+                        tag_no_case("algorithm"),
+                        whitespace0,
+                        tag("="),
+                        whitespace0,
+                        unsigned_number,
+                    ))),
+                ),
+            ),
+            preceded(
+                whitespace0,
+                map(column_name_list, PartitionExpression::Columns),
+            ),
+        ),
+        pair(
+            value(PartitionKind::Hash, tag_no_case("hash")),
+            preceded(
+                whitespace0,
+                map(parenthesized_text, |e| {
+                    PartitionExpression::Expr(String::from(e))
+                }),
+            ),
+        ),
+        pair(
+            alt((
+                value(PartitionKind::Range, tag_no_case("range")),
+                value(PartitionKind::List, tag_no_case("list")),
+            )),
+            preceded(
+                whitespace0,
+                alt((
+                    map(
+                        preceded(pair(tag_no_case("columns"), whitespace0), column_name_list),
+                        PartitionExpression::Columns,
+                    ),
+                    map(parenthesized_text, |e| {
+                        PartitionExpression::Expr(String::from(e))
+                    }),
+                )),
+            ),
+        ),
+    ))(i)?;
+    Ok((
+        i,
+        PartitionBy {
+            kind,
+            linear: linear.is_some(),
+            expr,
+            count: None,
+        },
+    ))
+}
+
+fn partition_count<'a>(keyword: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, u64> {
+    preceded(
+        tuple((whitespace1, tag_no_case(keyword), whitespace1)),
+        unsigned_number,
+    )
+}
+
+fn partition_values(i: &str) -> IResult<&str, PartitionValues> {
+    preceded(
+        pair(tag_no_case("values"), whitespace1),
+        alt((
+            value(
+                PartitionValues::LessThanMaxValue,
+                tuple((
+                    tag_no_case("less"),
+                    whitespace1,
+                    tag_no_case("than"),
+                    whitespace1,
+                    tag_no_case("maxvalue"),
+                )),
+            ),
+            map(
+                preceded(
+                    tuple((
+                        tag_no_case("less"),
+                        whitespace1,
+                        tag_no_case("than"),
+                        whitespace0,
+                    )),
+                    parenthesized_text,
+                ),
+                |bound| PartitionValues::LessThan(String::from(bound)),
+            ),
+            map(
+                preceded(pair(tag_no_case("in"), whitespace0), parenthesized_text),
+                |values| PartitionValues::In(String::from(values)),
+            ),
+        )),
+    )(i)
+}
+
+#[derive(Clone)]
+enum DefinitionOption {
+    Engine(String),
+    Comment(String),
+}
+
+fn definition_option(i: &str) -> IResult<&str, DefinitionOption> {
+    let eq = || tuple((whitespace0, opt(tag("=")), whitespace0));
+    alt((
+        map(
+            preceded(
+                tuple((
+                    opt(pair(tag_no_case("storage"), whitespace1)),
+                    tag_no_case("engine"),
+                    eq(),
+                )),
+                sql_identifier,
+            ),
+            |engine| DefinitionOption::Engine(String::from(engine)),
+        ),
+        map(
+            preceded(pair(tag_no_case("comment"), eq()), string_literal),
+            |comment| match comment {
+                Literal::String(s) => DefinitionOption::Comment(s),
+                other => DefinitionOption::Comment(format!("{}", other)),
+            },
+        ),
+    ))(i)
+}
+
+fn definition<'a>(
+    keyword: &'static str,
+    nested: bool,
+) -> impl FnMut(&'a str) -> IResult<&'a str, PartitionDefinition> {
+    move |i: &'a str| {
+        let (i, (_, _, name, values, options)) = tuple((
+            tag_no_case(keyword),
+            whitespace1,
+            sql_identifier,
+            opt(preceded(whitespace1, partition_values)),
+            many0(preceded(whitespace1, definition_option)),
+        ))(i)?;
+        let (i, subpartitions) = if nested {
+            opt(preceded(
+                whitespace0,
+                definition_list("subpartition", false),
+            ))(i)?
+        } else {
+            (i, None)
+        };
+
+        let mut def = PartitionDefinition {
+            name: String::from(name),
+            values,
+            subpartitions: subpartitions.unwrap_or_default(),
+            ..Default::default()
+        };
+        for option in options {
+            match option {
+                DefinitionOption::Engine(e) => def.engine = Some(e),
+                DefinitionOption::Comment(c) => def.comment = Some(c),
+            }
+        }
+        Ok((i, def))
+    }
+}
+
+fn definition_list<'a>(
+    keyword: &'static str,
+    nested: bool,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<PartitionDefinition>> {
+    delimited(
+        pair(tag("("), whitespace0),
+        separated_list1(ws_sep_comma, definition(keyword, nested)),
+        pair(whitespace0, tag(")")),
+    )
+}
+
+/// Parses a `PARTITION BY` clause, including subpartitioning and partition definitions.
+pub fn partition_scheme(i: &str) -> IResult<&str, PartitionScheme> {
+    let (remaining_input, (_, _, mut partition_by, count, subpartition_by, partitions)) = tuple((
+        keyword_phrase(&["partition", "by"]),
+        whitespace1,
+        partition_method,
+        opt(partition_count("partitions")),
+        opt(map(
+            preceded(
+                tuple((
+                    whitespace1,
+                    keyword_phrase(&["subpartition", "by"]),
+                    whitespace1,
+                )),
+                pair(partition_method, opt(partition_count("subpartitions"))),
+            ),
+            |(mut sub, count)| {
+                sub.count = count;
+                sub
+            },
+        )),
+        opt(preceded(whitespace0, definition_list("partition", true))),
+    ))(i)?;
+
+    partition_by.count = count;
+    Ok((
+        remaining_input,
+        PartitionScheme {
+            partition_by,
+            subpartition_by,
+            partitions: partitions.unwrap_or_default(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_partitions() {
+        let qstring = "PARTITION BY RANGE (YEAR(created_at)) (\
+                       PARTITION p2019 VALUES LESS THAN (2020) ENGINE = InnoDB, \
+                       PARTITION pmax VALUES LESS THAN MAXVALUE COMMENT 'catch-all')";
+        let res = partition_scheme(qstring).unwrap().1;
+        assert_eq!(
+            res,
+            PartitionScheme {
+                partition_by: PartitionBy {
+                    kind: PartitionKind::Range,
+                    linear: false,
+                    expr: PartitionExpression::Expr(String::from("YEAR(created_at)")),
+                    count: None,
+                },
+                subpartition_by: None,
+                partitions: vec![
+                    PartitionDefinition {
+                        name: String::from("p2019"),
+                        values: Some(PartitionValues::LessThan(String::from("2020"))),
+                        engine: Some(String::from("InnoDB")),
+                        ..Default::default()
+                    },
+                    PartitionDefinition {
+                        name: String::from("pmax"),
+                        values: Some(PartitionValues::LessThanMaxValue),
+                        comment: Some(String::from("catch-all")),
+                        ..Default::default()
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn list_columns_with_subpartitions() {
+        let qstring = "partition by list columns(region) subpartition by linear hash (id) \
+                       subpartitions 2 (partition p_eu values in ('de', 'fr') \
+                       (subpartition s0, subpartition s1))";
+        let res = partition_scheme(qstring).unwrap().1;
+        assert_eq!(
+            res.partition_by.expr,
+            PartitionExpression::Columns(vec![String::from("region")])
+        );
+        let sub = res.subpartition_by.as_ref().unwrap();
+        assert!(sub.linear);
+        assert_eq!(sub.count, Some(2));
+        assert_eq!(res.partitions[0].subpartitions.len(), 2);
+        assert_eq!(
+            format!("{}", res),
+            "PARTITION BY LIST COLUMNS(region) SUBPARTITION BY LINEAR HASH (id) SUBPARTITIONS 2 \
+             (PARTITION p_eu VALUES IN ('de', 'fr') (SUBPARTITION s0, SUBPARTITION s1))"
+        );
+    }
+
+    #[test]
+    fn key_partitions() {
+        let qstring = "PARTITION BY KEY ALGORITHM=2 (id) PARTITIONS 8";
+        let res = partition_scheme(qstring).unwrap().1;
+        assert_eq!(res.partition_by.kind, PartitionKind::Key);
+        assert_eq!(res.partition_by.count, Some(8));
+        assert_eq!(format!("{}", res), "PARTITION BY KEY (id) PARTITIONS 8");
+    }
+}
@@ -0,0 +1,768 @@
+use std::fmt;
+use std::mem;
+use std::ops::Range;
+
+use arithmetic::{ArithmeticBase, ArithmeticExpression};
+use column::{Column, FunctionExpression};
+use common::{FieldDefinitionExpression, FieldValueExpression, Literal, Operator, SqlType};
+use condition::{ConditionBase, ConditionExpression};
+use create::{CreateTableStatement, SelectSpecification};
+use ident::Ident;
+use join::{JoinConstraint, JoinRightSide};
+use lexer::{tokenize, LexError, TokenKind, Tokens};
+use parser::SqlQuery;
+use select::{JoinClause, SelectStatement};
+use table::Table;
+use values::ValuesStatement;
+
+/// Where a placeholder is used, as far as it tells what value should be bound to it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum PlaceholderContext {
+    /// Compared against a column, as in `id = ?` or `id IN (?, ?)`.
+    Condition(Column, Operator),
+    /// Inserted into or assigned to a column.
+    Value(Column),
+    /// Anywhere else, e.g. in arithmetic or an array subscript.
+    Other,
+}
+
+/// A `?` placeholder of a statement.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Placeholder {
+    /// The one-based number of the parameter bound to the placeholder, i.e. its position among
+    /// the statement's placeholders in textual order. This is the same numbering as `$1` and as
+    /// `PlaceholderSpan::index`.
+    pub index: usize,
+    pub context: PlaceholderContext,
+}
+
+impl Placeholder {
+    /// The column this placeholder is compared against or assigned to, if any. Its table is
+    /// filled in when the statement reads from or writes to a single table.
+    pub fn column(&self) -> Option<&Column> {
+        match self.context {
+            PlaceholderContext::Condition(ref col, _) | PlaceholderContext::Value(ref col) => {
+                Some(col)
+            }
+            PlaceholderContext::Other => None,
+        }
+    }
+
+    /// The type of the column this placeholder is bound to, looked up in `schema`.
+    pub fn sql_type<'a>(&self, schema: &'a [CreateTableStatement]) -> Option<&'a SqlType> {
+        let col = self.column()?;
+        schema
+            .iter()
+            .filter(|t| col.table.is_none() || col.table.as_ref() == Some(&t.table.name))
+            .flat_map(|t| t.fields.iter())
+            .find(|f| f.column.name == col.name)
+            .and_then(|f| f.sql_type.as_ref())
+    }
+}
+
+/// Error returned by `bind` when the number of parameters does not match the number of
+/// placeholders.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BindError {
+    pub expected: usize,
+    pub given: usize,
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "statement has {} placeholders but {} parameters were given",
+            self.expected, self.given
+        )
+    }
+}
+
+impl ::std::error::Error for BindError {}
+
+type Visitor<'v> = dyn FnMut(&Literal, &dyn Fn() -> PlaceholderContext) + 'v;
+type VisitorMut<'v> = dyn FnMut(&mut Literal, &dyn Fn() -> PlaceholderContext) + 'v;
+
+/// Defines a walker over the literals of a statement, in textual order, that calls `visit` on each
+/// placeholder. `placeholders` only reads the statement and `bind` replaces its placeholders, so
+/// the walker is written once for shared and once for mutable references.
+macro_rules! walker {
+    (
+        $walker:ident,
+        $visitor:ident,
+        $iter:ident,
+        $operands:ident,
+        $all_arguments:ident,
+        $as_deref:ident,
+        $as_ref:ident
+        $(, $mut:tt)?
+    ) => {
+        struct $walker<'v> {
+            /// The table that unqualified columns belong to, when there is only one.
+            table: Option<Ident>,
+            visit: &'v mut $visitor<'v>,
+        }
+
+        impl<'v> $walker<'v> {
+            fn column(&self, col: &Column) -> Column {
+                let mut col = col.clone();
+                if col.table.is_none() {
+                    col.table = self.table.clone();
+                }
+                col.alias = None;
+                col
+            }
+
+            fn literal(
+                &mut self,
+                lit: &$($mut)* Literal,
+                context: &dyn Fn() -> PlaceholderContext,
+            ) {
+                match *lit {
+                    Literal::Placeholder => (self.visit)(lit, context),
+                    Literal::Array(ref $($mut)* elements) => {
+                        for element in elements {
+                            self.literal(element, context);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+
+            fn other(&mut self, lit: &$($mut)* Literal) {
+                self.literal(lit, &|| PlaceholderContext::Other)
+            }
+
+            /// Visits the arguments of the function call computing `col`, if any.
+            fn function(&mut self, col: &$($mut)* Column) {
+                if let Some(&$($mut)* FunctionExpression::Call(_, ref $($mut)* args)) =
+                    col.function.$as_deref()
+                {
+                    for arg in args {
+                        self.operand(arg, None);
+                    }
+                }
+            }
+
+            fn arithmetic(&mut self, expr: &$($mut)* ArithmeticExpression) {
+                self.operands(expr.$operands());
+            }
+
+            fn operands(&mut self, operands: Vec<&$($mut)* ArithmeticBase>) {
+                for base in operands {
+                    match *base {
+                        ArithmeticBase::Scalar(ref $($mut)* lit) => self.other(lit),
+                        ArithmeticBase::Column(ref $($mut)* col) => self.function(col),
+                        _ => (),
+                    }
+                }
+            }
+
+            fn value(&mut self, value: &$($mut)* FieldValueExpression, target: Option<&Column>) {
+                match *value {
+                    FieldValueExpression::Literal(ref $($mut)* lit) => match target {
+                        Some(col) => {
+                            let col = self.column(col);
+                            let context = || PlaceholderContext::Value(col.clone());
+                            self.literal(&$($mut)* lit.value, &context)
+                        }
+                        None => self.other(&$($mut)* lit.value),
+                    },
+                    FieldValueExpression::Arithmetic(ref $($mut)* expr) => self.arithmetic(expr),
+                    FieldValueExpression::Variable(ref $($mut)* var) => {
+                        if let Some(ref $($mut)* value) = var.value {
+                            self.field(value);
+                        }
+                    }
+                    FieldValueExpression::Json(ref $($mut)* json) => {
+                        for &$($mut)* (_, ref $($mut)* lit) in &$($mut)* json.path {
+                            self.other(lit);
+                        }
+                    }
+                    FieldValueExpression::Subscript(ref $($mut)* sub) => {
+                        for lit in &$($mut)* sub.indices {
+                            self.other(lit);
+                        }
+                    }
+                    FieldValueExpression::Expression(ref $($mut)* expr) => self.condition(expr),
+                    FieldValueExpression::Predicate(ref $($mut)* pred) => {
+                        self.condition(&$($mut)* pred.condition)
+                    }
+                }
+            }
+
+            fn field(&mut self, field: &$($mut)* FieldDefinitionExpression) {
+                match *field {
+                    FieldDefinitionExpression::Col(ref $($mut)* col) => self.function(col),
+                    FieldDefinitionExpression::Value(ref $($mut)* value) => self.value(value, None),
+                    _ => (),
+                }
+            }
+
+            /// Visits a condition operand, where `compared` is the column on the other side of the
+            /// comparison it is part of.
+            fn operand(
+                &mut self,
+                cond: &$($mut)* ConditionExpression,
+                compared: Option<(Column, &Operator)>,
+            ) {
+                let context = || match compared {
+                    Some((ref col, op)) => PlaceholderContext::Condition(col.clone(), op.clone()),
+                    None => PlaceholderContext::Other,
+                };
+                match *cond {
+                    ConditionExpression::Base(ConditionBase::Literal(ref $($mut)* lit)) => {
+                        self.literal(lit, &context)
+                    }
+                    ConditionExpression::Base(ConditionBase::LiteralList(ref $($mut)* lits)) => {
+                        for lit in lits {
+                            self.literal(lit, &context);
+                        }
+                    }
+                    ConditionExpression::Base(ConditionBase::ExpressionList(ref $($mut)* exprs)) => {
+                        for expr in exprs {
+                            self.operand(expr, compared.clone());
+                        }
+                    }
+                    ConditionExpression::Base(ConditionBase::Escaped(ref $($mut)* pattern, _)) => {
+                        self.operand(pattern, compared)
+                    }
+                    _ => self.condition(cond),
+                }
+            }
+
+            fn column_of(&self, cond: &ConditionExpression) -> Option<Column> {
+                match *cond {
+                    ConditionExpression::Base(ConditionBase::Field(ref col)) => {
+                        Some(self.column(col))
+                    }
+                    _ => None,
+                }
+            }
+
+            /// Visits both sides of a comparison. Rows are compared element by element, as in
+            /// `(a, b) > (?, ?)` or `(a, b) IN ((?, ?), (?, ?))`.
+            fn comparison(
+                &mut self,
+                left: &$($mut)* ConditionExpression,
+                right: &$($mut)* ConditionExpression,
+                op: &Operator,
+            ) {
+                if let (
+                    &$($mut)* ConditionExpression::Base(ConditionBase::Row(ref $($mut)* lefts, _)),
+                    &$($mut)* ConditionExpression::Base(ConditionBase::Row(ref $($mut)* rights, _)),
+                ) = (&$($mut)* *left, &$($mut)* *right)
+                {
+                    if *op == Operator::In || *op == Operator::NotIn {
+                        let columns: Vec<_> = lefts.iter().map(|l| self.column_of(l)).collect();
+                        for l in lefts.$iter() {
+                            self.operand(l, None);
+                        }
+                        for row in rights.$iter() {
+                            match *row {
+                                ConditionExpression::Base(ConditionBase::Row(ref $($mut)* values, _))
+                                    if values.len() == columns.len() =>
+                                {
+                                    for (value, column) in values.$iter().zip(&columns) {
+                                        self.operand(value, column.clone().map(|c| (c, op)));
+                                    }
+                                }
+                                ref $($mut)* other => self.operand(other, None),
+                            }
+                        }
+                        return;
+                    }
+                    if lefts.len() == rights.len() {
+                        for (l, r) in lefts.$iter().zip(rights.$iter()) {
+                            self.comparison(l, r, op);
+                        }
+                        return;
+                    }
+                }
+                let left_column = self.column_of(left);
+                let right_column = self.column_of(right);
+                self.operand(left, right_column.map(|c| (c, op)));
+                self.operand(right, left_column.map(|c| (c, op)));
+            }
+
+            fn condition(&mut self, cond: &$($mut)* ConditionExpression) {
+                match *cond {
+                    ConditionExpression::ComparisonOp(ref $($mut)* tree) => {
+                        self.comparison(&$($mut)* tree.left, &$($mut)* tree.right, &tree.operator)
+                    }
+                    ConditionExpression::LogicalOp(ref $($mut)* tree) => {
+                        self.condition(&$($mut)* tree.left);
+                        self.condition(&$($mut)* tree.right);
+                    }
+                    ConditionExpression::NegationOp(ref $($mut)* expr)
+                    | ConditionExpression::Bracketed(ref $($mut)* expr)
+                    | ConditionExpression::Base(ConditionBase::Any(ref $($mut)* expr))
+                    | ConditionExpression::Base(ConditionBase::Escaped(ref $($mut)* expr, _)) => {
+                        self.operand(expr, None)
+                    }
+                    ConditionExpression::Arithmetic(ref $($mut)* expr) => self.arithmetic(expr),
+                    ConditionExpression::Base(ConditionBase::BitNot(ref $($mut)* operand)) => {
+                        let mut operands = vec![];
+                        operand.$operands(&mut operands);
+                        self.operands(operands);
+                    }
+                    ConditionExpression::Base(ConditionBase::NestedSelect(ref $($mut)* select)) => {
+                        let outer = self.table.take();
+                        self.select(select);
+                        self.table = outer;
+                    }
+                    ConditionExpression::Base(ConditionBase::Literal(ref $($mut)* lit)) => {
+                        self.other(lit)
+                    }
+                    ConditionExpression::Base(ConditionBase::LiteralList(ref $($mut)* lits)) => {
+                        for lit in lits {
+                            self.other(lit);
+                        }
+                    }
+                    ConditionExpression::Base(ConditionBase::Json(ref $($mut)* json)) => {
+                        for &$($mut)* (_, ref $($mut)* lit) in &$($mut)* json.path {
+                            self.other(lit);
+                        }
+                    }
+                    ConditionExpression::Base(ConditionBase::Subscript(ref $($mut)* sub)) => {
+                        for lit in &$($mut)* sub.indices {
+                            self.other(lit);
+                        }
+                    }
+                    ConditionExpression::Base(ConditionBase::Row(ref $($mut)* exprs, _))
+                    | ConditionExpression::Base(ConditionBase::ExpressionList(ref $($mut)* exprs)) => {
+                        for expr in exprs {
+                            self.operand(expr, None);
+                        }
+                    }
+                    ConditionExpression::Base(ConditionBase::Field(ref $($mut)* col)) => {
+                        self.function(col)
+                    }
+                    ConditionExpression::Base(_) => (),
+                }
+            }
+
+            fn join(&mut self, join: &$($mut)* JoinClause) {
+                match join.right {
+                    JoinRightSide::NestedSelect(ref $($mut)* select, _) => self.select(select),
+                    JoinRightSide::NestedJoin(ref $($mut)* nested) => self.join(nested),
+                    _ => (),
+                }
+                if let JoinConstraint::On(ref $($mut)* cond) = join.constraint {
+                    self.condition(cond);
+                }
+            }
+
+            fn select(&mut self, select: &$($mut)* SelectStatement) {
+                let table = match (select.tables.as_slice(), select.join.is_empty()) {
+                    ([ref table], true) if table.is_stored() => Some(table.name.clone()),
+                    _ => None,
+                };
+                let outer = mem::replace(&mut self.table, table);
+                for field in &$($mut)* select.fields {
+                    self.field(field);
+                }
+                for table in &$($mut)* select.tables {
+                    self.table_function(table);
+                }
+                for join in &$($mut)* select.join {
+                    self.join(join);
+                }
+                if let Some(ref $($mut)* cond) = select.where_clause {
+                    self.condition(cond);
+                }
+                let having = select.group_by.$as_ref().and_then(|g| g.having.$as_ref());
+                if let Some(cond) = having {
+                    self.condition(cond);
+                }
+                self.table = outer;
+            }
+
+            fn table_function(&mut self, table: &$($mut)* Table) {
+                if let Some(ref $($mut)* function) = table.function {
+                    for arg in function.$all_arguments() {
+                        if let ArithmeticBase::Scalar(ref $($mut)* lit) = *arg {
+                            self.other(lit);
+                        }
+                    }
+                }
+                if let Some(ref $($mut)* values) = table.values {
+                    self.values(&$($mut)* values.values);
+                }
+                if let Some(ref $($mut)* subquery) = table.subquery {
+                    self.select(subquery);
+                }
+            }
+
+            fn values(&mut self, values: &$($mut)* ValuesStatement) {
+                for value in values.rows.$iter().flatten() {
+                    self.value(value, None);
+                }
+            }
+
+            fn specification(&mut self, spec: &$($mut)* SelectSpecification) {
+                match *spec {
+                    SelectSpecification::Simple(ref $($mut)* select) => self.select(select),
+                    SelectSpecification::Compound(ref $($mut)* compound) => {
+                        for (_, ref $($mut)* select) in &$($mut)* compound.selects {
+                            self.select(select);
+                        }
+                    }
+                }
+            }
+
+            fn query(&mut self, query: &$($mut)* SqlQuery) {
+                match *query {
+                    SqlQuery::Select(ref $($mut)* select) => self.select(select),
+                    SqlQuery::CompoundSelect(ref $($mut)* compound) => {
+                        for (_, ref $($mut)* select) in &$($mut)* compound.selects {
+                            self.select(select);
+                        }
+                    }
+                    SqlQuery::Insert(ref $($mut)* insert) => {
+                        self.table = Some(insert.table.name.clone());
+                        for row in &$($mut)* insert.data {
+                            for (i, value) in row.$iter().enumerate() {
+                                let col = insert.fields.as_ref().and_then(|f| f.get(i));
+                                self.value(value, col);
+                            }
+                        }
+                        if let Some(ref $($mut)* on_duplicate) = insert.on_duplicate {
+                            for &$($mut)* (ref col, ref $($mut)* value) in on_duplicate {
+                                self.value(value, Some(col));
+                            }
+                        }
+                    }
+                    SqlQuery::Update(ref $($mut)* update) => {
+                        self.table = Some(update.table.name.clone());
+                        for &$($mut)* (ref col, ref $($mut)* value) in &$($mut)* update.fields {
+                            self.value(value, Some(col));
+                        }
+                        if let Some(ref $($mut)* cond) = update.where_clause {
+                            self.condition(cond);
+                        }
+                    }
+                    SqlQuery::Delete(ref $($mut)* delete) => {
+                        self.table = Some(delete.table.name.clone());
+                        if let Some(ref $($mut)* cond) = delete.where_clause {
+                            self.condition(cond);
+                        }
+                    }
+                    SqlQuery::Values(ref $($mut)* values) => self.values(values),
+                    SqlQuery::With(ref $($mut)* with) => {
+                        for cte in &$($mut)* with.ctes {
+                            self.specification(&$($mut)* cte.query);
+                        }
+                        self.specification(&$($mut)* *with.query);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    };
+}
+
+walker!(
+    Walker,
+    Visitor,
+    iter,
+    operands,
+    all_arguments,
+    as_deref,
+    as_ref
+);
+walker!(
+    WalkerMut,
+    VisitorMut,
+    iter_mut,
+    operands_mut,
+    all_arguments_mut,
+    as_deref_mut,
+    as_mut,
+    mut
+);
+
+/// Lists the `?` placeholders of a select, compound select, insert, update, delete or `VALUES`,
+/// in the order in which they are bound.
+pub fn placeholders(query: &SqlQuery) -> Vec<Placeholder> {
+    let mut found = Vec::new();
+    {
+        let mut visit = |_: &Literal, context: &dyn Fn() -> PlaceholderContext| {
+            found.push(Placeholder {
+                index: found.len() + 1,
+                context: context(),
+            })
+        };
+        Walker {
+            table: None,
+            visit: &mut visit,
+        }
+        .query(query);
+    }
+    found
+}
+
+/// Returns a copy of `query` with its placeholders replaced by `params`, in order.
+pub fn bind(query: &SqlQuery, params: &[Literal]) -> Result<SqlQuery, BindError> {
+    let mut bound = query.clone();
+    let mut count = 0;
+    {
+        let mut visit = |lit: &mut Literal, _: &dyn Fn() -> PlaceholderContext| {
+            if let Some(param) = params.get(count) {
+                *lit = param.clone();
+            }
+            count += 1;
+        };
+        WalkerMut {
+            table: None,
+            visit: &mut visit,
+        }
+        .query(&mut bound);
+    }
+    if count != params.len() {
+        return Err(BindError {
+            expected: count,
+            given: params.len(),
+        });
+    }
+    Ok(bound)
+}
+
+/// How a placeholder is written.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PlaceholderSyntax {
+    /// `?`
+    Positional,
+    /// `$1`
+    Numbered,
+    /// `:name`
+    Named,
+}
+
+/// A placeholder in SQL text, as found by `placeholder_spans`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PlaceholderSpan<'a> {
+    /// The one-based number of the parameter bound to the placeholder, as in `Placeholder::index`.
+    /// For `?` this is its
+    /// position among the `?` placeholders, for `$n` it is `n`, and for `:name` it is the position
+    /// of the name's first use among the distinct names, so that repeated names share a parameter.
+    pub index: usize,
+    pub syntax: PlaceholderSyntax,
+    pub text: &'a str,
+    /// The byte range of the placeholder in the text.
+    pub span: Range<usize>,
+}
+
+/// Iterator over the placeholders of a SQL text, produced by `placeholder_spans`.
+pub struct PlaceholderSpans<'a> {
+    tokens: Tokens<'a>,
+    positional: usize,
+    names: Vec<&'a str>,
+}
+
+/// Finds the placeholders of `sql` in textual order, which for a parsed statement is also the
+/// order of `placeholders`. Placeholders in string literals and comments are skipped. Only `?`
+/// is accepted by the parser; `$1` and `:name` are found too, for drivers that rewrite them.
+///
+/// Like `tokenize`, the iterator ends after an unterminated literal or comment, which it reports.
+pub fn placeholder_spans(sql: &str) -> PlaceholderSpans<'_> {
+    PlaceholderSpans {
+        tokens: tokenize(sql),
+        positional: 0,
+        names: Vec::new(),
+    }
+}
+
+impl<'a> Iterator for PlaceholderSpans<'a> {
+    type Item = Result<PlaceholderSpan<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let token = match self.tokens.next()? {
+                Ok(token) => token,
+                Err(e) => return Some(Err(e)),
+            };
+            if token.kind != TokenKind::Placeholder {
+                continue;
+            }
+            let (index, syntax) = if token.text.starts_with('$') {
+                let number = token.text[1..].parse().unwrap_or(0);
+                (number, PlaceholderSyntax::Numbered)
+            } else if token.text.starts_with(':') {
+                let name = &token.text[1..];
+                let position = match self.names.iter().position(|&n| n == name) {
+                    Some(position) => position,
+                    None => {
+                        self.names.push(name);
+                        self.names.len() - 1
+                    }
+                };
+                (position + 1, PlaceholderSyntax::Named)
+            } else {
+                self.positional += 1;
+                (self.positional, PlaceholderSyntax::Positional)
+            };
+            return Some(Ok(PlaceholderSpan {
+                index,
+                syntax,
+                text: token.text,
+                span: token.span(),
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+
+    #[test]
+    fn placeholder_contexts() {
+        let q = parse_query("SELECT id, ? FROM users WHERE age > ? AND id IN (?, ?)").unwrap();
+        let found = placeholders(&q);
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[0].context, PlaceholderContext::Other);
+        assert_eq!(
+            found[1].context,
+            PlaceholderContext::Condition(Column::from("users.age"), Operator::Greater)
+        );
+        assert_eq!(found[3].index, 4);
+        assert_eq!(found[3].column(), Some(&Column::from("users.id")));
+
+        let schema = match parse_query("CREATE TABLE users (id int, age tinyint)").unwrap() {
+            SqlQuery::CreateTable(table) => vec![table],
+            _ => unreachable!(),
+        };
+        let q = parse_query("INSERT INTO users (id, age) VALUES (?, ?)").unwrap();
+        let found = placeholders(&q);
+        assert_eq!(
+            found[0].context,
+            PlaceholderContext::Value(Column::from("users.id"))
+        );
+        assert_eq!(found[1].sql_type(&schema), Some(&SqlType::Tinyint(1)));
+
+        let q = parse_query(
+            "SELECT * FROM posts WHERE (created, id) < (?, ?) AND (a, b) IN ((?, ?), (1, ?))",
+        )
+        .unwrap();
+        let columns: Vec<_> = placeholders(&q)
+            .iter()
+            .map(|p| p.column().map(|c| c.name.to_string()))
+            .collect();
+        let expected = ["created", "id", "a", "b", "b"];
+        assert_eq!(
+            columns,
+            expected
+                .iter()
+                .map(|&c| Some(String::from(c)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn placeholders_in_expressions() {
+        let q = parse_query(
+            "SELECT coalesce(name, ?) FROM users WHERE id IN (?, now(), coalesce(?, 0)) \
+             AND lower(email) = lower(?)",
+        )
+        .unwrap();
+        let found = placeholders(&q);
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[0].context, PlaceholderContext::Other);
+        assert_eq!(
+            found[1].context,
+            PlaceholderContext::Condition(Column::from("users.id"), Operator::In)
+        );
+        assert_eq!(found[2].context, PlaceholderContext::Other);
+        assert_eq!(found[3].index, 4);
+
+        let q = parse_query("INSERT INTO users (id, created) VALUES (?, now()), (-1, ?)").unwrap();
+        let bound = bind(&q, &[Literal::from(1), Literal::CurrentTimestamp]).unwrap();
+        assert_eq!(
+            bound.to_string(),
+            "INSERT INTO users (id, created) VALUES (1, now()), (-1, CURRENT_TIMESTAMP)"
+        );
+    }
+
+    #[test]
+    fn placeholders_in_common_table_expressions() {
+        let q = parse_query(
+            "WITH t AS (SELECT id FROM users WHERE age > ?) SELECT id FROM t WHERE id = ?",
+        )
+        .unwrap();
+        let found = placeholders(&q);
+        assert_eq!(
+            found[0].context,
+            PlaceholderContext::Condition(Column::from("users.age"), Operator::Greater)
+        );
+        assert_eq!(found[1].column(), Some(&Column::from("t.id")));
+        let bound = bind(&q, &[Literal::from(18), Literal::from(1)]).unwrap();
+        assert_eq!(
+            bound.to_string(),
+            "WITH t AS (SELECT id FROM users WHERE age > 18) SELECT id FROM t WHERE id = 1"
+        );
+    }
+
+    #[test]
+    fn bind_parameters() {
+        let q = parse_query("UPDATE users SET name = ? WHERE id = ?").unwrap();
+        let bound = bind(&q, &[Literal::from(String::from("bob")), Literal::from(7)]).unwrap();
+        assert_eq!(
+            bound.to_string(),
+            "UPDATE users SET name = 'bob' WHERE id = 7"
+        );
+        assert!(placeholders(&bound).is_empty());
+        assert_eq!(
+            bind(&q, &[Literal::from(1)]),
+            Err(BindError {
+                expected: 2,
+                given: 1
+            })
+        );
+    }
+
+    #[test]
+    fn placeholder_spans_in_text() {
+        let sql = "SELECT ? FROM t WHERE a = '?' AND b IN (?, ?) AND c > ?";
+        let found: Vec<_> = placeholder_spans(sql).map(Result::unwrap).collect();
+        assert_eq!(
+            found
+                .iter()
+                .map(|p| (p.index, p.span.start))
+                .collect::<Vec<_>>(),
+            vec![(1, 7), (2, 40), (3, 43), (4, 54)]
+        );
+        assert!(found.iter().all(|p| &sql[p.span.clone()] == "?"));
+        let q = parse_query(sql).unwrap();
+        assert_eq!(
+            placeholders(&q).iter().map(|p| p.index).collect::<Vec<_>>(),
+            found.iter().map(|p| p.index).collect::<Vec<_>>()
+        );
+
+        let sql = "UPDATE t SET a = :x, b = $2 -- ?\n WHERE c = :y OR d = :x";
+        let found: Vec<_> = placeholder_spans(sql)
+            .map(|p| p.map(|p| (p.index, p.syntax, p.text)))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                Ok((1, PlaceholderSyntax::Named, ":x")),
+                Ok((2, PlaceholderSyntax::Numbered, "$2")),
+                Ok((2, PlaceholderSyntax::Named, ":y")),
+                Ok((1, PlaceholderSyntax::Named, ":x")),
+            ]
+        );
+
+        let mut unterminated = placeholder_spans("SELECT ? FROM t WHERE a = 'x");
+        assert_eq!(unterminated.next().map(|p| p.unwrap().index), Some(1));
+        assert_eq!(
+            unterminated.next(),
+            Some(Err(LexError {
+                offset: 26,
+                expected: "'"
+            }))
+        );
+        assert_eq!(unterminated.next(), None);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use arithmetic::{ArithmeticBase, ArithmeticExpression};
+use column::{Column, FunctionExpression};
+use condition::{ConditionBase, ConditionExpression};
+use select::SelectStatement;
+use simplify::{and_conditions, conjuncts};
+
+/// The conjuncts of a condition, grouped by what they need to be evaluated.
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct PredicateSplit {
+    /// Conjuncts that only read columns of one table, keyed by the name (or alias) the columns
+    /// are qualified with. These can be evaluated where that table lives.
+    pub per_table: BTreeMap<String, Vec<ConditionExpression>>,
+    /// Conjuncts relating columns of several tables.
+    pub join_predicates: Vec<ConditionExpression>,
+    /// The remaining conjuncts: those with subqueries, without columns, or with unqualified
+    /// columns that cannot be attributed to a table.
+    pub residual: Option<ConditionExpression>,
+}
+
+/// Collects the tables referenced by a condition, or returns `None` if one of them is unknown
+/// or the condition contains a subquery.
+struct TableCollector<'a> {
+    default_table: Option<&'a str>,
+    tables: BTreeSet<String>,
+}
+
+impl<'a> TableCollector<'a> {
+    fn column(&mut self, col: &Column) -> Option<()> {
+        if let Some(ref function) = col.function {
+            if let FunctionExpression::Call(_, ref args) = **function {
+                return args.iter().try_for_each(|a| self.condition(a));
+            }
+            return match function.aggregated_column() {
+                Some(arg) => self.column(arg),
+                None => Some(()),
+            };
+        }
+        let table = col.table.as_deref().or(self.default_table)?;
+        self.tables.insert(String::from(table));
+        Some(())
+    }
+
+    fn arithmetic_base(&mut self, base: &ArithmeticBase) -> Option<()> {
+        let mut operands = vec![];
+        base.operands(&mut operands);
+        for base in operands {
+            if let ArithmeticBase::Column(ref col) = *base {
+                self.column(col)?;
+            }
+        }
+        Some(())
+    }
+
+    fn arithmetic(&mut self, expr: &ArithmeticExpression) -> Option<()> {
+        self.arithmetic_base(&expr.left)?;
+        self.arithmetic_base(&expr.right)
+    }
+
+    fn condition(&mut self, cond: &ConditionExpression) -> Option<()> {
+        match *cond {
+            ConditionExpression::ComparisonOp(ref tree)
+            | ConditionExpression::LogicalOp(ref tree) => {
+                self.condition(&tree.left)?;
+                self.condition(&tree.right)
+            }
+            ConditionExpression::NegationOp(ref expr)
+            | ConditionExpression::Bracketed(ref expr)
+            | ConditionExpression::Base(ConditionBase::Any(ref expr))
+            | ConditionExpression::Base(ConditionBase::Escaped(ref expr, _)) => {
+                self.condition(expr)
+            }
+            ConditionExpression::Arithmetic(ref expr) => self.arithmetic(expr),
+            ConditionExpression::Base(ConditionBase::BitNot(ref operand)) => {
+                self.arithmetic_base(operand)
+            }
+            ConditionExpression::Base(ConditionBase::Field(ref col)) => self.column(col),
+            ConditionExpression::Base(ConditionBase::Json(ref json)) => self.column(&json.column),
+            ConditionExpression::Base(ConditionBase::Subscript(ref sub)) => {
+                self.column(&sub.column)
+            }
+            ConditionExpression::Base(ConditionBase::Row(ref exprs, _))
+            | ConditionExpression::Base(ConditionBase::ExpressionList(ref exprs)) => {
+                exprs.iter().try_for_each(|e| self.condition(e))
+            }
+            ConditionExpression::Base(ConditionBase::NestedSelect(_)) => None,
+            ConditionExpression::Base(ConditionBase::Literal(_))
+            | ConditionExpression::Base(ConditionBase::LiteralList(_))
+            | ConditionExpression::Base(ConditionBase::Variable(_)) => Some(()),
+        }
+    }
+}
+
+/// Splits the top-level `AND` chain of `cond` into per-table predicates, join predicates and a
+/// residual. Unqualified columns are attributed to `default_table` if given. Conditions with
+/// `OR`s spanning tables can be converted with `conjunctive_normal_form` first to expose more
+/// per-table predicates.
+pub fn split_predicates(cond: &ConditionExpression, default_table: Option<&str>) -> PredicateSplit {
+    let mut split = PredicateSplit::default();
+    let mut residual = Vec::new();
+    for conjunct in conjuncts(cond) {
+        let mut collector = TableCollector {
+            default_table,
+            tables: BTreeSet::new(),
+        };
+        let conjunct = match *conjunct {
+            ConditionExpression::Bracketed(ref inner) => inner.as_ref(),
+            _ => conjunct,
+        };
+        match collector.condition(conjunct).map(|_| collector.tables) {
+            Some(ref tables) if tables.len() == 1 => split
+                .per_table
+                .entry(tables.iter().next().cloned().unwrap_or_default())
+                .or_default()
+                .push(conjunct.clone()),
+            Some(ref tables) if tables.len() > 1 => split.join_predicates.push(conjunct.clone()),
+            _ => residual.push(conjunct.clone()),
+        }
+    }
+    split.residual = and_conditions(residual);
+    split
+}
+
+/// Splits the `WHERE` clause of a select. Unqualified columns are attributed to the selected
+/// table when there is only one.
+pub fn split_where_predicates(select: &SelectStatement) -> PredicateSplit {
+    let default_table = match (select.tables.as_slice(), select.join.is_empty()) {
+        ([ref table], true) => Some(table.alias.as_ref().unwrap_or(&table.name).as_str()),
+        _ => None,
+    };
+    match select.where_clause {
+        Some(ref cond) => split_predicates(cond, default_table),
+        None => PredicateSplit::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condition::condition_expr;
+    use select::selection;
+
+    #[test]
+    fn split_by_table() {
+        let cond = condition_expr(
+            "u.id = o.user_id AND u.active = 1 AND (o.total > 10 OR o.total < 0) \
+             AND u.id = u.parent_id AND 1 = 1 AND status = 'x' \
+             AND o.id IN (SELECT order_id FROM refunds)",
+        )
+        .unwrap()
+        .1;
+        let split = split_predicates(&cond, None);
+        assert_eq!(split.per_table.keys().collect::<Vec<_>>(), vec!["o", "u"]);
+        assert_eq!(split.per_table["u"].len(), 2);
+        assert_eq!(
+            split.per_table["o"][0].to_string(),
+            "o.total > 10 OR o.total < 0"
+        );
+        assert_eq!(split.join_predicates.len(), 1);
+        let residual = split.residual.unwrap();
+        let residual = conjuncts(&residual);
+        assert_eq!(residual.len(), 3);
+        assert_eq!(residual[1].to_string(), "status = 'x'");
+    }
+
+    #[test]
+    fn split_single_table_select() {
+        let select = selection("SELECT * FROM users WHERE id = 1 AND name = 'a'")
+            .unwrap()
+            .1;
+        let split = split_where_predicates(&select);
+        assert_eq!(split.per_table["users"].len(), 2);
+        assert!(split.join_predicates.is_empty());
+        assert_eq!(split.residual, None);
+    }
+}
@@ -0,0 +1,1781 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::iter;
+
+use alter::AlterTableDefinition;
+use arithmetic::{ArithmeticBase, ArithmeticExpression};
+use column::{Column, ColumnConstraint, ColumnSpecification, FunctionExpression};
+use common::{FieldDefinitionExpression, FieldValueExpression, KeyPart, TableKey};
+use compound_select::CompoundSelectStatement;
+use condition::{ConditionBase, ConditionExpression};
+use create::{CreateTableStatement, SelectSpecification};
+use ident::Ident;
+use join::{JoinConstraint, JoinOperator, JoinRightSide};
+use lineage::select_lineage;
+use merge::MergeAction;
+use order::OrderClause;
+use parser::SqlQuery;
+use select::{JoinClause, LimitClause, SelectModifiers, SelectStatement};
+use simplify::and_conditions;
+use table::Table;
+use with::WithStatement;
+
+/// Renames tables throughout a statement.
+struct Renamer<'r> {
+    renames: &'r HashMap<String, String>,
+    /// Aliases in scope; a column qualified by one of them keeps its qualifier.
+    aliases: Vec<Ident>,
+    /// Common table expressions in scope, which hide tables of the same name.
+    ctes: Vec<String>,
+}
+
+impl<'r> Renamer<'r> {
+    fn name<N: AsRef<str> + From<String>>(&self, name: &mut N) {
+        if let Some(new) = self.renames.get(name.as_ref()) {
+            *name = N::from(new.clone());
+        }
+    }
+
+    fn qualifier<N: AsRef<str> + From<String>>(&self, table: &mut N) {
+        let name = table.as_ref();
+        if !self.aliases.iter().any(|alias| alias == name) && !self.ctes.iter().any(|c| c == name) {
+            self.name(table);
+        }
+    }
+
+    fn table(&mut self, table: &mut Table) {
+        let cte = table.schema.is_none() && self.ctes.iter().any(|c| table.name == *c);
+        if table.is_stored() && !cte {
+            self.name(&mut table.name);
+        }
+        if let Some(ref mut subquery) = table.subquery {
+            self.select(subquery);
+        }
+        if let Some(ref mut function) = table.function {
+            for arg in function.all_arguments_mut() {
+                self.arithmetic_base(arg);
+            }
+        }
+    }
+
+    fn column(&mut self, col: &mut Column) {
+        if let Some(ref mut table) = col.table {
+            self.qualifier(table);
+        }
+        if let Some(ref mut function) = col.function {
+            match **function {
+                FunctionExpression::Avg(ref mut c, _)
+                | FunctionExpression::Count(ref mut c, _)
+                | FunctionExpression::Sum(ref mut c, _)
+                | FunctionExpression::Max(ref mut c)
+                | FunctionExpression::Min(ref mut c)
+                | FunctionExpression::GroupConcat(ref mut c, _)
+                | FunctionExpression::Values(ref mut c) => self.column(c),
+                FunctionExpression::Call(_, ref mut args) => {
+                    for arg in args {
+                        self.condition(arg);
+                    }
+                }
+                FunctionExpression::CountStar
+                | FunctionExpression::NextVal(_)
+                | FunctionExpression::SetConfig(..) => (),
+            }
+            // the parser names function columns after the call they make
+            if col.table.is_none() {
+                col.name = function.to_string().into();
+            }
+        }
+    }
+
+    fn arithmetic_base(&mut self, base: &mut ArithmeticBase) {
+        match *base {
+            ArithmeticBase::Column(ref mut col) => self.column(col),
+            ArithmeticBase::BitNot(ref mut base) => self.arithmetic_base(base),
+            ArithmeticBase::Nested(ref mut expr) => self.arithmetic(expr),
+            ArithmeticBase::Scalar(_) | ArithmeticBase::Variable(_) => (),
+        }
+    }
+
+    fn arithmetic(&mut self, expr: &mut ArithmeticExpression) {
+        self.arithmetic_base(&mut expr.left);
+        self.arithmetic_base(&mut expr.right);
+    }
+
+    fn value(&mut self, value: &mut FieldValueExpression) {
+        match *value {
+            FieldValueExpression::Arithmetic(ref mut expr) => self.arithmetic(expr),
+            FieldValueExpression::Literal(_) => (),
+            FieldValueExpression::Variable(ref mut var) => {
+                if let Some(ref mut value) = var.value {
+                    self.field(value);
+                }
+            }
+            FieldValueExpression::Json(ref mut json) => self.column(&mut json.column),
+            FieldValueExpression::Subscript(ref mut sub) => self.column(&mut sub.column),
+            FieldValueExpression::Expression(ref mut expr) => self.condition(expr),
+            FieldValueExpression::Predicate(ref mut pred) => self.condition(&mut pred.condition),
+        }
+    }
+
+    fn field(&mut self, field: &mut FieldDefinitionExpression) {
+        match *field {
+            FieldDefinitionExpression::All => (),
+            FieldDefinitionExpression::AllInTable(ref mut table) => self.qualifier(table),
+            FieldDefinitionExpression::Col(ref mut col) => self.column(col),
+            FieldDefinitionExpression::Value(ref mut value) => self.value(value),
+        }
+    }
+
+    fn condition(&mut self, cond: &mut ConditionExpression) {
+        match *cond {
+            ConditionExpression::ComparisonOp(ref mut tree)
+            | ConditionExpression::LogicalOp(ref mut tree) => {
+                self.condition(&mut tree.left);
+                self.condition(&mut tree.right);
+            }
+            ConditionExpression::NegationOp(ref mut expr)
+            | ConditionExpression::Bracketed(ref mut expr)
+            | ConditionExpression::Base(ConditionBase::Any(ref mut expr))
+            | ConditionExpression::Base(ConditionBase::Escaped(ref mut expr, _)) => {
+                self.condition(expr)
+            }
+            ConditionExpression::Arithmetic(ref mut expr) => self.arithmetic(expr),
+            ConditionExpression::Base(ConditionBase::BitNot(ref mut operand)) => {
+                self.arithmetic_base(operand)
+            }
+            ConditionExpression::Base(ConditionBase::Field(ref mut col)) => self.column(col),
+            ConditionExpression::Base(ConditionBase::NestedSelect(ref mut select)) => {
+                self.select(select)
+            }
+            ConditionExpression::Base(ConditionBase::Json(ref mut json)) => {
+                self.column(&mut json.column)
+            }
+            ConditionExpression::Base(ConditionBase::Subscript(ref mut sub)) => {
+                self.column(&mut sub.column)
+            }
+            ConditionExpression::Base(ConditionBase::Row(ref mut exprs, _))
+            | ConditionExpression::Base(ConditionBase::ExpressionList(ref mut exprs)) => {
+                for expr in exprs {
+                    self.condition(expr);
+                }
+            }
+            ConditionExpression::Base(ConditionBase::Literal(_))
+            | ConditionExpression::Base(ConditionBase::LiteralList(_))
+            | ConditionExpression::Base(ConditionBase::Variable(_)) => (),
+        }
+    }
+
+    fn order(&mut self, order: &mut Option<OrderClause>) {
+        for (field, _) in order.iter_mut().flat_map(|o| o.columns.iter_mut()) {
+            if let Some(col) = field.column_mut() {
+                self.column(col);
+            }
+        }
+    }
+
+    fn join_right(&mut self, right: &mut JoinRightSide) {
+        match *right {
+            JoinRightSide::Table(ref mut table) => self.table(table),
+            JoinRightSide::Tables(ref mut tables) => {
+                for table in tables {
+                    self.table(table);
+                }
+            }
+            JoinRightSide::NestedSelect(ref mut select, _) => self.select(select),
+            JoinRightSide::NestedJoin(ref mut nested) => self.join(nested),
+        }
+    }
+
+    fn join(&mut self, join: &mut JoinClause) {
+        self.join_right(&mut join.right);
+        match join.constraint {
+            JoinConstraint::On(ref mut cond) => self.condition(cond),
+            JoinConstraint::Using(ref mut columns) => {
+                for col in columns {
+                    self.column(col);
+                }
+            }
+            JoinConstraint::Empty => (),
+        }
+    }
+
+    /// Runs `f` with `aliases` in scope.
+    fn scoped<F: FnOnce(&mut Self)>(&mut self, aliases: Vec<Ident>, f: F) {
+        let outer = self.aliases.len();
+        self.aliases.extend(aliases);
+        f(self);
+        self.aliases.truncate(outer);
+    }
+
+    fn select(&mut self, select: &mut SelectStatement) {
+        let mut aliases: Vec<_> = select.tables.iter().filter_map(table_alias).collect();
+        for join in &select.join {
+            aliases.extend(join_aliases(&join.right));
+        }
+        self.scoped(aliases, |r| {
+            for field in &mut select.fields {
+                r.field(field);
+            }
+            for table in &mut select.tables {
+                r.table(table);
+            }
+            for join in &mut select.join {
+                r.join(join);
+            }
+            if let Some(ref mut cond) = select.where_clause {
+                r.condition(cond);
+            }
+            if let Some(ref mut group_by) = select.group_by {
+                for col in group_by.columns.iter_mut().filter_map(|f| f.column_mut()) {
+                    r.column(col);
+                }
+                if let Some(ref mut cond) = group_by.having {
+                    r.condition(cond);
+                }
+            }
+            r.order(&mut select.order);
+        });
+    }
+
+    fn specification(&mut self, spec: &mut SelectSpecification) {
+        match *spec {
+            SelectSpecification::Simple(ref mut select) => self.select(select),
+            SelectSpecification::Compound(ref mut compound) => {
+                for (_, ref mut select) in &mut compound.selects {
+                    self.select(select);
+                }
+                self.order(&mut compound.order);
+            }
+        }
+    }
+
+    fn with(&mut self, with: &mut WithStatement) {
+        let outer = self.ctes.len();
+        for cte in &mut with.ctes {
+            // a recursive expression refers to itself, any other only to those before it
+            if with.recursive {
+                self.ctes.push(cte.name.clone());
+            }
+            self.specification(&mut cte.query);
+            if !with.recursive {
+                self.ctes.push(cte.name.clone());
+            }
+        }
+        self.specification(&mut with.query);
+        self.ctes.truncate(outer);
+    }
+
+    fn key(&mut self, key: &mut TableKey) {
+        let parts = match *key {
+            TableKey::PrimaryKey(_, ref mut parts, _, _)
+            | TableKey::UniqueKey(_, ref mut parts, _)
+            | TableKey::FulltextKey(_, ref mut parts, _)
+            | TableKey::SpatialKey(_, ref mut parts, _)
+            | TableKey::Key(_, ref mut parts, _) => parts,
+        };
+        for part in parts {
+            self.key_part(part);
+        }
+    }
+
+    fn key_part(&mut self, part: &mut KeyPart) {
+        match *part {
+            KeyPart::Column(ref mut col, _, _) => self.column(col),
+            KeyPart::Expression(ref mut expr, _) => self.condition(expr),
+        }
+    }
+
+    fn column_spec(&mut self, spec: &mut ColumnSpecification) {
+        self.column(&mut spec.column);
+        for constraint in &mut spec.constraints {
+            if let ColumnConstraint::References { ref mut table, .. } = *constraint {
+                self.table(table);
+            }
+        }
+    }
+
+    fn query(&mut self, query: &mut SqlQuery) {
+        match *query {
+            SqlQuery::Select(ref mut select) => self.select(select),
+            SqlQuery::CompoundSelect(ref mut compound) => {
+                for (_, ref mut select) in &mut compound.selects {
+                    self.select(select);
+                }
+                self.order(&mut compound.order);
+            }
+            SqlQuery::Insert(ref mut insert) => {
+                self.table(&mut insert.table);
+                for col in insert.fields.iter_mut().flatten() {
+                    self.column(col);
+                }
+                for value in insert.data.iter_mut().flatten() {
+                    self.value(value);
+                }
+                for (col, value) in insert.on_duplicate.iter_mut().flatten() {
+                    self.column(col);
+                    self.value(value);
+                }
+            }
+            SqlQuery::Update(ref mut update) => {
+                self.scoped(update.table.alias.iter().cloned().collect(), |r| {
+                    r.table(&mut update.table);
+                    for (col, value) in &mut update.fields {
+                        r.column(col);
+                        r.value(value);
+                    }
+                    if let Some(ref mut cond) = update.where_clause {
+                        r.condition(cond);
+                    }
+                    r.order(&mut update.order);
+                });
+            }
+            SqlQuery::Delete(ref mut delete) => {
+                self.scoped(delete.table.alias.iter().cloned().collect(), |r| {
+                    r.table(&mut delete.table);
+                    if let Some(ref mut cond) = delete.where_clause {
+                        r.condition(cond);
+                    }
+                    r.order(&mut delete.order);
+                });
+            }
+            SqlQuery::CreateTable(ref mut create) => {
+                self.table(&mut create.table);
+                for spec in &mut create.fields {
+                    self.column_spec(spec);
+                }
+                for key in create.keys.iter_mut().flatten() {
+                    self.key(key);
+                }
+                for fk in create.fkeys.iter_mut().flatten() {
+                    self.table(&mut fk.that_table);
+                }
+            }
+            SqlQuery::AlterTable(ref mut alter) => {
+                self.table(&mut alter.table);
+                for definition in &mut alter.definitions {
+                    match *definition {
+                        AlterTableDefinition::AddColumn(ref mut spec, _)
+                        | AlterTableDefinition::ChangeColumn(_, ref mut spec, _)
+                        | AlterTableDefinition::ModifyColumn(ref mut spec, _) => {
+                            self.column_spec(spec)
+                        }
+                        AlterTableDefinition::AddKey(ref mut key) => self.key(key),
+                        AlterTableDefinition::AddForeignKey(ref mut fk) => {
+                            self.table(&mut fk.that_table)
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            SqlQuery::CreateView(ref mut view) => {
+                self.name(&mut view.name);
+                self.specification(&mut view.definition);
+            }
+            SqlQuery::With(ref mut with) => self.with(with),
+            SqlQuery::DeclareCursor(ref mut declare) => self.specification(&mut declare.query),
+            SqlQuery::CreateMaterializedView(ref mut view) => {
+                self.name(&mut view.name);
+                self.specification(&mut view.definition);
+            }
+            SqlQuery::RefreshMaterializedView(ref mut refresh) => self.name(&mut refresh.name),
+            SqlQuery::DropMaterializedView(ref mut drop) => {
+                for name in &mut drop.names {
+                    self.name(name);
+                }
+            }
+            SqlQuery::DropTable(ref mut drop) => {
+                for table in &mut drop.tables {
+                    self.table(table);
+                }
+            }
+            SqlQuery::CreateIndex(ref mut index) => {
+                self.table(&mut index.table);
+                for part in &mut index.columns {
+                    self.key_part(part);
+                }
+            }
+            SqlQuery::LockTables(ref mut lock) => {
+                for lock in &mut lock.tables {
+                    self.table(&mut lock.table);
+                }
+            }
+            SqlQuery::Handler(ref mut handler) => self.name(&mut handler.table),
+            SqlQuery::Merge(ref mut merge) => {
+                let mut aliases = join_aliases(&merge.source);
+                aliases.extend(merge.target.alias.clone());
+                self.scoped(aliases, |r| {
+                    r.table(&mut merge.target);
+                    r.join_right(&mut merge.source);
+                    r.condition(&mut merge.on);
+                    for clause in &mut merge.clauses {
+                        if let Some(ref mut cond) = clause.condition {
+                            r.condition(cond);
+                        }
+                        match clause.action {
+                            MergeAction::Update(ref mut assignments) => {
+                                for (col, value) in assignments {
+                                    r.column(col);
+                                    r.condition(value);
+                                }
+                            }
+                            MergeAction::Insert(ref mut columns, ref mut values) => {
+                                for col in columns.iter_mut().flatten() {
+                                    r.column(col);
+                                }
+                                for value in values {
+                                    r.condition(value);
+                                }
+                            }
+                            MergeAction::Delete => (),
+                        }
+                    }
+                });
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The alias that a table introduces. The name of a list of rows or a subquery is its alias.
+fn table_alias(table: &Table) -> Option<Ident> {
+    if table.values.is_some() || table.subquery.is_some() {
+        Some(table.name.clone())
+    } else {
+        table.alias.clone()
+    }
+}
+
+/// The aliases that a join's right side introduces.
+fn join_aliases(right: &JoinRightSide) -> Vec<Ident> {
+    match *right {
+        JoinRightSide::Table(ref t) => table_alias(t).into_iter().collect(),
+        JoinRightSide::Tables(ref ts) => ts.iter().filter_map(table_alias).collect(),
+        JoinRightSide::NestedSelect(_, ref alias) => alias.iter().map(Ident::from).collect(),
+        JoinRightSide::NestedJoin(ref nested) => join_aliases(&nested.right),
+    }
+}
+
+/// Renames every reference to the tables in `renames` (old name to new name) within `query`: the
+/// tables read and written, foreign key targets, view definitions, cursor selects, the bodies of
+/// common table expressions and the qualifiers of columns and `t.*`. Names are matched exactly,
+/// and a table qualified by its schema on its name alone. A qualifier that is the alias of a table
+/// in scope is left alone, as is the alias itself, so `SELECT u.id FROM users AS u` only has
+/// `users` renamed; so is a reference to a common table expression.
+pub fn rename_tables(query: &mut SqlQuery, renames: &HashMap<String, String>) {
+    Renamer {
+        renames,
+        aliases: Vec::new(),
+        ctes: Vec::new(),
+    }
+    .query(query)
+}
+
+/// Caps `limit` at `max` rows, adding a limit if there is none. An offset is kept.
+fn cap(limit: &mut Option<LimitClause>, max: u64) {
+    match *limit {
+        Some(ref mut limit) => limit.limit = limit.limit.min(max),
+        None => {
+            *limit = Some(LimitClause {
+                limit: max,
+                offset: 0,
+            })
+        }
+    }
+}
+
+fn limit_select(select: &mut SelectStatement, max: u64) {
+    match select.top {
+        Some(ref mut top) => *top = (*top).min(max),
+        None => cap(&mut select.limit, max),
+    }
+}
+
+fn limit_compound(compound: &mut CompoundSelectStatement, max: u64) {
+    // a trailing `LIMIT` is parsed as part of the last select, but printed after it applies to
+    // the compound select as a whole
+    let last = compound.selects.last_mut().map(|(_, s)| &mut s.limit);
+    match last {
+        Some(limit @ &mut Some(_)) if compound.limit.is_none() => cap(limit, max),
+        _ => cap(&mut compound.limit, max),
+    }
+}
+
+fn limit_specification(spec: &mut SelectSpecification, max: u64) {
+    match *spec {
+        SelectSpecification::Simple(ref mut select) => limit_select(select, max),
+        SelectSpecification::Compound(ref mut compound) => limit_compound(compound, max),
+    }
+}
+
+/// Ensures that a select or compound select returns at most `max` rows, by adding a `LIMIT` or
+/// lowering the one it has; a smaller limit is kept. A compound select is capped as a whole, and
+/// a select with SQL Server's `TOP` has that lowered instead. A `WITH` query has its main query
+/// capped, and a `DECLARE ... CURSOR` the select it opens. Other statements, and subqueries, are
+/// left unchanged.
+pub fn enforce_limit(query: &mut SqlQuery, max: u64) {
+    match *query {
+        SqlQuery::Select(ref mut select) => limit_select(select, max),
+        SqlQuery::CompoundSelect(ref mut compound) => limit_compound(compound, max),
+        SqlQuery::With(ref mut with) => limit_specification(&mut with.query, max),
+        SqlQuery::DeclareCursor(ref mut declare) => limit_specification(&mut declare.query, max),
+        _ => (),
+    }
+}
+
+/// Turns a select into one that counts the rows it returns, e.g. for the total of a paginated
+/// query. `ORDER BY`, `LIMIT`, `TOP` and `INTO` are dropped, along with any placeholders in them.
+///
+/// A select of plain columns without `DISTINCT` or `GROUP BY` has its select list replaced by
+/// `count(*)`; any other is wrapped as `SELECT count(*) FROM (...) AS sub`, since its select list
+/// may determine how many rows it returns.
+pub fn to_count_query(select: &SelectStatement) -> SelectStatement {
+    let mut inner = SelectStatement {
+        order: None,
+        limit: None,
+        top: None,
+        into: None,
+        ..select.clone()
+    };
+    let count_star = FieldDefinitionExpression::Col(Column {
+        name: Ident::from("count(*)"),
+        alias: None,
+        table: None,
+        function: Some(Box::new(FunctionExpression::CountStar)),
+    });
+    let plain = inner.fields.iter().all(|field| match *field {
+        FieldDefinitionExpression::Col(ref col) => col.function.is_none(),
+        FieldDefinitionExpression::All | FieldDefinitionExpression::AllInTable(_) => true,
+        FieldDefinitionExpression::Value(_) => false,
+    });
+    if plain && !inner.distinct && inner.group_by.is_none() {
+        inner.fields = vec![count_star];
+        return inner;
+    }
+    SelectStatement {
+        tables: vec![Table {
+            name: Ident::from("sub"),
+            subquery: Some(Box::new(inner)),
+            ..Default::default()
+        }],
+        fields: vec![count_star],
+        ..Default::default()
+    }
+}
+
+fn strip_condition_for_explain(cond: &mut ConditionExpression) {
+    match *cond {
+        ConditionExpression::ComparisonOp(ref mut tree)
+        | ConditionExpression::LogicalOp(ref mut tree) => {
+            strip_condition_for_explain(&mut tree.left);
+            strip_condition_for_explain(&mut tree.right);
+        }
+        ConditionExpression::NegationOp(ref mut expr)
+        | ConditionExpression::Bracketed(ref mut expr)
+        | ConditionExpression::Base(ConditionBase::Any(ref mut expr))
+        | ConditionExpression::Base(ConditionBase::Escaped(ref mut expr, _)) => {
+            strip_condition_for_explain(expr)
+        }
+        ConditionExpression::Base(ConditionBase::NestedSelect(ref mut select)) => {
+            strip_select_for_explain(select)
+        }
+        ConditionExpression::Base(ConditionBase::Row(ref mut exprs, _))
+        | ConditionExpression::Base(ConditionBase::ExpressionList(ref mut exprs)) => {
+            for expr in exprs {
+                strip_condition_for_explain(expr);
+            }
+        }
+        ConditionExpression::Base(_) | ConditionExpression::Arithmetic(_) => (),
+    }
+}
+
+fn strip_join_for_explain(join: &mut JoinClause) {
+    match join.right {
+        JoinRightSide::Table(ref mut table) => {
+            if let Some(ref mut subquery) = table.subquery {
+                strip_select_for_explain(subquery);
+            }
+        }
+        JoinRightSide::Tables(ref mut tables) => {
+            for subquery in tables.iter_mut().filter_map(|t| t.subquery.as_mut()) {
+                strip_select_for_explain(subquery);
+            }
+        }
+        JoinRightSide::NestedSelect(ref mut select, _) => strip_select_for_explain(select),
+        JoinRightSide::NestedJoin(ref mut nested) => strip_join_for_explain(nested),
+    }
+    if let JoinConstraint::On(ref mut cond) = join.constraint {
+        strip_condition_for_explain(cond);
+    }
+}
+
+fn strip_select_for_explain(select: &mut SelectStatement) {
+    select.into = None;
+    select.lock = None;
+    select
+        .modifiers
+        .remove(SelectModifiers::SQL_CALC_FOUND_ROWS);
+    for field in &mut select.fields {
+        if let FieldDefinitionExpression::Value(FieldValueExpression::Expression(ref mut e)) =
+            *field
+        {
+            strip_condition_for_explain(e);
+        }
+    }
+    for subquery in select.tables.iter_mut().filter_map(|t| t.subquery.as_mut()) {
+        strip_select_for_explain(subquery);
+    }
+    for join in &mut select.join {
+        strip_join_for_explain(join);
+    }
+    if let Some(ref mut cond) = select.where_clause {
+        strip_condition_for_explain(cond);
+    }
+    if let Some(ref mut cond) = select.group_by.as_mut().and_then(|g| g.having.as_mut()) {
+        strip_condition_for_explain(cond);
+    }
+}
+
+fn strip_specification_for_explain(spec: &mut SelectSpecification) {
+    match *spec {
+        SelectSpecification::Simple(ref mut select) => strip_select_for_explain(select),
+        SelectSpecification::Compound(ref mut compound) => {
+            for (_, ref mut select) in &mut compound.selects {
+                strip_select_for_explain(select);
+            }
+        }
+    }
+}
+
+/// Removes the clauses of a select or compound select that have no bearing on its plan but act
+/// when it runs, so that it can be wrapped in `EXPLAIN`, or in `EXPLAIN ANALYZE`, which runs it:
+/// `INTO`, locking clauses such as `FOR UPDATE`, and `SQL_CALC_FOUND_ROWS`. Subqueries and the
+/// common table expressions of a `WITH` query are stripped too. Other statements are left
+/// unchanged.
+pub fn strip_for_explain(query: &mut SqlQuery) {
+    match *query {
+        SqlQuery::Select(ref mut select) => strip_select_for_explain(select),
+        SqlQuery::CompoundSelect(ref mut compound) => {
+            for (_, ref mut select) in &mut compound.selects {
+                strip_select_for_explain(select);
+            }
+        }
+        SqlQuery::With(ref mut with) => {
+            for cte in &mut with.ctes {
+                strip_specification_for_explain(&mut cte.query);
+            }
+            strip_specification_for_explain(&mut with.query);
+        }
+        _ => (),
+    }
+}
+
+/// Points the columns of `cond` that are unqualified or qualified with `table` at `qualifier`.
+fn qualify(cond: &mut ConditionExpression, table: &str, qualifier: &str) {
+    let column = |col: &mut Column| match col.function {
+        Some(ref mut function) => {
+            if let FunctionExpression::Call(_, ref mut args) = **function {
+                for arg in args.iter_mut() {
+                    qualify(arg, table, qualifier);
+                }
+                col.name = function.to_string().into();
+            }
+        }
+        None => {
+            if col.table.as_deref().is_none_or(|t| t == table) {
+                col.table = Some(Ident::from(qualifier));
+            }
+        }
+    };
+    match *cond {
+        ConditionExpression::ComparisonOp(ref mut tree)
+        | ConditionExpression::LogicalOp(ref mut tree) => {
+            qualify(&mut tree.left, table, qualifier);
+            qualify(&mut tree.right, table, qualifier);
+        }
+        ConditionExpression::NegationOp(ref mut expr)
+        | ConditionExpression::Bracketed(ref mut expr)
+        | ConditionExpression::Base(ConditionBase::Any(ref mut expr))
+        | ConditionExpression::Base(ConditionBase::Escaped(ref mut expr, _)) => {
+            qualify(expr, table, qualifier)
+        }
+        ConditionExpression::Arithmetic(ref mut expr) => {
+            for base in expr.operands_mut() {
+                if let ArithmeticBase::Column(ref mut col) = *base {
+                    column(col);
+                }
+            }
+        }
+        ConditionExpression::Base(ConditionBase::BitNot(ref mut operand)) => {
+            let mut operands = vec![];
+            operand.operands_mut(&mut operands);
+            for base in operands {
+                if let ArithmeticBase::Column(ref mut col) = *base {
+                    column(col);
+                }
+            }
+        }
+        ConditionExpression::Base(ConditionBase::Field(ref mut col)) => column(col),
+        ConditionExpression::Base(ConditionBase::Json(ref mut json)) => column(&mut json.column),
+        ConditionExpression::Base(ConditionBase::Subscript(ref mut sub)) => column(&mut sub.column),
+        ConditionExpression::Base(ConditionBase::Row(ref mut exprs, _))
+        | ConditionExpression::Base(ConditionBase::ExpressionList(ref mut exprs)) => {
+            for expr in exprs {
+                qualify(expr, table, qualifier);
+            }
+        }
+        // a subquery in the predicate refers to its own tables
+        ConditionExpression::Base(_) => (),
+    }
+}
+
+/// Adds a predicate over one table to every select, update and delete that reads it.
+struct Injector<'p> {
+    table: &'p str,
+    predicate: &'p ConditionExpression,
+}
+
+impl<'p> Injector<'p> {
+    /// The predicate for an instance of the table, or `None` if `table` is another table.
+    fn predicate_for(&self, table: &Table) -> Option<ConditionExpression> {
+        if table.name != self.table || !table.is_stored() {
+            return None;
+        }
+        let mut predicate = self.predicate.clone();
+        qualify(
+            &mut predicate,
+            self.table,
+            table.alias.as_ref().unwrap_or(&table.name),
+        );
+        Some(predicate)
+    }
+
+    fn conjoin(&self, cond: &mut Option<ConditionExpression>, added: Vec<ConditionExpression>) {
+        if !added.is_empty() {
+            *cond = and_conditions(cond.take().into_iter().chain(added));
+        }
+    }
+
+    fn condition(&self, cond: &mut ConditionExpression) {
+        match *cond {
+            ConditionExpression::ComparisonOp(ref mut tree)
+            | ConditionExpression::LogicalOp(ref mut tree) => {
+                self.condition(&mut tree.left);
+                self.condition(&mut tree.right);
+            }
+            ConditionExpression::NegationOp(ref mut expr)
+            | ConditionExpression::Bracketed(ref mut expr)
+            | ConditionExpression::Base(ConditionBase::Any(ref mut expr))
+            | ConditionExpression::Base(ConditionBase::Escaped(ref mut expr, _)) => {
+                self.condition(expr)
+            }
+            ConditionExpression::Base(ConditionBase::NestedSelect(ref mut select)) => {
+                self.select(select)
+            }
+            ConditionExpression::Base(ConditionBase::Row(ref mut exprs, _))
+            | ConditionExpression::Base(ConditionBase::ExpressionList(ref mut exprs)) => {
+                for expr in exprs {
+                    self.condition(expr);
+                }
+            }
+            ConditionExpression::Base(ConditionBase::Field(ref mut col)) => self.column(col),
+            ConditionExpression::Arithmetic(ref mut expr) => self.arithmetic(expr),
+            ConditionExpression::Base(ConditionBase::BitNot(ref mut operand)) => {
+                self.arithmetic_base(operand)
+            }
+            ConditionExpression::Base(_) => (),
+        }
+    }
+
+    /// Handles the subqueries among the arguments of a function call.
+    fn column(&self, col: &mut Column) {
+        if let Some(FunctionExpression::Call(_, ref mut args)) = col.function.as_deref_mut() {
+            for arg in args {
+                self.condition(arg);
+            }
+        }
+    }
+
+    fn arithmetic(&self, expr: &mut ArithmeticExpression) {
+        self.arithmetic_base(&mut expr.left);
+        self.arithmetic_base(&mut expr.right);
+    }
+
+    fn arithmetic_base(&self, base: &mut ArithmeticBase) {
+        match *base {
+            ArithmeticBase::Column(ref mut col) => self.column(col),
+            ArithmeticBase::BitNot(ref mut base) => self.arithmetic_base(base),
+            ArithmeticBase::Nested(ref mut expr) => self.arithmetic(expr),
+            ArithmeticBase::Scalar(_) | ArithmeticBase::Variable(_) => (),
+        }
+    }
+
+    fn value(&self, value: &mut FieldValueExpression) {
+        match *value {
+            FieldValueExpression::Arithmetic(ref mut expr) => self.arithmetic(expr),
+            FieldValueExpression::Expression(ref mut expr) => self.condition(expr),
+            FieldValueExpression::Predicate(ref mut pred) => self.condition(&mut pred.condition),
+            FieldValueExpression::Variable(ref mut var) => {
+                if let Some(ref mut value) = var.value {
+                    self.field(value);
+                }
+            }
+            FieldValueExpression::Json(ref mut json) => self.column(&mut json.column),
+            FieldValueExpression::Subscript(ref mut sub) => self.column(&mut sub.column),
+            FieldValueExpression::Literal(_) => (),
+        }
+    }
+
+    fn field(&self, field: &mut FieldDefinitionExpression) {
+        match *field {
+            FieldDefinitionExpression::Col(ref mut col) => self.column(col),
+            FieldDefinitionExpression::Value(ref mut value) => self.value(value),
+            FieldDefinitionExpression::All | FieldDefinitionExpression::AllInTable(_) => (),
+        }
+    }
+
+    fn order(&self, order: &mut Option<OrderClause>) {
+        for (field, _) in order.iter_mut().flat_map(|o| o.columns.iter_mut()) {
+            if let Some(col) = field.column_mut() {
+                self.column(col);
+            }
+        }
+    }
+
+    /// Handles the right side of a join, returning the predicates that belong in the `WHERE`
+    /// clause. Those for the optional side of a `LEFT JOIN ... ON` go in its `ON` clause instead,
+    /// so that the join still produces rows without a match.
+    fn join(&self, join: &mut JoinClause) -> Vec<ConditionExpression> {
+        let mut added = Vec::new();
+        match join.right {
+            JoinRightSide::Table(ref table) => added.extend(self.predicate_for(table)),
+            JoinRightSide::Tables(ref tables) => {
+                added.extend(tables.iter().filter_map(|t| self.predicate_for(t)))
+            }
+            JoinRightSide::NestedSelect(ref mut select, _) => self.select(select),
+            JoinRightSide::NestedJoin(ref mut nested) => added.extend(self.join(nested)),
+        }
+        let outer =
+            join.operator == JoinOperator::LeftJoin || join.operator == JoinOperator::LeftOuterJoin;
+        match join.constraint {
+            JoinConstraint::On(ref mut cond) => {
+                self.condition(cond);
+                if outer && !added.is_empty() {
+                    *cond = and_conditions(iter::once(cond.clone()).chain(added.drain(..)))
+                        .expect("at least one condition");
+                }
+            }
+            JoinConstraint::Empty if outer && !added.is_empty() => {
+                join.constraint = JoinConstraint::On(
+                    and_conditions(added.drain(..)).expect("at least one condition"),
+                );
+            }
+            _ => (),
+        }
+        added
+    }
+
+    fn select(&self, select: &mut SelectStatement) {
+        let mut added: Vec<_> = select
+            .tables
+            .iter()
+            .filter_map(|t| self.predicate_for(t))
+            .collect();
+        for subquery in select.tables.iter_mut().filter_map(|t| t.subquery.as_mut()) {
+            self.select(subquery);
+        }
+        for join in &mut select.join {
+            added.extend(self.join(join));
+        }
+        for field in &mut select.fields {
+            self.field(field);
+        }
+        if let Some(ref mut cond) = select.where_clause {
+            self.condition(cond);
+        }
+        if let Some(ref mut group_by) = select.group_by {
+            for col in group_by.columns.iter_mut().filter_map(|f| f.column_mut()) {
+                self.column(col);
+            }
+            if let Some(ref mut cond) = group_by.having {
+                self.condition(cond);
+            }
+        }
+        self.order(&mut select.order);
+        self.conjoin(&mut select.where_clause, added);
+    }
+
+    fn specification(&self, spec: &mut SelectSpecification) {
+        match *spec {
+            SelectSpecification::Simple(ref mut select) => self.select(select),
+            SelectSpecification::Compound(ref mut compound) => {
+                for (_, ref mut select) in &mut compound.selects {
+                    self.select(select);
+                }
+            }
+        }
+    }
+}
+
+/// A statement that `inject_predicate` cannot restrict, as it may read rows that no predicate in
+/// it governs. Holds the statement as printed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnsupportedStatement(pub String);
+
+impl fmt::Display for UnsupportedStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot restrict the rows read by {}", self.0)
+    }
+}
+
+impl Error for UnsupportedStatement {}
+
+/// Adds `predicate`, a condition on rows of `table`, to every select, update and delete in `query`
+/// that reads `table`, including subqueries wherever they appear, each branch of a compound
+/// select, the common table expressions and main query of a `WITH` query, the query of a cursor,
+/// the definition of a view and the values of an insert. Columns of the predicate that are
+/// unqualified or qualified with `table` are qualified with the alias of each instance of the
+/// table, so `tenant = 7` restricts `FROM orders AS o` with `o.tenant = 7`.
+///
+/// The predicate is combined with the existing `WHERE` clause using `AND`, except for the optional
+/// side of a `LEFT JOIN ... ON`, where it joins the `ON` condition.
+///
+/// Statements that read rows out of reach of a predicate, which are `MERGE`, `CALL`, `DO`,
+/// `REFRESH MATERIALIZED VIEW`, a `HANDLER` on `table` and statements that failed to parse, are
+/// an error, and `query` is left unchanged. Statements that read no rows are left unchanged.
+pub fn inject_predicate(
+    query: &mut SqlQuery,
+    table: &str,
+    predicate: &ConditionExpression,
+) -> Result<(), UnsupportedStatement> {
+    let injector = Injector { table, predicate };
+    match *query {
+        SqlQuery::Select(ref mut select) => injector.select(select),
+        SqlQuery::CompoundSelect(ref mut compound) => {
+            for (_, ref mut select) in &mut compound.selects {
+                injector.select(select);
+            }
+        }
+        SqlQuery::With(ref mut with) => {
+            for cte in &mut with.ctes {
+                injector.specification(&mut cte.query);
+            }
+            injector.specification(&mut with.query);
+        }
+        SqlQuery::DeclareCursor(ref mut declare) => injector.specification(&mut declare.query),
+        SqlQuery::CreateView(ref mut view) => injector.specification(&mut view.definition),
+        SqlQuery::CreateMaterializedView(ref mut view) => {
+            injector.specification(&mut view.definition)
+        }
+        SqlQuery::Update(ref mut update) => {
+            for (_, value) in &mut update.fields {
+                injector.value(value);
+            }
+            if let Some(ref mut cond) = update.where_clause {
+                injector.condition(cond);
+            }
+            injector.order(&mut update.order);
+            let added = injector.predicate_for(&update.table).into_iter().collect();
+            injector.conjoin(&mut update.where_clause, added);
+        }
+        SqlQuery::Delete(ref mut delete) => {
+            if let Some(ref mut cond) = delete.where_clause {
+                injector.condition(cond);
+            }
+            injector.order(&mut delete.order);
+            let added = injector.predicate_for(&delete.table).into_iter().collect();
+            injector.conjoin(&mut delete.where_clause, added);
+        }
+        SqlQuery::Insert(ref mut insert) => {
+            let assigned = insert.on_duplicate.iter_mut().flatten().map(|(_, v)| v);
+            for value in insert.data.iter_mut().flatten().chain(assigned) {
+                injector.value(value);
+            }
+        }
+        SqlQuery::Values(ref mut values) => {
+            for value in values.rows.iter_mut().flatten() {
+                injector.value(value);
+            }
+        }
+        SqlQuery::Merge(_)
+        | SqlQuery::Call(_)
+        | SqlQuery::Do(_)
+        | SqlQuery::RefreshMaterializedView(_)
+        | SqlQuery::Unparsed(..) => return Err(UnsupportedStatement(query.to_string())),
+        SqlQuery::Handler(ref handler) if handler.table == table => {
+            return Err(UnsupportedStatement(query.to_string()))
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// A table that `expand_stars` has no definition for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnknownTable(pub String);
+
+impl fmt::Display for UnknownTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no definition for table {}", self.0)
+    }
+}
+
+impl Error for UnknownTable {}
+
+/// Replaces `*` and `t.*` with the columns they stand for.
+struct Expander<'s> {
+    schema: &'s [CreateTableStatement],
+}
+
+impl<'s> Expander<'s> {
+    /// The columns of a table in the `FROM` clause, in order.
+    fn table_columns(&self, table: &Table) -> Result<Vec<Ident>, UnknownTable> {
+        if let Some(ref subquery) = table.subquery {
+            let columns = select_lineage(subquery).into_iter().map(|c| c.name.into());
+            return Ok(columns.collect());
+        }
+        if let Some(ref values) = table.values {
+            // unnamed columns are called `column1`, `column2`, ... as in Postgres
+            let width = values.values.rows.first().map_or(0, |row| row.len());
+            let names = (values.column_aliases.len()..width).map(|n| format!("column{}", n + 1));
+            return Ok(values
+                .column_aliases
+                .iter()
+                .cloned()
+                .chain(names)
+                .map(Ident::from)
+                .collect());
+        }
+        if let Some(ref function) = table.function {
+            // a lone function's column is named after the table, those of `ROWS FROM` after
+            // their functions, and the row number after `WITH ORDINALITY` is `ordinality`
+            let mut names = match function.rows_from {
+                Some(ref others) => Some(table.name.clone())
+                    .into_iter()
+                    .chain(others.iter().map(|(name, _)| Ident::from(name)))
+                    .collect(),
+                None => vec![table.alias.clone().unwrap_or_else(|| table.name.clone())],
+            };
+            if function.with_ordinality {
+                names.push(Ident::from("ordinality"));
+            }
+            let aliased = function.column_aliases.len();
+            return Ok(function
+                .column_aliases
+                .iter()
+                .map(Ident::from)
+                .chain(names.into_iter().skip(aliased))
+                .collect());
+        }
+        self.schema
+            .iter()
+            .find(|create| create.table.name == table.name)
+            .map(|create| {
+                create
+                    .fields
+                    .iter()
+                    .map(|spec| spec.column.name.clone())
+                    .collect()
+            })
+            .ok_or_else(|| UnknownTable(table.name.to_string()))
+    }
+
+    fn condition(&self, cond: &mut ConditionExpression) -> Result<(), UnknownTable> {
+        match *cond {
+            ConditionExpression::ComparisonOp(ref mut tree)
+            | ConditionExpression::LogicalOp(ref mut tree) => {
+                self.condition(&mut tree.left)?;
+                self.condition(&mut tree.right)
+            }
+            ConditionExpression::NegationOp(ref mut expr)
+            | ConditionExpression::Bracketed(ref mut expr)
+            | ConditionExpression::Base(ConditionBase::Any(ref mut expr))
+            | ConditionExpression::Base(ConditionBase::Escaped(ref mut expr, _)) => {
+                self.condition(expr)
+            }
+            ConditionExpression::Base(ConditionBase::NestedSelect(ref mut select)) => {
+                self.select(select)
+            }
+            ConditionExpression::Base(ConditionBase::Row(ref mut exprs, _))
+            | ConditionExpression::Base(ConditionBase::ExpressionList(ref mut exprs)) => {
+                exprs.iter_mut().try_for_each(|e| self.condition(e))
+            }
+            ConditionExpression::Base(_) | ConditionExpression::Arithmetic(_) => Ok(()),
+        }
+    }
+
+    /// Collects the relations a join adds to the select, as (qualifier, columns).
+    fn join(
+        &self,
+        right: &mut JoinRightSide,
+        relations: &mut Vec<(Ident, Vec<Ident>)>,
+    ) -> Result<(), UnknownTable> {
+        match *right {
+            JoinRightSide::Table(ref table) => relations.push(self.relation(table)?),
+            JoinRightSide::Tables(ref tables) => {
+                for table in tables {
+                    relations.push(self.relation(table)?);
+                }
+            }
+            JoinRightSide::NestedSelect(ref mut select, ref alias) => {
+                self.select(select)?;
+                let columns = select_lineage(select).into_iter().map(|c| c.name.into());
+                let alias = alias.as_ref().map(Ident::from).unwrap_or_default();
+                relations.push((alias, columns.collect()));
+            }
+            JoinRightSide::NestedJoin(ref mut nested) => self.join(&mut nested.right, relations)?,
+        }
+        Ok(())
+    }
+
+    fn relation(&self, table: &Table) -> Result<(Ident, Vec<Ident>), UnknownTable> {
+        let qualifier = table.alias.as_ref().unwrap_or(&table.name);
+        Ok((qualifier.clone(), self.table_columns(table)?))
+    }
+
+    fn select(&self, select: &mut SelectStatement) -> Result<(), UnknownTable> {
+        let mut relations = Vec::new();
+        for table in &mut select.tables {
+            if let Some(ref mut subquery) = table.subquery {
+                self.select(subquery)?;
+            }
+            relations.push(self.relation(table)?);
+        }
+        // the columns `*` stands for, along with the relation each is taken from, which is `None`
+        // for a column that `USING` or a natural join merges
+        let qualified = |relations: &[(Ident, Vec<Ident>)]| {
+            relations
+                .iter()
+                .flat_map(|(qualifier, names)| {
+                    names
+                        .iter()
+                        .map(move |n| (Some(qualifier.clone()), n.clone()))
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut all = qualified(&relations);
+        for join in &mut select.join {
+            let first = relations.len();
+            self.join(&mut join.right, &mut relations)?;
+            let right = qualified(&relations[first..]);
+            let merged: Vec<Ident> = match join.constraint {
+                JoinConstraint::On(ref mut cond) => {
+                    self.condition(cond)?;
+                    Vec::new()
+                }
+                JoinConstraint::Using(ref columns) => {
+                    columns.iter().map(|c| c.name.clone()).collect()
+                }
+                JoinConstraint::Empty if join.natural => {
+                    let mut merged: Vec<Ident> = Vec::new();
+                    for (_, name) in &all {
+                        if right.iter().any(|(_, n)| n == name) && !merged.contains(name) {
+                            merged.push(name.clone());
+                        }
+                    }
+                    merged
+                }
+                JoinConstraint::Empty => Vec::new(),
+            };
+            // a merged column comes first, once, followed by the other columns of each side
+            all = merged
+                .iter()
+                .map(|name| (None, name.clone()))
+                .chain(all.into_iter().filter(|(_, n)| !merged.contains(n)))
+                .chain(right.into_iter().filter(|(_, n)| !merged.contains(n)))
+                .collect();
+        }
+        if let Some(ref mut cond) = select.where_clause {
+            self.condition(cond)?;
+        }
+
+        // columns are qualified unless there is only one relation to take them from
+        let qualify = relations.len() > 1;
+        let column = |qualifier: Option<&Ident>, name: &Ident| {
+            FieldDefinitionExpression::Col(Column {
+                name: name.clone(),
+                alias: None,
+                table: qualifier.filter(|_| qualify).cloned(),
+                function: None,
+            })
+        };
+        let columns = |(qualifier, names): &(Ident, Vec<Ident>)| {
+            names
+                .iter()
+                .map(|name| column(Some(qualifier), name))
+                .collect::<Vec<_>>()
+        };
+        let mut fields = Vec::with_capacity(select.fields.len());
+        for field in select.fields.drain(..) {
+            match field {
+                FieldDefinitionExpression::All => {
+                    fields.extend(all.iter().map(|(q, name)| column(q.as_ref(), name)))
+                }
+                FieldDefinitionExpression::AllInTable(ref table) => {
+                    let relation = relations
+                        .iter()
+                        .find(|r| r.0 == *table)
+                        .ok_or_else(|| UnknownTable(table.clone()))?;
+                    fields.extend(columns(relation));
+                }
+                field => fields.push(field),
+            }
+        }
+        select.fields = fields;
+        Ok(())
+    }
+}
+
+/// Rewrites `*` and `t.*` in the selects of `query`, including subqueries and the branches of a
+/// compound select, to the columns they stand for, using the table definitions in `schema`. The
+/// columns are listed in table order, then in the order they are defined in; they are qualified
+/// with the table name or alias if the select reads from more than one table. As in SQL, a column
+/// merged by `USING` or a natural join is listed once, unqualified, ahead of the other columns of
+/// the joined tables; `t.*` still lists it with the rest of `t`'s columns.
+///
+/// If a table that a `*` covers is not in `schema`, an error is returned and `query` is left
+/// unchanged.
+pub fn expand_stars(
+    query: &mut SqlQuery,
+    schema: &[CreateTableStatement],
+) -> Result<(), UnknownTable> {
+    let expander = Expander { schema };
+    let mut expanded = query.clone();
+    match expanded {
+        SqlQuery::Select(ref mut select) => expander.select(select)?,
+        SqlQuery::CompoundSelect(ref mut compound) => {
+            for (_, ref mut select) in &mut compound.selects {
+                expander.select(select)?;
+            }
+        }
+        SqlQuery::CreateView(ref mut view) => match *view.definition {
+            SelectSpecification::Simple(ref mut select) => expander.select(select)?,
+            SelectSpecification::Compound(ref mut compound) => {
+                for (_, ref mut select) in &mut compound.selects {
+                    expander.select(select)?;
+                }
+            }
+        },
+        _ => (),
+    }
+    *query = expanded;
+    Ok(())
+}
+
+/// Why `infer_insert_columns` could not list the columns of an `INSERT`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InsertColumnsError {
+    /// The table inserted into is not in the schema.
+    UnknownTable(String),
+    /// A row has a different number of values than the table has columns.
+    ArityMismatch {
+        table: String,
+        columns: usize,
+        values: usize,
+    },
+}
+
+impl fmt::Display for InsertColumnsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InsertColumnsError::UnknownTable(ref table) => {
+                write!(f, "no definition for table {}", table)
+            }
+            InsertColumnsError::ArityMismatch {
+                ref table,
+                columns,
+                values,
+            } => write!(
+                f,
+                "table {} has {} columns but a row inserted into it has {} values",
+                table, columns, values
+            ),
+        }
+    }
+}
+
+impl Error for InsertColumnsError {}
+
+/// Gives an `INSERT` without a column list the list of all of its table's columns, in the order
+/// they are defined in `schema`, so that the statement keeps its meaning if columns are later
+/// added or reordered. Statements that name their columns, and other statements, are left alone.
+///
+/// Every row must have a value for each column; if one does not, or the table is not in `schema`,
+/// an error is returned and `query` is left unchanged.
+pub fn infer_insert_columns(
+    query: &mut SqlQuery,
+    schema: &[CreateTableStatement],
+) -> Result<(), InsertColumnsError> {
+    let insert = match *query {
+        SqlQuery::Insert(ref mut insert) if insert.fields.is_none() => insert,
+        _ => return Ok(()),
+    };
+    let table = &insert.table.name;
+    let create = schema
+        .iter()
+        .find(|create| create.table.name == *table)
+        .ok_or_else(|| InsertColumnsError::UnknownTable(table.to_string()))?;
+    let columns = create.fields.len();
+    if let Some(row) = insert.data.iter().find(|row| row.len() != columns) {
+        return Err(InsertColumnsError::ArityMismatch {
+            table: table.to_string(),
+            columns,
+            values: row.len(),
+        });
+    }
+    insert.fields = Some(
+        create
+            .fields
+            .iter()
+            .map(|spec| Column {
+                name: spec.column.name.clone(),
+                ..Default::default()
+            })
+            .collect(),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+
+    fn renamed(q: &str) -> String {
+        let renames: HashMap<_, _> = vec![
+            (String::from("users"), String::from("t1_users")),
+            (String::from("orders"), String::from("t1_orders")),
+        ]
+        .into_iter()
+        .collect();
+        let mut query = parse_query(q).unwrap();
+        rename_tables(&mut query, &renames);
+        query.to_string()
+    }
+
+    #[test]
+    fn renames_selects() {
+        assert_eq!(
+            renamed(
+                "SELECT users.*, o.total, count(orders.id) FROM users \
+                 JOIN orders AS o ON users.id = o.uid \
+                 WHERE users.id IN (SELECT orders.uid FROM orders) GROUP BY users.id"
+            ),
+            "SELECT t1_users.*, o.total, count(t1_orders.id) FROM t1_users \
+             JOIN t1_orders AS o ON t1_users.id = o.uid \
+             WHERE t1_users.id IN (SELECT t1_orders.uid FROM t1_orders) GROUP BY t1_users.id"
+        );
+        assert_eq!(
+            renamed("SELECT id FROM accounts UNION SELECT id FROM users"),
+            "SELECT id FROM accounts UNION DISTINCT SELECT id FROM t1_users"
+        );
+        // the name of a subquery in FROM is an alias
+        assert_eq!(
+            renamed("SELECT orders.id FROM (SELECT users.id FROM users) AS orders"),
+            "SELECT orders.id FROM (SELECT t1_users.id FROM t1_users) AS orders"
+        );
+    }
+
+    #[test]
+    fn renames_common_table_expressions() {
+        assert_eq!(
+            renamed(
+                "WITH o AS (SELECT * FROM orders) SELECT * FROM o JOIN users ON o.uid = users.id"
+            ),
+            "WITH o AS (SELECT * FROM t1_orders) \
+             SELECT * FROM o JOIN t1_users ON o.uid = t1_users.id"
+        );
+        // an expression named after a table hides it from the queries that follow
+        assert_eq!(
+            renamed(
+                "WITH users AS (SELECT users.id FROM users WHERE karma > 0) \
+                 SELECT users.id FROM users"
+            ),
+            "WITH users AS (SELECT t1_users.id FROM t1_users WHERE karma > 0) \
+             SELECT users.id FROM users"
+        );
+        assert_eq!(
+            renamed("DECLARE c CURSOR FOR SELECT orders.id FROM orders"),
+            "DECLARE c CURSOR FOR SELECT t1_orders.id FROM t1_orders"
+        );
+    }
+
+    #[test]
+    fn renames_writes() {
+        assert_eq!(
+            renamed("INSERT INTO users (id, name) VALUES (1, 'a')"),
+            "INSERT INTO t1_users (id, name) VALUES (1, 'a')"
+        );
+        assert_eq!(
+            renamed("UPDATE users SET users.karma = 0 WHERE users.id = 1"),
+            "UPDATE t1_users SET t1_users.karma = 0 WHERE t1_users.id = 1"
+        );
+        assert_eq!(
+            renamed("DELETE FROM orders o WHERE o.id = 1"),
+            "DELETE FROM t1_orders AS o WHERE o.id = 1"
+        );
+    }
+
+    #[test]
+    fn renames_keep_quoting() {
+        assert_eq!(
+            renamed("SELECT o.id FROM \"users\" JOIN [orders] AS \"o\" ON users.id = o.uid"),
+            "SELECT o.id FROM \"t1_users\" JOIN [t1_orders] AS \"o\" ON t1_users.id = o.uid"
+        );
+        assert_eq!(
+            renamed("INSERT INTO `users` (id) VALUES (1)"),
+            "INSERT INTO `t1_users` (id) VALUES (1)"
+        );
+        // a qualified name is matched on the table, and keeps its schema
+        assert_eq!(
+            renamed("SELECT users.id FROM public.users"),
+            "SELECT t1_users.id FROM public.t1_users"
+        );
+    }
+
+    #[test]
+    fn renames_schema_objects() {
+        assert_eq!(
+            renamed(
+                "CREATE TABLE orders (id int, uid int REFERENCES users(id), \
+                 FOREIGN KEY (uid) REFERENCES users (id))"
+            ),
+            "CREATE TABLE t1_orders (id INT(32), uid INT(32) REFERENCES t1_users(id), \
+             FOREIGN KEY(uid) REFERENCES t1_users(id))"
+        );
+        assert_eq!(
+            renamed("CREATE VIEW big AS SELECT id FROM orders WHERE total > 100"),
+            "CREATE VIEW big AS SELECT id FROM t1_orders WHERE total > 100"
+        );
+        assert_eq!(
+            renamed("ALTER TABLE orders ADD COLUMN buyer int REFERENCES users(id), DROP uid"),
+            "ALTER TABLE t1_orders ADD COLUMN buyer INT(32) REFERENCES t1_users(id), \
+             DROP COLUMN uid"
+        );
+        assert_eq!(
+            renamed("DROP TABLE users, accounts"),
+            "DROP TABLE t1_users, accounts"
+        );
+    }
+
+    #[test]
+    fn enforces_limits() {
+        let limited = |q: &str| {
+            let mut query = parse_query(q).unwrap();
+            enforce_limit(&mut query, 100);
+            query.to_string()
+        };
+        assert_eq!(
+            limited("SELECT * FROM users"),
+            "SELECT * FROM users LIMIT 100"
+        );
+        assert_eq!(
+            limited("SELECT * FROM users LIMIT 1000 OFFSET 20"),
+            "SELECT * FROM users LIMIT 100 OFFSET 20"
+        );
+        assert_eq!(
+            limited("SELECT * FROM users WHERE id IN (SELECT uid FROM orders) LIMIT 5"),
+            "SELECT * FROM users WHERE id IN (SELECT uid FROM orders) LIMIT 5"
+        );
+        assert_eq!(
+            limited("SELECT id FROM users UNION SELECT id FROM orders LIMIT 500"),
+            "SELECT id FROM users UNION DISTINCT SELECT id FROM orders LIMIT 100"
+        );
+        assert_eq!(
+            limited("SELECT id FROM users UNION SELECT id FROM orders"),
+            "SELECT id FROM users UNION DISTINCT (SELECT id FROM orders) LIMIT 100"
+        );
+        assert_eq!(
+            limited("WITH o AS (SELECT * FROM orders LIMIT 1000) SELECT * FROM o"),
+            "WITH o AS (SELECT * FROM orders LIMIT 1000) SELECT * FROM o LIMIT 100"
+        );
+        assert_eq!(
+            limited("DECLARE c CURSOR FOR SELECT * FROM orders"),
+            "DECLARE c CURSOR FOR SELECT * FROM orders LIMIT 100"
+        );
+        assert_eq!(
+            limited("INSERT INTO users (id) VALUES (1)"),
+            "INSERT INTO users (id) VALUES (1)"
+        );
+    }
+
+    #[test]
+    fn counts_rows() {
+        let counted = |q: &str| match parse_query(q).unwrap() {
+            SqlQuery::Select(ref select) => to_count_query(select).to_string(),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            counted("SELECT u.id, u.name FROM users AS u WHERE u.karma > ? ORDER BY u.id LIMIT 20"),
+            "SELECT count(*) FROM users AS u WHERE u.karma > ?"
+        );
+        assert_eq!(
+            counted("SELECT * FROM users JOIN orders ON users.id = orders.uid LIMIT 10 OFFSET 40"),
+            "SELECT count(*) FROM users JOIN orders ON users.id = orders.uid"
+        );
+        assert_eq!(
+            counted("SELECT DISTINCT name FROM users ORDER BY name"),
+            "SELECT count(*) FROM (SELECT DISTINCT name FROM users) AS sub"
+        );
+        assert_eq!(
+            counted("SELECT uid, sum(total) FROM orders GROUP BY uid HAVING sum(total) > 10"),
+            "SELECT count(*) FROM (SELECT uid, sum(total) FROM orders \
+             GROUP BY uid HAVING sum(total) > 10) AS sub"
+        );
+        // an aggregate without GROUP BY returns a single row
+        assert_eq!(
+            counted("SELECT max(total) FROM orders"),
+            "SELECT count(*) FROM (SELECT max(total) FROM orders) AS sub"
+        );
+        let wrapped = parse_query(counted("SELECT DISTINCT name FROM users")).unwrap();
+        assert_eq!(
+            wrapped.to_string(),
+            "SELECT count(*) FROM (SELECT DISTINCT name FROM users) AS sub"
+        );
+    }
+
+    #[test]
+    fn strips_for_explain() {
+        let stripped = |q: &str| {
+            let mut query = parse_query(q).unwrap();
+            strip_for_explain(&mut query);
+            query.to_string()
+        };
+        assert_eq!(
+            stripped(
+                "SELECT SQL_CALC_FOUND_ROWS SQL_NO_CACHE id INTO @id FROM jobs \
+                 WHERE id IN (SELECT jid FROM queue FOR SHARE) LIMIT 1 FOR UPDATE SKIP LOCKED"
+            ),
+            "SELECT SQL_NO_CACHE id FROM jobs WHERE id IN (SELECT jid FROM queue) LIMIT 1"
+        );
+        assert_eq!(
+            stripped("SELECT * FROM (SELECT id FROM t FOR UPDATE) AS s LOCK IN SHARE MODE"),
+            "SELECT * FROM (SELECT id FROM t) AS s"
+        );
+        assert_eq!(
+            stripped("SELECT a FROM t UNION SELECT b FROM u INTO OUTFILE '/tmp/x'"),
+            "SELECT a FROM t UNION DISTINCT SELECT b FROM u"
+        );
+        assert_eq!(
+            stripped("WITH s AS (SELECT id FROM t FOR UPDATE) SELECT id INTO @id FROM s"),
+            "WITH s AS (SELECT id FROM t) SELECT id FROM s"
+        );
+        assert_eq!(
+            stripped("DELETE FROM t WHERE id = 1"),
+            "DELETE FROM t WHERE id = 1"
+        );
+    }
+
+    #[test]
+    fn injects_predicates() {
+        let predicate = ::condition::condition_expr("tenant = 7").unwrap().1;
+        let injected = |q: &str| {
+            let mut query = parse_query(q).unwrap();
+            inject_predicate(&mut query, "orders", &predicate).unwrap();
+            query.to_string()
+        };
+        assert_eq!(
+            injected("SELECT * FROM orders WHERE total > 1 OR total < 0"),
+            "SELECT * FROM orders WHERE (total > 1 OR total < 0) AND orders.tenant = 7"
+        );
+        assert_eq!(
+            injected(
+                "SELECT * FROM users AS u JOIN orders AS o ON u.id = o.uid \
+                 WHERE u.id IN (SELECT uid FROM orders)"
+            ),
+            "SELECT * FROM users AS u JOIN orders AS o ON u.id = o.uid \
+             WHERE u.id IN (SELECT uid FROM orders WHERE orders.tenant = 7) AND o.tenant = 7"
+        );
+        assert_eq!(
+            injected("SELECT * FROM users AS u LEFT JOIN orders AS o ON u.id = o.uid"),
+            "SELECT * FROM users AS u LEFT JOIN orders AS o ON u.id = o.uid AND o.tenant = 7"
+        );
+        assert_eq!(
+            injected("SELECT id FROM orders UNION SELECT id FROM users"),
+            "SELECT id FROM orders WHERE orders.tenant = 7 UNION DISTINCT SELECT id FROM users"
+        );
+        assert_eq!(
+            injected("WITH o AS (SELECT * FROM orders) SELECT * FROM o, orders AS p"),
+            "WITH o AS (SELECT * FROM orders WHERE orders.tenant = 7) \
+             SELECT * FROM o, orders AS p WHERE p.tenant = 7"
+        );
+        assert_eq!(
+            injected("DELETE FROM orders WHERE id = 1"),
+            "DELETE FROM orders WHERE id = 1 AND orders.tenant = 7"
+        );
+        assert_eq!(
+            injected("UPDATE users SET karma = 0"),
+            "UPDATE users SET karma = 0"
+        );
+    }
+
+    #[test]
+    fn injects_predicates_wherever_orders_are_read() {
+        let predicate = ::condition::condition_expr("tenant = 7").unwrap().1;
+        let injected = |q: &str| {
+            let mut query = parse_query(q).unwrap();
+            inject_predicate(&mut query, "orders", &predicate).map(|_| query.to_string())
+        };
+        let filtered = |q: &str| Ok(String::from(q));
+        assert_eq!(
+            injected("DECLARE c CURSOR FOR SELECT * FROM orders"),
+            filtered("DECLARE c CURSOR FOR SELECT * FROM orders WHERE orders.tenant = 7")
+        );
+        assert_eq!(
+            injected("UPDATE users SET total = (SELECT sum(x) FROM orders) WHERE id = 1"),
+            filtered(
+                "UPDATE users SET total = (SELECT sum(x) FROM orders WHERE orders.tenant = 7) \
+                 WHERE id = 1"
+            )
+        );
+        assert_eq!(
+            injected("SELECT coalesce((SELECT max(id) FROM orders), 0) FROM users"),
+            filtered(
+                "SELECT coalesce((SELECT max(id) FROM orders WHERE orders.tenant = 7), 0) \
+                 FROM users"
+            )
+        );
+        assert_eq!(
+            injected("INSERT INTO log (n) VALUES ((SELECT count(*) FROM orders))"),
+            filtered(
+                "INSERT INTO log (n) VALUES ((SELECT count(*) FROM orders WHERE orders.tenant = 7))"
+            )
+        );
+        assert_eq!(
+            injected("CREATE VIEW mine AS SELECT id FROM orders"),
+            filtered("CREATE VIEW mine AS SELECT id FROM orders WHERE orders.tenant = 7")
+        );
+        let merge = "MERGE INTO archive USING orders ON archive.id = orders.id \
+                     WHEN NOT MATCHED THEN INSERT VALUES (orders.id)";
+        assert_eq!(
+            injected(merge),
+            Err(UnsupportedStatement(
+                parse_query(merge).unwrap().to_string()
+            ))
+        );
+        assert!(injected("HANDLER orders READ FIRST").is_err());
+        assert!(injected("HANDLER users READ FIRST").is_ok());
+        assert_eq!(injected("DROP TABLE users"), filtered("DROP TABLE users"));
+    }
+
+    fn schema() -> Vec<CreateTableStatement> {
+        [
+            "CREATE TABLE users (id int, name text)",
+            "CREATE TABLE orders (id int, uid int, total int)",
+        ]
+        .iter()
+        .map(|q| match parse_query(q).unwrap() {
+            SqlQuery::CreateTable(create) => create,
+            _ => unreachable!(),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn expands_stars() {
+        let schema = schema();
+        let expanded = |q: &str| {
+            let mut query = parse_query(q).unwrap();
+            expand_stars(&mut query, &schema).map(|_| query.to_string())
+        };
+        assert_eq!(
+            expanded("SELECT * FROM users WHERE id IN (SELECT * FROM orders)"),
+            Ok(String::from(
+                "SELECT id, name FROM users WHERE id IN (SELECT id, uid, total FROM orders)"
+            ))
+        );
+        assert_eq!(
+            expanded("SELECT o.*, u.name FROM users AS u JOIN orders AS o ON u.id = o.uid"),
+            Ok(String::from(
+                "SELECT o.id, o.uid, o.total, u.name FROM users AS u \
+                 JOIN orders AS o ON u.id = o.uid"
+            ))
+        );
+        assert_eq!(
+            expanded("SELECT * FROM users, (VALUES (1, 2)) AS v(a)"),
+            Ok(String::from(
+                "SELECT users.id, users.name, v.a, v.column2 FROM users, (VALUES (1, 2)) AS v(a)"
+            ))
+        );
+        assert_eq!(
+            expanded("SELECT * FROM (SELECT * FROM orders) AS o"),
+            Ok(String::from(
+                "SELECT id, uid, total FROM (SELECT id, uid, total FROM orders) AS o"
+            ))
+        );
+        assert_eq!(
+            expanded("SELECT * FROM generate_series(1, 3) WITH ORDINALITY AS g(n)"),
+            Ok(String::from(
+                "SELECT n, ordinality FROM generate_series(1, 3) WITH ORDINALITY AS g(n)"
+            ))
+        );
+        assert_eq!(
+            expanded("SELECT * FROM users JOIN carts ON carts.uid = users.id"),
+            Err(UnknownTable(String::from("carts")))
+        );
+    }
+
+    #[test]
+    fn expands_stars_over_merged_columns() {
+        let schema = schema();
+        let field_count = |q: &str| {
+            let mut query = parse_query(q).unwrap();
+            expand_stars(&mut query, &schema).unwrap();
+            match query {
+                SqlQuery::Select(ref select) => (select.fields.len(), query.to_string()),
+                _ => unreachable!(),
+            }
+        };
+        // users (id, name) and orders (id, uid, total) share only id
+        assert_eq!(
+            field_count("SELECT * FROM users JOIN orders USING (id)"),
+            (
+                4,
+                String::from(
+                    "SELECT id, users.name, orders.uid, orders.total FROM users \
+                     JOIN orders USING (id)"
+                )
+            )
+        );
+        assert_eq!(field_count("SELECT * FROM users NATURAL JOIN orders").0, 4);
+        assert_eq!(
+            field_count("SELECT orders.* FROM users JOIN orders USING (id)").0,
+            3
+        );
+        assert_eq!(
+            field_count("SELECT * FROM users JOIN orders ON users.id = orders.id").0,
+            5
+        );
+    }
+
+    #[test]
+    fn infers_insert_columns() {
+        let schema = schema();
+        let inferred = |q: &str| {
+            let mut query = parse_query(q).unwrap();
+            infer_insert_columns(&mut query, &schema).map(|_| query.to_string())
+        };
+        assert_eq!(
+            inferred("INSERT INTO orders VALUES (1, 2, 3), (4, 5, ?)"),
+            Ok(String::from(
+                "INSERT INTO orders (id, uid, total) VALUES (1, 2, 3), (4, 5, ?)"
+            ))
+        );
+        assert_eq!(
+            inferred("INSERT INTO orders (uid, id) VALUES (1, 2)"),
+            Ok(String::from("INSERT INTO orders (uid, id) VALUES (1, 2)"))
+        );
+        assert_eq!(
+            inferred("INSERT INTO users VALUES (1, 'a'), (2)"),
+            Err(InsertColumnsError::ArityMismatch {
+                table: String::from("users"),
+                columns: 2,
+                values: 1,
+            })
+        );
+        assert_eq!(
+            inferred("INSERT INTO carts VALUES (1)"),
+            Err(InsertColumnsError::UnknownTable(String::from("carts")))
+        );
+    }
+}
@@ -1,22 +1,37 @@
-use nom::multispace;
-use nom::types::CompleteByteSlice;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, not, opt, recognize, value};
+use nom::multi::{fold_many0, many0, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt;
-use std::str;
+use std::ops;
 
-use column::Column;
+use arithmetic::ArithmeticBase;
 use common::FieldDefinitionExpression;
 use common::{
-    as_alias, field_definition_expr, field_list, opt_multispace, statement_terminator, table_list,
-    table_reference, unsigned_number,
+    as_alias, column_identifier_no_alias, field_definition_expr, field_list, keyword_phrase,
+    keyword_whitespace, literal, sql_identifier, statement_terminator, table_as_alias, table_list,
+    table_name, unsigned_number, user_variable, whitespace0, whitespace1, ws_sep_comma, QuotedName,
 };
 use condition::{condition_expr, ConditionExpression};
+use dialect::{only_in, Dialect};
+use ident::Ident;
+use into::{into_clause, IntoClause};
 use join::{join_operator, JoinConstraint, JoinOperator, JoinRightSide};
-use order::{order_clause, OrderClause};
-use table::Table;
+use keywords::{escape_if_keyword, tag_no_case};
+use limits::nested;
+use order::{field_reference, order_clause, FieldReference, OrderClause};
+use table::{
+    IndexHint, IndexHintScope, IndexHintType, Table, TableFunction, TableSample, TableValues,
+};
+use values::values;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct GroupByClause {
-    pub columns: Vec<Column>,
+    pub columns: Vec<FieldReference>,
     pub having: Option<ConditionExpression>,
 }
 
@@ -39,23 +54,36 @@ impl fmt::Display for GroupByClause {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct JoinClause {
+    /// Whether the join is `NATURAL`, i.e. on the columns both sides have in common.
+    #[serde(default)]
+    pub natural: bool,
     pub operator: JoinOperator,
+    /// Whether the right side is `LATERAL`, i.e. may refer to tables to its left.
+    pub lateral: bool,
     pub right: JoinRightSide,
     pub constraint: JoinConstraint,
 }
 
 impl fmt::Display for JoinClause {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.natural {
+            write!(f, "NATURAL ")?;
+        }
         write!(f, "{}", self.operator)?;
+        if self.lateral {
+            write!(f, " LATERAL")?;
+        }
         write!(f, " {}", self.right)?;
-        write!(f, " {}", self.constraint)?;
+        if self.constraint != JoinConstraint::Empty {
+            write!(f, " {}", self.constraint)?;
+        }
         Ok(())
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct LimitClause {
     pub limit: u64,
     pub offset: u64,
@@ -71,16 +99,166 @@ impl fmt::Display for LimitClause {
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// How strongly a locking read locks the rows it reads.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum LockStrength {
+    /// `FOR UPDATE`
+    Update,
+    /// `FOR SHARE`
+    Share,
+    /// MySQL's older `LOCK IN SHARE MODE`, which takes no further options.
+    ShareMode,
+}
+
+/// What a locking read does about rows locked by another transaction, instead of waiting.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum LockWait {
+    /// `NOWAIT`, failing at once.
+    NoWait,
+    /// `SKIP LOCKED`, leaving them out of the result.
+    SkipLocked,
+}
+
+/// The locking clause of a select, e.g. `FOR UPDATE OF orders SKIP LOCKED`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct LockingClause {
+    pub strength: LockStrength,
+    /// The tables, by name or alias, whose rows are locked; all of them if empty.
+    pub of: Vec<String>,
+    pub wait: Option<LockWait>,
+}
+
+impl fmt::Display for LockingClause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.strength {
+            LockStrength::Update => write!(f, "FOR UPDATE")?,
+            LockStrength::Share => write!(f, "FOR SHARE")?,
+            LockStrength::ShareMode => return write!(f, "LOCK IN SHARE MODE"),
+        }
+        if !self.of.is_empty() {
+            let tables: Vec<_> = self.of.iter().map(|t| escape_if_keyword(t)).collect();
+            write!(f, " OF {}", tables.join(", "))?;
+        }
+        match self.wait {
+            Some(LockWait::NoWait) => write!(f, " NOWAIT"),
+            Some(LockWait::SkipLocked) => write!(f, " SKIP LOCKED"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// MySQL-specific `SELECT` modifiers, stored as a set of bit flags. Serialized as the list of
+/// their keywords, so that the representation does not depend on the bit assignment.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
+#[serde(into = "Vec<String>", try_from = "Vec<String>")]
+pub struct SelectModifiers(u8);
+
+impl SelectModifiers {
+    pub const HIGH_PRIORITY: SelectModifiers = SelectModifiers(1);
+    pub const STRAIGHT_JOIN: SelectModifiers = SelectModifiers(1 << 1);
+    pub const SQL_SMALL_RESULT: SelectModifiers = SelectModifiers(1 << 2);
+    pub const SQL_BIG_RESULT: SelectModifiers = SelectModifiers(1 << 3);
+    pub const SQL_BUFFER_RESULT: SelectModifiers = SelectModifiers(1 << 4);
+    pub const SQL_CACHE: SelectModifiers = SelectModifiers(1 << 5);
+    pub const SQL_NO_CACHE: SelectModifiers = SelectModifiers(1 << 6);
+    pub const SQL_CALC_FOUND_ROWS: SelectModifiers = SelectModifiers(1 << 7);
+
+    /// All modifiers with their keywords, in the order MySQL's grammar lists them.
+    const NAMES: [(SelectModifiers, &'static str); 8] = [
+        (SelectModifiers::HIGH_PRIORITY, "HIGH_PRIORITY"),
+        (SelectModifiers::STRAIGHT_JOIN, "STRAIGHT_JOIN"),
+        (SelectModifiers::SQL_SMALL_RESULT, "SQL_SMALL_RESULT"),
+        (SelectModifiers::SQL_BIG_RESULT, "SQL_BIG_RESULT"),
+        (SelectModifiers::SQL_BUFFER_RESULT, "SQL_BUFFER_RESULT"),
+        (SelectModifiers::SQL_CACHE, "SQL_CACHE"),
+        (SelectModifiers::SQL_NO_CACHE, "SQL_NO_CACHE"),
+        (SelectModifiers::SQL_CALC_FOUND_ROWS, "SQL_CALC_FOUND_ROWS"),
+    ];
+
+    pub fn empty() -> SelectModifiers {
+        SelectModifiers(0)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, other: SelectModifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: SelectModifiers) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: SelectModifiers) {
+        self.0 &= !other.0;
+    }
+}
+
+impl ops::BitOr for SelectModifiers {
+    type Output = SelectModifiers;
+
+    fn bitor(self, other: SelectModifiers) -> SelectModifiers {
+        SelectModifiers(self.0 | other.0)
+    }
+}
+
+impl From<SelectModifiers> for Vec<String> {
+    fn from(modifiers: SelectModifiers) -> Vec<String> {
+        SelectModifiers::NAMES
+            .iter()
+            .filter(|&&(m, _)| modifiers.contains(m))
+            .map(|&(_, name)| String::from(name))
+            .collect()
+    }
+}
+
+impl TryFrom<Vec<String>> for SelectModifiers {
+    type Error = String;
+
+    fn try_from(names: Vec<String>) -> Result<SelectModifiers, String> {
+        let mut modifiers = SelectModifiers::empty();
+        for name in names {
+            match SelectModifiers::NAMES.iter().find(|&&(_, n)| n == name) {
+                Some(&(m, _)) => modifiers.insert(m),
+                None => return Err(format!("unknown select modifier {}", name)),
+            }
+        }
+        Ok(modifiers)
+    }
+}
+
+impl fmt::Display for SelectModifiers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = SelectModifiers::NAMES
+            .iter()
+            .filter(|&&(m, _)| self.contains(m))
+            .map(|&(_, name)| name)
+            .collect::<Vec<_>>();
+        write!(f, "{}", names.join(" "))
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct SelectStatement {
     pub tables: Vec<Table>,
     pub distinct: bool,
+    pub modifiers: SelectModifiers,
     pub fields: Vec<FieldDefinitionExpression>,
     pub join: Vec<JoinClause>,
     pub where_clause: Option<ConditionExpression>,
     pub group_by: Option<GroupByClause>,
     pub order: Option<OrderClause>,
     pub limit: Option<LimitClause>,
+    pub into: Option<IntoClause>,
+    /// SQL Server `SELECT TOP n`, which is kept apart from `limit` so that it prints the same.
+    #[serde(default)]
+    pub top: Option<u64>,
+    #[serde(default)]
+    pub lock: Option<LockingClause>,
 }
 
 impl fmt::Display for SelectStatement {
@@ -89,6 +267,12 @@ impl fmt::Display for SelectStatement {
         if self.distinct {
             write!(f, "DISTINCT ")?;
         }
+        if let Some(top) = self.top {
+            write!(f, "TOP {} ", top)?;
+        }
+        if !self.modifiers.is_empty() {
+            write!(f, "{} ", self.modifiers)?;
+        }
         write!(
             f,
             "{}",
@@ -99,7 +283,7 @@ impl fmt::Display for SelectStatement {
                 .join(", ")
         )?;
 
-        if self.tables.len() > 0 {
+        if !self.tables.is_empty() {
             write!(f, " FROM ")?;
             write!(
                 f,
@@ -127,179 +311,637 @@ impl fmt::Display for SelectStatement {
         if let Some(ref limit) = self.limit {
             write!(f, " {}", limit)?;
         }
+        if let Some(ref lock) = self.lock {
+            write!(f, " {}", lock)?;
+        }
+        if let Some(ref into) = self.into {
+            write!(f, " {}", into)?;
+        }
         Ok(())
     }
 }
 
+fn having_clause(i: &str) -> IResult<&str, ConditionExpression> {
+    let (remaining_input, (_, _, _, ce)) = tuple((
+        whitespace0,
+        tag_no_case("having"),
+        whitespace0,
+        condition_expr,
+    ))(i)?;
+
+    Ok((remaining_input, ce))
+}
+
 /// Parse GROUP BY clause
-named!(group_by_clause<CompleteByteSlice, GroupByClause>,
-    do_parse!(
-        opt_multispace >>
-        tag_no_case!("group by") >>
-        multispace >>
-        group_columns: field_list >>
-        having_clause: opt!(
-            do_parse!(
-                opt_multispace >>
-                tag_no_case!("having") >>
-                opt_multispace >>
-                ce: condition_expr >>
-                (ce)
-            )
-        ) >>
-        (GroupByClause {
-            columns: group_columns,
-            having: having_clause,
-        })
-    )
-);
+fn group_by_clause(i: &str) -> IResult<&str, GroupByClause> {
+    let (remaining_input, (_, _, _, columns, having)) = tuple((
+        whitespace0,
+        keyword_phrase(&["group", "by"]),
+        whitespace1,
+        many0(terminated(field_reference, opt(ws_sep_comma))),
+        opt(having_clause),
+    ))(i)?;
+
+    Ok((remaining_input, GroupByClause { columns, having }))
+}
+
+fn offset(i: &str) -> IResult<&str, u64> {
+    let (remaining_input, (_, _, _, val)) = tuple((
+        whitespace0,
+        tag_no_case("offset"),
+        whitespace1,
+        unsigned_number,
+    ))(i)?;
+
+    Ok((remaining_input, val))
+}
 
 /// Parse LIMIT clause
-named!(pub limit_clause<CompleteByteSlice, LimitClause>,
-    do_parse!(
-        opt_multispace >>
-        tag_no_case!("limit") >>
-        multispace >>
-        limit_val: unsigned_number >>
-        offset_val: opt!(
-            do_parse!(
-                opt_multispace >>
-                tag_no_case!("offset") >>
-                multispace >>
-                val: unsigned_number >>
-                (val)
-            )
-        ) >>
-    (LimitClause {
-        limit: limit_val,
-        offset: match offset_val {
-            None => 0,
-            Some(v) => v,
+pub fn limit_clause(i: &str) -> IResult<&str, LimitClause> {
+    let (remaining_input, (_, _, _, limit, opt_offset)) = tuple((
+        whitespace0,
+        tag_no_case("limit"),
+        whitespace1,
+        unsigned_number,
+        opt(offset),
+    ))(i)?;
+    let offset = opt_offset.unwrap_or(0);
+
+    Ok((remaining_input, LimitClause { limit, offset }))
+}
+
+/// Parse a locking clause, `FOR UPDATE`, `FOR SHARE` or `LOCK IN SHARE MODE`
+pub fn locking_clause(i: &str) -> IResult<&str, LockingClause> {
+    let share_mode = value(
+        LockingClause {
+            strength: LockStrength::ShareMode,
+            of: vec![],
+            wait: None,
+        },
+        tuple((
+            tag_no_case("lock"),
+            whitespace1,
+            tag_no_case("in"),
+            whitespace1,
+            tag_no_case("share"),
+            whitespace1,
+            tag_no_case("mode"),
+        )),
+    );
+    let wait = alt((
+        value(LockWait::NoWait, tag_no_case("nowait")),
+        value(
+            LockWait::SkipLocked,
+            tuple((tag_no_case("skip"), whitespace1, tag_no_case("locked"))),
+        ),
+    ));
+    let locking_read = map(
+        tuple((
+            tag_no_case("for"),
+            whitespace1,
+            alt((
+                value(LockStrength::Update, tag_no_case("update")),
+                value(LockStrength::Share, tag_no_case("share")),
+            )),
+            opt(preceded(
+                tuple((whitespace1, tag_no_case("of"), whitespace1)),
+                separated_list1(ws_sep_comma, sql_identifier),
+            )),
+            opt(preceded(whitespace1, wait)),
+        )),
+        |(_, _, strength, of, wait)| LockingClause {
+            strength,
+            of: of
+                .unwrap_or_default()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            wait,
         },
-    }))
-);
+    );
+
+    preceded(whitespace0, alt((share_mode, locking_read)))(i)
+}
+
+pub(crate) fn join_constraint(i: &str) -> IResult<&str, JoinConstraint> {
+    let using_clause = map(
+        tuple((
+            tag_no_case("using"),
+            keyword_whitespace,
+            delimited(
+                terminated(tag("("), whitespace0),
+                field_list,
+                preceded(whitespace0, tag(")")),
+            ),
+        )),
+        |t| JoinConstraint::Using(t.2),
+    );
+    let on_condition = alt((
+        delimited(
+            terminated(tag("("), whitespace0),
+            condition_expr,
+            preceded(whitespace0, tag(")")),
+        ),
+        condition_expr,
+    ));
+    let on_clause = map(
+        tuple((tag_no_case("on"), keyword_whitespace, on_condition)),
+        |t| JoinConstraint::On(t.2),
+    );
+
+    alt((using_clause, on_clause))(i)
+}
 
 /// Parse JOIN clause
-named!(join_clause<CompleteByteSlice, JoinClause>,
-    do_parse!(
-        opt_multispace >>
-        _natural: opt!(tag_no_case!("natural")) >>
-        opt_multispace >>
-        op: join_operator >>
-        multispace >>
-        right: join_rhs >>
-        multispace >>
-        constraint: alt!(
-              do_parse!(
-                  tag_no_case!("using") >>
-                  multispace >>
-                  fields: delimited!(
-                      terminated!(tag!("("), opt_multispace),
-                      field_list,
-                      preceded!(opt_multispace, tag!(")"))
-                  ) >>
-                  (JoinConstraint::Using(fields))
-              )
-            | do_parse!(
-                  tag_no_case!("on") >>
-                  multispace >>
-                  cond: alt!(
-                      delimited!(
-                          terminated!(tag!("("), opt_multispace),
-                          condition_expr,
-                          preceded!(opt_multispace, tag!(")"))
-                      )
-                      | condition_expr) >>
-                  (JoinConstraint::On(cond))
-              )
-        ) >>
-    (JoinClause {
-        operator: op,
-        right: right,
-        constraint: constraint,
-    }))
-);
+fn join_clause(i: &str) -> IResult<&str, JoinClause> {
+    let (remaining_input, (_, natural, operator, _, lateral, right, constraint)) = tuple((
+        whitespace0,
+        opt(terminated(tag_no_case("natural"), whitespace1)),
+        join_operator,
+        whitespace1,
+        opt(terminated(tag_no_case("lateral"), whitespace1)),
+        join_rhs,
+        opt(preceded(whitespace1, join_constraint)),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        JoinClause {
+            natural: natural.is_some(),
+            operator,
+            lateral: lateral.is_some(),
+            right,
+            constraint: constraint.unwrap_or(JoinConstraint::Empty),
+        },
+    ))
+}
 
 /// Different options for the right hand side of the join operator in a `join_clause`
-named!(join_rhs<CompleteByteSlice, JoinRightSide>,
-    alt!(
-          do_parse!(
-              select: delimited!(tag!("("), nested_selection, tag!(")")) >>
-              alias: opt!(as_alias) >>
-              (JoinRightSide::NestedSelect(Box::new(select), alias.map(String::from)))
-          )
-        | do_parse!(
-              nested_join: delimited!(tag!("("), join_clause, tag!(")")) >>
-              (JoinRightSide::NestedJoin(Box::new(nested_join)))
-          )
-        | do_parse!(
-              table: table_reference >>
-              (JoinRightSide::Table(table))
-          )
-        | do_parse!(
-              tables: delimited!(tag!("("), table_list, tag!(")")) >>
-              (JoinRightSide::Tables(tables))
-          )
-    )
-);
+pub fn join_rhs(i: &str) -> IResult<&str, JoinRightSide> {
+    let nested_select = map(
+        pair(
+            delimited(tag("("), nested_selection, tag(")")),
+            opt(as_alias),
+        ),
+        |t| JoinRightSide::NestedSelect(Box::new(t.0), t.1.map(String::from)),
+    );
+    let nested_join = map(
+        nested(tag("("), delimited(tag("("), join_clause, tag(")"))),
+        |nj| JoinRightSide::NestedJoin(Box::new(nj)),
+    );
+    let table = map(from_table, JoinRightSide::Table);
+    let tables = map(delimited(tag("("), table_list, tag(")")), |tables| {
+        JoinRightSide::Tables(tables)
+    });
+
+    alt((nested_select, nested_join, table, tables))(i)
+}
+
+fn table_function_argument(i: &str) -> IResult<&str, ArithmeticBase> {
+    alt((
+        map(literal, ArithmeticBase::Scalar),
+        map(user_variable, |v| ArithmeticBase::Variable(String::from(v))),
+        map(column_identifier_no_alias, ArithmeticBase::Column),
+    ))(i)
+}
+
+/// The names given to the output columns of a table function or table constructor.
+fn column_aliases(i: &str) -> IResult<&str, Vec<String>> {
+    map(
+        delimited(
+            tuple((whitespace0, tag("("), whitespace0)),
+            separated_list1(ws_sep_comma, sql_identifier),
+            pair(whitespace0, tag(")")),
+        ),
+        |columns| columns.into_iter().map(String::from).collect(),
+    )(i)
+}
+
+fn table_function_call(i: &str) -> IResult<&str, (String, Vec<ArithmeticBase>)> {
+    map(
+        pair(
+            sql_identifier,
+            delimited(
+                pair(tag("("), whitespace0),
+                separated_list0(ws_sep_comma, table_function_argument),
+                pair(whitespace0, tag(")")),
+            ),
+        ),
+        |(name, arguments)| (String::from(name), arguments),
+    )(i)
+}
+
+/// Postgres' `ROWS FROM (f(a), g(b))`.
+fn rows_from(i: &str) -> IResult<&str, Vec<(String, Vec<ArithmeticBase>)>> {
+    preceded(
+        tuple((
+            tag_no_case("rows"),
+            whitespace1,
+            tag_no_case("from"),
+            whitespace0,
+        )),
+        delimited(
+            pair(tag("("), whitespace0),
+            separated_list1(ws_sep_comma, table_function_call),
+            pair(whitespace0, tag(")")),
+        ),
+    )(i)
+}
+
+/// A set-returning function in the FROM clause, e.g. `UNNEST(tags) AS t(tag)`, possibly several
+/// in a `ROWS FROM (...)` and numbered `WITH ORDINALITY`.
+fn table_function(i: &str) -> IResult<&str, Table> {
+    let (remaining_input, (((name, arguments), rows_from), with_ordinality, alias, column_aliases)) =
+        tuple((
+            alt((
+                map(rows_from, |mut calls| (calls.remove(0), Some(calls))),
+                map(table_function_call, |call| (call, None)),
+            )),
+            opt(tuple((
+                whitespace1,
+                tag_no_case("with"),
+                whitespace1,
+                tag_no_case("ordinality"),
+            ))),
+            opt(table_alias),
+            opt(column_aliases),
+        ))(i)?;
+
+    Ok((
+        remaining_input,
+        Table {
+            name: name.into(),
+            alias: alias.as_ref().map(|a| Ident::from(&*a.0)),
+            quote: None,
+            alias_quote: alias.and_then(|a| a.1),
+            schema: None,
+            schema_quote: None,
+            function: Some(Box::new(TableFunction {
+                arguments,
+                column_aliases: column_aliases.unwrap_or_default(),
+                rows_from,
+                with_ordinality: with_ordinality.is_some(),
+            })),
+            values: None,
+            subquery: None,
+            lateral: false,
+            sample: None,
+            index_hints: vec![],
+        },
+    ))
+}
+
+/// A table constructor in the FROM clause, e.g. `(VALUES (1, 'a')) AS t(id, name)`, which must
+/// be given an alias.
+fn values_table(i: &str) -> IResult<&str, Table> {
+    let (remaining_input, (values, alias, column_aliases)) = tuple((
+        delimited(
+            pair(tag("("), whitespace0),
+            values,
+            pair(whitespace0, tag(")")),
+        ),
+        table_as_alias,
+        opt(column_aliases),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        Table {
+            name: Ident::from(alias.0),
+            quote: alias.1,
+            values: Some(Box::new(TableValues {
+                values,
+                column_aliases: column_aliases.unwrap_or_default(),
+            })),
+            ..Default::default()
+        },
+    ))
+}
+
+/// A subquery in the FROM clause, e.g. `(SELECT id FROM t) AS s`, which must be given an alias.
+fn derived_table(i: &str) -> IResult<&str, Table> {
+    let (remaining_input, (subquery, alias)) = pair(
+        delimited(
+            pair(tag("("), whitespace0),
+            nested_selection,
+            pair(whitespace0, tag(")")),
+        ),
+        table_as_alias,
+    )(i)?;
+
+    Ok((
+        remaining_input,
+        Table {
+            name: Ident::from(alias.0),
+            quote: alias.1,
+            subquery: Some(Box::new(subquery)),
+            ..Default::default()
+        },
+    ))
+}
+
+fn index_hint(i: &str) -> IResult<&str, IndexHint> {
+    let (remaining_input, (hint_type, _, _, scope, _, indexes)) = tuple((
+        alt((
+            value(IndexHintType::Use, tag_no_case("use")),
+            value(IndexHintType::Force, tag_no_case("force")),
+            value(IndexHintType::Ignore, tag_no_case("ignore")),
+        )),
+        whitespace1,
+        alt((tag_no_case("index"), tag_no_case("key"))),
+        opt(preceded(
+            tuple((whitespace1, tag_no_case("for"), whitespace1)),
+            alt((
+                value(IndexHintScope::Join, tag_no_case("join")),
+                value(
+                    IndexHintScope::OrderBy,
+                    tuple((tag_no_case("order"), whitespace1, tag_no_case("by"))),
+                ),
+                value(
+                    IndexHintScope::GroupBy,
+                    tuple((tag_no_case("group"), whitespace1, tag_no_case("by"))),
+                ),
+            )),
+        )),
+        whitespace0,
+        delimited(
+            pair(tag("("), whitespace0),
+            separated_list0(
+                ws_sep_comma,
+                alt((sql_identifier, map(tag_no_case("primary"), Cow::Borrowed))),
+            ),
+            pair(whitespace0, tag(")")),
+        ),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        IndexHint {
+            hint_type,
+            scope,
+            indexes: indexes.into_iter().map(String::from).collect(),
+        },
+    ))
+}
+
+fn table_sample(i: &str) -> IResult<&str, TableSample> {
+    let (remaining_input, (_, _, method, _, arguments, repeatable)) = tuple((
+        tag_no_case("tablesample"),
+        whitespace1,
+        // SYSTEM is a reserved word, so the built-in methods are matched before other names
+        alt((
+            map(recognize(keyword_phrase(&["bernoulli"])), Cow::Borrowed),
+            map(recognize(keyword_phrase(&["system"])), Cow::Borrowed),
+            sql_identifier,
+        )),
+        whitespace0,
+        delimited(
+            pair(tag("("), whitespace0),
+            separated_list1(ws_sep_comma, literal),
+            pair(whitespace0, tag(")")),
+        ),
+        opt(preceded(
+            tuple((whitespace1, tag_no_case("repeatable"), whitespace0)),
+            delimited(
+                pair(tag("("), whitespace0),
+                literal,
+                pair(whitespace0, tag(")")),
+            ),
+        )),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        TableSample {
+            method: method.to_uppercase(),
+            arguments,
+            repeatable,
+        },
+    ))
+}
+
+/// Like `table_as_alias`, but does not take `TABLESAMPLE` or an index hint for an alias.
+fn table_alias(i: &str) -> IResult<&str, QuotedName<'_>> {
+    preceded(
+        not(pair(
+            whitespace1,
+            alt((recognize(table_sample), recognize(index_hint))),
+        )),
+        table_as_alias,
+    )(i)
+}
+
+fn named_table(i: &str) -> IResult<&str, Table> {
+    map(pair(table_name, opt(table_alias)), |(table, alias)| Table {
+        alias: alias.as_ref().map(|a| Ident::from(&*a.0)),
+        alias_quote: alias.and_then(|a| a.1),
+        ..table
+    })(i)
+}
+
+/// A table or table function, with an optional sample and index hints.
+fn sampled_table(i: &str) -> IResult<&str, Table> {
+    let (remaining_input, (table, sample, index_hints)) = tuple((
+        alt((table_function, named_table)),
+        opt(preceded(whitespace1, table_sample)),
+        many0(preceded(whitespace1, index_hint)),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        Table {
+            sample,
+            index_hints,
+            ..table
+        },
+    ))
+}
+
+fn from_table(i: &str) -> IResult<&str, Table> {
+    alt((lateral_table, values_table, derived_table, sampled_table))(i)
+}
+
+/// A `LATERAL` subquery or table function, which may refer to the tables before it.
+fn lateral_table(i: &str) -> IResult<&str, Table> {
+    map(
+        preceded(
+            pair(tag_no_case("lateral"), whitespace1),
+            alt((derived_table, table_function)),
+        ),
+        |table| Table {
+            lateral: true,
+            ..table
+        },
+    )(i)
+}
+
+/// Parse the comma-separated tables of a FROM clause, which may include table functions.
+pub(crate) fn from_list(i: &str) -> IResult<&str, Vec<Table>> {
+    many0(terminated(from_table, opt(ws_sep_comma)))(i)
+}
 
 /// Parse WHERE clause of a selection
-named!(pub where_clause<CompleteByteSlice, ConditionExpression>,
-    do_parse!(
-        opt_multispace >>
-        tag_no_case!("where") >>
-        multispace >>
-        cond: condition_expr >>
-        (cond)
-    )
-);
+pub fn where_clause(i: &str) -> IResult<&str, ConditionExpression> {
+    let (remaining_input, (_, _, _, where_condition)) = tuple((
+        whitespace0,
+        tag_no_case("where"),
+        keyword_whitespace,
+        condition_expr,
+    ))(i)?;
+
+    Ok((remaining_input, where_condition))
+}
+
+fn select_modifier(i: &str) -> IResult<&str, SelectModifiers> {
+    alt((
+        value(SelectModifiers::HIGH_PRIORITY, tag_no_case("high_priority")),
+        value(SelectModifiers::STRAIGHT_JOIN, tag_no_case("straight_join")),
+        value(
+            SelectModifiers::SQL_SMALL_RESULT,
+            tag_no_case("sql_small_result"),
+        ),
+        value(
+            SelectModifiers::SQL_BIG_RESULT,
+            tag_no_case("sql_big_result"),
+        ),
+        value(
+            SelectModifiers::SQL_BUFFER_RESULT,
+            tag_no_case("sql_buffer_result"),
+        ),
+        value(SelectModifiers::SQL_CACHE, tag_no_case("sql_cache")),
+        value(SelectModifiers::SQL_NO_CACHE, tag_no_case("sql_no_cache")),
+        value(
+            SelectModifiers::SQL_CALC_FOUND_ROWS,
+            tag_no_case("sql_calc_found_rows"),
+        ),
+    ))(i)
+}
+
+/// Parse any number of whitespace-terminated select modifiers.
+fn select_modifiers(i: &str) -> IResult<&str, SelectModifiers> {
+    fold_many0(
+        terminated(select_modifier, whitespace1),
+        SelectModifiers::empty,
+        |acc, m| acc | m,
+    )(i)
+}
+
+/// SQL Server `TOP n` or `TOP (n)`.
+fn top_clause(i: &str) -> IResult<&str, u64> {
+    only_in(
+        Dialect::SqlServer,
+        delimited(
+            pair(tag_no_case("top"), whitespace0),
+            alt((
+                delimited(
+                    pair(tag("("), whitespace0),
+                    unsigned_number,
+                    pair(whitespace0, tag(")")),
+                ),
+                terminated(unsigned_number, whitespace1),
+            )),
+            whitespace0,
+        ),
+    )(i)
+}
 
 /// Parse rule for a SQL selection query.
-named!(pub selection<CompleteByteSlice, SelectStatement>,
-    do_parse!(
-        select: nested_selection >>
-        statement_terminator >>
-        (select)
-    )
-);
-
-named!(pub nested_selection<CompleteByteSlice, SelectStatement>,
-    do_parse!(
-        tag_no_case!("select") >>
-        multispace >>
-        distinct: opt!(tag_no_case!("distinct")) >>
-        opt_multispace >>
-        fields: field_definition_expr >>
-        delimited!(opt_multispace, tag_no_case!("from"), opt_multispace) >>
-        tables: table_list >>
-        join: many0!(join_clause) >>
-        cond: opt!(where_clause) >>
-        group_by: opt!(group_by_clause) >>
-        order: opt!(order_clause) >>
-        limit: opt!(limit_clause) >>
-        (SelectStatement {
-            tables: tables,
+pub fn selection(i: &str) -> IResult<&str, SelectStatement> {
+    terminated(select_statement, statement_terminator)(i)
+}
+
+pub fn nested_selection(i: &str) -> IResult<&str, SelectStatement> {
+    nested(tag_no_case("select"), select_statement)(i)
+}
+
+/// A selection that is not nested in another construct, so does not count towards the nesting
+/// limit.
+pub(crate) fn select_statement(i: &str) -> IResult<&str, SelectStatement> {
+    let (
+        remaining_input,
+        (
+            _,
+            _,
+            leading,
+            distinct,
+            _,
+            top,
+            trailing,
+            fields,
+            early_into,
+            tables,
+            join,
+            where_clause,
+            group_by,
+            order,
+            limit,
+            late_into,
+            lock,
+            last_into,
+        ),
+    ) = tuple((
+        tag_no_case("select"),
+        keyword_whitespace,
+        select_modifiers,
+        opt(tag_no_case("distinct")),
+        whitespace0,
+        opt(top_clause),
+        select_modifiers,
+        field_definition_expr,
+        // MySQL accepts INTO either before FROM or at the very end of the statement
+        opt(preceded(whitespace0, into_clause)),
+        // a selection of expressions alone, as in `SELECT set_config(...)`, reads no table
+        map(
+            opt(preceded(
+                delimited(whitespace0, tag_no_case("from"), keyword_whitespace),
+                from_list,
+            )),
+            Option::unwrap_or_default,
+        ),
+        many0(join_clause),
+        opt(where_clause),
+        opt(group_by_clause),
+        opt(order_clause),
+        opt(limit_clause),
+        opt(preceded(whitespace0, into_clause)),
+        opt(locking_clause),
+        opt(preceded(whitespace0, into_clause)),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        SelectStatement {
+            tables,
             distinct: distinct.is_some(),
-            fields: fields,
-            join: join,
-            where_clause: cond,
-            group_by: group_by,
-            order: order,
-            limit: limit,
-        })
-    )
-);
+            modifiers: leading | trailing,
+            fields,
+            join,
+            where_clause,
+            group_by,
+            order,
+            limit,
+            into: early_into.or(late_into).or(last_into),
+            top,
+            lock,
+        },
+    ))
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use column::{Column, FunctionExpression};
-    use common::{FieldDefinitionExpression, FieldValueExpression, Literal, Operator};
+    use common::{
+        FieldDefinitionExpression, FieldValueExpression, Literal, Operator, VariableExpression,
+    };
     use condition::ConditionBase::*;
     use condition::ConditionExpression::*;
-    use condition::ConditionTree;
+    use condition::{ConditionTree, PredicateExpression};
+    use dialect::with_dialect;
+    use format::QuoteStyle;
     use order::OrderType;
     use table::Table;
+    use values::ValuesStatement;
 
     fn columns(cols: &[&str]) -> Vec<FieldDefinitionExpression> {
         cols.iter()
@@ -311,7 +953,7 @@ mod tests {
     fn simple_select() {
         let qstring = "SELECT id, name FROM users;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         assert_eq!(
             res.unwrap().1,
             SelectStatement {
@@ -322,11 +964,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn select_without_from() {
+        let qstring = "SELECT nextval('ids');";
+
+        let res = selection(qstring).unwrap().1;
+        assert!(res.tables.is_empty());
+        assert_eq!(format!("{}", res), "SELECT nextval('ids')");
+    }
+
+    #[test]
+    fn predicates_in_field_list() {
+        let qstring = "SELECT id, age >= 18 AS adult, NOT banned FROM users";
+
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(res.fields.len(), 3);
+        assert_eq!(
+            res.fields[1],
+            FieldDefinitionExpression::Value(FieldValueExpression::Predicate(
+                PredicateExpression {
+                    condition: ComparisonOp(ConditionTree {
+                        operator: Operator::GreaterOrEqual,
+                        left: Box::new(Base(Field(Column::from("age")))),
+                        right: Box::new(Base(Literal(18.into()))),
+                    }),
+                    alias: Some(String::from("adult")),
+                }
+            ))
+        );
+        assert_eq!(
+            format!("{}", res),
+            "SELECT id, age >= 18 AS adult, NOT banned FROM users"
+        );
+
+        // plain operands are still parsed as what they are
+        let res = selection("SELECT a, a + 1 AS b FROM t").unwrap().1;
+        assert!(matches!(
+            res.fields[1],
+            FieldDefinitionExpression::Value(FieldValueExpression::Arithmetic(_))
+        ));
+    }
+
+    #[test]
+    fn predicates_and_parenthesized_operands_in_field_list() {
+        for (qstring, expected) in &[
+            ("SELECT data @> '{}' FROM t", "SELECT data @> '{}' FROM t"),
+            ("SELECT data ? 'k' FROM t", "SELECT data ? 'k' FROM t"),
+            ("SELECT (a = 1) AS x", "SELECT (a = 1) AS x"),
+            ("SELECT (a = 1) x", "SELECT (a = 1) AS x"),
+            (
+                "SELECT a NOT IN (1, 2) FROM t",
+                "SELECT a NOT IN (1, 2) FROM t",
+            ),
+        ] {
+            let res = selection(qstring).unwrap().1;
+            assert!(
+                matches!(
+                    res.fields[0],
+                    FieldDefinitionExpression::Value(FieldValueExpression::Predicate(_))
+                ),
+                "{}",
+                qstring
+            );
+            assert_eq!(&res.to_string(), expected);
+        }
+
+        // parentheses around a plain operand are left out, along with any alias after them
+        let res = selection("SELECT (a + b) FROM t").unwrap().1;
+        assert_eq!(res, selection("SELECT a + b FROM t").unwrap().1);
+        let res = selection("SELECT (max(a)) AS m, (1) FROM t").unwrap().1;
+        assert_eq!(res, selection("SELECT max(a) AS m, 1 FROM t").unwrap().1);
+    }
+
+    #[test]
+    fn row_comparisons_in_field_list() {
+        let res = selection("SELECT ROW(1, 2) = (a, b) FROM t").unwrap().1;
+        assert_eq!(res.to_string(), "SELECT ROW(1, 2) = (a, b) FROM t");
+        assert_eq!(selection(&res.to_string()).unwrap().1, res);
+    }
+
     #[test]
     fn more_involved_select() {
         let qstring = "SELECT users.id, users.name FROM users;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         assert_eq!(
             res.unwrap().1,
             SelectStatement {
@@ -343,7 +1064,7 @@ mod tests {
 
         let qstring = "SELECT NULL, 1, \"foo\", CURRENT_TIME FROM users;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         assert_eq!(
             res.unwrap().1,
             SelectStatement {
@@ -371,7 +1092,7 @@ mod tests {
     fn select_all() {
         let qstring = "SELECT * FROM users;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         assert_eq!(
             res.unwrap().1,
             SelectStatement {
@@ -386,7 +1107,7 @@ mod tests {
     fn select_all_in_table() {
         let qstring = "SELECT users.* FROM users, votes;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         assert_eq!(
             res.unwrap().1,
             SelectStatement {
@@ -397,11 +1118,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn select_all_in_table_with_join() {
+        let qstring = "SELECT t.*, u.name, `u`.* FROM t JOIN u ON t.id = u.tid";
+
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(
+            res.fields,
+            vec![
+                FieldDefinitionExpression::AllInTable(String::from("t")),
+                FieldDefinitionExpression::Col(Column::from("u.name")),
+                FieldDefinitionExpression::AllInTable(String::from("u")),
+            ]
+        );
+        assert_eq!(
+            res.to_string(),
+            "SELECT t.*, u.name, u.* FROM t JOIN u ON t.id = u.tid"
+        );
+
+        // The qualifier is a bare table name or alias, never an aliased table.
+        assert!(selection("SELECT t AS x.* FROM t AS x").is_err());
+    }
+
     #[test]
     fn spaces_optional() {
         let qstring = "SELECT id,name FROM users;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         assert_eq!(
             res.unwrap().1,
             SelectStatement {
@@ -418,8 +1161,8 @@ mod tests {
         let qstring_uc = "SELECT id, name FROM users;";
 
         assert_eq!(
-            selection(CompleteByteSlice(qstring_lc.as_bytes())).unwrap(),
-            selection(CompleteByteSlice(qstring_uc.as_bytes())).unwrap()
+            selection(qstring_lc).unwrap(),
+            selection(qstring_uc).unwrap()
         );
     }
 
@@ -428,8 +1171,8 @@ mod tests {
         let qstring_sem = "select id, name from users;";
         let qstring_linebreak = "select id, name from users\n";
 
-        let r1 = selection(CompleteByteSlice(qstring_sem.as_bytes())).unwrap();
-        let r2 = selection(CompleteByteSlice(qstring_linebreak.as_bytes())).unwrap();
+        let r1 = selection(qstring_sem).unwrap();
+        let r2 = selection(qstring_linebreak).unwrap();
         assert_eq!(r1, r2);
     }
 
@@ -437,7 +1180,7 @@ mod tests {
     fn where_clause() {
         let qstring = "select * from ContactInfo where email=?;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
 
         let expected_left = Base(Field(Column::from("email")));
         let expected_where_cond = Some(ComparisonOp(ConditionTree {
@@ -470,31 +1213,164 @@ mod tests {
             offset: 10,
         };
 
-        let res1 = selection(CompleteByteSlice(qstring1.as_bytes()));
-        let res2 = selection(CompleteByteSlice(qstring2.as_bytes()));
+        let res1 = selection(qstring1);
+        let res2 = selection(qstring2);
         assert_eq!(res1.unwrap().1.limit, Some(expected_lim1));
         assert_eq!(res2.unwrap().1.limit, Some(expected_lim2));
     }
 
+    #[test]
+    fn quoted_table_names() {
+        let res = selection("SELECT * FROM \"order items\" AS [i], `users` \"u\", t;")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res.tables,
+            vec![
+                Table {
+                    quote: Some(QuoteStyle::DoubleQuote),
+                    alias: Some("i".into()),
+                    alias_quote: Some(QuoteStyle::Bracket),
+                    ..Table::from("order items")
+                },
+                Table {
+                    quote: Some(QuoteStyle::Backtick),
+                    alias: Some("u".into()),
+                    alias_quote: Some(QuoteStyle::DoubleQuote),
+                    ..Table::from("users")
+                },
+                Table::from("t"),
+            ]
+        );
+        // names keep the quotes they were written in, and unquoted ones are only quoted if needed
+        assert_eq!(
+            res.to_string(),
+            "SELECT * FROM \"order items\" AS [i], `users` AS \"u\", t"
+        );
+        assert!(selection("SELECT * FROM \"users").is_err());
+    }
+
+    #[test]
+    fn schema_qualified_tables() {
+        let res = selection("SELECT * FROM public.users AS u, \"db\".\"order\", t")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res.tables,
+            vec![
+                Table {
+                    schema: Some("public".into()),
+                    alias: Some("u".into()),
+                    ..Table::from("users")
+                },
+                Table {
+                    schema: Some("db".into()),
+                    schema_quote: Some(QuoteStyle::DoubleQuote),
+                    quote: Some(QuoteStyle::DoubleQuote),
+                    ..Table::from("order")
+                },
+                Table::from("t"),
+            ]
+        );
+        assert_eq!(
+            res.to_string(),
+            "SELECT * FROM public.users AS u, \"db\".\"order\", t"
+        );
+    }
+
+    #[test]
+    fn quoted_table_names_with_doubled_quotes() {
+        let res = selection("SELECT * FROM \"t\"\"x\" AS \"a\"\"\", `t``x` [a]]], [t]]x]")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res.tables,
+            vec![
+                Table {
+                    quote: Some(QuoteStyle::DoubleQuote),
+                    alias: Some("a\"".into()),
+                    alias_quote: Some(QuoteStyle::DoubleQuote),
+                    ..Table::from("t\"x")
+                },
+                Table {
+                    quote: Some(QuoteStyle::Backtick),
+                    alias: Some("a]".into()),
+                    alias_quote: Some(QuoteStyle::Bracket),
+                    ..Table::from("t`x")
+                },
+                Table {
+                    quote: Some(QuoteStyle::Bracket),
+                    ..Table::from("t]x")
+                },
+            ]
+        );
+        assert_eq!(
+            res.to_string(),
+            "SELECT * FROM \"t\"\"x\" AS \"a\"\"\", `t``x` AS [a]]], [t]]x]"
+        );
+        assert!(selection("SELECT * FROM \"\"").is_err());
+    }
+
     #[test]
     fn table_alias() {
         let qstring1 = "select * from PaperTag as t;";
-        // let qstring2 = "select * from PaperTag t;";
+        let qstring2 = "select * from PaperTag t;";
 
-        let res1 = selection(CompleteByteSlice(qstring1.as_bytes()));
+        let res1 = selection(qstring1);
         assert_eq!(
-            res1.clone().unwrap().1,
+            res1.unwrap().1,
             SelectStatement {
                 tables: vec![Table {
-                    name: String::from("PaperTag"),
-                    alias: Some(String::from("t")),
+                    name: "PaperTag".into(),
+                    alias: Some("t".into()),
+                    quote: None,
+                    alias_quote: None,
+                    schema: None,
+                    schema_quote: None,
+                    function: None,
+                    values: None,
+                    subquery: None,
+                    lateral: false,
+                    sample: None,
+                    index_hints: vec![],
                 },],
                 fields: vec![FieldDefinitionExpression::All],
                 ..Default::default()
             }
         );
-        // let res2 = selection(qstring2.as_bytes());
-        // assert_eq!(res1.unwrap().1, res2.unwrap().1);
+        let res2 = selection(qstring2);
+        assert_eq!(res2.unwrap().1, selection(qstring1).unwrap().1);
+    }
+
+    #[test]
+    fn non_reserved_keyword_identifiers() {
+        let qstring =
+            "SELECT status, end, a AS offset FROM events WHERE action = 1 LIMIT 1 OFFSET 2";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(
+            res.fields[1],
+            FieldDefinitionExpression::Col(Column::from("end"))
+        );
+        assert_eq!(res.limit.as_ref().map(|l| l.offset), Some(2));
+        assert_eq!(res.to_string(), qstring);
+
+        // Without AS, a non-reserved keyword is not taken for an alias.
+        assert!(selection("SELECT a end FROM events").is_err());
+    }
+
+    #[test]
+    fn implicit_aliases() {
+        let qstring = "SELECT count(*) cnt, o.total + 1 t FROM orders o WHERE o.id = 1";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(res.tables[0].alias, Some("o".into()));
+        assert_eq!(
+            res.to_string(),
+            "SELECT count(*) AS cnt, o.total + 1 AS t FROM orders AS o WHERE o.id = 1"
+        );
+
+        // Reserved words never become implicit aliases, but may be quoted.
+        assert!(selection("SELECT name select FROM users").is_err());
+        assert!(selection("SELECT name `select` FROM users").is_ok());
     }
 
     #[test]
@@ -502,29 +1378,29 @@ mod tests {
         let qstring1 = "select name as TagName from PaperTag;";
         let qstring2 = "select PaperTag.name as TagName from PaperTag;";
 
-        let res1 = selection(CompleteByteSlice(qstring1.as_bytes()));
+        let res1 = selection(qstring1);
         assert_eq!(
-            res1.clone().unwrap().1,
+            res1.unwrap().1,
             SelectStatement {
                 tables: vec![Table::from("PaperTag")],
                 fields: vec![FieldDefinitionExpression::Col(Column {
-                    name: String::from("name"),
-                    alias: Some(String::from("TagName")),
+                    name: "name".into(),
+                    alias: Some("TagName".into()),
                     table: None,
                     function: None,
                 }),],
                 ..Default::default()
             }
         );
-        let res2 = selection(CompleteByteSlice(qstring2.as_bytes()));
+        let res2 = selection(qstring2);
         assert_eq!(
-            res2.clone().unwrap().1,
+            res2.unwrap().1,
             SelectStatement {
                 tables: vec![Table::from("PaperTag")],
                 fields: vec![FieldDefinitionExpression::Col(Column {
-                    name: String::from("name"),
-                    alias: Some(String::from("TagName")),
-                    table: Some(String::from("PaperTag")),
+                    name: "name".into(),
+                    alias: Some("TagName".into()),
+                    table: Some("PaperTag".into()),
                     function: None,
                 }),],
                 ..Default::default()
@@ -537,29 +1413,29 @@ mod tests {
         let qstring1 = "select name TagName from PaperTag;";
         let qstring2 = "select PaperTag.name TagName from PaperTag;";
 
-        let res1 = selection(CompleteByteSlice(qstring1.as_bytes()));
+        let res1 = selection(qstring1);
         assert_eq!(
-            res1.clone().unwrap().1,
+            res1.unwrap().1,
             SelectStatement {
                 tables: vec![Table::from("PaperTag")],
                 fields: vec![FieldDefinitionExpression::Col(Column {
-                    name: String::from("name"),
-                    alias: Some(String::from("TagName")),
+                    name: "name".into(),
+                    alias: Some("TagName".into()),
                     table: None,
                     function: None,
                 }),],
                 ..Default::default()
             }
         );
-        let res2 = selection(CompleteByteSlice(qstring2.as_bytes()));
+        let res2 = selection(qstring2);
         assert_eq!(
-            res2.clone().unwrap().1,
+            res2.unwrap().1,
             SelectStatement {
                 tables: vec![Table::from("PaperTag")],
                 fields: vec![FieldDefinitionExpression::Col(Column {
-                    name: String::from("name"),
-                    alias: Some(String::from("TagName")),
-                    table: Some(String::from("PaperTag")),
+                    name: "name".into(),
+                    alias: Some("TagName".into()),
+                    table: Some("PaperTag".into()),
                     function: None,
                 }),],
                 ..Default::default()
@@ -571,7 +1447,7 @@ mod tests {
     fn distinct() {
         let qstring = "select distinct tag from PaperTag where paperId=?;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let expected_left = Base(Field(Column::from("paperId")));
         let expected_where_cond = Some(ComparisonOp(ConditionTree {
             left: Box::new(expected_left),
@@ -594,7 +1470,7 @@ mod tests {
     fn simple_condition_expr() {
         let qstring = "select infoJson from PaperStorage where paperId=? and paperStorageId=?;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
 
         let left_ct = ConditionTree {
             left: Box::new(Base(Field(Column::from("paperId")))),
@@ -627,7 +1503,7 @@ mod tests {
     #[test]
     fn where_and_limit_clauses() {
         let qstring = "select * from users where id = ? limit 10\n";
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
 
         let expected_lim = Some(LimitClause {
             limit: 10,
@@ -656,14 +1532,14 @@ mod tests {
     fn aggregation_column() {
         let qstring = "SELECT max(addr_id) FROM address;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let agg_expr = FunctionExpression::Max(Column::from("addr_id"));
         assert_eq!(
             res.unwrap().1,
             SelectStatement {
                 tables: vec![Table::from("address")],
                 fields: vec![FieldDefinitionExpression::Col(Column {
-                    name: String::from("max(addr_id)"),
+                    name: "max(addr_id)".into(),
                     alias: None,
                     table: None,
                     function: Some(Box::new(agg_expr)),
@@ -677,13 +1553,13 @@ mod tests {
     fn aggregation_column_with_alias() {
         let qstring = "SELECT max(addr_id) AS max_addr FROM address;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let agg_expr = FunctionExpression::Max(Column::from("addr_id"));
         let expected_stmt = SelectStatement {
             tables: vec![Table::from("address")],
             fields: vec![FieldDefinitionExpression::Col(Column {
-                name: String::from("max_addr"),
-                alias: Some(String::from("max_addr")),
+                name: "max_addr".into(),
+                alias: Some("max_addr".into()),
                 table: None,
                 function: Some(Box::new(agg_expr)),
             })],
@@ -696,18 +1572,18 @@ mod tests {
     fn count_all() {
         let qstring = "SELECT COUNT(*) FROM votes GROUP BY aid;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let agg_expr = FunctionExpression::CountStar;
         let expected_stmt = SelectStatement {
             tables: vec![Table::from("votes")],
             fields: vec![FieldDefinitionExpression::Col(Column {
-                name: String::from("count(*)"),
+                name: "count(*)".into(),
                 alias: None,
                 table: None,
                 function: Some(Box::new(agg_expr)),
             })],
             group_by: Some(GroupByClause {
-                columns: vec![Column::from("aid")],
+                columns: vec!["aid".into()],
                 having: None,
             }),
             ..Default::default()
@@ -719,18 +1595,18 @@ mod tests {
     fn count_distinct() {
         let qstring = "SELECT COUNT(DISTINCT vote_id) FROM votes GROUP BY aid;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let agg_expr = FunctionExpression::Count(Column::from("vote_id"), true);
         let expected_stmt = SelectStatement {
             tables: vec![Table::from("votes")],
             fields: vec![FieldDefinitionExpression::Col(Column {
-                name: String::from("count(distinct vote_id)"),
+                name: "count(distinct vote_id)".into(),
                 alias: None,
                 table: None,
                 function: Some(Box::new(agg_expr)),
             })],
             group_by: Some(GroupByClause {
-                columns: vec![Column::from("aid")],
+                columns: vec!["aid".into()],
                 having: None,
             }),
             ..Default::default()
@@ -738,12 +1614,83 @@ mod tests {
         assert_eq!(res.unwrap().1, expected_stmt);
     }
 
+    #[test]
+    fn sequence_nextval() {
+        let qstring = "SELECT nextval('public.order_ids') AS id, NEXT VALUE FOR s FROM dual;";
+
+        let res = selection(qstring);
+        let expected_stmt = SelectStatement {
+            tables: vec![Table::from("dual")],
+            fields: vec![
+                FieldDefinitionExpression::Col(Column {
+                    name: "id".into(),
+                    alias: Some("id".into()),
+                    table: None,
+                    function: Some(Box::new(FunctionExpression::NextVal(String::from(
+                        "public.order_ids",
+                    )))),
+                }),
+                FieldDefinitionExpression::Col(Column {
+                    name: "nextval('s')".into(),
+                    alias: None,
+                    table: None,
+                    function: Some(Box::new(FunctionExpression::NextVal(String::from("s")))),
+                }),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(res.unwrap().1, expected_stmt);
+    }
+
+    #[test]
+    fn user_variables() {
+        let qstring = "SELECT @total := COUNT(*) AS n, @prev FROM votes WHERE aid = @aid;";
+
+        let res = selection(qstring);
+        let expected_stmt = SelectStatement {
+            tables: vec![Table::from("votes")],
+            fields: vec![
+                FieldDefinitionExpression::Value(FieldValueExpression::Variable(
+                    VariableExpression {
+                        name: String::from("total"),
+                        value: Some(Box::new(FieldDefinitionExpression::Col(Column {
+                            name: "count(*)".into(),
+                            alias: None,
+                            table: None,
+                            function: Some(Box::new(FunctionExpression::CountStar)),
+                        }))),
+                        alias: Some(String::from("n")),
+                    },
+                )),
+                FieldDefinitionExpression::Value(FieldValueExpression::Variable(
+                    VariableExpression {
+                        name: String::from("prev"),
+                        value: None,
+                        alias: None,
+                    },
+                )),
+            ],
+            where_clause: Some(ComparisonOp(ConditionTree {
+                operator: Operator::Equal,
+                left: Box::new(Base(Field(Column::from("aid")))),
+                right: Box::new(Base(Variable(String::from("aid")))),
+            })),
+            ..Default::default()
+        };
+        let res = res.unwrap().1;
+        assert_eq!(res, expected_stmt);
+        assert_eq!(
+            format!("{}", res),
+            "SELECT @total := count(*) AS n, @prev FROM votes WHERE aid = @aid"
+        );
+    }
+
     #[test]
     fn moderately_complex_selection() {
         let qstring = "SELECT * FROM item, author WHERE item.i_a_id = author.a_id AND \
                        item.i_subject = ? ORDER BY item.i_title limit 50;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let expected_where_cond = Some(LogicalOp(ConditionTree {
             left: Box::new(ComparisonOp(ConditionTree {
                 left: Box::new(Base(Field(Column::from("item.i_a_id")))),
@@ -779,12 +1726,14 @@ mod tests {
     fn simple_joins() {
         let qstring = "select paperId from PaperConflict join PCMember using (contactId);";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let expected_stmt = SelectStatement {
             tables: vec![Table::from("PaperConflict")],
             fields: columns(&["paperId"]),
             join: vec![JoinClause {
                 operator: JoinOperator::Join,
+                natural: false,
+                lateral: false,
                 right: JoinRightSide::Table(Table::from("PCMember")),
                 constraint: JoinConstraint::Using(vec![Column::from("contactId")]),
             }],
@@ -801,7 +1750,7 @@ mod tests {
                        join PaperReview on (PCMember.contactId=PaperReview.contactId) \
                        order by contactId;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let ct = ConditionTree {
             left: Box::new(Base(Field(Column::from("PCMember.contactId")))),
             right: Box::new(Base(Field(Column::from("PaperReview.contactId")))),
@@ -813,6 +1762,8 @@ mod tests {
             fields: columns(&["PCMember.contactId"]),
             join: vec![JoinClause {
                 operator: JoinOperator::Join,
+                natural: false,
+                lateral: false,
                 right: JoinRightSide::Table(Table::from("PaperReview")),
                 constraint: JoinConstraint::On(join_cond),
             }],
@@ -828,7 +1779,7 @@ mod tests {
                        from PCMember \
                        join PaperReview on PCMember.contactId=PaperReview.contactId \
                        order by contactId;";
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         assert_eq!(res.unwrap().1, expected);
     }
 
@@ -848,7 +1799,7 @@ mod tests {
                        (contactId) left join ChairAssistant using (contactId) left join Chair \
                        using (contactId) where ContactInfo.contactId=?;";
 
-        let res = selection(CompleteByteSlice(qstring.as_bytes()));
+        let res = selection(qstring);
         let ct = ConditionTree {
             left: Box::new(Base(Field(Column::from("ContactInfo.contactId")))),
             right: Box::new(Base(Literal(Literal::Placeholder))),
@@ -858,6 +1809,8 @@ mod tests {
         let mkjoin = |tbl: &str, col: &str| -> JoinClause {
             JoinClause {
                 operator: JoinOperator::LeftJoin,
+                natural: false,
+                lateral: false,
                 right: JoinRightSide::Table(Table::from(tbl)),
                 constraint: JoinConstraint::Using(vec![Column::from(col)]),
             }
@@ -890,7 +1843,7 @@ mod tests {
                     WHERE orders.o_c_id IN (SELECT o_c_id FROM orders, order_line \
                     WHERE orders.o_id = order_line.ol_o_id);";
 
-        let res = selection(CompleteByteSlice(qstr.as_bytes()));
+        let res = selection(qstr);
         let inner_where_clause = ComparisonOp(ConditionTree {
             left: Box::new(Base(Field(Column::from("orders.o_id")))),
             right: Box::new(Base(Field(Column::from("order_line.ol_o_id")))),
@@ -927,13 +1880,13 @@ mod tests {
                     WHERE orders.o_id = order_line.ol_o_id \
                     AND orders.o_id > (SELECT MAX(o_id) FROM orders));";
 
-        let res = selection(CompleteByteSlice(qstr.as_bytes()));
+        let res = selection(qstr);
 
         let agg_expr = FunctionExpression::Max(Column::from("o_id"));
         let recursive_select = SelectStatement {
             tables: vec![Table::from("orders")],
             fields: vec![FieldDefinitionExpression::Col(Column {
-                name: String::from("max(o_id)"),
+                name: "max(o_id)".into(),
                 alias: None,
                 table: None,
                 function: Some(Box::new(agg_expr)),
@@ -984,22 +1937,22 @@ mod tests {
 
     #[test]
     fn join_against_nested_select() {
-        let t0 = b"(SELECT ol_i_id FROM order_line)";
-        let t1 = b"(SELECT ol_i_id FROM order_line) AS ids";
+        let t0 = "(SELECT ol_i_id FROM order_line)";
+        let t1 = "(SELECT ol_i_id FROM order_line) AS ids";
 
-        assert!(join_rhs(CompleteByteSlice(t0)).is_ok());
-        assert!(join_rhs(CompleteByteSlice(t1)).is_ok());
+        assert!(join_rhs(t0).is_ok());
+        assert!(join_rhs(t1).is_ok());
 
-        let t0 = b"JOIN (SELECT ol_i_id FROM order_line) ON (orders.o_id = ol_i_id)";
-        let t1 = b"JOIN (SELECT ol_i_id FROM order_line) AS ids ON (orders.o_id = ids.ol_i_id)";
+        let t0 = "JOIN (SELECT ol_i_id FROM order_line) ON (orders.o_id = ol_i_id)";
+        let t1 = "JOIN (SELECT ol_i_id FROM order_line) AS ids ON (orders.o_id = ids.ol_i_id)";
 
-        assert!(join_clause(CompleteByteSlice(t0)).is_ok());
-        assert!(join_clause(CompleteByteSlice(t1)).is_ok());
+        assert!(join_clause(t0).is_ok());
+        assert!(join_clause(t1).is_ok());
 
         let qstr_with_alias = "SELECT o_id, ol_i_id FROM orders JOIN \
                                (SELECT ol_i_id FROM order_line) AS ids \
                                ON (orders.o_id = ids.ol_i_id);";
-        let res = selection(CompleteByteSlice(qstr_with_alias.as_bytes()));
+        let res = selection(qstr_with_alias);
 
         // N.B.: Don't alias the inner select to `inner`, which is, well, a SQL keyword!
         let inner_select = SelectStatement {
@@ -1013,6 +1966,8 @@ mod tests {
             fields: columns(&["o_id", "ol_i_id"]),
             join: vec![JoinClause {
                 operator: JoinOperator::Join,
+                natural: false,
+                lateral: false,
                 right: JoinRightSide::NestedSelect(Box::new(inner_select), Some("ids".into())),
                 constraint: JoinConstraint::On(ComparisonOp(ConditionTree {
                     operator: Operator::Equal,
@@ -1031,7 +1986,7 @@ mod tests {
         use arithmetic::{ArithmeticBase, ArithmeticExpression, ArithmeticOperator};
 
         let qstr = "SELECT MAX(o_id)-3333 FROM orders;";
-        let res = selection(CompleteByteSlice(qstr.as_bytes()));
+        let res = selection(qstr);
 
         let expected = SelectStatement {
             tables: vec![Table::from("orders")],
@@ -1040,7 +1995,7 @@ mod tests {
                     alias: None,
                     op: ArithmeticOperator::Subtract,
                     left: ArithmeticBase::Column(Column {
-                        name: String::from("max(o_id)"),
+                        name: "max(o_id)".into(),
                         alias: None,
                         table: None,
                         function: Some(Box::new(FunctionExpression::Max("o_id".into()))),
@@ -1059,7 +2014,7 @@ mod tests {
         use arithmetic::{ArithmeticBase, ArithmeticExpression, ArithmeticOperator};
 
         let qstr = "SELECT max(o_id) * 2 as double_max FROM orders;";
-        let res = selection(CompleteByteSlice(qstr.as_bytes()));
+        let res = selection(qstr);
 
         let expected = SelectStatement {
             tables: vec![Table::from("orders")],
@@ -1068,7 +2023,7 @@ mod tests {
                     alias: Some(String::from("double_max")),
                     op: ArithmeticOperator::Multiply,
                     left: ArithmeticBase::Column(Column {
-                        name: String::from("max(o_id)"),
+                        name: "max(o_id)".into(),
                         alias: None,
                         table: None,
                         function: Some(Box::new(FunctionExpression::Max("o_id".into()))),
@@ -1089,7 +2044,7 @@ mod tests {
                     JOIN `django_content_type`
                       ON ( `auth_permission`.`content_type_id` = `django_content_type`.`id` )
                     WHERE `auth_permission`.`content_type_id` IN (0);";
-        let res = selection(CompleteByteSlice(qstr.as_bytes()));
+        let res = selection(qstr);
 
         let expected_where_clause = Some(ComparisonOp(ConditionTree {
             left: Box::new(Base(Field(Column::from("auth_permission.content_type_id")))),
@@ -1098,14 +2053,22 @@ mod tests {
         }));
 
         let expected = SelectStatement {
-            tables: vec![Table::from("auth_permission")],
+            tables: vec![Table {
+                quote: Some(QuoteStyle::Backtick),
+                ..Table::from("auth_permission")
+            }],
             fields: vec![
                 FieldDefinitionExpression::Col(Column::from("auth_permission.content_type_id")),
                 FieldDefinitionExpression::Col(Column::from("auth_permission.codename")),
             ],
             join: vec![JoinClause {
                 operator: JoinOperator::Join,
-                right: JoinRightSide::Table(Table::from("django_content_type")),
+                natural: false,
+                lateral: false,
+                right: JoinRightSide::Table(Table {
+                    quote: Some(QuoteStyle::Backtick),
+                    ..Table::from("django_content_type")
+                }),
                 constraint: JoinConstraint::On(ComparisonOp(ConditionTree {
                     operator: Operator::Equal,
                     left: Box::new(Base(Field(Column::from("auth_permission.content_type_id")))),
@@ -1118,4 +2081,367 @@ mod tests {
 
         assert_eq!(res.unwrap().1, expected);
     }
+
+    #[test]
+    fn table_functions_in_from() {
+        let qstring = "SELECT g.n, t.x FROM generate_series(1, 10) AS g(n) \
+                       JOIN UNNEST(items.tags) t (x) ON g.n = t.x;";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(
+            res.tables,
+            vec![Table {
+                name: "generate_series".into(),
+                alias: Some("g".into()),
+                quote: None,
+                alias_quote: None,
+                schema: None,
+                schema_quote: None,
+                function: Some(Box::new(TableFunction {
+                    arguments: vec![
+                        ArithmeticBase::Scalar(Literal::Integer(1)),
+                        ArithmeticBase::Scalar(Literal::Integer(10)),
+                    ],
+                    column_aliases: vec![String::from("n")],
+                    ..Default::default()
+                })),
+                values: None,
+                subquery: None,
+                lateral: false,
+                sample: None,
+                index_hints: vec![],
+            }]
+        );
+        assert_eq!(
+            format!("{}", res),
+            "SELECT g.n, t.x FROM generate_series(1, 10) AS g(n) \
+             JOIN UNNEST(items.tags) AS t(x) ON g.n = t.x"
+        );
+    }
+
+    #[test]
+    fn rows_from_with_ordinality() {
+        let qstring = "SELECT * FROM rows from (unnest(a.ids), generate_series(1, ?)) \
+                       with  ordinality AS t(id, n, pos), unnest(tags) WITH ORDINALITY";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(
+            res.tables[0],
+            Table {
+                name: "unnest".into(),
+                alias: Some("t".into()),
+                function: Some(Box::new(TableFunction {
+                    arguments: vec![ArithmeticBase::Column(Column::from("a.ids"))],
+                    column_aliases: vec![
+                        String::from("id"),
+                        String::from("n"),
+                        String::from("pos"),
+                    ],
+                    rows_from: Some(vec![(
+                        String::from("generate_series"),
+                        vec![
+                            ArithmeticBase::Scalar(Literal::Integer(1)),
+                            ArithmeticBase::Scalar(Literal::Placeholder),
+                        ],
+                    )]),
+                    with_ordinality: true,
+                })),
+                ..Default::default()
+            }
+        );
+        let function = res.tables[1].function.as_ref().unwrap();
+        assert!(function.with_ordinality && function.rows_from.is_none());
+        assert_eq!(res.tables[1].alias, None);
+        assert_eq!(
+            format!("{}", res),
+            "SELECT * FROM ROWS FROM (unnest(a.ids), generate_series(1, ?)) WITH ORDINALITY \
+             AS t(id, n, pos), unnest(tags) WITH ORDINALITY"
+        );
+        let res = selection("SELECT * FROM ROWS FROM (f(1)) r").unwrap().1;
+        assert_eq!(res.to_string(), "SELECT * FROM ROWS FROM (f(1)) AS r");
+    }
+
+    #[test]
+    fn values_in_from() {
+        let qstring = "SELECT t.id, t.name FROM (VALUES (1, 'a'), (2, 'b')) AS t (id, name) \
+                       JOIN users ON users.id = t.id";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(
+            res.tables,
+            vec![Table {
+                name: "t".into(),
+                values: Some(Box::new(TableValues {
+                    values: ValuesStatement {
+                        rows: vec![vec![1.into(), "a".into()], vec![2.into(), "b".into()]],
+                        row_constructors: false,
+                    },
+                    column_aliases: vec![String::from("id"), String::from("name")],
+                })),
+                ..Default::default()
+            }]
+        );
+        assert_eq!(
+            format!("{}", res),
+            "SELECT t.id, t.name FROM (VALUES (1, 'a'), (2, 'b')) AS t(id, name) \
+             JOIN users ON users.id = t.id"
+        );
+
+        let res = selection("SELECT * FROM (VALUES ROW(1)) v, users")
+            .unwrap()
+            .1;
+        assert_eq!(res.tables[0].name, "v");
+        assert_eq!(res.tables[1], Table::from("users"));
+        // a table constructor needs an alias
+        assert!(selection("SELECT * FROM (VALUES (1))").is_err());
+    }
+
+    #[test]
+    fn locking_clauses() {
+        let res =
+            selection("SELECT id FROM jobs j WHERE done = 0 LIMIT 1 FOR UPDATE OF j SKIP LOCKED")
+                .unwrap()
+                .1;
+        assert_eq!(
+            res.lock,
+            Some(LockingClause {
+                strength: LockStrength::Update,
+                of: vec![String::from("j")],
+                wait: Some(LockWait::SkipLocked),
+            })
+        );
+        assert_eq!(
+            res.to_string(),
+            "SELECT id FROM jobs AS j WHERE done = 0 LIMIT 1 FOR UPDATE OF j SKIP LOCKED"
+        );
+
+        let printed = |q: &str| selection(q).unwrap().1.to_string();
+        assert_eq!(
+            printed("SELECT * FROM t for share nowait"),
+            "SELECT * FROM t FOR SHARE NOWAIT"
+        );
+        assert_eq!(
+            printed("SELECT * FROM t WHERE id = 1 LOCK IN SHARE MODE"),
+            "SELECT * FROM t WHERE id = 1 LOCK IN SHARE MODE"
+        );
+        // INTO may come before or after the locking clause
+        assert_eq!(
+            printed("SELECT a FROM t INTO @a FOR UPDATE"),
+            "SELECT a FROM t FOR UPDATE INTO @a"
+        );
+        assert_eq!(
+            printed("SELECT a FROM t FOR UPDATE INTO @a"),
+            "SELECT a FROM t FOR UPDATE INTO @a"
+        );
+    }
+
+    #[test]
+    fn subquery_in_from() {
+        let qstring = "SELECT s.uid, users.name FROM ( SELECT uid FROM orders LIMIT 5 ) s, users \
+                       WHERE s.uid = users.id";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(
+            res.tables[0],
+            Table {
+                name: "s".into(),
+                subquery: Some(Box::new(
+                    selection("SELECT uid FROM orders LIMIT 5").unwrap().1
+                )),
+                ..Default::default()
+            }
+        );
+        assert_eq!(res.tables[1], Table::from("users"));
+        assert_eq!(
+            format!("{}", res),
+            "SELECT s.uid, users.name FROM (SELECT uid FROM orders LIMIT 5) AS s, users \
+             WHERE s.uid = users.id"
+        );
+        // a subquery in FROM needs an alias
+        assert!(selection("SELECT * FROM (SELECT 1 FROM t)").is_err());
+    }
+
+    #[test]
+    fn lateral_tables_in_from() {
+        let qstring = "SELECT u.id, r.id FROM users u, LATERAL (SELECT id FROM reviews \
+                       WHERE reviews.uid = u.id LIMIT 3) r, lateral unnest(u.tags) AS t(tag)";
+        let res = selection(qstring).unwrap().1;
+        assert!(!res.tables[0].lateral);
+        assert!(res.tables[1].lateral && res.tables[1].subquery.is_some());
+        assert!(res.tables[2].lateral && res.tables[2].function.is_some());
+        assert_eq!(
+            format!("{}", res),
+            "SELECT u.id, r.id FROM users AS u, LATERAL (SELECT id FROM reviews \
+             WHERE reviews.uid = u.id LIMIT 3) AS r, LATERAL unnest(u.tags) AS t(tag)"
+        );
+        // only a subquery or a table function may be lateral
+        assert!(selection("SELECT * FROM t, LATERAL u").is_err());
+    }
+
+    #[test]
+    fn index_hints_and_tablesample() {
+        let qstring = "SELECT id FROM users u FORCE INDEX (idx_email) IGNORE KEY FOR ORDER BY (PRIMARY) \
+                       JOIN events TABLESAMPLE bernoulli (10) REPEATABLE (42) ON u.id = events.uid;";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(res.tables[0].alias, Some("u".into()));
+        assert_eq!(
+            res.tables[0].index_hints,
+            vec![
+                IndexHint {
+                    hint_type: IndexHintType::Force,
+                    scope: None,
+                    indexes: vec![String::from("idx_email")],
+                },
+                IndexHint {
+                    hint_type: IndexHintType::Ignore,
+                    scope: Some(IndexHintScope::OrderBy),
+                    indexes: vec![String::from("PRIMARY")],
+                },
+            ]
+        );
+        match res.join[0].right {
+            JoinRightSide::Table(ref t) => {
+                assert_eq!(t.alias, None);
+                assert_eq!(
+                    t.sample,
+                    Some(TableSample {
+                        method: String::from("BERNOULLI"),
+                        arguments: vec![Literal::Integer(10)],
+                        repeatable: Some(Literal::Integer(42)),
+                    })
+                );
+            }
+            _ => panic!(),
+        }
+        assert_eq!(
+            format!("{}", res),
+            "SELECT id FROM users AS u FORCE INDEX (idx_email) IGNORE INDEX FOR ORDER BY (PRIMARY) \
+             JOIN events TABLESAMPLE BERNOULLI (10) REPEATABLE (42) ON u.id = events.uid"
+        );
+    }
+
+    #[test]
+    fn system_tablesample() {
+        let res = selection("SELECT id FROM users TABLESAMPLE system (5)")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res.tables[0].sample,
+            Some(TableSample {
+                method: String::from("SYSTEM"),
+                arguments: vec![Literal::Integer(5)],
+                repeatable: None,
+            })
+        );
+        assert_eq!(
+            res.to_string(),
+            "SELECT id FROM users TABLESAMPLE SYSTEM (5)"
+        );
+        let res = selection("SELECT id FROM users TABLESAMPLE system_rows (5)")
+            .unwrap()
+            .1;
+        assert_eq!(res.tables[0].sample.as_ref().unwrap().method, "SYSTEM_ROWS");
+    }
+
+    #[test]
+    fn select_modifiers() {
+        let qstring =
+            "SELECT SQL_NO_CACHE DISTINCT straight_join SQL_CALC_FOUND_ROWS id FROM users;";
+        let res = selection(qstring).unwrap().1;
+        assert!(res.distinct);
+        assert_eq!(
+            res.modifiers,
+            SelectModifiers::SQL_NO_CACHE
+                | SelectModifiers::STRAIGHT_JOIN
+                | SelectModifiers::SQL_CALC_FOUND_ROWS
+        );
+        assert!(!res.modifiers.contains(SelectModifiers::HIGH_PRIORITY));
+        assert_eq!(
+            format!("{}", res),
+            "SELECT DISTINCT STRAIGHT_JOIN SQL_NO_CACHE SQL_CALC_FOUND_ROWS id FROM users"
+        );
+
+        let res = selection("SELECT sql_cache_hits FROM stats").unwrap().1;
+        assert!(res.modifiers.is_empty());
+    }
+
+    #[test]
+    fn sql_server_top() {
+        let qstring = "SELECT DISTINCT TOP 10 [id], name FROM [users] ORDER BY id";
+        // TOP is T-SQL only
+        assert!(selection(qstring).is_err());
+        let res = with_dialect(Dialect::SqlServer, || selection(qstring))
+            .unwrap()
+            .1;
+        assert_eq!(res.top, Some(10));
+        assert!(res.distinct);
+        assert_eq!(res.limit, None);
+        assert_eq!(
+            format!("{}", res),
+            "SELECT DISTINCT TOP 10 id, name FROM [users] ORDER BY id ASC"
+        );
+
+        let res = with_dialect(Dialect::SqlServer, || selection("select top(5) * from t"));
+        assert_eq!(res.unwrap().1.top, Some(5));
+        // a column named top is still a column
+        let res = with_dialect(Dialect::SqlServer, || selection("SELECT top FROM t"));
+        assert_eq!(res.unwrap().1.top, None);
+    }
+
+    #[test]
+    fn select_into() {
+        let qstring = "SELECT id, name INTO OUTFILE '/tmp/u.csv' FIELDS TERMINATED BY ',' \
+                       FROM users WHERE id > 10;";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(
+            format!("{}", res),
+            "SELECT id, name FROM users WHERE id > 10 INTO OUTFILE '/tmp/u.csv' \
+             FIELDS TERMINATED BY ','"
+        );
+
+        let qstring = "SELECT COUNT(*), MAX(id) FROM users LIMIT 1 INTO @n, @top";
+        let res = selection(qstring).unwrap().1;
+        assert_eq!(
+            res.into,
+            Some(IntoClause::Variables(vec![
+                String::from("n"),
+                String::from("top")
+            ]))
+        );
+    }
+
+    #[test]
+    fn aggregate_detection() {
+        let qstring = "SELECT user_id, max(age) + 1, count(*) FROM users \
+                       GROUP BY user_id HAVING sum(score) > 10";
+        let res = selection(qstring).unwrap().1;
+        assert!(!res.fields[0].contains_aggregate());
+        assert_eq!(
+            res.fields[1].aggregates(),
+            vec![&FunctionExpression::Max(Column::from("age"))]
+        );
+        assert_eq!(res.fields[2].aggregates()[0].aggregated_column(), None);
+
+        let having = res.group_by.unwrap().having.unwrap();
+        let aggregates = having.aggregates();
+        assert_eq!(aggregates.len(), 1);
+        assert_eq!(
+            aggregates[0].aggregated_column(),
+            Some(&Column::from("score"))
+        );
+
+        let res = selection("SELECT id FROM users WHERE id = nextval('ids')")
+            .unwrap()
+            .1;
+        assert!(!res.where_clause.unwrap().contains_aggregate());
+
+        // `GroupConcat` holds a single column, so a concatenation of several is a plain call
+        let res = selection("SELECT group_concat(a, ' ', b) AS info FROM t")
+            .unwrap()
+            .1;
+        assert_eq!(res.fields.len(), 1);
+        assert!(res.fields[0].contains_aggregate());
+    }
+
+    #[test]
+    fn fields_are_separated_by_commas() {
+        assert!(selection("SELECT a b c FROM t").is_err());
+        assert!(selection("SELECT group_concat (a, b) FROM t").is_ok());
+    }
 }
@@ -0,0 +1,384 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::digit1;
+use nom::combinator::{map, map_res, opt, recognize, value};
+use nom::multi::{many0, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+use std::str::FromStr;
+
+use common::{
+    keyword_phrase, sql_identifier, statement_terminator, whitespace0, whitespace1, ws_sep_comma,
+};
+use keywords::{escape_if_keyword, tag_no_case};
+
+/// Options shared by `CREATE SEQUENCE` and `ALTER SEQUENCE`; `None` means the option was not
+/// given (or was reset with `NO MINVALUE`/`NO MAXVALUE`).
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct SequenceOptions {
+    pub start: Option<i64>,
+    pub increment: Option<i64>,
+    pub min_value: Option<i64>,
+    pub max_value: Option<i64>,
+    pub cache: Option<i64>,
+    pub cycle: Option<bool>,
+}
+
+impl fmt::Display for SequenceOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(start) = self.start {
+            write!(f, " START WITH {}", start)?;
+        }
+        if let Some(increment) = self.increment {
+            write!(f, " INCREMENT BY {}", increment)?;
+        }
+        if let Some(min_value) = self.min_value {
+            write!(f, " MINVALUE {}", min_value)?;
+        }
+        if let Some(max_value) = self.max_value {
+            write!(f, " MAXVALUE {}", max_value)?;
+        }
+        if let Some(cache) = self.cache {
+            write!(f, " CACHE {}", cache)?;
+        }
+        match self.cycle {
+            Some(true) => write!(f, " CYCLE")?,
+            Some(false) => write!(f, " NO CYCLE")?,
+            None => (),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CreateSequenceStatement {
+    pub name: String,
+    pub if_not_exists: bool,
+    pub options: SequenceOptions,
+}
+
+impl fmt::Display for CreateSequenceStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CREATE SEQUENCE ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        write!(f, "{}{}", escape_if_keyword(&self.name), self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct AlterSequenceStatement {
+    pub name: String,
+    pub if_exists: bool,
+    pub options: SequenceOptions,
+    /// `RESTART`, optionally `WITH` a new value.
+    pub restart: Option<Option<i64>>,
+}
+
+impl fmt::Display for AlterSequenceStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ALTER SEQUENCE ")?;
+        if self.if_exists {
+            write!(f, "IF EXISTS ")?;
+        }
+        write!(f, "{}{}", escape_if_keyword(&self.name), self.options)?;
+        match self.restart {
+            Some(Some(value)) => write!(f, " RESTART WITH {}", value),
+            Some(None) => write!(f, " RESTART"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct DropSequenceStatement {
+    pub names: Vec<String>,
+    pub if_exists: bool,
+}
+
+impl fmt::Display for DropSequenceStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DROP SEQUENCE ")?;
+        if self.if_exists {
+            write!(f, "IF EXISTS ")?;
+        }
+        let names = self
+            .names
+            .iter()
+            .map(|n| escape_if_keyword(n))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{}", names)
+    }
+}
+
+#[derive(Clone)]
+enum SequenceOption {
+    Start(i64),
+    Increment(i64),
+    MinValue(Option<i64>),
+    MaxValue(Option<i64>),
+    Cache(i64),
+    Cycle(bool),
+    Restart(Option<i64>),
+}
+
+pub(crate) fn signed_integer(i: &str) -> IResult<&str, i64> {
+    map_res(recognize(pair(opt(tag("-")), digit1)), i64::from_str)(i)
+}
+
+/// Parses `keyword [noise] [=] value`, where `noise` is the optional `WITH`/`BY` word.
+fn option_value<'a>(
+    keyword: &'static str,
+    noise: Option<&'static str>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, i64> {
+    move |i: &'a str| {
+        let (i, _) = tag_no_case(keyword)(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = match noise {
+            Some(noise) => opt(terminated(tag_no_case(noise), whitespace1))(i)?,
+            None => (i, None),
+        };
+        let (i, _) = opt(terminated(tag("="), whitespace0))(i)?;
+        signed_integer(i)
+    }
+}
+
+fn sequence_option(i: &str) -> IResult<&str, SequenceOption> {
+    alt((
+        map(option_value("start", Some("with")), SequenceOption::Start),
+        map(
+            option_value("increment", Some("by")),
+            SequenceOption::Increment,
+        ),
+        map(option_value("minvalue", None), |v| {
+            SequenceOption::MinValue(Some(v))
+        }),
+        map(option_value("maxvalue", None), |v| {
+            SequenceOption::MaxValue(Some(v))
+        }),
+        map(option_value("cache", None), SequenceOption::Cache),
+        value(
+            SequenceOption::MinValue(None),
+            alt((
+                recognize(tuple((
+                    tag_no_case("no"),
+                    whitespace1,
+                    tag_no_case("minvalue"),
+                ))),
+                tag_no_case("nominvalue"),
+            )),
+        ),
+        value(
+            SequenceOption::MaxValue(None),
+            alt((
+                recognize(tuple((
+                    tag_no_case("no"),
+                    whitespace1,
+                    tag_no_case("maxvalue"),
+                ))),
+                tag_no_case("nomaxvalue"),
+            )),
+        ),
+        value(
+            SequenceOption::Cycle(false),
+            alt((
+                recognize(tuple((
+                    tag_no_case("no"),
+                    whitespace1,
+                    tag_no_case("cycle"),
+                ))),
+                tag_no_case("nocycle"),
+            )),
+        ),
+        value(SequenceOption::Cycle(true), tag_no_case("cycle")),
+        map(
+            preceded(
+                tag_no_case("restart"),
+                opt(preceded(
+                    whitespace1,
+                    preceded(
+                        opt(terminated(tag_no_case("with"), whitespace1)),
+                        signed_integer,
+                    ),
+                )),
+            ),
+            SequenceOption::Restart,
+        ),
+    ))(i)
+}
+
+fn collect_options(options: Vec<SequenceOption>) -> (SequenceOptions, Option<Option<i64>>) {
+    let mut opts = SequenceOptions::default();
+    let mut restart = None;
+    for option in options {
+        match option {
+            SequenceOption::Start(v) => opts.start = Some(v),
+            SequenceOption::Increment(v) => opts.increment = Some(v),
+            SequenceOption::MinValue(v) => opts.min_value = v,
+            SequenceOption::MaxValue(v) => opts.max_value = v,
+            SequenceOption::Cache(v) => opts.cache = Some(v),
+            SequenceOption::Cycle(c) => opts.cycle = Some(c),
+            SequenceOption::Restart(v) => restart = Some(v),
+        }
+    }
+    (opts, restart)
+}
+
+fn sequence_options(i: &str) -> IResult<&str, (SequenceOptions, Option<Option<i64>>)> {
+    map(
+        many0(preceded(whitespace1, sequence_option)),
+        collect_options,
+    )(i)
+}
+
+/// Parses the parenthesized sequence options of a `GENERATED ... AS IDENTITY` column.
+pub fn identity_options(i: &str) -> IResult<&str, SequenceOptions> {
+    let (remaining_input, (options, restart)) = delimited(
+        pair(tag("("), whitespace0),
+        map(
+            separated_list0(whitespace1, sequence_option),
+            collect_options,
+        ),
+        pair(whitespace0, tag(")")),
+    )(i)?;
+
+    if restart.is_some() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            i,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+
+    Ok((remaining_input, options))
+}
+
+pub fn create_sequence(i: &str) -> IResult<&str, CreateSequenceStatement> {
+    let (remaining_input, (_, _, if_not_exists, name, (options, restart), _)) = tuple((
+        keyword_phrase(&["create", "sequence"]),
+        whitespace1,
+        opt(terminated(
+            keyword_phrase(&["if", "not", "exists"]),
+            whitespace1,
+        )),
+        sql_identifier,
+        sequence_options,
+        statement_terminator,
+    ))(i)?;
+
+    // RESTART only makes sense when altering an existing sequence
+    if restart.is_some() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            i,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+
+    Ok((
+        remaining_input,
+        CreateSequenceStatement {
+            name: String::from(name),
+            if_not_exists: if_not_exists.is_some(),
+            options,
+        },
+    ))
+}
+
+pub fn alter_sequence(i: &str) -> IResult<&str, AlterSequenceStatement> {
+    let (remaining_input, (_, _, if_exists, name, (options, restart), _)) = tuple((
+        keyword_phrase(&["alter", "sequence"]),
+        whitespace1,
+        opt(terminated(keyword_phrase(&["if", "exists"]), whitespace1)),
+        sql_identifier,
+        sequence_options,
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        AlterSequenceStatement {
+            name: String::from(name),
+            if_exists: if_exists.is_some(),
+            options,
+            restart,
+        },
+    ))
+}
+
+pub fn drop_sequence(i: &str) -> IResult<&str, DropSequenceStatement> {
+    let (remaining_input, (_, _, if_exists, names, _)) = tuple((
+        keyword_phrase(&["drop", "sequence"]),
+        whitespace1,
+        opt(terminated(keyword_phrase(&["if", "exists"]), whitespace1)),
+        separated_list1(ws_sep_comma, sql_identifier),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        DropSequenceStatement {
+            names: names.into_iter().map(String::from).collect(),
+            if_exists: if_exists.is_some(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_sequence_with_options() {
+        let qstring = "CREATE SEQUENCE IF NOT EXISTS order_ids START WITH 100 INCREMENT BY -2 \
+                       MINVALUE -1000 NO MAXVALUE CACHE 20 NO CYCLE;";
+        let res = create_sequence(qstring);
+        assert_eq!(
+            res.unwrap().1,
+            CreateSequenceStatement {
+                name: String::from("order_ids"),
+                if_not_exists: true,
+                options: SequenceOptions {
+                    start: Some(100),
+                    increment: Some(-2),
+                    min_value: Some(-1000),
+                    max_value: None,
+                    cache: Some(20),
+                    cycle: Some(false),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn format_create_sequence() {
+        let qstring = "create sequence s increment 5 start 1 maxvalue 99 cycle";
+        let res = create_sequence(qstring);
+        assert_eq!(
+            format!("{}", res.unwrap().1),
+            "CREATE SEQUENCE s START WITH 1 INCREMENT BY 5 MAXVALUE 99 CYCLE"
+        );
+    }
+
+    #[test]
+    fn alter_and_drop_sequence() {
+        let res = alter_sequence("ALTER SEQUENCE s INCREMENT BY 10 RESTART WITH 5");
+        let stmt = res.unwrap().1;
+        assert_eq!(stmt.options.increment, Some(10));
+        assert_eq!(stmt.restart, Some(Some(5)));
+        assert_eq!(
+            format!("{}", stmt),
+            "ALTER SEQUENCE s INCREMENT BY 10 RESTART WITH 5"
+        );
+
+        let res = drop_sequence("DROP SEQUENCE IF EXISTS s1, s2;");
+        assert_eq!(
+            res.unwrap().1,
+            DropSequenceStatement {
+                names: vec![String::from("s1"), String::from("s2")],
+                if_exists: true,
+            }
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Versioned serialization of parsed queries.
+//!
+//! Every AST type implements serde's `Serialize` and `Deserialize` with serde's default data
+//! model: structs are maps keyed by field name, enums are externally tagged (`{"Select": {..}}`,
+//! or just `"Commit"` for unit variants), `Option`s are `null` when absent, and
+//! `SelectModifiers` is a list of keywords. Field and variant names are part of the format, so
+//! renaming, removing or retyping them changes the representation and bumps `AST_VERSION`;
+//! adding a variant, or a field that defaults when absent, does not.
+
+use parser::SqlQuery;
+
+/// Version of the serialized AST representation.
+pub const AST_VERSION: u32 = 13;
+
+/// A query tagged with the version of the representation it was serialized with, for shipping
+/// parsed queries between services that may be built against different versions of this crate.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct VersionedQuery {
+    pub version: u32,
+    pub query: SqlQuery,
+}
+
+impl VersionedQuery {
+    /// Returns the query if it was serialized with the current representation.
+    pub fn into_query(self) -> Result<SqlQuery, u32> {
+        if self.version == AST_VERSION {
+            Ok(self.query)
+        } else {
+            Err(self.version)
+        }
+    }
+}
+
+impl From<SqlQuery> for VersionedQuery {
+    fn from(query: SqlQuery) -> Self {
+        VersionedQuery {
+            version: AST_VERSION,
+            query,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use parser::parse_query;
+
+    #[test]
+    fn json_round_trip() {
+        let queries = [
+            "SELECT SQL_NO_CACHE DISTINCT u.id, count(*) FROM users AS u \
+             LEFT JOIN posts ON u.id = posts.author WHERE u.age > ? GROUP BY u.id LIMIT 5",
+            "CREATE TABLE t (id int(11) NOT NULL AUTO_INCREMENT, d decimal(10,2), \
+             PRIMARY KEY (id), UNIQUE KEY k (d))",
+            "INSERT INTO t (a, b) VALUES (1, 'x') ON DUPLICATE KEY UPDATE b = 'y'",
+            "ROLLBACK TO SAVEPOINT sp",
+        ];
+        for q in &queries {
+            let query = parse_query(q).unwrap();
+            let json = serde_json::to_string(&VersionedQuery::from(query.clone())).unwrap();
+            let back: VersionedQuery = serde_json::from_str(&json).unwrap();
+            assert_eq!(back.into_query(), Ok(query));
+        }
+    }
+
+    #[test]
+    fn documented_representation() {
+        let query = parse_query("SELECT SQL_NO_CACHE id FROM t").unwrap();
+        let json = serde_json::to_value(VersionedQuery::from(query)).unwrap();
+        assert_eq!(json["version"], AST_VERSION);
+        assert_eq!(
+            json["query"]["Select"]["modifiers"],
+            serde_json::json!(["SQL_NO_CACHE"])
+        );
+        assert_eq!(
+            json["query"]["Select"]["where_clause"],
+            serde_json::Value::Null
+        );
+
+        let old = serde_json::json!({"version": 0, "query": "UnlockTables"});
+        let old: VersionedQuery = serde_json::from_value(old).unwrap();
+        assert_eq!(old.into_query(), Err(0));
+    }
+}
@@ -1,39 +1,143 @@
-use nom::multispace;
-use nom::types::CompleteByteSlice;
-use std::{fmt, str};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while1};
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{map, recognize};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, tuple};
+use nom::IResult;
+use std::borrow::Cow;
+use std::fmt;
 
-use common::{literal, opt_multispace, sql_identifier, statement_terminator, Literal};
+use common::{
+    default_keyword, escape_string, is_sql_identifier, literal, sql_identifier,
+    statement_terminator, string_literal, user_variable, ws_sep_comma, Literal,
+};
+use keywords::{escape_if_keyword, tag_no_case};
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// The value a variable is set to: a literal, or a bare word such as Postgres' `on` or `warning`,
+/// which is kept as a word rather than turned into a string. Such words are case-insensitive, and
+/// are kept in lower case.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum SetValue {
+    Literal(Literal),
+    Identifier(String),
+}
+
+impl fmt::Display for SetValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SetValue::Literal(ref literal) => write!(f, "{}", literal),
+            SetValue::Identifier(ref word) => write!(f, "{}", word),
+        }
+    }
+}
+
+impl<T: Into<Literal>> From<T> for SetValue {
+    fn from(literal: T) -> Self {
+        SetValue::Literal(literal.into())
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct SetStatement {
     pub variable: String,
-    pub value: Literal,
+    pub value: SetValue,
 }
 
 impl fmt::Display for SetStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "SET ")?;
-        write!(f, "{} = {}", self.variable, self.value.to_string())?;
+        write!(f, "{} = {}", self.variable, self.value)?;
         Ok(())
     }
 }
 
-named!(pub set<CompleteByteSlice, SetStatement>,
-    do_parse!(
-        tag_no_case!("set") >>
-        multispace >>
-        var: sql_identifier >>
-        opt_multispace >>
-        tag_no_case!("=") >>
-        opt_multispace >>
-        val: literal >>
-        statement_terminator >>
-        (SetStatement {
-            variable: String::from(str::from_utf8(*var).unwrap()),
-            value: val,
-        })
-    )
-);
+/// Postgres' `SET search_path = schema, ...`, which names the schemas searched for unqualified
+/// object names.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct SetSearchPathStatement {
+    pub schemas: Vec<String>,
+}
+
+impl fmt::Display for SetSearchPathStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let schemas = self
+            .schemas
+            .iter()
+            .map(|s| {
+                if !s.is_empty() && s.chars().all(is_sql_identifier) {
+                    escape_if_keyword(s)
+                } else {
+                    format!("'{}'", escape_string(s))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "SET search_path = {}", schemas)
+    }
+}
+
+/// `=`, `:=` or Postgres' `TO`.
+fn assignment(i: &str) -> IResult<&str, &str> {
+    delimited(
+        multispace0,
+        alt((tag(":="), tag("="), tag_no_case("to"))),
+        multispace0,
+    )(i)
+}
+
+fn set_value(i: &str) -> IResult<&str, SetValue> {
+    alt((
+        map(alt((literal, default_keyword)), SetValue::Literal),
+        map(take_while1(is_sql_identifier), |word: &str| {
+            SetValue::Identifier(word.to_lowercase())
+        }),
+    ))(i)
+}
+
+/// A schema in the search path, which may be quoted as a string or, like `"$user"`, as an
+/// identifier.
+fn search_path_schema(i: &str) -> IResult<&str, String> {
+    alt((
+        map(string_literal, |s| match s {
+            Literal::String(s) => s,
+            _ => unreachable!("string_literal only returns strings"),
+        }),
+        map(take_while1(is_sql_identifier), String::from),
+    ))(i)
+}
+
+pub fn set_search_path(i: &str) -> IResult<&str, SetSearchPathStatement> {
+    let (remaining_input, (_, _, _, _, schemas, _)) = tuple((
+        tag_no_case("set"),
+        multispace1,
+        tag_no_case("search_path"),
+        assignment,
+        separated_list1(ws_sep_comma, search_path_schema),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((remaining_input, SetSearchPathStatement { schemas }))
+}
+
+pub fn set(i: &str) -> IResult<&str, SetStatement> {
+    let (remaining_input, (_, _, var, _, value, _)) = tuple((
+        tag_no_case("set"),
+        multispace1,
+        alt((map(recognize(user_variable), Cow::Borrowed), sql_identifier)),
+        assignment,
+        set_value,
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        SetStatement {
+            variable: String::from(var),
+            value,
+        },
+    ))
+}
 
 #[cfg(test)]
 mod tests {
@@ -42,7 +146,7 @@ mod tests {
     #[test]
     fn simple_set() {
         let qstring = "SET SQL_AUTO_IS_NULL = 0;";
-        let res = set(CompleteByteSlice(qstring.as_bytes()));
+        let res = set(qstring);
         assert_eq!(
             res.unwrap().1,
             SetStatement {
@@ -56,7 +160,44 @@ mod tests {
     fn format_set() {
         let qstring = "set autocommit=1";
         let expected = "SET autocommit = 1";
-        let res = set(CompleteByteSlice(qstring.as_bytes()));
+        let res = set(qstring);
         assert_eq!(format!("{}", res.unwrap().1), expected);
     }
+
+    #[test]
+    fn set_user_variable() {
+        let qstring = "SET @batch_size := 500;";
+        let res = set(qstring);
+        assert_eq!(format!("{}", res.unwrap().1), "SET @batch_size = 500");
+    }
+
+    #[test]
+    fn set_postgres_settings() {
+        let res = set("SET client_min_messages TO warning;").unwrap().1;
+        assert_eq!(res.value, SetValue::Identifier(String::from("warning")));
+        assert_eq!(res.to_string(), "SET client_min_messages = warning");
+        let res = set("SET standard_conforming_strings = on;").unwrap().1;
+        assert_eq!(res.value, SetValue::Identifier(String::from("on")));
+        assert_eq!(res.to_string(), "SET standard_conforming_strings = on");
+        let res = set("SET standard_conforming_strings = ON;").unwrap().1;
+        assert_eq!(res.value, SetValue::Identifier(String::from("on")));
+        let res = set("SET default_tablespace = '';").unwrap().1;
+        assert_eq!(res.value, SetValue::from(Literal::String(String::new())));
+        assert_eq!(res.to_string(), "SET default_tablespace = ''");
+        let res = set("SET statement_timeout = DEFAULT").unwrap().1;
+        assert_eq!(res.value, SetValue::from(Literal::Default));
+    }
+
+    #[test]
+    fn search_path() {
+        let res = set_search_path("SET search_path = \"$user\", public, '';");
+        let res = res.unwrap().1;
+        assert_eq!(
+            res.schemas,
+            vec![String::from("$user"), String::from("public"), String::new()]
+        );
+        assert_eq!(res.to_string(), "SET search_path = '$user', public, ''");
+        let res = set_search_path("set search_path to app").unwrap().1;
+        assert_eq!(res.to_string(), "SET search_path = app");
+    }
 }
@@ -0,0 +1,346 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use column::Column;
+use common::{FieldValueExpression, Literal, Operator};
+use condition::{ConditionBase, ConditionExpression};
+use create::SelectSpecification;
+use join::JoinRightSide;
+use parser::SqlQuery;
+use select::SelectStatement;
+use table::Table;
+
+/// The values of a table's sharding column that a statement is confined to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum ShardKey {
+    /// Only rows whose sharding column holds one of these values are read or written. The values
+    /// are sorted and distinct; there are none if the condition can never hold.
+    Values(Vec<Literal>),
+    /// Rows with any value may be affected, either because the column is not constrained or
+    /// because it is constrained by something other than equality with literals.
+    Indeterminate,
+}
+
+/// A table instance whose sharding column is being looked for.
+struct Target<'a> {
+    table: &'a Table,
+    column: &'a str,
+    /// Whether unqualified columns belong to the table, as they do when it is the only one.
+    only_table: bool,
+}
+
+impl<'a> Target<'a> {
+    fn is_key(&self, cond: &ConditionExpression) -> bool {
+        let col: &Column = match *cond {
+            ConditionExpression::Base(ConditionBase::Field(ref col)) => col,
+            _ => return false,
+        };
+        if col.function.is_some() || col.name != self.column {
+            return false;
+        }
+        match col.table {
+            Some(ref t) => *t == self.table.name || self.table.alias.as_ref() == Some(t),
+            None => self.only_table,
+        }
+    }
+
+    /// The set of values `cond` confines the column to, or `None` if it does not.
+    fn values(&self, cond: &ConditionExpression) -> Option<BTreeSet<Literal>> {
+        match *cond {
+            ConditionExpression::Bracketed(ref inner) => self.values(inner),
+            ConditionExpression::LogicalOp(ref tree) => {
+                let left = self.values(&tree.left);
+                let right = self.values(&tree.right);
+                match tree.operator {
+                    Operator::And => match (left, right) {
+                        (Some(l), Some(r)) => Some(l.intersection(&r).cloned().collect()),
+                        (l, r) => l.or(r),
+                    },
+                    Operator::Or => Some(left?.union(&right?).cloned().collect()),
+                    _ => None,
+                }
+            }
+            ConditionExpression::ComparisonOp(ref tree) => {
+                let other = if self.is_key(&tree.left) {
+                    &tree.right
+                } else if tree.operator == Operator::Equal && self.is_key(&tree.right) {
+                    &tree.left
+                } else {
+                    return None;
+                };
+                let literals = match (&tree.operator, &**other) {
+                    (
+                        &Operator::Equal,
+                        &ConditionExpression::Base(ConditionBase::Literal(ref lit)),
+                    ) => vec![lit],
+                    (
+                        &Operator::In,
+                        &ConditionExpression::Base(ConditionBase::LiteralList(ref lits)),
+                    ) => lits.iter().collect(),
+                    _ => return None,
+                };
+                concrete(literals)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The literals as a set, or `None` if one of them is not known until execution. `NULL` never
+/// compares equal, so it is left out.
+fn concrete(literals: Vec<&Literal>) -> Option<BTreeSet<Literal>> {
+    let mut values = BTreeSet::new();
+    for lit in literals {
+        match *lit {
+            Literal::Null => (),
+            Literal::Placeholder | Literal::Default => return None,
+            ref lit => {
+                values.insert(lit.clone());
+            }
+        }
+    }
+    Some(values)
+}
+
+/// Records what a statement does to one instance of a table; a table read several times is
+/// confined to the union of its instances.
+fn record(keys: &mut BTreeMap<String, ShardKey>, table: &str, values: Option<BTreeSet<Literal>>) {
+    let merged = match (keys.remove(table), values) {
+        (None, Some(values)) => ShardKey::Values(values.into_iter().collect()),
+        (Some(ShardKey::Values(mut known)), Some(values)) => {
+            known.extend(values);
+            known.sort();
+            known.dedup();
+            ShardKey::Values(known)
+        }
+        _ => ShardKey::Indeterminate,
+    };
+    keys.insert(String::from(table), merged);
+}
+
+fn filtered(
+    keys: &mut BTreeMap<String, ShardKey>,
+    columns: &HashMap<String, String>,
+    tables: &[&Table],
+    cond: Option<&ConditionExpression>,
+) {
+    for table in tables {
+        if !table.is_stored() {
+            continue;
+        }
+        if let Some(column) = columns.get(table.name.as_str()) {
+            let target = Target {
+                table,
+                column,
+                only_table: tables.len() == 1,
+            };
+            record(keys, &table.name, cond.and_then(|c| target.values(c)));
+        }
+    }
+}
+
+fn select_keys(
+    keys: &mut BTreeMap<String, ShardKey>,
+    columns: &HashMap<String, String>,
+    select: &SelectStatement,
+) {
+    let mut tables: Vec<&Table> = select.tables.iter().collect();
+    for join in &select.join {
+        let mut right = &join.right;
+        while let JoinRightSide::NestedJoin(ref nested) = *right {
+            right = &nested.right;
+        }
+        match *right {
+            JoinRightSide::Table(ref t) => tables.push(t),
+            JoinRightSide::Tables(ref ts) => tables.extend(ts),
+            JoinRightSide::NestedSelect(ref nested, _) => select_keys(keys, columns, nested),
+            JoinRightSide::NestedJoin(_) => (),
+        }
+    }
+    // a derived table is confined by its own `WHERE` clause
+    for subquery in tables.iter().filter_map(|t| t.subquery.as_ref()) {
+        select_keys(keys, columns, subquery);
+    }
+    filtered(keys, columns, &tables, select.where_clause.as_ref());
+}
+
+fn specification_keys(
+    keys: &mut BTreeMap<String, ShardKey>,
+    columns: &HashMap<String, String>,
+    spec: &SelectSpecification,
+) {
+    match *spec {
+        SelectSpecification::Simple(ref select) => select_keys(keys, columns, select),
+        SelectSpecification::Compound(ref compound) => {
+            for (_, ref select) in &compound.selects {
+                select_keys(keys, columns, select);
+            }
+        }
+    }
+}
+
+/// Finds the values of the sharding columns that `query` is confined to, given the name of the
+/// sharding column of each sharded table in `columns`.
+///
+/// The result has an entry for every sharded table the statement reads or writes directly, in a
+/// derived table, in the common table expressions of a `WITH` query or in the select of a
+/// `DECLARE ... CURSOR`. Only the `WHERE` clause is considered, where equalities and `IN` lists
+/// over literals are combined through `AND` and `OR`; rows inserted by `INSERT` are confined to
+/// the values they give the column. Placeholders are indeterminate, so statements should be
+/// bound before routing.
+pub fn shard_keys(
+    query: &SqlQuery,
+    columns: &HashMap<String, String>,
+) -> BTreeMap<String, ShardKey> {
+    let mut keys = BTreeMap::new();
+    match *query {
+        SqlQuery::Select(ref select) => select_keys(&mut keys, columns, select),
+        SqlQuery::CompoundSelect(ref compound) => {
+            for (_, ref select) in &compound.selects {
+                select_keys(&mut keys, columns, select);
+            }
+        }
+        SqlQuery::With(ref with) => {
+            for cte in &with.ctes {
+                specification_keys(&mut keys, columns, &cte.query);
+            }
+            specification_keys(&mut keys, columns, &with.query);
+        }
+        SqlQuery::DeclareCursor(ref declare) => {
+            specification_keys(&mut keys, columns, &declare.query)
+        }
+        SqlQuery::Update(ref update) => filtered(
+            &mut keys,
+            columns,
+            &[&update.table],
+            update.where_clause.as_ref(),
+        ),
+        SqlQuery::Delete(ref delete) => filtered(
+            &mut keys,
+            columns,
+            &[&delete.table],
+            delete.where_clause.as_ref(),
+        ),
+        SqlQuery::Insert(ref insert) => {
+            if let Some(column) = columns.get(insert.table.name.as_str()) {
+                let position = insert
+                    .fields
+                    .as_ref()
+                    .and_then(|fields| fields.iter().position(|f| f.name == *column));
+                // a value computed on insertion, such as `now()`, is not known
+                let values = position.and_then(|i| {
+                    let row_values: Option<Vec<_>> = insert
+                        .data
+                        .iter()
+                        .map(|row| match row.get(i) {
+                            Some(FieldValueExpression::Literal(ref lit)) => Some(&lit.value),
+                            _ => None,
+                        })
+                        .collect();
+                    concrete(row_values?)
+                });
+                record(&mut keys, &insert.table.name, values);
+            }
+        }
+        _ => (),
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+
+    fn keys(q: &str) -> BTreeMap<String, ShardKey> {
+        let columns: HashMap<_, _> = vec![
+            (String::from("users"), String::from("tenant")),
+            (String::from("orders"), String::from("tenant")),
+        ]
+        .into_iter()
+        .collect();
+        shard_keys(&parse_query(q).unwrap(), &columns)
+    }
+
+    fn values(v: &[i64]) -> ShardKey {
+        ShardKey::Values(v.iter().map(|&i| Literal::Integer(i)).collect())
+    }
+
+    #[test]
+    fn equalities_and_in_lists() {
+        assert_eq!(
+            keys("SELECT * FROM users WHERE tenant = 3 AND name = 'a'")["users"],
+            values(&[3])
+        );
+        assert_eq!(
+            keys("SELECT * FROM users WHERE 3 = tenant OR tenant IN (1, 2)")["users"],
+            values(&[1, 2, 3])
+        );
+        assert_eq!(
+            keys("DELETE FROM users WHERE tenant IN (1, 2) AND (tenant = 2 OR tenant = 5)")
+                ["users"],
+            values(&[2])
+        );
+        assert_eq!(
+            keys("UPDATE users SET name = 'a' WHERE tenant = 1 AND tenant = 2")["users"],
+            values(&[])
+        );
+        assert_eq!(
+            keys("INSERT INTO users (name, tenant) VALUES ('a', 7), ('b', 7)")["users"],
+            values(&[7])
+        );
+    }
+
+    #[test]
+    fn indeterminate_keys() {
+        for q in &[
+            "SELECT * FROM users",
+            "SELECT * FROM users WHERE tenant = 1 OR name = 'a'",
+            "SELECT * FROM users WHERE tenant > 1",
+            "SELECT * FROM users WHERE tenant = ?",
+            "SELECT * FROM users WHERE NOT tenant = 1",
+            "SELECT * FROM users WHERE tenant IN (SELECT id FROM tenants)",
+            "INSERT INTO users VALUES (1, 'a')",
+            "INSERT INTO users (tenant, name) VALUES (1, 'a'), (1 + 1, 'b')",
+        ] {
+            assert_eq!(keys(q)["users"], ShardKey::Indeterminate, "{}", q);
+        }
+    }
+
+    #[test]
+    fn joined_tables() {
+        let keys = keys(
+            "SELECT * FROM users AS u JOIN orders ON u.id = orders.uid \
+             JOIN accounts ON accounts.id = u.id WHERE u.tenant = 4 AND tenant = 5",
+        );
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys["users"], values(&[4]));
+        // an unqualified column cannot be attributed once there are several tables
+        assert_eq!(keys["orders"], ShardKey::Indeterminate);
+    }
+
+    #[test]
+    fn common_table_expressions() {
+        let keys = keys(
+            "WITH u AS (SELECT id FROM users WHERE tenant = 3), \
+             o AS (SELECT uid FROM orders) SELECT * FROM u JOIN o ON o.uid = u.id",
+        );
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys["users"], values(&[3]));
+        assert_eq!(keys["orders"], ShardKey::Indeterminate);
+    }
+
+    #[test]
+    fn derived_tables_and_cursors() {
+        let derived = keys(
+            "SELECT * FROM (SELECT id FROM users WHERE tenant = 3) AS u \
+             JOIN (SELECT uid FROM orders) AS o ON o.uid = u.id",
+        );
+        assert_eq!(derived.len(), 2);
+        assert_eq!(derived["users"], values(&[3]));
+        assert_eq!(derived["orders"], ShardKey::Indeterminate);
+        assert_eq!(
+            keys("DECLARE c CURSOR FOR SELECT * FROM orders WHERE tenant IN (1, 2)")["orders"],
+            values(&[1, 2])
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::cmp::Ordering;
+
+use common::{Literal, Operator};
+use condition::{ConditionBase, ConditionExpression, ConditionTree};
+
+/// A condition after simplification, which may have turned out to be constant.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum SimplifiedCondition {
+    True,
+    False,
+    Expression(ConditionExpression),
+}
+
+impl SimplifiedCondition {
+    /// The condition to put in a `WHERE` clause: `None` if it always holds, and an explicit
+    /// `1 = 0` if it never does.
+    pub fn into_condition(self) -> Option<ConditionExpression> {
+        match self {
+            SimplifiedCondition::True => None,
+            SimplifiedCondition::False => Some(ConditionExpression::ComparisonOp(ConditionTree {
+                operator: Operator::Equal,
+                left: Box::new(ConditionExpression::Base(ConditionBase::Literal(
+                    Literal::Integer(1),
+                ))),
+                right: Box::new(ConditionExpression::Base(ConditionBase::Literal(
+                    Literal::Integer(0),
+                ))),
+            })),
+            SimplifiedCondition::Expression(cond) => Some(cond),
+        }
+    }
+}
+
+fn logical(
+    op: Operator,
+    left: ConditionExpression,
+    right: ConditionExpression,
+) -> ConditionExpression {
+    ConditionExpression::LogicalOp(ConditionTree {
+        operator: op,
+        left: Box::new(left),
+        right: Box::new(right),
+    })
+}
+
+/// Joins conditions into a chain of `op`, bracketing operands that are themselves logical
+/// operations so that the result prints as it is structured.
+fn chain(op: Operator, operands: Vec<ConditionExpression>) -> Option<ConditionExpression> {
+    operands
+        .into_iter()
+        .map(|o| match o {
+            ConditionExpression::LogicalOp(ref tree) if tree.operator != op => {
+                ConditionExpression::Bracketed(Box::new(o))
+            }
+            o => o,
+        })
+        .rev()
+        .fold(None, |right, left| {
+            Some(match right {
+                Some(right) => logical(op.clone(), left, right),
+                None => left,
+            })
+        })
+}
+
+/// Combines conditions with `AND`, e.g. to add a predicate to an existing `WHERE` clause.
+/// Returns `None` if there are no conditions.
+pub fn and_conditions<I>(conditions: I) -> Option<ConditionExpression>
+where
+    I: IntoIterator<Item = ConditionExpression>,
+{
+    chain(Operator::And, conditions.into_iter().collect())
+}
+
+/// Splits a condition into the operands of its top-level `AND` chain, looking through
+/// parentheses.
+pub fn conjuncts(cond: &ConditionExpression) -> Vec<&ConditionExpression> {
+    fn collect<'a>(cond: &'a ConditionExpression, out: &mut Vec<&'a ConditionExpression>) {
+        match *cond {
+            ConditionExpression::Bracketed(ref inner) => collect(inner, out),
+            ConditionExpression::LogicalOp(ref tree) if tree.operator == Operator::And => {
+                collect(&tree.left, out);
+                collect(&tree.right, out);
+            }
+            _ => out.push(cond),
+        }
+    }
+    let mut out = Vec::new();
+    collect(cond, &mut out);
+    out
+}
+
+/// Evaluates a comparison of two literals, where the result is known. Strings are left alone, as
+/// how they compare depends on the collation: under MySQL's default ones, `'a' = 'A '` holds.
+fn compare_literals(op: &Operator, left: &Literal, right: &Literal) -> Option<bool> {
+    let ordering = match (left, right) {
+        (&Literal::Integer(a), &Literal::Integer(b)) => a.cmp(&b),
+        _ => return None,
+    };
+    match *op {
+        Operator::Equal => Some(ordering == Ordering::Equal),
+        Operator::NotEqual => Some(ordering != Ordering::Equal),
+        Operator::Greater => Some(ordering == Ordering::Greater),
+        Operator::GreaterOrEqual => Some(ordering != Ordering::Less),
+        Operator::Less => Some(ordering == Ordering::Less),
+        Operator::LessOrEqual => Some(ordering != Ordering::Greater),
+        _ => None,
+    }
+}
+
+fn constant(value: bool) -> SimplifiedCondition {
+    if value {
+        SimplifiedCondition::True
+    } else {
+        SimplifiedCondition::False
+    }
+}
+
+/// Simplifies the operands of a chain of `op`. A constant operand that decides the chain (true
+/// for `OR`, false for `AND`) replaces it, other constants are dropped, as are duplicates.
+fn simplify_chain(op: Operator, cond: &ConditionExpression) -> SimplifiedCondition {
+    fn collect<'a>(
+        op: &Operator,
+        cond: &'a ConditionExpression,
+        out: &mut Vec<&'a ConditionExpression>,
+    ) {
+        match *cond {
+            ConditionExpression::Bracketed(ref inner) => collect(op, inner, out),
+            ConditionExpression::LogicalOp(ref tree) if tree.operator == *op => {
+                collect(op, &tree.left, out);
+                collect(op, &tree.right, out);
+            }
+            _ => out.push(cond),
+        }
+    }
+
+    let absorbing = op == Operator::Or;
+    let mut raw = Vec::new();
+    collect(&op, cond, &mut raw);
+    let mut operands: Vec<ConditionExpression> = Vec::new();
+    for operand in raw {
+        let simplified = simplify(operand);
+        if simplified == constant(absorbing) {
+            return simplified;
+        }
+        if let SimplifiedCondition::Expression(expr) = simplified {
+            // A simplified operand may itself be a chain of the same operator.
+            let nested: Vec<_> = match expr {
+                ConditionExpression::LogicalOp(ref tree) if tree.operator == op => {
+                    let mut nested = Vec::new();
+                    collect(&op, &expr, &mut nested);
+                    nested.into_iter().cloned().collect()
+                }
+                expr => vec![expr],
+            };
+            for expr in nested {
+                if !operands.contains(&expr) {
+                    operands.push(expr);
+                }
+            }
+        }
+    }
+    match chain(op, operands) {
+        Some(cond) => SimplifiedCondition::Expression(cond),
+        None => constant(!absorbing),
+    }
+}
+
+/// Simplifies a condition: nested `AND`/`OR` chains are flattened and deduplicated, redundant
+/// parentheses and double negations are removed, and comparisons between integer literals, such
+/// as `1 = 1`, are evaluated.
+pub fn simplify(cond: &ConditionExpression) -> SimplifiedCondition {
+    match *cond {
+        ConditionExpression::Bracketed(ref inner) => simplify(inner),
+        ConditionExpression::LogicalOp(ref tree) => simplify_chain(tree.operator.clone(), cond),
+        ConditionExpression::NegationOp(ref inner) => match simplify(inner) {
+            SimplifiedCondition::True => SimplifiedCondition::False,
+            SimplifiedCondition::False => SimplifiedCondition::True,
+            SimplifiedCondition::Expression(ConditionExpression::NegationOp(inner)) => {
+                SimplifiedCondition::Expression(match *inner {
+                    ConditionExpression::Bracketed(inner) => *inner,
+                    inner => inner,
+                })
+            }
+            SimplifiedCondition::Expression(inner) => {
+                let inner = match inner {
+                    ConditionExpression::LogicalOp(_) => {
+                        ConditionExpression::Bracketed(Box::new(inner))
+                    }
+                    inner => inner,
+                };
+                SimplifiedCondition::Expression(ConditionExpression::NegationOp(Box::new(inner)))
+            }
+        },
+        ConditionExpression::ComparisonOp(ref tree) => match (&*tree.left, &*tree.right) {
+            (
+                &ConditionExpression::Base(ConditionBase::Literal(ref left)),
+                &ConditionExpression::Base(ConditionBase::Literal(ref right)),
+            ) => match compare_literals(&tree.operator, left, right) {
+                Some(value) => constant(value),
+                None => SimplifiedCondition::Expression(cond.clone()),
+            },
+            _ => SimplifiedCondition::Expression(cond.clone()),
+        },
+        _ => SimplifiedCondition::Expression(cond.clone()),
+    }
+}
+
+/// The most clauses `conjunctive_normal_form` produces before giving up on distributing `OR` over
+/// `AND`.
+pub const MAX_CNF_CLAUSES: usize = 1024;
+
+/// Distributes `OR` over `AND` in a simplified condition, returning its clauses, or `None` if
+/// there would be more than `MAX_CNF_CLAUSES` of them.
+fn cnf_clauses(cond: &ConditionExpression) -> Option<Vec<Vec<ConditionExpression>>> {
+    Some(match *cond {
+        ConditionExpression::Bracketed(ref inner) => cnf_clauses(inner)?,
+        ConditionExpression::LogicalOp(ref tree) if tree.operator == Operator::And => {
+            let mut clauses = cnf_clauses(&tree.left)?;
+            clauses.extend(cnf_clauses(&tree.right)?);
+            if clauses.len() > MAX_CNF_CLAUSES {
+                return None;
+            }
+            clauses
+        }
+        ConditionExpression::LogicalOp(ref tree) if tree.operator == Operator::Or => {
+            let left = cnf_clauses(&tree.left)?;
+            let right = cnf_clauses(&tree.right)?;
+            if left.len() * right.len() > MAX_CNF_CLAUSES {
+                return None;
+            }
+            let mut clauses = Vec::with_capacity(left.len() * right.len());
+            for l in &left {
+                for r in &right {
+                    let mut clause = l.clone();
+                    clause.extend(r.iter().cloned());
+                    clauses.push(clause);
+                }
+            }
+            clauses
+        }
+        _ => vec![vec![cond.clone()]],
+    })
+}
+
+/// Simplifies a condition and rewrites it as an `AND` of `OR`s. As this grows exponentially for
+/// an `OR` of many `AND`s, a condition that would take more than `MAX_CNF_CLAUSES` clauses is
+/// only simplified.
+pub fn conjunctive_normal_form(cond: &ConditionExpression) -> SimplifiedCondition {
+    match simplify(cond) {
+        SimplifiedCondition::Expression(cond) => {
+            let clauses = match cnf_clauses(&cond) {
+                Some(clauses) => clauses,
+                None => return SimplifiedCondition::Expression(cond),
+            };
+            let clauses = clauses
+                .into_iter()
+                .filter_map(|clause| chain(Operator::Or, clause));
+            match and_conditions(clauses) {
+                Some(cnf) => simplify(&cnf),
+                None => SimplifiedCondition::True,
+            }
+        }
+        constant => constant,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condition::condition_expr;
+
+    fn simplified(cond: &str) -> String {
+        match simplify(&condition_expr(cond).unwrap().1) {
+            SimplifiedCondition::Expression(cond) => cond.to_string(),
+            constant => format!("{:?}", constant),
+        }
+    }
+
+    #[test]
+    fn simplify_conditions() {
+        assert_eq!(
+            simplified("a = 1 AND (b = 2 AND (1 = 1))"),
+            "a = 1 AND b = 2"
+        );
+        assert_eq!(simplified("NOT (NOT (a = 1))"), "a = 1");
+        assert_eq!(simplified("NOT NOT (a = 1 OR b = 2)"), "a = 1 OR b = 2");
+        assert_eq!(simplified("a = 1 OR 2 = 2"), "True");
+        // strings compare by collation, where 'a' may equal 'A '
+        assert_eq!(simplified("a = 1 OR 'a' = 'A '"), "a = 1 OR 'a' = 'A '");
+        assert_eq!(simplified("'a' != 'a'"), "'a' != 'a'");
+        assert_eq!(simplified("a = 1 AND 2 < 1"), "False");
+        assert_eq!(simplified("a = 1 AND b = 2 AND a = 1"), "a = 1 AND b = 2");
+        assert_eq!(
+            simplified("(a = 1 OR b = 2) AND c = 3"),
+            "(a = 1 OR b = 2) AND c = 3"
+        );
+    }
+
+    #[test]
+    fn conjunctive_form() {
+        let cond = condition_expr("a = 1 OR (b = 2 AND c = 3)").unwrap().1;
+        let cnf = conjunctive_normal_form(&cond).into_condition().unwrap();
+        assert_eq!(cnf.to_string(), "(a = 1 OR b = 2) AND (a = 1 OR c = 3)");
+        assert_eq!(conjuncts(&cnf).len(), 2);
+    }
+
+    #[test]
+    fn conjunctive_form_is_capped() {
+        // 2^10 clauses are distributed, 2^11 are not
+        let or_of_ands = |n: usize| {
+            let ands: Vec<_> = (0..n)
+                .map(|i| format!("(a{} = 1 AND b{} = 2)", i, i))
+                .collect();
+            condition_expr(&ands.join(" OR ")).unwrap().1
+        };
+        let cnf = conjunctive_normal_form(&or_of_ands(10))
+            .into_condition()
+            .unwrap();
+        assert_eq!(conjuncts(&cnf).len(), MAX_CNF_CLAUSES);
+        let cond = or_of_ands(40);
+        assert_eq!(
+            conjunctive_normal_form(&cond),
+            SimplifiedCondition::Expression(cond)
+        );
+    }
+}
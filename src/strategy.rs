@@ -0,0 +1,508 @@
+//! `proptest` strategies for generating AST nodes, available with the `proptest` feature, along
+//! with `Arbitrary` implementations for the statements they cover.
+//!
+//! The generated trees are limited to what the parser produces for their printed form, so that
+//! `parse_query(query.to_string())` gives back `query`: identifiers are never keywords,
+//! operands of `AND`/`OR`/`NOT` that are themselves logical operations are bracketed, and
+//! unqualified names are used where the parser does not record a table.
+//!
+//! Statements are generated for selects, compound selects, inserts, updates, deletes, `CREATE
+//! TABLE`, `CREATE VIEW`, `ALTER TABLE`, `DROP TABLE` and `SET`, with conditions, arithmetic and
+//! literals in them. Other statements, and the options of these that only some dialects accept,
+//! are not generated.
+
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+use proptest::strategy::BoxedStrategy;
+
+use alter::{AlterTableDefinition, AlterTableStatement, ColumnPosition};
+use arithmetic::{ArithmeticBase, ArithmeticExpression, ArithmeticOperator};
+use column::{Column, ColumnConstraint, ColumnSpecification};
+use common::{FieldDefinitionExpression, FieldValueExpression, Literal, Operator, Real, SqlType};
+use compound_select::{CompoundSelectOperator, CompoundSelectStatement};
+use condition::{ConditionBase, ConditionExpression, ConditionTree};
+use create::{CreateTableStatement, CreateViewStatement, SelectSpecification};
+use delete::DeleteStatement;
+use drop::DropTableStatement;
+use ident::Ident;
+use insert::InsertStatement;
+use order::{FieldReference, OrderClause, OrderType};
+use parser::SqlQuery;
+use select::{LimitClause, SelectStatement};
+use set::{SetStatement, SetValue};
+use table::Table;
+use update::UpdateStatement;
+
+/// An identifier that is never a keyword and needs no quoting.
+pub fn identifier() -> impl Strategy<Value = String> {
+    "[a-z]{1,6}_[0-9]{1,3}"
+}
+
+pub fn table() -> impl Strategy<Value = Table> {
+    identifier().prop_map(|name| Table::from(name.as_str()))
+}
+
+/// A column, optionally qualified with a table name.
+pub fn column() -> impl Strategy<Value = Column> {
+    (option::of(identifier()), identifier()).prop_map(|(table, name)| Column {
+        name: name.into(),
+        alias: None,
+        table: table.map(Ident::from),
+        function: None,
+    })
+}
+
+fn unqualified_column() -> impl Strategy<Value = Column> {
+    identifier().prop_map(|name| Column::from(name.as_str()))
+}
+
+fn assignments() -> impl Strategy<Value = Vec<(Column, FieldValueExpression)>> {
+    vec((unqualified_column(), literal()), 1..4).prop_map(|assignments| {
+        assignments
+            .into_iter()
+            .map(|(c, l)| (c, FieldValueExpression::Literal(l.into())))
+            .collect()
+    })
+}
+
+pub fn literal() -> impl Strategy<Value = Literal> {
+    prop_oneof![
+        Just(Literal::Null),
+        Just(Literal::Placeholder),
+        (-1_000_000_000i64..1_000_000_000).prop_map(Literal::Integer),
+        (-1_000_000i128..1_000_000, 1u8..4)
+            .prop_map(|(mantissa, scale)| Literal::FixedPoint(Real::new(mantissa, scale))),
+        "[a-zA-Z0-9 ']{0,8}".prop_map(Literal::String),
+    ]
+}
+
+fn comparison_operator() -> impl Strategy<Value = Operator> {
+    prop_oneof![
+        Just(Operator::Equal),
+        Just(Operator::NotEqual),
+        Just(Operator::Greater),
+        Just(Operator::GreaterOrEqual),
+        Just(Operator::Less),
+        Just(Operator::LessOrEqual),
+    ]
+}
+
+fn tree(
+    operator: Operator,
+    left: ConditionExpression,
+    right: ConditionExpression,
+) -> ConditionTree {
+    ConditionTree {
+        operator,
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+}
+
+fn bracket_logical(cond: ConditionExpression) -> ConditionExpression {
+    match cond {
+        ConditionExpression::LogicalOp(_) => ConditionExpression::Bracketed(Box::new(cond)),
+        cond => cond,
+    }
+}
+
+/// A comparison of a column with a literal or another column, or an `IN` list.
+fn comparison() -> impl Strategy<Value = ConditionExpression> {
+    let field = || column().prop_map(|c| ConditionExpression::Base(ConditionBase::Field(c)));
+    let value = prop_oneof![
+        literal().prop_map(|l| ConditionExpression::Base(ConditionBase::Literal(l))),
+        field(),
+    ];
+    prop_oneof![
+        (field(), comparison_operator(), value).prop_map(|(left, op, right)| {
+            ConditionExpression::ComparisonOp(tree(op, left, right))
+        }),
+        (field(), vec(literal(), 1..4)).prop_map(|(left, list)| {
+            ConditionExpression::ComparisonOp(tree(
+                Operator::In,
+                left,
+                ConditionExpression::Base(ConditionBase::LiteralList(list)),
+            ))
+        }),
+    ]
+}
+
+/// A condition made of comparisons combined with `AND`, `OR` and `NOT`.
+pub fn condition() -> impl Strategy<Value = ConditionExpression> {
+    comparison().prop_recursive(4, 16, 2, |inner| {
+        prop_oneof![
+            (
+                prop_oneof![Just(Operator::And), Just(Operator::Or)],
+                inner.clone(),
+                inner.clone(),
+            )
+                .prop_map(|(op, left, right)| {
+                    ConditionExpression::LogicalOp(tree(
+                        op,
+                        bracket_logical(left),
+                        bracket_logical(right),
+                    ))
+                }),
+            inner
+                .clone()
+                .prop_map(|c| ConditionExpression::Bracketed(Box::new(c))),
+            inner.prop_map(|c| {
+                ConditionExpression::NegationOp(Box::new(ConditionExpression::Bracketed(Box::new(
+                    c,
+                ))))
+            }),
+        ]
+    })
+}
+
+fn arithmetic_operator() -> impl Strategy<Value = ArithmeticOperator> {
+    prop_oneof![
+        Just(ArithmeticOperator::Add),
+        Just(ArithmeticOperator::Subtract),
+        Just(ArithmeticOperator::Multiply),
+        Just(ArithmeticOperator::Divide),
+    ]
+}
+
+fn arithmetic_operand() -> impl Strategy<Value = ArithmeticBase> {
+    prop_oneof![
+        unqualified_column().prop_map(ArithmeticBase::Column),
+        (0i64..1000).prop_map(|n| ArithmeticBase::Scalar(Literal::Integer(n))),
+    ]
+}
+
+/// An arithmetic expression over columns and integers, with operands that are themselves
+/// expressions parenthesized.
+pub fn arithmetic_expression() -> impl Strategy<Value = ArithmeticExpression> {
+    let leaf = (
+        arithmetic_operator(),
+        arithmetic_operand(),
+        arithmetic_operand(),
+    )
+        .prop_map(|(op, left, right)| ArithmeticExpression::new(op, left, right, None));
+    leaf.prop_recursive(3, 8, 2, |inner| {
+        (
+            arithmetic_operator(),
+            prop_oneof![
+                arithmetic_operand(),
+                inner
+                    .clone()
+                    .prop_map(|e| ArithmeticBase::Nested(Box::new(e))),
+            ],
+            inner.prop_map(|e| ArithmeticBase::Nested(Box::new(e))),
+        )
+            .prop_map(|(op, left, right)| ArithmeticExpression::new(op, left, right, None))
+    })
+}
+
+fn field_definition() -> impl Strategy<Value = FieldDefinitionExpression> {
+    prop_oneof![
+        Just(FieldDefinitionExpression::All),
+        column().prop_map(FieldDefinitionExpression::Col),
+        (arithmetic_expression(), option::of(identifier())).prop_map(|(mut expr, alias)| {
+            expr.alias = alias;
+            FieldDefinitionExpression::Value(FieldValueExpression::Arithmetic(expr))
+        }),
+    ]
+}
+
+fn order_clause() -> impl Strategy<Value = OrderClause> {
+    let field = prop_oneof![
+        unqualified_column().prop_map(FieldReference::Column),
+        (1u64..10).prop_map(FieldReference::Position),
+    ];
+    let order = prop_oneof![
+        Just(OrderType::OrderAscending),
+        Just(OrderType::OrderDescending),
+    ];
+    vec((field, order), 1..3).prop_map(|columns| OrderClause { columns })
+}
+
+fn limit_clause() -> impl Strategy<Value = LimitClause> {
+    (1u64..1000, 0u64..1000).prop_map(|(limit, offset)| LimitClause { limit, offset })
+}
+
+pub fn select_statement() -> impl Strategy<Value = SelectStatement> {
+    (
+        vec(table(), 1..3),
+        any::<bool>(),
+        vec(field_definition(), 1..4),
+        option::of(condition()),
+        option::of(order_clause()),
+        option::of(limit_clause()),
+    )
+        .prop_map(
+            |(tables, distinct, fields, where_clause, order, limit)| SelectStatement {
+                tables,
+                distinct,
+                fields,
+                where_clause,
+                order,
+                limit,
+                ..Default::default()
+            },
+        )
+}
+
+pub fn compound_select_statement() -> impl Strategy<Value = CompoundSelectStatement> {
+    let operator = prop_oneof![
+        Just(CompoundSelectOperator::Union),
+        Just(CompoundSelectOperator::DistinctUnion),
+        Just(CompoundSelectOperator::Intersect),
+        Just(CompoundSelectOperator::Except),
+    ];
+    (
+        select_statement(),
+        vec((operator, select_statement()), 1..3),
+        option::of(order_clause()),
+        option::of(limit_clause()),
+    )
+        .prop_map(|(first, rest, order, limit)| CompoundSelectStatement {
+            selects: Some((None, first))
+                .into_iter()
+                .chain(rest.into_iter().map(|(op, select)| (Some(op), select)))
+                .collect(),
+            order,
+            limit,
+        })
+}
+
+fn select_specification() -> impl Strategy<Value = SelectSpecification> {
+    prop_oneof![
+        select_statement().prop_map(SelectSpecification::Simple),
+        compound_select_statement().prop_map(SelectSpecification::Compound),
+    ]
+}
+
+fn sql_type() -> impl Strategy<Value = SqlType> {
+    prop_oneof![
+        Just(SqlType::Bool),
+        (1u16..256).prop_map(SqlType::Int),
+        (1u16..256).prop_map(SqlType::Bigint),
+        (1u16..1024).prop_map(SqlType::Varchar),
+        Just(SqlType::Text),
+        Just(SqlType::Date),
+        (0u16..7).prop_map(SqlType::DateTime),
+        (1u8..30, 0u8..10).prop_map(|(m, d)| SqlType::Decimal(m, d)),
+    ]
+}
+
+fn default_value() -> impl Strategy<Value = Literal> {
+    prop_oneof![
+        Just(Literal::Null),
+        (-1_000_000_000i64..1_000_000_000).prop_map(Literal::Integer),
+        (-1_000_000i128..1_000_000, 1u8..4)
+            .prop_map(|(mantissa, scale)| Literal::FixedPoint(Real::new(mantissa, scale))),
+        "[a-zA-Z0-9 ]{0,8}".prop_map(Literal::String),
+    ]
+}
+
+fn column_constraint() -> impl Strategy<Value = ColumnConstraint> {
+    prop_oneof![
+        Just(ColumnConstraint::NotNull),
+        Just(ColumnConstraint::PrimaryKey),
+        Just(ColumnConstraint::Unique),
+        Just(ColumnConstraint::AutoIncrement),
+        default_value().prop_map(ColumnConstraint::DefaultValue),
+    ]
+}
+
+pub fn column_specification() -> impl Strategy<Value = ColumnSpecification> {
+    (
+        unqualified_column(),
+        sql_type(),
+        vec(column_constraint(), 0..3),
+    )
+        .prop_map(|(column, sql_type, constraints)| ColumnSpecification {
+            column,
+            sql_type: Some(sql_type),
+            type_attributes: Default::default(),
+            constraints,
+            comment: None,
+        })
+}
+
+pub fn create_table_statement() -> impl Strategy<Value = CreateTableStatement> {
+    (table(), vec(column_specification(), 1..4), any::<bool>()).prop_map(
+        |(table, mut fields, if_not_exists)| {
+            // the parser qualifies every column with the table being created
+            for field in &mut fields {
+                field.column.table = Some(table.name.clone());
+            }
+            CreateTableStatement {
+                table,
+                fields,
+                if_not_exists,
+                ..Default::default()
+            }
+        },
+    )
+}
+
+pub fn create_view_statement() -> impl Strategy<Value = CreateViewStatement> {
+    (identifier(), any::<bool>(), select_specification()).prop_map(
+        |(name, or_replace, definition)| CreateViewStatement {
+            name,
+            fields: vec![],
+            definition: Box::new(definition),
+            or_replace,
+            algorithm: None,
+            definer: None,
+            security: None,
+            check_option: None,
+        },
+    )
+}
+
+fn alter_table_definition() -> impl Strategy<Value = AlterTableDefinition> {
+    let position = option::of(prop_oneof![
+        Just(ColumnPosition::First),
+        identifier().prop_map(ColumnPosition::After),
+    ]);
+    prop_oneof![
+        (column_specification(), position)
+            .prop_map(|(spec, position)| AlterTableDefinition::AddColumn(spec, position)),
+        identifier().prop_map(AlterTableDefinition::DropColumn),
+        (identifier(), identifier())
+            .prop_map(|(old, new)| AlterTableDefinition::RenameColumn(old, new)),
+    ]
+}
+
+pub fn alter_table_statement() -> impl Strategy<Value = AlterTableStatement> {
+    (table(), vec(alter_table_definition(), 1..3)).prop_map(|(table, definitions)| {
+        AlterTableStatement {
+            table,
+            definitions,
+            ..Default::default()
+        }
+    })
+}
+
+pub fn drop_table_statement() -> impl Strategy<Value = DropTableStatement> {
+    (vec(table(), 1..3), any::<bool>(), any::<bool>()).prop_map(|(tables, if_exists, cascade)| {
+        DropTableStatement {
+            tables,
+            if_exists,
+            cascade,
+        }
+    })
+}
+
+pub fn set_statement() -> impl Strategy<Value = SetStatement> {
+    let value = prop_oneof![
+        (0i64..1000).prop_map(|n| SetValue::Literal(Literal::Integer(n))),
+        "[a-z0-9 ]{0,8}".prop_map(|s| SetValue::Literal(Literal::String(s))),
+        identifier().prop_map(SetValue::Identifier),
+    ];
+    (identifier(), value).prop_map(|(variable, value)| SetStatement { variable, value })
+}
+
+pub fn insert_statement() -> impl Strategy<Value = InsertStatement> {
+    (table(), 1usize..4)
+        .prop_flat_map(|(table, width)| {
+            (
+                Just(table),
+                vec(unqualified_column(), width),
+                vec(
+                    vec(literal().prop_map(FieldValueExpression::from), width),
+                    1..3,
+                ),
+                any::<bool>(),
+                any::<bool>(),
+                option::of(assignments()),
+            )
+        })
+        .prop_map(
+            |(table, fields, data, row_constructors, ignore, on_duplicate)| InsertStatement {
+                table,
+                fields: Some(fields),
+                data,
+                row_constructors,
+                ignore,
+                on_duplicate,
+                ..Default::default()
+            },
+        )
+}
+
+pub fn update_statement() -> impl Strategy<Value = UpdateStatement> {
+    (table(), assignments(), option::of(condition())).prop_map(|(table, fields, where_clause)| {
+        UpdateStatement {
+            table,
+            fields,
+            where_clause,
+            ..Default::default()
+        }
+    })
+}
+
+pub fn delete_statement() -> impl Strategy<Value = DeleteStatement> {
+    (table(), option::of(condition())).prop_map(|(table, where_clause)| DeleteStatement {
+        table,
+        where_clause,
+        ..Default::default()
+    })
+}
+
+/// A query of any of the kinds above.
+pub fn sql_query() -> impl Strategy<Value = SqlQuery> {
+    prop_oneof![
+        select_statement().prop_map(SqlQuery::Select),
+        compound_select_statement().prop_map(SqlQuery::CompoundSelect),
+        insert_statement().prop_map(SqlQuery::Insert),
+        update_statement().prop_map(SqlQuery::Update),
+        delete_statement().prop_map(SqlQuery::Delete),
+        create_table_statement().prop_map(SqlQuery::CreateTable),
+        create_view_statement().prop_map(SqlQuery::CreateView),
+        alter_table_statement().prop_map(SqlQuery::AlterTable),
+        drop_table_statement().prop_map(SqlQuery::DropTable),
+        set_statement().prop_map(SqlQuery::Set),
+    ]
+}
+
+macro_rules! arbitrary {
+    ($($ty:ty => $strategy:expr,)*) => {
+        $(
+            impl Arbitrary for $ty {
+                type Parameters = ();
+                type Strategy = BoxedStrategy<$ty>;
+
+                fn arbitrary_with(_: ()) -> Self::Strategy {
+                    $strategy.boxed()
+                }
+            }
+        )*
+    };
+}
+
+arbitrary! {
+    SqlQuery => sql_query(),
+    SelectStatement => select_statement(),
+    CompoundSelectStatement => compound_select_statement(),
+    InsertStatement => insert_statement(),
+    UpdateStatement => update_statement(),
+    DeleteStatement => delete_statement(),
+    CreateTableStatement => create_table_statement(),
+    CreateViewStatement => create_view_statement(),
+    AlterTableStatement => alter_table_statement(),
+    DropTableStatement => drop_table_statement(),
+    SetStatement => set_statement(),
+    ConditionExpression => condition(),
+    ArithmeticExpression => arithmetic_expression(),
+    Literal => literal(),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::parse_query;
+
+    proptest! {
+        #[test]
+        fn display_round_trip(query: SqlQuery) {
+            prop_assert_eq!(parse_query(query.to_string()), Ok(query));
+        }
+    }
+}
@@ -0,0 +1,538 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+use std::mem;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use common::is_sql_identifier;
+use dialect::Dialect;
+use parser::{parse_query_with_options, ParseError, ParseOptions, SqlQuery};
+
+/// Lexical context of the splitter at the end of the text consumed so far.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScanState {
+    Normal,
+    /// Within quotes, and whether a backslash escapes the next character there.
+    Quoted(char, bool),
+    LineComment,
+    BlockComment,
+}
+
+/// Splits SQL text read from a `BufRead` into individual statements.
+///
+/// Input is consumed a line at a time, so only the statement currently being assembled is held
+/// in memory. Delimiters inside string literals, quoted identifiers and comments are ignored, and
+/// the mysql client's `DELIMITER` command is honoured. Comments are removed from the returned
+/// statement text, and statements that consist only of whitespace and comments are skipped.
+pub struct StatementSplitter<R> {
+    reader: R,
+    dialect: Dialect,
+    delimiter: String,
+    state: ScanState,
+    current: String,
+    ready: VecDeque<String>,
+    line: String,
+    eof: bool,
+}
+
+impl<R: Read> StatementSplitter<BufReader<R>> {
+    /// Creates a splitter over an unbuffered reader.
+    pub fn from_reader(reader: R) -> Self {
+        StatementSplitter::new(BufReader::new(reader))
+    }
+}
+
+impl<R: BufRead> StatementSplitter<R> {
+    pub fn new(reader: R) -> Self {
+        StatementSplitter {
+            reader,
+            dialect: Dialect::default(),
+            delimiter: String::from(";"),
+            state: ScanState::Normal,
+            current: String::new(),
+            ready: VecDeque::new(),
+            line: String::new(),
+            eof: false,
+        }
+    }
+
+    /// Splits text written for `dialect`. A backslash escapes the next character in a string, but
+    /// under `Dialect::Postgres` only in an `E'...'` string, as Postgres reads other strings as
+    /// written.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Whether a backslash escapes the next character in a string opened by `quote`, which
+    /// follows the statement text assembled so far.
+    fn backslash_escapes(&self, quote: char) -> bool {
+        match quote {
+            '`' => false,
+            '\'' if self.dialect == Dialect::Postgres => {
+                let mut before = self.current.chars().rev();
+                before.next().is_some_and(|c| c.eq_ignore_ascii_case(&'e'))
+                    && !before.next().is_some_and(is_sql_identifier)
+            }
+            _ => self.dialect != Dialect::Postgres,
+        }
+    }
+
+    /// The statement delimiter currently in effect.
+    pub fn delimiter(&self) -> &str {
+        &self.delimiter
+    }
+
+    fn finish_statement(&mut self) {
+        let stmt = mem::take(&mut self.current);
+        let trimmed = stmt.trim();
+        if !trimmed.is_empty() {
+            self.ready.push_back(String::from(trimmed));
+        }
+    }
+
+    fn scan_line(&mut self) {
+        let line = mem::take(&mut self.line);
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match self.state {
+                ScanState::Quoted(q, escapes) => {
+                    self.current.push(c);
+                    if c == '\\' && escapes {
+                        if let Some((_, escaped)) = chars.next() {
+                            self.current.push(escaped);
+                        }
+                    } else if c == q {
+                        self.state = ScanState::Normal;
+                    }
+                }
+                ScanState::LineComment => {
+                    if c == '\n' {
+                        self.current.push(c);
+                        self.state = ScanState::Normal;
+                    }
+                }
+                ScanState::BlockComment => {
+                    if c == '*' && chars.peek().map(|&(_, n)| n) == Some('/') {
+                        chars.next();
+                        self.current.push(' ');
+                        self.state = ScanState::Normal;
+                    }
+                }
+                ScanState::Normal => {
+                    let rest = &line[i..];
+                    if self.current.trim().is_empty() && starts_with_delimiter_command(rest) {
+                        let new_delimiter = rest["delimiter".len()..].trim();
+                        if !new_delimiter.is_empty() {
+                            self.delimiter = String::from(new_delimiter);
+                        }
+                        self.current.clear();
+                        break;
+                    }
+                    if rest.starts_with(self.delimiter.as_str()) {
+                        for _ in 1..self.delimiter.chars().count() {
+                            chars.next();
+                        }
+                        self.finish_statement();
+                    } else if c == '\'' || c == '"' || c == '`' {
+                        self.state = ScanState::Quoted(c, self.backslash_escapes(c));
+                        self.current.push(c);
+                    } else if (c == '#' && !rest.starts_with("#>")) || starts_line_comment(rest) {
+                        self.state = ScanState::LineComment;
+                    } else if rest.starts_with("/*") {
+                        chars.next();
+                        self.state = ScanState::BlockComment;
+                    } else {
+                        self.current.push(c);
+                    }
+                }
+            }
+        }
+        // hand the buffer back so that its allocation is reused for the next line
+        self.line = line;
+        self.line.clear();
+    }
+}
+
+fn starts_with_delimiter_command(s: &str) -> bool {
+    let keyword = "delimiter";
+    s.len() > keyword.len()
+        && s.is_char_boundary(keyword.len())
+        && s[..keyword.len()].eq_ignore_ascii_case(keyword)
+        && s[keyword.len()..].starts_with([' ', '\t'])
+}
+
+/// `--` only starts a comment if followed by whitespace (or the end of the input), so that
+/// `1--1` remains an arithmetic expression.
+fn starts_line_comment(s: &str) -> bool {
+    s.starts_with("--") && s[2..].chars().next().is_none_or(char::is_whitespace)
+}
+
+impl<R: BufRead> Iterator for StatementSplitter<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        loop {
+            if let Some(stmt) = self.ready.pop_front() {
+                return Some(Ok(stmt));
+            }
+            if self.eof {
+                return None;
+            }
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => {
+                    self.eof = true;
+                    // the last statement does not need to be terminated
+                    self.finish_statement();
+                }
+                Ok(_) => self.scan_line(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Error produced while streaming statements.
+#[derive(Debug)]
+pub enum StreamError {
+    /// Reading from the underlying reader failed.
+    Io(io::Error),
+    /// A statement could not be parsed; holds the statement text and the parser's message.
+    Parse(String, &'static str),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StreamError::Io(ref e) => write!(f, "I/O error: {}", e),
+            StreamError::Parse(ref stmt, msg) => write!(f, "{}: {}", msg, stmt),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            StreamError::Io(ref e) => Some(e),
+            StreamError::Parse(..) => None,
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        StreamError::Io(e)
+    }
+}
+
+/// Iterator over the parsed statements of a SQL script, read incrementally.
+///
+/// A statement that fails to parse is reported as an error item, or as `SqlQuery::Unparsed` in
+/// lenient mode; either way iteration continues with the next statement.
+pub struct StatementStream<R> {
+    splitter: StatementSplitter<R>,
+    lenient: bool,
+    options: ParseOptions,
+}
+
+impl<R: Read> StatementStream<BufReader<R>> {
+    /// Creates a stream over an unbuffered reader.
+    pub fn from_reader(reader: R) -> Self {
+        StatementStream::new(BufReader::new(reader))
+    }
+}
+
+impl<R: BufRead> StatementStream<R> {
+    pub fn new(reader: R) -> Self {
+        StatementStream {
+            splitter: StatementSplitter::new(reader),
+            lenient: false,
+            options: ParseOptions::default(),
+        }
+    }
+
+    /// Yields statements that fail to parse as `SqlQuery::Unparsed` rather than as errors.
+    pub fn lenient(mut self) -> Self {
+        self.lenient = true;
+        self
+    }
+
+    /// Splits and parses each statement with the dialect and limits given by `options`.
+    pub fn options(mut self, options: ParseOptions) -> Self {
+        self.splitter.dialect = options.dialect;
+        self.options = options;
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for StatementStream<R> {
+    type Item = Result<SqlQuery, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.splitter.next().map(|stmt| {
+            let stmt = stmt?;
+            match parse_query_with_options(&stmt, &self.options) {
+                Ok(q) => Ok(q),
+                Err(e) if self.lenient => Ok(SqlQuery::Unparsed(stmt, ParseError::from(e))),
+                Err(e) => Err(StreamError::Parse(stmt, e)),
+            }
+        })
+    }
+}
+
+fn parse_lenient(stmt: String, options: &ParseOptions) -> SqlQuery {
+    match parse_query_with_options(&stmt, options) {
+        Ok(q) => q,
+        Err(e) => SqlQuery::Unparsed(stmt, ParseError::from(e)),
+    }
+}
+
+fn split_script(input: &str, dialect: Dialect) -> impl Iterator<Item = String> + '_ {
+    StatementSplitter::new(input.as_bytes())
+        .dialect(dialect)
+        .filter_map(|stmt| stmt.ok())
+}
+
+/// Parses every statement in `input`, keeping statements that fail to parse as
+/// `SqlQuery::Unparsed` instead of aborting.
+pub fn parse_script_lenient<T>(input: T) -> Vec<SqlQuery>
+where
+    T: AsRef<str>,
+{
+    parse_script_lenient_with_options(input, &ParseOptions::default())
+}
+
+/// Like `parse_script_lenient`, with the dialect and limits given by `options`.
+pub fn parse_script_lenient_with_options<T>(input: T, options: &ParseOptions) -> Vec<SqlQuery>
+where
+    T: AsRef<str>,
+{
+    split_script(input.as_ref(), options.dialect)
+        .map(|stmt| parse_lenient(stmt, options))
+        .collect()
+}
+
+/// Like `parse_script_lenient`, but parses the statements on rayon's thread pool once the script
+/// has been split into statements, which only takes a quick scan. The statements are returned in
+/// the order they appear in the script.
+#[cfg(feature = "rayon")]
+pub fn parse_script_parallel<T>(input: T) -> Vec<SqlQuery>
+where
+    T: AsRef<str>,
+{
+    parse_script_parallel_with_options(input, &ParseOptions::default())
+}
+
+/// Like `parse_script_parallel`, with the dialect and limits given by `options`. Each job is
+/// handed the options, as the worker threads do not share the caller's parser settings.
+#[cfg(feature = "rayon")]
+pub fn parse_script_parallel_with_options<T>(input: T, options: &ParseOptions) -> Vec<SqlQuery>
+where
+    T: AsRef<str>,
+{
+    let statements: Vec<_> = split_script(input.as_ref(), options.dialect).collect();
+    let options = *options;
+    statements
+        .into_par_iter()
+        .map(|stmt| parse_lenient(stmt, &options))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(input: &str) -> Vec<String> {
+        StatementSplitter::new(input.as_bytes())
+            .map(|s| s.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_terminators() {
+        assert_eq!(
+            split("SELECT 1 FROM t;\nSELECT a FROM u; SELECT b FROM v"),
+            vec!["SELECT 1 FROM t", "SELECT a FROM u", "SELECT b FROM v"]
+        );
+    }
+
+    #[test]
+    fn ignores_delimiters_in_strings_and_comments() {
+        assert_eq!(
+            split(
+                "-- a comment; still a comment\n\
+                 INSERT INTO t VALUES ('a;b', \"c\\\";d\"); # trailing; comment\n\
+                 /* block; comment */ SELECT `x;y` FROM t;\n\
+                 /*!40101 SET NAMES utf8 */;\n"
+            ),
+            vec![
+                "INSERT INTO t VALUES ('a;b', \"c\\\";d\")",
+                "SELECT `x;y` FROM t",
+            ]
+        );
+    }
+
+    #[test]
+    fn backslash_in_backtick_identifier() {
+        assert_eq!(
+            split("SELECT `a\\` FROM t; SELECT 'b\\'' FROM u;"),
+            vec!["SELECT `a\\` FROM t", "SELECT 'b\\'' FROM u"]
+        );
+    }
+
+    #[test]
+    fn backslashes_by_dialect() {
+        let postgres = |input: &str| -> Vec<String> {
+            StatementSplitter::new(input.as_bytes())
+                .dialect(Dialect::Postgres)
+                .map(|s| s.unwrap())
+                .collect()
+        };
+        let input = "SELECT 'C:\\'; SELECT 1";
+        assert_eq!(split(input), vec![input]);
+        assert_eq!(postgres(input), vec!["SELECT 'C:\\'", "SELECT 1"]);
+        assert_eq!(
+            postgres("SELECT \"a\\\", e'b\\';c' FROM t; SELECT 2"),
+            vec!["SELECT \"a\\\", e'b\\';c' FROM t", "SELECT 2"]
+        );
+
+        // the same values, as MySQL writes them
+        let options = ParseOptions::dialect(Dialect::Postgres);
+        assert_eq!(
+            parse_script_lenient_with_options(
+                "INSERT INTO t VALUES ('C:\\'); SELECT 'C:\\path', E'a\\'b' FROM t",
+                &options
+            ),
+            parse_script_lenient(
+                "INSERT INTO t VALUES ('C:\\\\'); SELECT 'C:\\\\path', 'a\\'b' FROM t"
+            )
+        );
+    }
+
+    #[test]
+    fn multiline_statements() {
+        assert_eq!(
+            split("CREATE TABLE t (\n  a int,\n  b text\n);\n"),
+            vec!["CREATE TABLE t (\n  a int,\n  b text\n)"]
+        );
+    }
+
+    #[test]
+    fn delimiter_command() {
+        let input = "DELIMITER ;;\n\
+                     SELECT 1 FROM t;;\n\
+                     DELIMITER ;\n\
+                     SELECT 2 FROM t;";
+        assert_eq!(split(input), vec!["SELECT 1 FROM t", "SELECT 2 FROM t"]);
+    }
+
+    #[test]
+    fn stream_parses_statements() {
+        let input = "INSERT INTO users VALUES (1, 'a');\nnot sql;\nDELETE FROM users;";
+        let results: Vec<_> = StatementStream::from_reader(input.as_bytes()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        match results[1] {
+            Err(StreamError::Parse(ref stmt, _)) => assert_eq!(stmt, "not sql"),
+            ref r => panic!("unexpected result {:?}", r),
+        }
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn lenient_script_parsing() {
+        let input = "SELECT id FROM users;\nSELEC oops FROM;\nDELETE FROM users WHERE id = 1;";
+        let queries = parse_script_lenient(input);
+        assert_eq!(queries.len(), 3);
+        assert_eq!(
+            queries[1],
+            SqlQuery::Unparsed(
+                String::from("SELEC oops FROM"),
+                ParseError::from("unsupported statement type")
+            )
+        );
+        assert_eq!(format!("{}", queries[1]), "SELEC oops FROM");
+        assert_eq!(format!("{}", queries[2]), "DELETE FROM users WHERE id = 1");
+
+        let streamed: Vec<_> = StatementStream::new(input.as_bytes())
+            .lenient()
+            .map(|q| q.unwrap())
+            .collect();
+        assert_eq!(streamed, queries);
+    }
+
+    #[test]
+    fn pg_dump_prelude() {
+        let input = "SET statement_timeout = 0;\n\
+                     SET client_encoding = 'UTF8';\n\
+                     SET standard_conforming_strings = on;\n\
+                     SELECT pg_catalog.set_config('search_path', '', false);\n\
+                     SET check_function_bodies = false;\n\
+                     SET xmloption = content;\n\
+                     SET row_security = off;\n\
+                     CREATE EXTENSION IF NOT EXISTS \"uuid-ossp\" WITH SCHEMA public;\n\
+                     SET search_path = public, pg_catalog;\n\
+                     SET default_tablespace = '';";
+        let queries = parse_script_lenient(input);
+        assert_eq!(queries.len(), 10);
+        for q in &queries {
+            if let SqlQuery::Unparsed(ref stmt, _) = *q {
+                panic!("failed to parse {}", stmt);
+            }
+        }
+        assert_eq!(
+            queries[2].to_string(),
+            "SET standard_conforming_strings = on"
+        );
+        assert_eq!(
+            queries[3].to_string(),
+            "SELECT pg_catalog.set_config('search_path', '', false)"
+        );
+        match queries[8] {
+            SqlQuery::SetSearchPath(ref set) => {
+                assert_eq!(set.schemas, vec!["public", "pg_catalog"]);
+            }
+            ref q => panic!("unexpected query {:?}", q),
+        }
+    }
+
+    #[test]
+    fn postgres_dialect_scripts() {
+        let input = "CREATE TABLE hosts (id uuid, addr inet);\nSELECT id FROM hosts;";
+        let options = ParseOptions::dialect(Dialect::Postgres);
+        match parse_script_lenient(input)[0] {
+            SqlQuery::Unparsed(..) => (),
+            ref q => panic!("unexpected query {:?}", q),
+        }
+        let queries = parse_script_lenient_with_options(input, &options);
+        assert_eq!(
+            queries[0].to_string(),
+            "CREATE TABLE hosts (id UUID, addr INET)"
+        );
+        let streamed: Vec<_> = StatementStream::new(input.as_bytes())
+            .options(options)
+            .map(|q| q.unwrap())
+            .collect();
+        assert_eq!(streamed, queries);
+        #[cfg(feature = "rayon")]
+        assert_eq!(parse_script_parallel_with_options(input, &options), queries);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_script_parsing() {
+        let script: String = (0..500)
+            .map(|i| match i % 3 {
+                0 => format!("INSERT INTO t VALUES ({}, 'a;{}');\n", i, i),
+                1 => format!("SELECT id FROM t WHERE id = {}; -- {};\n", i, i),
+                _ => format!("not sql {};\n", i),
+            })
+            .collect();
+        let queries = parse_script_parallel(&script);
+        assert_eq!(queries.len(), 500);
+        assert_eq!(queries, parse_script_lenient(&script));
+    }
+}
@@ -1,29 +1,280 @@
 use std::fmt;
 use std::str;
 
-use keywords::escape_if_keyword;
+use arithmetic::ArithmeticBase;
+use common::Literal;
+use format::QuoteStyle;
+use ident::Ident;
+use keywords::{escape_function_name, escape_identifier, escape_if_keyword, escape_quoted};
+use select::SelectStatement;
+use values::ValuesStatement;
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// Arguments and output column names of a set-returning function used in place of a table, as
+/// in `FROM generate_series(1, 10) AS g(n)`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct TableFunction {
+    pub arguments: Vec<ArithmeticBase>,
+    pub column_aliases: Vec<String>,
+    /// Set for Postgres' `ROWS FROM (f(a), g(b))`, holding the names and arguments of the
+    /// functions after the first, whose results are placed side by side with its own.
+    #[serde(default)]
+    pub rows_from: Option<Vec<(String, Vec<ArithmeticBase>)>>,
+    /// `WITH ORDINALITY`, which numbers the rows in an extra last column.
+    #[serde(default)]
+    pub with_ordinality: bool,
+}
+
+impl TableFunction {
+    /// The arguments of all the functions called, in order.
+    pub fn all_arguments(&self) -> impl Iterator<Item = &ArithmeticBase> {
+        let others = self.rows_from.iter().flatten();
+        self.arguments
+            .iter()
+            .chain(others.flat_map(|(_, arguments)| arguments))
+    }
+
+    /// Mutable access to the arguments of all the functions called, in order.
+    pub fn all_arguments_mut(&mut self) -> impl Iterator<Item = &mut ArithmeticBase> {
+        let others = self.rows_from.iter_mut().flatten();
+        self.arguments
+            .iter_mut()
+            .chain(others.flat_map(|(_, arguments)| arguments))
+    }
+}
+
+fn call(name: &str, arguments: &[ArithmeticBase]) -> String {
+    let arguments = arguments
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{}({})", escape_function_name(name), arguments)
+}
+
+/// Rows given in place of a table, as in `FROM (VALUES (1, 'a')) AS t(id, name)`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct TableValues {
+    pub values: ValuesStatement,
+    pub column_aliases: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum IndexHintType {
+    Use,
+    Force,
+    Ignore,
+}
+
+impl fmt::Display for IndexHintType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexHintType::Use => write!(f, "USE"),
+            IndexHintType::Force => write!(f, "FORCE"),
+            IndexHintType::Ignore => write!(f, "IGNORE"),
+        }
+    }
+}
+
+/// The part of query execution an index hint is restricted to (`FOR JOIN`, ...).
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum IndexHintScope {
+    Join,
+    OrderBy,
+    GroupBy,
+}
+
+impl fmt::Display for IndexHintScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexHintScope::Join => write!(f, "JOIN"),
+            IndexHintScope::OrderBy => write!(f, "ORDER BY"),
+            IndexHintScope::GroupBy => write!(f, "GROUP BY"),
+        }
+    }
+}
+
+/// MySQL index hint, e.g. `FORCE INDEX FOR JOIN (idx_a, idx_b)`. `KEY` is accepted as a synonym
+/// for `INDEX` and printed as the latter.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct IndexHint {
+    pub hint_type: IndexHintType,
+    pub scope: Option<IndexHintScope>,
+    pub indexes: Vec<String>,
+}
+
+impl fmt::Display for IndexHint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} INDEX", self.hint_type)?;
+        if let Some(scope) = self.scope {
+            write!(f, " FOR {}", scope)?;
+        }
+        let indexes = self
+            .indexes
+            .iter()
+            .map(|i| {
+                // the primary key's name is `PRIMARY`, which is never quoted in a hint
+                if i.eq_ignore_ascii_case("primary") {
+                    i.clone()
+                } else {
+                    escape_if_keyword(i)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, " ({})", indexes)
+    }
+}
+
+/// `TABLESAMPLE method (arguments) [REPEATABLE (seed)]`
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct TableSample {
+    pub method: String,
+    pub arguments: Vec<Literal>,
+    pub repeatable: Option<Literal>,
+}
+
+impl fmt::Display for TableSample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "TABLESAMPLE {} ({})", self.method, arguments)?;
+        if let Some(ref seed) = self.repeatable {
+            write!(f, " REPEATABLE ({})", seed)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct Table {
-    pub name: String,
-    pub alias: Option<String>,
+    pub name: Ident,
+    pub alias: Option<Ident>,
+    /// The quotes `name` was written in, if any, which it keeps when printed.
+    #[serde(default)]
+    pub quote: Option<QuoteStyle>,
+    /// The quotes `alias` was written in, if any.
+    #[serde(default)]
+    pub alias_quote: Option<QuoteStyle>,
+    /// The schema (or database) the table is in, when the name is qualified as in `public.t`.
+    #[serde(default)]
+    pub schema: Option<Ident>,
+    /// The quotes `schema` was written in, if any.
+    #[serde(default)]
+    pub schema_quote: Option<QuoteStyle>,
+    /// Set when `name` is a function called in the FROM clause rather than a stored table.
+    pub function: Option<Box<TableFunction>>,
+    /// Set when the table is a list of rows given in the query, `name` being its alias.
+    #[serde(default)]
+    pub values: Option<Box<TableValues>>,
+    /// Set when the table is a subquery in the FROM clause, `name` being its alias.
+    #[serde(default)]
+    pub subquery: Option<Box<SelectStatement>>,
+    /// Whether the subquery or table function is `LATERAL`, i.e. may refer to the tables before
+    /// it in the FROM clause, as in `FROM t, LATERAL (SELECT ...) AS x`.
+    #[serde(default)]
+    pub lateral: bool,
+    pub sample: Option<TableSample>,
+    pub index_hints: Vec<IndexHint>,
+}
+
+impl Table {
+    /// Whether the table names a stored table or view, rather than being a table function, a list
+    /// of rows or a subquery.
+    pub fn is_stored(&self) -> bool {
+        self.function.is_none() && self.values.is_none() && self.subquery.is_none()
+    }
+
+    /// The name as printed, in the quotes it was written in or else quoted as `escape_identifier`
+    /// does, and qualified by its schema if it has one.
+    pub fn escaped_name(&self) -> String {
+        match self.schema {
+            Some(ref schema) => format!(
+                "{}.{}",
+                escape_quoted(schema, self.schema_quote),
+                escape_quoted(&self.name, self.quote)
+            ),
+            None => escape_quoted(&self.name, self.quote),
+        }
+    }
+}
+
+fn column_list(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|c| escape_identifier(c))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 impl fmt::Display for Table {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", escape_if_keyword(&self.name))?;
+        if self.lateral {
+            write!(f, "LATERAL ")?;
+        }
+        if let Some(ref values) = self.values {
+            write!(f, "({}) AS {}", values.values, self.escaped_name())?;
+            if !values.column_aliases.is_empty() {
+                write!(f, "({})", column_list(&values.column_aliases))?;
+            }
+            return Ok(());
+        }
+        if let Some(ref subquery) = self.subquery {
+            return write!(f, "({}) AS {}", subquery, self.escaped_name());
+        }
+        match self.function {
+            Some(ref function) => {
+                let first = call(&self.name, &function.arguments);
+                match function.rows_from {
+                    Some(ref others) => {
+                        let calls = others.iter().map(|(name, arguments)| call(name, arguments));
+                        let calls = Some(first).into_iter().chain(calls).collect::<Vec<_>>();
+                        write!(f, "ROWS FROM ({})", calls.join(", "))?;
+                    }
+                    None => write!(f, "{}", first)?,
+                }
+                if function.with_ordinality {
+                    write!(f, " WITH ORDINALITY")?;
+                }
+            }
+            None => write!(f, "{}", self.escaped_name())?,
+        }
         if let Some(ref alias) = self.alias {
-            write!(f, " AS {}", escape_if_keyword(alias))?;
+            write!(f, " AS {}", escape_quoted(alias, self.alias_quote))?;
+        }
+        if let Some(ref function) = self.function {
+            if !function.column_aliases.is_empty() {
+                write!(f, "({})", column_list(&function.column_aliases))?;
+            }
+        }
+        if let Some(ref sample) = self.sample {
+            write!(f, " {}", sample)?;
+        }
+        for hint in &self.index_hints {
+            write!(f, " {}", hint)?;
         }
         Ok(())
     }
 }
 
-impl<'a> From<&'a str> for Table {
+impl From<&str> for Table {
     fn from(t: &str) -> Table {
         Table {
-            name: String::from(t),
+            name: Ident::from(t),
             alias: None,
+            quote: None,
+            alias_quote: None,
+            schema: None,
+            schema_quote: None,
+            function: None,
+            values: None,
+            subquery: None,
+            lateral: false,
+            sample: None,
+            index_hints: vec![],
         }
     }
 }
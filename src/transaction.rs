@@ -0,0 +1,121 @@
+use nom::branch::alt;
+use nom::character::complete::multispace1;
+use nom::combinator::{map, opt, value};
+use nom::sequence::{preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use common::{sql_identifier, statement_terminator};
+use keywords::{escape_if_keyword, tag_no_case};
+
+/// Transaction control statements. The optional `WORK`/`TRANSACTION` noise words are dropped.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum TransactionStatement {
+    /// `START TRANSACTION` or `BEGIN`
+    Start,
+    Commit,
+    /// `ROLLBACK`, optionally `TO SAVEPOINT name`
+    Rollback(Option<String>),
+    Savepoint(String),
+    ReleaseSavepoint(String),
+}
+
+impl fmt::Display for TransactionStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransactionStatement::Start => write!(f, "START TRANSACTION"),
+            TransactionStatement::Commit => write!(f, "COMMIT"),
+            TransactionStatement::Rollback(None) => write!(f, "ROLLBACK"),
+            TransactionStatement::Rollback(Some(ref savepoint)) => {
+                write!(f, "ROLLBACK TO SAVEPOINT {}", escape_if_keyword(savepoint))
+            }
+            TransactionStatement::Savepoint(ref name) => {
+                write!(f, "SAVEPOINT {}", escape_if_keyword(name))
+            }
+            TransactionStatement::ReleaseSavepoint(ref name) => {
+                write!(f, "RELEASE SAVEPOINT {}", escape_if_keyword(name))
+            }
+        }
+    }
+}
+
+fn noise_word(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        opt(preceded(
+            multispace1,
+            alt((tag_no_case("work"), tag_no_case("transaction"))),
+        )),
+    )(i)
+}
+
+fn savepoint_name(i: &str) -> IResult<&str, String> {
+    map(
+        preceded(
+            tuple((tag_no_case("savepoint"), multispace1)),
+            sql_identifier,
+        ),
+        String::from,
+    )(i)
+}
+
+pub fn transaction_statement(i: &str) -> IResult<&str, TransactionStatement> {
+    terminated(
+        alt((
+            value(
+                TransactionStatement::Start,
+                tuple((
+                    tag_no_case("start"),
+                    multispace1,
+                    tag_no_case("transaction"),
+                )),
+            ),
+            value(
+                TransactionStatement::Start,
+                tuple((tag_no_case("begin"), noise_word)),
+            ),
+            value(
+                TransactionStatement::Commit,
+                tuple((tag_no_case("commit"), noise_word)),
+            ),
+            map(
+                tuple((
+                    tag_no_case("rollback"),
+                    noise_word,
+                    opt(preceded(
+                        tuple((multispace1, tag_no_case("to"), multispace1)),
+                        alt((savepoint_name, map(sql_identifier, String::from))),
+                    )),
+                )),
+                |(_, _, savepoint)| TransactionStatement::Rollback(savepoint),
+            ),
+            map(savepoint_name, TransactionStatement::Savepoint),
+            map(
+                preceded(tuple((tag_no_case("release"), multispace1)), savepoint_name),
+                TransactionStatement::ReleaseSavepoint,
+            ),
+        )),
+        statement_terminator,
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_control() {
+        let parse = |q| transaction_statement(q).unwrap().1;
+        assert_eq!(parse("BEGIN;"), TransactionStatement::Start);
+        assert_eq!(parse("start transaction"), TransactionStatement::Start);
+        assert_eq!(parse("COMMIT WORK"), TransactionStatement::Commit);
+        assert_eq!(
+            parse("ROLLBACK TO SAVEPOINT sp1"),
+            TransactionStatement::Rollback(Some(String::from("sp1")))
+        );
+        assert_eq!(
+            format!("{}", parse("release savepoint sp1;")),
+            "RELEASE SAVEPOINT sp1"
+        );
+    }
+}
@@ -1,34 +1,41 @@
-use nom::multispace;
-use nom::types::CompleteByteSlice;
-use std::{fmt, str};
+use nom::combinator::{opt, verify};
+use nom::sequence::tuple;
+use nom::IResult;
+use std::fmt;
 
 use column::Column;
 use common::{
-    assignment_expr_list, opt_multispace, statement_terminator, table_reference,
-    FieldValueExpression,
+    assignment_expr_list, keyword_whitespace, statement_terminator, table_reference, whitespace0,
+    whitespace1, FieldValueExpression,
 };
 use condition::ConditionExpression;
-use keywords::escape_if_keyword;
-use select::where_clause;
+use keywords::tag_no_case;
+use order::{order_clause, OrderClause};
+use select::{limit_clause, where_clause, LimitClause};
 use table::Table;
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct UpdateStatement {
     pub table: Table,
     pub fields: Vec<(Column, FieldValueExpression)>,
     pub where_clause: Option<ConditionExpression>,
+    /// MySQL's `ORDER BY` and `LIMIT` on single-table updates, which take no offset.
+    #[serde(default)]
+    pub order: Option<OrderClause>,
+    #[serde(default)]
+    pub limit: Option<LimitClause>,
 }
 
 impl fmt::Display for UpdateStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "UPDATE {} ", escape_if_keyword(&self.table.name))?;
-        assert!(self.fields.len() > 0);
+        write!(f, "UPDATE {} ", self.table)?;
+        assert!(!self.fields.is_empty());
         write!(
             f,
             "SET {}",
             self.fields
                 .iter()
-                .map(|&(ref col, ref literal)| format!("{} = {}", col, literal.to_string()))
+                .map(|(col, literal)| format!("{} = {}", col, literal))
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
@@ -36,29 +43,44 @@ impl fmt::Display for UpdateStatement {
             write!(f, " WHERE ")?;
             write!(f, "{}", where_clause)?;
         }
+        if let Some(ref order) = self.order {
+            write!(f, " {}", order)?;
+        }
+        if let Some(ref limit) = self.limit {
+            write!(f, " {}", limit)?;
+        }
         Ok(())
     }
 }
 
-named!(pub updating<CompleteByteSlice, UpdateStatement>,
-    do_parse!(
-        tag_no_case!("update") >>
-        multispace >>
-        table: table_reference >>
-        multispace >>
-        tag_no_case!("set") >>
-        multispace >>
-        fields: assignment_expr_list >>
-        opt_multispace >>
-        cond: opt!(where_clause) >>
-        statement_terminator >>
-        (UpdateStatement {
-            table: table,
-            fields: fields,
-            where_clause: cond,
-        })
-    )
-);
+pub fn updating(i: &str) -> IResult<&str, UpdateStatement> {
+    let (remaining_input, (_, _, table, _, _, _, fields, _, where_clause, order, limit, _)) =
+        tuple((
+            tag_no_case("update"),
+            whitespace1,
+            table_reference,
+            whitespace1,
+            tag_no_case("set"),
+            keyword_whitespace,
+            assignment_expr_list,
+            whitespace0,
+            opt(where_clause),
+            opt(order_clause),
+            opt(verify(limit_clause, |limit| limit.offset == 0)),
+            statement_terminator,
+        ))(i)?;
+
+    Ok((
+        remaining_input,
+        UpdateStatement {
+            table,
+            fields,
+            where_clause,
+            order,
+            limit,
+        },
+    ))
+}
 
 #[cfg(test)]
 mod tests {
@@ -69,13 +91,14 @@ mod tests {
     use condition::ConditionBase::*;
     use condition::ConditionExpression::*;
     use condition::ConditionTree;
+    use format::QuoteStyle;
     use table::Table;
 
     #[test]
     fn simple_update() {
         let qstring = "UPDATE users SET id = 42, name = 'test'";
 
-        let res = updating(CompleteByteSlice(qstring.as_bytes()));
+        let res = updating(qstring);
         assert_eq!(
             res.unwrap().1,
             UpdateStatement {
@@ -101,7 +124,7 @@ mod tests {
     fn update_with_where_clause() {
         let qstring = "UPDATE users SET id = 42, name = 'test' WHERE id = 1";
 
-        let res = updating(CompleteByteSlice(qstring.as_bytes()));
+        let res = updating(qstring);
         let expected_left = Base(Field(Column::from("id")));
         let expected_where_cond = Some(ComparisonOp(ConditionTree {
             left: Box::new(expected_left),
@@ -134,7 +157,7 @@ mod tests {
     fn format_update_with_where_clause() {
         let qstring = "UPDATE users SET id = 42, name = 'test' WHERE id = 1";
         let expected = "UPDATE users SET id = 42, name = 'test' WHERE id = 1";
-        let res = updating(CompleteByteSlice(qstring.as_bytes()));
+        let res = updating(qstring);
         assert_eq!(format!("{}", res.unwrap().1), expected);
     }
 
@@ -142,7 +165,7 @@ mod tests {
     fn updated_with_neg_float() {
         let qstring = "UPDATE `stories` SET `hotness` = -19216.5479744 WHERE `stories`.`id` = ?";
 
-        let res = updating(CompleteByteSlice(qstring.as_bytes()));
+        let res = updating(qstring);
         let expected_left = Base(Field(Column::from("stories.id")));
         let expected_where_cond = Some(ComparisonOp(ConditionTree {
             left: Box::new(expected_left),
@@ -152,14 +175,14 @@ mod tests {
         assert_eq!(
             res.unwrap().1,
             UpdateStatement {
-                table: Table::from("stories"),
+                table: Table {
+                    quote: Some(QuoteStyle::Backtick),
+                    ..Table::from("stories")
+                },
                 fields: vec![(
                     Column::from("hotness"),
                     FieldValueExpression::Literal(LiteralExpression::from(Literal::FixedPoint(
-                        Real {
-                            integral: -19216,
-                            fractional: 5479744,
-                        }
+                        Real::new(-192165479744, 7)
                     ),)),
                 ),],
                 where_clause: expected_where_cond,
@@ -172,7 +195,7 @@ mod tests {
     fn update_with_arithmetic_and_where() {
         let qstring = "UPDATE users SET karma = karma + 1 WHERE users.id = ?;";
 
-        let res = updating(CompleteByteSlice(qstring.as_bytes()));
+        let res = updating(qstring);
         let expected_where_cond = Some(ComparisonOp(ConditionTree {
             left: Box::new(Base(Field(Column::from("users.id")))),
             right: Box::new(Base(Literal(Literal::Placeholder))),
@@ -202,7 +225,7 @@ mod tests {
     fn update_with_arithmetic() {
         let qstring = "UPDATE users SET karma = karma + 1;";
 
-        let res = updating(CompleteByteSlice(qstring.as_bytes()));
+        let res = updating(qstring);
         let expected_ae = ArithmeticExpression {
             op: ArithmeticOperator::Add,
             left: ArithmeticBase::Column(Column::from("karma")),
@@ -221,4 +244,66 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn update_with_order_and_limit() {
+        let qstring =
+            "UPDATE jobs SET state = 'queued' WHERE state = 'new' ORDER BY id DESC LIMIT 10";
+        let res = updating(qstring).unwrap().1;
+        assert_eq!(res.limit.as_ref().map(|l| l.limit), Some(10));
+        assert_eq!(
+            format!("{}", res),
+            "UPDATE jobs SET state = 'queued' WHERE state = 'new' ORDER BY id DESC LIMIT 10"
+        );
+    }
+
+    #[test]
+    fn update_with_table_alias() {
+        let qstring = "UPDATE users u SET u.karma = 0 WHERE u.id = 1";
+        let res = updating(qstring).unwrap().1;
+        assert_eq!(res.table.alias, Some("u".into()));
+        assert_eq!(
+            format!("{}", res),
+            "UPDATE users AS u SET u.karma = 0 WHERE u.id = 1"
+        );
+    }
+
+    #[test]
+    fn update_with_expressions_and_default() {
+        let qstring = "UPDATE users SET karma = DEFAULT, name = nickname, \
+                       score = (SELECT max(score) FROM users) WHERE id = 1";
+        let res = updating(qstring).unwrap().1;
+        assert_eq!(
+            res.fields,
+            vec![
+                (
+                    Column::from("karma"),
+                    FieldValueExpression::Literal(Literal::Default.into()),
+                ),
+                (
+                    Column::from("name"),
+                    FieldValueExpression::Expression(Base(Field(Column::from("nickname")))),
+                ),
+                (
+                    Column::from("score"),
+                    FieldValueExpression::Expression(Base(NestedSelect(Box::new(
+                        ::select::selection("SELECT max(score) FROM users")
+                            .unwrap()
+                            .1
+                    )))),
+                ),
+            ]
+        );
+        let res = updating("UPDATE users SET karma = DEFAULT, name = nickname WHERE id = 1");
+        assert_eq!(
+            format!("{}", res.unwrap().1),
+            "UPDATE users SET karma = DEFAULT, name = nickname WHERE id = 1"
+        );
+        // a column whose name starts with "default" is not the keyword
+        let res = updating("UPDATE t SET a = default_a").unwrap().1;
+        assert_eq!(
+            res.fields[0].1,
+            FieldValueExpression::Expression(Base(Field(Column::from("default_a"))))
+        );
+    }
 }
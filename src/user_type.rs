@@ -0,0 +1,347 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{map, opt, value};
+use nom::multi::{many0, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::IResult;
+use std::borrow::Cow;
+use std::fmt;
+
+use common::{
+    escape_string, literal, sql_identifier, statement_terminator, string_literal, type_identifier,
+    ws_sep_comma, Literal, SqlType,
+};
+use condition::{condition_expr, ConditionExpression};
+use keywords::{escape_if_keyword, tag_no_case};
+
+/// What a type created by `CREATE TYPE` is made of.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum TypeDefinition {
+    /// `AS ENUM ('label', ...)`
+    Enum(Vec<String>),
+    /// `AS (attribute type, ...)`
+    Composite(Vec<(String, SqlType)>),
+}
+
+impl fmt::Display for TypeDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TypeDefinition::Enum(ref labels) => {
+                let labels = labels
+                    .iter()
+                    .map(|l| format!("'{}'", escape_string(l)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "ENUM ({})", labels)
+            }
+            TypeDefinition::Composite(ref attributes) => {
+                let attributes = attributes
+                    .iter()
+                    .map(|(name, sql_type)| format!("{} {}", escape_if_keyword(name), sql_type))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "({})", attributes)
+            }
+        }
+    }
+}
+
+fn qualified_name(schema: &Option<String>, name: &str) -> String {
+    match *schema {
+        Some(ref schema) => format!("{}.{}", escape_if_keyword(schema), escape_if_keyword(name)),
+        None => escape_if_keyword(name),
+    }
+}
+
+/// Postgres' `CREATE TYPE name AS ...`, for enum and composite types.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CreateTypeStatement {
+    pub name: String,
+    /// The schema the type is created in, when the name is qualified as in `public.mood`.
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub definition: TypeDefinition,
+}
+
+impl fmt::Display for CreateTypeStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CREATE TYPE {} AS {}",
+            qualified_name(&self.schema, &self.name),
+            self.definition
+        )
+    }
+}
+
+/// A `[CONSTRAINT name] CHECK (condition)` on a domain, in which `VALUE` stands for the value
+/// being checked.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct DomainCheck {
+    pub name: Option<String>,
+    pub condition: ConditionExpression,
+}
+
+impl fmt::Display for DomainCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref name) = self.name {
+            write!(f, "CONSTRAINT {} ", escape_if_keyword(name))?;
+        }
+        write!(f, "CHECK ({})", self.condition)
+    }
+}
+
+/// Postgres' `CREATE DOMAIN name AS type [DEFAULT value] [NOT NULL] [CHECK (...)] ...`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CreateDomainStatement {
+    pub name: String,
+    /// The schema the domain is created in, when the name is qualified.
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub data_type: SqlType,
+    pub default: Option<Literal>,
+    pub not_null: bool,
+    pub checks: Vec<DomainCheck>,
+}
+
+impl fmt::Display for CreateDomainStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CREATE DOMAIN {} AS {}",
+            qualified_name(&self.schema, &self.name),
+            self.data_type
+        )?;
+        if let Some(ref default) = self.default {
+            write!(f, " DEFAULT {}", default)?;
+        }
+        if self.not_null {
+            write!(f, " NOT NULL")?;
+        }
+        for check in &self.checks {
+            write!(f, " {}", check)?;
+        }
+        Ok(())
+    }
+}
+
+fn enum_labels(i: &str) -> IResult<&str, Vec<String>> {
+    preceded(
+        pair(tag_no_case("enum"), multispace0),
+        delimited(
+            pair(tag("("), multispace0),
+            separated_list0(
+                ws_sep_comma,
+                map(string_literal, |label| match label {
+                    Literal::String(label) => label,
+                    _ => unreachable!("string_literal only returns strings"),
+                }),
+            ),
+            pair(multispace0, tag(")")),
+        ),
+    )(i)
+}
+
+fn composite_attributes(i: &str) -> IResult<&str, Vec<(String, SqlType)>> {
+    delimited(
+        pair(tag("("), multispace0),
+        separated_list1(
+            ws_sep_comma,
+            map(
+                separated_pair(sql_identifier, multispace1, type_identifier),
+                |(name, sql_type)| (String::from(name), sql_type),
+            ),
+        ),
+        pair(multispace0, tag(")")),
+    )(i)
+}
+
+/// The name of a type or domain, optionally qualified by its schema.
+fn type_name(i: &str) -> IResult<&str, (Option<Cow<'_, str>>, Cow<'_, str>)> {
+    pair(opt(terminated(sql_identifier, tag("."))), sql_identifier)(i)
+}
+
+pub fn create_type(i: &str) -> IResult<&str, CreateTypeStatement> {
+    let (remaining_input, (_, _, _, _, (schema, name), _, _, _, definition, _)) = tuple((
+        tag_no_case("create"),
+        multispace1,
+        tag_no_case("type"),
+        multispace1,
+        type_name,
+        multispace1,
+        tag_no_case("as"),
+        multispace0,
+        alt((
+            map(enum_labels, TypeDefinition::Enum),
+            map(composite_attributes, TypeDefinition::Composite),
+        )),
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        CreateTypeStatement {
+            name: String::from(name),
+            schema: schema.map(String::from),
+            definition,
+        },
+    ))
+}
+
+/// A constraint following the type of a domain.
+#[derive(Clone)]
+enum DomainConstraint {
+    Default(Literal),
+    NotNull,
+    Null,
+    Check(DomainCheck),
+}
+
+fn domain_constraint(i: &str) -> IResult<&str, DomainConstraint> {
+    preceded(
+        multispace0,
+        alt((
+            map(
+                preceded(pair(tag_no_case("default"), multispace1), literal),
+                DomainConstraint::Default,
+            ),
+            value(
+                DomainConstraint::NotNull,
+                tuple((tag_no_case("not"), multispace1, tag_no_case("null"))),
+            ),
+            value(DomainConstraint::Null, tag_no_case("null")),
+            map(
+                pair(
+                    opt(delimited(
+                        pair(tag_no_case("constraint"), multispace1),
+                        sql_identifier,
+                        multispace1,
+                    )),
+                    preceded(
+                        pair(tag_no_case("check"), multispace0),
+                        delimited(
+                            pair(tag("("), multispace0),
+                            condition_expr,
+                            pair(multispace0, tag(")")),
+                        ),
+                    ),
+                ),
+                |(name, condition)| {
+                    DomainConstraint::Check(DomainCheck {
+                        name: name.map(String::from),
+                        condition,
+                    })
+                },
+            ),
+        )),
+    )(i)
+}
+
+pub fn create_domain(i: &str) -> IResult<&str, CreateDomainStatement> {
+    let (remaining_input, (_, _, _, _, (schema, name), _, data_type, constraints, _)) = tuple((
+        tag_no_case("create"),
+        multispace1,
+        tag_no_case("domain"),
+        multispace1,
+        type_name,
+        opt(terminated(
+            preceded(multispace1, tag_no_case("as")),
+            multispace0,
+        )),
+        preceded(multispace0, type_identifier),
+        many0(domain_constraint),
+        statement_terminator,
+    ))(i)?;
+
+    let mut domain = CreateDomainStatement {
+        name: String::from(name),
+        schema: schema.map(String::from),
+        data_type,
+        default: None,
+        not_null: false,
+        checks: vec![],
+    };
+    for constraint in constraints {
+        match constraint {
+            DomainConstraint::Default(literal) => domain.default = Some(literal),
+            DomainConstraint::NotNull => domain.not_null = true,
+            DomainConstraint::Null => domain.not_null = false,
+            DomainConstraint::Check(check) => domain.checks.push(check),
+        }
+    }
+    Ok((remaining_input, domain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_enum_and_composite_types() {
+        let res = create_type("CREATE TYPE mood AS ENUM ('sad', 'ok', 'it''s fine');");
+        assert_eq!(
+            res.unwrap().1,
+            CreateTypeStatement {
+                name: String::from("mood"),
+                schema: None,
+                definition: TypeDefinition::Enum(vec![
+                    String::from("sad"),
+                    String::from("ok"),
+                    String::from("it's fine"),
+                ]),
+            }
+        );
+        let res = create_type("create type pair as (a int, b text)")
+            .unwrap()
+            .1;
+        assert_eq!(
+            res.definition,
+            TypeDefinition::Composite(vec![
+                (String::from("a"), SqlType::Int(32)),
+                (String::from("b"), SqlType::Text),
+            ])
+        );
+        assert_eq!(res.to_string(), "CREATE TYPE pair AS (a INT(32), b TEXT)");
+        assert_eq!(
+            create_type("CREATE TYPE unset AS ENUM ()")
+                .unwrap()
+                .1
+                .to_string(),
+            "CREATE TYPE unset AS ENUM ()"
+        );
+        let res = create_type("CREATE TYPE public.mood AS ENUM ('sad')")
+            .unwrap()
+            .1;
+        assert_eq!(res.schema, Some(String::from("public")));
+        assert_eq!(res.to_string(), "CREATE TYPE public.mood AS ENUM ('sad')");
+    }
+
+    #[test]
+    fn create_domains() {
+        let res = create_domain(
+            "CREATE DOMAIN posint AS integer DEFAULT 1 NOT NULL \
+             CONSTRAINT positive CHECK (VALUE > 0) CHECK (VALUE < 100);",
+        );
+        let domain = res.unwrap().1;
+        assert_eq!(domain.data_type, SqlType::Int(32));
+        assert_eq!(domain.default, Some(Literal::Integer(1)));
+        assert!(domain.not_null);
+        assert_eq!(domain.checks.len(), 2);
+        assert_eq!(domain.checks[0].name, Some(String::from("positive")));
+        assert_eq!(
+            domain.to_string(),
+            "CREATE DOMAIN posint AS INT(32) DEFAULT 1 NOT NULL \
+             CONSTRAINT positive CHECK (VALUE > 0) CHECK (VALUE < 100)"
+        );
+        let domain = create_domain("CREATE DOMAIN code varchar(8)").unwrap().1;
+        assert_eq!(domain.to_string(), "CREATE DOMAIN code AS VARCHAR(8)");
+        let domain = create_domain("CREATE DOMAIN public.code varchar(8)")
+            .unwrap()
+            .1;
+        assert_eq!(
+            domain.to_string(),
+            "CREATE DOMAIN public.code AS VARCHAR(8)"
+        );
+    }
+}
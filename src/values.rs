@@ -0,0 +1,104 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::map;
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair, preceded, terminated};
+use nom::IResult;
+use std::fmt;
+
+use common::{
+    field_value_expr, statement_terminator, whitespace0, ws_sep_comma, FieldValueExpression,
+};
+use keywords::tag_no_case;
+
+/// A table constructor, `VALUES (1, 'a'), (2, now())`, either on its own or in a FROM clause. MySQL
+/// requires each row to be written as `ROW(...)`, which is recorded so that it prints the same.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ValuesStatement {
+    pub rows: Vec<Vec<FieldValueExpression>>,
+    pub row_constructors: bool,
+}
+
+impl fmt::Display for ValuesStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let prefix = if self.row_constructors { "ROW" } else { "" };
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let values = row
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}({})", prefix, values)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "VALUES {}", rows)
+    }
+}
+
+fn row(i: &str) -> IResult<&str, Vec<FieldValueExpression>> {
+    delimited(
+        pair(tag("("), whitespace0),
+        separated_list1(ws_sep_comma, field_value_expr),
+        pair(whitespace0, tag(")")),
+    )(i)
+}
+
+fn row_constructor(i: &str) -> IResult<&str, Vec<FieldValueExpression>> {
+    preceded(pair(tag_no_case("row"), whitespace0), row)(i)
+}
+
+/// The rows of a table constructor, written either all with or all without `ROW`.
+pub fn values(i: &str) -> IResult<&str, ValuesStatement> {
+    preceded(
+        pair(tag_no_case("values"), whitespace0),
+        alt((
+            map(separated_list1(ws_sep_comma, row_constructor), |rows| {
+                ValuesStatement {
+                    rows,
+                    row_constructors: true,
+                }
+            }),
+            map(separated_list1(ws_sep_comma, row), |rows| ValuesStatement {
+                rows,
+                row_constructors: false,
+            }),
+        )),
+    )(i)
+}
+
+pub fn values_statement(i: &str) -> IResult<&str, ValuesStatement> {
+    terminated(values, statement_terminator)(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standalone_values() {
+        let res = values_statement("VALUES (1, 'a'), (2,'b');").unwrap();
+        assert_eq!(res.0, "");
+        assert_eq!(
+            res.1,
+            ValuesStatement {
+                rows: vec![vec![1.into(), "a".into()], vec![2.into(), "b".into()]],
+                row_constructors: false,
+            }
+        );
+        assert_eq!(format!("{}", res.1), "VALUES (1, 'a'), (2, 'b')");
+
+        let res = values_statement("values row(1, NULL), ROW (?, 2)")
+            .unwrap()
+            .1;
+        assert!(res.row_constructors);
+        assert_eq!(format!("{}", res), "VALUES ROW(1, NULL), ROW(?, 2)");
+
+        // rows are written either all with or all without ROW
+        assert!(values_statement("VALUES ROW(1), (2)").is_err());
+        assert!(values_statement("VALUES ()").is_err());
+    }
+}
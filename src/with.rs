@@ -0,0 +1,176 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, opt};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt;
+
+use column::Column;
+use common::{
+    keyword_whitespace, sql_identifier, statement_terminator, whitespace0, whitespace1,
+    ws_sep_comma,
+};
+use compound_select::compound_select;
+use create::{select_specification, view_columns, SelectSpecification};
+use keywords::{escape_if_keyword, tag_no_case};
+use limits::nested;
+use select::nested_selection;
+
+/// A common table expression, `name [(column, ...)] AS (query)`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct CommonTableExpression {
+    pub name: String,
+    /// The names given to the columns of the query, if any.
+    pub columns: Vec<Column>,
+    pub query: SelectSpecification,
+}
+
+impl fmt::Display for CommonTableExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", escape_if_keyword(&self.name))?;
+        if !self.columns.is_empty() {
+            write!(
+                f,
+                " ({})",
+                self.columns
+                    .iter()
+                    .map(|column| column.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        write!(f, " AS ({})", self.query)
+    }
+}
+
+/// A select or compound select preceded by `WITH [RECURSIVE]` and the common table expressions
+/// it reads from. Inserts, updates and deletes cannot be preceded by `WITH`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct WithStatement {
+    pub recursive: bool,
+    pub ctes: Vec<CommonTableExpression>,
+    pub query: Box<SelectSpecification>,
+}
+
+impl fmt::Display for WithStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WITH ")?;
+        if self.recursive {
+            write!(f, "RECURSIVE ")?;
+        }
+        write!(
+            f,
+            "{} {}",
+            self.ctes
+                .iter()
+                .map(|cte| cte.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.query
+        )
+    }
+}
+
+fn common_table_expression(i: &str) -> IResult<&str, CommonTableExpression> {
+    let (remaining_input, (name, columns, _, _, _, query)) = tuple((
+        sql_identifier,
+        opt(preceded(whitespace0, view_columns)),
+        whitespace1,
+        tag_no_case("as"),
+        whitespace0,
+        delimited(
+            pair(tag("("), whitespace0),
+            alt((
+                map(
+                    nested(alt((tag_no_case("select"), tag("("))), compound_select),
+                    SelectSpecification::Compound,
+                ),
+                map(nested_selection, SelectSpecification::Simple),
+            )),
+            pair(whitespace0, tag(")")),
+        ),
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        CommonTableExpression {
+            name: String::from(name),
+            columns: columns.unwrap_or_default(),
+            query,
+        },
+    ))
+}
+
+pub fn with_statement(i: &str) -> IResult<&str, WithStatement> {
+    let (remaining_input, (_, _, recursive, ctes, _, query, _)) = tuple((
+        tag_no_case("with"),
+        whitespace1,
+        opt(terminated(tag_no_case("recursive"), whitespace1)),
+        separated_list1(ws_sep_comma, common_table_expression),
+        keyword_whitespace,
+        select_specification,
+        statement_terminator,
+    ))(i)?;
+
+    Ok((
+        remaining_input,
+        WithStatement {
+            recursive: recursive.is_some(),
+            ctes,
+            query: Box::new(query),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::{parse_query, SqlQuery};
+
+    #[test]
+    fn common_table_expressions() {
+        let qstring = "WITH recent AS (SELECT id, user_id FROM orders WHERE id > 10), \
+                       totals (uid, n) AS (SELECT user_id, count(*) FROM recent GROUP BY user_id) \
+                       SELECT uid, n FROM totals";
+        let res = with_statement(qstring).unwrap().1;
+        assert!(!res.recursive);
+        assert_eq!(res.ctes.len(), 2);
+        assert_eq!(res.ctes[0].name, "recent");
+        assert_eq!(
+            res.ctes[1].columns,
+            vec![Column::from("uid"), Column::from("n")]
+        );
+        assert_eq!(
+            res.to_string(),
+            "WITH recent AS (SELECT id, user_id FROM orders WHERE id > 10), \
+             totals (uid, n) AS (SELECT user_id, count(*) FROM recent GROUP BY user_id) \
+             SELECT uid, n FROM totals"
+        );
+    }
+
+    #[test]
+    fn recursive_and_compound() {
+        let qstring =
+            "WITH RECURSIVE seq (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 5) \
+                       SELECT n FROM seq UNION SELECT id FROM t";
+        let res = parse_query(qstring).unwrap();
+        match res {
+            SqlQuery::With(ref with) => {
+                assert!(with.recursive);
+                match with.ctes[0].query {
+                    SelectSpecification::Compound(ref compound) => {
+                        assert_eq!(compound.selects.len(), 2)
+                    }
+                    ref other => panic!("unexpected definition {:?}", other),
+                }
+                match *with.query {
+                    SelectSpecification::Compound(_) => (),
+                    ref other => panic!("unexpected query {:?}", other),
+                }
+            }
+            ref other => panic!("unexpected query {:?}", other),
+        }
+        assert_eq!(parse_query(res.to_string()), Ok(res));
+    }
+}
@@ -1,5 +1,9 @@
 extern crate nom_sql;
 
+extern crate serde_json;
+
+use nom_sql::parser::{parse_query, parse_query_with_dialect, SqlQuery};
+use nom_sql::{format_query, Dialect, FormatOptions, IdentifierQuoting, KeywordCase, QuoteStyle};
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
@@ -9,7 +13,7 @@ fn parse_queryset(queries: Vec<String>) -> (i32, i32) {
     let mut parsed_err = 0;
     for query in queries.iter() {
         println!("Trying to parse '{}': ", &query);
-        match nom_sql::parser::parse_query(&query) {
+        match nom_sql::parser::parse_query(query) {
             Ok(_) => {
                 println!("ok");
                 parsed_ok.push(query);
@@ -31,14 +35,13 @@ fn parse_queryset(queries: Vec<String>) -> (i32, i32) {
     (parsed_ok.len() as i32, parsed_err)
 }
 
-fn test_queries_from_file(f: &Path, name: &str) -> Result<i32, i32> {
+/// Loads a file with one query per line.
+fn queries_from_file(f: &Path) -> Vec<String> {
     let mut f = File::open(f).unwrap();
     let mut s = String::new();
 
-    // Load queries
     f.read_to_string(&mut s).unwrap();
-    let lines: Vec<String> = s
-        .lines()
+    s.lines()
         .filter(|l| !l.is_empty() && !l.starts_with("#"))
         .map(|l| {
             if !(l.ends_with("\n") || l.ends_with(";")) {
@@ -46,7 +49,13 @@ fn test_queries_from_file(f: &Path, name: &str) -> Result<i32, i32> {
             } else {
                 String::from(l)
             }
-        }).collect();
+        })
+        .collect()
+}
+
+fn test_queries_from_file(f: &Path, name: &str) -> Result<i32, i32> {
+    // Load queries
+    let lines = queries_from_file(f);
     println!("Loaded {} {} queries", lines.len(), name);
 
     // Try parsing them all
@@ -56,7 +65,8 @@ fn test_queries_from_file(f: &Path, name: &str) -> Result<i32, i32> {
     Ok(ok)
 }
 
-fn parse_file(path: &str) -> (i32, i32) {
+/// Loads a file of statements that may span several lines, each ending with a semicolon.
+fn statements_from_file(path: &str) -> Vec<String> {
     let mut f = File::open(Path::new(path)).unwrap();
     let mut s = String::new();
 
@@ -71,7 +81,8 @@ fn parse_file(path: &str) -> (i32, i32) {
                 && !l.starts_with("--")
                 && !l.starts_with("DROP")
                 && !l.starts_with("/*")
-        }).collect();
+        })
+        .collect();
     let mut q = String::new();
     let mut queries = Vec::new();
     for l in lines {
@@ -84,12 +95,114 @@ fn parse_file(path: &str) -> (i32, i32) {
             q = String::new();
         }
     }
+    queries
+}
+
+fn parse_file(path: &str) -> (i32, i32) {
+    let queries = statements_from_file(path);
     println!("Loaded {} table definitions", queries.len());
 
     // Try parsing them all
     parse_queryset(queries)
 }
 
+/// Drops the quotes that tables, schemas and aliases were written in, which the formatter is asked
+/// to change.
+fn without_quote_marks(query: &SqlQuery) -> serde_json::Value {
+    fn strip(value: &mut serde_json::Value) {
+        match *value {
+            serde_json::Value::Object(ref mut fields) => {
+                for (key, field) in fields.iter_mut() {
+                    if key.ends_with("quote") {
+                        *field = serde_json::Value::Null;
+                    } else {
+                        strip(field);
+                    }
+                }
+            }
+            serde_json::Value::Array(ref mut items) => items.iter_mut().for_each(strip),
+            _ => (),
+        }
+    }
+    let mut value = serde_json::to_value(query).unwrap();
+    strip(&mut value);
+    value
+}
+
+/// Checks that `query` parses, and that it prints as SQL that parses back to the same AST: with
+/// `Display`, and with `format_query` under every identifier quoting policy, quote style and
+/// keyword case, save for the quotes the names are in.
+fn assert_round_trip(query: &str) {
+    let parsed =
+        parse_query(query).unwrap_or_else(|e| panic!("{} failed to parse: {}", query.trim(), e));
+    let printed = parsed.to_string();
+    assert_eq!(
+        parse_query(&printed),
+        Ok(parsed.clone()),
+        "{} was printed as {}",
+        query.trim(),
+        printed
+    );
+    let styles = [
+        QuoteStyle::Backtick,
+        QuoteStyle::DoubleQuote,
+        QuoteStyle::Bracket,
+    ];
+    let policies = [
+        IdentifierQuoting::Always,
+        IdentifierQuoting::WhenNeeded,
+        IdentifierQuoting::Never,
+    ];
+    for &quote_style in &styles {
+        for &identifier_quoting in &policies {
+            for &keyword_case in &[KeywordCase::Upper, KeywordCase::Lower] {
+                let opts = FormatOptions {
+                    identifier_quoting,
+                    quote_style,
+                    keyword_case,
+                    ..Default::default()
+                };
+                let formatted = format_query(&parsed, &opts);
+                // names that need quotes are taken for something else without them
+                let when_needed = FormatOptions {
+                    identifier_quoting: IdentifierQuoting::WhenNeeded,
+                    ..opts.clone()
+                };
+                if identifier_quoting == IdentifierQuoting::Never
+                    && format_query(&parsed, &when_needed) != formatted
+                {
+                    continue;
+                }
+                // MySQL reads double-quoted text as a string
+                let dialect = match quote_style {
+                    QuoteStyle::DoubleQuote => Dialect::Postgres,
+                    _ => Dialect::Generic,
+                };
+                let reparsed = parse_query_with_dialect(&formatted, dialect).unwrap_or_else(|e| {
+                    panic!("{} was formatted as {}: {}", query.trim(), formatted, e)
+                });
+                assert_eq!(
+                    without_quote_marks(&reparsed),
+                    without_quote_marks(&parsed),
+                    "{} was formatted as {}",
+                    query.trim(),
+                    formatted
+                );
+            }
+        }
+    }
+}
+
+/// The queries of `queries` that parse, of which there must be `expected`.
+fn parseable(queries: Vec<String>, expected: usize) -> Vec<String> {
+    let queries: Vec<_> = queries
+        .into_iter()
+        .filter(|q| parse_query(q).is_ok())
+        .collect();
+    assert_eq!(queries.len(), expected);
+    queries
+}
+
 #[test]
 fn hotcrp_queries() {
     assert!(test_queries_from_file(Path::new("tests/hotcrp-queries.txt"), "HotCRP").is_ok());
@@ -133,7 +246,8 @@ fn hotcrp_schema() {
         .map(str::trim)
         .filter(|l| {
             !l.is_empty() && !l.starts_with("#") && !l.starts_with("--") && !l.starts_with("DROP")
-        }).collect();
+        })
+        .collect();
     let mut q = String::new();
     let mut queries = Vec::new();
     for l in lines {
@@ -194,3 +308,142 @@ fn parse_select() {
     assert_eq!(fail, 0);
     assert_eq!(ok, 24);
 }
+
+#[test]
+fn display_round_trips() {
+    for file in &[
+        "tests/round-trip.txt",
+        "tests/tpc-w-tables.txt",
+        "tests/finkelstein82.txt",
+        "tests/select.txt",
+    ] {
+        for query in queries_from_file(Path::new(file)) {
+            assert_round_trip(&query);
+        }
+    }
+    for file in &[
+        "tests/mediawiki-schema.txt",
+        "tests/comments.txt",
+        "tests/autoincrement.txt",
+    ] {
+        for query in statements_from_file(file) {
+            assert_round_trip(&query);
+        }
+    }
+    // the rest of these use syntax that is not supported
+    for &(file, expected) in &[
+        ("tests/hotcrp-queries.txt", 183),
+        ("tests/hyrise-test-queries.txt", 17),
+        ("tests/tpc-w-queries.txt", 47),
+    ] {
+        for query in parseable(queries_from_file(Path::new(file)), expected) {
+            assert_round_trip(&query);
+        }
+    }
+}
+
+#[test]
+fn truncated_queries_do_not_panic() {
+    // Every prefix of a valid query is a plausible malformed input; parsing it (and printing
+    // whatever parses) must fail gracefully rather than panic.
+    let mut s = String::new();
+    File::open(Path::new("tests/select.txt"))
+        .unwrap()
+        .read_to_string(&mut s)
+        .unwrap();
+    for query in s.lines().filter(|l| !l.starts_with("#")) {
+        let bytes = query.as_bytes();
+        for end in 0..bytes.len() {
+            for suffix in &["", ")", "'"] {
+                let mut input = bytes[..end].to_vec();
+                input.extend_from_slice(suffix.as_bytes());
+                if let Ok(q) = nom_sql::parser::parse_query_bytes(&input) {
+                    let _ = q.to_string();
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn aliased_insert_and_create_tables_are_errors() {
+    assert!(nom_sql::parser::parse_query("INSERT INTO t AS x VALUES (1)").is_err());
+    assert!(nom_sql::parser::parse_query("CREATE TABLE t AS x (id int)").is_err());
+}
+
+#[test]
+fn whitespace_between_tokens_is_optional() {
+    // each statement is written with as little whitespace as its tokens allow, as one would
+    // write it, and with newlines and comments wherever whitespace may go
+    let variants = [
+        [
+            "SELECT a,count (b)FROM t WHERE(a>1)AND b LIKE'x%'GROUP BY a ORDER BY a",
+            "SELECT a, count(b) FROM t WHERE (a > 1) AND b LIKE 'x%' GROUP BY a ORDER BY a",
+            "SELECT\n  a\n  , count ( b ) -- per a\nFROM\n  t\nWHERE\n  ( a > 1 )\n  AND /* pattern */ b\
+             \n  LIKE 'x%'\nGROUP\n  BY a\nORDER\tBY\ta",
+        ],
+        [
+            "SELECT*FROM t1 LEFT JOIN t2 ON(t1.a=t2.b)WHERE t1.c IN(SELECT c FROM u)",
+            "SELECT * FROM t1 LEFT JOIN t2 ON (t1.a = t2.b) WHERE t1.c IN (SELECT c FROM u)",
+            "SELECT\n*\nFROM\nt1\nLEFT\n# outer\nJOIN\nt2\nON\n(\nt1.a\n=\nt2.b\n)\nWHERE\nt1.c\
+             \nIN\n(\nSELECT\nc\nFROM\nu\n)",
+        ],
+        [
+            "INSERT INTO t(a,b)VALUES(1,'x'),(2,'y')",
+            "INSERT INTO t (a, b) VALUES (1, 'x'), (2, 'y')",
+            "INSERT\nINTO\nt\n(\na\n,\nb\n)\nVALUES\n(\n1\n,\n'x'\n)\n,\n/* second */\n(\n2\n,\n'y'\n)",
+        ],
+        [
+            "UPDATE t SET a=1,b=b+1 WHERE(c=3)",
+            "UPDATE t SET a = 1, b = b + 1 WHERE (c = 3)",
+            "UPDATE\n  t\nSET\n  a = 1\n  , b = b + 1 -- bump\nWHERE\n  ( c = 3 )",
+        ],
+        [
+            "DELETE FROM t WHERE(a=1)",
+            "DELETE FROM t WHERE (a = 1)",
+            "DELETE\nFROM\n  t\nWHERE\n  (\n    a = 1\n  )",
+        ],
+        [
+            "CREATE TABLE t(a int NOT NULL,b varchar(10),PRIMARY KEY(a))",
+            "CREATE TABLE t (a int NOT NULL, b varchar(10), PRIMARY KEY (a))",
+            "CREATE TABLE t\n(\n  a int NOT NULL, -- id\n  b varchar ( 10 ) /* name */ ,\n  PRIMARY\n  KEY\
+             \n  ( a )\n)",
+        ],
+        [
+            "DROP TABLE IF EXISTS a,b RESTRICT",
+            "DROP TABLE IF EXISTS a, b RESTRICT",
+            "/* cleanup */ DROP\n  TABLE\n  IF  EXISTS -- maybe\n  a, b\n  RESTRICT",
+        ],
+        [
+            "CREATE DATABASE IF NOT EXISTS d CHARACTER SET utf8",
+            "CREATE DATABASE IF NOT EXISTS d CHARACTER SET utf8",
+            "CREATE DATABASE IF\tNOT /* ever */ EXISTS d CHARACTER\n  SET utf8",
+        ],
+        [
+            "CREATE SEQUENCE IF NOT EXISTS s START 1",
+            "CREATE SEQUENCE IF NOT EXISTS s START 1",
+            "-- ids\nCREATE  SEQUENCE\nIF  NOT  EXISTS s\n  START 1",
+        ],
+        [
+            "CREATE TABLE t (a int) PARTITION BY HASH (a) PARTITIONS 4",
+            "CREATE TABLE t (a int) PARTITION BY HASH (a) PARTITIONS 4",
+            "CREATE TABLE t (a int)\nPARTITION\n  BY HASH (a) PARTITIONS 4",
+        ],
+        [
+            "CREATE EXTENSION IF NOT EXISTS hstore",
+            "CREATE EXTENSION IF NOT EXISTS hstore",
+            "CREATE EXTENSION IF\n  NOT EXISTS hstore",
+        ],
+    ];
+    for statements in &variants {
+        let expected = nom_sql::parser::parse_query(statements[1]).unwrap();
+        for statement in statements {
+            assert_eq!(
+                nom_sql::parser::parse_query(statement).as_ref(),
+                Ok(&expected),
+                "{}",
+                statement
+            );
+        }
+    }
+}